@@ -0,0 +1,65 @@
+//! Hot loops of the simulation and decision scoring
+//!
+//! `cargo bench --features bench`, or `cargo bench --features bench -- step` for one group
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use wasm::bench::{rebuild_flat_snapshot, synthetic_countries, synthetic_simulation, SpatialIndexBench};
+use wasm::{generate_shortlist, score_actions_batch, LookupTables, PruningConfig};
+
+const ENTITY_COUNTS: [usize; 3] = [1_000, 10_000, 50_000];
+
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    group.sample_size(10);
+    for entity_count in ENTITY_COUNTS {
+        let mut logic = synthetic_simulation(entity_count);
+        // Past the first tick, which builds every buffer from scratch
+        logic.step();
+        group.bench_function(BenchmarkId::from_parameter(entity_count), |b| b.iter(|| logic.step()));
+    }
+    group.finish();
+}
+
+fn spatial_grid_rebuild(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spatial_grid_rebuild");
+    for entity_count in ENTITY_COUNTS {
+        let mut index = SpatialIndexBench::new(&synthetic_simulation(entity_count));
+        group.bench_function(BenchmarkId::from_parameter(index.entity_count()), |b| b.iter(|| index.rebuild()));
+    }
+    group.finish();
+}
+
+fn flat_snapshot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rebuild_flat_snapshot");
+    for entity_count in ENTITY_COUNTS {
+        let mut logic = synthetic_simulation(entity_count);
+        group.bench_function(BenchmarkId::from_parameter(entity_count), |b| {
+            b.iter(|| black_box(rebuild_flat_snapshot(&mut logic)))
+        });
+    }
+    group.finish();
+}
+
+fn scoring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("score_actions_batch");
+    let luts = LookupTables::new();
+    let config = PruningConfig::default();
+    for country_count in [10, 100, 1_000] {
+        let world = synthetic_countries(country_count, 6);
+        let shortlists: Vec<_> = (0..country_count)
+            .map(|id| generate_shortlist(id, world.get_country(id).unwrap(), &world, &config))
+            .collect();
+        group.bench_function(BenchmarkId::new("countries", country_count), |b| {
+            b.iter(|| {
+                for (id, shortlist) in (0..country_count).zip(&shortlists) {
+                    let country = world.get_country(id).unwrap();
+                    black_box(score_actions_batch(country, shortlist, &world, &luts));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step, spatial_grid_rebuild, flat_snapshot, scoring);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+//! Arbitrary bytes into the command protocol decoder
+//!
+//! Decoding must fail cleanly or yield commands that encode back to the same bytes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasm::fuzz::{decode_commands, encode_commands};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(commands) = decode_commands(data) {
+        assert_eq!(encode_commands(&commands), data);
+    }
+});
@@ -0,0 +1,21 @@
+//! Arbitrary bytes into the binary snapshot decoder
+//!
+//! Random bytes rarely pass the CRC, so every input is also tried with a valid
+//! checksum appended to reach the header and record checks behind it.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasm::fuzz::{crc32, decode_snapshot_v1};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_snapshot_v1(data);
+
+    let mut sealed = data.to_vec();
+    sealed.extend_from_slice(&crc32(data).to_le_bytes());
+    if let Ok(snapshot) = decode_snapshot_v1(&sealed) {
+        let fields = &snapshot.fields;
+        assert!(!fields.is_empty());
+        assert!(fields.iter().enumerate().all(|(i, field)| !fields[..i].contains(field)));
+    }
+});
@@ -0,0 +1,26 @@
+"""Plot the leader's territory share over a few seeds
+
+    maturin develop --release && python examples/territory_curves.py
+"""
+
+import json
+
+import matplotlib.pyplot as plt
+import numpy as np
+
+import invasia
+
+for seed in range(4):
+    sim = invasia.Simulation(json.dumps({"seed": seed, "entity_count": 20}))
+    territory = sim.snapshot_fields().index("territory")
+    cells = sim.grid_size**2
+    shares = []
+    while not sim.is_complete and sim.tick < 3000:
+        sim.step(10)
+        shares.append(sim.snapshot()[:, territory].max() / cells)
+    plt.plot(np.arange(len(shares)) * 10, shares, label=f"seed {seed}")
+
+plt.xlabel("tick")
+plt.ylabel("leader's share of the grid")
+plt.legend()
+plt.show()
@@ -0,0 +1,240 @@
+//! Python bindings for the simulation and decision scoring core
+//!
+//! Build into the active virtualenv with `maturin develop --release`, then
+//! `import invasia`. Configs, commands and logs cross as the same JSON the
+//! wasm module takes; snapshots come back as numpy arrays.
+
+use numpy::{PyArray1, PyArray2, PyArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm::{RunConfig, SimulationLogic};
+
+fn parse<T: DeserializeOwned>(json: &str) -> PyResult<T> {
+    serde_json::from_str(json).map_err(|err| PyValueError::new_err(format!("Malformed input: {}", err)))
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> PyResult<String> {
+    serde_json::to_string(value).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn unknown_config(kind: &str) -> PyErr {
+    PyValueError::new_err(format!(
+        "Unknown config {:?}, expected terrain, resources, conquest, neutrals, unrest, events, calendar, fairness \
+         or win_condition",
+        kind
+    ))
+}
+
+/// A simulation run on a fixed timestep, so the same config always plays out the same
+///
+/// `Simulation('{"seed": 7, "entity_count": 50}')` takes a `RunConfig` as
+/// JSON; missing fields fall back to the defaults.
+#[pyclass(name = "Simulation", unsendable)] // Brains and event callbacks stay on one thread
+struct PySimulation {
+    logic: SimulationLogic,
+}
+
+#[pymethods]
+impl PySimulation {
+    #[new]
+    #[pyo3(signature = (config = None))]
+    fn new(config: Option<&str>) -> PyResult<Self> {
+        let config: RunConfig = match config {
+            Some(json) => parse(json)?,
+            None => RunConfig::default(),
+        };
+        let logic = SimulationLogic::from_run_config(&config).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        Ok(Self { logic })
+    }
+
+    /// Run `ticks` ticks, stopping early once the run is decided
+    #[pyo3(signature = (ticks = 1))]
+    fn step(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            if self.logic.is_complete() {
+                break;
+            }
+            self.logic.step();
+        }
+    }
+
+    #[getter]
+    fn tick(&self) -> u64 {
+        self.logic.tick()
+    }
+
+    #[getter]
+    fn grid_size(&self) -> usize {
+        self.logic.grid_size()
+    }
+
+    /// Whether one entity, team or overlord is left, or a victory condition was met
+    #[getter]
+    fn is_complete(&self) -> bool {
+        self.logic.is_complete()
+    }
+
+    fn count_alive(&self) -> usize {
+        self.logic.count_alive()
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.logic.state_hash()
+    }
+
+    /// One row per entity and one float32 column per field in `snapshot_fields()`
+    fn snapshot<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f32>>> {
+        let stride = self.logic.snapshot_schema().stride.max(1);
+        let flat = self.logic.request_flat_snapshot().unwrap_or(&[]);
+        PyArray1::from_slice_bound(py, flat).reshape([flat.len() / stride, stride])
+    }
+
+    /// Names of the `snapshot()` columns, in order
+    fn snapshot_fields(&self) -> PyResult<Vec<String>> {
+        self.logic
+            .snapshot_schema()
+            .fields
+            .iter()
+            .map(|layout| to_json(&layout.field).map(|name| name.trim_matches('"').to_string()))
+            .collect()
+    }
+
+    /// Pick the `snapshot()` columns by `SnapshotField` bit
+    fn set_snapshot_fields(&mut self, mask: u32) -> PyResult<()> {
+        self.logic.set_snapshot_fields(mask).map_err(PyValueError::new_err)
+    }
+
+    /// Owner ids as uint32 and defense strengths as float32, each grid_size x grid_size
+    fn grid<'py>(&mut self, py: Python<'py>) -> PyResult<(Bound<'py, PyArray2<u32>>, Bound<'py, PyArray2<f32>>)> {
+        let size = self.logic.grid_size();
+        let (owners, defense) = self.logic.request_grid_snapshot();
+        let owners = PyArray1::from_slice_bound(py, owners).reshape([size, size])?;
+        let defense = PyArray1::from_slice_bound(py, defense).reshape([size, size])?;
+        Ok((owners, defense))
+    }
+
+    /// Territory per alive entity over the run, as `EntityTimeline` JSON
+    fn timelines(&self) -> PyResult<String> {
+        to_json(&self.logic.all_timelines())
+    }
+
+    /// Apply a protocol `Command` given as JSON, e.g. `{"kind": "SetTickRate", "tick_rate": 30}`
+    fn apply_command(&mut self, command: &str) -> PyResult<()> {
+        self.logic.apply_command(parse(command)?).map_err(PyValueError::new_err)
+    }
+
+    /// One of the configs a run is set up with, as JSON
+    fn config(&self, kind: &str) -> PyResult<String> {
+        let logic = &self.logic;
+        match kind {
+            "terrain" => to_json(&logic.terrain_config()),
+            "resources" => to_json(&logic.resource_config()),
+            "conquest" => to_json(&logic.conquest_config()),
+            "neutrals" => to_json(&logic.neutral_config()),
+            "unrest" => to_json(&logic.unrest_config()),
+            "events" => to_json(&logic.event_config()),
+            "calendar" => to_json(&logic.calendar_config()),
+            "fairness" => to_json(&logic.fairness_config()),
+            "win_condition" => to_json(&logic.win_condition()),
+            _ => Err(unknown_config(kind)),
+        }
+    }
+
+    /// Replace one of the configs with JSON in the shape `config(kind)` returns
+    fn set_config(&mut self, kind: &str, json: &str) -> PyResult<()> {
+        let logic = &mut self.logic;
+        match kind {
+            "terrain" => logic.set_terrain_config(parse(json)?),
+            "resources" => logic.set_resource_config(parse(json)?),
+            "conquest" => logic.set_conquest_config(parse(json)?),
+            "neutrals" => logic.set_neutral_config(parse(json)?),
+            "unrest" => logic.set_unrest_config(parse(json)?),
+            "events" => logic.set_event_config(parse(json)?),
+            "calendar" => logic.set_calendar_config(parse(json)?),
+            "fairness" => logic.set_fairness_config(parse(json)?),
+            "win_condition" => logic.set_win_condition(parse(json)?),
+            _ => return Err(unknown_config(kind)),
+        }
+        .map_err(PyValueError::new_err)
+    }
+}
+
+/// The country-level decision scoring system
+#[pyclass(name = "DecisionSystem")]
+struct PyDecisionSystem {
+    system: wasm::DecisionSystem,
+}
+
+#[pymethods]
+impl PyDecisionSystem {
+    #[new]
+    #[pyo3(signature = (seed = 0))]
+    fn new(seed: u64) -> Self {
+        Self {
+            system: wasm::DecisionSystem::init(seed),
+        }
+    }
+
+    fn add_country(&mut self, id: u32) {
+        self.system.add_country(id);
+    }
+
+    fn add_edge(&mut self, from_id: u32, to_id: u32, distance: usize, hostility: f32) {
+        self.system.add_edge(from_id, to_id, distance, hostility);
+    }
+
+    fn set_country_stats(&mut self, id: u32, json: &str) -> PyResult<()> {
+        self.system.set_country_stats(id, json).map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn set_personality(&mut self, id: u32, json: &str) -> PyResult<()> {
+        self.system.set_personality(id, json).map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn set_selection_policy(&mut self, json: &str) -> PyResult<()> {
+        self.system.set_selection_policy(json).map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn set_pruning_config(&mut self, json: &str) -> PyResult<()> {
+        self.system.set_pruning_config(json).map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn load_scenario(&mut self, json: &str) -> PyResult<()> {
+        self.system.load_scenario(json).map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    fn export_scenario(&self) -> PyResult<String> {
+        self.system.export_scenario().map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    #[pyo3(signature = (ticks = 1))]
+    fn step(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.system.tick();
+        }
+    }
+
+    #[getter]
+    fn tick(&self) -> u64 {
+        self.system.get_tick()
+    }
+
+    /// Decision logs as `DecisionLog[]` JSON, cleared so each entry is returned once
+    fn drain_logs(&mut self) -> PyResult<String> {
+        to_json(&self.system.take_logs())
+    }
+
+    /// Changes the last tick made to the world, as JSON
+    fn deltas(&self) -> PyResult<String> {
+        to_json(self.system.deltas())
+    }
+}
+
+#[pymodule]
+fn invasia(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PySimulation>()?;
+    module.add_class::<PyDecisionSystem>()?;
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Hot paths and synthetic worlds for the criterion suite in `benches/`
+//!
+//! Only built with the `bench` feature; nothing here is part of the supported API.
+
+use crate::constants::{DEFAULT_CELL_SIZE, DEFAULT_GRID_SIZE, DEFAULT_WORLD_SIZE};
+use crate::data::GridUpdateBuilder;
+use crate::decision_scoring::{Country, CountryEdge, WorldState, XorShiftRng};
+use crate::logic::SimulationLogic;
+use crate::types::{EntitySnapshot, TimeMode, WorldConfig};
+
+/// Seed every synthetic world starts from, so runs compare like with like
+pub const BENCH_SEED: u32 = 0x1A2B_3C4D;
+
+/// World sized so the grid has about four spaces per entity at the default density
+pub fn synthetic_world_config(entity_count: usize) -> WorldConfig {
+    let grid_size = ((entity_count as f32 * 4.0).sqrt().ceil() as usize).max(DEFAULT_GRID_SIZE);
+    let world_size = DEFAULT_WORLD_SIZE * grid_size as f32 / DEFAULT_GRID_SIZE as f32;
+    WorldConfig::new(world_size, DEFAULT_CELL_SIZE, grid_size)
+}
+
+/// A running simulation on a fixed timestep, seeded with `BENCH_SEED`
+pub fn synthetic_simulation(entity_count: usize) -> SimulationLogic {
+    let mut logic = SimulationLogic::with_world(entity_count, synthetic_world_config(entity_count));
+    logic.set_time_mode(TimeMode::fixed(16.0).expect("positive timestep"));
+    logic.reseed_entities(BENCH_SEED);
+    logic.start();
+    logic
+}
+
+/// Rebuild the flat `Float32Array` snapshot from scratch; returns its length
+pub fn rebuild_flat_snapshot(logic: &mut SimulationLogic) -> usize {
+    let data = logic.data_mut();
+    data.mark_snapshots_dirty();
+    data.ensure_flat_snapshot_ready();
+    data.flat_snapshot_slice().len()
+}
+
+/// The spatial hash over a simulation's entity positions, rebuilt on demand
+pub struct SpatialIndexBench {
+    builder: GridUpdateBuilder,
+    snapshots: Vec<EntitySnapshot>,
+}
+
+impl SpatialIndexBench {
+    pub fn new(logic: &SimulationLogic) -> Self {
+        Self {
+            builder: GridUpdateBuilder::for_world(&logic.world_config()),
+            snapshots: logic.entities().iter().map(EntitySnapshot::from).collect(),
+        }
+    }
+
+    pub fn rebuild(&mut self) {
+        self.builder.rebuild(&self.snapshots);
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.snapshots.len()
+    }
+}
+
+/// `country_count` countries in a ring, each bordering the next few with
+/// seeded resources, military and hostility
+pub fn synthetic_countries(country_count: u32, neighbors: u32) -> WorldState {
+    let mut rng = XorShiftRng::new(BENCH_SEED as u64);
+    let mut world = WorldState::new();
+    for id in 0..country_count {
+        let mut country = Country::new(id);
+        country.resources = 50.0 + rng.next_f32() * 450.0;
+        country.m_eff = 10.0 + rng.next_f32() * 190.0;
+        for offset in 1..=neighbors.min(country_count.saturating_sub(1)) {
+            let neighbor_id = (id + offset) % country_count;
+            let mut edge = CountryEdge::new(neighbor_id);
+            edge.hostility = rng.next_f32();
+            country.add_edge(edge);
+            country.intel.insert(neighbor_id, 0.5 + rng.next_f32() * 0.5);
+        }
+        world.add_country(country);
+    }
+    world.update_weights();
+    world
+}
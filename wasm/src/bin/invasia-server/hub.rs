@@ -0,0 +1,448 @@
+//! The thread that owns every hosted simulation and answers client requests
+//!
+//! Simulations aren't `Send`, so connections hand their messages to the hub
+//! over a channel and get replies back on their own outbox.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{Receiver, Sender};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use wasm::{decode_commands, Command, RunConfig, SimError, SimulationLogic};
+
+use crate::websocket::Frame;
+
+pub type ClientId = u64;
+
+/// What a connection hands the hub
+pub enum Event {
+    /// A text or binary message, to be answered on `outbox`
+    Message { client: ClientId, outbox: Sender<Frame>, frame: Frame },
+    Disconnected { client: ClientId },
+}
+
+/// Caps on what clients may ask of the server
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_simulations: usize,
+    pub max_entities: usize,
+    pub max_grid_size: usize,
+    pub max_ticks_per_step: u64,
+    pub max_scheduled_orders: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SnapshotFormat {
+    /// `{"tick", "schema", "values"}`, the flat snapshot and its layout
+    #[default]
+    Json,
+    /// A binary frame in the `encode_snapshot_v1` format
+    Binary,
+}
+
+/// A JSON text request; `id` is echoed back on the reply so clients can match them up
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    request: Request,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request {
+    Create {
+        #[serde(default)]
+        config: Box<RunConfig>,
+    },
+    Destroy {
+        sim: u32,
+    },
+    List,
+    Step {
+        sim: u32,
+        #[serde(default = "one_tick")]
+        ticks: u64,
+    },
+    Snapshot {
+        sim: u32,
+        #[serde(default)]
+        format: SnapshotFormat,
+    },
+    Command {
+        sim: u32,
+        commands: Vec<Command>,
+    },
+    Watch {
+        sim: u32,
+    },
+    Unwatch {
+        sim: u32,
+    },
+}
+
+fn one_tick() -> u64 {
+    1
+}
+
+enum Reply {
+    Json(Value),
+    Binary(Vec<u8>),
+}
+
+struct Hosted {
+    logic: SimulationLogic,
+    /// Clients sent the JSON snapshot after every step
+    watchers: Vec<(ClientId, Sender<Frame>)>,
+}
+
+pub struct Hub {
+    simulations: BTreeMap<u32, Hosted>,
+    next_id: u32,
+    limits: Limits,
+}
+
+impl Hub {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            simulations: BTreeMap::new(),
+            next_id: 1,
+            limits,
+        }
+    }
+
+    /// Serve events until every connection and the listener have hung up
+    pub fn run(mut self, events: Receiver<Event>) {
+        for event in events {
+            match event {
+                Event::Message { client, outbox, frame } => {
+                    let reply = match frame {
+                        Frame::Text(text) => self.handle_text(client, &outbox, &text),
+                        Frame::Binary(bytes) => self.handle_binary(&bytes),
+                        _ => continue,
+                    };
+                    // A client that hung up meanwhile is cleaned up by its Disconnected event
+                    let _ = outbox.send(reply);
+                }
+                Event::Disconnected { client } => self.disconnect(client),
+            }
+        }
+    }
+
+    fn disconnect(&mut self, client: ClientId) {
+        for hosted in self.simulations.values_mut() {
+            hosted.watchers.retain(|&(watcher, _)| watcher != client);
+        }
+    }
+
+    fn handle_text(&mut self, client: ClientId, outbox: &Sender<Frame>, text: &str) -> Frame {
+        let envelope: Envelope = match serde_json::from_str(text) {
+            Ok(envelope) => envelope,
+            Err(err) => return error_reply(Value::Null, format!("Malformed input: {}", err)),
+        };
+        match self.handle(client, outbox, envelope.request) {
+            Ok(Reply::Json(mut body)) => {
+                body["id"] = envelope.id;
+                body["ok"] = Value::Bool(true);
+                Frame::Text(body.to_string())
+            }
+            Ok(Reply::Binary(bytes)) => Frame::Binary(bytes),
+            Err(err) => error_reply(envelope.id, err),
+        }
+    }
+
+    /// `[sim u32 LE][encode_commands bytes]`, answered with a JSON reply
+    fn handle_binary(&mut self, bytes: &[u8]) -> Frame {
+        let Some((sim, commands)) = bytes.split_first_chunk::<4>() else {
+            return error_reply(Value::Null, "Binary messages start with the simulation id".to_string());
+        };
+        let sim = u32::from_le_bytes(*sim);
+        let limits = self.limits;
+        let applied = decode_commands(commands).and_then(|commands| {
+            let logic = &mut self.hosted(sim)?.logic;
+            apply_commands(logic, commands, limits)
+        });
+        match applied {
+            Ok(applied) => Frame::Text(json!({"id": null, "ok": true, "sim": sim, "applied": applied}).to_string()),
+            Err(err) => error_reply(Value::Null, err),
+        }
+    }
+
+    fn handle(&mut self, client: ClientId, outbox: &Sender<Frame>, request: Request) -> Result<Reply, String> {
+        match request {
+            Request::Create { config } => self.create(&config),
+            Request::Destroy { sim } => {
+                let hosted = self.simulations.remove(&sim).ok_or_else(|| unknown_simulation(sim))?;
+                let event = Frame::Text(json!({"event": "destroyed", "sim": sim}).to_string());
+                for (_, watcher) in &hosted.watchers {
+                    let _ = watcher.send(event.clone());
+                }
+                Ok(Reply::Json(json!({"sim": sim})))
+            }
+            Request::List => {
+                let simulations: Vec<Value> = self
+                    .simulations
+                    .iter()
+                    .map(|(&sim, hosted)| {
+                        json!({
+                            "sim": sim,
+                            "tick": hosted.logic.tick(),
+                            "alive": hosted.logic.count_alive(),
+                            "complete": hosted.logic.is_complete(),
+                        })
+                    })
+                    .collect();
+                Ok(Reply::Json(json!({"simulations": simulations})))
+            }
+            Request::Step { sim, ticks } => {
+                if ticks > self.limits.max_ticks_per_step {
+                    return Err(format!("ticks must be at most {}", self.limits.max_ticks_per_step));
+                }
+                let hosted = self.hosted(sim)?;
+                for _ in 0..ticks {
+                    if hosted.logic.is_complete() {
+                        break;
+                    }
+                    hosted.logic.step();
+                }
+                if !hosted.watchers.is_empty() {
+                    let event = json!({"event": "tick", "sim": sim, "snapshot": json_snapshot(&mut hosted.logic)});
+                    let event = Frame::Text(event.to_string());
+                    hosted.watchers.retain(|(_, watcher)| watcher.send(event.clone()).is_ok());
+                }
+                Ok(Reply::Json(json!({
+                    "sim": sim,
+                    "tick": hosted.logic.tick(),
+                    "complete": hosted.logic.is_complete(),
+                })))
+            }
+            Request::Snapshot { sim, format } => {
+                let logic = &mut self.hosted(sim)?.logic;
+                Ok(match format {
+                    SnapshotFormat::Json => Reply::Json(json!({"sim": sim, "snapshot": json_snapshot(logic)})),
+                    SnapshotFormat::Binary => Reply::Binary(logic.request_binary_snapshot()),
+                })
+            }
+            Request::Command { sim, commands } => {
+                let limits = self.limits;
+                let applied = apply_commands(&mut self.hosted(sim)?.logic, commands, limits)?;
+                Ok(Reply::Json(json!({"sim": sim, "applied": applied})))
+            }
+            Request::Watch { sim } => {
+                let hosted = self.hosted(sim)?;
+                if hosted.watchers.iter().all(|&(watcher, _)| watcher != client) {
+                    hosted.watchers.push((client, outbox.clone()));
+                }
+                Ok(Reply::Json(json!({"sim": sim})))
+            }
+            Request::Unwatch { sim } => {
+                self.hosted(sim)?.watchers.retain(|&(watcher, _)| watcher != client);
+                Ok(Reply::Json(json!({"sim": sim})))
+            }
+        }
+    }
+
+    fn create(&mut self, config: &RunConfig) -> Result<Reply, String> {
+        let limits = self.limits;
+        if self.simulations.len() >= limits.max_simulations {
+            return Err(format!("The server already hosts {} simulations", limits.max_simulations));
+        }
+        // A bad world would panic or spin the hub thread every simulation shares
+        SimError::check_entity_count(config.entity_count).map_err(|err| err.to_string())?;
+        config.world.validate().map_err(|err| err.to_string())?;
+        if config.entity_count > limits.max_entities {
+            return Err(format!("entity_count must be at most {}", limits.max_entities));
+        }
+        if config.world.grid_size > limits.max_grid_size {
+            return Err(format!("grid_size must be at most {}", limits.max_grid_size));
+        }
+        let logic = SimulationLogic::from_run_config(config).map_err(|err| err.to_string())?;
+        let sim = self.next_id;
+        self.next_id += 1;
+        let tick = logic.tick();
+        self.simulations.insert(sim, Hosted { logic, watchers: Vec::new() });
+        Ok(Reply::Json(json!({"sim": sim, "tick": tick})))
+    }
+
+    fn hosted(&mut self, sim: u32) -> Result<&mut Hosted, String> {
+        self.simulations.get_mut(&sim).ok_or_else(|| unknown_simulation(sim))
+    }
+}
+
+/// Apply `commands` in order, stopping at the first one the simulation turns
+/// away or that would take it past `limits`
+fn apply_commands(logic: &mut SimulationLogic, commands: Vec<Command>, limits: Limits) -> Result<usize, String> {
+    let count = commands.len();
+    for (index, command) in commands.into_iter().enumerate() {
+        check_command(logic, &command, limits)
+            .and_then(|()| logic.apply_command(command))
+            .map_err(|err| format!("Command {}: {}", index, err))?;
+    }
+    Ok(count)
+}
+
+/// Refuse commands that would grow a simulation past the server's limits, with
+/// armies capped like entities; no command resizes the grid, so `max_grid_size`
+/// holds from `create` on
+fn check_command(logic: &SimulationLogic, command: &Command, limits: Limits) -> Result<(), String> {
+    match command {
+        Command::SpawnEntity { .. } if logic.entities().len() >= limits.max_entities => {
+            Err(format!("Simulations may have at most {} entities", limits.max_entities))
+        }
+        Command::RaiseArmy { .. } if logic.armies().len() >= limits.max_entities => {
+            Err(format!("Simulations may have at most {} armies", limits.max_entities))
+        }
+        Command::ScheduleOrder { .. } if logic.scheduled_command_count() >= limits.max_scheduled_orders => {
+            Err(format!("At most {} orders may be scheduled at once", limits.max_scheduled_orders))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn json_snapshot(logic: &mut SimulationLogic) -> Value {
+    let tick = logic.tick();
+    let schema = logic.snapshot_schema();
+    let values = logic.request_flat_snapshot().unwrap_or(&[]);
+    json!({"tick": tick, "schema": schema, "values": values})
+}
+
+fn unknown_simulation(sim: u32) -> String {
+    format!("Unknown simulation {}", sim)
+}
+
+fn error_reply(id: Value, error: String) -> Frame {
+    Frame::Text(json!({"id": id, "ok": false, "error": error}).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        max_simulations: 2,
+        max_entities: 50,
+        max_grid_size: 64,
+        max_ticks_per_step: 100,
+        max_scheduled_orders: 2,
+    };
+
+    fn request(hub: &mut Hub, client: ClientId, outbox: &Sender<Frame>, text: &str) -> Value {
+        match hub.handle_text(client, outbox, text) {
+            Frame::Text(reply) => serde_json::from_str(&reply).unwrap(),
+            frame => panic!("expected a JSON reply, got {:?}", frame),
+        }
+    }
+
+    #[test]
+    fn clients_create_step_and_watch_the_same_simulation() {
+        let mut hub = Hub::new(LIMITS);
+        let (alice, alice_inbox) = mpsc::channel();
+        let (bob, bob_inbox) = mpsc::channel();
+
+        let created = request(&mut hub, 1, &alice, r#"{"id": 7, "op": "create", "config": {"entity_count": 6}}"#);
+        assert_eq!(created["id"], 7);
+        assert_eq!(created["ok"], true);
+        let sim = created["sim"].as_u64().unwrap();
+
+        let watched = request(&mut hub, 2, &bob, &format!(r#"{{"op": "watch", "sim": {}}}"#, sim));
+        assert_eq!(watched["ok"], true);
+        let stepped = request(&mut hub, 1, &alice, &format!(r#"{{"op": "step", "sim": {}, "ticks": 3}}"#, sim));
+        assert_eq!(stepped["tick"], 3);
+
+        let Frame::Text(pushed) = bob_inbox.try_recv().unwrap() else { panic!("expected a text push") };
+        let pushed: Value = serde_json::from_str(&pushed).unwrap();
+        assert_eq!(pushed["event"], "tick");
+        assert_eq!(pushed["snapshot"]["tick"], 3);
+        let stride = pushed["snapshot"]["schema"]["stride"].as_u64().unwrap();
+        assert_eq!(pushed["snapshot"]["values"].as_array().unwrap().len() as u64, 6 * stride);
+        assert!(alice_inbox.try_recv().is_err(), "only watchers get pushes");
+
+        hub.disconnect(2);
+        request(&mut hub, 1, &alice, &format!(r#"{{"op": "step", "sim": {}}}"#, sim));
+        assert!(bob_inbox.try_recv().is_err(), "disconnected clients stop watching");
+    }
+
+    #[test]
+    fn commands_arrive_as_json_or_the_binary_protocol() {
+        let mut hub = Hub::new(LIMITS);
+        let (outbox, _inbox) = mpsc::channel();
+        let created = request(&mut hub, 1, &outbox, r#"{"op": "create", "config": {"entity_count": 4}}"#);
+        let sim = created["sim"].as_u64().unwrap();
+
+        let spawn = r#"{"kind": "SpawnEntity", "x": 0, "y": 0, "strength": 40}"#;
+        let command = format!(r#"{{"op": "command", "sim": {}, "commands": [{}]}}"#, sim, spawn);
+        let applied = request(&mut hub, 1, &outbox, &command);
+        assert_eq!(applied["applied"], 1);
+
+        let mut message = (sim as u32).to_le_bytes().to_vec();
+        message.extend(wasm::encode_commands(&[Command::SetTickRate { tick_rate: 30 }]));
+        let Frame::Text(reply) = hub.handle_binary(&message) else { panic!("expected a JSON reply") };
+        assert!(reply.contains(r#""applied":1"#), "{}", reply);
+
+        let Frame::Text(reply) = hub.handle_binary(&[1, 0]) else { panic!("expected a JSON reply") };
+        assert!(reply.contains(r#""ok":false"#), "{}", reply);
+    }
+
+    #[test]
+    fn requests_past_the_limits_are_refused() {
+        let mut hub = Hub::new(LIMITS);
+        let (outbox, _inbox) = mpsc::channel();
+        let too_many = request(&mut hub, 1, &outbox, r#"{"op": "create", "config": {"entity_count": 51}}"#);
+        assert_eq!(too_many["ok"], false);
+        let sim = request(&mut hub, 1, &outbox, r#"{"op": "create"}"#)["sim"].as_u64().unwrap();
+        request(&mut hub, 1, &outbox, r#"{"op": "create"}"#);
+        assert_eq!(request(&mut hub, 1, &outbox, r#"{"op": "create"}"#)["ok"], false);
+        let long_step = request(&mut hub, 1, &outbox, &format!(r#"{{"op": "step", "sim": {}, "ticks": 101}}"#, sim));
+        assert_eq!(long_step["ok"], false);
+        assert_eq!(request(&mut hub, 1, &outbox, r#"{"op": "step", "sim": 99}"#)["error"], "Unknown simulation 99");
+        assert_eq!(request(&mut hub, 1, &outbox, r#"{"op": "fly"}"#)["ok"], false);
+    }
+
+    #[test]
+    fn broken_worlds_are_refused_before_they_are_built() {
+        let mut hub = Hub::new(LIMITS);
+        let (outbox, _inbox) = mpsc::channel();
+        let create = |world: &str| format!(r#"{{"op": "create", "config": {{"world": {}}}}}"#, world);
+        for (world, error) in [
+            (r#"{"world_size": 2400, "cell_size": 150, "grid_size": 0}"#, "Grid size must be at least 1"),
+            (r#"{"world_size": -5, "cell_size": 150, "grid_size": 8}"#, "world_size must be positive"),
+            (r#"{"world_size": 2400, "cell_size": -1, "grid_size": 8}"#, "cell_size must be positive"),
+            (r#"{"world_size": 1e39, "cell_size": 150, "grid_size": 8}"#, "world_size must be positive"),
+        ] {
+            let refused = request(&mut hub, 1, &outbox, &create(world));
+            assert_eq!(refused["error"], error, "{}", world);
+        }
+        assert!(hub.simulations.is_empty());
+    }
+
+    #[test]
+    fn commands_past_the_limits_are_refused() {
+        let mut hub = Hub::new(LIMITS);
+        let (outbox, _inbox) = mpsc::channel();
+        let created = request(&mut hub, 1, &outbox, r#"{"op": "create", "config": {"entity_count": 49}}"#);
+        let sim = created["sim"].as_u64().unwrap();
+        let command = |commands: &str| format!(r#"{{"op": "command", "sim": {}, "commands": [{}]}}"#, sim, commands);
+
+        let spawn = r#"{"kind": "SpawnEntity", "x": 0, "y": 0, "strength": 40}"#;
+        assert_eq!(request(&mut hub, 1, &outbox, &command(spawn))["applied"], 1);
+        let refused = request(&mut hub, 1, &outbox, &command(spawn));
+        assert_eq!(refused["error"], "Command 0: Simulations may have at most 50 entities");
+
+        let schedule = r#"{"kind": "ScheduleOrder", "tick": 5, "entity_id": 0, "order": {"kind": "Hold"}}"#;
+        let two = format!("{}, {}", schedule, schedule);
+        assert_eq!(request(&mut hub, 1, &outbox, &command(&two))["applied"], 2);
+        let mut message = (sim as u32).to_le_bytes().to_vec();
+        message.extend(wasm::encode_commands(&[serde_json::from_str(schedule).unwrap()]));
+        let Frame::Text(reply) = hub.handle_binary(&message) else { panic!("expected a JSON reply") };
+        assert!(reply.contains("At most 2 orders may be scheduled at once"), "{}", reply);
+
+        let raise = r#"{"kind": "RaiseArmy", "entity_id": 0, "strength": 0.01, "grid_index": 0}"#;
+        let armies = vec![raise; 51].join(", ");
+        let refused = request(&mut hub, 1, &outbox, &command(&armies));
+        assert_eq!(refused["error"], "Command 50: Simulations may have at most 50 armies");
+    }
+}
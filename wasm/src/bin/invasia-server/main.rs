@@ -0,0 +1,141 @@
+//! Host authoritative simulations for thin clients over WebSocket
+//!
+//! `cargo run --release --features server --bin invasia-server -- --addr 127.0.0.1:8787`
+//!
+//! Text messages are JSON requests tagged by `op`, answered with the same
+//! `id` and `ok`: `create` (`config` is a `RunConfig`), `destroy`, `list`,
+//! `step` (`ticks`), `snapshot` (`format` `json` or `binary`), `command`
+//! (`commands` as protocol `Command` JSON), and `watch`/`unwatch`, after which
+//! the client is sent the JSON snapshot of every step any client makes.
+//! Binary messages are a simulation id (u32 LE) followed by `encode_commands` bytes.
+
+mod hub;
+mod websocket;
+
+use std::io::{self, BufReader};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use clap::Parser;
+
+use hub::{ClientId, Event, Hub, Limits};
+use websocket::{Frame, FrameReader};
+
+#[derive(Parser)]
+#[command(name = "invasia-server", about = "Host Invasia simulations for WebSocket clients")]
+struct Args {
+    /// Address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8787")]
+    addr: String,
+
+    /// Simulations hosted at once
+    #[arg(long, default_value_t = 64)]
+    max_simulations: usize,
+
+    /// Entities a simulation may be created with
+    #[arg(long, default_value_t = 10_000)]
+    max_entities: usize,
+
+    /// Grid width and height a simulation may be created with
+    #[arg(long, default_value_t = 512)]
+    max_grid_size: usize,
+
+    /// Ticks a single step request may run
+    #[arg(long, default_value_t = 10_000)]
+    max_ticks_per_step: u64,
+
+    /// Orders a simulation may hold scheduled for future ticks
+    #[arg(long, default_value_t = 10_000)]
+    max_scheduled_orders: usize,
+
+    /// Largest message accepted from a client, in bytes
+    #[arg(long, default_value_t = 1 << 20)]
+    max_message_bytes: usize,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let listener = match TcpListener::bind(&args.addr) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("could not listen on {}: {}", args.addr, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("listening on ws://{}", args.addr);
+
+    let limits = Limits {
+        max_simulations: args.max_simulations,
+        max_entities: args.max_entities,
+        max_grid_size: args.max_grid_size,
+        max_ticks_per_step: args.max_ticks_per_step,
+        max_scheduled_orders: args.max_scheduled_orders,
+    };
+    let (events, inbox) = mpsc::channel();
+    // The hub is built on its own thread; simulations never leave it
+    thread::spawn(move || Hub::new(limits).run(inbox));
+
+    for (client, stream) in (1..).zip(listener.incoming()) {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("could not accept a connection: {}", err);
+                continue;
+            }
+        };
+        let events = events.clone();
+        let max_message_bytes = args.max_message_bytes;
+        thread::spawn(move || {
+            if let Err(err) = serve(client, stream, &events, max_message_bytes) {
+                eprintln!("client {} dropped: {}", client, err);
+            }
+            let _ = events.send(Event::Disconnected { client });
+        });
+    }
+    ExitCode::SUCCESS
+}
+
+/// Upgrade the connection, then pass its messages to the hub until it closes.
+/// Replies and pushes go out on a writer thread so a slow reader never blocks the hub.
+fn serve(client: ClientId, mut stream: TcpStream, events: &Sender<Event>, max_message_bytes: usize) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    websocket::accept(&mut stream)?;
+
+    let (outbox, outgoing) = mpsc::channel::<Frame>();
+    let mut writer = stream.try_clone()?;
+    thread::spawn(move || {
+        for frame in outgoing {
+            if websocket::write_frame(&mut writer, &frame).is_err() || frame == Frame::Close {
+                break;
+            }
+        }
+        let _ = writer.shutdown(Shutdown::Both);
+    });
+
+    let mut reader = BufReader::new(stream);
+    let mut frames = FrameReader::new(max_message_bytes);
+    loop {
+        match frames.read_frame(&mut reader)? {
+            Frame::Ping(payload) => {
+                let _ = outbox.send(Frame::Pong(payload));
+            }
+            Frame::Pong(_) => {}
+            Frame::Close => {
+                let _ = outbox.send(Frame::Close);
+                return Ok(());
+            }
+            frame => {
+                let message = Event::Message {
+                    client,
+                    outbox: outbox.clone(),
+                    frame,
+                };
+                if events.send(message).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
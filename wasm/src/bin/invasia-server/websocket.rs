@@ -0,0 +1,325 @@
+//! Just enough of RFC 6455 to talk to browsers and other WebSocket clients:
+//! the opening handshake and unextended frames
+
+use std::io::{self, Read, Write};
+
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Longest opening handshake request read before giving up on a client
+const MAX_HANDSHAKE_LEN: usize = 8 * 1024;
+
+pub const OPCODE_CONTINUATION: u8 = 0x0;
+pub const OPCODE_TEXT: u8 = 0x1;
+pub const OPCODE_BINARY: u8 = 0x2;
+pub const OPCODE_CLOSE: u8 = 0x8;
+pub const OPCODE_PING: u8 = 0x9;
+pub const OPCODE_PONG: u8 = 0xA;
+
+/// A whole message or control frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Read the client's opening handshake and switch protocols, or answer 400
+/// and fail if it isn't a WebSocket upgrade
+pub fn accept(stream: &mut (impl Read + Write)) -> io::Result<()> {
+    let mut request = Vec::new();
+    let mut byte = [0u8; 1];
+    while !request.ends_with(b"\r\n\r\n") {
+        if request.len() >= MAX_HANDSHAKE_LEN {
+            return Err(invalid("handshake too long"));
+        }
+        stream.read_exact(&mut byte)?;
+        request.push(byte[0]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let key = request.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("sec-websocket-key").then(|| value.trim().to_string())
+    });
+    let Some(key) = key else {
+        stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+        return Err(invalid("not a WebSocket upgrade"));
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    );
+    stream.write_all(response.as_bytes())
+}
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key, ACCEPT_GUID).as_bytes()))
+}
+
+/// Longest payload a ping, pong or close frame may carry
+const MAX_CONTROL_LEN: u64 = 125;
+
+/// Reads one client's frames, keeping a fragmented message pending across
+/// the control frames that may arrive between its fragments
+pub struct FrameReader {
+    max_len: usize,
+    message: Option<(u8, Vec<u8>)>,
+}
+
+impl FrameReader {
+    /// Refuse messages longer than `max_len` bytes once their fragments are joined
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, message: None }
+    }
+
+    /// Read the next message, joining fragments, or control frame. Clients must
+    /// mask what they send; control frames must be unfragmented and short.
+    pub fn read_frame(&mut self, stream: &mut impl Read) -> io::Result<Frame> {
+        loop {
+            let mut header = [0u8; 2];
+            stream.read_exact(&mut header)?;
+            let fin = header[0] & 0x80 != 0;
+            let opcode = header[0] & 0x0F;
+            let control = opcode & 0x08 != 0;
+            if header[1] & 0x80 == 0 {
+                return Err(invalid("client frames must be masked"));
+            }
+            let len = match header[1] & 0x7F {
+                126 => {
+                    let mut len = [0u8; 2];
+                    stream.read_exact(&mut len)?;
+                    u16::from_be_bytes(len) as u64
+                }
+                127 => {
+                    let mut len = [0u8; 8];
+                    stream.read_exact(&mut len)?;
+                    u64::from_be_bytes(len)
+                }
+                len => len as u64,
+            };
+            if control && !fin {
+                return Err(invalid("control frames must not be fragmented"));
+            }
+            if control && len > MAX_CONTROL_LEN {
+                return Err(invalid("control frame too long"));
+            }
+            // Control frames don't join the pending message, so only its fragments add up
+            let buffered = match &self.message {
+                Some((_, payload)) if !control => payload.len(),
+                _ => 0,
+            };
+            if buffered as u64 + len > self.max_len as u64 {
+                return Err(invalid("message too long"));
+            }
+            let mut mask = [0u8; 4];
+            stream.read_exact(&mut mask)?;
+            let mut payload = vec![0u8; len as usize];
+            stream.read_exact(&mut payload)?;
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+
+            match opcode {
+                OPCODE_PING => return Ok(Frame::Ping(payload)),
+                OPCODE_PONG => return Ok(Frame::Pong(payload)),
+                OPCODE_CLOSE => return Ok(Frame::Close),
+                OPCODE_TEXT | OPCODE_BINARY if self.message.is_none() => self.message = Some((opcode, payload)),
+                OPCODE_CONTINUATION => match self.message.as_mut() {
+                    Some((_, buffer)) => buffer.extend_from_slice(&payload),
+                    None => return Err(invalid("continuation without a message")),
+                },
+                _ => return Err(invalid("unexpected opcode")),
+            }
+            if fin {
+                let (opcode, payload) = self.message.take().expect("message started above");
+                return match opcode {
+                    OPCODE_TEXT => {
+                        String::from_utf8(payload).map(Frame::Text).map_err(|_| invalid("text is not UTF-8"))
+                    }
+                    _ => Ok(Frame::Binary(payload)),
+                };
+            }
+        }
+    }
+}
+
+/// Write `frame` unmasked and unfragmented, as servers send them
+pub fn write_frame(stream: &mut impl Write, frame: &Frame) -> io::Result<()> {
+    let (opcode, payload) = match frame {
+        Frame::Text(text) => (OPCODE_TEXT, text.as_bytes()),
+        Frame::Binary(bytes) => (OPCODE_BINARY, bytes.as_slice()),
+        Frame::Ping(bytes) => (OPCODE_PING, bytes.as_slice()),
+        Frame::Pong(bytes) => (OPCODE_PONG, bytes.as_slice()),
+        Frame::Close => (OPCODE_CLOSE, &[][..]),
+    };
+    let mut header = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => header.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            header.push(126);
+            header.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            header.push(127);
+            header.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    stream.write_all(&header)?;
+    stream.write_all(payload)?;
+    stream.flush()
+}
+
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A client frame, masked as clients must send them
+    fn client_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x37, 0xFA, 0x21, 0x3D];
+        let mut frame = vec![if fin { 0x80 } else { 0 } | opcode];
+        match payload.len() {
+            len if len < 126 => frame.push(0x80 | len as u8),
+            len => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn accept_key_matches_the_rfc_example() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    #[test]
+    fn fragments_are_joined_and_control_frames_come_through() {
+        let mut bytes = client_frame(false, OPCODE_TEXT, b"Hel");
+        bytes.extend(client_frame(true, OPCODE_CONTINUATION, b"lo"));
+        bytes.extend(client_frame(true, OPCODE_PING, b"p"));
+        bytes.extend(client_frame(true, OPCODE_BINARY, &[7; 300]));
+        let mut stream = bytes.as_slice();
+        let mut reader = FrameReader::new(1024);
+        assert_eq!(reader.read_frame(&mut stream).unwrap(), Frame::Text("Hello".to_string()));
+        assert_eq!(reader.read_frame(&mut stream).unwrap(), Frame::Ping(b"p".to_vec()));
+        assert_eq!(reader.read_frame(&mut stream).unwrap(), Frame::Binary(vec![7; 300]));
+    }
+
+    #[test]
+    fn control_frames_between_fragments_keep_the_message() {
+        let mut bytes = client_frame(false, OPCODE_TEXT, b"Hel");
+        bytes.extend(client_frame(true, OPCODE_PING, b"p"));
+        bytes.extend(client_frame(false, OPCODE_CONTINUATION, b"l"));
+        bytes.extend(client_frame(true, OPCODE_PONG, b""));
+        bytes.extend(client_frame(true, OPCODE_CONTINUATION, b"o"));
+        bytes.extend(client_frame(false, OPCODE_BINARY, &[1, 2]));
+        bytes.extend(client_frame(true, OPCODE_CLOSE, b""));
+        let mut stream = bytes.as_slice();
+        let mut reader = FrameReader::new(1024);
+        assert_eq!(reader.read_frame(&mut stream).unwrap(), Frame::Ping(b"p".to_vec()));
+        assert_eq!(reader.read_frame(&mut stream).unwrap(), Frame::Pong(Vec::new()));
+        assert_eq!(reader.read_frame(&mut stream).unwrap(), Frame::Text("Hello".to_string()));
+        assert_eq!(reader.read_frame(&mut stream).unwrap(), Frame::Close);
+    }
+
+    #[test]
+    fn unmasked_and_oversized_frames_are_refused() {
+        let mut unmasked = client_frame(true, OPCODE_TEXT, b"hi");
+        unmasked[1] &= 0x7F;
+        assert!(FrameReader::new(1024).read_frame(&mut unmasked.as_slice()).is_err());
+        let oversized = client_frame(true, OPCODE_BINARY, &[0; 300]);
+        assert!(FrameReader::new(200).read_frame(&mut oversized.as_slice()).is_err());
+    }
+
+    #[test]
+    fn fragmented_and_long_control_frames_are_refused() {
+        let fragmented = client_frame(false, OPCODE_PING, b"p");
+        assert!(FrameReader::new(1024).read_frame(&mut fragmented.as_slice()).is_err());
+        let long = client_frame(true, OPCODE_PING, &[0; 126]);
+        assert!(FrameReader::new(1024).read_frame(&mut long.as_slice()).is_err());
+        let longest = client_frame(true, OPCODE_PING, &[0; 125]);
+        assert_eq!(FrameReader::new(1024).read_frame(&mut longest.as_slice()).unwrap(), Frame::Ping(vec![0; 125]));
+    }
+
+    #[test]
+    fn written_frames_carry_their_length() {
+        let mut short = Vec::new();
+        write_frame(&mut short, &Frame::Text("hi".to_string())).unwrap();
+        assert_eq!(short, [0x81, 2, b'h', b'i']);
+        let mut long = Vec::new();
+        write_frame(&mut long, &Frame::Binary(vec![1; 70_000])).unwrap();
+        assert_eq!(&long[..2], &[0x82, 127]);
+        assert_eq!(u64::from_be_bytes(long[2..10].try_into().unwrap()), 70_000);
+    }
+}
@@ -0,0 +1,118 @@
+//! Run Invasia simulations natively, without a browser
+//!
+//! `cargo run --release --features cli -- --entities 50 --grid-size 30 --seed 7`
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use wasm::{AiState, BatchRunner, RunConfig, RunReport, SimulationLogic, WorldConfig};
+
+#[derive(Parser)]
+#[command(name = "invasia", about = "Run an Invasia simulation natively and report the outcome")]
+struct Args {
+    /// Number of AI entities
+    #[arg(short, long, default_value_t = 10)]
+    entities: usize,
+
+    /// Territory grid width and height
+    #[arg(short, long, default_value_t = 50)]
+    grid_size: usize,
+
+    /// Seed for the entities, terrain and resources
+    #[arg(short, long, default_value_t = 0)]
+    seed: u32,
+
+    /// Stop after this many ticks if more than one entity is left
+    #[arg(short, long, default_value_t = 10_000)]
+    max_ticks: u64,
+
+    /// Ticks between progress lines, 0 to only print the final stats
+    #[arg(short, long, default_value_t = 100)]
+    progress_every: u64,
+
+    /// Write the final report here, as CSV for a `.csv` path and JSON otherwise
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let config = RunConfig {
+        entity_count: args.entities,
+        world: WorldConfig::default().with_grid_size(args.grid_size),
+        max_ticks: args.max_ticks,
+        ..RunConfig::default()
+    }
+    .with_seed(args.seed);
+
+    let report = match BatchRunner::run_with_progress(&config, |logic| {
+        if args.progress_every > 0 && logic.tick() % args.progress_every == 0 {
+            print_progress(logic);
+        }
+    }) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("invalid configuration: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    print_report(&report);
+
+    if let Some(path) = args.output {
+        let reports = [report];
+        let contents = if path.extension().is_some_and(|ext| ext == "csv") {
+            Ok(BatchRunner::to_csv(&reports))
+        } else {
+            BatchRunner::to_json(&reports)
+        };
+        if let Err(err) = contents.and_then(|contents| fs::write(&path, contents).map_err(|err| err.to_string())) {
+            eprintln!("could not write {}: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_progress(logic: &SimulationLogic) {
+    let alive = logic.entities().iter().filter(|entity| entity.state != AiState::Dead);
+    let (mut count, mut military) = (0, 0.0);
+    let mut leader = None;
+    for entity in alive {
+        count += 1;
+        military += entity.military_strength;
+        if leader.is_none_or(|(_, territory)| entity.territory > territory) {
+            leader = Some((entity.id, entity.territory));
+        }
+    }
+    let leader = leader
+        .map(|(id, territory)| format!("leader {} with {} spaces", id, territory))
+        .unwrap_or_else(|| "no leader".to_string());
+    println!(
+        "tick {:>6}: {} alive, {:.0} total military, {}",
+        logic.tick(),
+        count,
+        military,
+        leader
+    );
+}
+
+fn print_report(report: &RunReport) {
+    let outcome = match (report.completed, report.winner_id) {
+        (true, Some(winner_id)) if report.vassals > 0 => {
+            format!("entity {} won with {} vassals", winner_id, report.vassals)
+        }
+        (true, Some(winner_id)) => format!("entity {} won", winner_id),
+        (true, None) => "no entity survived".to_string(),
+        (false, _) => format!("stopped with {} entities left", report.survivors),
+    };
+    println!("{} after {} ticks", outcome, report.ticks);
+    if let Some(leader_id) = report.leader_id {
+        println!("largest territory: entity {} with {} spaces", leader_id, report.leader_territory);
+    }
+    println!(
+        "{} conquests, {} battles, {} deaths",
+        report.conquests, report.battles, report.deaths
+    );
+}
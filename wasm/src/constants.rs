@@ -1,11 +1,110 @@
 // Shared simulation constants
 
+// Default world scale
+pub const DEFAULT_WORLD_SIZE: f32 = 2400.0; // World spans -1200..1200 on both axes
+pub const DEFAULT_CELL_SIZE: f32 = 5.0; // Spatial hash bucket size
+pub const DEFAULT_GRID_SIZE: usize = 50; // Territory grid width/height
+pub const MAX_SPATIAL_GRID_DIM: usize = 1024; // Spatial hash buckets per axis before cells are widened
+
 // Resource generation rates per grid space per second
 pub const MILITARY_STRENGTH_PER_SPACE_PER_SEC: f32 = 0.5;
 pub const MONEY_PER_SPACE_PER_SEC: f32 = 1.0;
 
+// Upkeep and purchases
+pub const UPKEEP_PER_SPACE_PER_SEC: f32 = 0.1; // Money each owned grid space costs per second
+pub const UPKEEP_PER_MILITARY_PER_SEC: f32 = 0.01; // Money each point of military strength costs per second
+pub const MILITARY_PRICE: f32 = 2.0; // Money paid per point of military strength bought
+pub const UPKEEP_RESERVE_SECS: f32 = 10.0; // Seconds of upkeep an AI keeps in hand before buying military
+pub const BANKRUPTCY_DESERTION_PER_SEC: f32 = 0.1; // Fraction of an unpaid military that deserts per second
+pub const BANKRUPTCY_MORALE_DRAIN_PER_SEC: f32 = 0.05; // Morale lost per second while upkeep goes unpaid
+
 // Combat costs and bonuses
 pub const ATTACK_COST: f32 = 10.0; // Cost to attempt conquering a grid space
 pub const DEFENSE_BONUS_MULTIPLIER: f32 = 1.5; // Defense bonus when defending
 pub const DEFENSE_ACCUMULATION: f32 = 1.0; // Defense strength added per defending AI per tick
 pub const MAX_DEFENSE_STRENGTH: f32 = 50.0; // Maximum defense strength cap
+
+// Battles over owned spaces
+pub const COMBAT_SHARPNESS: f32 = 4.0; // How quickly win odds rise with the strength ratio
+pub const COMBAT_WIN_ATTACKER_LOSS: f32 = 0.75; // Fraction of the conquest cost a winning attacker loses
+pub const COMBAT_LOSS_ATTACKER_LOSS: f32 = 0.5; // Fraction of the conquest cost a repelled attacker loses
+pub const COMBAT_DEFENDER_LOSS: f32 = 0.5; // Fraction of the conquest cost the defender loses either way
+pub const COMBAT_DEFENSE_WEAR: f32 = 0.25; // Fraction of a space's defense worn down by a repelled attack
+
+// Supply lines
+pub const SUPPLY_CHECK_INTERVAL: u64 = 10; // Ticks between territory connectivity checks
+pub const SUPPLY_DECAY: f32 = 0.25; // Supply a cut-off space loses per check; at zero it falls back to unowned
+
+// Capitals
+pub const CAPITAL_TERRITORY_LOSS: f32 = 0.25; // Fraction of its remaining spaces an entity hands to whoever takes its capital
+pub const CAPITAL_RESOURCE_LOSS: f32 = 0.25; // Fraction of its military strength and money handed over with them
+pub const CAPITAL_START_DEFENSE: f32 = 5.0; // Defense strength an entity's starting space begins with
+pub const CAPITAL_DEFENSE_THREAT: u32 = 2; // Nearby attackers that make an AI drop its plans to fortify its capital
+
+// Morale
+pub const MORALE_MIN: f32 = 0.5; // Lowest morale multiplier on military strength
+pub const MORALE_MAX: f32 = 1.5; // Highest morale multiplier on military strength
+pub const MORALE_CONQUEST_GAIN: f32 = 0.05; // Morale gained per grid space taken
+pub const MORALE_LOSS_DRAIN: f32 = 0.05; // Morale lost per grid space lost or assault repelled
+pub const MORALE_STARVATION_DRAIN: f32 = 0.1; // Morale lost per supply check with all territory cut off, pro rata
+pub const MORALE_RECOVERY_PER_SEC: f32 = 0.02; // Drift back toward a morale of 1 per second
+pub const MORALE_WAVER: f32 = 0.75; // Below this an AI won't march on a neighbor
+
+// Relations
+pub const RELATIONS_LIMIT: f32 = 100.0; // Relations run from -RELATIONS_LIMIT to RELATIONS_LIMIT, 0 neutral
+pub const RELATIONS_ASSAULT_DROP: f32 = 5.0; // Relations lost per assault either side makes on the other's land
+pub const RELATIONS_CHECK_INTERVAL: u64 = 10; // Ticks between relations warming for pairs left in peace
+pub const RELATIONS_PEACE_GAIN: f32 = 1.0; // Relations gained per check by neighbors and old foes who didn't fight
+pub const RELATIONS_RIVALRY: f32 = -50.0; // At or below this an AI goes after the other over unowned land
+pub const RELATIONS_FRIENDLY: f32 = 50.0; // At or above this an AI won't attack the other
+
+// Unrest
+pub const UNREST_CHECK_INTERVAL: u64 = 10; // Ticks between unrest building up and restless spaces revolting
+
+// Disasters
+pub const DISASTER_CHECK_INTERVAL: u64 = 10; // Ticks between rolls for a new disaster of each kind
+
+// Vassals
+pub const VASSAL_CHECK_INTERVAL: u64 = 30; // Ticks between looks for entities ready to submit to a neighbor
+pub const VASSAL_TERRITORY_SHARE: f32 = 0.02; // Largest share of the grid an entity can hold and still submit
+pub const VASSAL_SIZE_RATIO: f32 = 4.0; // Times the territory a bordering neighbor needs to take it as a vassal
+pub const VASSAL_TRIBUTE_PER_SEC: f32 = 0.5; // Money a vassal pays its overlord per second, while it has any
+
+// Neutrals
+pub const NEUTRAL_GROWTH_INTERVAL: u64 = 10; // Ticks between neutral garrisons gaining defense
+
+// Behavior trees
+pub const BEHAVIOR_TREE_MAX_NODES: usize = 256; // Nodes one loaded tree may have
+
+// Heatmaps
+pub const HEATMAP_COMBAT_DECAY: f32 = 0.9; // Share of a space's recent-combat heat kept each tick
+
+// Movement
+pub const ENTITY_MOVE_SPEED: f32 = 8.0; // World units an entity walks per tick
+
+// Scheduling
+#[cfg(feature = "bindings")]
+pub const MAX_TICKS_PER_FRAME: u32 = 5; // Catch-up ticks a scheduler frame runs before dropping the backlog
+pub const MAX_CHECKPOINTS: usize = 128; // Rollback checkpoints kept, oldest dropped first
+
+// Benchmarks
+pub const BENCHMARK_WINDOW: usize = 120; // Most recent durations kept per timed section
+
+// Entity inspection
+pub const ENTITY_HISTORY_LEN: usize = 120; // Stat samples kept per watched entity
+pub const MAX_WATCHED_ENTITIES: usize = 16; // Entities whose history is recorded at once
+
+// World statistics
+pub const STATS_HISTORY_LEN: usize = 300; // Ticks of aggregate stats kept for progress graphs
+pub const TIMELINE_SAMPLE_INTERVAL: u64 = 10; // Ticks between territory timeline samples at the start of a run
+pub const TIMELINE_CAPACITY: usize = 256; // Timeline samples kept before halving their resolution
+
+// Event log
+pub const EVENT_LOG_CAPACITY: usize = 256; // Most recent events kept in memory
+pub const SYNC_EVENT_TAIL: usize = 64; // Events included in a late-join sync bundle
+
+// Notable events
+pub const NOTABLE_EVENT_CAPACITY: usize = 64; // Most recent highlights kept
+pub const NOTABLE_COMEBACK_LOW: f32 = 0.05; // Share of all held territory a comeback starts below
+pub const NOTABLE_COMEBACK_HIGH: f32 = 0.15; // Share held before the fall, and climbed back to for a comeback
+pub const NOTABLE_EXPANSION_SPACES: usize = 10; // Latest conquests of an entity timed for the fastest expansion
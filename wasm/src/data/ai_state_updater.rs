@@ -1,57 +1,153 @@
-use crate::constants::{ATTACK_COST, MILITARY_STRENGTH_PER_SPACE_PER_SEC, MONEY_PER_SPACE_PER_SEC};
-use crate::types::{AiEntity, AiState, EntitySnapshot};
+use crate::constants::{
+    ATTACK_COST, BANKRUPTCY_DESERTION_PER_SEC, BANKRUPTCY_MORALE_DRAIN_PER_SEC, MILITARY_STRENGTH_PER_SPACE_PER_SEC,
+    MONEY_PER_SPACE_PER_SEC, MORALE_RECOVERY_PER_SEC, RELATIONS_FRIENDLY, RELATIONS_RIVALRY, ENTITY_MOVE_SPEED,
+    VASSAL_TRIBUTE_PER_SEC,
+};
+use crate::types::{
+    AiEntity, AiState, AllianceGraph, EntitySnapshot, GridSpace, PlayerCommand, Relations, ResourceConfig,
+    SeasonEffects, TerrainConfig,
+};
+use crate::utils::distances_sq_x4;
 
+use super::brain::{EntityAction, EntityOrder, EntityView};
+use super::decision_adapter::GridBehavior;
 use super::grid_update_builder::GridUpdateBuilder;
+use super::pathfinder::Pathfinder;
+
+/// Threats, allies and rivals found around an entity during its neighbor scan
+struct NeighborScan {
+    nearby_attackers: u32,
+    ally_in_need: Option<u32>,
+    nearest_ally_dist_sq: f32,
+    rival: Option<(f32, u32)>, // Relations score and id of the bitterest rival in reach
+    prey: Option<(f32, u32)>, // Military strength and id of the weakest neighbor that isn't a friend
+}
+
+impl NeighborScan {
+    fn new() -> Self {
+        Self {
+            nearby_attackers: 0,
+            ally_in_need: None,
+            nearest_ally_dist_sq: f32::INFINITY,
+            rival: None,
+            prey: None,
+        }
+    }
+
+    /// Account for up to four neighbors, measuring their distances together
+    fn observe(
+        &mut self,
+        entity: &AiEntity,
+        neighbors: &[usize],
+        entity_snapshots: &[EntitySnapshot],
+        alliances: &AllianceGraph,
+        relations: &Relations,
+    ) {
+        // Unused lanes sit on the entity itself and are never read
+        let mut xs = [entity.position_x; 4];
+        let mut ys = [entity.position_y; 4];
+        for (lane, &other_index) in neighbors.iter().enumerate() {
+            let other = unsafe { entity_snapshots.get_unchecked(other_index) };
+            xs[lane] = other.position_x;
+            ys[lane] = other.position_y;
+        }
+        let distances = distances_sq_x4(entity.position_x, entity.position_y, xs, ys);
+
+        for (&other_index, &dist_sq) in neighbors.iter().zip(&distances) {
+            let other = unsafe { entity_snapshots.get_unchecked(other_index) };
+
+            // Allies are never threats; remember the closest one that is under pressure
+            if alliances.are_allies(entity.id, other.id) {
+                if other.state == AiState::Defending && dist_sq < self.nearest_ally_dist_sq {
+                    self.nearest_ally_dist_sq = dist_sq;
+                    self.ally_in_need = Some(other.id);
+                }
+                continue;
+            }
+
+            // An overlord leaves its vassals alone, whatever it thinks of them
+            if other.overlord == Some(entity.id) {
+                continue;
+            }
+
+            let score = relations.get(entity.id, other.id);
+            if score <= RELATIONS_RIVALRY && self.rival.is_none_or(|rival| (score, other.id) < rival) {
+                self.rival = Some((score, other.id));
+            }
+            let strength = other.military_strength;
+            if score < RELATIONS_FRIENDLY && self.prey.is_none_or(|prey| (strength, other.id) < prey) {
+                self.prey = Some((strength, other.id));
+            }
+
+            // Count nearby attacking entities as immediate threats
+            if other.state == AiState::Attacking && dist_sq < 5000.0 {
+                self.nearby_attackers += 1;
+            }
+        }
+    }
+}
 
 pub struct AiStateUpdater {
     current_time: f64,
+    time_scale: f32,
+    season: SeasonEffects,
 }
 
 impl AiStateUpdater {
     pub fn new() -> Self {
-        Self { current_time: 0.0 }
+        Self {
+            current_time: 0.0,
+            time_scale: 1.0,
+            season: SeasonEffects::NEUTRAL,
+        }
     }
 
     pub fn update_time(&mut self, time_ms: f64) {
         self.current_time = time_ms;
     }
 
+    /// Effects of the season the coming updates fall in
+    pub fn update_season(&mut self, season: SeasonEffects) {
+        self.season = season;
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Multiplier on elapsed time for resource accumulation; negative or
+    /// non-finite values are ignored
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        if time_scale.is_finite() && time_scale >= 0.0 {
+            self.time_scale = time_scale;
+        }
+    }
+
+    /// Accumulate an entity's resources and take stock of its surroundings
+    /// for its brain; `None` for a dead entity
     #[allow(clippy::too_many_arguments)]
-    pub fn update_entity(
-        &mut self,
+    pub fn observe(
+        &self,
         entity: &mut AiEntity,
-        _tick: u64,
         self_index: usize,
-        self_snapshot: EntitySnapshot,
         entity_snapshots: &[EntitySnapshot],
         grid: &GridUpdateBuilder,
-    ) {
+        alliances: &AllianceGraph,
+        relations: &Relations,
+        suggestion: GridBehavior,
+    ) -> Option<EntityView> {
         if entity.state == AiState::Dead {
-            return;
+            return None;
         }
 
-        // Time-based resource accumulation (decoupled from tick rate)
-        let time_delta_sec = if entity.last_update_time > 0.0 {
-            (self.current_time - entity.last_update_time) / 1000.0 // Convert ms to seconds
-        } else {
-            0.0
-        };
-        entity.last_update_time = self.current_time;
-
-        if time_delta_sec > 0.0 && entity.territory > 0 {
-            // Generate resources based on owned territory and elapsed time
-            let territory_count = entity.territory as f32;
-            let time_delta_sec_f32 = time_delta_sec as f32;
-            entity.military_strength += MILITARY_STRENGTH_PER_SPACE_PER_SEC * territory_count * time_delta_sec_f32;
-            entity.money += MONEY_PER_SPACE_PER_SEC * territory_count * time_delta_sec_f32;
-        }
+        self.accumulate_resources(entity);
 
-        // AI decision making - greedy territory expansion while considering defense
-        
-        // Check for nearby enemies and threats
-        let mut nearest_enemy_idx: Option<usize> = None;
-        let mut nearest_enemy_dist_sq = f32::INFINITY;
-        let mut nearby_attackers = 0;
+        // Look for nearby attackers and allies that need support, measuring
+        // distances four neighbors at a time
+        let self_snapshot = entity_snapshots[self_index];
+        let mut scan = NeighborScan::new();
+        let mut batch = [0usize; 4];
+        let mut batch_len = 0;
 
         grid.for_each_neighbor(
             self_snapshot.position_x,
@@ -61,69 +157,235 @@ impl AiStateUpdater {
                     return;
                 }
                 debug_assert!(other_index < entity_snapshots.len());
-                let other = unsafe { entity_snapshots.get_unchecked(other_index) };
-                
-                let dx = entity.position_x - other.position_x;
-                let dy = entity.position_y - other.position_y;
-                let dist_sq = dx * dx + dy * dy;
-
-                // Count nearby attacking entities as immediate threats
-                if other.state == AiState::Attacking && dist_sq < 5000.0 {
-                    nearby_attackers += 1;
-                }
-
-                // Track nearest enemy for defensive purposes
-                if other.state != AiState::Dead && dist_sq < nearest_enemy_dist_sq {
-                    nearest_enemy_dist_sq = dist_sq;
-                    nearest_enemy_idx = Some(other_index);
+                batch[batch_len] = other_index;
+                batch_len += 1;
+                if batch_len == batch.len() {
+                    scan.observe(entity, &batch, entity_snapshots, alliances, relations);
+                    batch_len = 0;
                 }
             },
         );
+        scan.observe(entity, &batch[..batch_len], entity_snapshots, alliances, relations);
 
-        // Greedy AI logic: prioritize attacking to gain territory
-        match entity.state {
-            AiState::Idle => {
-                // Be aggressive: attack if we have enough resources
-                // Consider defense needs if under immediate threat
-                if nearby_attackers > 0 && entity.military_strength < ATTACK_COST * 2.0 {
-                    // Under threat and low on resources, defend
-                    entity.state = AiState::Defending;
-                } else if entity.military_strength >= ATTACK_COST {
-                    // Greedy: attack whenever we have the minimum cost
-                    // This ensures AIs actively try to expand their territory
-                    entity.state = AiState::Attacking;
-                } else if nearby_attackers > 0 {
-                    // Not enough to attack but under threat, defend
-                    entity.state = AiState::Defending;
-                }
-                // Otherwise stay idle and accumulate resources
+        Some(EntityView {
+            id: entity.id,
+            profile: entity.profile,
+            military_strength: entity.military_strength,
+            morale: entity.morale,
+            money: entity.money,
+            upkeep_per_sec: entity.upkeep_per_sec(),
+            territory: entity.territory,
+            capital: entity.capital,
+            nearby_attackers: scan.nearby_attackers,
+            ally_in_need: scan.ally_in_need,
+            rival: scan.rival.map(|(_, rival_id)| rival_id),
+            prey: scan.prey,
+            suggestion,
+        })
+    }
+
+    /// Carry out the action an entity's brain decided on
+    pub fn act(&self, entity: &mut AiEntity, action: EntityAction) {
+        if action.buy_military > 0.0 {
+            // A brain may ask for more than the entity can pay for; it then buys nothing
+            let _ = entity.buy_military(action.buy_military);
+        }
+        entity.attack_target = None;
+        entity.fortify_target = None;
+        entity.support_target = None;
+        entity.state = match action.order {
+            EntityOrder::Hold => AiState::Idle,
+            EntityOrder::Expand => AiState::Attacking,
+            EntityOrder::Attack { target_id } => {
+                entity.attack_target = Some(target_id);
+                AiState::Attacking
             }
-            AiState::Attacking => {
-                // Continue attacking as long as we have resources
-                if entity.military_strength < ATTACK_COST {
-                    // Out of resources, switch to defending or idle
-                    if nearby_attackers > 0 {
-                        entity.state = AiState::Defending;
-                    } else {
-                        entity.state = AiState::Idle;
-                    }
-                }
+            EntityOrder::Defend => {
+                entity.fortify_target = entity.capital;
+                AiState::Defending
+            }
+            EntityOrder::Fortify { grid_index } => {
+                entity.fortify_target = Some(grid_index);
+                AiState::Defending
+            }
+            EntityOrder::Support { ally_id } => {
+                entity.support_target = Some(ally_id);
+                AiState::Allied
             }
-            AiState::Defending => {
-                // Transition from defending to attacking when safe and strong enough
-                if nearby_attackers == 0 && entity.military_strength >= ATTACK_COST * 1.5 {
-                    // No immediate threats and good resources, go on offense
-                    entity.state = AiState::Attacking;
-                } else if entity.military_strength < ATTACK_COST * 0.5 {
-                    // Very low on resources, stay idle to accumulate
-                    entity.state = AiState::Idle;
-                } else if nearby_attackers == 0 && nearest_enemy_dist_sq > 15000.0 {
-                    entity.state = AiState::Idle;
+        };
+    }
+
+    /// Observe, decide with a `GreedyBrain` and act in one go
+    #[cfg(all(test, feature = "bindings"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_entity(
+        &self,
+        entity: &mut AiEntity,
+        tick: u64,
+        self_index: usize,
+        entity_snapshots: &[EntitySnapshot],
+        grid: &GridUpdateBuilder,
+        alliances: &AllianceGraph,
+        relations: &Relations,
+        behavior: GridBehavior,
+    ) {
+        use super::brain::{Brain, GreedyBrain, WorldView};
+
+        let Some(view) = self.observe(entity, self_index, entity_snapshots, grid, alliances, relations, behavior) else {
+            return;
+        };
+        let world = WorldView {
+            tick,
+            #[cfg(not(target_arch = "wasm32"))]
+            entities: entity_snapshots,
+            #[cfg(not(target_arch = "wasm32"))]
+            alliances,
+            relations,
+        };
+        let action = GreedyBrain.decide(view, &world);
+        self.act(entity, action);
+    }
+
+    /// Carry out a player command in place of the AI decision
+    ///
+    /// `destination` is the center of the commanded grid space, if any.
+    pub fn follow_command(
+        &self,
+        entity: &mut AiEntity,
+        command: PlayerCommand,
+        destination: Option<(f32, f32)>,
+    ) {
+        if entity.state == AiState::Dead {
+            return;
+        }
+
+        self.accumulate_resources(entity);
+
+        entity.attack_target = None;
+        entity.fortify_target = None;
+        entity.support_target = None;
+        entity.state = match command {
+            PlayerCommand::MoveTo { .. } => {
+                if let Some(destination) = destination {
+                    if self.step_toward(&mut entity.position_x, &mut entity.position_y, destination) {
+                        // Arrived, wait here for the next order
+                        entity.command = Some(PlayerCommand::Hold);
+                    }
                 }
+                AiState::Idle
             }
-            AiState::Dead => {
-                return;
+            PlayerCommand::Attack { .. } if entity.effective_strength() >= ATTACK_COST => {
+                AiState::Attacking
             }
+            PlayerCommand::Attack { .. } | PlayerCommand::Hold => AiState::Idle,
+            PlayerCommand::Defend => AiState::Defending,
+        };
+    }
+
+    /// Frontier space an attacker goes for next, with the owned space to
+    /// strike it from
+    ///
+    /// Spaces are ranked by conquest cost per unit of income they would add,
+    /// so gold mines and farms are worth a tougher fight.
+    #[allow(clippy::too_many_arguments)]
+    pub fn choose_attack_space(
+        &self,
+        entity: &AiEntity,
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        from: usize,
+        terrain: &TerrainConfig,
+        resources: &ResourceConfig,
+    ) -> Option<(usize, usize)> {
+        Pathfinder::cheapest_frontier(
+            grid_spaces,
+            grid_size,
+            entity.id,
+            entity.attack_target,
+            from,
+            |space| Self::frontier_cost(space, terrain, resources),
+        )
+    }
+
+    /// Space an attacker pushes on to from `from`, a space it has just taken,
+    /// ranked like `choose_attack_space`
+    pub fn choose_follow_up_space(
+        &self,
+        entity: &AiEntity,
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        from: usize,
+        terrain: &TerrainConfig,
+        resources: &ResourceConfig,
+    ) -> Option<usize> {
+        Pathfinder::cheapest_neighbor(grid_spaces, grid_size, entity.attack_target, from, |space| {
+            Self::frontier_cost(space, terrain, resources)
+        })
+    }
+
+    /// Conquest cost per unit of income a space would add
+    fn frontier_cost(space: &GridSpace, terrain: &TerrainConfig, resources: &ResourceConfig) -> f32 {
+        let (money_yield, military_yield) = resources.space_yields(space, terrain);
+        space.conquest_cost(terrain) / (money_yield + military_yield)
+    }
+
+    /// Walk one tick's worth from a position toward a world position,
+    /// returning whether it has arrived
+    pub fn step_toward(&self, position_x: &mut f32, position_y: &mut f32, (x, y): (f32, f32)) -> bool {
+        let dx = x - *position_x;
+        let dy = y - *position_y;
+        let dist = (dx * dx + dy * dy).sqrt();
+        if dist <= ENTITY_MOVE_SPEED {
+            *position_x = x;
+            *position_y = y;
+            true
+        } else {
+            *position_x += dx / dist * ENTITY_MOVE_SPEED;
+            *position_y += dy / dist * ENTITY_MOVE_SPEED;
+            false
+        }
+    }
+
+    /// Time-based resource accumulation (decoupled from tick rate)
+    fn accumulate_resources(&self, entity: &mut AiEntity) {
+        let time_delta_sec = if entity.last_update_time > 0.0 {
+            (self.current_time - entity.last_update_time) / 1000.0 // Convert ms to seconds
+        } else {
+            0.0
+        };
+        entity.last_update_time = self.current_time;
+
+        if time_delta_sec <= 0.0 {
+            return;
+        }
+        let time_delta_sec_f32 = time_delta_sec as f32 * self.time_scale;
+
+        // Morale settles back toward 1 as time passes
+        let recovery = MORALE_RECOVERY_PER_SEC * time_delta_sec_f32;
+        entity.adjust_morale((1.0 - entity.morale).clamp(-recovery, recovery));
+
+        // Upkeep is owed on what was held over the elapsed time
+        let upkeep = entity.upkeep_per_sec() * time_delta_sec_f32;
+
+        if entity.territory > 0 {
+            // Generate resources based on owned territory, weighted by its
+            // terrain and resource nodes, the season and elapsed time
+            entity.military_strength += MILITARY_STRENGTH_PER_SPACE_PER_SEC * entity.military_yield * time_delta_sec_f32;
+            entity.money += MONEY_PER_SPACE_PER_SEC * entity.money_yield * self.season.money_factor * time_delta_sec_f32;
+        }
+
+        entity.money -= upkeep;
+        if entity.money < 0.0 {
+            // Bankrupt: the shortfall goes unpaid, so troops desert and the rest lose heart
+            entity.money = 0.0;
+            entity.military_strength *= (1.0 - BANKRUPTCY_DESERTION_PER_SEC * time_delta_sec_f32).max(0.0);
+            entity.adjust_morale(-BANKRUPTCY_MORALE_DRAIN_PER_SEC * time_delta_sec_f32);
+        }
+
+        // Vassals set their tribute aside out of whatever money is left
+        if entity.overlord.is_some() {
+            entity.tribute_due = (VASSAL_TRIBUTE_PER_SEC * time_delta_sec_f32).min(entity.money);
+            entity.money -= entity.tribute_due;
         }
     }
 }
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::BEHAVIOR_TREE_MAX_NODES;
+
+use super::brain::{Brain, EntityAction, EntityOrder, EntityView, GreedyBrain, WorldView};
+use super::decision_adapter::GridBehavior;
+
+/// Behavior tree node as written in JSON, e.g.
+///
+/// ```json
+/// { "type": "selector", "children": [
+///     { "type": "sequence", "children": [
+///         { "type": "condition", "check": "attackers_at_least", "value": 1 },
+///         { "type": "action", "do": "defend", "buy_military": 5 } ] },
+///     { "type": "action", "do": "expand" } ] }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TreeNode {
+    /// Runs its children in order until one fails
+    Sequence { children: Vec<TreeNode> },
+    /// Runs its children in order until one succeeds
+    Selector { children: Vec<TreeNode> },
+    Condition(ConditionNode),
+    Action(ActionNode),
+}
+
+/// Leaf that succeeds when its check holds, or fails when `invert` is set
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ConditionNode {
+    pub check: Check,
+    /// Threshold for the checks that compare against one
+    #[serde(default)]
+    pub value: f32,
+    #[serde(default)]
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Check {
+    /// At least `value` attackers that aren't allies are in reach
+    AttackersAtLeast,
+    /// Military strength, scaled by morale, is at least `value`
+    StrengthAtLeast,
+    MoneyAtLeast,
+    MoraleAtLeast,
+    /// A rival is in reach
+    HasRival,
+    /// The weakest neighbor in reach has at most `value` times our strength
+    PreyWeakerThan,
+    /// An ally in reach is defending
+    AllyInNeed,
+    /// The decision scoring pipeline suggests attacking a neighbor
+    SuggestsAttack,
+}
+
+/// Leaf that decides the entity's action, failing if it has nothing to act on
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ActionNode {
+    #[serde(rename = "do")]
+    pub act: TreeAction,
+    /// Military strength to buy before carrying the action out
+    #[serde(default)]
+    pub buy_military: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeAction {
+    Hold,
+    Expand,
+    Defend,
+    AttackRival,
+    AttackPrey,
+    /// Attack the neighbor the decision scoring pipeline suggests
+    AttackSuggested,
+    SupportAlly,
+    /// Whatever the greedy brain would do
+    Greedy,
+}
+
+/// How often one node has run and how it went, for debugging a tree
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct NodeStats {
+    pub node: String, // Node type, with the check or action of a leaf, e.g. "action expand"
+    pub ticks: u64,
+    pub successes: u64,
+    pub failures: u64,
+}
+
+/// Tree node with its children stored as indices into the flattened tree
+#[derive(Debug, Clone)]
+enum Node {
+    Sequence(Vec<usize>),
+    Selector(Vec<usize>),
+    Condition(ConditionNode),
+    Action(ActionNode),
+}
+
+/// Behavior tree flattened in pre-order, evaluated as an entity's brain
+///
+/// The last action a tick reaches is the decision, so a failed branch's
+/// action is overridden by whichever branch runs after it. A tick that
+/// reaches no action falls back to the greedy brain.
+#[derive(Debug, Clone)]
+pub struct BehaviorTree {
+    nodes: Vec<Node>,
+    stats: Vec<NodeStats>,
+}
+
+impl BehaviorTree {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let root: TreeNode = serde_json::from_str(json).map_err(|err| format!("Invalid behavior tree: {}", err))?;
+        Self::new(&root)
+    }
+
+    pub fn new(root: &TreeNode) -> Result<Self, String> {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            stats: Vec::new(),
+        };
+        tree.push(root)?;
+        Ok(tree)
+    }
+
+    /// Flatten `node` and its subtree onto the end of the tree, returning its index
+    fn push(&mut self, node: &TreeNode) -> Result<usize, String> {
+        if self.nodes.len() == BEHAVIOR_TREE_MAX_NODES {
+            return Err(format!("Behavior trees may have at most {} nodes", BEHAVIOR_TREE_MAX_NODES));
+        }
+        let index = self.nodes.len();
+        let (placeholder, name) = match *node {
+            TreeNode::Sequence { .. } => (Node::Sequence(Vec::new()), "sequence".to_string()),
+            TreeNode::Selector { .. } => (Node::Selector(Vec::new()), "selector".to_string()),
+            TreeNode::Condition(condition) => {
+                (Node::Condition(condition), format!("condition {}", condition.check.name()))
+            }
+            TreeNode::Action(action) => (Node::Action(action), format!("action {}", action.act.name())),
+        };
+        self.nodes.push(placeholder);
+        self.stats.push(NodeStats {
+            node: name,
+            ..NodeStats::default()
+        });
+        if let TreeNode::Sequence { children } | TreeNode::Selector { children } = node {
+            if children.is_empty() {
+                return Err(format!("{} node {} has no children", self.stats[index].node, index));
+            }
+            let mut indices = Vec::with_capacity(children.len());
+            for child in children {
+                indices.push(self.push(child)?);
+            }
+            if let Node::Sequence(slots) | Node::Selector(slots) = &mut self.nodes[index] {
+                *slots = indices;
+            }
+        }
+        Ok(index)
+    }
+
+    /// Execution counts of every node, in pre-order; the handler reads them through `Brain::node_stats`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stats(&self) -> &[NodeStats] {
+        &self.stats
+    }
+
+    /// Run node `index` and its subtree, returning whether it succeeded
+    fn tick(
+        nodes: &[Node],
+        stats: &mut [NodeStats],
+        index: usize,
+        view: &EntityView,
+        ctx: &WorldView,
+        decision: &mut Option<EntityAction>,
+    ) -> bool {
+        let success = match &nodes[index] {
+            Node::Sequence(children) => children
+                .iter()
+                .all(|&child| Self::tick(nodes, stats, child, view, ctx, decision)),
+            Node::Selector(children) => children
+                .iter()
+                .any(|&child| Self::tick(nodes, stats, child, view, ctx, decision)),
+            Node::Condition(condition) => condition.holds(view) != condition.invert,
+            Node::Action(action) => match action.order(view, ctx) {
+                Some(mut chosen) => {
+                    chosen.buy_military += action.buy_military;
+                    *decision = Some(chosen);
+                    true
+                }
+                None => false,
+            },
+        };
+        let node = &mut stats[index];
+        node.ticks += 1;
+        if success {
+            node.successes += 1;
+        } else {
+            node.failures += 1;
+        }
+        success
+    }
+}
+
+impl Brain for BehaviorTree {
+    fn decide(&mut self, view: EntityView, ctx: &WorldView) -> EntityAction {
+        let mut decision = None;
+        Self::tick(&self.nodes, &mut self.stats, 0, &view, ctx, &mut decision);
+        decision.unwrap_or_else(|| GreedyBrain.decide(view, ctx))
+    }
+
+    fn node_stats(&self) -> Option<&[NodeStats]> {
+        Some(&self.stats)
+    }
+}
+
+impl ConditionNode {
+    fn holds(&self, view: &EntityView) -> bool {
+        match self.check {
+            Check::AttackersAtLeast => view.nearby_attackers as f32 >= self.value,
+            Check::StrengthAtLeast => view.effective_strength() >= self.value,
+            Check::MoneyAtLeast => view.money >= self.value,
+            Check::MoraleAtLeast => view.morale >= self.value,
+            Check::HasRival => view.rival.is_some(),
+            Check::PreyWeakerThan => view
+                .prey
+                .is_some_and(|(strength, _)| strength <= self.value * view.effective_strength()),
+            Check::AllyInNeed => view.ally_in_need.is_some(),
+            Check::SuggestsAttack => matches!(view.suggestion, GridBehavior::Attack { .. }),
+        }
+    }
+}
+
+impl Check {
+    fn name(self) -> &'static str {
+        match self {
+            Check::AttackersAtLeast => "attackers_at_least",
+            Check::StrengthAtLeast => "strength_at_least",
+            Check::MoneyAtLeast => "money_at_least",
+            Check::MoraleAtLeast => "morale_at_least",
+            Check::HasRival => "has_rival",
+            Check::PreyWeakerThan => "prey_weaker_than",
+            Check::AllyInNeed => "ally_in_need",
+            Check::SuggestsAttack => "suggests_attack",
+        }
+    }
+}
+
+impl ActionNode {
+    /// The action this leaf takes, or `None` if there is nothing to take it against
+    fn order(&self, view: &EntityView, ctx: &WorldView) -> Option<EntityAction> {
+        let order = match self.act {
+            TreeAction::Hold => EntityOrder::Hold,
+            TreeAction::Expand => EntityOrder::Expand,
+            TreeAction::Defend => EntityOrder::Defend,
+            TreeAction::AttackRival => EntityOrder::Attack { target_id: view.rival? },
+            TreeAction::AttackPrey => EntityOrder::Attack { target_id: view.prey?.1 },
+            TreeAction::AttackSuggested => match view.suggestion {
+                GridBehavior::Attack { target_id } => EntityOrder::Attack { target_id },
+                _ => return None,
+            },
+            TreeAction::SupportAlly => EntityOrder::Support { ally_id: view.ally_in_need? },
+            TreeAction::Greedy => return Some(GreedyBrain.decide(*view, ctx)),
+        };
+        Some(order.into())
+    }
+}
+
+impl TreeAction {
+    fn name(self) -> &'static str {
+        match self {
+            TreeAction::Hold => "hold",
+            TreeAction::Expand => "expand",
+            TreeAction::Defend => "defend",
+            TreeAction::AttackRival => "attack_rival",
+            TreeAction::AttackPrey => "attack_prey",
+            TreeAction::AttackSuggested => "attack_suggested",
+            TreeAction::SupportAlly => "support_ally",
+            TreeAction::Greedy => "greedy",
+        }
+    }
+}
@@ -5,14 +5,13 @@ impl BenchmarkMetricBuilder {
         Self
     }
 
-    pub fn measure_tick<F, T>(&self, mut f: F) -> (T, f64)
-    where
-        F: FnMut() -> T,
-    {
-        let start = performance_now();
-        let result = f();
-        let duration = elapsed_duration(start);
-        (result, duration)
+    /// Timestamp to hand to `elapsed` once a section finishes
+    pub fn start(&self) -> f64 {
+        performance_now()
+    }
+
+    pub fn elapsed(&self, start: f64) -> f64 {
+        elapsed_duration(start)
     }
 
     pub fn measure_snapshot<F, T>(&self, mut f: F) -> (T, f64)
@@ -40,12 +39,7 @@ fn elapsed_duration(start: f64) -> f64 {
 
 #[cfg(target_arch = "wasm32")]
 fn performance_now() -> f64 {
-    thread_local! {
-        static PERFORMANCE: Option<web_sys::Performance> =
-            web_sys::window().and_then(|w| w.performance());
-    }
-
-    PERFORMANCE.with(|perf| perf.as_ref().map(|p| p.now()).unwrap_or(0.0))
+    crate::utils::now_ms()
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{ATTACK_COST, MILITARY_PRICE, RELATIONS_FRIENDLY, RELATIONS_RIVALRY, UPKEEP_PER_MILITARY_PER_SEC};
+use crate::types::{BehaviorProfile, Relations};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::types::{AllianceGraph, EntitySnapshot};
+
+use super::behavior_tree::NodeStats;
+use super::decision_adapter::{can_afford, GridBehavior};
+
+/// What an entity knows of itself and its surroundings when its brain decides
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EntityView {
+    pub id: u32,
+    pub profile: BehaviorProfile,
+    pub military_strength: f32,
+    pub morale: f32,
+    pub money: f32,
+    pub upkeep_per_sec: f32,
+    pub territory: u32,
+    pub capital: Option<u32>,
+    pub nearby_attackers: u32, // Attackers in reach that aren't allies
+    pub ally_in_need: Option<u32>, // Closest ally in reach that is defending
+    pub rival: Option<u32>, // Bitterest rival in reach, if relations have soured that far
+    pub prey: Option<(f32, u32)>, // Military strength and id of the weakest neighbor that isn't a friend
+    pub suggestion: GridBehavior, // Behavior the decision scoring pipeline picked
+}
+
+impl EntityView {
+    /// Military strength as it counts in battle, scaled by morale
+    pub fn effective_strength(&self) -> f32 {
+        self.military_strength * self.morale
+    }
+}
+
+/// The tick's starting state, shared by every decision in it
+pub struct WorldView<'a> {
+    pub tick: u64,
+    /// Native only, for custom brains; the built-in ones don't look at it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub entities: &'a [EntitySnapshot],
+    /// Native only, like `entities`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub alliances: &'a AllianceGraph,
+    pub relations: &'a Relations,
+}
+
+/// What an entity does with the tick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum EntityOrder {
+    /// Stand still while resources build up
+    Hold,
+    /// Claim unowned land bordering its territory
+    Expand,
+    /// Conquer spaces owned by a neighbor
+    Attack { target_id: u32 },
+    /// Reinforce its capital
+    Defend,
+    /// Reinforce one of its own spaces
+    Fortify { grid_index: u32 },
+    /// Reinforce an ally's home space
+    Support { ally_id: u32 },
+}
+
+/// A brain's decision: an order, and troops to buy before carrying it out.
+/// JSON puts the order's fields alongside, e.g. `{"kind":"Expand","buy_military":5}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EntityAction {
+    #[serde(flatten)]
+    pub order: EntityOrder,
+    /// Military strength to buy at `MILITARY_PRICE` a point; nothing is
+    /// bought if the entity can't pay for all of it
+    #[serde(default)]
+    pub buy_military: f32,
+}
+
+impl From<EntityOrder> for EntityAction {
+    fn from(order: EntityOrder) -> Self {
+        Self {
+            order,
+            buy_military: 0.0,
+        }
+    }
+}
+
+/// Decides what an AI-controlled entity does each tick
+///
+/// Brains are registered with `SimulationLogic::register_brain` and run one
+/// entity at a time, so they may keep state between decisions. Entities under
+/// player orders skip their brain.
+pub trait Brain {
+    fn decide(&mut self, view: EntityView, ctx: &WorldView) -> EntityAction;
+
+    /// Execution counts of each node, for brains built from a behavior tree
+    fn node_stats(&self) -> Option<&[NodeStats]> {
+        None
+    }
+}
+
+/// Follows the decision scoring pipeline's suggestion, bent by the entity's
+/// behavior profile, grudges and the threats around it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GreedyBrain;
+
+impl GreedyBrain {
+    /// Military to buy that leaves `reserve_secs` of upkeep in hand, counting
+    /// the upkeep on the troops bought
+    fn savings_to_spend(view: &EntityView, reserve_secs: f32) -> f32 {
+        let reserve = view.upkeep_per_sec * reserve_secs;
+        let amount = (view.money - reserve) / (MILITARY_PRICE + UPKEEP_PER_MILITARY_PER_SEC * reserve_secs);
+        amount.max(0.0)
+    }
+}
+
+impl Brain for GreedyBrain {
+    fn decide(&mut self, view: EntityView, ctx: &WorldView) -> EntityAction {
+        let profile = view.profile.thresholds();
+        let relations = ctx.relations;
+        // Losing the capital costs far more than any one plan gains; shaken
+        // troops fall back to it sooner and won't march on a neighbor at all
+        let threatened = view.nearby_attackers as f32 >= profile.capital_threat * view.morale;
+        // Savings go on troops once there is fighting to do, or only under
+        // threat for a profile that hoards its money
+        let buy_military = if threatened || (!profile.hoards_money && view.suggestion != GridBehavior::Accumulate) {
+            Self::savings_to_spend(&view, profile.upkeep_reserve_secs)
+        } else {
+            0.0
+        };
+        let strength = (view.military_strength + buy_military) * view.morale;
+        // Grudges decide who gets attacked: friends are left alone, and a
+        // rival in reach is worth more than unowned land. Profiles that don't
+        // pick fights only go to war over a grudge.
+        let behavior = match (view.suggestion, view.rival) {
+            (GridBehavior::Attack { target_id }, _)
+                if relations.get(view.id, target_id) < RELATIONS_FRIENDLY
+                    && (profile.picks_fights || relations.get(view.id, target_id) <= RELATIONS_RIVALRY) =>
+            {
+                view.suggestion
+            }
+            (GridBehavior::Attack { .. } | GridBehavior::Expand, Some(rival_id)) => {
+                GridBehavior::Attack { target_id: rival_id }
+            }
+            (GridBehavior::Attack { .. }, None) => GridBehavior::Expand,
+            (behavior, _) => behavior,
+        };
+        // Opportunists fall on any neighbor weak enough, whatever the plan was
+        let behavior = match (behavior, view.prey) {
+            (GridBehavior::Expand | GridBehavior::Accumulate, Some((prey_strength, prey_id)))
+                if prey_strength < profile.prey_ratio * strength =>
+            {
+                GridBehavior::Attack { target_id: prey_id }
+            }
+            _ => behavior,
+        };
+        let behavior = match (view.capital, behavior) {
+            (Some(capital), _) if threatened => GridBehavior::Fortify { grid_index: capital },
+            (_, GridBehavior::Attack { .. }) if view.morale < profile.waver_morale => GridBehavior::Accumulate,
+            (_, GridBehavior::Attack { .. } | GridBehavior::Expand)
+                if strength < profile.attack_margin * ATTACK_COST =>
+            {
+                GridBehavior::Accumulate
+            }
+            _ if can_afford(strength, behavior) => behavior,
+            _ => GridBehavior::Accumulate,
+        };
+        let order = match behavior {
+            GridBehavior::Attack { target_id } => EntityOrder::Attack { target_id },
+            GridBehavior::Fortify { grid_index } => EntityOrder::Fortify { grid_index },
+            GridBehavior::Expand if view.nearby_attackers > 0 || view.ally_in_need.is_none() => EntityOrder::Expand,
+            // Hold the capital while resources build up
+            GridBehavior::Expand | GridBehavior::Accumulate if view.nearby_attackers > 0 => EntityOrder::Defend,
+            GridBehavior::Expand | GridBehavior::Accumulate => match view.ally_in_need {
+                // Safe ourselves, so help hold an ally's territory before growing
+                Some(ally_id) => EntityOrder::Support { ally_id },
+                None => EntityOrder::Hold,
+            },
+        };
+        EntityAction { order, buy_military }
+    }
+}
+
+/// Decision callback of a `ScriptedBrain`; `None` defers to the greedy brain
+type BrainScript = Box<dyn FnMut(&EntityView, &WorldView) -> Option<EntityAction>>;
+
+/// Hands each decision to a callback, such as a JS function, falling back to
+/// a `GreedyBrain` whenever the callback has no answer
+pub struct ScriptedBrain {
+    script: BrainScript,
+    fallback: GreedyBrain,
+}
+
+impl ScriptedBrain {
+    pub fn new(script: impl FnMut(&EntityView, &WorldView) -> Option<EntityAction> + 'static) -> Self {
+        Self {
+            script: Box::new(script),
+            fallback: GreedyBrain,
+        }
+    }
+}
+
+impl Brain for ScriptedBrain {
+    fn decide(&mut self, view: EntityView, ctx: &WorldView) -> EntityAction {
+        match (self.script)(&view, ctx) {
+            Some(action) => action,
+            None => self.fallback.decide(view, ctx),
+        }
+    }
+}
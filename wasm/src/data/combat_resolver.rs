@@ -0,0 +1,54 @@
+use crate::constants::{COMBAT_DEFENDER_LOSS, COMBAT_LOSS_ATTACKER_LOSS, COMBAT_SHARPNESS, COMBAT_WIN_ATTACKER_LOSS};
+use crate::decision_scoring::{LogRatioLUT, SigmoidLUT};
+
+/// Result of a single battle over a grid space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CombatOutcome {
+    pub success: bool,
+    pub attacker_losses: f32,
+    pub defender_losses: f32,
+}
+
+/// Resolves attacks on owned spaces
+///
+/// The chance of taking a space is a sigmoid of the log ratio between the
+/// strength thrown at it and its conquest cost, so an attack at even odds
+/// wins half the time and a doubled force almost always does. Both sides
+/// take casualties whatever the outcome.
+pub struct CombatResolver {
+    sigmoid: SigmoidLUT,
+    log_ratio: LogRatioLUT,
+}
+
+impl CombatResolver {
+    pub fn new() -> Self {
+        Self {
+            sigmoid: SigmoidLUT::default(),
+            log_ratio: LogRatioLUT::default(),
+        }
+    }
+
+    /// Chance that `attacker_strength` takes a space costing `defense_cost`
+    pub fn win_probability(&self, attacker_strength: f32, defense_cost: f32) -> f32 {
+        if defense_cost <= 0.0 {
+            return 1.0;
+        }
+        let log_ratio = self.log_ratio.lookup(attacker_strength / defense_cost);
+        self.sigmoid.lookup(COMBAT_SHARPNESS * log_ratio)
+    }
+
+    /// Fight a battle, with `roll` drawn uniformly from `0..1`
+    pub fn resolve(&self, attacker_strength: f32, defense_cost: f32, roll: f32) -> CombatOutcome {
+        let success = roll < self.win_probability(attacker_strength, defense_cost);
+        let attacker_loss = if success {
+            COMBAT_WIN_ATTACKER_LOSS
+        } else {
+            COMBAT_LOSS_ATTACKER_LOSS
+        };
+        CombatOutcome {
+            success,
+            attacker_losses: (defense_cost * attacker_loss).min(attacker_strength),
+            defender_losses: defense_cost * COMBAT_DEFENDER_LOSS,
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::constants::{ATTACK_COST, MAX_DEFENSE_STRENGTH, MONEY_PER_SPACE_PER_SEC};
+use crate::decision_scoring::{
+    generate_shortlist_into, score_actions_batch_into, Action, ActionCandidate, BatchScoreResult,
+    BorderTile, Country, CountryEdge, LookupTables, PruningConfig, WorldState,
+};
+use serde::Serialize;
+
+use crate::types::{AiEntity, AiState, AllianceGraph, GridSpace, TerrainConfig};
+
+/// Grid resources are tiny next to the country model's, where costs assume
+/// stockpiles in the hundreds, so they are scaled up before scoring
+const RESOURCE_SCALE: f32 = 100.0;
+
+/// Grid-level behavior chosen for an entity this tick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind")]
+pub enum GridBehavior {
+    /// Hold still and let resources accumulate
+    Accumulate,
+    /// Conquer cells owned by a specific neighbor
+    Attack { target_id: u32 },
+    /// Claim unowned land bordering our territory
+    Expand,
+    /// Reinforce one of our own border cells
+    Fortify { grid_index: u32 },
+}
+
+/// Runs the decision scoring pipeline over the live grid simulation
+///
+/// Each tick a `Country` view is built for every alive entity from its
+/// territory: neighbors are the owners of adjacent cells and border tiles are
+/// owned cells touching hostile territory. The best scored `Action` is then
+/// mapped back onto a `GridBehavior`:
+///
+/// - `Attack` becomes an attack on that neighbor's cells
+/// - `Fortify`/`Move` reinforce the chosen border cell
+/// - `Invest`/`Research` improve the growth channel, which on the grid means
+///   claiming unowned land, so they expand when there is land to take
+/// - everything else accumulates
+///
+/// The world, its countries and the scoring buffers are kept between ticks
+/// and refilled in place, so a steady-state tick does not allocate.
+pub struct DecisionAdapter {
+    luts: LookupTables,
+    pruning: PruningConfig,
+    world: WorldState,
+    countries: Vec<Option<Country>>,
+    has_frontier: Vec<bool>,
+    behaviors: Vec<GridBehavior>,
+    shortlist: Vec<Action>,
+    ranked: Vec<ActionCandidate>,
+    batch: BatchScoreResult,
+}
+
+impl DecisionAdapter {
+    pub fn new() -> Self {
+        let mut pruning = PruningConfig::new();
+        // Alliances and wars are managed by the grid simulation itself,
+        // which has no use for espionage either
+        pruning.k_diplomacy = 0;
+        pruning.k_war = 0;
+        pruning.k_espionage = 0;
+        Self {
+            luts: LookupTables::new(),
+            pruning,
+            world: WorldState::new(),
+            countries: Vec::new(),
+            has_frontier: Vec::new(),
+            behaviors: Vec::new(),
+            shortlist: Vec::new(),
+            ranked: Vec::new(),
+            batch: BatchScoreResult::new(Vec::new(), Vec::new()),
+        }
+    }
+
+    /// Score every alive entity the AI controls and return one behavior per
+    /// entity index; entities under player orders are left on `Accumulate`
+    pub fn decide(
+        &mut self,
+        entities: &[AiEntity],
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        alliances: &AllianceGraph,
+        terrain: &TerrainConfig,
+    ) -> &[GridBehavior] {
+        self.behaviors.clear();
+        self.behaviors.resize(entities.len(), GridBehavior::Accumulate);
+        if entities.iter().all(|entity| entity.state == AiState::Dead || entity.command.is_some()) {
+            return &self.behaviors;
+        }
+
+        self.build_countries(entities, grid_spaces, grid_size, alliances, terrain);
+
+        let world = &mut self.world;
+        for country in self.countries.iter_mut().filter_map(Option::take) {
+            world.add_country(country);
+        }
+        world.update_weights();
+        // Entity ids are their indices, so this is the sorted order `update_threat_indices` uses
+        for entity in entities {
+            world.update_threat_index(entity.id, &self.luts);
+        }
+
+        // Countries bordering an active attacker get the defensive boost (§6)
+        for entity in entities {
+            let under_attack = world.get_country(entity.id).is_some_and(|country| {
+                country.edges.iter().any(|edge| {
+                    entities
+                        .get(edge.neighbor_id as usize)
+                        .is_some_and(|other| other.state == AiState::Attacking)
+                })
+            });
+            if under_attack {
+                if let Some(country) = world.get_country_mut(entity.id) {
+                    country.weights.apply_defensive_boost();
+                }
+            }
+        }
+
+        for (index, entity) in entities.iter().enumerate() {
+            if entity.command.is_some() {
+                continue;
+            }
+            let Some(country) = world.get_country(entity.id) else {
+                continue;
+            };
+            generate_shortlist_into(entity.id, country, world, &self.pruning, &mut self.shortlist, &mut self.ranked);
+            score_actions_batch_into(country, &self.shortlist, world, &self.luts, &mut self.batch);
+
+            let mut best_index = 0;
+            let mut best_score = f32::NEG_INFINITY;
+            for (i, &score) in self.batch.final_scores.iter().enumerate() {
+                if score > best_score {
+                    best_score = score;
+                    best_index = i;
+                }
+            }
+
+            self.behaviors[index] = match self.shortlist.get(best_index) {
+                Some(Action::Attack { target_id }) => GridBehavior::Attack {
+                    target_id: *target_id,
+                },
+                Some(Action::Fortify { tile_id }) | Some(Action::Move { tile_id }) => {
+                    GridBehavior::Fortify {
+                        grid_index: *tile_id,
+                    }
+                }
+                Some(Action::Invest { .. }) | Some(Action::Research { .. })
+                    if self.has_frontier[index] =>
+                {
+                    GridBehavior::Expand
+                }
+                _ => GridBehavior::Accumulate,
+            };
+        }
+
+        // Hand the countries back so next tick refills them in place
+        for country in world.drain_countries() {
+            let index = country.id as usize;
+            self.countries[index] = Some(country);
+        }
+        &self.behaviors
+    }
+
+    fn build_countries(
+        &mut self,
+        entities: &[AiEntity],
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        alliances: &AllianceGraph,
+        terrain: &TerrainConfig,
+    ) {
+        self.countries.resize_with(entities.len(), || None);
+        for (slot, entity) in self.countries.iter_mut().zip(entities) {
+            if entity.state == AiState::Dead {
+                *slot = None;
+                continue;
+            }
+            let country = slot.get_or_insert_with(|| Country::new(entity.id));
+            country.reset(entity.id);
+            country.m_eff = entity.effective_strength();
+            country.resources = (entity.money + entity.military_strength) * RESOURCE_SCALE;
+            country.gdp = entity.money_yield * MONEY_PER_SPACE_PER_SEC;
+            country.prestige = entity.territory as f32;
+            country.territory = entity.territory;
+            country.ally_count = alliances.allies_of(entity.id).count();
+        }
+        self.has_frontier.clear();
+        self.has_frontier.resize(entities.len(), false);
+
+        let offsets = [(-1i32, 0i32), (1, 0), (0, -1), (0, 1)];
+        for (grid_index, space) in grid_spaces.iter().enumerate() {
+            let Some(owner_id) = space.owner_id else {
+                continue;
+            };
+            let owner_index = owner_id as usize;
+            if !matches!(self.countries.get(owner_index), Some(Some(_))) {
+                continue;
+            }
+
+            let row = (grid_index / grid_size) as i32;
+            let col = (grid_index % grid_size) as i32;
+            let mut threat = 0.0;
+            for (dr, dc) in offsets {
+                let (r, c) = (row + dr, col + dc);
+                if r < 0 || r >= grid_size as i32 || c < 0 || c >= grid_size as i32 {
+                    continue;
+                }
+                let neighbor_space = &grid_spaces[r as usize * grid_size + c as usize];
+                let neighbor_id = match neighbor_space.owner_id {
+                    None => {
+                        self.has_frontier[owner_index] = true;
+                        continue;
+                    }
+                    Some(id) if id == owner_id || alliances.are_allies(owner_id, id) => continue,
+                    Some(id) => id,
+                };
+                // A vassal is no threat to its overlord, nor a target for it
+                let Some(neighbor) = entities
+                    .get(neighbor_id as usize)
+                    .filter(|e| e.state != AiState::Dead && e.overlord != Some(owner_id))
+                else {
+                    continue;
+                };
+
+                let hostility = if neighbor.state == AiState::Attacking {
+                    1.0
+                } else {
+                    0.5
+                };
+                threat += neighbor.effective_strength() * hostility;
+
+                let fortification = neighbor_space.defense_strength / MAX_DEFENSE_STRENGTH;
+                // Attackers cross wherever the terrain is easiest
+                let terrain_penalty = terrain.effects(neighbor_space.terrain).defense_bonus;
+                if let Some(country) = self.countries[owner_index].as_mut() {
+                    match country.get_edge_mut(neighbor_id) {
+                        Some(edge) => {
+                            edge.border_length += 1.0;
+                            edge.fortification = edge.fortification.max(fortification);
+                            edge.terrain_penalty = edge.terrain_penalty.min(terrain_penalty);
+                        }
+                        None => {
+                            let mut edge = CountryEdge::new(neighbor_id);
+                            edge.hostility = hostility;
+                            edge.fortification = fortification;
+                            edge.terrain_penalty = terrain_penalty;
+                            country.add_edge(edge);
+                            // Neighbors' armies are in plain sight on the grid
+                            country.intel.insert(neighbor_id, 1.0);
+                        }
+                    }
+                }
+            }
+
+            if threat > 0.0 {
+                if let Some(country) = self.countries[owner_index].as_mut() {
+                    let mut tile = BorderTile::new(grid_index as u32, col, row);
+                    tile.threat_gradient = threat;
+                    tile.fortification = space.defense_strength;
+                    country.border_tiles.push(tile);
+                }
+            }
+        }
+    }
+}
+
+/// Whether an entity with `effective_strength` can pay for the behavior it was assigned
+pub fn can_afford(effective_strength: f32, behavior: GridBehavior) -> bool {
+    match behavior {
+        GridBehavior::Attack { .. } | GridBehavior::Expand => {
+            effective_strength >= ATTACK_COST
+        }
+        GridBehavior::Fortify { .. } | GridBehavior::Accumulate => true,
+    }
+}
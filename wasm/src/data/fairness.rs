@@ -0,0 +1,153 @@
+use std::collections::BinaryHeap;
+
+use crate::types::{FairnessConfig, FairnessReport, GridSpace, ResourceConfig, StartFairness, TerrainConfig};
+
+use super::pathfinder::{adjacent_spaces, OpenSpace};
+
+/// Measures how evenly matched the entities' starting positions are
+///
+/// Each start is judged on the land within walking distance, what that land
+/// yields and how close the nearest other start is. Search buffers are kept
+/// between calls.
+#[derive(Default)]
+pub struct FairnessChecker {
+    costs: Vec<f32>,
+    touched: Vec<usize>, // Spaces whose cost the last survey set, reset before the next
+    open: BinaryHeap<OpenSpace>,
+    is_start: Vec<bool>,
+}
+
+impl FairnessChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report on `starts`, given as `(entity_id, grid_index)` pairs
+    pub fn assess(
+        &mut self,
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        starts: &[(u32, usize)],
+        terrain: &TerrainConfig,
+        resources: &ResourceConfig,
+        config: &FairnessConfig,
+    ) -> FairnessReport {
+        self.costs.clear();
+        self.costs.resize(grid_spaces.len(), f32::INFINITY);
+        self.touched.clear();
+        self.is_start.clear();
+        self.is_start.resize(grid_spaces.len(), false);
+        for &(_, start) in starts {
+            self.is_start[start] = true;
+        }
+        let starts: Vec<StartFairness> = starts
+            .iter()
+            .map(|&(entity_id, start)| {
+                let (reachable_area, resource_value) =
+                    self.survey(grid_spaces, grid_size, start, terrain, resources, config.radius);
+                StartFairness {
+                    entity_id,
+                    grid_index: start as u32,
+                    reachable_area,
+                    resource_value,
+                    nearest_neighbor: self.nearest_start(start, grid_size),
+                }
+            })
+            .collect();
+
+        let area_spread = spread(starts.iter().map(|start| start.reachable_area as f32));
+        let resource_spread = spread(starts.iter().map(|start| start.resource_value));
+        let distance_spread = spread(starts.iter().filter_map(|start| start.nearest_neighbor).map(|d| d as f32));
+        let mut report = FairnessReport {
+            starts,
+            area_spread,
+            resource_spread,
+            distance_spread,
+            tolerance: config.tolerance,
+            fair: false,
+            attempts: 0,
+        };
+        report.fair = report.worst_spread() <= config.tolerance;
+        report
+    }
+
+    /// Spaces within `radius` walking cost of `start`, paying each entered
+    /// space's terrain movement cost, and their money and military
+    /// multipliers added up
+    fn survey(
+        &mut self,
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        start: usize,
+        terrain: &TerrainConfig,
+        resources: &ResourceConfig,
+        radius: f32,
+    ) -> (u32, f32) {
+        for index in self.touched.drain(..) {
+            self.costs[index] = f32::INFINITY;
+        }
+        self.open.clear();
+        self.costs[start] = 0.0;
+        self.touched.push(start);
+        self.open.push(OpenSpace {
+            estimate: 0.0,
+            index: start,
+        });
+
+        let (mut area, mut value) = (0, 0.0);
+        while let Some(OpenSpace { estimate: cost, index }) = self.open.pop() {
+            if cost > self.costs[index] {
+                continue;
+            }
+            area += 1;
+            let (money, military) = resources.space_yields(&grid_spaces[index], terrain);
+            value += money + military;
+            for next in adjacent_spaces(index, grid_size) {
+                let next_cost = cost + terrain.effects(grid_spaces[next].terrain).movement_cost;
+                if next_cost <= radius && next_cost < self.costs[next] {
+                    if self.costs[next].is_infinite() {
+                        self.touched.push(next);
+                    }
+                    self.costs[next] = next_cost;
+                    self.open.push(OpenSpace {
+                        estimate: next_cost,
+                        index: next,
+                    });
+                }
+            }
+        }
+        (area, value)
+    }
+
+    /// Grid steps from `start` to the closest other start, searching outward
+    /// one ring at a time
+    fn nearest_start(&self, start: usize, grid_size: usize) -> Option<u32> {
+        let (row, col) = ((start / grid_size) as isize, (start % grid_size) as isize);
+        let size = grid_size as isize;
+        for distance in 1..2 * size {
+            for row_offset in -distance..=distance {
+                let col_offset = distance - row_offset.abs();
+                for col_offset in [col_offset, -col_offset] {
+                    let (r, c) = (row + row_offset, col + col_offset);
+                    if (0..size).contains(&r) && (0..size).contains(&c) && self.is_start[(r * size + c) as usize] {
+                        return Some(distance as u32);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Gap between the largest and smallest value as a fraction of the largest,
+/// 0 when there is nothing to compare
+fn spread(values: impl IntoIterator<Item = f32>) -> f32 {
+    let (min, max) = values
+        .into_iter()
+        .fold((f32::INFINITY, 0.0f32), |(min, max), value| (min.min(value), max.max(value)));
+    if max > 0.0 {
+        1.0 - min / max
+    } else {
+        0.0
+    }
+}
@@ -1,6 +1,6 @@
-use crate::types::{AiState, EntitySnapshot};
+use crate::constants::MAX_SPATIAL_GRID_DIM;
+use crate::types::{AiState, EntitySnapshot, WorldConfig};
 
-const GRID_SIZE: usize = 500;
 const MAX_ENTITIES_PER_CELL: usize = 4;
 
 pub struct GridUpdateBuilder {
@@ -8,9 +8,10 @@ pub struct GridUpdateBuilder {
 }
 
 impl GridUpdateBuilder {
-    pub fn new(cell_size: f32, search_radius: f32) -> Self {
+    /// Size the spatial hash to cover the whole world
+    pub fn for_world(world: &WorldConfig) -> Self {
         Self {
-            grid: SpatialGrid::new(cell_size, search_radius),
+            grid: SpatialGrid::new(world.world_size, world.cell_size, world.search_radius()),
         }
     }
 
@@ -24,12 +25,38 @@ impl GridUpdateBuilder {
     {
         self.grid.for_each_neighbor(x, y, f);
     }
+
+    /// Up to `k` entity indices closest to `(x, y)`, nearest first
+    pub fn nearest_k(&self, x: f32, y: f32, k: usize) -> Vec<usize> {
+        self.grid.nearest_k(x, y, k)
+    }
+
+    /// Entity indices within `radius` of `(x, y)`, nearest first
+    pub fn nearest_within(&self, x: f32, y: f32, radius: f32) -> Vec<usize> {
+        self.grid.nearest_within(x, y, radius)
+    }
+
+    /// Entity indices positioned inside the rectangle, lowest index first
+    pub fn within_rect(&self, min: (f32, f32), max: (f32, f32)) -> Vec<usize> {
+        self.grid.within_rect(min, max)
+    }
+
+    /// Entities that didn't fit in their cell's inline slots during the last rebuild
+    pub fn overflow_count(&self) -> usize {
+        self.grid.overflow_count
+    }
 }
 
 struct SpatialGrid {
     cell_size: f32,
     _search_radius: f32,
+    dim: usize,
     cells: Vec<([usize; MAX_ENTITIES_PER_CELL], usize)>,
+    // Entities beyond a cell's inline capacity, indexed like `cells`
+    spill: Vec<Vec<usize>>,
+    spilled_cells: Vec<usize>,
+    // Indexed positions by entity index, for distance-sorted queries
+    positions: Vec<(f32, f32)>,
     grid_min: (i32, i32),
     grid_max: (i32, i32),
     overflow_count: usize,
@@ -37,8 +64,12 @@ struct SpatialGrid {
 }
 
 impl SpatialGrid {
-    fn new(cell_size: f32, search_radius: f32) -> Self {
-        let capacity = GRID_SIZE * GRID_SIZE;
+    fn new(world_size: f32, cell_size: f32, search_radius: f32) -> Self {
+        // Widen cells for very large worlds so the bucket array stays bounded
+        let cell_size = cell_size.max(world_size / MAX_SPATIAL_GRID_DIM as f32);
+        // One spare bucket on each side absorbs positions sitting on the world edge
+        let dim = ((world_size / cell_size).ceil() as usize + 2).min(MAX_SPATIAL_GRID_DIM + 2);
+        let capacity = dim * dim;
         let mut cells = Vec::with_capacity(capacity);
         cells.resize(capacity, ([0; MAX_ENTITIES_PER_CELL], 0));
 
@@ -53,9 +84,13 @@ impl SpatialGrid {
         Self {
             cell_size,
             _search_radius: search_radius,
+            dim,
             cells,
-            grid_min: (-(GRID_SIZE as i32 / 2), -(GRID_SIZE as i32 / 2)),
-            grid_max: (GRID_SIZE as i32 / 2, GRID_SIZE as i32 / 2),
+            spill: vec![Vec::new(); capacity],
+            spilled_cells: Vec::new(),
+            positions: Vec::new(),
+            grid_min: (-(dim as i32 / 2), -(dim as i32 / 2)),
+            grid_max: (dim as i32 - dim as i32 / 2, dim as i32 - dim as i32 / 2),
             overflow_count: 0,
             neighbor_offsets,
         }
@@ -65,6 +100,11 @@ impl SpatialGrid {
         for cell in &mut self.cells {
             cell.1 = 0;
         }
+        for &cell_idx in &self.spilled_cells {
+            self.spill[cell_idx].clear();
+        }
+        self.spilled_cells.clear();
+        self.positions.clear();
         self.overflow_count = 0;
     }
 
@@ -84,54 +124,38 @@ impl SpatialGrid {
         }
         let x = (cx - self.grid_min.0) as usize;
         let y = (cy - self.grid_min.1) as usize;
-        Some(y * GRID_SIZE + x)
+        Some(y * self.dim + x)
     }
 
     fn rebuild(&mut self, snapshots: &[EntitySnapshot]) {
         self.clear();
+        self.positions
+            .extend(snapshots.iter().map(|e| (e.position_x, e.position_y)));
         for (index, entity) in snapshots.iter().enumerate() {
-            // Track Attacking, Defending, and Idle entities in the spatial grid
-            if entity.state != AiState::Attacking
-                && entity.state != AiState::Defending
-                && entity.state != AiState::Idle
-            {
+            // Every alive entity is indexed, whatever it is currently doing
+            if entity.state == AiState::Dead {
                 continue;
             }
 
-            let coords = self.cell_coords(entity.position_x, entity.position_y);
-            if let Some(cell_idx) = self.cell_index(coords.0, coords.1) {
+            // Entities past the world edge go in the border buckets rather than being lost
+            let (cx, cy) = self.cell_coords(entity.position_x, entity.position_y);
+            let cx = cx.clamp(self.grid_min.0, self.grid_max.0 - 1);
+            let cy = cy.clamp(self.grid_min.1, self.grid_max.1 - 1);
+            if let Some(cell_idx) = self.cell_index(cx, cy) {
                 let cell = &mut self.cells[cell_idx];
                 if cell.1 < MAX_ENTITIES_PER_CELL {
                     cell.0[cell.1] = index;
                     cell.1 += 1;
                 } else {
-                    self.overflow_count += 1;
-                    #[cfg(debug_assertions)]
-                    {
-                        eprintln!(
-                            "Warning: Spatial grid cell at ({}, {}) is full (max {} Attacking/Defending entities). Entity {} at ({:.2}, {:.2}) dropped. Total overflow: {}",
-                            coords.0,
-                            coords.1,
-                            MAX_ENTITIES_PER_CELL,
-                            index,
-                            entity.position_x,
-                            entity.position_y,
-                            self.overflow_count
-                        );
+                    // Dense cells spill into a per-cell list instead of dropping entities
+                    if self.spill[cell_idx].is_empty() {
+                        self.spilled_cells.push(cell_idx);
                     }
+                    self.spill[cell_idx].push(index);
+                    self.overflow_count += 1;
                 }
             }
         }
-
-        #[cfg(debug_assertions)]
-        {
-            if self.overflow_count > 0 {
-                eprintln!(
-                    "Spatial grid rebuild complete. {} Attacking/Defending entities couldn't be added due to cell capacity limits.",
-                    self.overflow_count
-                );
-            }
-        }
     }
 
     fn for_each_neighbor<F>(&self, x: f32, y: f32, mut f: F)
@@ -145,7 +169,123 @@ impl SpatialGrid {
                 for &entity_idx in &cell.0[..cell.1] {
                     f(entity_idx);
                 }
+                for &entity_idx in &self.spill[cell_idx] {
+                    f(entity_idx);
+                }
             }
         }
     }
+
+    /// Call `f` for every entity in cells exactly `ring` cells away from `(cx, cy)`
+    fn for_each_in_ring<F>(&self, cx: i32, cy: i32, ring: i32, mut f: F)
+    where
+        F: FnMut(usize),
+    {
+        let mut visit = |x: i32, y: i32| {
+            if let Some(cell_idx) = self.cell_index(x, y) {
+                let cell = &self.cells[cell_idx];
+                for &entity_idx in cell.0[..cell.1].iter().chain(&self.spill[cell_idx]) {
+                    f(entity_idx);
+                }
+            }
+        };
+        if ring == 0 {
+            visit(cx, cy);
+            return;
+        }
+        for x in (cx - ring)..=(cx + ring) {
+            visit(x, cy - ring);
+            visit(x, cy + ring);
+        }
+        for y in (cy - ring + 1)..(cy + ring) {
+            visit(cx - ring, y);
+            visit(cx + ring, y);
+        }
+    }
+
+    fn distance_sq(&self, x: f32, y: f32, entity_idx: usize) -> f32 {
+        let (ex, ey) = self.positions[entity_idx];
+        let dx = x - ex;
+        let dy = y - ey;
+        dx * dx + dy * dy
+    }
+
+    fn nearest_k(&self, x: f32, y: f32, k: usize) -> Vec<usize> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let (cx, cy) = self.cell_coords(x, y);
+        // Rings far enough out to cover the whole grid from any query point
+        let max_ring = self.dim as i32 * 2;
+        let mut found: Vec<(f32, usize)> = Vec::new();
+        for ring in 0..=max_ring {
+            self.for_each_in_ring(cx, cy, ring, |entity_idx| {
+                found.push((self.distance_sq(x, y, entity_idx), entity_idx));
+            });
+            if found.len() >= k {
+                // Anything in a later ring is at least `ring` cells away
+                sort_by_distance(&mut found);
+                let bound = ring as f32 * self.cell_size;
+                if found[k - 1].0 <= bound * bound {
+                    break;
+                }
+            }
+        }
+        sort_by_distance(&mut found);
+        found.into_iter().take(k).map(|(_, idx)| idx).collect()
+    }
+
+    fn nearest_within(&self, x: f32, y: f32, radius: f32) -> Vec<usize> {
+        if radius.is_nan() || radius < 0.0 {
+            return Vec::new();
+        }
+        let radius_sq = radius * radius;
+        let (cx, cy) = self.cell_coords(x, y);
+        let rings = ((radius / self.cell_size).ceil() as i32 + 1).min(self.dim as i32 * 2);
+        let mut found = Vec::new();
+        for ring in 0..=rings {
+            self.for_each_in_ring(cx, cy, ring, |entity_idx| {
+                let dist_sq = self.distance_sq(x, y, entity_idx);
+                if dist_sq <= radius_sq {
+                    found.push((dist_sq, entity_idx));
+                }
+            });
+        }
+        sort_by_distance(&mut found);
+        found.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    fn within_rect(&self, min: (f32, f32), max: (f32, f32)) -> Vec<usize> {
+        // Clamped like `rebuild` so entities in the border buckets are still visited
+        let clamp = |(cx, cy): (i32, i32)| {
+            (
+                cx.clamp(self.grid_min.0, self.grid_max.0 - 1),
+                cy.clamp(self.grid_min.1, self.grid_max.1 - 1),
+            )
+        };
+        let (min_cx, min_cy) = clamp(self.cell_coords(min.0, min.1));
+        let (max_cx, max_cy) = clamp(self.cell_coords(max.0, max.1));
+        let mut found = Vec::new();
+        for cy in min_cy..=max_cy {
+            for cx in min_cx..=max_cx {
+                let Some(cell_idx) = self.cell_index(cx, cy) else {
+                    continue;
+                };
+                let cell = &self.cells[cell_idx];
+                for &entity_idx in cell.0[..cell.1].iter().chain(&self.spill[cell_idx]) {
+                    let (x, y) = self.positions[entity_idx];
+                    if x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1 {
+                        found.push(entity_idx);
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+/// Nearest first, ties broken by entity index so results are deterministic
+fn sort_by_distance(found: &mut [(f32, usize)]) {
+    found.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
 }
@@ -0,0 +1,95 @@
+use crate::constants::HEATMAP_COMBAT_DECAY;
+use crate::types::{GridSpace, HeatmapKind, SimulationEvent};
+
+/// Keeps per-space combat and ownership tallies for heatmap overlays
+///
+/// Tallies are updated from each tick's events and changed spaces, and start
+/// over whenever the run does: the tick goes back or the grid is resized.
+/// Recent combat heat decays lazily, so a space is only touched when fought
+/// over or read.
+#[derive(Default)]
+pub struct HeatmapTracker {
+    tick: u64,
+    contested: Vec<f32>,
+    combat_heat: Vec<f32>,
+    combat_ticks: Vec<u64>, // Tick each space's heat was last updated
+    ownership_changes: Vec<f32>,
+    owners: Vec<Option<u32>>, // Owner each space had when the tick began
+}
+
+impl HeatmapTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note the owners of `changed` spaces before a tick runs, starting over
+    /// if the run was restarted, rewound or resized since the last tick
+    pub fn begin_tick(&mut self, tick: u64, grid_spaces: &[GridSpace], changed: &[usize]) {
+        if tick < self.tick || grid_spaces.len() != self.owners.len() {
+            self.clear(grid_spaces);
+        }
+        self.tick = tick;
+        for &index in changed {
+            self.owners[index] = grid_spaces[index].owner_id;
+        }
+    }
+
+    /// Tally a tick's battles
+    pub fn record_event(&mut self, event: &SimulationEvent) {
+        if let SimulationEvent::Battle { tick, grid_index, .. } = *event {
+            let index = grid_index as usize;
+            if index >= self.contested.len() {
+                return;
+            }
+            self.contested[index] += 1.0;
+            self.combat_heat[index] = self.heat(index, tick) + 1.0;
+            self.combat_ticks[index] = tick;
+        }
+    }
+
+    /// Count the `changed` spaces whose owner differs from the start of the tick
+    pub fn end_tick(&mut self, tick: u64, grid_spaces: &[GridSpace], changed: &[usize]) {
+        self.tick = tick;
+        for &index in changed {
+            let owner = grid_spaces[index].owner_id;
+            if self.owners[index] != owner {
+                self.owners[index] = owner;
+                self.ownership_changes[index] += 1.0;
+            }
+        }
+    }
+
+    /// One value per grid space, row-major, as of `tick`
+    pub fn heatmap(&self, kind: HeatmapKind, tick: u64, grid_spaces: &[GridSpace]) -> Vec<f32> {
+        match kind {
+            HeatmapKind::DefenseStrength => grid_spaces.iter().map(GridSpace::displayed_defense).collect(),
+            HeatmapKind::Unrest => grid_spaces
+                .iter()
+                .map(|space| if space.owner_id.is_some() { space.unrest } else { 0.0 })
+                .collect(),
+            // Tallies from before a restart or resize no longer apply
+            _ if tick < self.tick || grid_spaces.len() != self.owners.len() => vec![0.0; grid_spaces.len()],
+            HeatmapKind::ContestedFrequency => self.contested.clone(),
+            HeatmapKind::RecentCombat => (0..grid_spaces.len()).map(|index| self.heat(index, tick)).collect(),
+            HeatmapKind::OwnershipChanges => self.ownership_changes.clone(),
+        }
+    }
+
+    /// Heat of space `index` as of `tick`
+    fn heat(&self, index: usize, tick: u64) -> f32 {
+        let elapsed = tick.saturating_sub(self.combat_ticks[index]).min(i32::MAX as u64) as i32;
+        self.combat_heat[index] * HEATMAP_COMBAT_DECAY.powi(elapsed)
+    }
+
+    fn clear(&mut self, grid_spaces: &[GridSpace]) {
+        let grid_len = grid_spaces.len();
+        for values in [&mut self.contested, &mut self.combat_heat, &mut self.ownership_changes] {
+            values.clear();
+            values.resize(grid_len, 0.0);
+        }
+        self.combat_ticks.clear();
+        self.combat_ticks.resize(grid_len, 0);
+        self.owners.clear();
+        self.owners.extend(grid_spaces.iter().map(|space| space.owner_id));
+    }
+}
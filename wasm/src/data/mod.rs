@@ -1,58 +1,181 @@
 mod ai_neighbor_builder;
 mod ai_state_updater;
+mod behavior_tree;
+mod brain;
 mod benchmark_metric_builder;
+mod combat_resolver;
+mod decision_adapter;
+mod fairness;
 mod grid_update_builder;
+mod heatmap_tracker;
+mod notable_detector;
+mod pathfinder;
+mod supply_network;
 
 pub use ai_neighbor_builder::AiNeighborBuilder;
 pub use ai_state_updater::AiStateUpdater;
+pub use behavior_tree::{BehaviorTree, NodeStats};
 pub use benchmark_metric_builder::BenchmarkMetricBuilder;
+pub use brain::{Brain, EntityAction, EntityView, GreedyBrain, ScriptedBrain, WorldView};
+#[cfg(not(target_arch = "wasm32"))]
+pub use brain::EntityOrder;
+pub use combat_resolver::CombatResolver;
+pub use decision_adapter::DecisionAdapter;
+#[cfg(not(target_arch = "wasm32"))]
+pub use decision_adapter::GridBehavior;
+pub use fairness::FairnessChecker;
 pub use grid_update_builder::GridUpdateBuilder;
+pub use heatmap_tracker::HeatmapTracker;
+pub use notable_detector::NotableDetector;
+pub use pathfinder::Pathfinder;
+pub use supply_network::SupplyNetwork;
 
+use pathfinder::{adjacent_spaces, manhattan_distance};
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::mem;
+
+use crate::constants::{
+    CAPITAL_RESOURCE_LOSS, CAPITAL_START_DEFENSE, CAPITAL_TERRITORY_LOSS, ENTITY_HISTORY_LEN, EVENT_LOG_CAPACITY,
+    MAX_WATCHED_ENTITIES, MORALE_STARVATION_DRAIN, RELATIONS_CHECK_INTERVAL, RELATIONS_PEACE_GAIN, SUPPLY_DECAY,
+    SYNC_EVENT_TAIL, VASSAL_SIZE_RATIO, VASSAL_TERRITORY_SHARE,
+};
 use crate::types::{
-    AiEntity, BenchmarkMetrics, EntitySnapshot, GridSpace, PublicEntitySnapshot,
-    SimulationSnapshot, SNAPSHOT_FIELD_COUNT,
+    decode_grid_runs, encode_grid_runs, Affliction, AiEntity, AiState, AllianceGraph, Army, BehaviorProfile,
+    BenchmarkMetrics, CalendarConfig, CalendarDate, ConquestConfig, Diagnostics, DisasterKind, EntityDetails,
+    EntitySnapshot, EntityStats, FairnessConfig, FairnessReport, GridDiff, GridSpace, EventConfig, LeaderboardEntry,
+    LeaderboardKey, MapCell, MapDefinition, MapEntity, MapResource, MemoryStats, NeutralConfig, PlayerCommand,
+    PublicEntitySnapshot, SimStats, Relations, Season, SeasonEffects, SimulationEvent, SimulationSnapshot,
+    SnapshotField, SnapshotSchema, SyncBundle, SyncedEntity, ViewportCell, ViewportSnapshot, ResizePolicy,
+    ResourceConfig, TerrainConfig, UnrestConfig, WinCondition, WorldConfig, SNAPSHOT_FIELD_COUNT, SYNC_BUNDLE_VERSION,
+    UNOWNED_GRID_OWNER, vec_bytes,
 };
+use crate::types::resource::ResourceNode;
+use crate::types::terrain::TerrainType;
+use crate::utils::{grid_hash, StateHasher};
+
+/// Keeps start nudges independent of terrain features from the same seed
+const START_SEED_SALT: u32 = 0x2545_f491;
 
+#[derive(Clone)]
 pub struct SimulationData {
     tick: u64,
+    sim_time_ms: f64, // Simulated time so far, advanced every tick whatever the clock
     running: bool,
     tick_rate: u32,
     entity_count: usize,
-    grid_size: usize,  // Width/height of the grid
+    world: WorldConfig,
+    terrain: TerrainConfig,
+    resources: ResourceConfig,
+    conquest: ConquestConfig,
+    neutrals: NeutralConfig,
+    unrest: UnrestConfig,
+    event_config: EventConfig,
+    calendar: CalendarConfig,
+    win_condition: WinCondition,
+    victory_cells: Vec<u32>, // Grid indices of the victory point cells, sorted
+    afflictions: Vec<Affliction>, // Plagues and famines under way, in the order they struck
+    fairness: FairnessConfig,
+    start_attempts: u32, // Placements tried for the current starts, 0 if they weren't generated
+    map_terrain: Option<Vec<TerrainType>>, // Terrain of a loaded map, used instead of the terrain seed
+    map_resources: Option<Vec<Option<ResourceNode>>>, // Resource nodes of a loaded map, instead of the resource seed
     entities: Vec<AiEntity>,
+    run_start: Option<(Vec<AiEntity>, Vec<MapCell>)>, // Entities and owned spaces at tick 0, unknown after a sync
+    team_sizes: Vec<usize>, // Entities per team in id order, empty for a free-for-all
     grid_spaces: Vec<GridSpace>, // Flattened 2D grid
-    snapshot_buffer: Vec<EntitySnapshot>,
+    snapshot_buffer: Vec<EntitySnapshot>, // Read side of the entity double buffer: state as of the last rebuild
     flat_snapshot: Vec<f32>,
+    snapshot_fields: Vec<SnapshotField>, // Fields in each flat snapshot record, in snapshot order
+    grid_owner_snapshot: Vec<u32>,
+    grid_defense_snapshot: Vec<f32>,
+    grid_dirty_flags: Vec<bool>,
+    dirty_grid_cells: Vec<usize>,
+    grid_diff: GridDiff,
     snapshot_dirty: bool,
     flat_snapshot_dirty: bool,
     resource_transfers: Vec<(usize, f32, f32)>,
     dead_indices: Vec<usize>,
+    vassal_candidates: Vec<Option<u32>>, // Largest neighbor each entity could submit to, by entity id
+    events: VecDeque<SimulationEvent>,
+    new_events: Vec<SimulationEvent>, // Recorded since the last `drain_new_events`, for event callbacks
+    alliances: AllianceGraph,
+    relations: Relations,
+    armies: Vec<Army>,
+    next_army_id: u32,
+    pending_commands: Vec<(u32, PlayerCommand)>,
+    scheduled_commands: BTreeMap<u64, Vec<(u32, PlayerCommand)>>, // Lockstep commands by the tick they apply on
+    input_hasher: StateHasher, // Every command applied so far, in application order
+    watched_history: Vec<(u32, VecDeque<EntityStats>)>, // Recent stats of inspected entities, oldest watch first
+    stats: SimStats,
+    leaderboard_key: LeaderboardKey,
+    leaderboard: Vec<u32>, // Alive entity ids, best first by `leaderboard_key`
+    leaderboard_known: usize, // Entities considered by the leaderboard; later ids were spawned since
     metrics: BenchmarkMetrics,
 }
 
 impl SimulationData {
     pub fn new(entity_count: usize) -> Self {
-        Self::with_grid_size(entity_count, 50) // Default grid size 50x50
+        Self::with_world(entity_count, WorldConfig::default())
     }
 
-    pub fn with_grid_size(entity_count: usize, grid_size: usize) -> Self {
-        let total_grid_spaces = grid_size * grid_size;
+    pub fn with_world(entity_count: usize, world: WorldConfig) -> Self {
+        let total_grid_spaces = world.grid_size * world.grid_size;
         let mut data = Self {
             tick: 0,
+            sim_time_ms: 0.0,
             running: false,
             tick_rate: 60,
             entity_count,
-            grid_size,
+            world,
+            terrain: TerrainConfig::default(),
+            resources: ResourceConfig::default(),
+            conquest: ConquestConfig::default(),
+            neutrals: NeutralConfig::default(),
+            unrest: UnrestConfig::default(),
+            event_config: EventConfig::default(),
+            calendar: CalendarConfig::default(),
+            win_condition: WinCondition::default(),
+            victory_cells: Vec::new(),
+            afflictions: Vec::new(),
+            fairness: FairnessConfig::default(),
+            start_attempts: 0,
+            map_terrain: None,
+            map_resources: None,
             entities: Vec::with_capacity(entity_count),
+            run_start: None,
+            team_sizes: Vec::new(),
             grid_spaces: vec![GridSpace::new(); total_grid_spaces],
             snapshot_buffer: Vec::with_capacity(entity_count),
             flat_snapshot: Vec::with_capacity(entity_count * SNAPSHOT_FIELD_COUNT),
+            snapshot_fields: SnapshotField::ALL.to_vec(),
+            grid_owner_snapshot: Vec::with_capacity(total_grid_spaces),
+            grid_defense_snapshot: Vec::with_capacity(total_grid_spaces),
+            grid_dirty_flags: vec![false; total_grid_spaces],
+            dirty_grid_cells: Vec::with_capacity(total_grid_spaces),
+            grid_diff: GridDiff::default(),
             snapshot_dirty: true,
             flat_snapshot_dirty: true,
             resource_transfers: Vec::with_capacity(128),
             dead_indices: Vec::with_capacity(128),
+            vassal_candidates: Vec::new(),
+            events: VecDeque::with_capacity(EVENT_LOG_CAPACITY),
+            new_events: Vec::new(),
+            alliances: AllianceGraph::new(),
+            relations: Relations::new(),
+            armies: Vec::new(),
+            next_army_id: 0,
+            pending_commands: Vec::new(),
+            scheduled_commands: BTreeMap::new(),
+            input_hasher: StateHasher::new(),
+            watched_history: Vec::new(),
+            stats: SimStats::default(),
+            leaderboard_key: LeaderboardKey::default(),
+            leaderboard: Vec::new(),
+            leaderboard_known: 0,
             metrics: BenchmarkMetrics::default(),
         };
+        data.apply_world_layers();
         data.rebuild_entities(entity_count);
         data
     }
@@ -63,6 +186,12 @@ impl SimulationData {
 
     pub fn increment_tick(&mut self) {
         self.tick = self.tick.wrapping_add(1);
+        self.sim_time_ms += 1000.0 / self.tick_rate as f64;
+    }
+
+    /// Milliseconds simulated so far at each tick's rate, the `TimeMode::SimTime` clock
+    pub fn sim_time_ms(&self) -> f64 {
+        self.sim_time_ms
     }
 
     pub fn set_running(&mut self, running: bool) {
@@ -92,59 +221,241 @@ impl SimulationData {
 
     pub fn rebuild_entities(&mut self, entity_count: usize) {
         self.entities.clear();
+        self.clear_grid_ownership();
         
-        // Reset grid spaces
-        for space in &mut self.grid_spaces {
-            *space = GridSpace::new();
-        }
-        
-        // Assign each AI a deterministic starting grid space based on even distribution
-        for i in 0..entity_count {
-            let mut entity = AiEntity::new(i as u32);
-            
-            // Find an unoccupied grid space for this AI
-            // Use deterministic placement based on entity ID
-            let grid_area = self.grid_size * self.grid_size;
-            let spacing = (grid_area as f32 / entity_count as f32).sqrt().floor() as usize;
-            let spacing = spacing.max(1);
-            
-            let divisor = (self.grid_size / spacing).max(1);
-            let row = (i / divisor) * spacing;
-            let col = (i % divisor) * spacing;
-            let grid_index = (row.min(self.grid_size - 1)) * self.grid_size + col.min(self.grid_size - 1);
-            
-            // Assign this grid space to the AI, ensuring uniqueness
-            let mut assigned_index = grid_index;
-            let mut found = false;
-            for offset in 0..self.grid_spaces.len() {
-                let idx = (grid_index + offset) % self.grid_spaces.len();
-                if self.grid_spaces[idx].owner_id.is_none() {
-                    assigned_index = idx;
-                    found = true;
-                    break;
-                }
-            }
-            if found {
-                self.grid_spaces[assigned_index] = GridSpace::with_owner(entity.id, 5.0);
+        let half_extent = self.world.half_extent();
+        let starts = self.fairest_starts(entity_count);
+        for (i, start) in starts.into_iter().enumerate() {
+            let mut entity = AiEntity::with_half_extent(i as u32, half_extent);
+            if let Some(assigned_index) = start {
+                let space = &mut self.grid_spaces[assigned_index];
+                *space = GridSpace {
+                    terrain: space.terrain,
+                    resource: space.resource,
+                    ..GridSpace::with_owner(entity.id, CAPITAL_START_DEFENSE)
+                };
+                (entity.money_yield, entity.military_yield) =
+                    self.resources.space_yields(space, &self.terrain);
+                entity.resource_nodes = space.resource.is_some() as u32;
+                entity.capital = Some(assigned_index as u32);
                 
                 // Update entity position to be centered in their grid space
-                let assigned_row = assigned_index / self.grid_size;
-                let assigned_col = assigned_index % self.grid_size;
-                let grid_x = (assigned_col as f32 + 0.5) * (2400.0 / self.grid_size as f32) - 1200.0;
-                let grid_y = (assigned_row as f32 + 0.5) * (2400.0 / self.grid_size as f32) - 1200.0;
+                let (grid_x, grid_y) = self.grid_index_to_position(assigned_index);
                 entity.position_x = grid_x;
                 entity.position_y = grid_y;
             }
             
             self.entities.push(entity);
         }
-        
+        self.restart_run();
+    }
+
+    /// Start the run over from tick 0 with the entities back where and as it
+    /// began, a loaded map's starts included, on the current terrain and
+    /// resources. Starts that aren't known, after a sync, are placed afresh.
+    pub fn restart_from_run_start(&mut self) {
+        let Some((entities, cells)) = self.run_start.take() else {
+            self.reset_entities();
+            return;
+        };
+        self.clear_grid_ownership();
+        for cell in &cells {
+            let space = &mut self.grid_spaces[cell.grid_index as usize];
+            *space = GridSpace {
+                terrain: space.terrain,
+                resource: space.resource,
+                ..GridSpace::with_owner(cell.owner_id, cell.defense_strength)
+            };
+        }
+        self.entities = entities;
+        self.restart_run();
+        self.update_territories();
+    }
+
+    /// Starting spaces for `entity_count` entities that are within the
+    /// fairness tolerance, or the fairest placement tried
+    ///
+    /// The first placement spreads the starts evenly from the grid's corner.
+    /// Later ones center that lattice and nudge each start by up to a
+    /// quarter of the lattice spacing, differently every attempt.
+    fn fairest_starts(&mut self, entity_count: usize) -> Vec<Option<usize>> {
+        let mut checker = FairnessChecker::new();
+        let mut best: Option<(Vec<Option<usize>>, f32)> = None;
+        self.start_attempts = 0;
+        for attempt in 0..self.fairness.max_attempts {
+            let starts = self.start_spaces(entity_count, attempt);
+            let placed: Vec<(u32, usize)> =
+                starts.iter().enumerate().filter_map(|(id, start)| Some((id as u32, (*start)?))).collect();
+            let report = checker.assess(
+                &self.grid_spaces,
+                self.world.grid_size,
+                &placed,
+                &self.terrain,
+                &self.resources,
+                &self.fairness,
+            );
+            self.start_attempts = attempt + 1;
+            let spread = report.worst_spread();
+            if best.as_ref().is_none_or(|&(_, best_spread)| spread < best_spread) {
+                best = Some((starts, spread));
+            }
+            if report.fair {
+                break;
+            }
+        }
+        best.map(|(starts, _)| starts).unwrap_or_default()
+    }
+
+    /// One placement of `entity_count` starts on distinct spaces; see `fairest_starts`
+    fn start_spaces(&self, entity_count: usize, attempt: u32) -> Vec<Option<usize>> {
+        let grid_size = self.world.grid_size;
+        let grid_area = grid_size * grid_size;
+        let spacing = ((grid_area as f32 / entity_count as f32).sqrt().floor() as usize).max(1);
+        let divisor = (grid_size / spacing).max(1);
+        let seed = self.terrain.seed ^ attempt.wrapping_mul(START_SEED_SALT);
+        let mut taken = vec![false; grid_area];
+        (0..entity_count)
+            .map(|i| {
+                let mut row = (i / divisor) * spacing;
+                let mut col = (i % divisor) * spacing;
+                if attempt > 0 {
+                    let reach = spacing / 4;
+                    let nudge = |value: usize, roll: u32| {
+                        (value + spacing / 2 + roll as usize % (2 * reach + 1)).saturating_sub(reach)
+                    };
+                    row = nudge(row, grid_hash(seed, i as i32, 0));
+                    col = nudge(col, grid_hash(seed, i as i32, 1));
+                }
+                let grid_index = row.min(grid_size - 1) * grid_size + col.min(grid_size - 1);
+                // The next free space on, so every entity gets a start of its own
+                let start = (0..grid_area).map(|offset| (grid_index + offset) % grid_area).find(|&index| !taken[index])?;
+                taken[start] = true;
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// How evenly matched the alive entities' capitals are
+    pub fn validate_start_positions(&self) -> FairnessReport {
+        let starts: Vec<(u32, usize)> = self
+            .entities
+            .iter()
+            .filter(|entity| entity.state != AiState::Dead)
+            .filter_map(|entity| Some((entity.id, entity.capital? as usize)))
+            .collect();
+        let mut report = FairnessChecker::new().assess(
+            &self.grid_spaces,
+            self.world.grid_size,
+            &starts,
+            &self.terrain,
+            &self.resources,
+            &self.fairness,
+        );
+        report.attempts = self.start_attempts;
+        report
+    }
+
+    pub fn fairness_config(&self) -> FairnessConfig {
+        self.fairness
+    }
+
+    /// Respawn the entities on starts placed under `fairness`
+    pub fn set_fairness_config(&mut self, fairness: FairnessConfig) -> Result<(), String> {
+        fairness.validate()?;
+        self.fairness = fairness;
+        self.rebuild_entities(self.entity_count);
+        Ok(())
+    }
+
+    /// Reset grid spaces, keeping their terrain and resources, and garrison
+    /// them before any AI claims its start
+    fn clear_grid_ownership(&mut self) {
+        for space in &mut self.grid_spaces {
+            *space = GridSpace {
+                terrain: space.terrain,
+                resource: space.resource,
+                ..GridSpace::new()
+            };
+        }
+        self.place_garrisons();
+    }
+
+    /// Start the run over from tick 0 with the entities and grid as they are
+    /// now, forgetting everything that happened so far
+    fn restart_run(&mut self) {
+        let start_cells = self
+            .grid_spaces
+            .iter()
+            .enumerate()
+            .filter_map(|(index, space)| {
+                Some(MapCell {
+                    grid_index: index as u32,
+                    owner_id: space.owner_id?,
+                    defense_strength: space.defense_strength,
+                })
+            })
+            .collect();
+        self.run_start = Some((self.entities.clone(), start_cells));
+        let entity_count = self.entities.len();
+        self.mark_all_grid_cells_dirty();
         self.entity_count = entity_count;
         self.snapshot_buffer = Vec::with_capacity(entity_count);
-        self.flat_snapshot = Vec::with_capacity(entity_count * SNAPSHOT_FIELD_COUNT);
+        self.flat_snapshot = Vec::with_capacity(entity_count * self.snapshot_fields.len());
         self.snapshot_dirty = true;
         self.flat_snapshot_dirty = true;
+        self.events.clear();
+        self.new_events.clear();
+        self.alliances.clear();
+        self.relations.clear();
+        self.afflictions.clear();
+        self.armies.clear();
+        self.next_army_id = 0;
+        self.pending_commands.clear();
+        self.scheduled_commands.clear();
+        self.input_hasher = StateHasher::new();
+        self.watched_history.clear();
+        self.stats.clear();
+        self.leaderboard.clear();
+        self.leaderboard_known = 0;
         self.tick = 0;
+        self.sim_time_ms = 0.0;
+        for entity in &mut self.entities {
+            entity.victory_hold = 0;
+        }
+        self.assign_teams();
+        self.update_leaderboard();
+    }
+
+    /// Split the entities into consecutive teams of `team_sizes` in id order,
+    /// allying every pair of teammates
+    ///
+    /// Falls back to a free-for-all when the sizes do not add up to the
+    /// entity count.
+    fn assign_teams(&mut self) {
+        if self.team_sizes.iter().sum::<usize>() != self.entities.len() {
+            self.team_sizes.clear();
+        }
+        let mut first = 0;
+        for (team_id, &size) in self.team_sizes.iter().enumerate() {
+            let members = first..first + size;
+            for a in members.clone() {
+                self.entities[a].team_id = Some(team_id as u32);
+                for b in (a + 1)..members.end {
+                    self.alliances.add(a as u32, b as u32);
+                }
+            }
+            first = members.end;
+        }
+    }
+
+    /// Respawn the entities as teams of the given sizes
+    pub fn set_teams(&mut self, team_sizes: &[usize]) {
+        self.team_sizes = team_sizes.to_vec();
+        self.rebuild_entities(team_sizes.iter().sum());
+    }
+
+    pub fn team_sizes(&self) -> &[usize] {
+        &self.team_sizes
     }
 
     pub fn entity_mut(&mut self, index: usize) -> Option<&mut AiEntity> {
@@ -167,6 +478,628 @@ impl SimulationData {
         &mut self.dead_indices
     }
 
+    pub fn alliances(&self) -> &AllianceGraph {
+        &self.alliances
+    }
+
+    pub fn alliances_mut(&mut self) -> &mut AllianceGraph {
+        &mut self.alliances
+    }
+
+    pub fn relations(&self) -> &Relations {
+        &self.relations
+    }
+
+    pub fn relations_mut(&mut self) -> &mut Relations {
+        &mut self.relations
+    }
+
+    /// Warm relations between neighbors and old foes that haven't fought
+    /// for `RELATIONS_CHECK_INTERVAL` ticks
+    pub fn keep_peace(&mut self) {
+        let grid_size = self.grid_size();
+        let mut neighbors = BTreeSet::new();
+        for (index, space) in self.grid_spaces.iter().enumerate() {
+            let Some(owner_id) = space.owner_id else {
+                continue;
+            };
+            // Looking only at later spaces visits each shared edge once
+            for next in adjacent_spaces(index, grid_size).filter(|&next| next > index) {
+                match self.grid_spaces[next].owner_id {
+                    Some(other_id) if other_id != owner_id => {
+                        neighbors.insert((owner_id.min(other_id), owner_id.max(other_id)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.relations.keep_peace(self.tick, RELATIONS_CHECK_INTERVAL, neighbors, RELATIONS_PEACE_GAIN);
+    }
+
+    /// Make every small entity bordering a much larger one that vassal's
+    ///
+    /// An entity holding less than `VASSAL_TERRITORY_SHARE` of the grid submits
+    /// to the largest neighbor with `VASSAL_SIZE_RATIO` times its territory.
+    /// Vassals stay put, overlords don't submit and vassals take no vassals.
+    pub fn submit_vassals(&mut self) {
+        let grid_size = self.grid_size();
+        let threshold = VASSAL_TERRITORY_SHARE * self.grid_spaces.len() as f32;
+        let entities = &self.entities;
+        let free = |id: u32| {
+            entities
+                .get(id as usize)
+                .filter(|e| e.state != AiState::Dead && e.overlord.is_none())
+        };
+        let mut candidates = mem::take(&mut self.vassal_candidates);
+        candidates.clear();
+        candidates.resize(entities.len(), None);
+        for (index, space) in self.grid_spaces.iter().enumerate() {
+            let Some(owner_id) = space.owner_id else {
+                continue;
+            };
+            for next in adjacent_spaces(index, grid_size).filter(|&next| next > index) {
+                let Some(other_id) = self.grid_spaces[next].owner_id.filter(|&id| id != owner_id) else {
+                    continue;
+                };
+                for (small_id, large_id) in [(owner_id, other_id), (other_id, owner_id)] {
+                    let (Some(small), Some(large)) = (free(small_id), free(large_id)) else {
+                        continue;
+                    };
+                    let submits = (small.territory as f32) < threshold
+                        && large.territory as f32 >= VASSAL_SIZE_RATIO * small.territory as f32;
+                    // Ties go to the lower id
+                    let slot = &mut candidates[small_id as usize];
+                    let rank = |id: u32| (entities[id as usize].territory, Reverse(id));
+                    if submits && slot.is_none_or(|id| rank(large_id) > rank(id)) {
+                        *slot = Some(large_id);
+                    }
+                }
+            }
+        }
+
+        for (vassal_id, &slot) in candidates.iter().enumerate() {
+            let (vassal_id, Some(overlord_id)) = (vassal_id as u32, slot) else {
+                continue;
+            };
+            // Earlier submissions this check may have made either side ineligible
+            let overlord_free = self.entities[overlord_id as usize].overlord.is_none();
+            if !overlord_free || self.vassals_of(vassal_id).next().is_some() {
+                continue;
+            }
+            self.entities[vassal_id as usize].overlord = Some(overlord_id);
+            self.snapshot_dirty = true;
+            self.record_event(SimulationEvent::Vassalized {
+                tick: self.tick,
+                vassal_id,
+                overlord_id,
+            });
+        }
+        self.vassal_candidates = candidates;
+    }
+
+    /// Hand the tribute every vassal set aside this tick to its overlord
+    pub fn collect_tribute(&mut self) {
+        for i in 0..self.entities.len() {
+            let tribute = mem::take(&mut self.entities[i].tribute_due);
+            if let Some(overlord_id) = self.entities[i].overlord.filter(|_| tribute > 0.0) {
+                self.entities[overlord_id as usize].money += tribute;
+            }
+        }
+    }
+
+    /// Entities paying tribute to `overlord_id`, in id order
+    pub fn vassals_of(&self, overlord_id: u32) -> impl Iterator<Item = u32> + '_ {
+        self.entities
+            .iter()
+            .filter(move |e| e.overlord == Some(overlord_id))
+            .map(|e| e.id)
+    }
+
+    /// Whether `vassal_id` pays tribute to `overlord_id`
+    pub fn is_vassal_of(&self, vassal_id: u32, overlord_id: u32) -> bool {
+        self.entities
+            .get(vassal_id as usize)
+            .is_some_and(|e| e.overlord == Some(overlord_id))
+    }
+
+    /// Free a fallen entity's vassals and release it from its own overlord
+    pub fn end_vassalage(&mut self, entity_id: u32) {
+        for entity in &mut self.entities {
+            if entity.id == entity_id || entity.overlord == Some(entity_id) {
+                entity.overlord = None;
+                entity.tribute_due = 0.0;
+            }
+        }
+    }
+
+    /// Form an alliance if both entities exist, are alive and distinct
+    pub fn propose_alliance(&mut self, a: u32, b: u32) -> bool {
+        let alive = |id: u32| {
+            self.entities
+                .get(id as usize)
+                .is_some_and(|e| e.id == id && e.state != AiState::Dead)
+        };
+        if !alive(a) || !alive(b) {
+            return false;
+        }
+        let formed = self.alliances.add(a, b);
+        if formed {
+            self.snapshot_dirty = true;
+        }
+        formed
+    }
+
+    /// Queue a player command for an alive entity, applied at the start of the next tick
+    pub fn queue_command(&mut self, entity_id: u32, command: PlayerCommand) -> Result<(), String> {
+        self.check_command(entity_id, command)?;
+        self.pending_commands.push((entity_id, command));
+        Ok(())
+    }
+
+    /// Schedule a player command for the step that reaches `tick`. Commands for one
+    /// tick apply in entity id and command order, whatever order they arrived in,
+    /// so lockstep peers that schedule the same commands stay in sync
+    pub fn schedule_command(&mut self, tick: u64, entity_id: u32, command: PlayerCommand) -> Result<(), String> {
+        if tick <= self.tick {
+            return Err(format!("Tick {} has already been simulated; the next tick is {}", tick, self.tick + 1));
+        }
+        self.check_command(entity_id, command)?;
+        let commands = self.scheduled_commands.entry(tick).or_default();
+        let key = (entity_id, command.key());
+        let index = commands.partition_point(|&(id, queued)| (id, queued.key()) <= key);
+        commands.insert(index, (entity_id, command));
+        Ok(())
+    }
+
+    /// Hash of the commands scheduled for `tick`, for peers to compare before simulating it
+    pub fn scheduled_input_hash(&self, tick: u64) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(tick);
+        for &(entity_id, command) in self.scheduled_commands.get(&tick).into_iter().flatten() {
+            Self::hash_command(&mut hasher, entity_id, command);
+        }
+        hasher.finish()
+    }
+
+    /// Commands scheduled for ticks not yet simulated
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn scheduled_command_count(&self) -> usize {
+        self.scheduled_commands.values().map(Vec::len).sum()
+    }
+
+    /// Hash of every command applied so far and the ticks they applied on
+    pub fn input_hash(&self) -> u64 {
+        self.input_hasher.finish()
+    }
+
+    fn hash_command(hasher: &mut StateHasher, entity_id: u32, command: PlayerCommand) {
+        let (kind, target) = command.key();
+        hasher.write_u32(entity_id);
+        hasher.write_u32(kind);
+        hasher.write_u32(target);
+    }
+
+    /// Reject commands for dead or unknown entities and grid indices off the grid
+    fn check_command(&self, entity_id: u32, command: PlayerCommand) -> Result<(), String> {
+        let alive = self
+            .entities
+            .get(entity_id as usize)
+            .is_some_and(|e| e.id == entity_id && e.state != AiState::Dead);
+        if !alive {
+            return Err(format!("Entity {} is not alive", entity_id));
+        }
+        if let PlayerCommand::MoveTo { grid_index } | PlayerCommand::Attack { grid_index } = command {
+            if grid_index as usize >= self.grid_spaces.len() {
+                return Err(format!("Grid index {} is outside the grid", grid_index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Hand this tick's scheduled commands, then every queued command, to their
+    /// entities, later commands replacing earlier ones
+    pub fn apply_pending_commands(&mut self) {
+        let scheduled = self.scheduled_commands.remove(&self.tick).unwrap_or_default();
+        let commands: Vec<(u32, PlayerCommand)> = scheduled.into_iter().chain(self.pending_commands.drain(..)).collect();
+        for (entity_id, command) in commands {
+            self.input_hasher.write_u64(self.tick);
+            Self::hash_command(&mut self.input_hasher, entity_id, command);
+            if let Some(entity) = self.entities.get_mut(entity_id as usize) {
+                if entity.state != AiState::Dead {
+                    entity.command = Some(command);
+                }
+            }
+        }
+    }
+
+    /// Current player command for an entity, switching to hold once an attack target is taken
+    pub fn active_command(&mut self, index: usize) -> Option<PlayerCommand> {
+        let entity = self.entities.get_mut(index)?;
+        if let Some(PlayerCommand::Attack { grid_index }) = entity.command {
+            let owner = self.grid_spaces.get(grid_index as usize).and_then(|s| s.owner_id);
+            if owner == Some(entity.id) {
+                entity.command = Some(PlayerCommand::Hold);
+            }
+        }
+        entity.command
+    }
+
+    pub fn armies(&self) -> &[Army] {
+        &self.armies
+    }
+
+    pub fn armies_mut(&mut self) -> &mut Vec<Army> {
+        &mut self.armies
+    }
+
+    /// Detach `strength` from an entity's military into a new army marching on
+    /// `objective`, returning the army id
+    pub fn raise_army(&mut self, owner_id: u32, strength: f32, objective: u32) -> Result<u32, String> {
+        if objective as usize >= self.grid_spaces.len() {
+            return Err(format!("Grid index {} is outside the grid", objective));
+        }
+        let owner = self
+            .entities
+            .get_mut(owner_id as usize)
+            .filter(|e| e.id == owner_id && e.state != AiState::Dead)
+            .ok_or_else(|| format!("Entity {} is not alive", owner_id))?;
+        if !(strength.is_finite() && strength > 0.0) {
+            return Err("Army strength must be positive".to_string());
+        }
+        if strength > owner.military_strength {
+            return Err(format!(
+                "Entity {} has only {} military strength",
+                owner_id, owner.military_strength
+            ));
+        }
+
+        owner.military_strength -= strength;
+        let army = Army {
+            id: self.next_army_id,
+            owner_id,
+            strength,
+            position_x: owner.position_x,
+            position_y: owner.position_y,
+            objective,
+        };
+        self.next_army_id = self.next_army_id.wrapping_add(1);
+        self.armies.push(army);
+        self.snapshot_dirty = true;
+        Ok(army.id)
+    }
+
+    /// Spend an entity's money on `amount` military strength at `MILITARY_PRICE` a point
+    pub fn buy_military(&mut self, entity_id: u32, amount: f32) -> Result<(), String> {
+        let entity = self
+            .entities
+            .get_mut(entity_id as usize)
+            .filter(|e| e.id == entity_id && e.state != AiState::Dead)
+            .ok_or_else(|| format!("Entity {} is not alive", entity_id))?;
+        entity.buy_military(amount)?;
+        self.snapshot_dirty = true;
+        Ok(())
+    }
+
+    /// Have an entity's AI decide with `profile` from now on
+    pub fn set_behavior(&mut self, entity_id: u32, profile: BehaviorProfile) -> Result<(), String> {
+        let entity = self
+            .entities
+            .get_mut(entity_id as usize)
+            .filter(|e| e.id == entity_id && e.state != AiState::Dead)
+            .ok_or_else(|| format!("Entity {} is not alive", entity_id))?;
+        entity.profile = profile;
+        self.snapshot_dirty = true;
+        Ok(())
+    }
+
+    /// Hand an entity's decisions to the brain registered under `brain_id`
+    pub fn set_brain(&mut self, entity_id: u32, brain_id: u32) -> Result<(), String> {
+        let entity = self
+            .entities
+            .get_mut(entity_id as usize)
+            .filter(|e| e.id == entity_id && e.state != AiState::Dead)
+            .ok_or_else(|| format!("Entity {} is not alive", entity_id))?;
+        entity.brain = brain_id;
+        Ok(())
+    }
+
+    /// Add an entity mid-simulation on the grid space under `(x, y)`, taking
+    /// that space from whoever held it, and return its id
+    ///
+    /// Existing entities, territories and alliances are left as they are.
+    /// The newcomer plays for itself.
+    pub fn spawn_entity(&mut self, x: f32, y: f32, military_strength: f32) -> Result<u32, String> {
+        if !(military_strength.is_finite() && military_strength >= 0.0) {
+            return Err("Military strength must not be negative".to_string());
+        }
+        let grid_index = self
+            .position_to_grid_index(x, y)
+            .ok_or_else(|| format!("Position ({}, {}) is outside the world", x, y))?;
+
+        let id = self.entities.len() as u32;
+        let mut entity = AiEntity::with_half_extent(id, self.world.half_extent());
+        entity.military_strength = military_strength;
+        entity.position_x = x;
+        entity.position_y = y;
+        entity.capital = Some(grid_index as u32);
+        self.entities.push(entity);
+        self.entity_count = self.entities.len();
+
+        let space = &mut self.grid_spaces[grid_index];
+        *space = GridSpace {
+            terrain: space.terrain,
+            resource: space.resource,
+            ..GridSpace::with_owner(id, CAPITAL_START_DEFENSE)
+        };
+        self.mark_grid_cell_dirty(grid_index);
+        self.update_territories();
+        self.mark_snapshots_dirty();
+        Ok(id)
+    }
+
+    /// Take an entity out of the simulation mid-run
+    ///
+    /// Its land falls back to unowned, its armies and alliances end and it
+    /// stays in place as a dead entity so ids keep matching indices.
+    pub fn remove_entity(&mut self, entity_id: u32) -> Result<(), String> {
+        self.alive_entity(entity_id)?;
+        for index in 0..self.grid_spaces.len() {
+            let space = &mut self.grid_spaces[index];
+            if space.owner_id == Some(entity_id) {
+                *space = GridSpace {
+                    terrain: space.terrain,
+                    resource: space.resource,
+                    ..GridSpace::new()
+                };
+                self.mark_grid_cell_dirty(index);
+            }
+        }
+        self.retire_entity(entity_id);
+        self.update_territories();
+        self.mark_snapshots_dirty();
+        Ok(())
+    }
+
+    /// Fold `absorbed_id` into `entity_id`: its land, armies, money and
+    /// military strength pass over and it dies
+    ///
+    /// Land cut off from the surviving capital withers through supply decay.
+    pub fn merge_entities(&mut self, entity_id: u32, absorbed_id: u32) -> Result<(), String> {
+        if entity_id == absorbed_id {
+            return Err(format!("Entity {} can't merge with itself", entity_id));
+        }
+        self.alive_entity(entity_id)?;
+        let absorbed = self.alive_entity(absorbed_id)?;
+        let (money, military_strength) = (absorbed.money, absorbed.military_strength);
+        for index in 0..self.grid_spaces.len() {
+            let space = &mut self.grid_spaces[index];
+            if space.owner_id == Some(absorbed_id) {
+                space.owner_id = Some(entity_id);
+                self.mark_grid_cell_dirty(index);
+            }
+        }
+        for army in self.armies.iter_mut().filter(|army| army.owner_id == absorbed_id) {
+            army.owner_id = entity_id;
+        }
+        let entity = &mut self.entities[entity_id as usize];
+        entity.money += money;
+        entity.military_strength += military_strength;
+        self.retire_entity(absorbed_id);
+        self.update_territories();
+        self.mark_snapshots_dirty();
+        Ok(())
+    }
+
+    /// Break part of an entity's empire away as a new entity, returning its id
+    ///
+    /// The new entity takes `fraction` of the money, military strength and
+    /// spaces, the spaces being those nearest the one farthest from the old
+    /// capital, where its own capital goes. The old capital always stays.
+    pub fn split_entity(&mut self, entity_id: u32, fraction: f32) -> Result<u32, String> {
+        if !(fraction > 0.0 && fraction < 1.0) {
+            return Err("Fraction must be between 0 and 1".to_string());
+        }
+        let entity = self.alive_entity(entity_id)?;
+        let capital = entity.capital.map(|capital| capital as usize);
+        let grid_size = self.world.grid_size;
+        let mut spaces: Vec<usize> = (0..self.grid_spaces.len())
+            .filter(|&index| self.grid_spaces[index].owner_id == Some(entity_id) && Some(index) != capital)
+            .collect();
+        let owned = spaces.len() + capital.is_some() as usize;
+        if spaces.is_empty() || owned < 2 {
+            return Err(format!("Entity {} holds too little land to split", entity_id));
+        }
+        let taken = ((owned as f32 * fraction).round() as usize).clamp(1, owned - 1);
+        let from = capital.unwrap_or(spaces[0]);
+        let seed = spaces
+            .iter()
+            .copied()
+            .max_by_key(|&index| (manhattan_distance(from, index, grid_size), Reverse(index)))
+            .unwrap_or(from);
+        spaces.sort_unstable_by_key(|&index| (manhattan_distance(seed, index, grid_size), index));
+
+        let id = self.entities.len() as u32;
+        let mut breakaway = AiEntity::with_half_extent(id, self.world.half_extent());
+        let parent = &mut self.entities[entity_id as usize];
+        breakaway.profile = parent.profile;
+        breakaway.money = parent.money.max(0.0) * fraction;
+        breakaway.military_strength = parent.military_strength.max(0.0) * fraction;
+        parent.money -= breakaway.money;
+        parent.military_strength -= breakaway.military_strength;
+        (breakaway.position_x, breakaway.position_y) = self.grid_index_to_position(seed);
+        breakaway.capital = Some(seed as u32);
+        self.entities.push(breakaway);
+        self.entity_count = self.entities.len();
+        for &index in &spaces[..taken] {
+            self.grid_spaces[index].owner_id = Some(id);
+            self.mark_grid_cell_dirty(index);
+        }
+        self.update_territories();
+        self.mark_snapshots_dirty();
+        Ok(id)
+    }
+
+    fn alive_entity(&self, entity_id: u32) -> Result<&AiEntity, String> {
+        self.entities
+            .get(entity_id as usize)
+            .filter(|e| e.id == entity_id && e.state != AiState::Dead)
+            .ok_or_else(|| format!("Entity {} is not alive", entity_id))
+    }
+
+    /// Mark a living entity dead in place, ending its orders, armies and
+    /// alliances, without touching its land
+    fn retire_entity(&mut self, entity_id: u32) {
+        let entity = &mut self.entities[entity_id as usize];
+        entity.state = AiState::Dead;
+        entity.military_strength = 0.0;
+        entity.money = 0.0;
+        entity.command = None;
+        entity.support_target = None;
+        entity.attack_target = None;
+        entity.capital = None;
+        entity.fortify_target = None;
+        entity.attack_space = None;
+        self.armies.retain(|army| army.owner_id != entity_id);
+        self.alliances.remove_entity(entity_id);
+        self.relations.remove_entity(entity_id);
+        self.end_vassalage(entity_id);
+        self.pending_commands.retain(|&(id, _)| id != entity_id);
+        for commands in self.scheduled_commands.values_mut() {
+            commands.retain(|&(id, _)| id != entity_id);
+        }
+        self.record_event(SimulationEvent::Death {
+            tick: self.tick,
+            entity_id,
+        });
+    }
+
+    /// Append an event, dropping the oldest once the log is full
+    pub fn record_event(&mut self, event: SimulationEvent) {
+        if self.events.len() == EVENT_LOG_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+        self.new_events.push(event);
+    }
+
+    /// Events recorded since the previous drain, oldest first
+    pub fn drain_new_events(&mut self) -> std::vec::Drain<'_, SimulationEvent> {
+        self.new_events.drain(..)
+    }
+
+    /// Logged events, oldest first
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fn events(&self) -> impl Iterator<Item = &SimulationEvent> {
+        self.events.iter()
+    }
+
+    /// Reseed every entity's RNG from `seed`, so runs over the same world can diverge
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fn reseed_entities(&mut self, seed: u32) {
+        for entity in &mut self.entities {
+            entity.set_rng_state(grid_hash(seed, entity.id as i32, 0));
+        }
+    }
+
+    /// Check the state for entities outside the world, land held by the dead
+    /// and resources gone negative; `spatial_overflow` is passed through
+    pub fn diagnose(&self, spatial_overflow: usize) -> Diagnostics {
+        let half_extent = self.world.half_extent();
+        let in_world = |value: f32| (-half_extent..=half_extent).contains(&value);
+        let alive = |id: u32| self.entities.get(id as usize).is_some_and(|entity| entity.state != AiState::Dead);
+        let sound = |amount: f32| amount.is_finite() && amount >= 0.0;
+        Diagnostics {
+            tick: self.tick,
+            spatial_overflow,
+            out_of_bounds: self
+                .entities
+                .iter()
+                .filter(|entity| entity.state != AiState::Dead)
+                .filter(|entity| !in_world(entity.position_x) || !in_world(entity.position_y))
+                .map(|entity| entity.id)
+                .collect(),
+            orphaned_cells: self
+                .grid_spaces
+                .iter()
+                .enumerate()
+                .filter(|(_, space)| space.owner_id.is_some_and(|owner_id| !alive(owner_id)))
+                .map(|(index, _)| index as u32)
+                .collect(),
+            negative_resources: self
+                .entities
+                .iter()
+                .filter(|entity| !sound(entity.money) || !sound(entity.military_strength))
+                .map(|entity| entity.id)
+                .collect(),
+        }
+    }
+
+    pub fn memory_stats(&self) -> MemoryStats {
+        let (start_entities, start_cells) = self
+            .run_start
+            .as_ref()
+            .map_or((0, 0), |(entities, cells)| (vec_bytes(entities), vec_bytes(cells)));
+        let entity_bytes = vec_bytes(&self.entities) + start_entities;
+        let grid_bytes = vec_bytes(&self.grid_spaces)
+            + vec_bytes(&self.grid_owner_snapshot)
+            + vec_bytes(&self.grid_defense_snapshot)
+            + vec_bytes(&self.grid_dirty_flags)
+            + vec_bytes(&self.dirty_grid_cells)
+            + vec_bytes(&self.grid_diff.indices)
+            + vec_bytes(&self.grid_diff.owners)
+            + vec_bytes(&self.grid_diff.defense)
+            + self.map_terrain.as_ref().map_or(0, vec_bytes)
+            + self.map_resources.as_ref().map_or(0, vec_bytes)
+            + vec_bytes(&self.victory_cells)
+            + start_cells;
+        let snapshot_bytes = vec_bytes(&self.snapshot_buffer) + vec_bytes(&self.flat_snapshot);
+        let event_log_bytes = (self.events.capacity() + self.new_events.capacity()) * mem::size_of::<SimulationEvent>();
+        let tick_buffer_bytes = vec_bytes(&self.resource_transfers)
+            + vec_bytes(&self.dead_indices)
+            + vec_bytes(&self.vassal_candidates)
+            + vec_bytes(&self.afflictions)
+            + vec_bytes(&self.armies)
+            + vec_bytes(&self.pending_commands)
+            + vec_bytes(&self.leaderboard);
+        MemoryStats {
+            entity_count: self.entities.len(),
+            entity_capacity: self.entities.capacity(),
+            entity_bytes,
+            grid_cells: self.grid_spaces.len(),
+            grid_bytes,
+            snapshot_bytes,
+            event_count: self.events.len(),
+            event_log_bytes,
+            total_bytes: entity_bytes + grid_bytes + snapshot_bytes + event_log_bytes + tick_buffer_bytes,
+        }
+    }
+
+    /// Release capacity the buffers grew into but no longer use, e.g. after many
+    /// entities died or a large world was replaced with a small one
+    pub fn shrink_to_fit(&mut self) {
+        self.entities.shrink_to_fit();
+        self.grid_spaces.shrink_to_fit();
+        self.victory_cells.shrink_to_fit();
+        self.snapshot_buffer.shrink_to_fit();
+        self.flat_snapshot.shrink_to_fit();
+        self.grid_owner_snapshot.shrink_to_fit();
+        self.grid_defense_snapshot.shrink_to_fit();
+        self.grid_dirty_flags.shrink_to_fit();
+        self.dirty_grid_cells.shrink_to_fit();
+        self.grid_diff.indices.shrink_to_fit();
+        self.grid_diff.owners.shrink_to_fit();
+        self.grid_diff.defense.shrink_to_fit();
+        self.events.shrink_to_fit();
+        self.new_events.shrink_to_fit();
+        self.resource_transfers.shrink_to_fit();
+        self.dead_indices.shrink_to_fit();
+        self.vassal_candidates.shrink_to_fit();
+        self.afflictions.shrink_to_fit();
+        self.armies.shrink_to_fit();
+        self.pending_commands.shrink_to_fit();
+        self.leaderboard.shrink_to_fit();
+    }
+
     pub fn mark_snapshots_dirty(&mut self) {
         self.snapshot_dirty = true;
         self.flat_snapshot_dirty = true;
@@ -181,7 +1114,6 @@ impl SimulationData {
         self.snapshot_dirty
     }
 
-    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
     pub fn flat_snapshot_dirty(&self) -> bool {
         self.flat_snapshot_dirty
     }
@@ -197,6 +1129,14 @@ impl SimulationData {
         &self.snapshot_buffer
     }
 
+    /// Both sides of the entity double buffer for the update pass: the snapshots,
+    /// alliances and relations every update reads, and the live entities each
+    /// update writes only its own of. No update sees another's changes, so the
+    /// result doesn't depend on the order (or thread) entities are updated in.
+    pub fn entity_update_buffers(&mut self) -> (&[EntitySnapshot], &AllianceGraph, &Relations, &mut [AiEntity]) {
+        (&self.snapshot_buffer, &self.alliances, &self.relations, &mut self.entities)
+    }
+
     pub fn metrics(&self) -> &BenchmarkMetrics {
         &self.metrics
     }
@@ -207,51 +1147,832 @@ impl SimulationData {
 
     pub fn build_public_snapshot(&mut self) -> SimulationSnapshot {
         self.snapshot_dirty = false;
-        self.entities
+        self.entities.iter().map(|entity| self.public_entity_snapshot(entity)).collect()
+    }
+
+    fn public_entity_snapshot(&self, entity: &AiEntity) -> PublicEntitySnapshot {
+        let mut snapshot = PublicEntitySnapshot::from(entity);
+        snapshot.allies = self.alliances.allies_of(entity.id).collect();
+        snapshot.vassals = self.vassals_of(entity.id).collect();
+        snapshot.armies = self
+            .armies
             .iter()
-            .map(PublicEntitySnapshot::from)
-            .collect()
+            .filter(|army| army.owner_id == entity.id)
+            .copied()
+            .collect();
+        snapshot
+    }
+
+    /// The given entities plus every grid space overlapping the rectangle
+    /// from `min` to `max`
+    pub fn build_viewport_snapshot(
+        &self,
+        entity_indices: &[usize],
+        min: (f32, f32),
+        max: (f32, f32),
+    ) -> ViewportSnapshot {
+        let entities = entity_indices
+            .iter()
+            .filter_map(|&index| self.entities.get(index))
+            .map(|entity| self.public_entity_snapshot(entity))
+            .collect();
+
+        let grid_size = self.world.grid_size;
+        let half_extent = self.world.half_extent();
+        let space_extent = self.world.grid_space_extent();
+        let to_grid = |value: f32| ((value + half_extent) / space_extent).floor();
+        let (min_col, min_row) = (to_grid(min.0), to_grid(min.1));
+        let (max_col, max_row) = (to_grid(max.0), to_grid(max.1));
+        let mut grid_cells = Vec::new();
+        // Skip rectangles that miss the grid entirely
+        if max_col >= 0.0 && max_row >= 0.0 && min_col < grid_size as f32 && min_row < grid_size as f32 {
+            let clamp = |value: f32| (value.max(0.0) as usize).min(grid_size - 1);
+            for row in clamp(min_row)..=clamp(max_row) {
+                for col in clamp(min_col)..=clamp(max_col) {
+                    let index = row * grid_size + col;
+                    let space = &self.grid_spaces[index];
+                    grid_cells.push(ViewportCell {
+                        index: index as u32,
+                        owner_id: space.displayed_owner(),
+                        defense_strength: space.displayed_defense(),
+                    });
+                }
+            }
+        }
+
+        ViewportSnapshot { entities, grid_cells }
+    }
+
+    /// Every field of one entity, with its allies and armies
+    pub fn entity_details(&self, entity_id: u32) -> Option<EntityDetails> {
+        let entity = self.entities.get(entity_id as usize)?;
+        let mut details = EntityDetails::from(entity);
+        details.allies = self.alliances.allies_of(entity_id).collect();
+        details.vassals = self.vassals_of(entity_id).collect();
+        details.armies = self
+            .armies
+            .iter()
+            .filter(|army| army.owner_id == entity_id)
+            .copied()
+            .collect();
+        Some(details)
+    }
+
+    /// Start recording an entity's stats every tick, returning `false` for an
+    /// unknown id
+    ///
+    /// Only the most recently watched `MAX_WATCHED_ENTITIES` are recorded.
+    pub fn watch_entity(&mut self, entity_id: u32) -> bool {
+        let Some(entity) = self.entities.get(entity_id as usize) else {
+            return false;
+        };
+        if self.watched_history.iter().any(|(id, _)| *id == entity_id) {
+            return true;
+        }
+        if self.watched_history.len() == MAX_WATCHED_ENTITIES {
+            self.watched_history.remove(0);
+        }
+        let mut history = VecDeque::with_capacity(ENTITY_HISTORY_LEN);
+        history.push_back(EntityStats::sample(entity, self.tick));
+        self.watched_history.push((entity_id, history));
+        true
+    }
+
+    /// Recorded stats of a watched entity, oldest first
+    pub fn entity_history(&self, entity_id: u32) -> Option<&VecDeque<EntityStats>> {
+        self.watched_history
+            .iter()
+            .find(|(id, _)| *id == entity_id)
+            .map(|(_, history)| history)
+    }
+
+    /// Sample every watched entity at the end of a tick
+    pub fn record_entity_history(&mut self) {
+        for (entity_id, history) in &mut self.watched_history {
+            let Some(entity) = self.entities.get(*entity_id as usize) else {
+                continue;
+            };
+            if history.len() == ENTITY_HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(EntityStats::sample(entity, self.tick));
+        }
+    }
+
+    /// Aggregate the world's stats at the end of a tick
+    pub fn record_stats(&mut self) {
+        self.stats.record(self.tick, &self.entities);
+    }
+
+    pub fn stats(&self) -> &SimStats {
+        &self.stats
+    }
+
+    /// Choose the flat and binary snapshot fields by `SnapshotField` bit
+    pub fn set_snapshot_fields(&mut self, mask: u32) -> Result<(), String> {
+        let fields = SnapshotField::from_mask(mask).ok_or_else(|| {
+            format!(
+                "Snapshot field mask {:#x} must select at least one of the bits in {:#x}",
+                mask,
+                SnapshotField::ALL_MASK
+            )
+        })?;
+        if fields != self.snapshot_fields {
+            self.snapshot_fields = fields;
+            self.flat_snapshot_dirty = true;
+        }
+        Ok(())
+    }
+
+    pub fn snapshot_fields(&self) -> &[SnapshotField] {
+        &self.snapshot_fields
+    }
+
+    pub fn snapshot_schema(&self) -> SnapshotSchema {
+        SnapshotSchema::new(&self.snapshot_fields)
     }
 
-    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
     pub fn ensure_flat_snapshot_ready(&mut self) {
         if self.flat_snapshot_dirty {
             self.rebuild_flat_snapshot();
         }
     }
 
-    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
     pub fn flat_snapshot_slice(&self) -> &[f32] {
         &self.flat_snapshot
     }
 
+    /// Refresh the flat owner/defense buffers from the current grid
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn rebuild_grid_snapshot(&mut self) {
+        self.grid_owner_snapshot.clear();
+        self.grid_defense_snapshot.clear();
+        for space in &self.grid_spaces {
+            self.grid_owner_snapshot
+                .push(space.displayed_owner().unwrap_or(UNOWNED_GRID_OWNER));
+            self.grid_defense_snapshot.push(space.displayed_defense());
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn grid_snapshot_slices(&self) -> (&[u32], &[f32]) {
+        (&self.grid_owner_snapshot, &self.grid_defense_snapshot)
+    }
+
     pub fn destroy(&mut self) {
         self.running = false;
         self.entities.clear();
         self.snapshot_buffer.clear();
         self.flat_snapshot.clear();
+        self.grid_owner_snapshot.clear();
+        self.grid_defense_snapshot.clear();
         self.resource_transfers.clear();
         self.dead_indices.clear();
+        self.events.clear();
+        self.new_events.clear();
+        self.alliances.clear();
+        self.relations.clear();
+        self.afflictions.clear();
+        self.armies.clear();
+        self.next_army_id = 0;
+        self.pending_commands.clear();
+        self.scheduled_commands.clear();
+        self.input_hasher = StateHasher::new();
+        self.watched_history.clear();
+        self.stats.clear();
+        self.leaderboard.clear();
+        self.leaderboard_known = 0;
         self.snapshot_dirty = true;
         self.flat_snapshot_dirty = true;
         self.tick = 0;
+        self.sim_time_ms = 0.0;
     }
 
+    /// Respawn `entity_count` entities, playing every one for itself
     pub fn set_entity_count(&mut self, entity_count: usize) {
+        self.team_sizes.clear();
         self.rebuild_entities(entity_count);
     }
 
     pub fn grid_size(&self) -> usize {
-        self.grid_size
+        self.world.grid_size
+    }
+
+    /// World position of the center of a grid space
+    pub fn grid_index_to_position(&self, index: usize) -> (f32, f32) {
+        let grid_size = self.world.grid_size;
+        let half_extent = self.world.half_extent();
+        let space_extent = self.world.grid_space_extent();
+        let row = index / grid_size;
+        let col = index % grid_size;
+        (
+            (col as f32 + 0.5) * space_extent - half_extent,
+            (row as f32 + 0.5) * space_extent - half_extent,
+        )
     }
 
     pub fn set_grid_size(&mut self, grid_size: usize) {
-        self.grid_size = grid_size;
-        let total_grid_spaces = grid_size * grid_size;
+        self.set_world_config(self.world.with_grid_size(grid_size));
+    }
+
+    /// Rescale the grid to `grid_size` spaces a side without restarting the
+    /// run, carrying land, ownership and every order's target over to the
+    /// spaces covering the same ground
+    ///
+    /// Each new space copies the old space `policy` picks, terrain and
+    /// resource nodes aside: those come from the space under its center, and
+    /// each node lands once on the new space covering its old one. Capitals
+    /// are always kept; an entity whose capital lands on another's moves it
+    /// as if it had been lost. The remapped layers stand in for generated or
+    /// loaded ones until the world is next replaced.
+    pub fn resize_grid(&mut self, grid_size: usize, policy: ResizePolicy) {
+        let old_size = self.world.grid_size;
+        let grid_size = grid_size.max(1);
+        let old_spaces = mem::take(&mut self.grid_spaces);
+        // Old row or column under the center of new row or column `i`
+        let nearest = |i: usize| ((i as f32 + 0.5) * old_size as f32 / grid_size as f32) as usize;
+        let remap = |index: u32| {
+            let (row, col) = (index as usize / old_size, index as usize % old_size);
+            let scale = |i: usize| ((i as f32 + 0.5) * grid_size as f32 / old_size as f32) as usize;
+            (scale(row).min(grid_size - 1) * grid_size + scale(col).min(grid_size - 1)) as u32
+        };
+
+        let mut footprint = Vec::new();
+        for row in 0..grid_size {
+            for col in 0..grid_size {
+                let center = nearest(row).min(old_size - 1) * old_size + nearest(col).min(old_size - 1);
+                let source = match policy {
+                    ResizePolicy::Nearest => center,
+                    ResizePolicy::Majority => {
+                        // Old spaces overlapping this one
+                        let span = |i: usize| {
+                            let start = i * old_size / grid_size;
+                            start..((i + 1) * old_size).div_ceil(grid_size).clamp(start + 1, old_size)
+                        };
+                        footprint.clear();
+                        for old_row in span(row) {
+                            footprint.extend(span(col).map(|old_col| old_row * old_size + old_col));
+                        }
+                        footprint.sort_unstable_by_key(|&index| (old_spaces[index].owner_id, index));
+                        let center_owner = old_spaces[center].owner_id;
+                        let mut best = (0, center); // Votes and first space of the leading owner
+                        let same_owner = |&a: &usize, &b: &usize| old_spaces[a].owner_id == old_spaces[b].owner_id;
+                        for run in footprint.chunk_by(same_owner) {
+                            let votes = (run.len(), old_spaces[run[0]].owner_id == center_owner);
+                            if votes > (best.0, old_spaces[best.1].owner_id == center_owner) {
+                                best = (run.len(), run[0]);
+                            }
+                        }
+                        if old_spaces[best.1].owner_id == center_owner {
+                            center
+                        } else {
+                            best.1
+                        }
+                    }
+                };
+                self.grid_spaces.push(GridSpace {
+                    terrain: old_spaces[center].terrain,
+                    resource: None,
+                    ..old_spaces[source]
+                });
+            }
+        }
+        for (index, space) in old_spaces.iter().enumerate() {
+            let Some(node) = space.resource else {
+                continue;
+            };
+            let resource = &mut self.grid_spaces[remap(index as u32) as usize].resource;
+            if resource.is_none() {
+                *resource = Some(node);
+            }
+        }
+        for entity in &mut self.entities {
+            if let Some(capital) = entity.capital {
+                let old_capital = &old_spaces[capital as usize];
+                let capital = remap(capital);
+                entity.capital = Some(capital);
+                if old_capital.owner_id == Some(entity.id) {
+                    let space = &mut self.grid_spaces[capital as usize];
+                    *space = GridSpace {
+                        terrain: space.terrain,
+                        resource: space.resource,
+                        ..*old_capital
+                    };
+                }
+            }
+            entity.fortify_target = entity.fortify_target.map(remap);
+            entity.command = entity.command.map(|command| command.remap_grid_index(remap));
+        }
+        for army in &mut self.armies {
+            army.objective = remap(army.objective);
+        }
+        for cell in &mut self.victory_cells {
+            *cell = remap(*cell);
+        }
+        self.victory_cells.sort_unstable();
+        self.victory_cells.dedup();
+        for (_, command) in self.pending_commands.iter_mut().chain(self.scheduled_commands.values_mut().flatten()) {
+            *command = command.remap_grid_index(remap);
+        }
+
+        self.world = self.world.with_grid_size(grid_size);
+        self.map_terrain = Some(self.grid_spaces.iter().map(|space| space.terrain).collect());
+        self.map_resources = Some(self.grid_spaces.iter().map(|space| space.resource).collect());
+        self.relocate_lost_capitals();
+        self.update_territories();
+        self.mark_all_grid_cells_dirty();
+        self.mark_snapshots_dirty();
+    }
+
+    pub fn world_config(&self) -> WorldConfig {
+        self.world
+    }
+
+    /// Apply a new world scale and respawn entities inside it, dropping the
+    /// terrain, resource nodes and victory point cells of a loaded map
+    pub fn set_world_config(&mut self, world: WorldConfig) {
+        self.world = world;
+        self.map_terrain = None;
+        self.map_resources = None;
+        self.victory_cells.clear();
+        let total_grid_spaces = world.grid_size * world.grid_size;
         self.grid_spaces.resize(total_grid_spaces, GridSpace::new());
+        self.apply_world_layers();
         self.rebuild_entities(self.entity_count);
     }
 
+    pub fn terrain_config(&self) -> TerrainConfig {
+        self.terrain
+    }
+
+    /// Generate the terrain and resource layers afresh from their configs
+    /// under the current territories, dropping those of a loaded map
+    pub fn regenerate_world_layers(&mut self) {
+        self.map_terrain = None;
+        self.map_resources = None;
+        self.apply_world_layers();
+        self.update_territories();
+    }
+
+    /// Regenerate the terrain and resource layers under the current territories
+    pub fn set_terrain_config(&mut self, terrain: TerrainConfig) -> Result<(), String> {
+        terrain.validate()?;
+        self.terrain = terrain;
+        self.apply_world_layers();
+        self.update_territories();
+        Ok(())
+    }
+
+    pub fn resource_config(&self) -> ResourceConfig {
+        self.resources
+    }
+
+    /// Respawn resource nodes under the current territories
+    pub fn set_resource_config(&mut self, resources: ResourceConfig) -> Result<(), String> {
+        resources.validate()?;
+        self.resources = resources;
+        self.apply_world_layers();
+        self.update_territories();
+        Ok(())
+    }
+
+    pub fn conquest_config(&self) -> ConquestConfig {
+        self.conquest
+    }
+
+    pub fn set_conquest_config(&mut self, conquest: ConquestConfig) -> Result<(), String> {
+        conquest.validate()?;
+        self.conquest = conquest;
+        Ok(())
+    }
+
+    pub fn neutral_config(&self) -> NeutralConfig {
+        self.neutrals
+    }
+
+    /// Garrison the unowned land afresh from `neutrals`
+    pub fn set_neutral_config(&mut self, neutrals: NeutralConfig) -> Result<(), String> {
+        neutrals.validate()?;
+        self.neutrals = neutrals;
+        self.place_garrisons();
+        self.mark_all_grid_cells_dirty();
+        Ok(())
+    }
+
+    pub fn unrest_config(&self) -> UnrestConfig {
+        self.unrest
+    }
+
+    /// Change the unrest rates; unrest already built up stays
+    pub fn set_unrest_config(&mut self, unrest: UnrestConfig) -> Result<(), String> {
+        unrest.validate()?;
+        self.unrest = unrest;
+        Ok(())
+    }
+
+    /// Build up or settle `ticks` worth of unrest on every owned space, then
+    /// let the spaces at the revolt threshold roll to revolt. Returns how many did.
+    ///
+    /// A revolting space defects back to its former owner if that entity is
+    /// still alive and the roll says so, or otherwise falls to a rebel garrison.
+    pub fn stir_unrest(&mut self, ticks: u64) -> usize {
+        let config = self.unrest;
+        let grid_size = self.world.grid_size;
+        let mut revolts = 0;
+        for index in 0..self.grid_spaces.len() {
+            let space = self.grid_spaces[index];
+            let Some(owner_id) = space.owner_id else {
+                continue;
+            };
+            let capital = self.entities.get(owner_id as usize).and_then(|owner| owner.capital);
+            if capital == Some(index as u32) {
+                continue;
+            }
+            let distance = capital.map_or(0, |capital| manhattan_distance(capital as usize, index, grid_size));
+            let change = distance as f32 * config.distance_unrest_per_tick - config.decay_per_tick;
+            let unrest = (space.unrest + change * ticks as f32).clamp(0.0, 1.0);
+            self.grid_spaces[index].unrest = unrest;
+            if unrest < config.revolt_threshold || !config.revolts(self.tick, index, grid_size) {
+                continue;
+            }
+
+            let defected_to = space.former_owner.filter(|&former_id| {
+                former_id != owner_id
+                    && self.entities.get(former_id as usize).is_some_and(|e| e.state != AiState::Dead)
+                    && config.defects(self.tick, index, grid_size)
+            });
+            self.grid_spaces[index] = match defected_to {
+                Some(former_id) => GridSpace {
+                    owner_id: Some(former_id),
+                    former_owner: Some(owner_id),
+                    unrest: 0.0,
+                    supply: 1.0,
+                    ..space
+                },
+                None => GridSpace {
+                    terrain: space.terrain,
+                    resource: space.resource,
+                    garrison: config.rebel_garrison,
+                    ..GridSpace::new()
+                },
+            };
+            self.mark_grid_cell_dirty(index);
+            self.record_event(SimulationEvent::Revolt {
+                tick: self.tick,
+                grid_index: index as u32,
+                owner_id,
+                defected_to,
+            });
+            revolts += 1;
+        }
+        if revolts > 0 {
+            self.update_territories();
+        }
+        revolts
+    }
+
+    pub fn event_config(&self) -> EventConfig {
+        self.event_config
+    }
+
+    /// Change how often disasters strike; plagues and famines under way run their course
+    pub fn set_event_config(&mut self, event_config: EventConfig) -> Result<(), String> {
+        event_config.validate()?;
+        self.event_config = event_config;
+        Ok(())
+    }
+
+    pub fn afflictions(&self) -> &[Affliction] {
+        &self.afflictions
+    }
+
+    pub fn calendar_config(&self) -> CalendarConfig {
+        self.calendar
+    }
+
+    /// Change the length and effects of the seasons; the date follows from the tick
+    pub fn set_calendar_config(&mut self, calendar: CalendarConfig) -> Result<(), String> {
+        calendar.validate()?;
+        self.calendar = calendar;
+        Ok(())
+    }
+
+    /// Date of the current tick in the world calendar
+    pub fn calendar_date(&self) -> CalendarDate {
+        self.calendar.date(self.tick)
+    }
+
+    /// Effects of the current tick's season
+    pub fn season_effects(&self) -> SeasonEffects {
+        self.calendar.effects(self.calendar.season(self.tick))
+    }
+
+    pub fn win_condition(&self) -> WinCondition {
+        self.win_condition
+    }
+
+    /// Change what it takes to win; every entity's victory point hold starts over
+    pub fn set_win_condition(&mut self, win_condition: WinCondition) -> Result<(), String> {
+        win_condition.validate()?;
+        self.win_condition = win_condition;
+        for entity in &mut self.entities {
+            entity.victory_hold = 0;
+        }
+        self.mark_snapshots_dirty();
+        Ok(())
+    }
+
+    pub fn victory_cells(&self) -> &[u32] {
+        &self.victory_cells
+    }
+
+    /// Make `cells` the victory point cells, replacing the current ones
+    pub fn set_victory_cells(&mut self, cells: &[u32]) -> Result<(), String> {
+        if let Some(&cell) = cells.iter().find(|&&cell| cell as usize >= self.grid_spaces.len()) {
+            return Err(format!("Grid index {} is outside the grid", cell));
+        }
+        self.victory_cells.clear();
+        self.victory_cells.extend_from_slice(cells);
+        self.victory_cells.sort_unstable();
+        self.victory_cells.dedup();
+        self.update_territories();
+        self.mark_snapshots_dirty();
+        Ok(())
+    }
+
+    /// Count another tick for every entity holding the victory points the win
+    /// condition needs and start the count over for the others, recording the
+    /// victory of any that has now held them long enough
+    pub fn hold_victory_points(&mut self) {
+        let WinCondition::VictoryPoints { needed, hold_ticks } = self.win_condition else {
+            return;
+        };
+        for index in 0..self.entities.len() {
+            let entity = &mut self.entities[index];
+            if entity.victory_points < needed {
+                entity.victory_hold = 0;
+                continue;
+            }
+            entity.victory_hold += 1;
+            if entity.victory_hold == hold_ticks {
+                let entity_id = entity.id;
+                self.record_event(SimulationEvent::Victory { tick: self.tick, entity_id });
+            }
+        }
+    }
+
+    /// Lowest-id alive entity that has won on victory points, if any
+    pub fn victor(&self) -> Option<u32> {
+        let WinCondition::VictoryPoints { hold_ticks, .. } = self.win_condition else {
+            return None;
+        };
+        self.entities
+            .iter()
+            .find(|entity| entity.state != AiState::Dead && entity.victory_hold >= hold_ticks)
+            .map(|entity| entity.id)
+    }
+
+    /// Roll for a disaster of each kind and let those that come up strike a
+    /// random alive entity or, for an earthquake, a random grid space
+    pub fn strike_disasters(&mut self) {
+        let config = self.event_config;
+        for kind in DisasterKind::ALL {
+            if !config.strikes(kind, self.tick) {
+                continue;
+            }
+            let target = match kind {
+                DisasterKind::Earthquake => config.pick(kind, self.tick, self.grid_spaces.len()) as u32,
+                DisasterKind::Plague | DisasterKind::Famine => {
+                    let mut alive = self.entities.iter().filter(|e| e.state != AiState::Dead);
+                    let count = alive.clone().count();
+                    match alive.nth(config.pick(kind, self.tick, count)) {
+                        Some(entity) => entity.id,
+                        None => continue,
+                    }
+                }
+            };
+            // Targets were picked among valid ones, so this can't fail
+            let _ = self.trigger_disaster(kind, target);
+        }
+    }
+
+    /// Strike with a disaster of `kind` now: `target` is the entity for a
+    /// plague or famine, which lasts the configured duration (extending one
+    /// already under way), and the epicenter's grid index for an earthquake
+    pub fn trigger_disaster(&mut self, kind: DisasterKind, target: u32) -> Result<(), String> {
+        let config = self.event_config;
+        let (entity_id, grid_index) = match kind {
+            DisasterKind::Earthquake => {
+                let epicenter = target as usize;
+                if epicenter >= self.grid_spaces.len() {
+                    return Err(format!("Grid index {} is outside the grid", target));
+                }
+                let grid_size = self.world.grid_size;
+                for index in 0..self.grid_spaces.len() {
+                    if manhattan_distance(epicenter, index, grid_size) <= config.earthquake_radius as usize {
+                        let space = &mut self.grid_spaces[index];
+                        space.defense_strength *= config.earthquake_defense_factor;
+                        space.garrison *= config.earthquake_defense_factor;
+                        self.mark_grid_cell_dirty(index);
+                    }
+                }
+                (None, Some(target))
+            }
+            DisasterKind::Plague | DisasterKind::Famine => {
+                self.alive_entity(target)?;
+                let ends_at = self.tick + config.duration_ticks;
+                let under_way = self
+                    .afflictions
+                    .iter_mut()
+                    .find(|affliction| affliction.kind == kind && affliction.entity_id == target);
+                match under_way {
+                    Some(affliction) => affliction.ends_at = ends_at,
+                    None => self.afflictions.push(Affliction {
+                        kind,
+                        entity_id: target,
+                        ends_at,
+                    }),
+                }
+                (Some(target), None)
+            }
+        };
+        self.record_event(SimulationEvent::Disaster {
+            tick: self.tick,
+            disaster: kind,
+            entity_id,
+            grid_index,
+        });
+        if kind == DisasterKind::Plague {
+            self.update_territories();
+        }
+        Ok(())
+    }
+
+    /// Lift the plagues and famines that have run their course and let the
+    /// rest drain their entities' money
+    pub fn apply_afflictions(&mut self) {
+        let tick = self.tick;
+        let lifted = self.afflictions.len();
+        self.afflictions.retain(|affliction| affliction.ends_at > tick);
+        let drain = self.event_config.famine_money_per_tick;
+        for affliction in &self.afflictions {
+            if affliction.kind == DisasterKind::Famine {
+                if let Some(entity) = self.entities.get_mut(affliction.entity_id as usize) {
+                    entity.money = (entity.money - drain).max(0.0);
+                }
+            }
+        }
+        if self.afflictions.len() < lifted {
+            self.update_territories();
+        }
+    }
+
+    /// Give every unowned space its starting garrison, if it rolls one
+    fn place_garrisons(&mut self) {
+        let grid_size = self.world.grid_size;
+        for (index, space) in self.grid_spaces.iter_mut().enumerate() {
+            if space.owner_id.is_none() {
+                space.garrison = self.neutrals.garrison(index, grid_size, space.terrain);
+            }
+        }
+    }
+
+    /// Strengthen every garrison by `ticks` worth of growth, up to the cap
+    pub fn grow_garrisons(&mut self, ticks: u64) {
+        let growth = self.neutrals.defense_growth_per_tick * ticks as f32;
+        if growth <= 0.0 {
+            return;
+        }
+        for index in 0..self.grid_spaces.len() {
+            let space = &mut self.grid_spaces[index];
+            if space.owner_id.is_none() && space.garrison > 0.0 && space.garrison < self.neutrals.max_defense {
+                space.garrison = (space.garrison + growth).min(self.neutrals.max_defense);
+                self.mark_grid_cell_dirty(index);
+            }
+        }
+    }
+
+    /// Lay terrain and resource nodes over the grid without touching
+    /// ownership, generating whichever layers no loaded map provides
+    fn apply_world_layers(&mut self) {
+        let grid_size = self.world.grid_size;
+        let terrain = match &self.map_terrain {
+            Some(terrain) => terrain.clone(),
+            None => self.terrain.generate(grid_size),
+        };
+        let resources = match &self.map_resources {
+            Some(resources) => resources.clone(),
+            None => self.resources.generate(&terrain, grid_size),
+        };
+        for ((space, terrain), resource) in self.grid_spaces.iter_mut().zip(terrain).zip(resources) {
+            space.terrain = terrain;
+            space.resource = resource;
+        }
+    }
+
+    /// Replace the world with a map's grid, land and entities and start the
+    /// run over on it
+    ///
+    /// Configs are kept; entities play for themselves.
+    pub fn load_map(&mut self, map: &MapDefinition) -> Result<(), String> {
+        map.validate()?;
+        self.world = self.world.with_grid_size(map.grid_size);
+        self.grid_spaces.resize(map.grid_size * map.grid_size, GridSpace::new());
+        self.map_terrain = map.terrain.clone();
+        self.map_resources = map.resource_layer();
+        self.victory_cells = map.victory_points.clone();
+        self.victory_cells.sort_unstable();
+        self.apply_world_layers();
+        self.clear_grid_ownership();
+
+        let half_extent = self.world.half_extent();
+        self.entities.clear();
+        for (id, spawn) in map.entities.iter().enumerate() {
+            let mut entity = AiEntity::with_half_extent(id as u32, half_extent);
+            let capital = spawn.capital as usize;
+            (entity.position_x, entity.position_y) = self.grid_index_to_position(capital);
+            entity.capital = Some(spawn.capital);
+            entity.military_strength = spawn.military_strength.unwrap_or(entity.military_strength);
+            entity.money = spawn.money.unwrap_or(entity.money);
+            entity.profile = spawn.profile.unwrap_or(entity.profile);
+            self.entities.push(entity);
+            let space = &mut self.grid_spaces[capital];
+            *space = GridSpace {
+                terrain: space.terrain,
+                resource: space.resource,
+                ..GridSpace::with_owner(id as u32, CAPITAL_START_DEFENSE)
+            };
+        }
+        for cell in &map.cells {
+            let space = &mut self.grid_spaces[cell.grid_index as usize];
+            *space = GridSpace {
+                terrain: space.terrain,
+                resource: space.resource,
+                ..GridSpace::with_owner(cell.owner_id, cell.defense_strength)
+            };
+        }
+        self.team_sizes.clear();
+        self.start_attempts = 0;
+        self.restart_run();
+        self.update_territories();
+        Ok(())
+    }
+
+    /// The world as a map that `load_map` starts a new run from
+    ///
+    /// Alive entities are renumbered in id order; garrisons, supply and
+    /// everything besides land and entities are left out.
+    pub fn export_map(&self) -> MapDefinition {
+        let mut new_ids = vec![None; self.entities.len()];
+        let mut entities = Vec::new();
+        for entity in self.entities.iter().filter(|e| e.state != AiState::Dead) {
+            let capital = entity
+                .capital
+                .or_else(|| self.position_to_grid_index(entity.position_x, entity.position_y).map(|i| i as u32));
+            if let Some(capital) = capital {
+                new_ids[entity.id as usize] = Some(entities.len() as u32);
+                entities.push(MapEntity {
+                    capital,
+                    military_strength: Some(entity.military_strength),
+                    money: Some(entity.money),
+                    profile: Some(entity.profile),
+                });
+            }
+        }
+        let cells = self
+            .grid_spaces
+            .iter()
+            .enumerate()
+            .filter_map(|(index, space)| {
+                let owner_id = new_ids.get(space.owner_id? as usize).copied().flatten()?;
+                Some(MapCell {
+                    grid_index: index as u32,
+                    owner_id,
+                    defense_strength: space.defense_strength,
+                })
+            })
+            .collect();
+        let resources = self
+            .grid_spaces
+            .iter()
+            .enumerate()
+            .filter_map(|(index, space)| {
+                Some(MapResource {
+                    grid_index: index as u32,
+                    node: space.resource?,
+                })
+            })
+            .collect();
+        MapDefinition {
+            grid_size: self.world.grid_size,
+            terrain: Some(self.grid_spaces.iter().map(|space| space.terrain).collect()),
+            resources: Some(resources),
+            entities,
+            cells,
+            victory_points: self.victory_cells.clone(),
+        }
+    }
+
     pub fn grid_spaces(&self) -> &[GridSpace] {
         &self.grid_spaces
     }
@@ -260,16 +1981,148 @@ impl SimulationData {
         self.grid_spaces.get_mut(index)
     }
 
+    /// Grid spaces changed since the last diff was taken
+    pub fn dirty_grid_cells(&self) -> &[usize] {
+        &self.dirty_grid_cells
+    }
+
+    /// Remember that a grid space changed so the next diff includes it
+    pub fn mark_grid_cell_dirty(&mut self, index: usize) {
+        if let Some(flag) = self.grid_dirty_flags.get_mut(index) {
+            if !*flag {
+                *flag = true;
+                self.dirty_grid_cells.push(index);
+            }
+        }
+    }
+
+    pub fn mark_all_grid_cells_dirty(&mut self) {
+        self.grid_dirty_flags.clear();
+        self.grid_dirty_flags.resize(self.grid_spaces.len(), true);
+        self.dirty_grid_cells.clear();
+        self.dirty_grid_cells.extend(0..self.grid_spaces.len());
+    }
+
+    /// Collect every grid space changed since the previous call and reset tracking
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn take_grid_diff(&mut self) -> &GridDiff {
+        self.grid_diff.clear();
+        for &index in &self.dirty_grid_cells {
+            let space = &self.grid_spaces[index];
+            self.grid_diff.indices.push(index as u32);
+            self.grid_diff
+                .owners
+                .push(space.displayed_owner().unwrap_or(UNOWNED_GRID_OWNER));
+            self.grid_diff.defense.push(space.displayed_defense());
+            self.grid_dirty_flags[index] = false;
+        }
+        self.dirty_grid_cells.clear();
+        &self.grid_diff
+    }
+
     pub fn position_to_grid_index(&self, x: f32, y: f32) -> Option<usize> {
-        // Convert world coordinates (-1200 to 1200) to grid coordinates
-        let grid_x = ((x + 1200.0) / 2400.0 * self.grid_size as f32).floor() as i32;
-        let grid_y = ((y + 1200.0) / 2400.0 * self.grid_size as f32).floor() as i32;
+        // Convert world coordinates (-half_extent to half_extent) to grid coordinates
+        let grid_size = self.world.grid_size;
+        let half_extent = self.world.half_extent();
+        let world_size = self.world.world_size;
+        let grid_x = ((x + half_extent) / world_size * grid_size as f32).floor() as i32;
+        let grid_y = ((y + half_extent) / world_size * grid_size as f32).floor() as i32;
         
-        if grid_x < 0 || grid_x >= self.grid_size as i32 || grid_y < 0 || grid_y >= self.grid_size as i32 {
+        if grid_x < 0 || grid_x >= grid_size as i32 || grid_y < 0 || grid_y >= grid_size as i32 {
             return None;
         }
         
-        Some((grid_y as usize) * self.grid_size + (grid_x as usize))
+        Some((grid_y as usize) * grid_size + (grid_x as usize))
+    }
+
+    /// Move every capital its entity no longer holds to the nearest space it
+    /// still owns
+    pub fn relocate_lost_capitals(&mut self) {
+        let grid_size = self.world.grid_size;
+        for entity in &mut self.entities {
+            let Some(capital) = entity.capital else {
+                continue;
+            };
+            let capital = capital as usize;
+            if self.grid_spaces.get(capital).is_some_and(|space| space.owner_id == Some(entity.id)) {
+                continue;
+            }
+            entity.capital = SupplyNetwork::nearest_owned_space(&self.grid_spaces, grid_size, entity.id, capital)
+                .map(|index| index as u32);
+        }
+    }
+
+    /// Hand part of `defender_id`'s remaining land, nearest its fallen capital
+    /// first, and part of its military strength and money to `attacker_id`,
+    /// then move its capital. Spaces that end up cut off from the attacker's
+    /// capital wither through supply decay. Returns how many spaces changed hands.
+    pub fn capture_capital(&mut self, defender_id: u32, attacker_id: u32, capital: usize) -> u32 {
+        let grid_size = self.world.grid_size;
+        let mut spaces: Vec<usize> = (0..self.grid_spaces.len())
+            .filter(|&index| self.grid_spaces[index].owner_id == Some(defender_id))
+            .collect();
+        spaces.sort_unstable_by_key(|&index| (manhattan_distance(capital, index, grid_size), index));
+        let handed_over = (spaces.len() as f32 * CAPITAL_TERRITORY_LOSS).ceil() as usize;
+        for &index in &spaces[..handed_over] {
+            let space = &mut self.grid_spaces[index];
+            space.owner_id = Some(attacker_id);
+            space.supply = 1.0;
+            space.unrest = self.unrest.conquest_unrest;
+            space.former_owner = Some(defender_id);
+            self.mark_grid_cell_dirty(index);
+        }
+
+        let (military_strength, money) = match self.entities.get_mut(defender_id as usize) {
+            Some(defender) => {
+                let military_strength = defender.military_strength.max(0.0) * CAPITAL_RESOURCE_LOSS;
+                let money = defender.money.max(0.0) * CAPITAL_RESOURCE_LOSS;
+                defender.military_strength -= military_strength;
+                defender.money -= money;
+                (military_strength, money)
+            }
+            None => (0.0, 0.0),
+        };
+        if let Some(attacker) = self.entities.get_mut(attacker_id as usize) {
+            attacker.military_strength += military_strength;
+            attacker.money += money;
+        }
+        self.relocate_lost_capitals();
+        self.update_territories();
+        handed_over as u32
+    }
+
+    /// Resupply the spaces flagged in `connected` and run down the rest,
+    /// starving their owners' morale; spaces that run out fall back to
+    /// unowned. Returns how many did.
+    pub fn apply_supply(&mut self, connected: &[bool]) -> usize {
+        let mut lost = 0;
+        for index in 0..self.grid_spaces.len() {
+            let space = &mut self.grid_spaces[index];
+            if space.owner_id.is_none() {
+                continue;
+            }
+            if connected.get(index).copied().unwrap_or(false) {
+                space.supply = 1.0;
+                continue;
+            }
+            if let Some(owner) = space.owner_id.and_then(|id| self.entities.get_mut(id as usize)) {
+                owner.adjust_morale(-MORALE_STARVATION_DRAIN / owner.territory.max(1) as f32);
+            }
+            space.supply = (space.supply - SUPPLY_DECAY).max(0.0);
+            if space.supply == 0.0 {
+                *space = GridSpace {
+                    terrain: space.terrain,
+                    resource: space.resource,
+                    ..GridSpace::new()
+                };
+                self.mark_grid_cell_dirty(index);
+                lost += 1;
+            }
+        }
+        if lost > 0 {
+            self.update_territories();
+        }
+        lost
     }
 
     /// Update all entities' territory counts based on owned grid spaces
@@ -277,6 +2130,10 @@ impl SimulationData {
         // Reset all territory counts
         for entity in &mut self.entities {
             entity.territory = 0;
+            entity.money_yield = 0.0;
+            entity.military_yield = 0.0;
+            entity.resource_nodes = 0;
+            entity.victory_points = 0;
         }
         
         // Count owned grid spaces for each entity using direct indexing
@@ -286,28 +2143,315 @@ impl SimulationData {
                 // Directly index into entities array using owner_id
                 let idx = owner_id as usize;
                 if idx < self.entities.len() && self.entities[idx].id == owner_id {
-                    self.entities[idx].territory += 1;
+                    let entity = &mut self.entities[idx];
+                    entity.territory += 1;
+                    let (money_yield, military_yield) = self.resources.space_yields(space, &self.terrain);
+                    entity.money_yield += money_yield;
+                    entity.military_yield += military_yield;
+                    entity.resource_nodes += space.resource.is_some() as u32;
                 }
             }
         }
+        for &cell in &self.victory_cells {
+            let owner_id = self.grid_spaces[cell as usize].owner_id;
+            if let Some(entity) = owner_id.and_then(|owner_id| self.entities.get_mut(owner_id as usize)) {
+                entity.victory_points += 1;
+            }
+        }
+        // Plague-stricken entities raise fewer troops from the same land
+        for affliction in &self.afflictions {
+            if affliction.kind == DisasterKind::Plague {
+                if let Some(entity) = self.entities.get_mut(affliction.entity_id as usize) {
+                    entity.military_yield *= self.event_config.plague_military_factor;
+                }
+            }
+        }
+        self.update_leaderboard();
+    }
+
+    /// Re-rank the alive entities by the leaderboard key
+    ///
+    /// The previous order is kept between updates and is usually nearly
+    /// sorted already, which the stable sort handles in close to linear time.
+    fn update_leaderboard(&mut self) {
+        let entities = &self.entities;
+        self.leaderboard
+            .retain(|&id| entities.get(id as usize).is_some_and(|entity| entity.state != AiState::Dead));
+        // Entities spawned since the last update join at the bottom
+        for entity in entities.iter().skip(self.leaderboard_known) {
+            if entity.state != AiState::Dead {
+                self.leaderboard.push(entity.id);
+            }
+        }
+        self.leaderboard_known = entities.len();
+        let key = self.leaderboard_key;
+        self.leaderboard
+            .sort_by(|&a, &b| key.compare(&entities[a as usize], &entities[b as usize]));
+    }
+
+    pub fn set_leaderboard_key(&mut self, key: LeaderboardKey) {
+        self.leaderboard_key = key;
+        self.update_leaderboard();
+    }
+
+    /// The `n` best alive entities by the leaderboard key, as of the last
+    /// territory update
+    pub fn leaderboard(&self, n: usize) -> Vec<LeaderboardEntry> {
+        self.leaderboard
+            .iter()
+            .take(n)
+            .map(|&id| LeaderboardEntry::from(&self.entities[id as usize]))
+            .collect()
+    }
+
+    /// Deterministic hash of the simulation-relevant state
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(self.tick);
+        hasher.write_u64(self.sim_time_ms.to_bits());
+        hasher.write_f32(self.world.world_size);
+        hasher.write_f32(self.world.cell_size);
+        hasher.write_u64(self.world.grid_size as u64);
+        hasher.write_u32(self.terrain.seed);
+        for effects in [self.terrain.plains, self.terrain.forest, self.terrain.mountain, self.terrain.water] {
+            hasher.write_f32(effects.movement_cost);
+            hasher.write_f32(effects.defense_bonus);
+            hasher.write_f32(effects.resource_multiplier);
+        }
+        hasher.write_u32(self.resources.seed);
+        hasher.write_f32(self.resources.gold_mine_chance);
+        hasher.write_f32(self.resources.farm_chance);
+        hasher.write_f32(self.resources.gold_mine_money_multiplier);
+        hasher.write_f32(self.resources.farm_military_multiplier);
+        hasher.write_u32(self.conquest.max_conquests_per_tick);
+        hasher.write_f32(self.conquest.extra_conquest_cost_growth);
+        hasher.write_u32(self.neutrals.seed);
+        hasher.write_f32(self.neutrals.garrison_chance);
+        hasher.write_f32(self.neutrals.initial_defense);
+        hasher.write_f32(self.neutrals.defense_growth_per_tick);
+        hasher.write_f32(self.neutrals.max_defense);
+        hasher.write_u32(self.unrest.seed);
+        hasher.write_f32(self.unrest.conquest_unrest);
+        hasher.write_f32(self.unrest.distance_unrest_per_tick);
+        hasher.write_f32(self.unrest.decay_per_tick);
+        hasher.write_f32(self.unrest.revolt_threshold);
+        hasher.write_f32(self.unrest.revolt_chance);
+        hasher.write_f32(self.unrest.defect_chance);
+        hasher.write_f32(self.unrest.rebel_garrison);
+        hasher.write_u32(self.event_config.seed);
+        hasher.write_f32(self.event_config.plague_chance);
+        hasher.write_f32(self.event_config.famine_chance);
+        hasher.write_f32(self.event_config.earthquake_chance);
+        hasher.write_u64(self.event_config.duration_ticks);
+        hasher.write_f32(self.event_config.plague_military_factor);
+        hasher.write_f32(self.event_config.famine_money_per_tick);
+        hasher.write_u32(self.event_config.earthquake_radius);
+        hasher.write_f32(self.event_config.earthquake_defense_factor);
+        hasher.write_u64(self.calendar.ticks_per_season);
+        for season in Season::ALL {
+            let effects = self.calendar.effects(season);
+            hasher.write_f32(effects.attack_factor);
+            hasher.write_f32(effects.money_factor);
+        }
+        match self.win_condition {
+            WinCondition::Annihilation => hasher.write_u32(0),
+            WinCondition::VictoryPoints { needed, hold_ticks } => {
+                hasher.write_u32(1);
+                hasher.write_u32(needed);
+                hasher.write_u64(hold_ticks);
+            }
+        }
+        hasher.write_u32(self.victory_cells.len() as u32);
+        for &cell in &self.victory_cells {
+            hasher.write_u32(cell);
+        }
+        // Only a loaded map's layers, so generated worlds hash as before
+        for &terrain in self.map_terrain.iter().flatten() {
+            hasher.write_u32(terrain as u32);
+        }
+        for &resource in self.map_resources.iter().flatten() {
+            hasher.write_u32(resource.map_or(0, |node| node as u32));
+        }
+        hasher.write_u64(self.entities.len() as u64);
+        for entity in &self.entities {
+            hasher.write_u32(entity.id);
+            hasher.write_f32(entity.military_strength);
+            hasher.write_f32(entity.position_x);
+            hasher.write_f32(entity.position_y);
+            hasher.write_u32(entity.state.into());
+            hasher.write_u32(entity.territory);
+            hasher.write_u32(entity.team_id.map_or(u32::MAX, |id| id));
+            hasher.write_u32(entity.overlord.map_or(u32::MAX, |id| id));
+            hasher.write_u64(entity.victory_hold);
+            hasher.write_f32(entity.money);
+            hasher.write_f32(entity.morale);
+            hasher.write_u32(entity.profile as u32);
+            hasher.write_u32(entity.capital.map_or(u32::MAX, |index| index));
+            hasher.write_u32(entity.rng_state());
+        }
+        for space in &self.grid_spaces {
+            hasher.write_u32(space.owner_id.map_or(u32::MAX, |id| id));
+            hasher.write_f32(space.defense_strength);
+            hasher.write_f32(space.supply);
+            hasher.write_f32(space.garrison);
+            hasher.write_f32(space.unrest);
+            hasher.write_u32(space.former_owner.map_or(u32::MAX, |id| id));
+        }
+        for (a, b) in self.alliances.pairs() {
+            hasher.write_u32(a);
+            hasher.write_u32(b);
+        }
+        for affliction in &self.afflictions {
+            hasher.write_u32(affliction.kind as u32);
+            hasher.write_u32(affliction.entity_id);
+            hasher.write_u64(affliction.ends_at);
+        }
+        for (a, b, relation) in self.relations.pairs() {
+            hasher.write_u32(a);
+            hasher.write_u32(b);
+            hasher.write_f32(relation.score);
+            hasher.write_u64(relation.last_clash.map_or(u64::MAX, |tick| tick));
+        }
+        hasher.write_u32(self.next_army_id);
+        for army in &self.armies {
+            hasher.write_u32(army.id);
+            hasher.write_u32(army.owner_id);
+            hasher.write_f32(army.strength);
+            hasher.write_f32(army.position_x);
+            hasher.write_f32(army.position_y);
+            hasher.write_u32(army.objective);
+        }
+        hasher.finish()
+    }
+
+    pub fn build_sync_bundle(&self) -> SyncBundle {
+        let tail_start = self.events.len().saturating_sub(SYNC_EVENT_TAIL);
+        SyncBundle {
+            version: SYNC_BUNDLE_VERSION,
+            tick: self.tick,
+            sim_time_ms: self.sim_time_ms,
+            state_hash: self.state_hash(),
+            running: self.running,
+            tick_rate: self.tick_rate,
+            world: self.world,
+            terrain: self.terrain,
+            resources: self.resources,
+            conquest: self.conquest,
+            neutrals: self.neutrals,
+            unrest: self.unrest,
+            event_config: self.event_config,
+            calendar: self.calendar,
+            win_condition: self.win_condition,
+            victory_cells: self.victory_cells.clone(),
+            map_terrain: self.map_terrain.clone(),
+            map_resources: self.map_resources.clone(),
+            entities: self.entities.iter().map(SyncedEntity::from).collect(),
+            teams: self.team_sizes.clone(),
+            grid_runs: encode_grid_runs(&self.grid_spaces),
+            alliances: self.alliances.pairs().collect(),
+            relations: self.relations.pairs().collect(),
+            armies: self.armies.clone(),
+            next_army_id: self.next_army_id,
+            afflictions: self.afflictions.clone(),
+            recent_events: self.events.iter().skip(tail_start).copied().collect(),
+        }
+    }
+
+    /// Replace the current state with the contents of a sync bundle
+    ///
+    /// The state is only replaced once the bundle has been fully validated,
+    /// including a hash check against the sender's state.
+    pub fn apply_sync_bundle(&mut self, bundle: &SyncBundle) -> Result<(), String> {
+        if bundle.version != SYNC_BUNDLE_VERSION {
+            return Err(format!(
+                "Unsupported sync bundle version {} (expected {})",
+                bundle.version, SYNC_BUNDLE_VERSION
+            ));
+        }
+        bundle.world.validate().map_err(|err| err.to_string())?;
+        bundle.terrain.validate()?;
+        bundle.resources.validate()?;
+        bundle.conquest.validate()?;
+        bundle.neutrals.validate()?;
+        bundle.unrest.validate()?;
+        bundle.event_config.validate()?;
+        bundle.calendar.validate()?;
+        bundle.win_condition.validate()?;
+        let grid_len = bundle
+            .world
+            .grid_size
+            .checked_mul(bundle.world.grid_size)
+            .ok_or_else(|| "Sync bundle grid size is too large".to_string())?;
+        let grid_spaces = decode_grid_runs(&bundle.grid_runs, grid_len)
+            .ok_or_else(|| "Sync bundle grid does not match its grid size".to_string())?;
+        if bundle.map_terrain.as_ref().is_some_and(|terrain| terrain.len() != grid_len)
+            || bundle.map_resources.as_ref().is_some_and(|resources| resources.len() != grid_len)
+        {
+            return Err("Sync bundle map layers do not match its grid size".to_string());
+        }
+        if bundle.victory_cells.iter().any(|&cell| cell as usize >= grid_len) {
+            return Err("Sync bundle victory point cells lie outside its grid".to_string());
+        }
+
+        let mut restored = Self::with_world(0, bundle.world);
+        restored.tick = bundle.tick;
+        restored.sim_time_ms = bundle.sim_time_ms;
+        restored.running = bundle.running;
+        restored.tick_rate = bundle.tick_rate;
+        restored.entity_count = bundle.entities.len();
+        restored.entities = bundle.entities.iter().map(AiEntity::from).collect();
+        restored.team_sizes = bundle.teams.clone();
+        restored.grid_spaces = grid_spaces;
+        restored.terrain = bundle.terrain;
+        restored.resources = bundle.resources;
+        restored.conquest = bundle.conquest;
+        restored.neutrals = bundle.neutrals;
+        restored.unrest = bundle.unrest;
+        restored.event_config = bundle.event_config;
+        restored.calendar = bundle.calendar;
+        restored.win_condition = bundle.win_condition;
+        restored.victory_cells = bundle.victory_cells.clone();
+        restored.afflictions = bundle.afflictions.clone();
+        restored.map_terrain = bundle.map_terrain.clone();
+        restored.map_resources = bundle.map_resources.clone();
+        restored.apply_world_layers();
+        restored.update_territories();
+        restored.armies = bundle.armies.clone();
+        restored.next_army_id = bundle.next_army_id;
+        restored.events = bundle.recent_events.iter().copied().collect();
+        for &(a, b) in &bundle.alliances {
+            restored.alliances.add(a, b);
+        }
+        for &(a, b, relation) in &bundle.relations {
+            restored.relations.insert(a, b, relation);
+        }
+
+        let hash = restored.state_hash();
+        if hash != bundle.state_hash {
+            return Err(format!(
+                "Sync bundle hash mismatch: expected {:016x}, got {:016x}",
+                bundle.state_hash, hash
+            ));
+        }
+
+        restored.fairness = self.fairness;
+        restored.start_attempts = 0;
+        restored.run_start = None;
+        restored.metrics = std::mem::take(&mut self.metrics);
+        *self = restored;
+        Ok(())
     }
 
-    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
     fn rebuild_flat_snapshot(&mut self) {
-        let required_len = self.entity_len() * SNAPSHOT_FIELD_COUNT;
+        let stride = self.snapshot_fields.len();
+        let required_len = self.entity_len() * stride;
         if self.flat_snapshot.len() != required_len {
             self.flat_snapshot.resize(required_len, 0.0);
         }
-        for (i, entity) in self.entities.iter().enumerate() {
-            let base = i * SNAPSHOT_FIELD_COUNT;
-            self.flat_snapshot[base] = entity.id as f32;
-            self.flat_snapshot[base + 1] = entity.military_strength;
-            self.flat_snapshot[base + 2] = entity.money;
-            self.flat_snapshot[base + 3] = entity.territory as f32;
-            let state_value: u32 = entity.state.into();
-            self.flat_snapshot[base + 4] = state_value as f32;
-            self.flat_snapshot[base + 5] = entity.position_x;
-            self.flat_snapshot[base + 6] = entity.position_y;
+        for (entity, record) in self.entities.iter().zip(self.flat_snapshot.chunks_exact_mut(stride)) {
+            for (value, field) in record.iter_mut().zip(&self.snapshot_fields) {
+                *value = field.flat_value(entity);
+            }
         }
         self.flat_snapshot_dirty = false;
     }
@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+
+use crate::constants::{NOTABLE_COMEBACK_HIGH, NOTABLE_COMEBACK_LOW, NOTABLE_EVENT_CAPACITY, NOTABLE_EXPANSION_SPACES};
+use crate::types::{AiEntity, AiState, NotableEvent, SimulationEvent};
+
+/// How far into a comeback an entity is
+#[derive(Debug, Clone, Copy, Default)]
+enum Standing {
+    /// Hasn't held `NOTABLE_COMEBACK_HIGH` of the territory yet
+    #[default]
+    Minor,
+    /// Held it, and hasn't fallen below `NOTABLE_COMEBACK_LOW` since
+    Strong,
+    /// Fell below `NOTABLE_COMEBACK_LOW`, lowest at `tick`
+    Fallen { tick: u64, share: f32 },
+}
+
+/// Watches each tick's events and territory for moments worth highlighting
+///
+/// Highlights start over whenever the run does, when the tick goes back.
+#[derive(Default)]
+pub struct NotableDetector {
+    tick: u64,
+    notable: VecDeque<NotableEvent>, // Most recent `NOTABLE_EVENT_CAPACITY`, oldest first
+    blooded: bool,
+    standings: Vec<Standing>, // Indexed by entity id
+    conquests: Vec<VecDeque<u64>>, // Ticks of each entity's latest conquests, indexed by id
+    fastest_expansion: Option<u64>,
+}
+
+impl NotableDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Highlights found so far as of `tick`, oldest first, or none if the
+    /// run has restarted since the last tick was watched
+    pub fn notable_events(&self, tick: u64) -> Vec<NotableEvent> {
+        if tick < self.tick {
+            return Vec::new();
+        }
+        self.notable.iter().copied().collect()
+    }
+
+    /// Look for comebacks in the territory held at the end of `tick`
+    pub fn end_tick(&mut self, tick: u64, entities: &[AiEntity]) {
+        if tick <= self.tick {
+            self.clear();
+        }
+        self.tick = tick;
+        if self.standings.len() < entities.len() {
+            self.standings.resize(entities.len(), Standing::Minor);
+        }
+        let total: u32 = entities.iter().map(|entity| entity.territory).sum();
+        if total == 0 {
+            return;
+        }
+        for entity in entities {
+            let share = entity.territory as f32 / total as f32;
+            let standing = &mut self.standings[entity.id as usize];
+            match *standing {
+                _ if entity.state == AiState::Dead => *standing = Standing::Minor,
+                Standing::Minor if share >= NOTABLE_COMEBACK_HIGH => *standing = Standing::Strong,
+                Standing::Strong if share < NOTABLE_COMEBACK_LOW => *standing = Standing::Fallen { tick, share },
+                Standing::Fallen { share: low, .. } if share < low => *standing = Standing::Fallen { tick, share },
+                Standing::Fallen { tick: low_tick, share: low_share } if share >= NOTABLE_COMEBACK_HIGH => {
+                    *standing = Standing::Strong;
+                    let entity_id = entity.id;
+                    self.push(NotableEvent::Comeback {
+                        tick,
+                        entity_id,
+                        low_tick,
+                        low_share,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Look for first blood and quick expansions among a tick's events
+    pub fn record_event(&mut self, event: &SimulationEvent) {
+        let SimulationEvent::Conquest {
+            tick,
+            grid_index,
+            attacker_id,
+            previous_owner,
+        } = *event
+        else {
+            return;
+        };
+        if let Some(defender_id) = previous_owner.filter(|_| !self.blooded) {
+            self.blooded = true;
+            self.push(NotableEvent::FirstBlood {
+                tick,
+                grid_index,
+                attacker_id,
+                defender_id,
+            });
+        }
+
+        let index = attacker_id as usize;
+        if self.conquests.len() <= index {
+            self.conquests.resize_with(index + 1, VecDeque::new);
+        }
+        let conquests = &mut self.conquests[index];
+        if conquests.len() == NOTABLE_EXPANSION_SPACES {
+            conquests.pop_front();
+        }
+        conquests.push_back(tick);
+        let Some(&first) = conquests.front().filter(|_| conquests.len() == NOTABLE_EXPANSION_SPACES) else {
+            return;
+        };
+        let ticks = tick - first;
+        if self.fastest_expansion.is_none_or(|fastest| ticks < fastest) {
+            self.fastest_expansion = Some(ticks);
+            self.push(NotableEvent::FastestExpansion {
+                tick,
+                entity_id: attacker_id,
+                ticks,
+            });
+        }
+    }
+
+    fn push(&mut self, event: NotableEvent) {
+        if self.notable.len() == NOTABLE_EVENT_CAPACITY {
+            self.notable.pop_front();
+        }
+        self.notable.push_back(event);
+    }
+
+    fn clear(&mut self) {
+        self.notable.clear();
+        self.blooded = false;
+        self.standings.clear();
+        self.conquests.clear();
+        self.fastest_expansion = None;
+    }
+}
@@ -0,0 +1,260 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::constants::MAX_DEFENSE_STRENGTH;
+use crate::types::{AllianceGraph, GridSpace, TerrainConfig};
+
+// Step costs for entering a grid space
+const FRIENDLY_STEP_COST: f32 = 1.0; // Own or allied land
+const UNOWNED_STEP_COST: f32 = 2.0;
+const ENEMY_STEP_COST: f32 = 3.0;
+const ENEMY_DEFENSE_STEP_COST: f32 = 10.0; // Extra cost for a fully fortified enemy space
+
+/// Open set entry ordered so the `BinaryHeap` pops the lowest estimate first,
+/// breaking ties on grid index to keep routes deterministic
+pub(super) struct OpenSpace {
+    pub(super) estimate: f32,
+    pub(super) index: usize,
+}
+
+impl PartialEq for OpenSpace {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenSpace {}
+
+impl PartialOrd for OpenSpace {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenSpace {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+/// A* search over the ownership grid
+///
+/// Walking through friendly land is cheapest, unowned land costs a little
+/// more and enemy land costs more the better it is defended, so attackers
+/// stay inside their borders and skirt fortified spaces on the way to the
+/// front. Every step is further scaled by the terrain's movement cost.
+/// Search buffers are kept between calls.
+pub struct Pathfinder {
+    costs: Vec<f32>,
+    came_from: Vec<usize>,
+    open: BinaryHeap<OpenSpace>,
+    path: Vec<usize>,
+}
+
+impl Pathfinder {
+    pub fn new() -> Self {
+        Self {
+            costs: Vec::new(),
+            came_from: Vec::new(),
+            open: BinaryHeap::new(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Cost for `mover_id` to enter a grid space
+    pub fn step_cost(
+        space: &GridSpace,
+        mover_id: u32,
+        alliances: &AllianceGraph,
+        terrain: &TerrainConfig,
+    ) -> f32 {
+        let ownership_cost = match space.owner_id {
+            None => UNOWNED_STEP_COST,
+            Some(owner_id) if owner_id == mover_id || alliances.are_allies(mover_id, owner_id) => {
+                FRIENDLY_STEP_COST
+            }
+            Some(_) => {
+                let fortification = (space.defense_strength / MAX_DEFENSE_STRENGTH).clamp(0.0, 1.0);
+                ENEMY_STEP_COST + fortification * ENEMY_DEFENSE_STEP_COST
+            }
+        };
+        ownership_cost * terrain.effects(space.terrain).movement_cost
+    }
+
+    /// Cheapest route from `start` to `goal`, excluding `start` and ending
+    /// with `goal`; empty when already there and `None` when out of range
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path(
+        &mut self,
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        start: usize,
+        goal: usize,
+        mover_id: u32,
+        alliances: &AllianceGraph,
+        terrain: &TerrainConfig,
+    ) -> Option<&[usize]> {
+        let space_count = grid_spaces.len();
+        if start >= space_count || goal >= space_count {
+            return None;
+        }
+
+        self.path.clear();
+        if start == goal {
+            return Some(&self.path);
+        }
+
+        self.costs.clear();
+        self.costs.resize(space_count, f32::INFINITY);
+        self.came_from.clear();
+        self.came_from.resize(space_count, usize::MAX);
+        self.open.clear();
+
+        // No step is cheaper than friendly land of the easiest terrain, so
+        // Manhattan distance at that rate never overestimates
+        let min_step_cost = FRIENDLY_STEP_COST * terrain.min_movement_cost();
+        let heuristic = |index: usize| manhattan_distance(index, goal, grid_size) as f32 * min_step_cost;
+
+        self.costs[start] = 0.0;
+        self.open.push(OpenSpace {
+            estimate: heuristic(start),
+            index: start,
+        });
+
+        while let Some(OpenSpace { estimate, index }) = self.open.pop() {
+            if index == goal {
+                break;
+            }
+            let cost = self.costs[index];
+            // Skip entries superseded by a cheaper route
+            if estimate > cost + heuristic(index) {
+                continue;
+            }
+
+            for neighbor in adjacent_spaces(index, grid_size) {
+                let next_cost = cost + Self::step_cost(&grid_spaces[neighbor], mover_id, alliances, terrain);
+                if next_cost < self.costs[neighbor] {
+                    self.costs[neighbor] = next_cost;
+                    self.came_from[neighbor] = index;
+                    self.open.push(OpenSpace {
+                        estimate: next_cost + heuristic(neighbor),
+                        index: neighbor,
+                    });
+                }
+            }
+        }
+
+        if self.came_from[goal] == usize::MAX {
+            return None;
+        }
+
+        let mut index = goal;
+        while index != start {
+            self.path.push(index);
+            index = self.came_from[index];
+        }
+        self.path.reverse();
+        Some(&self.path)
+    }
+
+    /// The space adjacent to `mover_id`'s territory that belongs to
+    /// `target_owner` (`None` for unowned land) and has the lowest
+    /// `frontier_cost`, together with the owned space to strike it from
+    ///
+    /// Ties go to the front closest to `from`, where the mover currently
+    /// stands.
+    pub fn cheapest_frontier(
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        mover_id: u32,
+        target_owner: Option<u32>,
+        from: usize,
+        frontier_cost: impl Fn(&GridSpace) -> f32,
+    ) -> Option<(usize, usize)> {
+        let mut best: Option<(f32, usize, usize, usize)> = None;
+        for (staging, space) in grid_spaces.iter().enumerate() {
+            if space.owner_id != Some(mover_id) {
+                continue;
+            }
+            let distance = manhattan_distance(from, staging, grid_size);
+            for target in adjacent_spaces(staging, grid_size) {
+                let target_space = &grid_spaces[target];
+                if target_space.owner_id != target_owner {
+                    continue;
+                }
+                let key = (frontier_cost(target_space), distance, target, staging);
+                let better = match best {
+                    None => true,
+                    Some(current) => {
+                        key.0
+                            .total_cmp(&current.0)
+                            .then((key.1, key.2).cmp(&(current.1, current.2)))
+                            == Ordering::Less
+                    }
+                };
+                if better {
+                    best = Some(key);
+                }
+            }
+        }
+        best.map(|(_, _, target, staging)| (target, staging))
+    }
+
+    /// The space next to `from` that belongs to `target_owner` (`None` for
+    /// unowned land) and has the lowest `frontier_cost`, ties going to the
+    /// lowest index
+    pub fn cheapest_neighbor(
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        target_owner: Option<u32>,
+        from: usize,
+        frontier_cost: impl Fn(&GridSpace) -> f32,
+    ) -> Option<usize> {
+        adjacent_spaces(from, grid_size)
+            .filter(|&target| grid_spaces[target].owner_id == target_owner)
+            .map(|target| (frontier_cost(&grid_spaces[target]), target))
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(_, target)| target)
+    }
+
+    /// The space owned by `mover_id` bordering `target` that is closest to `from`
+    pub fn staging_space(
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        mover_id: u32,
+        target: usize,
+        from: usize,
+    ) -> Option<usize> {
+        if target >= grid_spaces.len() {
+            return None;
+        }
+        adjacent_spaces(target, grid_size)
+            .filter(|&staging| grid_spaces[staging].owner_id == Some(mover_id))
+            .min_by_key(|&staging| (manhattan_distance(from, staging, grid_size), staging))
+    }
+
+    /// Whether two grid spaces share an edge
+    pub fn are_adjacent(a: usize, b: usize, grid_size: usize) -> bool {
+        manhattan_distance(a, b, grid_size) == 1
+    }
+}
+
+pub(super) fn manhattan_distance(a: usize, b: usize, grid_size: usize) -> usize {
+    let (row_a, col_a) = (a / grid_size, a % grid_size);
+    let (row_b, col_b) = (b / grid_size, b % grid_size);
+    row_a.abs_diff(row_b) + col_a.abs_diff(col_b)
+}
+
+/// In-bounds 4-directional neighbors of a grid space
+pub(super) fn adjacent_spaces(index: usize, grid_size: usize) -> impl Iterator<Item = usize> {
+    let row = index / grid_size;
+    let col = index % grid_size;
+    let up = (row > 0).then(|| index - grid_size);
+    let down = (row + 1 < grid_size).then(|| index + grid_size);
+    let left = (col > 0).then(|| index - 1);
+    let right = (col + 1 < grid_size).then(|| index + 1);
+    [up, down, left, right].into_iter().flatten()
+}
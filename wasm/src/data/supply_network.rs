@@ -0,0 +1,60 @@
+use crate::types::GridSpace;
+
+use super::pathfinder::{adjacent_spaces, manhattan_distance};
+
+/// Traces each entity's territory outward from its capital to find the
+/// spaces cut off from it
+///
+/// The flags and flood fill stack are reused between checks.
+#[derive(Debug, Clone, Default)]
+pub struct SupplyNetwork {
+    connected: Vec<bool>,
+    stack: Vec<usize>,
+}
+
+impl SupplyNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flag every space joined to one of `capitals` by an unbroken edge-to-edge
+    /// chain of spaces with the capital's owner
+    pub fn trace(
+        &mut self,
+        grid_spaces: &[GridSpace],
+        grid_size: usize,
+        capitals: impl IntoIterator<Item = usize>,
+    ) -> &[bool] {
+        self.connected.clear();
+        self.connected.resize(grid_spaces.len(), false);
+        for capital in capitals {
+            let Some(owner) = grid_spaces.get(capital).and_then(|space| space.owner_id) else {
+                continue;
+            };
+            if self.connected[capital] {
+                continue;
+            }
+            self.connected[capital] = true;
+            self.stack.push(capital);
+            while let Some(index) = self.stack.pop() {
+                for next in adjacent_spaces(index, grid_size) {
+                    if !self.connected[next] && grid_spaces[next].owner_id == Some(owner) {
+                        self.connected[next] = true;
+                        self.stack.push(next);
+                    }
+                }
+            }
+        }
+        &self.connected
+    }
+
+    /// The space of `owner_id` closest to `from`, ties going to the lowest index
+    pub fn nearest_owned_space(grid_spaces: &[GridSpace], grid_size: usize, owner_id: u32, from: usize) -> Option<usize> {
+        grid_spaces
+            .iter()
+            .enumerate()
+            .filter(|(_, space)| space.owner_id == Some(owner_id))
+            .min_by_key(|&(index, _)| (manhattan_distance(from, index, grid_size), index))
+            .map(|(index, _)| index)
+    }
+}
@@ -1,5 +1,7 @@
 /// Action types and candidate generation
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
 
 /// Action types for countries (§2, §3)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -10,18 +12,33 @@ pub enum Action {
     /// Invest in a specific sector
     Invest { sector: InvestSector },
     
-    /// Research a technology
-    Research { tech: TechType },
+    /// Research a tech tree node, by its index in the tree
+    Research { tech: u32 },
     
     /// Form alliance with neighbor
     Ally { target_id: u32 },
     
+    /// Leave an alliance, at a prestige cost
+    BreakAlliance { target_id: u32 },
+    
     /// Sign pact with neighbor
     Pact { target_id: u32 },
     
     /// Trade agreement with neighbor
     Trade { target_id: u32 },
     
+    /// Gather intelligence on a neighbor's military
+    Spy { target_id: u32 },
+    
+    /// Covertly damage a neighbor's military
+    Sabotage { target_id: u32 },
+    
+    /// Declare war on a neighbor
+    DeclareWar { target_id: u32 },
+    
+    /// Sign a peace treaty ending a war
+    MakePeace { target_id: u32 },
+    
     /// Fortify a border tile
     Fortify { tile_id: u32 },
     
@@ -38,15 +55,41 @@ impl Action {
         match self {
             Action::Attack { target_id } => format!("Attack country {}", target_id),
             Action::Invest { sector } => format!("Invest in {:?}", sector),
-            Action::Research { tech } => format!("Research {:?}", tech),
+            Action::Research { tech } => format!("Research tech {}", tech),
             Action::Ally { target_id } => format!("Ally with country {}", target_id),
+            Action::BreakAlliance { target_id } => format!("Break alliance with country {}", target_id),
             Action::Pact { target_id } => format!("Sign pact with country {}", target_id),
             Action::Trade { target_id } => format!("Trade with country {}", target_id),
+            Action::Spy { target_id } => format!("Spy on country {}", target_id),
+            Action::Sabotage { target_id } => format!("Sabotage country {}", target_id),
+            Action::DeclareWar { target_id } => format!("Declare war on country {}", target_id),
+            Action::MakePeace { target_id } => format!("Make peace with country {}", target_id),
             Action::Fortify { tile_id } => format!("Fortify tile {}", tile_id),
             Action::Move { tile_id } => format!("Move to tile {}", tile_id),
             Action::Pass => "Pass".to_string(),
         }
     }
+    
+    /// Stable ordering used to break score ties: action kind in declaration
+    /// order, then target, sector, tech or tile id
+    pub fn tie_break_key(&self) -> (u8, u32) {
+        match self {
+            Action::Attack { target_id } => (0, *target_id),
+            Action::Invest { sector } => (1, *sector as u32),
+            Action::Research { tech } => (2, *tech),
+            Action::Ally { target_id } => (3, *target_id),
+            Action::BreakAlliance { target_id } => (4, *target_id),
+            Action::Pact { target_id } => (5, *target_id),
+            Action::Trade { target_id } => (6, *target_id),
+            Action::Spy { target_id } => (7, *target_id),
+            Action::Sabotage { target_id } => (8, *target_id),
+            Action::DeclareWar { target_id } => (9, *target_id),
+            Action::MakePeace { target_id } => (10, *target_id),
+            Action::Fortify { tile_id } => (11, *tile_id),
+            Action::Move { tile_id } => (12, *tile_id),
+            Action::Pass => (13, 0),
+        }
+    }
 }
 
 /// Investment sectors (§3.2)
@@ -58,23 +101,41 @@ pub enum InvestSector {
     Technology,
 }
 
-/// Technology types (§3.3)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum TechType {
-    MilitaryAdvancement,
-    EconomicEfficiency,
-    DiplomaticInfluence,
-    TechnologicalBreakthrough,
+impl InvestSector {
+    /// GDP added per tick of investment
+    pub fn gdp_boost(self) -> f32 {
+        match self {
+            InvestSector::Economy => 5.0,
+            InvestSector::Infrastructure => 3.0,
+            InvestSector::Technology => 4.0,
+            InvestSector::Military => 2.0,
+        }
+    }
+    
+    /// Resources spent on one investment
+    pub fn base_cost(self) -> f32 {
+        match self {
+            InvestSector::Economy => 20.0,
+            InvestSector::Infrastructure => 30.0,
+            InvestSector::Technology => 25.0,
+            InvestSector::Military => 15.0,
+        }
+    }
 }
 
-/// Candidate pruning configuration (§5)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Candidate pruning configuration (§5); fields omitted from JSON take their defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default, deny_unknown_fields)]
 pub struct PruningConfig {
     pub k_attack: usize,      // Top K attacks by upper bound
     pub k_fortify: usize,     // Top K border tiles by threat gradient
     pub k_invest: usize,      // Top K sectors by ROI
     pub k_research: usize,    // Top K techs by marginal value
     pub k_diplomacy: usize,   // Up to K diplomatic actions
+    pub k_war: usize,         // Up to K war declarations or peace offers
+    pub k_espionage: usize,   // Up to K spy/sabotage missions
+    pub lookahead_depth: usize,  // Plies simulated for attack candidates: 0 (off), 1 or 2
 }
 
 impl PruningConfig {
@@ -85,6 +146,9 @@ impl PruningConfig {
             k_invest: 2,
             k_research: 2,
             k_diplomacy: 2,
+            k_war: 1,
+            k_espionage: 1,
+            lookahead_depth: 0,
         }
     }
 }
@@ -110,53 +174,58 @@ impl ActionCandidate {
 
 /// Generate shortlist of candidate actions (§5)
 pub fn generate_shortlist(
-    _country_id: u32,
+    country_id: u32,
     country: &super::country::Country,
     world: &super::world::WorldState,
     config: &PruningConfig,
 ) -> Vec<Action> {
     let mut candidates = Vec::new();
+    generate_shortlist_into(country_id, country, world, config, &mut candidates, &mut Vec::new());
+    candidates
+}
+
+/// `generate_shortlist` into `candidates`, ranking each category in `scratch`;
+/// both are cleared first, so callers can reuse them every tick without allocating
+pub fn generate_shortlist_into(
+    _country_id: u32,
+    country: &super::country::Country,
+    world: &super::world::WorldState,
+    config: &PruningConfig,
+    candidates: &mut Vec<Action>,
+    scratch: &mut Vec<ActionCandidate>,
+) {
+    candidates.clear();
     
     // Always include Pass
     candidates.push(Action::Pass);
     
     // Generate attack candidates (top K by upper bound of ΔSec + ΔRes)
-    let mut attack_candidates = Vec::new();
+    scratch.clear();
     for edge in &country.edges {
         if let Some(neighbor) = world.get_country(edge.neighbor_id) {
-            // Upper bound heuristic: resource gain + threat reduction
-            let resource_upper = neighbor.resources * 0.5;  // Potential resource gain
+            // Upper bound heuristic: resource gain + threat reduction; expansionists weigh the loot more
+            let resource_upper = neighbor.resources * country.personality.expansionism;  // Potential resource gain
             let threat_reduction = edge.hostility * neighbor.m_eff * 0.3;  // Threat reduction estimate
             let priority = resource_upper + threat_reduction;
             
-            attack_candidates.push(ActionCandidate::new(
+            scratch.push(ActionCandidate::new(
                 Action::Attack { target_id: edge.neighbor_id },
                 priority,
             ));
         }
     }
     // Sort by priority and take top K
-    attack_candidates.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
-    candidates.extend(
-        attack_candidates.iter()
-            .take(config.k_attack)
-            .map(|c| c.action.clone())
-    );
+    take_top(scratch, config.k_attack, candidates);
     
     // Generate fortify/move candidates (top K by |∇TI|)
-    let mut fortify_candidates = Vec::new();
+    scratch.clear();
     for tile in &country.border_tiles {
-        fortify_candidates.push(ActionCandidate::new(
+        scratch.push(ActionCandidate::new(
             Action::Fortify { tile_id: tile.id },
             tile.threat_gradient.abs(),
         ));
     }
-    fortify_candidates.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
-    candidates.extend(
-        fortify_candidates.iter()
-            .take(config.k_fortify)
-            .map(|c| c.action.clone())
-    );
+    take_top(scratch, config.k_fortify, candidates);
     
     // Generate invest candidates (top K by ROI estimate)
     let invest_sectors = [
@@ -165,7 +234,7 @@ pub fn generate_shortlist(
         InvestSector::Economy,
         InvestSector::Technology,
     ];
-    let mut invest_candidates = Vec::new();
+    scratch.clear();
     for sector in &invest_sectors {
         // Simple ROI heuristic based on current needs
         let roi = match sector {
@@ -174,66 +243,112 @@ pub fn generate_shortlist(
             InvestSector::Technology => country.marginal_values.tech,
             InvestSector::Infrastructure => country.marginal_values.economy * 0.5,
         };
-        invest_candidates.push(ActionCandidate::new(
+        scratch.push(ActionCandidate::new(
             Action::Invest { sector: *sector },
             roi,
         ));
     }
-    invest_candidates.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
-    candidates.extend(
-        invest_candidates.iter()
-            .take(config.k_invest)
-            .map(|c| c.action.clone())
-    );
+    take_top(scratch, config.k_invest, candidates);
     
-    // Generate research candidates (top K by Σ m_tq * MV_q)
-    let tech_types = [
-        TechType::MilitaryAdvancement,
-        TechType::EconomicEfficiency,
-        TechType::DiplomaticInfluence,
-        TechType::TechnologicalBreakthrough,
-    ];
-    let mut research_candidates = Vec::new();
-    for tech in &tech_types {
-        // Marginal value weighted by tech impact
-        let mv_weighted = match tech {
-            TechType::MilitaryAdvancement => country.marginal_values.military * 1.5,
-            TechType::EconomicEfficiency => country.marginal_values.economy * 1.5,
-            TechType::DiplomaticInfluence => country.marginal_values.diplomacy * 1.5,
-            TechType::TechnologicalBreakthrough => country.marginal_values.tech * 2.0,
-        };
-        research_candidates.push(ActionCandidate::new(
-            Action::Research { tech: *tech },
-            mv_weighted,
-        ));
+    // Generate research candidates (top K unlockable nodes by Σ m_tq * MV_q)
+    let tech_tree = world.tech_tree();
+    scratch.clear();
+    for tech in tech_tree.unlockable(country) {
+        if let Some(node) = tech_tree.get(tech) {
+            scratch.push(ActionCandidate::new(
+                Action::Research { tech },
+                node.value_for(country),
+            ));
+        }
     }
-    research_candidates.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
-    candidates.extend(
-        research_candidates.iter()
-            .take(config.k_research)
-            .map(|c| c.action.clone())
-    );
+    take_top(scratch, config.k_research, candidates);
     
     // Generate diplomacy candidates (up to K with improving stance)
-    let mut diplo_candidates = Vec::new();
+    scratch.clear();
     for edge in &country.edges {
-        // Consider diplomacy if relations are neutral to positive or if strategically valuable
-        if edge.relations >= -20.0 {
+        if world.are_allies(country.id, edge.neighbor_id) {
+            // Consider leaving alliances with allies that have turned hostile; loyal countries hold on longer
+            if edge.hostility >= country.personality.loyalty {
+                let priority = edge.hostility * 100.0 - edge.relations;
+                scratch.push(ActionCandidate::new(
+                    Action::BreakAlliance { target_id: edge.neighbor_id },
+                    priority,
+                ));
+            }
+        } else if edge.relations >= -20.0 && !world.at_war(country.id, edge.neighbor_id) {
+            // Consider diplomacy if relations are neutral to positive or if strategically valuable
             let priority = edge.relations + 50.0;  // Favor better relations
-            diplo_candidates.push(ActionCandidate::new(
+            scratch.push(ActionCandidate::new(
                 Action::Ally { target_id: edge.neighbor_id },
                 priority,
             ));
         }
+        
+        // Offer trade to any neighbor we aren't fighting or already trading with
+        if edge.relations >= -20.0
+            && !world.at_war(country.id, edge.neighbor_id)
+            && !world.has_trade_route(country.id, edge.neighbor_id)
+        {
+            if let Some(neighbor) = world.get_country(edge.neighbor_id) {
+                let priority = edge.relations + country.gdp.min(neighbor.gdp) * 0.1;
+                scratch.push(ActionCandidate::new(
+                    Action::Trade { target_id: edge.neighbor_id },
+                    priority,
+                ));
+            }
+        }
     }
-    diplo_candidates.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
-    candidates.extend(
-        diplo_candidates.iter()
-            .take(config.k_diplomacy)
-            .map(|c| c.action.clone())
-    );
+    take_top(scratch, config.k_diplomacy, candidates);
     
-    candidates
+    // Generate war/peace candidates: peace with current enemies, war on hostile neighbors
+    scratch.clear();
+    for enemy_id in world.enemies_of(country.id) {
+        scratch.push(ActionCandidate::new(
+            Action::MakePeace { target_id: enemy_id },
+            country.war_exhaustion * super::country::Personality::scale(country.personality.risk_aversion),
+        ));
+    }
+    for edge in &country.edges {
+        if edge.hostility >= 1.0 - country.personality.aggression
+            && !world.at_war(country.id, edge.neighbor_id)
+            && !world.are_allies(country.id, edge.neighbor_id)
+            && world.get_country(edge.neighbor_id).is_some()
+        {
+            scratch.push(ActionCandidate::new(
+                Action::DeclareWar { target_id: edge.neighbor_id },
+                edge.hostility * 10.0,
+            ));
+        }
+    }
+    take_top(scratch, config.k_war, candidates);
+    
+    // Generate espionage candidates: spy where intel is poor, sabotage hostile neighbors we know well
+    scratch.clear();
+    for edge in &country.edges {
+        let Some(neighbor) = world.get_country(edge.neighbor_id) else {
+            continue;
+        };
+        let intel = country.intel_on(edge.neighbor_id);
+        if intel < 1.0 {
+            scratch.push(ActionCandidate::new(
+                Action::Spy { target_id: edge.neighbor_id },
+                (1.0 - intel) * edge.hostility * neighbor.m_eff,
+            ));
+        }
+        if edge.hostility >= 0.5 && !world.are_allies(country.id, edge.neighbor_id) {
+            scratch.push(ActionCandidate::new(
+                Action::Sabotage { target_id: edge.neighbor_id },
+                intel * edge.hostility * neighbor.m_eff,
+            ));
+        }
+    }
+    take_top(scratch, config.k_espionage, candidates);
+}
+
+/// Append the `k` highest-priority entries of `ranked` to `candidates`
+fn take_top(ranked: &mut [ActionCandidate], k: usize, candidates: &mut Vec<Action>) {
+    ranked.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap());
+    candidates.extend(ranked.iter().take(k).map(|c| c.action.clone()));
 }
 
 #[cfg(test)]
@@ -251,6 +366,39 @@ mod tests {
         assert_eq!(action.description(), "Pass");
     }
 
+    #[test]
+    fn test_shortlist_offers_breaking_hostile_alliances() {
+        let mut world = WorldState::new();
+        let mut country = Country::new(1);
+        let mut edge = CountryEdge::new(2);
+        edge.hostility = 0.8;
+        country.add_edge(edge);
+        country.add_edge(CountryEdge::new(3));
+        world.add_country(Country::new(2));
+        world.add_country(Country::new(3));
+        world.add_alliance(1, 2);
+        world.add_alliance(1, 3);
+        
+        let shortlist = generate_shortlist(1, &country, &world, &PruningConfig::default());
+        
+        assert!(shortlist.contains(&Action::BreakAlliance { target_id: 2 }));
+        assert!(!shortlist.contains(&Action::BreakAlliance { target_id: 3 }));  // Still friendly
+        assert!(!shortlist.iter().any(|a| matches!(a, Action::Ally { .. })));  // Already allied
+    }
+
+    #[test]
+    fn test_shortlist_offers_trade_until_a_route_exists() {
+        let mut world = WorldState::new();
+        let mut country = Country::new(1);
+        country.add_edge(CountryEdge::new(2));
+        world.add_country(Country::new(2));
+        let config = PruningConfig { k_diplomacy: 4, ..PruningConfig::default() };
+        
+        assert!(generate_shortlist(1, &country, &world, &config).contains(&Action::Trade { target_id: 2 }));
+        world.add_trade_route(2, 1, 1.0, 0.0);
+        assert!(!generate_shortlist(1, &country, &world, &config).contains(&Action::Trade { target_id: 2 }));
+    }
+
     #[test]
     fn test_pruning_config() {
         let config = PruningConfig::default();
@@ -1,8 +1,17 @@
 /// Country state and edge relationship data
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use super::scoring::ScoreCache;
+
+/// Intel assumed on countries we have never spied on
+pub const DEFAULT_INTEL: f32 = 0.5;
 
 /// Adaptive weights for decision scoring (§4)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
 pub struct AdaptiveWeights {
     pub alpha: i32,    // Resource gain weight
     pub beta: i32,     // Security weight
@@ -25,12 +34,20 @@ impl AdaptiveWeights {
         }
     }
     
-    /// Update weights based on needs signals
-    pub fn update(&mut self, resources: f32, threat_index: f32, growth: f32, ally_count: usize, recent_losses: f32) {
+    /// Update weights based on needs signals, biased by personality
+    pub fn update(
+        &mut self,
+        resources: f32,
+        threat_index: f32,
+        growth: f32,
+        ally_count: usize,
+        recent_losses: f32,
+        personality: &Personality,
+    ) {
         // Resource weight: α_i = clamp(α0 * (1 + c_R * (R* - R_i)/R*), α_min, α_max)
         let r_target = 1000.0;
         let c_r = 0.5;
-        let alpha_base = 8.0;
+        let alpha_base = 8.0 * Personality::scale(personality.expansionism);
         let alpha_new = alpha_base * (1.0 + c_r * (r_target - resources) / r_target);
         self.alpha = (alpha_new.round() as i32).clamp(2, 16);
         
@@ -49,7 +66,7 @@ impl AdaptiveWeights {
         self.gamma = (gamma_new.round() as i32).clamp(2, 16);
         
         // Position weight: based on diplomatic isolation
-        let delta_base = 4.0;
+        let delta_base = 4.0 * Personality::scale(personality.loyalty);
         let isolation_factor = if ally_count > 0 {
             1.0 / (ally_count as f32 + 1.0)
         } else {
@@ -58,11 +75,12 @@ impl AdaptiveWeights {
         let delta_new = delta_base * isolation_factor;
         self.delta = (delta_new.round() as i32).clamp(2, 16);
         
-        // Cost weight: relatively stable
-        self.kappa = 8;
+        // Cost weight: stable apart from aggression, which makes costs weigh less
+        let kappa_new = 8.0 * Personality::scale(1.0 - personality.aggression);
+        self.kappa = (kappa_new.round() as i32).clamp(2, 16);
         
         // Risk weight: increase with recent losses
-        let rho_base = 4.0;
+        let rho_base = 4.0 * Personality::scale(personality.risk_aversion);
         let loss_factor = 1.0 + (recent_losses / 100.0);
         let rho_new = rho_base * loss_factor;
         self.rho = (rho_new.round() as i32).clamp(2, 16);
@@ -121,16 +139,73 @@ impl Default for MarginalValues {
     }
 }
 
-/// Edge relationship between two countries (§8)
+/// Character traits that bias a country's weights and shortlists, each from 0.0 to 1.0.
+/// The neutral 0.5 leaves behavior unchanged; fields omitted from JSON stay neutral
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Personality {
+    pub aggression: f32,      // Cheaper-feeling costs; declares war on less hostile neighbors
+    pub risk_aversion: f32,   // Heavier risk weight; keener to make peace
+    pub expansionism: f32,    // Heavier resource weight; attacks ranked by the loot on offer
+    pub loyalty: f32,         // Heavier positional weight; tolerates hostile allies longer
+}
+
+impl Personality {
+    pub const NEUTRAL: f32 = 0.5;
+    
+    pub fn new() -> Self {
+        Self {
+            aggression: Self::NEUTRAL,
+            risk_aversion: Self::NEUTRAL,
+            expansionism: Self::NEUTRAL,
+            loyalty: Self::NEUTRAL,
+        }
+    }
+    
+    pub fn validate(&self) -> Result<(), String> {
+        let traits = [
+            ("aggression", self.aggression),
+            ("risk_aversion", self.risk_aversion),
+            ("expansionism", self.expansionism),
+            ("loyalty", self.loyalty),
+        ];
+        match traits.into_iter().find(|(_, value)| !(0.0..=1.0).contains(value)) {
+            Some((name, value)) => Err(format!("Personality trait {} must be between 0 and 1, got {}", name, value)),
+            None => Ok(()),
+        }
+    }
+    
+    /// Multiplier in [0.5, 1.5] for a trait; exactly 1.0 at neutral
+    pub fn scale(value: f32) -> f32 {
+        0.5 + value
+    }
+}
+
+impl Default for Personality {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Edge relationship between two countries (§8); fields omitted from JSON
+/// take the values `CountryEdge::new` gives them
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CountryEdge {
     pub neighbor_id: u32,
+    #[serde(default = "CountryEdge::default_distance_bucket")]
     pub distance_bucket: usize,   // Discrete distance for kernel lookup
+    #[serde(default)]
     pub terrain_penalty: f32,
+    #[serde(default)]
     pub fortification: f32,
+    #[serde(default = "CountryEdge::default_border_length")]
     pub border_length: f32,
+    #[serde(default)]
     pub supply_diff: f32,
+    #[serde(default)]
     pub hostility: f32,           // 0.0 to 1.0
+    #[serde(default)]
     pub relations: f32,           // -100 to +100
 }
 
@@ -147,6 +222,29 @@ impl CountryEdge {
             relations: 0.0,
         }
     }
+    
+    fn default_distance_bucket() -> usize {
+        1
+    }
+    
+    fn default_border_length() -> f32 {
+        1.0
+    }
+    
+    /// Name of the first field that isn't a finite number
+    pub fn first_non_finite(&self) -> Option<&'static str> {
+        [
+            ("terrain_penalty", self.terrain_penalty),
+            ("fortification", self.fortification),
+            ("border_length", self.border_length),
+            ("supply_diff", self.supply_diff),
+            ("hostility", self.hostility),
+            ("relations", self.relations),
+        ]
+        .into_iter()
+        .find(|(_, value)| !value.is_finite())
+        .map(|(name, _)| name)
+    }
 }
 
 /// Country state with cached features (§8)
@@ -160,12 +258,16 @@ pub struct Country {
     pub morale: f32,             // Military morale
     pub tech_level: f32,         // Technology level
     pub resources: f32,          // Available resources
+    pub territory: u32,          // Tiles held
     pub threat_index: f32,       // Cached TI_i
     pub ally_count: usize,       // Number of allies
     pub recent_losses: f32,      // Recent casualty count
+    pub war_exhaustion: f32,     // Grows each tick at war, decays in peace
     
     // Adaptive components
     pub weights: AdaptiveWeights,
+    #[serde(default)]
+    pub personality: Personality,
     pub marginal_values: MarginalValues,
     
     // Neighbors and edges
@@ -173,6 +275,16 @@ pub struct Country {
     
     // Border tiles for fortify/move actions
     pub border_tiles: Vec<BorderTile>,
+    
+    // Tech tree nodes already researched
+    pub researched: HashSet<u32>,
+    
+    // How accurately other countries' strength is known, 0.0 (guesswork) to 1.0 (exact)
+    pub intel: HashMap<u32, f32>,
+    
+    // Last tick's scores; None (dirty) once this country or a neighbor changes
+    #[serde(skip)]
+    pub score_cache: Option<ScoreCache>,
 }
 
 impl Country {
@@ -187,16 +299,42 @@ impl Country {
             morale: 1.0,
             tech_level: 1.0,
             resources: 500.0,
+            territory: 10,
             threat_index: 0.0,
             ally_count: 0,
             recent_losses: 0.0,
+            war_exhaustion: 0.0,
             weights: AdaptiveWeights::new(),
+            personality: Personality::new(),
             marginal_values: MarginalValues::new(),
             edges: Vec::new(),
             border_tiles: Vec::new(),
+            researched: HashSet::new(),
+            intel: HashMap::new(),
+            score_cache: None,
         }
     }
     
+    /// Reset to `Country::new(id)`, keeping the edge, border tile, research
+    /// and intel buffers so they can be refilled without allocating
+    pub fn reset(&mut self, id: u32) {
+        let mut edges = std::mem::take(&mut self.edges);
+        let mut border_tiles = std::mem::take(&mut self.border_tiles);
+        let mut researched = std::mem::take(&mut self.researched);
+        let mut intel = std::mem::take(&mut self.intel);
+        edges.clear();
+        border_tiles.clear();
+        researched.clear();
+        intel.clear();
+        *self = Self {
+            edges,
+            border_tiles,
+            researched,
+            intel,
+            ..Self::new(id)
+        };
+    }
+    
     /// Add an edge to a neighbor
     pub fn add_edge(&mut self, edge: CountryEdge) {
         self.edges.push(edge);
@@ -211,16 +349,113 @@ impl Country {
     pub fn get_edge_mut(&mut self, neighbor_id: u32) -> Option<&mut CountryEdge> {
         self.edges.iter_mut().find(|e| e.neighbor_id == neighbor_id)
     }
+    
+    /// Intel level on another country
+    pub fn intel_on(&self, country_id: u32) -> f32 {
+        self.intel.get(&country_id).copied().unwrap_or(DEFAULT_INTEL)
+    }
+    
+    /// Mark cached scores stale so the next tick rescores this country
+    pub fn invalidate_scores(&mut self) {
+        self.score_cache = None;
+    }
+    
+    /// Whether the cached scores are stale or missing
+    pub fn scores_dirty(&self) -> bool {
+        self.score_cache.is_none()
+    }
+    
+    /// Remove the edge to a neighbor, returning whether one existed
+    pub fn remove_edge(&mut self, neighbor_id: u32) -> bool {
+        let before = self.edges.len();
+        self.edges.retain(|e| e.neighbor_id != neighbor_id);
+        self.edges.len() != before
+    }
+}
+
+/// Partial update of a country's core stats; omitted fields keep their values
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CountryStats {
+    pub m_eff: Option<f32>,
+    pub gdp: Option<f32>,
+    pub growth: Option<f32>,
+    pub prestige: Option<f32>,
+    pub morale: Option<f32>,
+    pub tech_level: Option<f32>,
+    pub resources: Option<f32>,
+    pub territory: Option<u32>,
+    pub recent_losses: Option<f32>,
+}
+
+impl CountryStats {
+    /// Every stat of `country`
+    pub fn of(country: &Country) -> Self {
+        Self {
+            m_eff: Some(country.m_eff),
+            gdp: Some(country.gdp),
+            growth: Some(country.growth),
+            prestige: Some(country.prestige),
+            morale: Some(country.morale),
+            tech_level: Some(country.tech_level),
+            resources: Some(country.resources),
+            territory: Some(country.territory),
+            recent_losses: Some(country.recent_losses),
+        }
+    }
+    
+    /// Overwrite the stats present in this update
+    pub fn apply(&self, country: &mut Country) {
+        let fields = [
+            (self.m_eff, &mut country.m_eff),
+            (self.gdp, &mut country.gdp),
+            (self.growth, &mut country.growth),
+            (self.prestige, &mut country.prestige),
+            (self.morale, &mut country.morale),
+            (self.tech_level, &mut country.tech_level),
+            (self.resources, &mut country.resources),
+            (self.recent_losses, &mut country.recent_losses),
+        ];
+        for (value, stat) in fields {
+            if let Some(value) = value {
+                *stat = value;
+            }
+        }
+        if let Some(territory) = self.territory {
+            country.territory = territory;
+        }
+    }
+    
+    /// Name of the first stat that isn't a finite number
+    pub fn first_non_finite(&self) -> Option<&'static str> {
+        [
+            ("m_eff", self.m_eff),
+            ("gdp", self.gdp),
+            ("growth", self.growth),
+            ("prestige", self.prestige),
+            ("morale", self.morale),
+            ("tech_level", self.tech_level),
+            ("resources", self.resources),
+            ("recent_losses", self.recent_losses),
+        ]
+        .into_iter()
+        .find(|(_, value)| value.is_some_and(|value| !value.is_finite()))
+        .map(|(name, _)| name)
+    }
 }
 
-/// Border tile for fortify/move actions
+/// Border tile for fortify/move actions; omitted values in JSON default to zero
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct BorderTile {
     pub id: u32,
     pub position_x: i32,
     pub position_y: i32,
+    #[serde(default)]
     pub threat_gradient: f32,    // |∇TI| for prioritization
+    #[serde(default)]
     pub fortification: f32,
+    #[serde(default)]
     pub garrison_strength: f32,
 }
 
@@ -258,12 +493,37 @@ mod tests {
         assert_eq!(country.edges[0].neighbor_id, 2);
     }
 
+    #[test]
+    fn test_remove_edge() {
+        let mut country = Country::new(1);
+        country.add_edge(CountryEdge::new(2));
+        country.add_edge(CountryEdge::new(3));
+        
+        assert!(country.remove_edge(2));
+        assert!(!country.remove_edge(2));
+        assert_eq!(country.edges.len(), 1);
+        assert_eq!(country.edges[0].neighbor_id, 3);
+    }
+
+    #[test]
+    fn test_country_stats_partial_update() {
+        let mut country = Country::new(1);
+        let stats: CountryStats = serde_json::from_str(r#"{"m_eff": 250.0, "resources": 40.0}"#).unwrap();
+        stats.apply(&mut country);
+        
+        assert_eq!(country.m_eff, 250.0);
+        assert_eq!(country.resources, 40.0);
+        assert_eq!(country.gdp, 100.0);  // Untouched
+        
+        assert!(serde_json::from_str::<CountryStats>(r#"{"military": 1.0}"#).is_err());
+    }
+
     #[test]
     fn test_adaptive_weights_update() {
         let _country = Country::new(1);
         let mut weights = AdaptiveWeights::new();
         
-        weights.update(200.0, 50.0, 20.0, 0, 0.0);
+        weights.update(200.0, 50.0, 20.0, 0, 0.0, &Personality::new());
         
         // Should increase alpha (need resources)
         assert!(weights.alpha >= 8);
@@ -277,6 +537,22 @@ mod tests {
         assert!(weights.gamma >= 2 && weights.gamma <= 16);
     }
 
+    #[test]
+    fn test_neutral_personality_keeps_baseline_weights() {
+        let mut neutral = AdaptiveWeights::new();
+        neutral.update(500.0, 1.0, 5.0, 1, 10.0, &Personality::new());
+        assert_eq!((neutral.kappa, neutral.rho), (8, 4));
+        
+        let mut expansionist = AdaptiveWeights::new();
+        let personality = Personality { expansionism: 1.0, loyalty: 0.0, ..Personality::new() };
+        expansionist.update(500.0, 1.0, 5.0, 1, 10.0, &personality);
+        assert!(expansionist.alpha > neutral.alpha);
+        assert!(expansionist.delta <= neutral.delta);
+        
+        assert!(Personality { aggression: -0.1, ..Personality::new() }.validate().is_err());
+        assert!(Personality { risk_aversion: f32::NAN, ..Personality::new() }.validate().is_err());
+    }
+
     #[test]
     fn test_marginal_values_update() {
         let mut marginal_values = MarginalValues::new();
@@ -0,0 +1,145 @@
+/// Q16.16 fixed-point numbers for platform-independent scoring
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Signed Q16.16 fixed-point value: an `i32` counting 1/65536ths.
+/// Arithmetic saturates instead of wrapping, so out-of-range scores clamp
+/// at roughly ±32768 the same way on every platform
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Fixed(pub i32);
+
+impl Fixed {
+    pub const FRAC_BITS: u32 = 16;
+    pub const ONE: Fixed = Fixed(1 << Self::FRAC_BITS);
+    pub const ZERO: Fixed = Fixed(0);
+    pub const MAX: Fixed = Fixed(i32::MAX);
+    pub const MIN: Fixed = Fixed(i32::MIN);
+
+    pub const fn from_int(value: i32) -> Self {
+        Fixed(value.saturating_mul(1 << Self::FRAC_BITS))
+    }
+
+    /// Nearest fixed-point value, saturating out of range; NaN becomes zero
+    pub fn from_f32(value: f32) -> Self {
+        Self::from_f64(value as f64)
+    }
+
+    /// Nearest fixed-point value, saturating out of range; NaN becomes zero
+    pub fn from_f64(value: f64) -> Self {
+        // `as` saturates and maps NaN to 0, so this is defined for every input
+        Fixed((value * Self::ONE.0 as f64).round() as i32)
+    }
+
+    pub fn to_f32(self) -> f32 {
+        self.0 as f32 / Self::ONE.0 as f32
+    }
+
+    pub fn raw(self) -> i32 {
+        self.0
+    }
+
+    /// Largest integer not above this value
+    pub fn floor(self) -> i32 {
+        self.0 >> Self::FRAC_BITS
+    }
+
+    /// Fractional part, in [0, 1)
+    pub fn fract(self) -> Fixed {
+        Fixed(self.0 & (Self::ONE.0 - 1))
+    }
+
+    /// Multiply by an integer, such as an adaptive weight
+    pub fn mul_int(self, factor: i32) -> Self {
+        Fixed(self.0.saturating_mul(factor))
+    }
+
+    /// Quotient rounded toward zero; dividing by zero saturates toward the sign of `self`
+    pub fn saturating_div(self, divisor: Fixed) -> Self {
+        if divisor.0 == 0 {
+            return if self.0 < 0 { Self::MIN } else { Self::MAX };
+        }
+        Self::saturate(((self.0 as i64) << Self::FRAC_BITS) / divisor.0 as i64)
+    }
+
+    pub fn clamp(self, min: Fixed, max: Fixed) -> Self {
+        Fixed(self.0.clamp(min.0, max.0))
+    }
+
+    /// Linear interpolation between `a` and `b` by `t` in [0, 1]
+    pub fn lerp(a: Fixed, b: Fixed, t: Fixed) -> Self {
+        a + (b - a) * t
+    }
+
+    fn saturate(value: i64) -> Self {
+        Fixed(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    /// Product rounded to nearest, ties away from zero
+    fn mul(self, rhs: Fixed) -> Fixed {
+        let product = self.0 as i64 * rhs.0 as i64;
+        let half = 1i64 << (Self::FRAC_BITS - 1);
+        let rounded = if product >= 0 { product + half } else { product - half };
+        Self::saturate(rounded / (1i64 << Self::FRAC_BITS))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_round_to_nearest() {
+        assert_eq!(Fixed::from_f32(1.0), Fixed::ONE);
+        assert_eq!(Fixed::from_f32(-2.5).to_f32(), -2.5);
+        assert_eq!(Fixed::from_f32(1.0 / 3.0).raw(), 21845);
+        assert_eq!(Fixed::from_f32(f32::NAN), Fixed::ZERO);
+        assert_eq!(Fixed::from_f32(1e9), Fixed::MAX);
+        assert_eq!(Fixed::from_int(-3).floor(), -3);
+        assert_eq!(Fixed::from_f32(2.75).fract(), Fixed::from_f32(0.75));
+        assert_eq!(Fixed::from_f32(-0.25).floor(), -1);
+    }
+
+    #[test]
+    fn test_arithmetic_saturates() {
+        let a = Fixed::from_f32(1.5);
+        let b = Fixed::from_f32(-0.25);
+        assert_eq!(a + b, Fixed::from_f32(1.25));
+        assert_eq!(a * b, Fixed::from_f32(-0.375));
+        assert_eq!(a.saturating_div(b), Fixed::from_int(-6));
+        assert_eq!(a.mul_int(4), Fixed::from_int(6));
+        assert_eq!(Fixed::MAX + Fixed::ONE, Fixed::MAX);
+        assert_eq!(Fixed::from_int(30000) * Fixed::from_int(30000), Fixed::MAX);
+        assert_eq!(-Fixed::MIN, Fixed::MAX);
+        assert_eq!(b.saturating_div(Fixed::ZERO), Fixed::MIN);
+        assert_eq!(Fixed::lerp(Fixed::ZERO, Fixed::from_int(4), Fixed::from_f32(0.25)), Fixed::ONE);
+    }
+}
@@ -0,0 +1,158 @@
+/// Lookahead for attack candidates: score the world an attack leads to (§3.1, §5)
+use super::actions::*;
+use super::luts::*;
+use super::resolution::resolve_action;
+use super::scoring::*;
+use super::selection::argmax;
+use super::world::WorldState;
+
+/// Deepest lookahead supported: our attack, then the defender's reply
+pub const MAX_LOOKAHEAD_DEPTH: usize = 2;
+
+/// Adjust each shortlisted attack's components by how much better (or worse)
+/// the country's best follow-up action is after the attack's expected outcome
+/// than after passing, discounted by ply. At depth 2 the defender answers
+/// with its own best action before the follow-up is scored
+pub fn apply_attack_lookahead(
+    country_id: u32,
+    shortlist: &[Action],
+    components: &mut [ScoreComponents],
+    world: &WorldState,
+    luts: &LookupTables,
+    config: &PruningConfig,
+) {
+    let depth = config.lookahead_depth.min(MAX_LOOKAHEAD_DEPTH);
+    if depth == 0 {
+        return;
+    }
+    let discount = luts.discount.get(depth);
+
+    for (action, comp) in shortlist.iter().zip(components.iter_mut()) {
+        let Action::Attack { target_id } = *action else {
+            continue;
+        };
+        let reply_from = (depth >= 2).then_some(target_id);
+        let after_attack = follow_up(world, country_id, Some(action), reply_from, luts, config);
+        let after_pass = follow_up(world, country_id, None, reply_from, luts, config);
+        if let (Some(after_attack), Some(after_pass)) = (after_attack, after_pass) {
+            add_discounted_difference(comp, &after_attack, &after_pass, discount);
+        }
+    }
+}
+
+/// Components of the country's best action once `action` (if any) and the
+/// defender's reply (if any) have been resolved on a copy of the world
+fn follow_up(
+    world: &WorldState,
+    country_id: u32,
+    action: Option<&Action>,
+    reply_from: Option<u32>,
+    luts: &LookupTables,
+    config: &PruningConfig,
+) -> Option<ScoreComponents> {
+    let mut next = world.clone();
+    let mut deltas = Vec::new();
+    if let Some(action) = action {
+        resolve_action(&mut next, country_id, action, luts, &mut deltas);
+    }
+    if let Some(defender_id) = reply_from {
+        if let Some((reply, _)) = best_action(&next, defender_id, luts, config) {
+            resolve_action(&mut next, defender_id, &reply, luts, &mut deltas);
+        }
+    }
+    next.update_threat_indices(luts);
+    next.update_weights();
+    best_action(&next, country_id, luts, config).map(|(_, components)| components)
+}
+
+/// Argmax action over a country's shortlist, with its components
+fn best_action(
+    world: &WorldState,
+    country_id: u32,
+    luts: &LookupTables,
+    config: &PruningConfig,
+) -> Option<(Action, ScoreComponents)> {
+    let country = world.get_country(country_id)?;
+    let shortlist = generate_shortlist(country_id, country, world, config);
+    let batch = score_actions_batch(country, &shortlist, world, luts);
+    let best = argmax(&shortlist, &batch.final_scores)?;
+    Some((shortlist[best].clone(), batch.components[best].clone()))
+}
+
+fn add_discounted_difference(
+    comp: &mut ScoreComponents,
+    after: &ScoreComponents,
+    baseline: &ScoreComponents,
+    discount: f32,
+) {
+    comp.delta_res = (comp.delta_res + discount * (after.delta_res - baseline.delta_res)).clamp(-32.0, 32.0);
+    comp.delta_sec = (comp.delta_sec + discount * (after.delta_sec - baseline.delta_sec)).clamp(-32.0, 32.0);
+    comp.delta_growth = (comp.delta_growth + discount * (after.delta_growth - baseline.delta_growth)).clamp(-32.0, 32.0);
+    comp.delta_pos = (comp.delta_pos + discount * (after.delta_pos - baseline.delta_pos)).clamp(-32.0, 32.0);
+    comp.cost = (comp.cost + discount * (after.cost - baseline.cost)).clamp(0.0, 16.0);
+    comp.risk = (comp.risk + discount * (after.risk - baseline.risk)).clamp(0.0, 16.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decision_scoring::country::*;
+
+    fn world_with_rival(rival_m_eff: f32) -> WorldState {
+        let mut world = WorldState::new();
+        let mut attacker = Country::new(1);
+        let mut edge = CountryEdge::new(2);
+        edge.hostility = 0.8;
+        attacker.add_edge(edge);
+        attacker.intel.insert(2, 1.0);
+        let mut rival = Country::new(2);
+        rival.m_eff = rival_m_eff;
+        rival.add_edge(CountryEdge::new(1));
+        rival.intel.insert(1, 1.0);
+        world.add_country(attacker);
+        world.add_country(rival);
+        world
+    }
+
+    #[test]
+    fn test_lookahead_only_touches_attacks() {
+        let world = world_with_rival(100.0);
+        let luts = LookupTables::new();
+        let config = PruningConfig { lookahead_depth: 2, ..PruningConfig::default() };
+        let shortlist = vec![Action::Pass, Action::Attack { target_id: 2 }];
+        let country = world.get_country(1).unwrap();
+        let original = score_actions_batch(country, &shortlist, &world, &luts);
+
+        let mut components = original.components.clone();
+        apply_attack_lookahead(1, &shortlist, &mut components, &world, &luts, &config);
+        assert_eq!(components[0].delta_res, original.components[0].delta_res);
+        let changed = |a: &ScoreComponents, b: &ScoreComponents| {
+            a.delta_res != b.delta_res || a.delta_sec != b.delta_sec || a.delta_growth != b.delta_growth
+                || a.delta_pos != b.delta_pos || a.cost != b.cost || a.risk != b.risk
+        };
+        assert!(changed(&components[1], &original.components[1]));
+
+        let mut untouched = original.components.clone();
+        apply_attack_lookahead(1, &shortlist, &mut untouched, &world, &luts, &PruningConfig::default());
+        assert!(!changed(&untouched[1], &original.components[1]));
+    }
+
+    #[test]
+    fn test_lookahead_is_deterministic_and_bounded() {
+        let world = world_with_rival(60.0);
+        let luts = LookupTables::new();
+        let shortlist = vec![Action::Attack { target_id: 2 }];
+        let country = world.get_country(1).unwrap();
+        for depth in 1..=3 {
+            let config = PruningConfig { lookahead_depth: depth, ..PruningConfig::default() };
+            let run = || {
+                let mut components = score_actions_batch(country, &shortlist, &world, &luts).components;
+                apply_attack_lookahead(1, &shortlist, &mut components, &world, &luts, &config);
+                components[0].final_score(&country.weights)
+            };
+            let score = run();
+            assert_eq!(score, run());
+            assert!(score.is_finite());
+        }
+    }
+}
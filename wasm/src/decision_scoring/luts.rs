@@ -1,12 +1,29 @@
 /// Lookup Tables (LUTs) for efficient computation
-/// All scoring uses fixed, precomputed LUTs and integer/fixed-point arithmetic
-
+/// All scoring uses fixed, precomputed LUTs; each table also has a Q16.16 copy,
+/// built with f64 math, which every lookup goes through with the `fixed-point` feature
 use serde::{Deserialize, Serialize};
 
+use super::fixed::Fixed;
+
+/// Interpolate a table sampled evenly over [min, max] in pure integer arithmetic
+fn interpolate_fixed(table: &[Fixed], min: Fixed, max: Fixed, x: Fixed) -> Fixed {
+    let x = x.clamp(min, max);
+    let intervals = (table.len() - 1) as i64;
+    let span = (max - min).raw() as i64;
+    let pos = Fixed(((((x - min).raw() as i64 * intervals) << Fixed::FRAC_BITS) / span) as i32);
+    let idx = pos.floor() as usize;
+    if idx >= table.len() - 1 {
+        table[table.len() - 1]
+    } else {
+        Fixed::lerp(table[idx], table[idx + 1], pos.fract())
+    }
+}
+
 /// Sigmoid lookup table for logistic function over bounded range [-4, +4]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SigmoidLUT {
     table: Vec<f32>,
+    fixed_table: Vec<Fixed>,
     min_x: f32,
     max_x: f32,
     step: f32,
@@ -17,16 +34,21 @@ impl SigmoidLUT {
     pub fn new(min_x: f32, max_x: f32, steps: usize) -> Self {
         let step = (max_x - min_x) / (steps - 1) as f32;
         let mut table = Vec::with_capacity(steps);
+        let mut fixed_table = Vec::with_capacity(steps);
         
         for i in 0..steps {
             let x = min_x + i as f32 * step;
             // Sigmoid: σ(x) = 1 / (1 + e^(-x))
             let value = 1.0 / (1.0 + (-x).exp());
             table.push(value);
+            
+            let x = min_x as f64 + i as f64 * (max_x as f64 - min_x as f64) / (steps - 1) as f64;
+            fixed_table.push(Fixed::from_f64(1.0 / (1.0 + (-x).exp())));
         }
         
         Self {
             table,
+            fixed_table,
             min_x,
             max_x,
             step,
@@ -35,6 +57,10 @@ impl SigmoidLUT {
     
     /// Lookup sigmoid value with linear interpolation
     pub fn lookup(&self, x: f32) -> f32 {
+        if cfg!(feature = "fixed-point") {
+            return self.lookup_fixed(Fixed::from_f32(x)).to_f32();
+        }
+        
         // Clamp to range
         let x_clamped = x.clamp(self.min_x, self.max_x);
         
@@ -50,6 +76,11 @@ impl SigmoidLUT {
             self.table[idx] * (1.0 - frac) + self.table[idx + 1] * frac
         }
     }
+    
+    /// Lookup sigmoid value in Q16.16 with integer interpolation
+    pub fn lookup_fixed(&self, x: Fixed) -> Fixed {
+        interpolate_fixed(&self.fixed_table, Fixed::from_f32(self.min_x), Fixed::from_f32(self.max_x), x)
+    }
 }
 
 impl Default for SigmoidLUT {
@@ -63,6 +94,7 @@ impl Default for SigmoidLUT {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogRatioLUT {
     table: Vec<f32>,
+    fixed_table: Vec<Fixed>,
     min_ratio: f32,
     max_ratio: f32,
     step: f32,
@@ -73,6 +105,7 @@ impl LogRatioLUT {
     pub fn new(min_ratio: f32, max_ratio: f32, steps: usize) -> Self {
         let step = (max_ratio - min_ratio) / (steps - 1) as f32;
         let mut table = Vec::with_capacity(steps);
+        let mut fixed_table = Vec::with_capacity(steps);
         
         for i in 0..steps {
             let ratio = min_ratio + i as f32 * step;
@@ -83,10 +116,14 @@ impl LogRatioLUT {
                 f32::NEG_INFINITY
             };
             table.push(value);
+            
+            let ratio = min_ratio as f64 + i as f64 * (max_ratio as f64 - min_ratio as f64) / (steps - 1) as f64;
+            fixed_table.push(if ratio > 0.0 { Fixed::from_f64(ratio.ln()) } else { Fixed::MIN });
         }
         
         Self {
             table,
+            fixed_table,
             min_ratio,
             max_ratio,
             step,
@@ -95,6 +132,10 @@ impl LogRatioLUT {
     
     /// Lookup log ratio with linear interpolation
     pub fn lookup(&self, ratio: f32) -> f32 {
+        if cfg!(feature = "fixed-point") {
+            return self.lookup_fixed(Fixed::from_f32(ratio)).to_f32();
+        }
+        
         // Clamp to range
         let ratio_clamped = ratio.clamp(self.min_ratio, self.max_ratio);
         
@@ -110,6 +151,11 @@ impl LogRatioLUT {
             self.table[idx] * (1.0 - frac) + self.table[idx + 1] * frac
         }
     }
+    
+    /// Lookup log ratio in Q16.16 with integer interpolation
+    pub fn lookup_fixed(&self, ratio: Fixed) -> Fixed {
+        interpolate_fixed(&self.fixed_table, Fixed::from_f32(self.min_ratio), Fixed::from_f32(self.max_ratio), ratio)
+    }
 }
 
 impl Default for LogRatioLUT {
@@ -123,6 +169,7 @@ impl Default for LogRatioLUT {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscountLUT {
     factors: Vec<f32>,
+    fixed_factors: Vec<Fixed>,
     discount_rate: f32,
 }
 
@@ -130,15 +177,23 @@ impl DiscountLUT {
     /// Create a new discount LUT with specified rate and horizon
     pub fn new(discount_rate: f32, horizon: usize) -> Self {
         let mut factors = Vec::with_capacity(horizon);
+        let mut fixed_factors = Vec::with_capacity(horizon);
         
         for h in 1..=horizon {
             // d^h where d is discount factor (e.g., 0.95)
-            let factor = discount_rate.powi(h as i32);
+            let fixed_factor = Fixed::from_f64((discount_rate as f64).powi(h as i32));
+            let factor = if cfg!(feature = "fixed-point") {
+                fixed_factor.to_f32()
+            } else {
+                discount_rate.powi(h as i32)
+            };
             factors.push(factor);
+            fixed_factors.push(fixed_factor);
         }
         
         Self {
             factors,
+            fixed_factors,
             discount_rate,
         }
     }
@@ -152,6 +207,15 @@ impl DiscountLUT {
         }
     }
     
+    /// Get discount factor for given horizon (1-indexed) in Q16.16
+    pub fn get_fixed(&self, horizon: usize) -> Fixed {
+        if horizon == 0 || horizon > self.fixed_factors.len() {
+            Fixed::ZERO
+        } else {
+            self.fixed_factors[horizon - 1]
+        }
+    }
+    
     /// Get all discount factors
     pub fn factors(&self) -> &[f32] {
         &self.factors
@@ -169,6 +233,7 @@ impl Default for DiscountLUT {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DistanceKernelLUT {
     kernels: Vec<f32>,
+    fixed_kernels: Vec<Fixed>,
     max_distance: usize,
 }
 
@@ -176,15 +241,23 @@ impl DistanceKernelLUT {
     /// Create a new distance kernel LUT with exponential decay
     pub fn new(max_distance: usize, decay_rate: f32) -> Self {
         let mut kernels = Vec::with_capacity(max_distance + 1);
+        let mut fixed_kernels = Vec::with_capacity(max_distance + 1);
         
         for d in 0..=max_distance {
             // K(d) = exp(-decay_rate * d), monotone decreasing
-            let kernel = (-decay_rate * d as f32).exp();
+            let fixed_kernel = Fixed::from_f64((-(decay_rate as f64) * d as f64).exp());
+            let kernel = if cfg!(feature = "fixed-point") {
+                fixed_kernel.to_f32()
+            } else {
+                (-decay_rate * d as f32).exp()
+            };
             kernels.push(kernel);
+            fixed_kernels.push(fixed_kernel);
         }
         
         Self {
             kernels,
+            fixed_kernels,
             max_distance,
         }
     }
@@ -197,6 +270,15 @@ impl DistanceKernelLUT {
             self.kernels[distance]
         }
     }
+    
+    /// Get kernel value for distance bucket in Q16.16
+    pub fn get_fixed(&self, distance: usize) -> Fixed {
+        if distance > self.max_distance {
+            Fixed::ZERO
+        } else {
+            self.fixed_kernels[distance]
+        }
+    }
 }
 
 impl Default for DistanceKernelLUT {
@@ -284,6 +366,24 @@ mod tests {
         assert_eq!(lut.get(9), 0.0);
     }
 
+    #[test]
+    fn test_fixed_lookups_track_float_lookups() {
+        let luts = LookupTables::new();
+        for i in -50..=50 {
+            let x = i as f32 * 0.1;
+            let fixed = luts.sigmoid.lookup_fixed(Fixed::from_f32(x)).to_f32();
+            assert!((fixed - luts.sigmoid.lookup(x)).abs() < 1e-3, "sigmoid({})", x);
+        }
+        for i in 0..=40 {
+            let ratio = 0.1 + i as f32 * 0.1;
+            let fixed = luts.log_ratio.lookup_fixed(Fixed::from_f32(ratio)).to_f32();
+            assert!((fixed - luts.log_ratio.lookup(ratio)).abs() < 1e-3, "ln({})", ratio);
+        }
+        assert!((luts.discount.get_fixed(3).to_f32() - luts.discount.get(3)).abs() < 1e-4);
+        assert_eq!(luts.distance_kernel.get_fixed(0), Fixed::ONE);
+        assert_eq!(luts.distance_kernel.get_fixed(99), Fixed::ZERO);
+    }
+
     #[test]
     fn test_distance_kernel_lut() {
         let lut = DistanceKernelLUT::new(10, 0.2);
@@ -1,13 +1,25 @@
 // AI Decision Scoring System - Main module
 
+pub mod fixed;
 pub mod luts;
+pub mod lookahead;
 pub mod country;
 pub mod actions;
 pub mod scoring;
+pub mod resolution;
+pub mod scenario;
+pub mod selection;
+pub mod tech_tree;
 pub mod world;
 
+pub use fixed::*;
 pub use luts::*;
+pub use lookahead::*;
 pub use country::*;
 pub use actions::*;
 pub use scoring::*;
+pub use resolution::*;
+pub use scenario::*;
+pub use selection::*;
+pub use tech_tree::*;
 pub use world::*;
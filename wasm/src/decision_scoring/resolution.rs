@@ -0,0 +1,620 @@
+/// Action resolution: applying chosen actions to the world state (§6)
+use serde::{Deserialize, Serialize};
+
+use super::actions::*;
+use super::country::*;
+use super::luts::*;
+use super::scoring::{attack_win_probability, betrayal_prestige_penalty};
+use super::world::WorldState;
+
+// Attack outcomes scale with the attacker's win probability, so a likely
+// victory takes most of the spoils and a likely defeat costs the attacker most
+const CAPTURE_SHARE: f32 = 0.2;        // Share of defender territory taken on a certain win
+const PLUNDER_SHARE: f32 = 0.5;        // Share of defender resources taken on a certain win
+const CASUALTY_RATE: f32 = 0.1;        // Share of m_eff lost in an even fight
+
+const MILITARY_INVEST_GAIN: f32 = 10.0;
+const INFRASTRUCTURE_GROWTH_GAIN: f32 = 0.5;
+const TECHNOLOGY_INVEST_GAIN: f32 = 0.05;
+
+const ALLY_RELATIONS_GAIN: f32 = 20.0;
+const BETRAYAL_RELATIONS_DROP: f32 = 40.0;
+const PACT_RELATIONS_GAIN: f32 = 10.0;
+const PACT_HOSTILITY_DROP: f32 = 0.2;
+const TRADE_RELATIONS_GAIN: f32 = 5.0;
+const TRADE_INCOME_RATE: f32 = 0.05;      // Per-tick income as a share of the poorer partner's GDP
+const TRADE_GDP_RATE: f32 = 0.02;         // GDP uplift while trading, as a share of the poorer partner's GDP
+const WAR_RELATIONS_DROP: f32 = 30.0;
+const WAR_HOSTILITY_RISE: f32 = 0.3;
+const PEACE_RELATIONS_GAIN: f32 = 10.0;
+
+const SPY_COST: f32 = 10.0;
+const SPY_INTEL_GAIN: f32 = 0.3;
+const SABOTAGE_COST: f32 = 20.0;
+const SABOTAGE_DAMAGE: f32 = 0.05;        // Share of the target's m_eff destroyed
+const SABOTAGE_RELATIONS_DROP: f32 = 20.0; // Scaled by how likely the saboteur is traced (1 - intel)
+
+const FORTIFY_GAIN: f32 = 0.5;
+const MOVE_SHARE: f32 = 0.1;              // Share of m_eff moved into a tile's garrison
+
+/// Core stat changed by investment or research
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatKind {
+    MilitaryStrength,
+    Gdp,
+    Growth,
+    TechLevel,
+    Prestige,
+}
+
+impl StatKind {
+    pub fn value(self, country: &Country) -> f32 {
+        match self {
+            StatKind::MilitaryStrength => country.m_eff,
+            StatKind::Gdp => country.gdp,
+            StatKind::Growth => country.growth,
+            StatKind::TechLevel => country.tech_level,
+            StatKind::Prestige => country.prestige,
+        }
+    }
+}
+
+/// One change to the world caused by resolving actions, for replay and UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WorldDelta {
+    /// Tiles captured by `to` from `from`
+    TerritoryTransferred { from: u32, to: u32, amount: u32 },
+    /// Resources moved between countries, e.g. plunder
+    ResourcesTransferred { from: u32, to: u32, amount: f32 },
+    /// Resources gained (positive) or spent (negative)
+    ResourcesChanged { country_id: u32, amount: f32 },
+    /// Military strength lost in combat
+    Casualties { country_id: u32, amount: f32 },
+    StatChanged { country_id: u32, stat: StatKind, amount: f32 },
+    /// Tech tree node `tech` researched
+    TechResearched { country_id: u32, tech: u32 },
+    AllianceFormed { country_a: u32, country_b: u32 },
+    /// `country_a` left the alliance
+    AllianceBroken { country_a: u32, country_b: u32 },
+    /// The alliance's term ran out
+    AllianceExpired { country_a: u32, country_b: u32 },
+    /// New intel level `country_id` holds on `target_id`
+    IntelGathered { country_id: u32, target_id: u32, intel: f32 },
+    /// Military strength `target_id` lost to sabotage
+    Sabotaged { country_id: u32, target_id: u32, damage: f32 },
+    WarDeclared { country_a: u32, country_b: u32 },
+    PeaceSigned { country_a: u32, country_b: u32 },
+    /// New relations and hostility on the `from` → `to` edge
+    RelationsChanged { from: u32, to: u32, relations: f32, hostility: f32 },
+    TradeRouteOpened { country_a: u32, country_b: u32, income: f32, gdp_bonus: f32 },
+    /// Trade stopped by a war between the partners, removing its GDP bonus
+    TradeRouteDisrupted { country_a: u32, country_b: u32 },
+    /// Trade resumed after peace, restoring its GDP bonus
+    TradeRouteRestored { country_a: u32, country_b: u32 },
+    /// Income paid to both partners of a trade route
+    TradeIncome { country_a: u32, country_b: u32, income: f32 },
+    Fortified { country_id: u32, tile_id: u32, fortification: f32 },
+    GarrisonMoved { country_id: u32, tile_id: u32, amount: f32 },
+}
+
+/// Suspend trade between countries at war, resume it at peace, then pay out
+/// every active route
+pub fn collect_trade_income(world: &mut WorldState, deltas: &mut Vec<WorldDelta>) {
+    for (country_a, country_b, disrupted) in world.update_trade_disruptions() {
+        deltas.push(if disrupted {
+            WorldDelta::TradeRouteDisrupted { country_a, country_b }
+        } else {
+            WorldDelta::TradeRouteRestored { country_a, country_b }
+        });
+    }
+
+    let routes = world.trade_routes().to_vec();
+    for route in routes.iter().filter(|route| !route.disrupted) {
+        for id in [route.country_a, route.country_b] {
+            if let Some(country) = world.get_country_mut(id) {
+                country.resources += route.income;
+            }
+        }
+        deltas.push(WorldDelta::TradeIncome {
+            country_a: route.country_a,
+            country_b: route.country_b,
+            income: route.income,
+        });
+    }
+}
+
+/// Apply `action` chosen by `country_id` and record what changed
+///
+/// Actions against missing countries, edges or tiles and actions the country
+/// can no longer afford resolve to nothing.
+pub fn resolve_action(
+    world: &mut WorldState,
+    country_id: u32,
+    action: &Action,
+    luts: &LookupTables,
+    deltas: &mut Vec<WorldDelta>,
+) {
+    match action {
+        Action::Attack { target_id } => resolve_attack(world, country_id, *target_id, luts, deltas),
+        Action::Invest { sector } => resolve_invest(world, country_id, *sector, deltas),
+        Action::Research { tech } => resolve_research(world, country_id, *tech, deltas),
+        Action::Ally { target_id } => {
+            if *target_id == country_id
+                || world.get_country(*target_id).is_none()
+                || world.at_war(country_id, *target_id)
+                || !world.add_alliance(country_id, *target_id)
+            {
+                return;
+            }
+            deltas.push(WorldDelta::AllianceFormed { country_a: country_id, country_b: *target_id });
+            shift_relations(world, country_id, *target_id, ALLY_RELATIONS_GAIN, 0.0, deltas);
+        }
+        Action::BreakAlliance { target_id } => {
+            if !world.remove_alliance(country_id, *target_id) {
+                return;
+            }
+            deltas.push(WorldDelta::AllianceBroken { country_a: country_id, country_b: *target_id });
+            if let Some(country) = world.get_country_mut(country_id) {
+                let amount = -betrayal_prestige_penalty(country);
+                apply_stat(country, StatKind::Prestige, amount);
+                deltas.push(WorldDelta::StatChanged { country_id, stat: StatKind::Prestige, amount });
+            }
+            shift_relations(world, country_id, *target_id, -BETRAYAL_RELATIONS_DROP, 0.0, deltas);
+        }
+        Action::Pact { target_id } => {
+            shift_relations(world, country_id, *target_id, PACT_RELATIONS_GAIN, PACT_HOSTILITY_DROP, deltas);
+        }
+        Action::Trade { target_id } => resolve_trade(world, country_id, *target_id, deltas),
+        Action::Spy { target_id } => {
+            if *target_id == country_id || world.get_country(*target_id).is_none() {
+                return;
+            }
+            let Some(country) = world.get_country_mut(country_id) else {
+                return;
+            };
+            if country.resources < SPY_COST {
+                return;
+            }
+            country.resources -= SPY_COST;
+            let intel = (country.intel_on(*target_id) + SPY_INTEL_GAIN).min(1.0);
+            country.intel.insert(*target_id, intel);
+            deltas.push(WorldDelta::ResourcesChanged { country_id, amount: -SPY_COST });
+            deltas.push(WorldDelta::IntelGathered { country_id, target_id: *target_id, intel });
+        }
+        Action::Sabotage { target_id } => resolve_sabotage(world, country_id, *target_id, deltas),
+        Action::DeclareWar { target_id } => {
+            if world.get_country(*target_id).is_some() {
+                start_war(world, country_id, *target_id, deltas);
+            }
+        }
+        Action::MakePeace { target_id } => {
+            if !world.make_peace(country_id, *target_id) {
+                return;
+            }
+            deltas.push(WorldDelta::PeaceSigned { country_a: country_id, country_b: *target_id });
+            shift_relations(world, country_id, *target_id, PEACE_RELATIONS_GAIN, 0.0, deltas);
+        }
+        Action::Fortify { tile_id } => {
+            let Some(tile) = world
+                .get_country_mut(country_id)
+                .and_then(|country| country.border_tiles.iter_mut().find(|t| t.id == *tile_id))
+            else {
+                return;
+            };
+            tile.fortification += FORTIFY_GAIN;
+            deltas.push(WorldDelta::Fortified {
+                country_id,
+                tile_id: *tile_id,
+                fortification: tile.fortification,
+            });
+        }
+        Action::Move { tile_id } => {
+            let Some(country) = world.get_country_mut(country_id) else {
+                return;
+            };
+            let amount = country.m_eff * MOVE_SHARE;
+            let Some(tile) = country.border_tiles.iter_mut().find(|t| t.id == *tile_id) else {
+                return;
+            };
+            tile.garrison_strength += amount;
+            deltas.push(WorldDelta::GarrisonMoved { country_id, tile_id: *tile_id, amount });
+        }
+        Action::Pass => {}
+    }
+}
+
+/// Resolve an attack by its expected outcome: spoils and the defender's
+/// losses grow with the win probability, the attacker's losses shrink with it
+fn resolve_attack(
+    world: &mut WorldState,
+    attacker_id: u32,
+    defender_id: u32,
+    luts: &LookupTables,
+    deltas: &mut Vec<WorldDelta>,
+) {
+    if attacker_id == defender_id {
+        return;
+    }
+    let (Some(attacker), Some(defender)) = (world.get_country(attacker_id), world.get_country(defender_id)) else {
+        return;
+    };
+    let Some(edge) = attacker.get_edge(defender_id) else {
+        return;
+    };
+    let p_win = attack_win_probability(attacker, defender, edge, luts);
+    let already_at_war = world.at_war(attacker_id, defender_id);
+
+    let territory = ((defender.territory as f32 * CAPTURE_SHARE * p_win).round() as u32).min(defender.territory);
+    let plunder = defender.resources.max(0.0) * PLUNDER_SHARE * p_win;
+    let attacker_casualties = (attacker.m_eff * CASUALTY_RATE * (1.5 - p_win)).min(attacker.m_eff);
+    let defender_casualties = (defender.m_eff * CASUALTY_RATE * (0.5 + p_win)).min(defender.m_eff);
+
+    if let Some(defender) = world.get_country_mut(defender_id) {
+        defender.territory -= territory;
+        defender.resources -= plunder;
+        defender.m_eff -= defender_casualties;
+        defender.recent_losses += defender_casualties;
+    }
+    if let Some(attacker) = world.get_country_mut(attacker_id) {
+        attacker.territory += territory;
+        attacker.resources += plunder;
+        attacker.m_eff -= attacker_casualties;
+        attacker.recent_losses += attacker_casualties;
+    }
+
+    // Attacking a country we aren't at war with starts one
+    if !already_at_war {
+        start_war(world, attacker_id, defender_id, deltas);
+    }
+    if territory > 0 {
+        deltas.push(WorldDelta::TerritoryTransferred { from: defender_id, to: attacker_id, amount: territory });
+    }
+    if plunder > 0.0 {
+        deltas.push(WorldDelta::ResourcesTransferred { from: defender_id, to: attacker_id, amount: plunder });
+    }
+    deltas.push(WorldDelta::Casualties { country_id: attacker_id, amount: attacker_casualties });
+    deltas.push(WorldDelta::Casualties { country_id: defender_id, amount: defender_casualties });
+}
+
+/// Destroy part of the target's military; the less we know about the target,
+/// the clumsier the operation and the more its relations with us suffer
+fn resolve_sabotage(world: &mut WorldState, country_id: u32, target_id: u32, deltas: &mut Vec<WorldDelta>) {
+    if country_id == target_id || world.get_country(target_id).is_none() {
+        return;
+    }
+    let Some(country) = world.get_country_mut(country_id) else {
+        return;
+    };
+    if country.resources < SABOTAGE_COST {
+        return;
+    }
+    country.resources -= SABOTAGE_COST;
+    let intel = country.intel_on(target_id);
+    deltas.push(WorldDelta::ResourcesChanged { country_id, amount: -SABOTAGE_COST });
+
+    if let Some(target) = world.get_country_mut(target_id) {
+        let damage = target.m_eff * SABOTAGE_DAMAGE;
+        target.m_eff -= damage;
+        deltas.push(WorldDelta::Sabotaged { country_id, target_id, damage });
+    }
+    if let Some(edge) = world.get_country_mut(target_id).and_then(|target| target.get_edge_mut(country_id)) {
+        edge.relations = (edge.relations - SABOTAGE_RELATIONS_DROP * (1.0 - intel)).clamp(-100.0, 100.0);
+        deltas.push(WorldDelta::RelationsChanged {
+            from: target_id,
+            to: country_id,
+            relations: edge.relations,
+            hostility: edge.hostility,
+        });
+    }
+}
+
+fn resolve_invest(world: &mut WorldState, country_id: u32, sector: InvestSector, deltas: &mut Vec<WorldDelta>) {
+    let Some(country) = world.get_country_mut(country_id) else {
+        return;
+    };
+    let cost = sector.base_cost();
+    if country.resources < cost {
+        return;
+    }
+    country.resources -= cost;
+    country.gdp += sector.gdp_boost();
+    deltas.push(WorldDelta::ResourcesChanged { country_id, amount: -cost });
+    deltas.push(WorldDelta::StatChanged { country_id, stat: StatKind::Gdp, amount: sector.gdp_boost() });
+
+    let (stat, amount) = match sector {
+        InvestSector::Military => (StatKind::MilitaryStrength, MILITARY_INVEST_GAIN),
+        InvestSector::Infrastructure => (StatKind::Growth, INFRASTRUCTURE_GROWTH_GAIN),
+        InvestSector::Technology => (StatKind::TechLevel, TECHNOLOGY_INVEST_GAIN),
+        InvestSector::Economy => return,
+    };
+    apply_stat(country, stat, amount);
+    deltas.push(WorldDelta::StatChanged { country_id, stat, amount });
+}
+
+fn resolve_research(world: &mut WorldState, country_id: u32, tech: u32, deltas: &mut Vec<WorldDelta>) {
+    let Some(country) = world.get_country(country_id) else {
+        return;
+    };
+    let tech_tree = world.tech_tree();
+    let Some(node) = tech_tree.get(tech).filter(|_| tech_tree.is_unlockable(country, tech)) else {
+        return;
+    };
+    if country.resources < node.cost {
+        return;
+    }
+    let cost = node.cost;
+    let modifiers = node.modifiers.clone();
+
+    let Some(country) = world.get_country_mut(country_id) else {
+        return;
+    };
+    country.resources -= cost;
+    country.researched.insert(tech);
+    deltas.push(WorldDelta::ResourcesChanged { country_id, amount: -cost });
+    deltas.push(WorldDelta::TechResearched { country_id, tech });
+    for modifier in &modifiers {
+        let amount = modifier.delta(country);
+        apply_stat(country, modifier.stat, amount);
+        deltas.push(WorldDelta::StatChanged { country_id, stat: modifier.stat, amount });
+    }
+}
+
+fn resolve_trade(world: &mut WorldState, country_id: u32, target_id: u32, deltas: &mut Vec<WorldDelta>) {
+    let (Some(country), Some(target)) = (world.get_country(country_id), world.get_country(target_id)) else {
+        return;
+    };
+    if country_id == target_id || country.get_edge(target_id).is_none() || world.at_war(country_id, target_id) {
+        return;
+    }
+    let smaller_gdp = country.gdp.min(target.gdp).max(0.0);
+    let income = smaller_gdp * TRADE_INCOME_RATE;
+    let gdp_bonus = smaller_gdp * TRADE_GDP_RATE;
+    if !world.add_trade_route(country_id, target_id, income, gdp_bonus) {
+        return;
+    }
+    deltas.push(WorldDelta::TradeRouteOpened { country_a: country_id, country_b: target_id, income, gdp_bonus });
+    shift_relations(world, country_id, target_id, TRADE_RELATIONS_GAIN, 0.0, deltas);
+}
+
+/// Declare war and sour relations, unless the two are allies or already at war
+fn start_war(world: &mut WorldState, a: u32, b: u32, deltas: &mut Vec<WorldDelta>) {
+    if !world.declare_war(a, b) {
+        return;
+    }
+    deltas.push(WorldDelta::WarDeclared { country_a: a, country_b: b });
+    shift_relations(world, a, b, -WAR_RELATIONS_DROP, -WAR_HOSTILITY_RISE, deltas);
+}
+
+/// Shift relations along both directions of the edge between `a` and `b`;
+/// negative amounts sour them
+fn shift_relations(
+    world: &mut WorldState,
+    a: u32,
+    b: u32,
+    relations_gain: f32,
+    hostility_drop: f32,
+    deltas: &mut Vec<WorldDelta>,
+) {
+    for (from, to) in [(a, b), (b, a)] {
+        let Some(edge) = world.get_country_mut(from).and_then(|country| country.get_edge_mut(to)) else {
+            continue;
+        };
+        edge.relations = (edge.relations + relations_gain).clamp(-100.0, 100.0);
+        edge.hostility = (edge.hostility - hostility_drop).clamp(0.0, 1.0);
+        deltas.push(WorldDelta::RelationsChanged {
+            from,
+            to,
+            relations: edge.relations,
+            hostility: edge.hostility,
+        });
+    }
+}
+
+fn apply_stat(country: &mut Country, stat: StatKind, amount: f32) {
+    match stat {
+        StatKind::MilitaryStrength => country.m_eff += amount,
+        StatKind::Gdp => country.gdp += amount,
+        StatKind::Growth => country.growth += amount,
+        StatKind::TechLevel => country.tech_level += amount,
+        StatKind::Prestige => country.prestige += amount,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_countries() -> WorldState {
+        let mut world = WorldState::new();
+        let mut attacker = Country::new(1);
+        attacker.add_edge(CountryEdge::new(2));
+        let mut defender = Country::new(2);
+        defender.add_edge(CountryEdge::new(1));
+        world.add_country(attacker);
+        world.add_country(defender);
+        world
+    }
+
+    #[test]
+    fn test_attack_transfers_territory_and_hurts_both_sides() {
+        let mut world = two_countries();
+        world.get_country_mut(1).unwrap().m_eff = 400.0;
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+
+        resolve_action(&mut world, 1, &Action::Attack { target_id: 2 }, &luts, &mut deltas);
+
+        let attacker = world.get_country(1).unwrap();
+        let defender = world.get_country(2).unwrap();
+        assert!(attacker.territory > 10);
+        assert_eq!(attacker.territory + defender.territory, 20);  // Territory is conserved
+        assert!(attacker.resources > 500.0);
+        assert!(defender.resources < 500.0);
+        assert!(attacker.m_eff < 400.0);
+        assert!(defender.m_eff < 100.0);
+        assert!(defender.recent_losses > 0.0);
+
+        assert!(deltas.iter().any(|d| matches!(d, WorldDelta::TerritoryTransferred { from: 2, to: 1, .. })));
+        assert_eq!(deltas.iter().filter(|d| matches!(d, WorldDelta::Casualties { .. })).count(), 2);
+    }
+
+    #[test]
+    fn test_unaffordable_actions_do_nothing() {
+        let mut world = two_countries();
+        world.get_country_mut(1).unwrap().resources = 5.0;
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+
+        resolve_action(&mut world, 1, &Action::Invest { sector: InvestSector::Military }, &luts, &mut deltas);
+        resolve_action(&mut world, 1, &Action::Research { tech: 0 }, &luts, &mut deltas);
+
+        assert!(deltas.is_empty());
+        assert_eq!(world.get_country(1).unwrap().m_eff, 100.0);
+    }
+
+    #[test]
+    fn test_trade_routes_pay_both_partners_every_tick() {
+        let mut world = two_countries();
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+
+        resolve_action(&mut world, 1, &Action::Trade { target_id: 2 }, &luts, &mut deltas);
+        resolve_action(&mut world, 2, &Action::Trade { target_id: 1 }, &luts, &mut deltas);
+        assert_eq!(world.trade_routes().len(), 1);  // The second agreement renews nothing
+        assert_eq!(deltas.iter().filter(|d| matches!(d, WorldDelta::TradeRouteOpened { .. })).count(), 1);
+
+        deltas.clear();
+        collect_trade_income(&mut world, &mut deltas);
+        collect_trade_income(&mut world, &mut deltas);
+        let income = world.trade_routes()[0].income;
+        assert_eq!(world.get_country(1).unwrap().resources, 500.0 + 2.0 * income);
+        assert_eq!(world.get_country(2).unwrap().resources, 500.0 + 2.0 * income);
+        assert_eq!(deltas.len(), 2);
+    }
+
+    #[test]
+    fn test_wars_disrupt_trade_until_peace() {
+        let mut world = two_countries();
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+        resolve_action(&mut world, 1, &Action::Trade { target_id: 2 }, &luts, &mut deltas);
+        let bonus = world.trade_routes()[0].gdp_bonus;
+        assert_eq!(world.get_country(2).unwrap().gdp, 100.0 + bonus);
+
+        world.declare_war(1, 2);
+        deltas.clear();
+        collect_trade_income(&mut world, &mut deltas);
+        assert_eq!(deltas, vec![WorldDelta::TradeRouteDisrupted { country_a: 1, country_b: 2 }]);
+        assert_eq!(world.get_country(1).unwrap().resources, 500.0);
+        assert_eq!(world.get_country(1).unwrap().gdp, 100.0);
+
+        world.make_peace(1, 2);
+        deltas.clear();
+        collect_trade_income(&mut world, &mut deltas);
+        assert_eq!(deltas[0], WorldDelta::TradeRouteRestored { country_a: 1, country_b: 2 });
+        assert!(matches!(deltas[1], WorldDelta::TradeIncome { .. }));
+        assert_eq!(world.get_country(1).unwrap().gdp, 100.0 + bonus);
+    }
+
+    #[test]
+    fn test_pacts_warm_relations_both_ways() {
+        let mut world = two_countries();
+        world.get_country_mut(1).unwrap().get_edge_mut(2).unwrap().hostility = 0.5;
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+
+        resolve_action(&mut world, 1, &Action::Pact { target_id: 2 }, &luts, &mut deltas);
+
+        let edge = world.get_country(1).unwrap().get_edge(2).unwrap();
+        assert_eq!(edge.relations, PACT_RELATIONS_GAIN);
+        assert!((edge.hostility - 0.3).abs() < 1e-6);
+        assert_eq!(world.get_country(2).unwrap().get_edge(1).unwrap().relations, PACT_RELATIONS_GAIN);
+        assert_eq!(deltas.len(), 2);
+    }
+
+    #[test]
+    fn test_war_and_peace() {
+        let mut world = two_countries();
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+
+        resolve_action(&mut world, 1, &Action::Attack { target_id: 2 }, &luts, &mut deltas);
+        assert!(world.at_war(1, 2));
+        assert!(deltas.contains(&WorldDelta::WarDeclared { country_a: 1, country_b: 2 }));
+        assert_eq!(world.get_country(2).unwrap().get_edge(1).unwrap().hostility, WAR_HOSTILITY_RISE);
+
+        // Enemies can't ally until they make peace
+        deltas.clear();
+        resolve_action(&mut world, 2, &Action::Ally { target_id: 1 }, &luts, &mut deltas);
+        assert!(deltas.is_empty());
+
+        resolve_action(&mut world, 2, &Action::MakePeace { target_id: 1 }, &luts, &mut deltas);
+        assert!(!world.at_war(1, 2));
+        assert_eq!(deltas[0], WorldDelta::PeaceSigned { country_a: 2, country_b: 1 });
+    }
+
+    #[test]
+    fn test_breaking_an_alliance_costs_prestige() {
+        let mut world = two_countries();
+        world.add_alliance(1, 2);
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+
+        resolve_action(&mut world, 1, &Action::BreakAlliance { target_id: 2 }, &luts, &mut deltas);
+
+        assert!(!world.are_allies(1, 2));
+        assert!(world.get_country(1).unwrap().prestige < 10.0);
+        assert_eq!(world.get_country(2).unwrap().get_edge(1).unwrap().relations, -BETRAYAL_RELATIONS_DROP);
+        assert_eq!(deltas[0], WorldDelta::AllianceBroken { country_a: 1, country_b: 2 });
+    }
+
+    #[test]
+    fn test_spying_raises_intel_and_sabotage_damages_the_target() {
+        let mut world = two_countries();
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+
+        resolve_action(&mut world, 1, &Action::Spy { target_id: 2 }, &luts, &mut deltas);
+        assert_eq!(world.get_country(1).unwrap().intel_on(2), DEFAULT_INTEL + SPY_INTEL_GAIN);
+        assert_eq!(world.get_country(1).unwrap().resources, 500.0 - SPY_COST);
+
+        resolve_action(&mut world, 1, &Action::Sabotage { target_id: 2 }, &luts, &mut deltas);
+        assert_eq!(world.get_country(2).unwrap().m_eff, 100.0 * (1.0 - SABOTAGE_DAMAGE));
+        assert!(world.get_country(2).unwrap().get_edge(1).unwrap().relations < 0.0);
+        assert!(deltas.contains(&WorldDelta::Sabotaged { country_id: 1, target_id: 2, damage: 5.0 }));
+    }
+
+    #[test]
+    fn test_research_unlocks_nodes_and_applies_their_modifiers() {
+        let mut world = two_countries();
+        let luts = LookupTables::new();
+        let mut deltas = Vec::new();
+        let (military_advancement, combined_arms) = (0, 4);
+
+        // Locked behind military_advancement and technological_breakthrough
+        resolve_action(&mut world, 1, &Action::Research { tech: combined_arms }, &luts, &mut deltas);
+        assert!(deltas.is_empty());
+
+        resolve_action(&mut world, 1, &Action::Research { tech: military_advancement }, &luts, &mut deltas);
+        let country = world.get_country(1).unwrap();
+        assert!(country.researched.contains(&military_advancement));
+        assert_eq!(country.resources, 470.0);
+        assert!((country.m_eff - 105.0).abs() < 1e-3);
+        assert!((country.tech_level - 1.1).abs() < 1e-6);
+        assert!(deltas.contains(&WorldDelta::TechResearched { country_id: 1, tech: military_advancement }));
+
+        // Each node is researched once
+        deltas.clear();
+        resolve_action(&mut world, 1, &Action::Research { tech: military_advancement }, &luts, &mut deltas);
+        assert!(deltas.is_empty());
+    }
+
+    #[test]
+    fn test_world_deltas_serialize_with_a_kind_tag() {
+        let delta = WorldDelta::Casualties { country_id: 3, amount: 1.5 };
+        let json = serde_json::to_string(&delta).unwrap();
+        assert_eq!(json, r#"{"kind":"casualties","country_id":3,"amount":1.5}"#);
+    }
+}
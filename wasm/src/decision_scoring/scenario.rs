@@ -0,0 +1,197 @@
+/// World scenarios: whole worlds described in JSON, for authoring test worlds and tuned setups
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use super::country::*;
+use super::world::WorldState;
+
+/// One country in a scenario; stats omitted from `stats` keep `Country::new`'s defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioCountry {
+    pub id: u32,
+    #[serde(default)]
+    pub stats: CountryStats,
+    #[serde(default)]
+    pub personality: Personality,
+    #[serde(default)]
+    pub edges: Vec<CountryEdge>,
+    #[serde(default)]
+    pub border_tiles: Vec<BorderTile>,
+    #[serde(default)]
+    pub intel: BTreeMap<u32, f32>,  // Intel on other countries; unlisted ones get DEFAULT_INTEL
+}
+
+/// A complete world: countries with their edges and border tiles, plus alliances and wars
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub countries: Vec<ScenarioCountry>,
+    #[serde(default)]
+    pub alliances: Vec<(u32, u32)>,
+    #[serde(default)]
+    pub wars: Vec<(u32, u32)>,
+}
+
+impl Scenario {
+    /// Describe `world`'s countries, alliances and wars, in ascending id order
+    pub fn from_world(world: &WorldState) -> Self {
+        let mut ids: Vec<u32> = world.countries().keys().copied().collect();
+        ids.sort();
+        let countries = ids
+            .into_iter()
+            .filter_map(|id| world.get_country(id))
+            .map(|country| ScenarioCountry {
+                id: country.id,
+                stats: CountryStats::of(country),
+                personality: country.personality,
+                edges: country.edges.clone(),
+                border_tiles: country.border_tiles.clone(),
+                intel: country.intel.iter().map(|(&id, &intel)| (id, intel)).collect(),
+            })
+            .collect();
+        Self { countries, alliances: world.alliances(), wars: world.wars() }
+    }
+
+    /// Build a fresh world at tick 0, rejecting duplicate or unknown ids,
+    /// self-references, non-finite values and wars between allies
+    pub fn to_world(&self) -> Result<WorldState, String> {
+        let mut world = WorldState::new();
+        let ids: HashSet<u32> = self.countries.iter().map(|country| country.id).collect();
+        if ids.len() != self.countries.len() {
+            return Err("Scenario lists a country id more than once".to_string());
+        }
+
+        for entry in &self.countries {
+            let id = entry.id;
+            if let Some(name) = entry.stats.first_non_finite() {
+                return Err(format!("Country {} stat {} must be finite", id, name));
+            }
+            let mut country = Country::new(id);
+            entry.stats.apply(&mut country);
+            entry.personality.validate().map_err(|e| format!("Country {}: {}", id, e))?;
+            country.personality = entry.personality;
+
+            let mut neighbors = HashSet::new();
+            for edge in &entry.edges {
+                let neighbor = edge.neighbor_id;
+                if neighbor == id || !ids.contains(&neighbor) {
+                    return Err(format!("Country {} has an edge to invalid country {}", id, neighbor));
+                }
+                if !neighbors.insert(neighbor) {
+                    return Err(format!("Country {} has more than one edge to {}", id, neighbor));
+                }
+                if let Some(name) = edge.first_non_finite() {
+                    return Err(format!("Edge {} -> {} field {} must be finite", id, neighbor, name));
+                }
+                country.add_edge(edge.clone());
+            }
+
+            let mut tile_ids = HashSet::new();
+            for tile in &entry.border_tiles {
+                if !tile_ids.insert(tile.id) {
+                    return Err(format!("Country {} lists border tile {} more than once", id, tile.id));
+                }
+                let values = [tile.threat_gradient, tile.fortification, tile.garrison_strength];
+                if values.iter().any(|value| !value.is_finite()) {
+                    return Err(format!("Country {} border tile {} must have finite values", id, tile.id));
+                }
+            }
+            country.border_tiles = entry.border_tiles.clone();
+
+            for (&other, &intel) in &entry.intel {
+                if !ids.contains(&other) || !intel.is_finite() {
+                    return Err(format!("Country {} has invalid intel on country {}", id, other));
+                }
+                country.intel.insert(other, intel.clamp(0.0, 1.0));
+            }
+            world.add_country(country);
+        }
+
+        let check_pair = |kind: &str, (a, b): (u32, u32)| {
+            if a == b || !ids.contains(&a) || !ids.contains(&b) {
+                return Err(format!("Invalid {} between {} and {}", kind, a, b));
+            }
+            Ok(())
+        };
+        for &pair in &self.alliances {
+            check_pair("alliance", pair)?;
+            world.add_alliance(pair.0, pair.1);
+        }
+        for &pair in &self.wars {
+            check_pair("war", pair)?;
+            if !world.declare_war(pair.0, pair.1) && !world.at_war(pair.0, pair.1) {
+                return Err(format!("Countries {} and {} can't be both allied and at war", pair.0, pair.1));
+            }
+        }
+        Ok(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Result<WorldState, String> {
+        let scenario: Scenario = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        scenario.to_world()
+    }
+
+    #[test]
+    fn test_scenario_builds_world() {
+        let world = parse(r#"{
+            "countries": [
+                {"id": 1, "stats": {"m_eff": 250, "territory": 12},
+                 "edges": [{"neighbor_id": 2, "hostility": 0.8}],
+                 "border_tiles": [{"id": 7, "position_x": 3, "position_y": -1, "fortification": 2}],
+                 "intel": {"2": 1.0}},
+                {"id": 2, "edges": [{"neighbor_id": 1, "distance_bucket": 3}]},
+                {"id": 3, "personality": {"aggression": 0.9}}
+            ],
+            "alliances": [[2, 3]],
+            "wars": [[1, 2]]
+        }"#).unwrap();
+
+        let country = world.get_country(1).unwrap();
+        assert_eq!(country.m_eff, 250.0);
+        assert_eq!(country.territory, 12);
+        assert_eq!(country.gdp, Country::new(1).gdp);
+        assert_eq!(country.edges[0].hostility, 0.8);
+        assert_eq!(country.edges[0].distance_bucket, 1);
+        assert_eq!(country.border_tiles[0].fortification, 2.0);
+        assert_eq!(country.intel_on(2), 1.0);
+        assert_eq!(world.get_country(2).unwrap().edges[0].distance_bucket, 3);
+        assert!(world.are_allies(2, 3));
+        assert_eq!(world.get_country(3).unwrap().ally_count, 1);
+        assert!(world.at_war(1, 2));
+        assert_eq!(world.get_country(3).unwrap().personality.aggression, 0.9);
+        assert_eq!(world.get_country(3).unwrap().personality.loyalty, Personality::NEUTRAL);
+    }
+
+    #[test]
+    fn test_invalid_scenarios_are_rejected() {
+        assert!(parse(r#"{"countries": [{"id": 1}, {"id": 1}]}"#).unwrap_err().contains("more than once"));
+        assert!(parse(r#"{"countries": [{"id": 1, "edges": [{"neighbor_id": 9}]}]}"#).unwrap_err().contains("invalid"));
+        assert!(parse(r#"{"countries": [{"id": 1}], "alliances": [[1, 1]]}"#).unwrap_err().contains("alliance"));
+        let allied_enemies = r#"{"countries": [{"id": 1}, {"id": 2}], "alliances": [[1, 2]], "wars": [[1, 2]]}"#;
+        assert!(parse(allied_enemies).unwrap_err().contains("allied and at war"));
+        assert!(parse(r#"{"countries": [{"id": 1, "colour": "red"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_export_round_trips() {
+        let json = r#"{
+            "countries": [
+                {"id": 1, "edges": [{"neighbor_id": 2, "hostility": 0.5}], "intel": {"2": 0.75}},
+                {"id": 2, "stats": {"resources": 40}, "border_tiles": [{"id": 1, "position_x": 0, "position_y": 0}]}
+            ],
+            "wars": [[2, 1]]
+        }"#;
+        let world = parse(json).unwrap();
+        let exported = serde_json::to_string(&Scenario::from_world(&world)).unwrap();
+        let reloaded = parse(&exported).unwrap();
+        assert_eq!(serde_json::to_string(&Scenario::from_world(&reloaded)).unwrap(), exported);
+        assert_eq!(reloaded.wars(), vec![(1, 2)]);
+        assert_eq!(reloaded.get_country(2).unwrap().resources, 40.0);
+    }
+}
@@ -1,15 +1,20 @@
 /// Decision scoring system (§1, §2, §3)
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
 use super::actions::*;
 use super::country::*;
+use super::fixed::Fixed;
 use super::luts::*;
 use super::world::WorldState;
+use crate::utils::grid_hash;
 
-#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128", not(feature = "fixed-point")))]
 use core::arch::wasm32;
 
 /// Six-channel score components (§1)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
 pub struct ScoreComponents {
     pub delta_res: f32,      // Resource gain
     pub delta_sec: f32,      // Security/threat reduction
@@ -31,8 +36,12 @@ impl ScoreComponents {
         }
     }
     
-    /// Compute final score with adaptive weights (§1)
+    /// Compute final score with adaptive weights (§1); exact Q16.16 with the `fixed-point` feature
     pub fn final_score(&self, weights: &AdaptiveWeights) -> f32 {
+        if cfg!(feature = "fixed-point") {
+            return self.to_fixed().final_score(weights).to_f32();
+        }
+        
         let alpha = weights.alpha as f32;
         let beta = weights.beta as f32;
         let gamma = weights.gamma as f32;
@@ -47,6 +56,70 @@ impl ScoreComponents {
         kappa * self.cost -
         rho * self.risk
     }
+    
+    /// Each component times its weight, with cost and risk negated, so the
+    /// fields add up to `final_score` (to within rounding under `fixed-point`)
+    pub fn weighted(&self, weights: &AdaptiveWeights) -> ScoreComponents {
+        ScoreComponents {
+            delta_res: weights.alpha as f32 * self.delta_res,
+            delta_sec: weights.beta as f32 * self.delta_sec,
+            delta_growth: weights.gamma as f32 * self.delta_growth,
+            delta_pos: weights.delta as f32 * self.delta_pos,
+            cost: -(weights.kappa as f32 * self.cost),
+            risk: -(weights.rho as f32 * self.risk),
+        }
+    }
+    
+    /// Components rounded to Q16.16
+    pub fn to_fixed(&self) -> FixedComponents {
+        FixedComponents {
+            delta_res: Fixed::from_f32(self.delta_res),
+            delta_sec: Fixed::from_f32(self.delta_sec),
+            delta_growth: Fixed::from_f32(self.delta_growth),
+            delta_pos: Fixed::from_f32(self.delta_pos),
+            cost: Fixed::from_f32(self.cost),
+            risk: Fixed::from_f32(self.risk),
+        }
+    }
+}
+
+/// Score components in Q16.16, for scoring that is bit-identical on every platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FixedComponents {
+    pub delta_res: Fixed,
+    pub delta_sec: Fixed,
+    pub delta_growth: Fixed,
+    pub delta_pos: Fixed,
+    pub cost: Fixed,
+    pub risk: Fixed,
+}
+
+impl FixedComponents {
+    /// Weighted sum in integer arithmetic; the integer weights make every product exact,
+    /// and the total saturates only once at the end
+    pub fn final_score(&self, weights: &AdaptiveWeights) -> Fixed {
+        let terms = [
+            (self.delta_res, weights.alpha),
+            (self.delta_sec, weights.beta),
+            (self.delta_growth, weights.gamma),
+            (self.delta_pos, weights.delta),
+            (-self.cost, weights.kappa),
+            (-self.risk, weights.rho),
+        ];
+        let total: i64 = terms.iter().map(|(value, weight)| value.raw() as i64 * *weight as i64).sum();
+        Fixed(total.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+    
+    pub fn to_f32(&self) -> ScoreComponents {
+        ScoreComponents {
+            delta_res: self.delta_res.to_f32(),
+            delta_sec: self.delta_sec.to_f32(),
+            delta_growth: self.delta_growth.to_f32(),
+            delta_pos: self.delta_pos.to_f32(),
+            cost: self.cost.to_f32(),
+            risk: self.risk.to_f32(),
+        }
+    }
 }
 
 /// Batched scoring output bundling per-action components and final scores
@@ -60,6 +133,18 @@ impl BatchScoreResult {
     pub fn new(components: Vec<ScoreComponents>, final_scores: Vec<f32>) -> Self {
         Self { components, final_scores }
     }
+    
+    /// Recompute final scores from the existing components with new weights
+    pub fn reweight(&mut self, weights: &AdaptiveWeights) {
+        finalize_scores_batch(&self.components, weights, &mut self.final_scores);
+    }
+}
+
+/// A country's last shortlist and its scores, reused while nothing they depend on changes
+#[derive(Debug, Clone)]
+pub struct ScoreCache {
+    pub shortlist: Vec<Action>,
+    pub result: BatchScoreResult,
 }
 
 /// Score all actions up-front and fuse final score computation with SIMD acceleration when available.
@@ -69,42 +154,52 @@ pub fn score_actions_batch(
     world: &WorldState,
     luts: &LookupTables,
 ) -> BatchScoreResult {
-    if actions.is_empty() {
-        return BatchScoreResult::new(Vec::new(), Vec::new());
-    }
+    let mut batch = BatchScoreResult::new(Vec::with_capacity(actions.len()), Vec::with_capacity(actions.len()));
+    score_actions_batch_into(country, actions, world, luts, &mut batch);
+    batch
+}
 
-    let mut components = Vec::with_capacity(actions.len());
+/// `score_actions_batch` into an existing result, reusing its buffers
+pub fn score_actions_batch_into(
+    country: &Country,
+    actions: &[Action],
+    world: &WorldState,
+    luts: &LookupTables,
+    batch: &mut BatchScoreResult,
+) {
+    batch.components.clear();
     for action in actions {
-        components.push(score_action(country, action, world, luts));
+        batch.components.push(score_action(country, action, world, luts));
     }
-
-    let final_scores = finalize_scores_batch(&components, &country.weights);
-    BatchScoreResult::new(components, final_scores)
+    finalize_scores_batch(&batch.components, &country.weights, &mut batch.final_scores);
 }
 
-fn finalize_scores_batch(components: &[ScoreComponents], weights: &AdaptiveWeights) -> Vec<f32> {
-    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+fn finalize_scores_batch(components: &[ScoreComponents], weights: &AdaptiveWeights, scores: &mut Vec<f32>) {
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128", not(feature = "fixed-point")))]
     {
-        unsafe { finalize_scores_batch_simd(components, weights) }
+        unsafe { finalize_scores_batch_simd(components, weights, scores) }
     }
-    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128", not(feature = "fixed-point"))))]
     {
-        finalize_scores_batch_scalar(components, weights)
+        finalize_scores_batch_scalar(components, weights, scores)
     }
 }
 
-fn finalize_scores_batch_scalar(components: &[ScoreComponents], weights: &AdaptiveWeights) -> Vec<f32> {
-    components.iter().map(|c| c.final_score(weights)).collect()
+fn finalize_scores_batch_scalar(components: &[ScoreComponents], weights: &AdaptiveWeights, scores: &mut Vec<f32>) {
+    scores.clear();
+    scores.extend(components.iter().map(|c| c.final_score(weights)));
 }
 
-#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128", not(feature = "fixed-point")))]
 unsafe fn finalize_scores_batch_simd(
     components: &[ScoreComponents],
     weights: &AdaptiveWeights,
-) -> Vec<f32> {
+    scores: &mut Vec<f32>,
+) {
     use core::mem::transmute;
 
-    let mut scores = vec![0.0; components.len()];
+    scores.clear();
+    scores.resize(components.len(), 0.0);
 
     let w_res = wasm32::f32x4_splat(weights.alpha as f32);
     let w_sec = wasm32::f32x4_splat(weights.beta as f32);
@@ -169,8 +264,21 @@ unsafe fn finalize_scores_batch_simd(
         scores[offset] = component.final_score(weights);
         offset += 1;
     }
+}
 
-    scores
+/// Largest relative error in a neighbor's estimated m_eff, reached with no intel
+const INTEL_MAX_ERROR: f32 = 0.5;
+
+/// `observer`'s estimate of `target`'s military strength: exact with full
+/// intel, otherwise off by a deterministic per-tick error that shrinks as intel grows
+pub fn perceived_m_eff(observer: &Country, target: &Country, tick: u64) -> f32 {
+    let intel = observer.intel_on(target.id).clamp(0.0, 1.0);
+    if intel >= 1.0 {
+        return target.m_eff;
+    }
+    let bits = grid_hash(tick as u32, observer.id as i32, target.id as i32);
+    let noise = bits as f32 / u32::MAX as f32 * 2.0 - 1.0;  // [-1, 1]
+    target.m_eff * (1.0 + noise * INTEL_MAX_ERROR * (1.0 - intel))
 }
 
 /// Compute threat index for a country (§2)
@@ -184,16 +292,17 @@ pub fn compute_threat_index(
     for edge in &country.edges {
         if let Some(neighbor) = world.get_country(edge.neighbor_id) {
             let kernel = luts.distance_kernel.get(edge.distance_bucket);
+            let m_eff = perceived_m_eff(country, neighbor, world.get_tick());
             
             // Check if neighbor is an ally
             let is_ally = world.are_allies(country.id, neighbor.id);
             
             if is_ally {
                 // Allies reduce threat
-                threat -= kernel * neighbor.m_eff;
+                threat -= kernel * m_eff;
             } else {
                 // Enemies contribute to threat based on hostility
-                threat += kernel * neighbor.m_eff * edge.hostility;
+                threat += kernel * m_eff * edge.hostility;
             }
         }
     }
@@ -201,6 +310,51 @@ pub fn compute_threat_index(
     threat
 }
 
+// Win probability logit weights (§3.1)
+const ATTACK_B_FORT: f32 = 0.3;
+const ATTACK_B_TERR: f32 = 0.2;
+const ATTACK_B_DIST: f32 = 0.1;
+const ATTACK_LAMBDA: f32 = 1.5;
+
+/// Probability that `attacker` wins an attack across `edge` (§3.1)
+pub fn attack_win_probability(
+    attacker: &Country,
+    defender: &Country,
+    edge: &CountryEdge,
+    luts: &LookupTables,
+) -> f32 {
+    // Compute effective force ratio (§3.1)
+    let g_penalty = 1.0 + edge.terrain_penalty;
+    let fr = attacker.m_eff / (defender.m_eff * g_penalty);
+    if cfg!(feature = "fixed-point") {
+        return attack_win_probability_fixed(fr, edge, luts).to_f32();
+    }
+    
+    // Win probability using sigmoid
+    let ln_fr = luts.log_ratio.lookup(fr);
+    
+    let logit = ATTACK_LAMBDA * (
+        ln_fr
+        - ATTACK_B_FORT * edge.fortification
+        - ATTACK_B_TERR * edge.terrain_penalty
+        - ATTACK_B_DIST * (edge.distance_bucket as f32)
+    );
+    
+    luts.sigmoid.lookup(logit)
+}
+
+/// `attack_win_probability` for force ratio `fr`, in Q16.16 through the fixed LUT paths
+fn attack_win_probability_fixed(fr: f32, edge: &CountryEdge, luts: &LookupTables) -> Fixed {
+    let ln_fr = luts.log_ratio.lookup_fixed(Fixed::from_f32(fr));
+    let logit = Fixed::from_f32(ATTACK_LAMBDA) * (
+        ln_fr
+        - Fixed::from_f32(ATTACK_B_FORT) * Fixed::from_f32(edge.fortification)
+        - Fixed::from_f32(ATTACK_B_TERR) * Fixed::from_f32(edge.terrain_penalty)
+        - Fixed::from_f32(ATTACK_B_DIST).mul_int(edge.distance_bucket as i32)
+    );
+    luts.sigmoid.lookup_fixed(logit)
+}
+
 /// Score an attack action (§3.1)
 pub fn score_attack(
     attacker: &Country,
@@ -222,25 +376,7 @@ pub fn score_attack(
         None => return comp,
     };
     
-    // Compute effective force ratio (§3.1)
-    let g_penalty = 1.0 + edge.terrain_penalty;
-    let fr = attacker.m_eff / (defender.m_eff * g_penalty);
-    
-    // Win probability using sigmoid
-    let ln_fr = luts.log_ratio.lookup(fr);
-    let b_fort = 0.3;
-    let b_terr = 0.2;
-    let b_dist = 0.1;
-    let lambda = 1.5;
-    
-    let logit = lambda * (
-        ln_fr
-        - b_fort * edge.fortification
-        - b_terr * edge.terrain_penalty
-        - b_dist * (edge.distance_bucket as f32)
-    );
-    
-    let p_win = luts.sigmoid.lookup(logit);
+    let p_win = attack_win_probability(attacker, defender, edge, luts);
     
     // Expected values
     let v_win_res = defender.resources * 0.5;  // Gain half of defender's resources
@@ -267,7 +403,8 @@ pub fn score_attack(
     let delta_upkeep = defender.m_eff * 0.05;  // Occupation costs
     let dipl_penalty = edge.relations.max(0.0) * 0.5;  // Penalty for attacking friends
     
-    comp.cost = c_cas * e_casualties + c_upkeep * delta_upkeep + c_dipl * dipl_penalty;
+    comp.cost = c_cas * e_casualties + c_upkeep * delta_upkeep + c_dipl * dipl_penalty
+        + war_weariness_cost(attacker);
     
     // Normalize to target ranges [-32, +32] for deltas, [0, 16] for cost/risk
     comp.delta_res = (comp.delta_res / 50.0).clamp(-32.0, 32.0);
@@ -289,34 +426,25 @@ pub fn score_invest(
     
     // Compute ROI over horizon H
     let h = 8;  // Short horizon
-    let mut roi = 0.0;
     
     // Base GDP increase per sector
-    let gdp_boost = match sector {
-        InvestSector::Economy => 5.0,
-        InvestSector::Infrastructure => 3.0,
-        InvestSector::Technology => 4.0,
-        InvestSector::Military => 2.0,
-    };
+    let gdp_boost = sector.gdp_boost();
     
-    // Discounted future value
-    for horizon in 1..=h {
-        let discount = luts.discount.get(horizon);
-        let delta_gdp = gdp_boost * (1.0 + country.growth / 100.0).powi(horizon as i32);
-        roi += discount * delta_gdp;
-    }
-    roi /= h as f32;
-    
-    comp.delta_growth = roi;
+    comp.delta_growth = if cfg!(feature = "fixed-point") {
+        invest_roi_fixed(gdp_boost, country.growth, h, luts).to_f32()
+    } else {
+        // Discounted future value
+        let mut roi = 0.0;
+        for horizon in 1..=h {
+            let discount = luts.discount.get(horizon);
+            let delta_gdp = gdp_boost * (1.0 + country.growth / 100.0).powi(horizon as i32);
+            roi += discount * delta_gdp;
+        }
+        roi / h as f32
+    };
     
     // Cost varies by sector
-    let base_cost = match sector {
-        InvestSector::Economy => 20.0,
-        InvestSector::Infrastructure => 30.0,
-        InvestSector::Technology => 25.0,
-        InvestSector::Military => 15.0,
-    };
-    comp.cost = base_cost / country.resources.max(10.0);
+    comp.cost = sector.base_cost() / country.resources.max(10.0);
     
     // Risk is low for investments
     comp.risk = 1.0;
@@ -328,32 +456,39 @@ pub fn score_invest(
     comp
 }
 
-/// Score a research action (§3.3)
+/// Mean discounted GDP gain over `h` ticks in Q16.16, compounding `growth`
+/// percent by repeated multiplication rather than `powi`
+fn invest_roi_fixed(gdp_boost: f32, growth: f32, h: usize, luts: &LookupTables) -> Fixed {
+    let boost = Fixed::from_f32(gdp_boost);
+    let rate = Fixed::ONE + Fixed::from_f32(growth).saturating_div(Fixed::from_int(100));
+    let mut compounded = Fixed::ONE;
+    let mut roi = Fixed::ZERO;
+    for horizon in 1..=h {
+        compounded = compounded * rate;
+        roi = roi + luts.discount.get_fixed(horizon) * (boost * compounded);
+    }
+    roi.saturating_div(Fixed::from_int(h as i32))
+}
+
+/// Score a research action (§3.3); only nodes the country can unlock now score
 pub fn score_research(
     country: &Country,
-    tech: TechType,
+    tech: u32,
+    world: &WorldState,
 ) -> ScoreComponents {
     let mut comp = ScoreComponents::zero();
     
-    // Marginal value weighted by tech multipliers
-    let mv = &country.marginal_values;
-    let delta_growth = match tech {
-        TechType::MilitaryAdvancement => mv.military * 1.5,
-        TechType::EconomicEfficiency => mv.economy * 1.8,
-        TechType::DiplomaticInfluence => mv.diplomacy * 1.2,
-        TechType::TechnologicalBreakthrough => mv.tech * 2.0,
+    let tech_tree = world.tech_tree();
+    let node = match tech_tree.get(tech) {
+        Some(node) if tech_tree.is_unlockable(country, tech) => node,
+        _ => return comp,
     };
     
-    comp.delta_growth = delta_growth;
+    // Marginal value weighted by the node's stat modifiers
+    comp.delta_growth = node.value_for(country);
     
     // Research cost (RP_t)
-    let rp_cost = match tech {
-        TechType::MilitaryAdvancement => 30.0,
-        TechType::EconomicEfficiency => 25.0,
-        TechType::DiplomaticInfluence => 20.0,
-        TechType::TechnologicalBreakthrough => 40.0,
-    };
-    comp.cost = rp_cost / country.resources.max(10.0);
+    comp.cost = node.cost / country.resources.max(10.0);
     
     // Risk is zero for research
     comp.risk = 0.0;
@@ -441,6 +576,177 @@ fn estimate_alliance_benefit(_target: &Country, proposer: &Country) -> f32 {
     proposer.m_eff * 0.2 + proposer.prestige * 0.1
 }
 
+/// Score leaving an alliance (§3.4): the ally's support is lost and breaking
+/// faith costs prestige, which only pays off against an ally turned hostile
+pub fn score_break_alliance(
+    country: &Country,
+    target_id: u32,
+    world: &WorldState,
+) -> ScoreComponents {
+    let mut comp = ScoreComponents::zero();
+    
+    let target = match world.get_country(target_id) {
+        Some(t) => t,
+        None => return comp,
+    };
+    if !world.are_allies(country.id, target_id) {
+        return comp;
+    }
+    let hostility = country.get_edge(target_id).map_or(0.0, |e| e.hostility);
+    
+    // A hostile ally is a threat we can't answer; a friendly one is protection we give up
+    comp.delta_sec = (hostility - 0.5) * target.m_eff * 0.5;
+    comp.delta_pos = -betrayal_prestige_penalty(country);
+    comp.cost = 2.0;
+    comp.risk = 2.0;
+    
+    // Normalize
+    comp.delta_sec = (comp.delta_sec / 50.0).clamp(-32.0, 32.0);
+    comp.delta_pos = (comp.delta_pos / 5.0).clamp(-32.0, 32.0);
+    
+    comp
+}
+
+/// Prestige a country loses for breaking an alliance
+pub fn betrayal_prestige_penalty(country: &Country) -> f32 {
+    5.0 + country.prestige * 0.2
+}
+
+/// Score a spy mission (§3.4): the value of sharper estimates of a hostile
+/// neighbor against the diplomatic fallout if the agents are caught
+pub fn score_spy(
+    country: &Country,
+    target_id: u32,
+    world: &WorldState,
+) -> ScoreComponents {
+    let mut comp = ScoreComponents::zero();
+    
+    let target = match world.get_country(target_id) {
+        Some(t) => t,
+        None => return comp,
+    };
+    let edge = match country.get_edge(target_id) {
+        Some(e) => e,
+        None => return comp,
+    };
+    
+    // Information value: the uncertainty removed about a threatening neighbor
+    let missing_intel = 1.0 - country.intel_on(target_id);
+    comp.delta_sec = missing_intel * edge.hostility * target.m_eff * 0.3;
+    
+    comp.cost = 10.0 / country.resources.max(10.0);
+    comp.risk = 2.0 + edge.relations.max(0.0) * 0.05;  // Getting caught hurts good relations most
+    
+    // Normalize
+    comp.delta_sec = (comp.delta_sec / 10.0).clamp(-32.0, 32.0);
+    comp.cost = (comp.cost * 10.0).clamp(0.0, 16.0);
+    comp.risk = comp.risk.clamp(0.0, 16.0);
+    
+    comp
+}
+
+/// Score sabotage (§3.4): weakening a hostile neighbor pays off with good intel
+/// to aim it, but risks more fallout than spying
+pub fn score_sabotage(
+    country: &Country,
+    target_id: u32,
+    world: &WorldState,
+) -> ScoreComponents {
+    let mut comp = ScoreComponents::zero();
+    
+    let target = match world.get_country(target_id) {
+        Some(t) => t,
+        None => return comp,
+    };
+    let edge = match country.get_edge(target_id) {
+        Some(e) => e,
+        None => return comp,
+    };
+    let intel = country.intel_on(target_id);
+    
+    comp.delta_sec = edge.hostility * target.m_eff * 0.05 * (0.5 + intel);
+    comp.delta_pos = -edge.relations.max(0.0) * 0.1;
+    
+    comp.cost = 20.0 / country.resources.max(10.0);
+    comp.risk = 4.0 * (1.0 - intel) + edge.relations.max(0.0) * 0.05;
+    
+    // Normalize
+    comp.delta_sec = (comp.delta_sec / 10.0).clamp(-32.0, 32.0);
+    comp.delta_pos = (comp.delta_pos / 5.0).clamp(-32.0, 32.0);
+    comp.cost = (comp.cost * 10.0).clamp(0.0, 16.0);
+    comp.risk = comp.risk.clamp(0.0, 16.0);
+    
+    comp
+}
+
+/// Extra cost of fighting on, growing the longer a country has been at war
+fn war_weariness_cost(country: &Country) -> f32 {
+    let c_exhaustion = 2.0;
+    c_exhaustion * country.war_exhaustion
+}
+
+/// Score a war declaration: security gained by confronting a hostile, weaker
+/// neighbor, paid for in diplomatic standing and war weariness
+pub fn score_declare_war(
+    country: &Country,
+    target_id: u32,
+    world: &WorldState,
+) -> ScoreComponents {
+    let mut comp = ScoreComponents::zero();
+    
+    let target = match world.get_country(target_id) {
+        Some(t) => t,
+        None => return comp,
+    };
+    let edge = match country.get_edge(target_id) {
+        Some(e) => e,
+        None => return comp,
+    };
+    
+    let strength_ratio = country.m_eff / (country.m_eff + target.m_eff).max(1.0);
+    comp.delta_sec = edge.hostility * (country.m_eff - target.m_eff) * 0.5;
+    comp.delta_pos = -edge.relations.max(0.0) * 0.2;  // Betraying friends costs standing
+    
+    comp.cost = 10.0 + war_weariness_cost(country);
+    comp.risk = 8.0 * strength_ratio * (1.0 - strength_ratio);
+    
+    // Normalize
+    comp.delta_sec = (comp.delta_sec / 50.0).clamp(-32.0, 32.0);
+    comp.delta_pos = (comp.delta_pos / 5.0).clamp(-32.0, 32.0);
+    comp.cost = (comp.cost / 5.0).clamp(0.0, 16.0);
+    comp.risk = comp.risk.clamp(0.0, 16.0);
+    
+    comp
+}
+
+/// Score a peace treaty: worth more the more exhausted the country is
+pub fn score_make_peace(
+    country: &Country,
+    target_id: u32,
+    world: &WorldState,
+) -> ScoreComponents {
+    let mut comp = ScoreComponents::zero();
+    
+    let target = match world.get_country(target_id) {
+        Some(t) => t,
+        None => return comp,
+    };
+    if !world.at_war(country.id, target_id) {
+        return comp;
+    }
+    
+    comp.delta_sec = target.m_eff * 0.3;  // The enemy stops fighting us
+    comp.delta_growth = country.war_exhaustion;  // Recovery once the war ends
+    comp.cost = 3.0;  // Conceding prestige
+    comp.risk = 0.5;
+    
+    // Normalize
+    comp.delta_sec = (comp.delta_sec / 50.0).clamp(-32.0, 32.0);
+    comp.delta_growth = (comp.delta_growth / 5.0).clamp(-32.0, 32.0);
+    
+    comp
+}
+
 /// Score a fortify action (§3.5)
 pub fn score_fortify(
     country: &Country,
@@ -499,14 +805,33 @@ pub fn score_action(
     action: &Action,
     world: &WorldState,
     luts: &LookupTables,
+) -> ScoreComponents {
+    let components = score_action_unrounded(country, action, world, luts);
+    if cfg!(feature = "fixed-point") {
+        // Round here so logged components match what was scored, bit for bit
+        return components.to_fixed().to_f32();
+    }
+    components
+}
+
+fn score_action_unrounded(
+    country: &Country,
+    action: &Action,
+    world: &WorldState,
+    luts: &LookupTables,
 ) -> ScoreComponents {
     match action {
         Action::Attack { target_id } => score_attack(country, *target_id, world, luts),
         Action::Invest { sector } => score_invest(country, *sector, luts),
-        Action::Research { tech } => score_research(country, *tech),
+        Action::Research { tech } => score_research(country, *tech, world),
         Action::Ally { target_id } => score_diplomacy(country, *target_id, DiplomacyType::Ally, world, luts),
+        Action::BreakAlliance { target_id } => score_break_alliance(country, *target_id, world),
         Action::Pact { target_id } => score_diplomacy(country, *target_id, DiplomacyType::Pact, world, luts),
         Action::Trade { target_id } => score_diplomacy(country, *target_id, DiplomacyType::Trade, world, luts),
+        Action::Spy { target_id } => score_spy(country, *target_id, world),
+        Action::Sabotage { target_id } => score_sabotage(country, *target_id, world),
+        Action::DeclareWar { target_id } => score_declare_war(country, *target_id, world),
+        Action::MakePeace { target_id } => score_make_peace(country, *target_id, world),
         Action::Fortify { tile_id } => score_fortify(country, *tile_id),
         Action::Move { tile_id } => score_move(country, *tile_id),
         Action::Pass => ScoreComponents::zero(),  // Pass has zero change
@@ -551,14 +876,107 @@ mod tests {
     fn test_score_research() {
         let mut country = Country::new(1);
         country.marginal_values.tech = 5.0;
+        let world = WorldState::new();
+        let breakthrough = 3;  // technological_breakthrough in the standard tree
         
-        let comp = score_research(&country, TechType::TechnologicalBreakthrough);
+        let comp = score_research(&country, breakthrough, &world);
         
         // Should have positive growth delta
         assert!(comp.delta_growth > 0.0);
         
         // Risk should be zero for research
         assert_eq!(comp.risk, 0.0);
+        
+        // Known and locked nodes aren't worth anything
+        let combined_arms = 4;
+        assert_eq!(score_research(&country, combined_arms, &world).delta_growth, 0.0);
+        country.researched.insert(breakthrough);
+        assert_eq!(score_research(&country, breakthrough, &world).delta_growth, 0.0);
+        country.researched.insert(0);
+        assert!(score_research(&country, combined_arms, &world).delta_growth > 0.0);
+    }
+
+    #[test]
+    fn test_prolonged_wars_raise_attack_cost() {
+        let mut world = WorldState::new();
+        let mut attacker = Country::new(1);
+        attacker.add_edge(CountryEdge::new(2));
+        world.add_country(Country::new(2));
+        let luts = LookupTables::new();
+        
+        let fresh = score_attack(&attacker, 2, &world, &luts);
+        attacker.war_exhaustion = 20.0;
+        let weary = score_attack(&attacker, 2, &world, &luts);
+        
+        assert!(weary.cost > fresh.cost);
+        assert!(score_declare_war(&attacker, 2, &world).cost > 2.0);
+    }
+
+    #[test]
+    fn test_make_peace_only_scores_ongoing_wars() {
+        let mut world = WorldState::new();
+        let mut country = Country::new(1);
+        country.war_exhaustion = 10.0;
+        world.add_country(Country::new(2));
+        
+        assert_eq!(score_make_peace(&country, 2, &world).delta_growth, 0.0);
+        world.declare_war(1, 2);
+        assert!(score_make_peace(&country, 2, &world).delta_growth > 0.0);
+    }
+
+    #[test]
+    fn test_breaking_alliances_costs_prestige() {
+        let mut world = WorldState::new();
+        let mut country = Country::new(1);
+        country.add_edge(CountryEdge::new(2));
+        world.add_country(Country::new(2));
+        
+        assert_eq!(score_break_alliance(&country, 2, &world).delta_pos, 0.0);  // Not allied
+        world.add_alliance(1, 2);
+        let friendly = score_break_alliance(&country, 2, &world);
+        assert!(friendly.delta_pos < 0.0);
+        assert!(friendly.delta_sec < 0.0);
+        
+        country.get_edge_mut(2).unwrap().hostility = 1.0;
+        assert!(score_break_alliance(&country, 2, &world).delta_sec > 0.0);
+    }
+
+    #[test]
+    fn test_intel_sharpens_strength_estimates() {
+        let mut observer = Country::new(1);
+        let mut target = Country::new(2);
+        target.m_eff = 200.0;
+        
+        observer.intel.insert(2, 0.0);
+        let guesses: Vec<f32> = (0..20).map(|tick| perceived_m_eff(&observer, &target, tick)).collect();
+        assert!(guesses.iter().all(|&m| (100.0..=300.0).contains(&m)));
+        assert!(guesses.iter().any(|&m| (m - 200.0).abs() > 1.0));
+        assert_eq!(perceived_m_eff(&observer, &target, 7), perceived_m_eff(&observer, &target, 7));
+        
+        observer.intel.insert(2, 1.0);
+        assert_eq!(perceived_m_eff(&observer, &target, 7), 200.0);
+    }
+
+    #[test]
+    fn test_spying_is_worth_less_with_good_intel() {
+        let mut world = WorldState::new();
+        let mut country = Country::new(1);
+        let mut edge = CountryEdge::new(2);
+        edge.hostility = 0.8;
+        country.add_edge(edge);
+        world.add_country(Country::new(2));
+        
+        let blind = score_spy(&country, 2, &world);
+        country.intel.insert(2, 0.9);
+        let informed = score_spy(&country, 2, &world);
+        assert!(blind.delta_sec > informed.delta_sec);
+        
+        // Better intel makes sabotage both more effective and less risky
+        let aimed = score_sabotage(&country, 2, &world);
+        country.intel.insert(2, 0.0);
+        let blind_sabotage = score_sabotage(&country, 2, &world);
+        assert!(aimed.delta_sec > blind_sabotage.delta_sec);
+        assert!(aimed.risk < blind_sabotage.risk);
     }
 
     #[test]
@@ -575,6 +993,69 @@ mod tests {
         assert_eq!(comp.cost, 0.0);
     }
 
+    #[test]
+    fn test_fixed_final_score_matches_float() {
+        let components = ScoreComponents {
+            delta_res: 3.25,
+            delta_sec: -1.5,
+            delta_growth: 0.1,
+            delta_pos: 12.0,
+            cost: 4.0,
+            risk: 0.75,
+        };
+        let weights = AdaptiveWeights::new();
+        let fixed = components.to_fixed();
+        assert!((fixed.final_score(&weights).to_f32() - components.final_score(&weights)).abs() < 1e-3);
+        assert_eq!(fixed.to_f32().to_fixed(), fixed);
+        
+        let saturated = FixedComponents { delta_res: Fixed::MAX, ..fixed };
+        assert_eq!(saturated.final_score(&weights), Fixed::MAX);
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn test_fixed_point_scores_are_exact_q16() {
+        let country = Country::new(1);
+        let world = WorldState::new();
+        let luts = LookupTables::new();
+        let actions = [Action::Invest { sector: InvestSector::Economy }, Action::Research { tech: 0 }];
+        let batch = score_actions_batch(&country, &actions, &world, &luts);
+        for (components, score) in batch.components.iter().zip(&batch.final_scores) {
+            assert_eq!(components.to_fixed().to_f32().delta_res, components.delta_res);
+            assert_eq!(Fixed::from_f32(*score).to_f32(), *score);
+        }
+    }
+
+    #[cfg(feature = "fixed-point")]
+    #[test]
+    fn test_fixed_point_scores_pin_exact_bits() {
+        // Every platform must land on these bits; a change here changes AI decisions
+        let mut world = WorldState::new();
+        let mut country = Country::new(1);
+        country.growth = 3.5;
+        let mut edge = CountryEdge::new(2);
+        edge.fortification = 0.4;
+        edge.terrain_penalty = 0.25;
+        edge.distance_bucket = 2;
+        country.add_edge(edge);
+        let mut defender = Country::new(2);
+        defender.m_eff = 70.0;
+        world.add_country(defender);
+        let luts = LookupTables::new();
+        let weights = AdaptiveWeights::new();
+        
+        let defender = world.get_country(2).unwrap();
+        let p_win = attack_win_probability(&country, defender, country.get_edge(2).unwrap(), &luts);
+        assert_eq!(Fixed::from_f32(p_win).raw(), 27016);
+        assert_eq!(invest_roi_fixed(InvestSector::Economy.gdp_boost(), country.growth, 8, &luts).raw(), 303904);
+        
+        let actions = [Action::Attack { target_id: 2 }, Action::Invest { sector: InvestSector::Economy }];
+        let batch = score_actions_batch(&country, &actions, &world, &luts);
+        let bits: Vec<i32> = batch.final_scores.iter().map(|&score| Fixed::from_f32(score).raw()).collect();
+        assert_eq!(bits, [25636, -228736]);
+        assert_eq!(batch.components[0].to_fixed().final_score(&weights).raw(), bits[0]);
+    }
+
     #[test]
     fn test_score_actions_batch_matches_scalar() {
         let country = Country::new(1);
@@ -583,7 +1064,7 @@ mod tests {
         let actions = vec![
             Action::Pass,
             Action::Invest { sector: InvestSector::Economy },
-            Action::Research { tech: TechType::EconomicEfficiency },
+            Action::Research { tech: 1 },
         ];
 
         let batch = score_actions_batch(&country, &actions, &world, &luts);
@@ -0,0 +1,165 @@
+/// Action selection: turning a scored shortlist into one choice (§6 step 5)
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use super::actions::Action;
+
+/// How a country picks among its scored candidates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SelectionPolicy {
+    /// Highest score; ties go to the lowest `Action::tie_break_key`
+    #[default]
+    Argmax,
+    /// Argmax, except a uniformly random candidate with probability `epsilon`
+    EpsilonGreedy { epsilon: f32 },
+    /// Sample with probability proportional to exp(score / temperature)
+    Softmax { temperature: f32 },
+}
+
+impl SelectionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            SelectionPolicy::Argmax => Ok(()),
+            SelectionPolicy::EpsilonGreedy { epsilon } if !(0.0..=1.0).contains(&epsilon) => {
+                Err(format!("Epsilon must be between 0 and 1, got {}", epsilon))
+            }
+            SelectionPolicy::Softmax { temperature } if !(temperature.is_finite() && temperature > 0.0) => {
+                Err(format!("Softmax temperature must be positive, got {}", temperature))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Index of the chosen action in `actions`, or None for an empty shortlist
+    pub fn select(&self, actions: &[Action], scores: &[f32], rng: &mut XorShiftRng) -> Option<usize> {
+        let best = argmax(actions, scores)?;
+        match *self {
+            SelectionPolicy::Argmax => Some(best),
+            SelectionPolicy::EpsilonGreedy { epsilon } => {
+                if rng.next_f32() < epsilon {
+                    Some(rng.next_below(scores.len()))
+                } else {
+                    Some(best)
+                }
+            }
+            SelectionPolicy::Softmax { temperature } => {
+                // Shift by the best score so exp never overflows; NaN scores get no weight
+                let max = scores[best];
+                let weights: Vec<f32> = scores
+                    .iter()
+                    .map(|&score| if score.is_nan() { 0.0 } else { ((score - max) / temperature).exp() })
+                    .collect();
+                let total: f32 = weights.iter().sum();
+                let mut target = rng.next_f32() * total;
+                for (index, &weight) in weights.iter().enumerate() {
+                    if target < weight {
+                        return Some(index);
+                    }
+                    target -= weight;
+                }
+                Some(best)
+            }
+        }
+    }
+}
+
+/// Highest-scoring index, ignoring NaN scores, with ties broken by action
+/// kind and target id so the result doesn't depend on shortlist order
+pub fn argmax(actions: &[Action], scores: &[f32]) -> Option<usize> {
+    (0..scores.len().min(actions.len())).reduce(|best, index| {
+        let (score, best_score) = (scores[index], scores[best]);
+        let better = if best_score.is_nan() {
+            true
+        } else if score.is_nan() {
+            false
+        } else {
+            score > best_score
+                || (score == best_score && actions[index].tie_break_key() < actions[best].tie_break_key())
+        };
+        if better { index } else { best }
+    })
+}
+
+/// Small seeded xorshift64* generator; the same seed gives the same choices on every platform
+#[derive(Debug, Clone)]
+pub struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck at zero, so mix the seed into a nonzero state
+        let state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ 0xd1b5_4a32_d192_ed03;
+        Self { state: if state == 0 { 1 } else { state } }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform in [0, n); n must be nonzero
+    pub fn next_below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argmax_breaks_ties_by_action_not_order() {
+        let mut rng = XorShiftRng::new(1);
+        let forward = [Action::Attack { target_id: 3 }, Action::Attack { target_id: 2 }, Action::Pass];
+        let backward = [Action::Pass, Action::Attack { target_id: 2 }, Action::Attack { target_id: 3 }];
+
+        let chosen = SelectionPolicy::Argmax.select(&forward, &[5.0, 5.0, 1.0], &mut rng);
+        assert_eq!(chosen, Some(1));
+        let chosen = SelectionPolicy::Argmax.select(&backward, &[1.0, 5.0, 5.0], &mut rng);
+        assert_eq!(chosen, Some(1));
+
+        assert_eq!(SelectionPolicy::Argmax.select(&forward, &[f32::NAN, 0.0, -1.0], &mut rng), Some(1));
+        assert_eq!(SelectionPolicy::Argmax.select(&[], &[], &mut rng), None);
+    }
+
+    #[test]
+    fn test_stochastic_policies_are_reproducible() {
+        let actions: Vec<Action> = (0..4).map(|id| Action::Attack { target_id: id }).collect();
+        let scores = [1.0, 2.0, 3.0, 2.5];
+        for policy in [
+            SelectionPolicy::EpsilonGreedy { epsilon: 0.5 },
+            SelectionPolicy::Softmax { temperature: 1.0 },
+        ] {
+            let run = |seed| {
+                let mut rng = XorShiftRng::new(seed);
+                (0..50).map(|_| policy.select(&actions, &scores, &mut rng).unwrap()).collect::<Vec<_>>()
+            };
+            let picks = run(7);
+            assert_eq!(picks, run(7));
+            assert!(picks.iter().any(|&pick| pick != 2), "{:?} never explored", policy);
+            assert!(picks.iter().filter(|&&pick| pick == 2).count() > picks.iter().filter(|&&pick| pick == 0).count());
+        }
+    }
+
+    #[test]
+    fn test_invalid_policies_are_rejected() {
+        assert!(SelectionPolicy::EpsilonGreedy { epsilon: 1.5 }.validate().is_err());
+        assert!(SelectionPolicy::Softmax { temperature: 0.0 }.validate().is_err());
+        assert!(SelectionPolicy::Softmax { temperature: f32::NAN }.validate().is_err());
+        assert!(SelectionPolicy::Softmax { temperature: 0.5 }.validate().is_ok());
+
+        let parsed: SelectionPolicy = serde_json::from_str(r#"{"kind": "softmax", "temperature": 2.0}"#).unwrap();
+        assert_eq!(parsed, SelectionPolicy::Softmax { temperature: 2.0 });
+    }
+}
@@ -0,0 +1,265 @@
+/// Technology tree: research nodes with prerequisites, costs and stat modifiers (§3.3)
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::country::Country;
+use super::resolution::StatKind;
+
+/// Change one tech applies to a stat: `value * scale + add`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatModifier {
+    pub stat: StatKind,
+    #[serde(default)]
+    pub add: f32,
+    #[serde(default = "StatModifier::default_scale")]
+    pub scale: f32,
+}
+
+impl StatModifier {
+    fn default_scale() -> f32 {
+        1.0
+    }
+
+    /// How much this modifier would change `country`'s stat
+    pub fn delta(&self, country: &Country) -> f32 {
+        let value = self.stat.value(country);
+        value * self.scale + self.add - value
+    }
+}
+
+/// One researchable technology
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TechNode {
+    pub id: String,
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    pub cost: f32,                   // Research points (resources) spent (RP_t)
+    #[serde(default)]
+    pub modifiers: Vec<StatModifier>,
+}
+
+impl TechNode {
+    /// Marginal-value-weighted gain of researching this node (Σ m_tq * MV_q),
+    /// with each stat measured in typical single-research steps
+    pub fn value_for(&self, country: &Country) -> f32 {
+        let mv = &country.marginal_values;
+        self.modifiers
+            .iter()
+            .map(|modifier| {
+                let (marginal_value, step) = match modifier.stat {
+                    StatKind::MilitaryStrength => (mv.military, 5.0),
+                    StatKind::Gdp => (mv.economy, 5.0),
+                    StatKind::Growth => (mv.economy, 0.5),
+                    StatKind::TechLevel => (mv.tech, 0.1),
+                    StatKind::Prestige => (mv.diplomacy, 2.0),
+                };
+                marginal_value * modifier.delta(country) / step
+            })
+            .sum()
+    }
+}
+
+/// Validated tech tree; nodes are referred to by their index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<TechNode>", into = "Vec<TechNode>")]
+pub struct TechTree {
+    nodes: Vec<TechNode>,
+    prerequisites: Vec<Vec<u32>>,  // Node indices each node requires
+}
+
+impl TechTree {
+    /// Build a tree, rejecting duplicate ids, unknown or cyclic prerequisites
+    /// and negative or non-finite costs and modifiers
+    pub fn new(nodes: Vec<TechNode>) -> Result<Self, String> {
+        let mut index: HashMap<&str, u32> = HashMap::with_capacity(nodes.len());
+        for (i, node) in nodes.iter().enumerate() {
+            if index.insert(node.id.as_str(), i as u32).is_some() {
+                return Err(format!("Duplicate tech id {:?}", node.id));
+            }
+            if !node.cost.is_finite() || node.cost < 0.0 {
+                return Err(format!("Tech {:?} needs a finite, non-negative cost", node.id));
+            }
+            if node.modifiers.iter().any(|m| !m.add.is_finite() || !m.scale.is_finite()) {
+                return Err(format!("Tech {:?} has a non-finite modifier", node.id));
+            }
+        }
+
+        let prerequisites = nodes
+            .iter()
+            .map(|node| {
+                node.prerequisites
+                    .iter()
+                    .map(|id| {
+                        index.get(id.as_str()).copied().ok_or_else(|| {
+                            format!("Tech {:?} requires unknown tech {:?}", node.id, id)
+                        })
+                    })
+                    .collect::<Result<Vec<u32>, String>>()
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let tree = Self { nodes, prerequisites };
+        tree.check_acyclic()?;
+        Ok(tree)
+    }
+
+    /// Peel off nodes whose prerequisites are all resolved; anything left over is on a cycle
+    fn check_acyclic(&self) -> Result<(), String> {
+        let mut remaining: Vec<usize> = self.prerequisites.iter().map(Vec::len).collect();
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for (node, prerequisites) in self.prerequisites.iter().enumerate() {
+            for &prereq in prerequisites {
+                dependents[prereq as usize].push(node);
+            }
+        }
+
+        let mut ready: Vec<usize> = (0..self.nodes.len()).filter(|&node| remaining[node] == 0).collect();
+        let mut resolved = 0;
+        while let Some(node) = ready.pop() {
+            resolved += 1;
+            for &dependent in &dependents[node] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push(dependent);
+                }
+            }
+        }
+
+        match remaining.iter().position(|&count| count > 0) {
+            Some(node) if resolved < self.nodes.len() => {
+                Err(format!("Tech {:?} is part of or depends on a prerequisite cycle", self.nodes[node].id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The built-in tree: the four classic research lines plus later techs that build on them
+    pub fn standard() -> Self {
+        fn node(id: &str, prerequisites: &[&str], cost: f32, modifiers: &[(StatKind, f32, f32)]) -> TechNode {
+            TechNode {
+                id: id.to_string(),
+                prerequisites: prerequisites.iter().map(|p| p.to_string()).collect(),
+                cost,
+                modifiers: modifiers
+                    .iter()
+                    .map(|&(stat, add, scale)| StatModifier { stat, add, scale })
+                    .collect(),
+            }
+        }
+        use StatKind::*;
+        Self::new(vec![
+            node("military_advancement", &[], 30.0, &[(MilitaryStrength, 0.0, 1.05), (TechLevel, 0.1, 1.0)]),
+            node("economic_efficiency", &[], 25.0, &[(Gdp, 0.0, 1.05), (TechLevel, 0.1, 1.0)]),
+            node("diplomatic_influence", &[], 20.0, &[(Prestige, 2.0, 1.0), (TechLevel, 0.1, 1.0)]),
+            node("technological_breakthrough", &[], 40.0, &[(TechLevel, 0.3, 1.0)]),
+            node(
+                "combined_arms",
+                &["military_advancement", "technological_breakthrough"],
+                60.0,
+                &[(MilitaryStrength, 0.0, 1.1), (TechLevel, 0.1, 1.0)],
+            ),
+            node("industrialization", &["economic_efficiency"], 50.0, &[(Gdp, 0.0, 1.1), (Growth, 1.0, 1.0)]),
+            node(
+                "statecraft",
+                &["diplomatic_influence", "economic_efficiency"],
+                45.0,
+                &[(Prestige, 5.0, 1.0), (TechLevel, 0.1, 1.0)],
+            ),
+        ])
+        .expect("the standard tech tree is valid")
+    }
+
+    pub fn nodes(&self) -> &[TechNode] {
+        &self.nodes
+    }
+
+    pub fn get(&self, tech: u32) -> Option<&TechNode> {
+        self.nodes.get(tech as usize)
+    }
+
+    /// Whether `country` can research `tech` now: not yet known, with every prerequisite known
+    pub fn is_unlockable(&self, country: &Country, tech: u32) -> bool {
+        match self.prerequisites.get(tech as usize) {
+            Some(prerequisites) => {
+                !country.researched.contains(&tech)
+                    && prerequisites.iter().all(|prereq| country.researched.contains(prereq))
+            }
+            None => false,
+        }
+    }
+
+    /// Every node `country` can research now, in tree order
+    pub fn unlockable<'a>(&'a self, country: &'a Country) -> impl Iterator<Item = u32> + 'a {
+        (0..self.nodes.len() as u32).filter(move |&tech| self.is_unlockable(country, tech))
+    }
+}
+
+impl Default for TechTree {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl TryFrom<Vec<TechNode>> for TechTree {
+    type Error = String;
+
+    fn try_from(nodes: Vec<TechNode>) -> Result<Self, Self::Error> {
+        Self::new(nodes)
+    }
+}
+
+impl From<TechTree> for Vec<TechNode> {
+    fn from(tree: TechTree) -> Self {
+        tree.nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Result<TechTree, String> {
+        let nodes: Vec<TechNode> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        TechTree::new(nodes)
+    }
+
+    #[test]
+    fn test_prerequisites_gate_unlocking() {
+        let tree = parse(r#"[
+            {"id": "bronze", "cost": 10, "modifiers": [{"stat": "military_strength", "add": 5}]},
+            {"id": "iron", "prerequisites": ["bronze"], "cost": 20, "modifiers": [{"stat": "military_strength", "scale": 1.2}]}
+        ]"#).unwrap();
+        let mut country = Country::new(1);
+
+        assert_eq!(tree.unlockable(&country).collect::<Vec<_>>(), vec![0]);
+        country.researched.insert(0);
+        assert_eq!(tree.unlockable(&country).collect::<Vec<_>>(), vec![1]);
+        assert!(!tree.is_unlockable(&country, 0));  // Already known
+        assert!(!tree.is_unlockable(&country, 9));  // No such node
+
+        assert!((tree.get(1).unwrap().modifiers[0].delta(&country) - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_invalid_trees_are_rejected() {
+        assert!(parse(r#"[{"id": "a", "cost": 1}, {"id": "a", "cost": 1}]"#).unwrap_err().contains("Duplicate"));
+        assert!(parse(r#"[{"id": "a", "prerequisites": ["b"], "cost": 1}]"#).unwrap_err().contains("unknown"));
+        assert!(parse(r#"[{"id": "a", "cost": -1}]"#).unwrap_err().contains("cost"));
+        let cycle = r#"[
+            {"id": "a", "prerequisites": ["c"], "cost": 1},
+            {"id": "b", "prerequisites": ["a"], "cost": 1},
+            {"id": "c", "prerequisites": ["b"], "cost": 1}
+        ]"#;
+        assert!(parse(cycle).unwrap_err().contains("prerequisite cycle"));
+        assert!(parse(r#"[{"id": "a", "cost": 1, "modifiers": [{"stat": "luck"}]}]"#).is_err());
+    }
+
+    #[test]
+    fn test_standard_tree_round_trips_through_json() {
+        let tree = TechTree::standard();
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(serde_json::from_str::<TechTree>(&json).unwrap(), tree);
+    }
+}
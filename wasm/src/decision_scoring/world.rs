@@ -1,12 +1,27 @@
 /// World state and simulation management
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+#[cfg(feature = "bindings")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
+use crate::error::SimError;
 use super::actions::*;
 use super::country::*;
+use super::lookahead::*;
 use super::luts::*;
+use super::resolution::*;
+use super::scenario::*;
 use super::scoring::*;
+use super::selection::*;
+use super::tech_tree::*;
+
+const WAR_EXHAUSTION_PER_WAR: f32 = 1.0;  // Added each tick for every ongoing war
+const WAR_EXHAUSTION_DECAY: f32 = 0.9;    // Multiplier each tick at peace
+const DEFAULT_LOG_CAPACITY: usize = 10_000;  // Decision logs kept before the oldest are dropped
 
 /// Alliance relationships between countries
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,11 +30,27 @@ pub struct Alliance {
     pub country_b: u32,
 }
 
+/// Recurring trade between two countries: both receive `income` resources each
+/// tick and `gdp_bonus` GDP while it runs; a war between them suspends both
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeRoute {
+    pub country_a: u32,
+    pub country_b: u32,
+    pub income: f32,
+    pub gdp_bonus: f32,
+    pub disrupted: bool,
+}
+
 /// World state containing all countries and relationships
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorldState {
     countries: HashMap<u32, Country>,
     alliances: HashSet<(u32, u32)>,  // Normalized pairs (min, max)
+    alliance_expiry: HashMap<(u32, u32), u64>,  // Tick each temporary alliance ends
+    alliance_duration: Option<u64>,  // Ticks new alliances last, None for permanent
+    wars: HashSet<(u32, u32)>,       // Normalized pairs (min, max)
+    trade_routes: Vec<TradeRoute>,   // In the order they were opened
+    tech_tree: TechTree,
     tick: u64,
 }
 
@@ -29,6 +60,11 @@ impl WorldState {
         Self {
             countries: HashMap::new(),
             alliances: HashSet::new(),
+            alliance_expiry: HashMap::new(),
+            alliance_duration: None,
+            wars: HashSet::new(),
+            trade_routes: Vec::new(),
+            tech_tree: TechTree::standard(),
             tick: 0,
         }
     }
@@ -38,14 +74,51 @@ impl WorldState {
         self.countries.insert(country.id, country);
     }
     
+    /// Remove a country along with its alliances and every edge pointing at it
+    pub fn remove_country(&mut self, id: u32) -> Option<Country> {
+        let removed = self.countries.remove(&id)?;
+        
+        let broken: Vec<(u32, u32)> = self.alliances
+            .iter()
+            .copied()
+            .filter(|&(a, b)| a == id || b == id)
+            .collect();
+        for (a, b) in broken {
+            self.remove_alliance(a, b);
+        }
+        self.wars.retain(|&(a, b)| a != id && b != id);
+        
+        for country in self.countries.values_mut() {
+            country.remove_edge(id);
+            country.invalidate_scores();
+        }
+        let mut routes = std::mem::take(&mut self.trade_routes);
+        routes.retain(|route| {
+            let partner = match (route.country_a == id, route.country_b == id) {
+                (true, _) => route.country_b,
+                (_, true) => route.country_a,
+                _ => return true,
+            };
+            if !route.disrupted {
+                self.adjust_gdp(partner, -route.gdp_bonus);
+            }
+            false
+        });
+        self.trade_routes = routes;
+        
+        Some(removed)
+    }
+    
     /// Get a country by ID
     pub fn get_country(&self, id: u32) -> Option<&Country> {
         self.countries.get(&id)
     }
     
-    /// Get a mutable country by ID
+    /// Get a mutable country by ID; its cached scores are assumed stale
     pub fn get_country_mut(&mut self, id: u32) -> Option<&mut Country> {
-        self.countries.get_mut(&id)
+        let country = self.countries.get_mut(&id)?;
+        country.invalidate_scores();
+        Some(country)
     }
     
     /// Get all countries
@@ -53,18 +126,78 @@ impl WorldState {
         &self.countries
     }
     
-    /// Add an alliance between two countries
-    pub fn add_alliance(&mut self, a: u32, b: u32) {
+    /// Add an alliance between two countries, lasting `alliance_duration` ticks
+    /// if one is set; returns false if they were already allied
+    pub fn add_alliance(&mut self, a: u32, b: u32) -> bool {
         let pair = if a < b { (a, b) } else { (b, a) };
-        self.alliances.insert(pair);
+        if !self.alliances.insert(pair) {
+            return false;
+        }
+        if let Some(duration) = self.alliance_duration {
+            self.alliance_expiry.insert(pair, self.tick + duration);
+        }
         
         // Update ally counts
-        if let Some(country_a) = self.countries.get_mut(&a) {
+        if let Some(country_a) = self.get_country_mut(a) {
             country_a.ally_count += 1;
         }
-        if let Some(country_b) = self.countries.get_mut(&b) {
+        if let Some(country_b) = self.get_country_mut(b) {
             country_b.ally_count += 1;
         }
+        true
+    }
+    
+    /// Dissolve the alliance between two countries; returns false if they weren't allied
+    pub fn remove_alliance(&mut self, a: u32, b: u32) -> bool {
+        let pair = if a < b { (a, b) } else { (b, a) };
+        if !self.alliances.remove(&pair) {
+            return false;
+        }
+        self.alliance_expiry.remove(&pair);
+        
+        for id in [a, b] {
+            if let Some(country) = self.get_country_mut(id) {
+                country.ally_count = country.ally_count.saturating_sub(1);
+            }
+        }
+        true
+    }
+    
+    /// Set how many ticks alliances formed from now on last; None makes them permanent
+    pub fn set_alliance_duration(&mut self, duration: Option<u64>) {
+        self.alliance_duration = duration;
+    }
+    
+    /// Tick an alliance ends at, or None if it is permanent or doesn't exist
+    pub fn alliance_expiry(&self, a: u32, b: u32) -> Option<u64> {
+        let pair = if a < b { (a, b) } else { (b, a) };
+        self.alliance_expiry.get(&pair).copied()
+    }
+    
+    /// Dissolve every alliance whose term has run out, returning the pairs in order
+    pub fn expire_alliances(&mut self) -> Vec<(u32, u32)> {
+        let mut expired: Vec<(u32, u32)> = self.alliance_expiry
+            .iter()
+            .filter(|&(_, &expires_at)| expires_at <= self.tick)
+            .map(|(&pair, _)| pair)
+            .collect();
+        expired.sort();
+        for &(a, b) in &expired {
+            self.remove_alliance(a, b);
+        }
+        expired
+    }
+    
+    /// Every alliance as a (lower id, higher id) pair, in ascending order
+    pub fn alliances(&self) -> Vec<(u32, u32)> {
+        let mut alliances: Vec<(u32, u32)> = self.alliances.iter().copied().collect();
+        alliances.sort();
+        alliances
+    }
+    
+    /// How many ticks alliances formed from now on last, None if permanent
+    pub fn alliance_duration(&self) -> Option<u64> {
+        self.alliance_duration
     }
     
     /// Check if two countries are allies
@@ -73,6 +206,138 @@ impl WorldState {
         self.alliances.contains(&pair)
     }
     
+    /// Declare war between two countries; returns false if they are allies or already at war
+    pub fn declare_war(&mut self, a: u32, b: u32) -> bool {
+        if a == b || self.are_allies(a, b) {
+            return false;
+        }
+        let pair = if a < b { (a, b) } else { (b, a) };
+        if !self.wars.insert(pair) {
+            return false;
+        }
+        self.invalidate_scores_of(&[a, b]);
+        true
+    }
+    
+    /// End the war between two countries; returns false if they weren't at war
+    pub fn make_peace(&mut self, a: u32, b: u32) -> bool {
+        let pair = if a < b { (a, b) } else { (b, a) };
+        if !self.wars.remove(&pair) {
+            return false;
+        }
+        self.invalidate_scores_of(&[a, b]);
+        true
+    }
+    
+    /// Check if two countries are at war
+    pub fn at_war(&self, a: u32, b: u32) -> bool {
+        let pair = if a < b { (a, b) } else { (b, a) };
+        self.wars.contains(&pair)
+    }
+    
+    /// Every ongoing war as a (lower id, higher id) pair, in ascending order
+    pub fn wars(&self) -> Vec<(u32, u32)> {
+        let mut wars: Vec<(u32, u32)> = self.wars.iter().copied().collect();
+        wars.sort();
+        wars
+    }
+    
+    /// Countries at war with `id`, in ascending id order
+    pub fn enemies_of(&self, id: u32) -> Vec<u32> {
+        let mut enemies: Vec<u32> = self.wars
+            .iter()
+            .filter_map(|&(a, b)| if a == id { Some(b) } else if b == id { Some(a) } else { None })
+            .collect();
+        enemies.sort();
+        enemies
+    }
+    
+    /// Accumulate war exhaustion for every ongoing war and let it recover in peace
+    pub fn update_war_exhaustion(&mut self) {
+        let mut war_counts: HashMap<u32, usize> = HashMap::new();
+        for &(a, b) in &self.wars {
+            *war_counts.entry(a).or_default() += 1;
+            *war_counts.entry(b).or_default() += 1;
+        }
+        for country in self.countries.values_mut() {
+            let previous = country.war_exhaustion;
+            match war_counts.get(&country.id) {
+                Some(&wars) => country.war_exhaustion += WAR_EXHAUSTION_PER_WAR * wars as f32,
+                None => country.war_exhaustion *= WAR_EXHAUSTION_DECAY,
+            }
+            if country.war_exhaustion != previous {
+                country.invalidate_scores();
+            }
+        }
+    }
+    
+    /// Open a trade route between two countries; returns false if they already trade
+    pub fn add_trade_route(&mut self, a: u32, b: u32, income: f32, gdp_bonus: f32) -> bool {
+        if self.has_trade_route(a, b) {
+            return false;
+        }
+        let disrupted = self.at_war(a, b);
+        if !disrupted {
+            self.adjust_gdp(a, gdp_bonus);
+            self.adjust_gdp(b, gdp_bonus);
+        }
+        self.trade_routes.push(TradeRoute { country_a: a, country_b: b, income, gdp_bonus, disrupted });
+        true
+    }
+    
+    /// Suspend routes whose partners are at war and resume those at peace,
+    /// returning each route that changed with its new disrupted state
+    pub fn update_trade_disruptions(&mut self) -> Vec<(u32, u32, bool)> {
+        let mut changed = Vec::new();
+        for index in 0..self.trade_routes.len() {
+            let route = &self.trade_routes[index];
+            let (a, b, gdp_bonus) = (route.country_a, route.country_b, route.gdp_bonus);
+            let disrupted = self.at_war(a, b);
+            if disrupted == route.disrupted {
+                continue;
+            }
+            self.trade_routes[index].disrupted = disrupted;
+            self.invalidate_scores_of(&[a, b]);
+            let change = if disrupted { -gdp_bonus } else { gdp_bonus };
+            self.adjust_gdp(a, change);
+            self.adjust_gdp(b, change);
+            changed.push((a, b, disrupted));
+        }
+        changed
+    }
+    
+    fn adjust_gdp(&mut self, id: u32, amount: f32) {
+        if let Some(country) = self.get_country_mut(id) {
+            country.gdp += amount;
+        }
+    }
+    
+    /// Check if two countries trade, in either direction
+    pub fn has_trade_route(&self, a: u32, b: u32) -> bool {
+        self.trade_routes.iter().any(|route| {
+            (route.country_a == a && route.country_b == b) || (route.country_a == b && route.country_b == a)
+        })
+    }
+    
+    /// Get all trade routes
+    pub fn trade_routes(&self) -> &[TradeRoute] {
+        &self.trade_routes
+    }
+    
+    /// Get the tech tree countries research through
+    pub fn tech_tree(&self) -> &TechTree {
+        &self.tech_tree
+    }
+    
+    /// Replace the tech tree; node indices change meaning, so all research is forgotten
+    pub fn set_tech_tree(&mut self, tech_tree: TechTree) {
+        self.tech_tree = tech_tree;
+        for country in self.countries.values_mut() {
+            country.researched.clear();
+            country.invalidate_scores();
+        }
+    }
+    
     /// Get current tick
     pub fn get_tick(&self) -> u64 {
         self.tick
@@ -84,15 +349,29 @@ impl WorldState {
         country_ids.sort(); // Ensure deterministic order
         
         for &id in &country_ids {
-            if let Some(country) = self.countries.get(&id) {
-                let ti = compute_threat_index(country, self, luts);
-                if let Some(country_mut) = self.countries.get_mut(&id) {
+            self.update_threat_index(id, luts);
+        }
+    }
+    
+    /// Update one country's threat index; it depends only on the neighbors'
+    /// strength, so countries can be updated in any order
+    pub fn update_threat_index(&mut self, id: u32, luts: &LookupTables) {
+        if let Some(country) = self.countries.get(&id) {
+            let ti = compute_threat_index(country, self, luts);
+            if let Some(country_mut) = self.countries.get_mut(&id) {
+                if country_mut.threat_index != ti {
                     country_mut.threat_index = ti;
+                    country_mut.invalidate_scores();
                 }
             }
         }
     }
     
+    /// Remove every country, keeping the map's capacity for the next ones
+    pub fn drain_countries(&mut self) -> impl Iterator<Item = Country> + '_ {
+        self.countries.drain().map(|(_, country)| country)
+    }
+    
     /// Update all countries' adaptive weights
     pub fn update_weights(&mut self) {
         for country in self.countries.values_mut() {
@@ -106,10 +385,40 @@ impl WorldState {
             let tech_level = country.tech_level;
             let prestige = country.prestige;
             
-            country.weights.update(resources, threat_index, growth, ally_count, recent_losses);
+            country.weights.update(resources, threat_index, growth, ally_count, recent_losses, &country.personality);
             country.marginal_values.update(m_eff, gdp, tech_level, prestige);
         }
     }
+    
+    /// Mark the given countries' cached scores stale
+    pub fn invalidate_scores_of(&mut self, ids: &[u32]) {
+        for id in ids {
+            if let Some(country) = self.countries.get_mut(id) {
+                country.invalidate_scores();
+            }
+        }
+    }
+    
+    /// Mark every country's cached scores stale
+    pub fn invalidate_all_scores(&mut self) {
+        for country in self.countries.values_mut() {
+            country.invalidate_scores();
+        }
+    }
+    
+    /// Spread staleness one hop: a country bordering a changed country rescores too
+    pub fn propagate_score_invalidation(&mut self) {
+        let dirty: HashSet<u32> = self.countries
+            .values()
+            .filter(|country| country.scores_dirty())
+            .map(|country| country.id)
+            .collect();
+        for country in self.countries.values_mut() {
+            if country.edges.iter().any(|edge| dirty.contains(&edge.neighbor_id)) {
+                country.invalidate_scores();
+            }
+        }
+    }
 }
 
 impl Default for WorldState {
@@ -120,6 +429,7 @@ impl Default for WorldState {
 
 /// Decision log entry for telemetry (§9)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
 pub struct DecisionLog {
     pub tick: u64,
     pub country_id: u32,
@@ -130,51 +440,176 @@ pub struct DecisionLog {
     pub rejected_actions: Vec<(String, f32)>,  // Top 1-2 rejected with scores
 }
 
+impl DecisionLog {
+    pub const CSV_HEADER: &'static str = "tick,country_id,chosen_action,score,\
+                                          delta_res,delta_sec,delta_growth,delta_pos,cost,risk,\
+                                          alpha,beta,gamma,delta,kappa,rho,\
+                                          rejected_1,rejected_1_score,rejected_2,rejected_2_score";
+    
+    /// One CSV line in `CSV_HEADER` order; missing rejected actions are left empty
+    pub fn to_csv_row(&self) -> String {
+        let rejected = |index: usize| match self.rejected_actions.get(index) {
+            Some((action, score)) => format!("{},{}", csv_text(action), score),
+            None => ",".to_string(),
+        };
+        let (c, w) = (&self.components, &self.weights);
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.tick,
+            self.country_id,
+            csv_text(&self.chosen_action),
+            self.score,
+            c.delta_res,
+            c.delta_sec,
+            c.delta_growth,
+            c.delta_pos,
+            c.cost,
+            c.risk,
+            w.alpha,
+            w.beta,
+            w.gamma,
+            w.delta,
+            w.kappa,
+            w.rho,
+            rejected(0),
+            rejected(1),
+        )
+    }
+}
+
+/// Quote a CSV text field if it contains a separator, quote or line break
+fn csv_text(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// How often countries reused last tick's scores instead of rescoring (§9)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoreCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl ScoreCacheStats {
+    /// Fraction of country decisions served from the cache; 0 before any tick
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f32 / total as f32
+    }
+}
+
+/// One scored candidate of a decision, with each component's weighted contribution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplainedCandidate {
+    pub action: Action,
+    pub description: String,
+    pub score: f32,
+    pub components: ScoreComponents,
+    pub contributions: ScoreComponents,  // Weighted components; cost and risk are negative
+    pub chosen: bool,
+}
+
+/// Everything a country weighed in its last decision: the whole scored shortlist, best first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionExplanation {
+    pub tick: u64,
+    pub country_id: u32,
+    pub weights: AdaptiveWeights,
+    pub candidates: Vec<ExplainedCandidate>,
+}
+
+/// A country's most recent shortlist and scores, kept for `explain_decision`
+struct LastDecision {
+    tick: u64,
+    shortlist: Vec<Action>,
+    batch: BatchScoreResult,
+    weights: AdaptiveWeights,
+    chosen: Option<usize>,
+}
+
+/// One country's output from the scoring phase of a tick
+struct CountryScores {
+    country_id: u32,
+    shortlist: Vec<Action>,
+    batch: BatchScoreResult,
+    cache_hit: bool,
+}
+
 /// AI Decision System - main coordinator (§6, §10)
-#[wasm_bindgen]
+#[cfg_attr(feature = "bindings", wasm_bindgen)]
 pub struct DecisionSystem {
     world: WorldState,
     luts: LookupTables,
     pruning_config: PruningConfig,
-    logs: Vec<DecisionLog>,
+    pruning_overrides: HashMap<u32, PruningConfig>,  // Per-country replacements for pruning_config
+    logs: VecDeque<DecisionLog>,  // Ring buffer of the newest log_capacity entries
+    log_capacity: usize,
+    dropped_logs: u64,  // Entries evicted from the full buffer
+    deltas: Vec<WorldDelta>,  // Changes made by the last tick
+    selection_policy: SelectionPolicy,
+    rng: XorShiftRng,  // Drives stochastic selection policies; seeded from rng_seed
     rng_seed: u64,
+    cache_stats: ScoreCacheStats,
+    last_decisions: HashMap<u32, LastDecision>,  // Each country's latest decision, for explanations
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "bindings", wasm_bindgen)]
 impl DecisionSystem {
     /// Create a new decision system
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "bindings", wasm_bindgen(constructor))]
     pub fn new() -> Self {
         Self {
             world: WorldState::new(),
             luts: LookupTables::new(),
             pruning_config: PruningConfig::new(),
-            logs: Vec::new(),
+            pruning_overrides: HashMap::new(),
+            logs: VecDeque::new(),
+            log_capacity: DEFAULT_LOG_CAPACITY,
+            dropped_logs: 0,
+            deltas: Vec::new(),
+            selection_policy: SelectionPolicy::default(),
+            rng: XorShiftRng::new(12345),
             rng_seed: 12345,
+            cache_stats: ScoreCacheStats::default(),
+            last_decisions: HashMap::new(),
         }
     }
     
     /// Initialize with custom seed for determinism
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
     pub fn init(seed: u64) -> Self {
         Self {
             world: WorldState::new(),
             luts: LookupTables::new(),
             pruning_config: PruningConfig::new(),
-            logs: Vec::new(),
+            pruning_overrides: HashMap::new(),
+            logs: VecDeque::new(),
+            log_capacity: DEFAULT_LOG_CAPACITY,
+            dropped_logs: 0,
+            deltas: Vec::new(),
+            selection_policy: SelectionPolicy::default(),
+            rng: XorShiftRng::new(seed),
             rng_seed: seed,
+            cache_stats: ScoreCacheStats::default(),
+            last_decisions: HashMap::new(),
         }
     }
     
     /// Add a country to the world
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
     pub fn add_country(&mut self, id: u32) {
         let country = Country::new(id);
         self.world.add_country(country);
     }
     
     /// Add an edge between two countries
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
     pub fn add_edge(&mut self, from_id: u32, to_id: u32, distance: usize, hostility: f32) {
         if let Some(country) = self.world.get_country_mut(from_id) {
             let mut edge = CountryEdge::new(to_id);
@@ -184,198 +619,517 @@ impl DecisionSystem {
         }
     }
     
-    /// Execute one tick of the decision system (§6)
-    #[wasm_bindgen]
-    pub fn tick(&mut self) {
-        // 1. Update weights
-        self.world.update_weights();
-        
-        // 2. Update local fields (TI, caches)
-        self.world.update_threat_indices(&self.luts);
-        
-        // 2.5. First pass: identify potential attacks to detect countries under attack
-        let mut countries_under_attack: HashSet<u32> = HashSet::new();
-        let mut country_ids: Vec<u32> = self.world.countries().keys().copied().collect();
-        country_ids.sort(); // Ensure deterministic order
-        
-        for country_id in &country_ids {
-            if let Some(country) = self.world.get_country(*country_id) {
-                // Quick scan of shortlist to detect attack actions
-                let shortlist = generate_shortlist(
-                    *country_id,
-                    country,
-                    &self.world,
-                    &self.pruning_config,
-                );
-                
-                for action in &shortlist {
-                    if let Action::Attack { target_id } = action {
-                        countries_under_attack.insert(*target_id);
-                    }
-                }
+    /// Remove a country, its alliances and all edges to it; returns whether it existed
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn remove_country(&mut self, id: u32) -> bool {
+        self.pruning_overrides.remove(&id);
+        self.last_decisions.remove(&id);
+        self.world.remove_country(id).is_some()
+    }
+    
+    /// Remove the edge from one country to another; returns whether it existed
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn remove_edge(&mut self, from_id: u32, to_id: u32) -> bool {
+        self.world
+            .get_country_mut(from_id)
+            .is_some_and(|country| country.remove_edge(to_id))
+    }
+    
+    /// Change an existing edge's hostility and distance bucket; returns whether it existed
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn update_edge(&mut self, from_id: u32, to_id: u32, hostility: f32, distance: usize) -> bool {
+        match self.world.get_country_mut(from_id).and_then(|country| country.get_edge_mut(to_id)) {
+            Some(edge) => {
+                edge.hostility = hostility;
+                edge.distance_bucket = distance;
+                true
             }
+            None => false,
         }
-        
-        // 2.6. Apply defensive boost to countries under attack
-        for country_id in &countries_under_attack {
-            if let Some(country) = self.world.get_country_mut(*country_id) {
-                country.weights.apply_defensive_boost();
-            }
+    }
+    
+    /// Overwrite a country's core stats from a JSON object such as
+    /// `{"m_eff": 150, "resources": 800}`; omitted stats are left unchanged
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn set_country_stats(&mut self, id: u32, json: &str) -> Result<(), SimError> {
+        let stats: CountryStats = serde_json::from_str(json)?;
+        if let Some(name) = stats.first_non_finite() {
+            return Err(SimError::InvalidInput(format!("Country stat {} must be finite", name)));
         }
-        
-        // 3-5. Build shortlist, score, and choose for each country
-        let mut decisions: HashMap<u32, (Action, f32, ScoreComponents)> = HashMap::new();
-        
-        for country_id in country_ids {
-            if let Some(country) = self.world.get_country(country_id) {
-                // 3. Build shortlist
-                let shortlist = generate_shortlist(
-                    country_id,
-                    country,
-                    &self.world,
-                    &self.pruning_config,
-                );
-                
-                // 4. Score each action
-                let mut best_action = Action::Pass;
-                let mut best_score = f32::NEG_INFINITY;
-                let mut best_components = ScoreComponents::zero();
-                let mut scored_actions = Vec::new();
-
-                let batch = score_actions_batch(country, &shortlist, &self.world, &self.luts);
-                for (idx, action) in shortlist.iter().enumerate() {
-                    let components = &batch.components[idx];
-                    let score = batch.final_scores[idx];
-
-                    scored_actions.push((action.description(), score));
-
-                    if score > best_score {
-                        best_score = score;
-                        best_action = action.clone();
-                        best_components = components.clone();
-                    }
-                }
-                
-                // 5. Choose action (argmax)
-                decisions.insert(country_id, (best_action.clone(), best_score, best_components.clone()));
-                
-                // 7. Log telemetry
-                let mut rejected = scored_actions
-                    .into_iter()
-                    .filter(|(desc, _)| desc != &best_action.description())
-                    .collect::<Vec<_>>();
-                rejected.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-                rejected.truncate(2);  // Top 2 rejected
-                
-                self.logs.push(DecisionLog {
-                    tick: self.world.tick,
-                    country_id,
-                    chosen_action: best_action.description(),
-                    score: best_score,
-                    components: best_components,
-                    weights: country.weights.clone(),
-                    rejected_actions: rejected,
-                });
-            }
+        let country = self
+            .world
+            .get_country_mut(id)
+            .ok_or_else(|| SimError::InvalidInput(format!("Unknown country {}", id)))?;
+        stats.apply(country);
+        Ok(())
+    }
+    
+    /// Set a country's personality from a JSON object such as
+    /// `{"aggression": 0.9, "loyalty": 0.2}`; omitted traits are neutral (0.5)
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn set_personality(&mut self, id: u32, json: &str) -> Result<(), SimError> {
+        let personality: Personality = serde_json::from_str(json)?;
+        personality.validate()?;
+        let country = self
+            .world
+            .get_country_mut(id)
+            .ok_or_else(|| SimError::InvalidInput(format!("Unknown country {}", id)))?;
+        country.personality = personality;
+        Ok(())
+    }
+    
+    /// Get a country's personality, or null for an unknown id
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen]
+    pub fn get_personality(&self, id: u32) -> JsValue {
+        match self.world.get_country(id) {
+            Some(country) => serde_wasm_bindgen::to_value(&country.personality).unwrap_or(JsValue::NULL),
+            None => JsValue::NULL,
         }
-        
-        // 6. Apply actions and emit deltas
-        self.apply_actions(decisions);
-        
-        // Increment tick
-        self.world.tick += 1;
+    }
+    
+    /// Replace the shortlist sizes used by countries without their own override,
+    /// e.g. `{"k_attack": 1}`; omitted sizes reset to their defaults
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn set_pruning_config(&mut self, json: &str) -> Result<(), SimError> {
+        self.pruning_config = serde_json::from_str(json)?;
+        self.world.invalidate_all_scores();
+        Ok(())
+    }
+    
+    /// Give one country its own shortlist sizes in place of the shared config
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn set_country_pruning_config(&mut self, id: u32, json: &str) -> Result<(), SimError> {
+        if self.world.get_country(id).is_none() {
+            return Err(SimError::InvalidInput(format!("Unknown country {}", id)));
+        }
+        let config: PruningConfig = serde_json::from_str(json)?;
+        self.pruning_overrides.insert(id, config);
+        self.world.invalidate_scores_of(&[id]);
+        Ok(())
+    }
+    
+    /// Return a country to the shared pruning config; returns whether it had an override
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn clear_country_pruning_config(&mut self, id: u32) -> bool {
+        self.world.invalidate_scores_of(&[id]);
+        self.pruning_overrides.remove(&id).is_some()
+    }
+    
+    /// Get the pruning config a country's shortlists are built with
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen(unchecked_return_type = "PruningConfig")]
+    pub fn get_pruning_config(&self, id: u32) -> JsValue {
+        serde_wasm_bindgen::to_value(self.pruning_config_for(id)).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Choose how countries pick among scored actions, e.g. `{"kind": "argmax"}`,
+    /// `{"kind": "epsilon_greedy", "epsilon": 0.1}` or `{"kind": "softmax", "temperature": 2.0}`
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn set_selection_policy(&mut self, json: &str) -> Result<(), SimError> {
+        let policy: SelectionPolicy = serde_json::from_str(json)?;
+        policy.validate()?;
+        self.selection_policy = policy;
+        Ok(())
+    }
+    
+    /// Get the current selection policy
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen(unchecked_return_type = "SelectionPolicy")]
+    pub fn get_selection_policy(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.selection_policy).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Execute one tick of the decision system (§6); with the `parallel`
+    /// feature countries are scored across threads, then applied in id order
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn tick(&mut self) {
+        self.run_tick(true);
     }
     
     /// Get current tick
-    #[wasm_bindgen]
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
     pub fn get_tick(&self) -> u64 {
         self.world.get_tick()
     }
     
-    /// Get decision logs as JSON
-    #[wasm_bindgen]
+    /// Get decision logs as JSON, oldest first
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen(unchecked_return_type = "DecisionLog[]")]
     pub fn get_logs(&self) -> JsValue {
         serde_wasm_bindgen::to_value(&self.logs).unwrap_or(JsValue::NULL)
     }
     
+    /// Get one country's decision logs as JSON, oldest first
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen(unchecked_return_type = "DecisionLog[]")]
+    pub fn get_country_logs(&self, country_id: u32) -> JsValue {
+        let logs: Vec<&DecisionLog> = self.logs.iter().filter(|log| log.country_id == country_id).collect();
+        serde_wasm_bindgen::to_value(&logs).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Get decision logs as JSON and clear them, so each entry is exported once
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen(unchecked_return_type = "DecisionLog[]")]
+    pub fn drain_logs(&mut self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.take_logs()).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Get decision logs as CSV with a header line, optionally for one country only
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn get_logs_csv(&self, country_id: Option<u32>) -> String {
+        let mut csv = String::from(DecisionLog::CSV_HEADER);
+        csv.push('\n');
+        for log in self.logs.iter().filter(|log| country_id.is_none_or(|id| log.country_id == id)) {
+            csv.push_str(&log.to_csv_row());
+            csv.push('\n');
+        }
+        csv
+    }
+    
+    /// Keep at most `capacity` logs, dropping the oldest first; 0 disables logging
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn set_log_capacity(&mut self, capacity: usize) {
+        self.log_capacity = capacity;
+        while self.logs.len() > capacity {
+            self.logs.pop_front();
+            self.dropped_logs += 1;
+        }
+    }
+    
+    /// Get the most logs kept at once
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn get_log_capacity(&self) -> usize {
+        self.log_capacity
+    }
+    
+    /// Get how many logs were dropped because the buffer was full
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn get_dropped_log_count(&self) -> u64 {
+        self.dropped_logs
+    }
+    
     /// Get world state snapshot as JSON
+    #[cfg(feature = "bindings")]
     #[wasm_bindgen]
     pub fn get_world_snapshot(&self) -> JsValue {
         serde_wasm_bindgen::to_value(&self.world).unwrap_or(JsValue::NULL)
     }
     
-    /// Clear logs (for memory management)
+    /// Replace the tech tree with nodes from JSON such as
+    /// `[{"id": "iron", "prerequisites": ["bronze"], "cost": 40,
+    ///    "modifiers": [{"stat": "military_strength", "scale": 1.1}]}]`;
+    /// countries lose everything researched under the old tree
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn load_tech_tree(&mut self, json: &str) -> Result<(), SimError> {
+        let nodes: Vec<TechNode> = serde_json::from_str(json)?;
+        self.world.set_tech_tree(TechTree::new(nodes)?);
+        Ok(())
+    }
+    
+    /// Replace the world with one described in JSON, e.g.
+    /// `{"countries": [{"id": 1, "stats": {"m_eff": 150}, "edges": [{"neighbor_id": 2, "hostility": 0.6}]},
+    ///   {"id": 2, "border_tiles": [{"id": 1, "position_x": 0, "position_y": 3}]}],
+    ///   "alliances": [], "wars": [[1, 2]]}`; the world restarts at tick 0 with the
+    /// current tech tree and alliance duration, and scenario alliances are permanent
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn load_scenario(&mut self, json: &str) -> Result<(), SimError> {
+        let scenario: Scenario = serde_json::from_str(json)?;
+        let mut world = scenario.to_world()?;
+        world.set_tech_tree(self.world.tech_tree().clone());
+        world.set_alliance_duration(self.world.alliance_duration());
+        self.world = world;
+        
+        let world = &self.world;
+        self.pruning_overrides.retain(|id, _| world.get_country(*id).is_some());
+        self.deltas.clear();
+        self.last_decisions.clear();
+        Ok(())
+    }
+    
+    /// Describe the current world in the JSON format `load_scenario` reads
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn export_scenario(&self) -> Result<String, SimError> {
+        serde_json::to_string(&Scenario::from_world(&self.world))
+            .map_err(|err| SimError::Serialization(err.to_string()))
+    }
+    
+    /// Get the tech tree's nodes as JSON, in index order
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen]
+    pub fn get_tech_tree(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.world.tech_tree()).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Get the trade graph as JSON: one entry per route with its partners,
+    /// income, GDP bonus and whether a war has disrupted it
+    #[cfg(feature = "bindings")]
     #[wasm_bindgen]
+    pub fn get_trade_network(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(self.world.trade_routes()).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Make alliances formed from now on last `ticks` ticks; None makes them permanent
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn set_alliance_duration(&mut self, ticks: Option<u64>) {
+        self.world.set_alliance_duration(ticks);
+    }
+    
+    /// Get the changes made by the last tick as JSON
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen]
+    pub fn get_deltas(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.deltas).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Clear logs (for memory management)
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
     pub fn clear_logs(&mut self) {
         self.logs.clear();
     }
     
-    /// Get the RNG seed used for deterministic behavior
+    /// Explain a country's last decision as JSON: every shortlisted action, best first,
+    /// with its score, raw components, weighted contributions and whether it was chosen;
+    /// null if the country hasn't decided yet
+    #[cfg(feature = "bindings")]
     #[wasm_bindgen]
+    pub fn explain_last_decision(&self, country_id: u32) -> JsValue {
+        match self.explain_decision(country_id) {
+            Some(explanation) => serde_wasm_bindgen::to_value(&explanation).unwrap_or(JsValue::NULL),
+            None => JsValue::NULL,
+        }
+    }
+    
+    /// Get score cache hits and misses as JSON
+    #[cfg(feature = "bindings")]
+    #[wasm_bindgen]
+    pub fn get_score_cache_stats(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.cache_stats).unwrap_or(JsValue::NULL)
+    }
+    
+    /// Fraction of country decisions that reused cached scores
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
+    pub fn get_score_cache_hit_rate(&self) -> f32 {
+        self.cache_stats.hit_rate()
+    }
+    
+    /// Get the RNG seed used for deterministic behavior
+    #[cfg_attr(feature = "bindings", wasm_bindgen)]
     pub fn get_rng_seed(&self) -> u64 {
         self.rng_seed
     }
 }
 
+impl Default for DecisionSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Non-WASM methods
 impl DecisionSystem {
-    /// Apply all chosen actions to world state
-    fn apply_actions(&mut self, decisions: HashMap<u32, (Action, f32, ScoreComponents)>) {
-        for (country_id, (action, _score, components)) in decisions {
-            self.apply_action(country_id, &action, &components);
-        }
+    /// Pruning config for a country: its override if set, otherwise the shared one
+    fn pruning_config_for(&self, country_id: u32) -> &PruningConfig {
+        self.pruning_overrides.get(&country_id).unwrap_or(&self.pruning_config)
     }
     
-    /// Apply a single action
-    fn apply_action(&mut self, country_id: u32, action: &Action, components: &ScoreComponents) {
-        match action {
-            Action::Attack { target_id: _ } => {
-                // Simple implementation: apply resource and security changes
-                if let Some(country) = self.world.get_country_mut(country_id) {
-                    country.resources += components.delta_res * 50.0;  // Denormalize
-                    country.resources = country.resources.max(0.0);
-                }
-            }
-            Action::Invest { sector: _ } => {
-                if let Some(country) = self.world.get_country_mut(country_id) {
-                    // Apply growth
-                    country.growth += components.delta_growth * 0.1;
-                    country.resources -= components.cost * 20.0;  // Denormalize cost
-                    country.resources = country.resources.max(0.0);
-                }
-            }
-            Action::Research { tech: _ } => {
-                if let Some(country) = self.world.get_country_mut(country_id) {
-                    // Apply tech advancement
-                    country.tech_level += 0.1;
-                    country.resources -= components.cost * 20.0;
-                    country.resources = country.resources.max(0.0);
-                }
-            }
-            Action::Ally { target_id } => {
-                // Form alliance
-                self.world.add_alliance(country_id, *target_id);
-            }
-            Action::Pact { .. } | Action::Trade { .. } => {
-                // Update relations/resources
-                if let Some(country) = self.world.get_country_mut(country_id) {
-                    country.resources += components.delta_res * 50.0;
-                }
-            }
-            Action::Fortify { tile_id } => {
-                if let Some(country) = self.world.get_country_mut(country_id) {
-                    if let Some(tile) = country.border_tiles.iter_mut().find(|t| t.id == *tile_id) {
-                        tile.fortification += 0.5;
-                    }
-                }
-            }
-            Action::Move { .. } => {
-                // Movement logic (simplified)
+    /// One tick: parallel (when enabled) read-only scoring, then serial selection and application
+    fn run_tick(&mut self, parallel: bool) {
+        // 0. End alliances whose term is over
+        self.deltas.clear();
+        for (country_a, country_b) in self.world.expire_alliances() {
+            self.deltas.push(WorldDelta::AllianceExpired { country_a, country_b });
+        }
+        
+        // 1. Update weights and war exhaustion
+        self.world.update_weights();
+        self.world.update_war_exhaustion();
+        
+        // 2. Update local fields (TI, caches)
+        self.world.update_threat_indices(&self.luts);
+        
+        // 2.4. Countries bordering a changed country can't reuse their cached scores
+        self.world.propagate_score_invalidation();
+        
+        // 3-4. Build shortlists and score them; countries only read the world here
+        let mut country_ids: Vec<u32> = self.world.countries().keys().copied().collect();
+        country_ids.sort(); // Ensure deterministic order
+        let scored = self.score_countries(&country_ids, parallel);
+        
+        // 4.5. Apply defensive boost to countries under attack; weights only
+        // rescale scores, so this goes around get_country_mut to keep the cache
+        let countries_under_attack: HashSet<u32> = scored
+            .iter()
+            .flat_map(|entry| &entry.shortlist)
+            .filter_map(|action| match action {
+                Action::Attack { target_id } => Some(*target_id),
+                _ => None,
+            })
+            .collect();
+        for country_id in &countries_under_attack {
+            if let Some(country) = self.world.countries.get_mut(country_id) {
+                country.weights.apply_defensive_boost();
             }
-            Action::Pass => {
-                // No action
+        }
+        
+        // 5. Choose with the selection policy, serially so RNG draws stay in id order
+        let mut decisions: Vec<(u32, Action)> = Vec::with_capacity(scored.len());
+        for mut entry in scored {
+            let Some(country) = self.world.countries.get_mut(&entry.country_id) else {
+                continue;
+            };
+            entry.batch.reweight(&country.weights);
+            if entry.cache_hit {
+                self.cache_stats.hits += 1;
+            } else {
+                self.cache_stats.misses += 1;
+                country.score_cache = Some(ScoreCache {
+                    shortlist: entry.shortlist.clone(),
+                    result: entry.batch.clone(),
+                });
             }
+            
+            let (shortlist, batch) = (&entry.shortlist, &entry.batch);
+            let chosen = self.selection_policy.select(shortlist, &batch.final_scores, &mut self.rng);
+            let (best_action, best_score, best_components) = match chosen {
+                Some(idx) => (shortlist[idx].clone(), batch.final_scores[idx], batch.components[idx].clone()),
+                None => (Action::Pass, f32::NEG_INFINITY, ScoreComponents::zero()),
+            };
+            
+            // 7. Log telemetry
+            let mut rejected = shortlist
+                .iter()
+                .zip(&batch.final_scores)
+                .enumerate()
+                .filter(|(idx, _)| Some(*idx) != chosen)
+                .map(|(_, (action, score))| (action.description(), *score))
+                .collect::<Vec<_>>();
+            rejected.sort_by(|a, b| b.1.total_cmp(&a.1));
+            rejected.truncate(2);  // Top 2 rejected
+            
+            let log = DecisionLog {
+                tick: self.world.tick,
+                country_id: entry.country_id,
+                chosen_action: best_action.description(),
+                score: best_score,
+                components: best_components,
+                weights: country.weights.clone(),
+                rejected_actions: rejected,
+            };
+            let last = LastDecision {
+                tick: self.world.tick,
+                shortlist: entry.shortlist,
+                batch: entry.batch,
+                weights: country.weights.clone(),
+                chosen,
+            };
+            self.last_decisions.insert(entry.country_id, last);
+            self.record_log(log);
+            decisions.push((entry.country_id, best_action));
+        }
+        
+        // 6. Apply actions and emit deltas; anything they change drops the fresh caches
+        self.apply_actions(decisions);
+        
+        // Increment tick
+        self.world.tick += 1;
+    }
+    
+    /// Shortlist and scores for each country, in the order given
+    fn score_countries(&self, country_ids: &[u32], parallel: bool) -> Vec<CountryScores> {
+        #[cfg(feature = "parallel")]
+        if parallel {
+            return country_ids.par_iter().filter_map(|&id| self.score_country(id)).collect();
+        }
+        #[cfg(not(feature = "parallel"))]
+        let _ = parallel;  // Without rayon scoring is always serial
+        country_ids.iter().filter_map(|&id| self.score_country(id)).collect()
+    }
+    
+    /// Reuse a country's cached shortlist and scores, or build and score a fresh shortlist
+    fn score_country(&self, country_id: u32) -> Option<CountryScores> {
+        let country = self.world.get_country(country_id)?;
+        let config = self.pruning_config_for(country_id);
+        // Lookahead scores depend on countries beyond the invalidation radius, so never reuse them
+        if let (Some(cache), 0) = (&country.score_cache, config.lookahead_depth) {
+            return Some(CountryScores {
+                country_id,
+                shortlist: cache.shortlist.clone(),
+                batch: cache.result.clone(),
+                cache_hit: true,
+            });
+        }
+        let shortlist = generate_shortlist(country_id, country, &self.world, config);
+        let mut batch = score_actions_batch(country, &shortlist, &self.world, &self.luts);
+        if config.lookahead_depth > 0 {
+            apply_attack_lookahead(country_id, &shortlist, &mut batch.components, &self.world, &self.luts, config);
+            batch.reweight(&country.weights);
         }
+        Some(CountryScores { country_id, shortlist, batch, cache_hit: false })
+    }
+    
+    /// Pay out trade routes, then apply the chosen actions in country id order
+    fn apply_actions(&mut self, decisions: Vec<(u32, Action)>) {
+        collect_trade_income(&mut self.world, &mut self.deltas);
+        for (country_id, action) in decisions {
+            resolve_action(&mut self.world, country_id, &action, &self.luts, &mut self.deltas);
+        }
+    }
+    
+    /// Append a log entry, evicting the oldest once the buffer is full
+    fn record_log(&mut self, log: DecisionLog) {
+        if self.log_capacity == 0 {
+            self.dropped_logs += 1;
+            return;
+        }
+        if self.logs.len() == self.log_capacity {
+            self.logs.pop_front();
+            self.dropped_logs += 1;
+        }
+        self.logs.push_back(log);
+    }
+    
+    /// The full scored shortlist behind a country's last decision, or None
+    /// if it hasn't decided since it was added or the world was loaded
+    pub fn explain_decision(&self, country_id: u32) -> Option<DecisionExplanation> {
+        let last = self.last_decisions.get(&country_id)?;
+        let mut candidates: Vec<ExplainedCandidate> = last
+            .shortlist
+            .iter()
+            .zip(&last.batch.final_scores)
+            .zip(&last.batch.components)
+            .enumerate()
+            .map(|(idx, ((action, &score), components))| ExplainedCandidate {
+                action: action.clone(),
+                description: action.description(),
+                score,
+                components: components.clone(),
+                contributions: components.weighted(&last.weights),
+                chosen: Some(idx) == last.chosen,
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Some(DecisionExplanation {
+            tick: last.tick,
+            country_id,
+            weights: last.weights.clone(),
+            candidates,
+        })
+    }
+    
+    /// Remove and return every log entry, oldest first
+    pub fn take_logs(&mut self) -> Vec<DecisionLog> {
+        self.logs.drain(..).collect()
+    }
+    
+    /// Changes made by the last tick
+    pub fn deltas(&self) -> &[WorldDelta] {
+        &self.deltas
+    }
+    
+    /// Score cache hits and misses since the system was created
+    pub fn score_cache_stats(&self) -> ScoreCacheStats {
+        self.cache_stats
     }
 }
 
@@ -414,6 +1168,503 @@ mod tests {
         assert!(world.are_allies(2, 1));  // Symmetric
     }
 
+    #[test]
+    fn test_remove_country_cleans_up_relationships() {
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        system.add_country(2);
+        system.add_country(3);
+        system.add_edge(1, 2, 1, 0.5);
+        system.add_edge(3, 2, 1, 0.5);
+        system.add_edge(2, 1, 1, 0.5);
+        system.world.add_alliance(1, 2);
+        
+        assert!(system.remove_country(2));
+        assert!(!system.remove_country(2));
+        
+        assert!(system.world.get_country(2).is_none());
+        assert!(!system.world.are_allies(1, 2));
+        assert_eq!(system.world.get_country(1).unwrap().ally_count, 0);
+        assert!(system.world.get_country(1).unwrap().edges.is_empty());
+        assert!(system.world.get_country(3).unwrap().edges.is_empty());
+        
+        // Ticking after removal only logs the remaining countries
+        system.tick();
+        assert_eq!(system.logs.len(), 2);
+    }
+
+    #[test]
+    fn test_edge_mutation() {
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        system.add_country(2);
+        system.add_edge(1, 2, 1, 0.2);
+        
+        assert!(system.update_edge(1, 2, 0.9, 3));
+        let edge = system.world.get_country(1).unwrap().get_edge(2).unwrap();
+        assert_eq!(edge.hostility, 0.9);
+        assert_eq!(edge.distance_bucket, 3);
+        
+        assert!(!system.update_edge(2, 1, 0.9, 3));  // No such edge
+        assert!(!system.update_edge(7, 2, 0.9, 3));  // No such country
+        
+        assert!(system.remove_edge(1, 2));
+        assert!(!system.remove_edge(1, 2));
+        assert!(system.world.get_country(1).unwrap().get_edge(2).is_none());
+    }
+
+    #[test]
+    fn test_set_country_stats() {
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        
+        system.set_country_stats(1, r#"{"m_eff": 300.0, "tech_level": 4.5}"#).unwrap();
+        let country = system.world.get_country(1).unwrap();
+        assert_eq!(country.m_eff, 300.0);
+        assert_eq!(country.tech_level, 4.5);
+        assert_eq!(country.resources, 500.0);
+        
+        assert!(matches!(
+            system.set_country_stats(2, r#"{"gdp": 1.0}"#),
+            Err(SimError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            system.set_country_stats(1, r#"{"gdp": "lots"}"#),
+            Err(SimError::MalformedInput(_))
+        ));
+        assert!(matches!(
+            system.set_country_stats(1, r#"{"gdp": 1e300}"#),
+            Err(SimError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_personality_biases_weights_and_shortlists() {
+        let mut system = DecisionSystem::new();
+        for id in 1..=2 {
+            system.add_country(id);
+        }
+        system.add_edge(1, 2, 1, 0.3);
+        system.add_edge(2, 1, 1, 0.3);
+        
+        let declares_war = |system: &DecisionSystem| {
+            let country = system.world.get_country(1).unwrap();
+            generate_shortlist(1, country, &system.world, system.pruning_config_for(1))
+                .contains(&Action::DeclareWar { target_id: 2 })
+        };
+        assert!(!declares_war(&system));
+        
+        system.set_personality(1, r#"{"aggression": 0.9, "risk_aversion": 1.0}"#).unwrap();
+        assert!(declares_war(&system));
+        system.world.update_weights();
+        let bold = &system.world.get_country(1).unwrap().weights;
+        let neutral = &system.world.get_country(2).unwrap().weights;
+        assert!(bold.kappa < neutral.kappa);
+        assert!(bold.rho > neutral.rho);
+        assert_eq!(bold.alpha, neutral.alpha);  // Omitted traits stay neutral
+        
+        assert!(matches!(system.set_personality(1, r#"{"loyalty": 1.5}"#), Err(SimError::InvalidInput(_))));
+        assert!(matches!(system.set_personality(1, r#"{"greed": 1.0}"#), Err(SimError::MalformedInput(_))));
+        assert!(matches!(system.set_personality(9, "{}"), Err(SimError::InvalidInput(_))));
+        assert_eq!(system.world.get_country(1).unwrap().personality.aggression, 0.9);
+    }
+
+    #[test]
+    fn test_per_country_pruning_overrides() {
+        let mut system = DecisionSystem::new();
+        for id in 1..=4 {
+            system.add_country(id);
+        }
+        for to in 2..=4 {
+            system.add_edge(1, to, 1, 0.5);
+            system.add_edge(to, 1, 1, 0.5);
+        }
+        
+        let attacks = |system: &DecisionSystem, id: u32| {
+            let country = system.world.get_country(id).unwrap();
+            generate_shortlist(id, country, &system.world, system.pruning_config_for(id))
+                .iter()
+                .filter(|action| matches!(action, Action::Attack { .. }))
+                .count()
+        };
+        assert_eq!(attacks(&system, 1), 3);
+        
+        system.set_pruning_config(r#"{"k_attack": 2}"#).unwrap();
+        assert_eq!(attacks(&system, 1), 2);
+        assert_eq!(system.pruning_config_for(1).k_fortify, 3);  // Omitted sizes keep defaults
+        
+        system.set_country_pruning_config(1, r#"{"k_attack": 0}"#).unwrap();
+        assert_eq!(attacks(&system, 1), 0);
+        assert_eq!(system.pruning_config_for(2).k_attack, 2);
+        
+        assert!(system.clear_country_pruning_config(1));
+        assert!(!system.clear_country_pruning_config(1));
+        assert_eq!(attacks(&system, 1), 2);
+        
+        assert!(matches!(
+            system.set_country_pruning_config(9, "{}"),
+            Err(SimError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            system.set_pruning_config(r#"{"k_attacks": 1}"#),
+            Err(SimError::MalformedInput(_))
+        ));
+        assert_eq!(system.pruning_config_for(1).k_attack, 2);  // Rejected input leaves the config alone
+        
+        system.set_country_pruning_config(2, r#"{"k_invest": 0}"#).unwrap();
+        system.remove_country(2);
+        assert!(system.pruning_overrides.is_empty());
+    }
+
+    #[test]
+    fn test_tick_emits_world_deltas() {
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        system.add_country(2);
+        system.add_edge(1, 2, 1, 0.5);
+        system.add_edge(2, 1, 1, 0.5);
+        system.world.add_trade_route(1, 2, 4.0, 0.0);
+        
+        system.tick();
+        
+        let deltas = system.deltas();
+        assert_eq!(deltas[0], WorldDelta::TradeIncome { country_a: 1, country_b: 2, income: 4.0 });
+        assert!(deltas.len() > 1);  // Both countries act on their choices too
+        
+        // Re-forming an existing alliance doesn't count the ally twice
+        assert!(system.world.add_alliance(1, 2));
+        assert!(!system.world.add_alliance(2, 1));
+        assert_eq!(system.world.get_country(1).unwrap().ally_count, 1);
+        
+        system.world.add_country(Country::new(3));
+        system.world.add_trade_route(1, 3, 1.0, 5.0);
+        let gdp = system.world.get_country(1).unwrap().gdp;
+        system.remove_country(2);
+        system.remove_country(3);
+        assert!(system.world.trade_routes().is_empty());
+        assert_eq!(system.world.get_country(1).unwrap().gdp, gdp - 5.0);  // The lost partner's bonus goes too
+    }
+
+    #[test]
+    fn test_war_exhaustion_accumulates_and_recovers() {
+        let mut world = WorldState::new();
+        world.add_country(Country::new(1));
+        world.add_country(Country::new(2));
+        world.add_country(Country::new(3));
+        world.add_alliance(1, 3);
+        
+        assert!(!world.declare_war(1, 3));  // Allies can't go to war
+        assert!(world.declare_war(2, 1));
+        assert!(!world.declare_war(1, 2));
+        assert!(world.at_war(1, 2));
+        assert_eq!(world.enemies_of(1), vec![2]);
+        
+        for _ in 0..3 {
+            world.update_war_exhaustion();
+        }
+        assert_eq!(world.get_country(1).unwrap().war_exhaustion, 3.0);
+        assert_eq!(world.get_country(3).unwrap().war_exhaustion, 0.0);
+        
+        assert!(world.make_peace(1, 2));
+        world.update_war_exhaustion();
+        assert!(world.get_country(2).unwrap().war_exhaustion < 3.0);
+        
+        world.declare_war(2, 3);
+        world.remove_country(3);
+        assert!(world.enemies_of(2).is_empty());
+    }
+
+    #[test]
+    fn test_alliance_removal_and_expiry() {
+        let mut world = WorldState::new();
+        world.add_country(Country::new(1));
+        world.add_country(Country::new(2));
+        world.add_country(Country::new(3));
+        
+        world.add_alliance(1, 2);
+        assert!(world.remove_alliance(2, 1));
+        assert!(!world.remove_alliance(1, 2));
+        assert_eq!(world.get_country(1).unwrap().ally_count, 0);
+        
+        world.set_alliance_duration(Some(2));
+        world.add_alliance(1, 3);
+        assert_eq!(world.alliance_expiry(1, 3), Some(2));
+        
+        world.tick = 1;
+        assert!(world.expire_alliances().is_empty());
+        world.tick = 2;
+        assert_eq!(world.expire_alliances(), vec![(1, 3)]);
+        assert!(!world.are_allies(1, 3));
+        assert_eq!(world.get_country(3).unwrap().ally_count, 0);
+    }
+
+    #[test]
+    fn test_load_tech_tree() {
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        system.world.get_country_mut(1).unwrap().researched.insert(0);
+        
+        system.load_tech_tree(r#"[
+            {"id": "bronze", "cost": 10, "modifiers": [{"stat": "military_strength", "add": 5}]},
+            {"id": "iron", "prerequisites": ["bronze"], "cost": 20}
+        ]"#).unwrap();
+        assert_eq!(system.world.tech_tree().nodes().len(), 2);
+        assert!(system.world.get_country(1).unwrap().researched.is_empty());
+        
+        assert!(matches!(
+            system.load_tech_tree(r#"[{"id": "iron", "prerequisites": ["bronze"], "cost": 20}]"#),
+            Err(SimError::InvalidInput(_))
+        ));
+        assert!(matches!(system.load_tech_tree("{}"), Err(SimError::MalformedInput(_))));
+        assert_eq!(system.world.tech_tree().nodes().len(), 2);  // Rejected trees change nothing
+    }
+
+    #[test]
+    fn test_selection_policy_is_configurable_and_seeded() {
+        let run = |seed: u64, policy: &str| {
+            let mut system = DecisionSystem::init(seed);
+            system.set_selection_policy(policy).unwrap();
+            for id in 1..=4 {
+                system.add_country(id);
+            }
+            for id in 1..4 {
+                system.add_edge(id, id + 1, 1, 0.5);
+                system.add_edge(id + 1, id, 1, 0.5);
+            }
+            for _ in 0..10 {
+                system.tick();
+            }
+            system.logs.iter().map(|log| log.chosen_action.clone()).collect::<Vec<_>>()
+        };
+        
+        let softmax = r#"{"kind": "softmax", "temperature": 50.0}"#;
+        assert_eq!(run(7, softmax), run(7, softmax));
+        assert_ne!(run(7, softmax), run(8, softmax));
+        assert_eq!(run(7, r#"{"kind": "argmax"}"#), run(8, r#"{"kind": "argmax"}"#));
+        
+        let mut system = DecisionSystem::new();
+        assert!(matches!(
+            system.set_selection_policy(r#"{"kind": "epsilon_greedy", "epsilon": 2.0}"#),
+            Err(SimError::InvalidInput(_))
+        ));
+        assert!(matches!(system.set_selection_policy(r#"{"kind": "greedy"}"#), Err(SimError::MalformedInput(_))));
+        assert_eq!(system.selection_policy, SelectionPolicy::Argmax);
+    }
+
+    #[test]
+    fn test_score_cache_matches_full_rescoring() {
+        let build = || {
+            let mut system = DecisionSystem::init(3);
+            for id in 1..=5 {
+                system.add_country(id);
+            }
+            for id in 1..5 {
+                system.add_edge(id, id + 1, 1, 0.3);
+                system.add_edge(id + 1, id, 1, 0.3);
+            }
+            for country in system.world.countries.values_mut() {
+                let neighbors: Vec<u32> = country.edges.iter().map(|edge| edge.neighbor_id).collect();
+                for neighbor in neighbors {
+                    country.intel.insert(neighbor, 1.0);  // No per-tick estimate noise
+                }
+            }
+            system
+        };
+        
+        let mut cached = build();
+        let mut uncached = build();
+        for _ in 0..20 {
+            cached.tick();
+            uncached.world.invalidate_all_scores();
+            uncached.tick();
+        }
+        
+        let logs = |system: &DecisionSystem| serde_json::to_string(&system.logs).unwrap();
+        assert_eq!(logs(&cached), logs(&uncached));
+        assert!(cached.score_cache_stats().hits > 0);
+        assert_eq!(uncached.score_cache_stats().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_changes_invalidate_country_and_neighbors() {
+        let mut system = DecisionSystem::new();
+        for id in 1..=4 {
+            system.add_country(id);
+        }
+        system.add_edge(1, 2, 1, 0.0);
+        system.add_edge(3, 4, 1, 0.0);
+        // Only Pass is left, so ticks change nothing on their own
+        let pass_only = r#"{"k_attack": 0, "k_fortify": 0, "k_invest": 0, "k_research": 0,
+            "k_diplomacy": 0, "k_war": 0, "k_espionage": 0}"#;
+        system.set_pruning_config(pass_only).unwrap();
+        system.tick();
+        assert!(system.world.countries().values().all(|country| !country.scores_dirty()));
+        
+        system.set_country_stats(2, r#"{"m_eff": 300}"#).unwrap();
+        system.world.propagate_score_invalidation();
+        let dirty = |system: &DecisionSystem, id| system.world.get_country(id).unwrap().scores_dirty();
+        assert!(dirty(&system, 2));
+        assert!(dirty(&system, 1));  // Borders 2
+        assert!(!dirty(&system, 3) && !dirty(&system, 4));
+        
+        system.tick();
+        assert_eq!(system.score_cache_stats(), ScoreCacheStats { hits: 2, misses: 6 });
+        assert_eq!(system.get_score_cache_hit_rate(), 0.25);
+    }
+
+    #[test]
+    fn test_parallel_tick_matches_serial() {
+        let build = || {
+            let mut system = DecisionSystem::init(11);
+            system.set_selection_policy(r#"{"kind": "softmax", "temperature": 20.0}"#).unwrap();
+            for id in 0..12 {
+                system.add_country(id);
+            }
+            for id in 0..12 {
+                let next = (id + 1) % 12;
+                let hostility = (id % 4) as f32 * 0.25;
+                system.add_edge(id, next, 1, hostility);
+                system.add_edge(next, id, 1 + (id % 2) as usize, hostility);
+            }
+            system
+        };
+        
+        let mut parallel = build();
+        let mut serial = build();
+        for _ in 0..15 {
+            parallel.tick();
+            serial.run_tick(false);
+            assert_eq!(
+                serde_json::to_string(parallel.deltas()).unwrap(),
+                serde_json::to_string(serial.deltas()).unwrap()
+            );
+        }
+        assert_eq!(serde_json::to_string(&parallel.logs).unwrap(), serde_json::to_string(&serial.logs).unwrap());
+        assert_eq!(parallel.score_cache_stats(), serial.score_cache_stats());
+    }
+
+    #[test]
+    fn test_logs_are_a_bounded_ring_buffer() {
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        system.add_country(2);
+        system.set_log_capacity(5);
+        for _ in 0..4 {
+            system.tick();
+        }
+        
+        assert_eq!(system.logs.len(), 5);
+        assert_eq!(system.get_dropped_log_count(), 3);
+        assert_eq!(system.logs.front().unwrap().tick, 1);  // Tick 0 and half of tick 1 were evicted
+        
+        let drained = system.take_logs();
+        assert_eq!(drained.len(), 5);
+        assert_eq!(drained.last().unwrap().tick, 3);
+        assert!(system.logs.is_empty());
+        
+        system.tick();
+        system.set_log_capacity(1);
+        assert_eq!(system.logs.len(), 1);
+        assert_eq!(system.logs[0].country_id, 2);
+        system.set_log_capacity(0);
+        system.tick();
+        assert!(system.logs.is_empty());
+    }
+
+    #[test]
+    fn test_logs_export_as_csv() {
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        system.add_country(2);
+        system.add_edge(1, 2, 1, 0.5);
+        system.tick();
+        system.tick();
+        
+        let csv = system.get_logs_csv(None);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], DecisionLog::CSV_HEADER);
+        assert_eq!(lines.len(), 5);
+        let columns = DecisionLog::CSV_HEADER.split(',').count();
+        assert!(lines.iter().all(|line| line.split(',').count() == columns));
+        assert!(lines[1].starts_with("0,1,"));
+        
+        let country_csv = system.get_logs_csv(Some(2));
+        assert_eq!(country_csv.lines().count(), 3);
+        assert!(country_csv.lines().skip(1).all(|line| line.split(',').nth(1) == Some("2")));
+        
+        assert_eq!(csv_text("Invest in Economy"), "Invest in Economy");
+        assert_eq!(csv_text("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    #[test]
+    fn test_explain_decision_covers_whole_shortlist() {
+        let mut system = DecisionSystem::new();
+        for id in 1..=3 {
+            system.add_country(id);
+        }
+        system.add_edge(1, 2, 1, 0.5);
+        system.add_edge(1, 3, 1, 0.2);
+        assert!(system.explain_decision(1).is_none());
+        system.tick();
+        
+        let explanation = system.explain_decision(1).unwrap();
+        let log = system.logs.front().unwrap();
+        assert_eq!((explanation.tick, explanation.country_id), (0, 1));
+        assert!(explanation.candidates.len() > 3);  // More than the chosen action and two rejected
+        assert!(explanation.candidates.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        
+        let chosen: Vec<_> = explanation.candidates.iter().filter(|candidate| candidate.chosen).collect();
+        assert_eq!(chosen.len(), 1);
+        assert_eq!(chosen[0].description, log.chosen_action);
+        assert_eq!(explanation.candidates[1].description, log.rejected_actions[0].0);
+        for candidate in &explanation.candidates {
+            let c = &candidate.contributions;
+            let total = c.delta_res + c.delta_sec + c.delta_growth + c.delta_pos + c.cost + c.risk;
+            assert!((total - candidate.score).abs() < 1e-2, "{} vs {}", total, candidate.score);
+            assert!(c.cost <= 0.0 && c.risk <= 0.0);
+        }
+        
+        system.remove_country(1);
+        assert!(system.explain_decision(1).is_none());
+        assert!(system.explain_decision(2).is_some());
+    }
+
+    #[test]
+    fn test_load_and_export_scenario() {
+        let mut system = DecisionSystem::new();
+        system.add_country(9);
+        system.set_country_pruning_config(9, r#"{"k_attack": 0}"#).unwrap();
+        system.tick();
+        
+        system.load_scenario(r#"{
+            "countries": [
+                {"id": 1, "edges": [{"neighbor_id": 2, "hostility": 0.9}]},
+                {"id": 2, "edges": [{"neighbor_id": 1, "hostility": 0.2}]}
+            ],
+            "alliances": [[1, 2]]
+        }"#).unwrap();
+        assert_eq!(system.get_tick(), 0);
+        assert!(system.world.get_country(9).is_none());
+        assert!(system.pruning_overrides.is_empty());
+        assert!(system.world.are_allies(1, 2));
+        system.clear_logs();
+        
+        let exported = system.export_scenario().unwrap();
+        let mut copy = DecisionSystem::new();
+        copy.load_scenario(&exported).unwrap();
+        assert_eq!(copy.export_scenario().unwrap(), exported);
+        system.tick();
+        copy.tick();
+        assert_eq!(serde_json::to_string(&system.logs).unwrap(), serde_json::to_string(&copy.logs).unwrap());
+        
+        assert!(matches!(system.load_scenario(r#"{"countries": "none"}"#), Err(SimError::MalformedInput(_))));
+        assert!(matches!(
+            system.load_scenario(r#"{"countries": [{"id": 1}], "wars": [[1, 5]]}"#),
+            Err(SimError::InvalidInput(_))
+        ));
+        assert!(system.world.get_country(2).is_some());  // Rejected scenarios change nothing
+    }
+
     #[test]
     fn test_decision_system_creation() {
         let system = DecisionSystem::new();
@@ -577,7 +1828,7 @@ mod tests {
         system.tick();
         
         // Verify weights are still bounded
-        if let Some(log) = system.logs.first() {
+        if let Some(log) = system.logs.front() {
             assert!(log.weights.alpha >= 2 && log.weights.alpha <= 16);
             assert!(log.weights.beta >= 2 && log.weights.beta <= 16);
             assert!(log.weights.gamma >= 2 && log.weights.gamma <= 16);
@@ -672,7 +1923,7 @@ mod tests {
         assert!(!all_actions.is_empty());
         
         // Verify logs are generated
-        assert!(system.logs.len() > 0);
+        assert!(!system.logs.is_empty());
     }
 
     #[test]
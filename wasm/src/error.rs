@@ -0,0 +1,84 @@
+//! Errors returned across the wasm boundary
+
+use std::fmt;
+
+#[cfg(feature = "bindings")]
+use wasm_bindgen::JsValue;
+
+/// Why a simulation call failed; thrown to JS as its message string
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimError {
+    /// A simulation needs at least one entity
+    ZeroEntityCount,
+    /// The territory grid needs at least one space per side
+    ZeroGridSize,
+    /// Ticks per second must be positive
+    ZeroTickRate,
+    /// JSON or JS input that doesn't describe the expected type
+    MalformedInput(String),
+    /// Input that parsed but was rejected by the simulation, such as an unknown entity
+    InvalidInput(String),
+    /// A result could not be converted to a JS value
+    Serialization(String),
+    /// A browser API the call relies on is missing or threw
+    Browser(String),
+}
+
+impl SimError {
+    pub fn check_entity_count(entity_count: usize) -> Result<usize, SimError> {
+        if entity_count == 0 {
+            return Err(SimError::ZeroEntityCount);
+        }
+        Ok(entity_count)
+    }
+
+    pub fn check_grid_size(grid_size: usize) -> Result<usize, SimError> {
+        if grid_size == 0 {
+            return Err(SimError::ZeroGridSize);
+        }
+        Ok(grid_size)
+    }
+
+    pub fn check_tick_rate(tick_rate: u32) -> Result<u32, SimError> {
+        if tick_rate == 0 {
+            return Err(SimError::ZeroTickRate);
+        }
+        Ok(tick_rate)
+    }
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::ZeroEntityCount => write!(f, "Entity count must be at least 1"),
+            SimError::ZeroGridSize => write!(f, "Grid size must be at least 1"),
+            SimError::ZeroTickRate => write!(f, "Tick rate must be at least 1"),
+            SimError::MalformedInput(message) => write!(f, "Malformed input: {}", message),
+            SimError::InvalidInput(message) => write!(f, "{}", message),
+            SimError::Serialization(message) => write!(f, "Could not serialize the result: {}", message),
+            SimError::Browser(message) => write!(f, "Browser call failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+/// Simulation-level rejections, which are reported as plain strings
+impl From<String> for SimError {
+    fn from(message: String) -> Self {
+        SimError::InvalidInput(message)
+    }
+}
+
+impl From<serde_json::Error> for SimError {
+    fn from(err: serde_json::Error) -> Self {
+        SimError::MalformedInput(err.to_string())
+    }
+}
+
+#[cfg(feature = "bindings")]
+impl From<SimError> for JsValue {
+    fn from(err: SimError) -> Self {
+        JsValue::from_str(&err.to_string())
+    }
+}
@@ -0,0 +1,7 @@
+//! Decoders for the cargo-fuzz targets in `fuzz/`
+//!
+//! Only built with the `fuzz` feature; nothing here is part of the supported API.
+
+pub use crate::protocol::{decode_commands, encode_commands, Command};
+pub use crate::types::binary_snapshot::{decode_snapshot_v1, BinarySnapshot};
+pub use crate::utils::crc32;
@@ -1,11 +1,45 @@
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 mod constants;
 mod data;
 mod decision_scoring;
+mod error;
+#[cfg(feature = "fuzz")]
+#[doc(hidden)]
+pub mod fuzz;
+mod logger;
 mod logic;
+#[cfg(test)]
+mod properties;
+mod protocol;
+#[cfg(feature = "bindings")]
 mod service;
 mod types;
 mod utils;
 
+#[cfg(all(target_arch = "wasm32", not(feature = "bindings")))]
+compile_error!("the wasm32 build reads its clock and console through the `bindings` feature");
+#[cfg(all(target_arch = "wasm32", feature = "parallel", not(target_feature = "atomics")))]
+compile_error!("`parallel` on wasm32 shares memory with its workers: build with `npm run build:wasm:threads`");
+
 pub use decision_scoring::*;
+pub use error::SimError;
+pub use logger::{set_log_level, LogLevel, Logger};
+#[cfg(feature = "bindings")]
 pub use service::SimulationHandler as Simulation;
+#[cfg(feature = "bindings")]
+pub use service::{ComparisonHandler, Scheduler};
+#[cfg(all(target_arch = "wasm32", feature = "parallel"))]
+pub use service::init_thread_pool;
 pub use types::{AiEntity, AiState};
+#[cfg(not(target_arch = "wasm32"))]
+pub use data::{Brain, EntityAction, EntityOrder, EntityView, GreedyBrain, GridBehavior, ScriptedBrain, WorldView};
+#[cfg(not(target_arch = "wasm32"))]
+pub use logic::{BatchRunner, ComparisonRunner, EventCallback, SimulationLogic};
+#[cfg(not(target_arch = "wasm32"))]
+pub use protocol::{decode_commands, encode_commands, Command, PROTOCOL_VERSION};
+#[cfg(not(target_arch = "wasm32"))]
+pub use types::{BehaviorProfile, MapCell, MapDefinition, MapEntity, MapResource, RunConfig, RunReport, WorldConfig};
+#[cfg(not(target_arch = "wasm32"))]
+pub use types::{DivergenceReport, DivergenceSample};
@@ -0,0 +1,111 @@
+//! Leveled diagnostics that reach the browser console on wasm and stderr natively
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(feature = "bindings")]
+use wasm_bindgen::prelude::*;
+
+/// How much the simulation reports; each level includes the ones above it
+#[cfg_attr(feature = "bindings", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+}
+
+impl LogLevel {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LogLevel::Off,
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            3 => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Warn as u8);
+
+pub struct Logger;
+
+impl Logger {
+    pub fn level() -> LogLevel {
+        LogLevel::from_u8(LEVEL.load(Ordering::Relaxed))
+    }
+
+    pub fn set_level(level: LogLevel) {
+        LEVEL.store(level as u8, Ordering::Relaxed);
+    }
+
+    pub fn enabled(level: LogLevel) -> bool {
+        level != LogLevel::Off && level <= Self::level()
+    }
+
+    /// Write `message` at `level`; prefer the `log!` macro, which skips
+    /// formatting for disabled levels
+    pub fn write(level: LogLevel, message: &str) {
+        if !Self::enabled(level) {
+            return;
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let message = JsValue::from_str(message);
+            match level {
+                LogLevel::Error => web_sys::console::error_1(&message),
+                LogLevel::Warn => web_sys::console::warn_1(&message),
+                LogLevel::Info => web_sys::console::info_1(&message),
+                LogLevel::Debug | LogLevel::Off => web_sys::console::debug_1(&message),
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        eprintln!("[{:?}] {}", level, message);
+    }
+}
+
+/// Only report diagnostics at `level` or more severe
+#[cfg_attr(feature = "bindings", wasm_bindgen)]
+pub fn set_log_level(level: LogLevel) {
+    Logger::set_level(level);
+}
+
+/// Send panics to `console.error` rather than leaving only an opaque
+/// `unreachable` trap in the browser
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
+fn install_panic_hook() {
+    console_error_panic_hook::set_once();
+}
+
+/// `log!(LogLevel::Warn, "format {}", args)`, formatting only when the level is enabled
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::logger::Logger::enabled($level) {
+            $crate::logger::Logger::write($level, &format!($($arg)*));
+        }
+    };
+}
+
+pub(crate) use log;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_include_everything_more_severe() {
+        let previous = Logger::level();
+        Logger::set_level(LogLevel::Info);
+        assert!(Logger::enabled(LogLevel::Error));
+        assert!(Logger::enabled(LogLevel::Info));
+        assert!(!Logger::enabled(LogLevel::Debug));
+
+        set_log_level(LogLevel::Off);
+        assert!(!Logger::enabled(LogLevel::Error));
+        assert!(!Logger::enabled(LogLevel::Off));
+        Logger::set_level(previous);
+    }
+}
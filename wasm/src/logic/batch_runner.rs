@@ -0,0 +1,94 @@
+use crate::logic::SimulationLogic;
+use crate::types::{AiState, RunConfig, RunReport, SimulationEvent};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Runs many simulations headlessly, e.g. to sweep seeds while balancing
+/// combat constants
+///
+/// Every run uses a fixed timestep, so a batch gives the same reports on
+/// any machine.
+pub struct BatchRunner;
+
+impl BatchRunner {
+    /// Run every config until one entity is left or its `max_ticks` is reached
+    pub fn run(configs: &[RunConfig]) -> Result<Vec<RunReport>, String> {
+        #[cfg(feature = "parallel")]
+        let reports = configs.par_iter().map(Self::run_one).collect();
+        #[cfg(not(feature = "parallel"))]
+        let reports = configs.iter().map(Self::run_one).collect();
+        reports
+    }
+
+    /// The same config once per seed
+    pub fn sweep_seeds(base: RunConfig, seeds: impl IntoIterator<Item = u32>) -> Result<Vec<RunReport>, String> {
+        let configs: Vec<RunConfig> = seeds.into_iter().map(|seed| base.with_seed(seed)).collect();
+        Self::run(&configs)
+    }
+
+    pub fn run_one(config: &RunConfig) -> Result<RunReport, String> {
+        Self::run_with_progress(config, |_| {})
+    }
+
+    /// Run a single config, handing the simulation to `on_tick` after every step
+    pub fn run_with_progress(
+        config: &RunConfig,
+        mut on_tick: impl FnMut(&SimulationLogic),
+    ) -> Result<RunReport, String> {
+        let mut logic = SimulationLogic::from_run_config(config).map_err(|err| err.to_string())?;
+
+        let (mut conquests, mut battles, mut deaths) = (0, 0, 0);
+        while !logic.is_complete() && logic.tick() < config.max_ticks {
+            logic.step();
+            let tick = logic.tick();
+            for event in logic.events() {
+                match *event {
+                    SimulationEvent::Conquest { tick: at, .. } if at == tick => conquests += 1,
+                    SimulationEvent::Battle { tick: at, .. } if at == tick => battles += 1,
+                    SimulationEvent::Death { tick: at, .. } if at == tick => deaths += 1,
+                    _ => {}
+                }
+            }
+            on_tick(&logic);
+        }
+
+        let alive = || logic.entities().iter().filter(|entity| entity.state != AiState::Dead);
+        let completed = logic.is_complete();
+        let winner = logic.victor().or_else(|| alive().find(|entity| entity.overlord.is_none()).map(|entity| entity.id));
+        // Ties go to the lowest id
+        let leader = alive().fold(None, |best: Option<(u32, u32)>, entity| match best {
+            Some((_, territory)) if territory >= entity.territory => best,
+            _ => Some((entity.id, entity.territory)),
+        });
+        Ok(RunReport {
+            seed: config.seed,
+            entity_count: config.entity_count,
+            grid_size: logic.grid_size(),
+            completed,
+            winner_id: if completed { winner } else { None },
+            ticks: logic.tick(),
+            survivors: alive().count(),
+            vassals: alive().filter(|entity| entity.overlord.is_some()).count(),
+            leader_id: leader.map(|(id, _)| id),
+            leader_territory: leader.map_or(0, |(_, territory)| territory),
+            conquests,
+            battles,
+            deaths,
+        })
+    }
+
+    pub fn to_json(reports: &[RunReport]) -> Result<String, String> {
+        serde_json::to_string(reports).map_err(|err| err.to_string())
+    }
+
+    /// Header line followed by one line per report
+    pub fn to_csv(reports: &[RunReport]) -> String {
+        let mut csv = String::from(RunReport::CSV_HEADER);
+        csv.push('\n');
+        for report in reports {
+            csv.push_str(&report.to_csv_row());
+            csv.push('\n');
+        }
+        csv
+    }
+}
@@ -0,0 +1,105 @@
+use crate::logic::SimulationLogic;
+use crate::types::{DivergenceReport, DivergenceSample, RunConfig};
+
+/// Steps two simulations tick for tick and measures how far apart they drift,
+/// e.g. to see what a balance change does to the same starting world
+///
+/// Both sides share a seed, so any divergence comes from the configs alone.
+pub struct ComparisonRunner {
+    a: SimulationLogic,
+    b: SimulationLogic,
+    samples: Vec<DivergenceSample>,
+}
+
+impl ComparisonRunner {
+    /// Compare `config_a` against `config_b`, both reseeded with `seed`
+    pub fn new(seed: u32, config_a: &RunConfig, config_b: &RunConfig) -> Result<Self, String> {
+        let a = SimulationLogic::from_run_config(&config_a.with_seed(seed)).map_err(|err| err.to_string())?;
+        let b = SimulationLogic::from_run_config(&config_b.with_seed(seed)).map_err(|err| err.to_string())?;
+        Ok(Self::from_simulations(a, b))
+    }
+
+    /// Compare two simulations set up by the caller, from whatever tick they are on
+    pub fn from_simulations(a: SimulationLogic, b: SimulationLogic) -> Self {
+        Self {
+            a,
+            b,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Step both simulations once and record their divergence
+    pub fn step(&mut self) -> DivergenceSample {
+        self.a.step();
+        self.b.step();
+        let sample = self.sample();
+        self.samples.push(sample);
+        sample
+    }
+
+    /// Step up to `ticks` times, stopping early once both runs are decided; returns the ticks run
+    pub fn step_many(&mut self, ticks: u64) -> u64 {
+        let mut run = 0;
+        while run < ticks && !self.is_complete() {
+            self.step();
+            run += 1;
+        }
+        run
+    }
+
+    /// Whether both runs are decided
+    pub fn is_complete(&self) -> bool {
+        self.a.is_complete() && self.b.is_complete()
+    }
+
+    pub fn a(&self) -> &SimulationLogic {
+        &self.a
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))] // For native callers
+    pub fn b(&self) -> &SimulationLogic {
+        &self.b
+    }
+
+    /// Every sample so far, oldest first
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fn samples(&self) -> &[DivergenceSample] {
+        &self.samples
+    }
+
+    pub fn report(&self) -> DivergenceReport {
+        DivergenceReport::from_samples(&self.samples)
+    }
+
+    fn sample(&self) -> DivergenceSample {
+        let (alive_a, alive_b) = (self.a.count_alive(), self.b.count_alive());
+        DivergenceSample {
+            tick: self.a.tick(),
+            territory_distance: territory_distance(&self.a, &self.b),
+            alive_a,
+            alive_b,
+            alive_difference: alive_a as i64 - alive_b as i64,
+        }
+    }
+}
+
+/// Total variation distance between the share of the grid each entity holds
+/// in `a` and in `b`, with unowned land as one more owner. Grids of different
+/// sizes compare by share, not cell count.
+fn territory_distance(a: &SimulationLogic, b: &SimulationLogic) -> f32 {
+    let share = |logic: &SimulationLogic, index: usize| {
+        let cells = (logic.grid_size() * logic.grid_size()).max(1) as f64;
+        logic.entities().get(index).map_or(0.0, |entity| entity.territory as f64 / cells)
+    };
+    let entities = a.entities().len().max(b.entities().len());
+    // Entities are indexed by id, so the same index is the same entity on both sides
+    let (mut distance, mut owned_a, mut owned_b) = (0.0, 0.0, 0.0);
+    for index in 0..entities {
+        let (share_a, share_b) = (share(a, index), share(b, index));
+        distance += (share_a - share_b).abs();
+        owned_a += share_a;
+        owned_b += share_b;
+    }
+    distance += (owned_a - owned_b).abs(); // Unowned land
+    (distance / 2.0).clamp(0.0, 1.0) as f32
+}
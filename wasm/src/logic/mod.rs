@@ -1,3 +1,9 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod batch_runner;
+mod comparison_runner;
 mod sim_logic;
 
-pub use sim_logic::SimulationLogic;
+#[cfg(not(target_arch = "wasm32"))]
+pub use batch_runner::BatchRunner;
+pub use comparison_runner::ComparisonRunner;
+pub use sim_logic::{EventCallback, SimulationLogic};
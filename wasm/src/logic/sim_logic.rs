@@ -1,66 +1,298 @@
-use crate::constants::{ATTACK_COST, DEFENSE_ACCUMULATION, DEFENSE_BONUS_MULTIPLIER, MAX_DEFENSE_STRENGTH};
+use crate::constants::{
+    ATTACK_COST, COMBAT_DEFENSE_WEAR, DEFENSE_ACCUMULATION, DISASTER_CHECK_INTERVAL, MAX_CHECKPOINTS,
+    MAX_DEFENSE_STRENGTH, MORALE_CONQUEST_GAIN, MORALE_LOSS_DRAIN, NEUTRAL_GROWTH_INTERVAL, RELATIONS_ASSAULT_DROP,
+    RELATIONS_CHECK_INTERVAL, SUPPLY_CHECK_INTERVAL, UNREST_CHECK_INTERVAL, VASSAL_CHECK_INTERVAL,
+};
 use crate::data::{
-    AiNeighborBuilder, AiStateUpdater, BenchmarkMetricBuilder, GridUpdateBuilder, SimulationData,
+    AiNeighborBuilder, AiStateUpdater, BehaviorTree, BenchmarkMetricBuilder, Brain, CombatResolver, DecisionAdapter,
+    EntityAction, EntityView, GreedyBrain, GridUpdateBuilder, HeatmapTracker, NodeStats, NotableDetector, Pathfinder,
+    SimulationData, SupplyNetwork, WorldView,
+};
+use crate::error::SimError;
+use crate::logger::{log, LogLevel};
+use crate::protocol::Command;
+use crate::types::{
+    encode_snapshot_v1, Affliction, AiEntity, AiState, Army, BehaviorProfile, CalendarConfig, CalendarDate,
+    ConquestConfig, Diagnostics, DisasterKind, EntityDetails, EntityStats, EntityTimeline, EventConfig, FairnessConfig,
+    FairnessReport, GridDiff, HeatmapKind, LeaderboardEntry, LeaderboardKey, MapDefinition, MemoryStats,
+    MetricsReport, NeutralConfig, NotableEvent, PlayerCommand, RunConfig, SimulationEvent, SimulationSnapshot,
+    SnapshotSchema, SyncBundle, TickProfile, TickStats, TimeMode, ResizePolicy, ResourceConfig, TerrainConfig,
+    UnrestConfig, ViewportSnapshot, WinCondition, WorldConfig,
 };
-use crate::types::{AiState, SimulationSnapshot};
-use crate::utils::Instant;
+use crate::utils::{grid_hash, Instant};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
 use std::mem;
+use std::sync::Arc;
+
+/// Player command with the world position it heads for, if any
+type ResolvedCommand = (PlayerCommand, Option<(f32, f32)>);
+
+/// Attacker index, id, military strength and the grid space it strikes
+type Assault = (usize, u32, f32, usize);
+
+/// Called with each event of the kind it was registered for
+pub type EventCallback = Box<dyn FnMut(&SimulationEvent)>;
+
+/// Times each phase of a tick on the benchmark clock
+///
+/// `lap` closes the phase running since the previous lap (or `begin`), so
+/// phases are measured back to back without gaps.
+#[derive(Default)]
+struct TickProfiler {
+    profile: TickProfile,
+    tick_start: f64,
+    lap_start: f64,
+}
+
+impl TickProfiler {
+    fn begin(&mut self, tick: u64, clock: &BenchmarkMetricBuilder) {
+        self.profile = TickProfile {
+            tick,
+            ..TickProfile::default()
+        };
+        self.tick_start = clock.start();
+        self.lap_start = self.tick_start;
+    }
+
+    /// Duration of the phase that just ended
+    fn lap(&mut self, clock: &BenchmarkMetricBuilder) -> f64 {
+        let duration = clock.elapsed(self.lap_start);
+        self.lap_start = clock.start();
+        duration
+    }
+
+    fn finish(&mut self, clock: &BenchmarkMetricBuilder) -> TickProfile {
+        self.profile.total_ms = clock.elapsed(self.tick_start);
+        self.profile
+    }
+}
 
 pub struct SimulationLogic {
-    data: SimulationData,
+    data: Arc<SimulationData>,
     neighbor_builder: AiNeighborBuilder,
     state_updater: AiStateUpdater,
+    decision_adapter: DecisionAdapter,
+    brains: Vec<Box<dyn Brain>>, // Indexed by `AiEntity::brain`; the greedy brain comes first
+    behavior_trees: BTreeMap<u32, u32>, // Brain id of the behavior tree loaded for each entity id
     grid_builder: GridUpdateBuilder,
+    pathfinder: Pathfinder,
+    combat_resolver: CombatResolver,
+    supply_network: SupplyNetwork,
+    heatmaps: HeatmapTracker,
+    notable: NotableDetector,
     benchmark_builder: BenchmarkMetricBuilder,
+    tick_profiler: TickProfiler,
     start_time: Instant,
+    time_mode: TimeMode,
+    commands: Vec<Option<ResolvedCommand>>,
+    // Per-tick scratch, kept so steady-state ticks reuse their allocations
+    views: Vec<Option<EntityView>>,
+    actions: Vec<Option<EntityAction>>,
+    defense_updates: Vec<(usize, u32)>,
+    assaults: Vec<Assault>,
+    spatial_index_stale: bool,
+    validate_steps: bool, // Check the state after every step, in debug builds only
+    on_entity_death: Option<EventCallback>,
+    on_conquest: Option<EventCallback>,
+    checkpoints: Vec<(u32, Arc<SimulationData>)>, // Oldest first; each shares data with the live state until either changes
+    next_checkpoint_id: u32,
 }
 
 impl SimulationLogic {
     pub fn new(entity_count: usize) -> Self {
+        Self::with_world(entity_count, WorldConfig::default())
+    }
+
+    pub fn with_world(entity_count: usize, world: WorldConfig) -> Self {
         Self {
-            data: SimulationData::new(entity_count),
+            data: Arc::new(SimulationData::with_world(entity_count, world)),
             neighbor_builder: AiNeighborBuilder::new(),
             state_updater: AiStateUpdater::new(),
-            grid_builder: GridUpdateBuilder::new(5.0, 10.0),
+            decision_adapter: DecisionAdapter::new(),
+            brains: vec![Box::new(GreedyBrain)],
+            behavior_trees: BTreeMap::new(),
+            grid_builder: GridUpdateBuilder::for_world(&world),
+            pathfinder: Pathfinder::new(),
+            combat_resolver: CombatResolver::new(),
+            supply_network: SupplyNetwork::new(),
+            heatmaps: HeatmapTracker::new(),
+            notable: NotableDetector::new(),
             benchmark_builder: BenchmarkMetricBuilder::new(),
+            tick_profiler: TickProfiler::default(),
             start_time: Instant::now(),
+            time_mode: TimeMode::default(),
+            commands: Vec::new(),
+            views: Vec::new(),
+            actions: Vec::new(),
+            defense_updates: Vec::new(),
+            assaults: Vec::new(),
+            spatial_index_stale: true,
+            validate_steps: false,
+            on_entity_death: None,
+            on_conquest: None,
+            checkpoints: Vec::new(),
+            next_checkpoint_id: 0,
         }
     }
 
+    /// A started simulation set up from `config`, on its fixed timestep
+    pub fn from_run_config(config: &RunConfig) -> Result<Self, SimError> {
+        SimError::check_entity_count(config.entity_count)?;
+        config.world.validate()?;
+        let time_mode = TimeMode::fixed(config.ms_per_tick)
+            .ok_or_else(|| SimError::InvalidInput("ms_per_tick must be positive".to_string()))?;
+        let mut logic = Self::with_world(config.entity_count, config.world);
+        logic.set_terrain_config(config.terrain)?;
+        logic.set_resource_config(config.resources)?;
+        logic.set_neutral_config(config.neutrals)?;
+        logic.set_unrest_config(config.unrest)?;
+        logic.set_event_config(config.events)?;
+        logic.set_calendar_config(config.calendar)?;
+        logic.set_time_mode(time_mode);
+        logic.reseed_entities(config.seed);
+        logic.start();
+        Ok(logic)
+    }
+
     pub fn step(&mut self) {
-        self.data.increment_tick();
+        let data = &self.data;
+        self.heatmaps.begin_tick(data.tick(), data.grid_spaces(), data.dirty_grid_cells());
+        self.writable_data().increment_tick();
         let current_tick = self.data.tick();
         
-        // Use actual wall clock time for time-based resource generation
-        let elapsed = self.start_time.elapsed();
-        let current_time_ms = elapsed.as_millis() as f64;
+        // Wall clock or synthetic time for time-based resource generation
+        let current_time_ms = match self.time_mode {
+            TimeMode::RealTime => self.start_time.elapsed().as_millis() as f64,
+            TimeMode::Fixed { ms_per_tick } => current_tick as f64 * ms_per_tick,
+            TimeMode::SimTime => self.data.sim_time_ms(),
+        };
         self.state_updater.update_time(current_time_ms);
+        self.state_updater.update_season(self.data.season_effects());
         
-        let (_, duration) = self.benchmark_builder.measure_tick(|| {
-            self.neighbor_builder.rebuild_snapshots(&mut self.data);
-            let snapshots = self.data.snapshots().to_vec();
-            self.grid_builder.rebuild(&snapshots);
-
-            let entity_count = self.data.entity_len();
-            for i in 0..entity_count {
-                if let Some(entity) = self.data.entity_mut(i) {
-                    let snapshot = snapshots[i];
-                    self.state_updater.update_entity(
-                        entity,
-                        current_tick,
-                        i,
-                        snapshot,
-                        &snapshots,
-                        &self.grid_builder,
-                    );
-                }
+        self.tick_profiler.begin(current_tick, &self.benchmark_builder);
+        // Player commands take over their entities before the AI runs
+        self.writable_data().apply_pending_commands();
+
+        self.neighbor_builder.rebuild_snapshots(Arc::make_mut(&mut self.data));
+        self.tick_profiler.profile.snapshot_rebuild_ms = self.tick_profiler.lap(&self.benchmark_builder);
+        self.grid_builder.rebuild(self.data.snapshots());
+        self.spatial_index_stale = false;
+        if self.grid_builder.overflow_count() > 0 {
+            log!(
+                LogLevel::Debug,
+                "tick {}: {} entities spilled past their spatial grid cell's inline slots",
+                current_tick,
+                self.grid_builder.overflow_count()
+            );
+        }
+        self.tick_profiler.profile.grid_rebuild_ms = self.tick_profiler.lap(&self.benchmark_builder);
+
+        let terrain = self.data.terrain_config();
+        let behaviors = self.decision_adapter.decide(
+            self.data.entities(),
+            self.data.grid_spaces(),
+            self.data.grid_size(),
+            self.data.alliances(),
+            &terrain,
+        );
+        // Resolve player commands up front so the update itself only
+        // touches each entity on its own
+        let entity_count = self.data.entity_len();
+        self.commands.clear();
+        for i in 0..entity_count {
+            let resolved = Arc::make_mut(&mut self.data).active_command(i).map(|command| {
+                let destination = match command {
+                    PlayerCommand::MoveTo { grid_index } => {
+                        Some(self.data.grid_index_to_position(grid_index as usize))
+                    }
+                    _ => None,
+                };
+                (command, destination)
+            });
+            self.commands.push(resolved);
+        }
+
+        // Updates read the tick's starting state and write only their own
+        // entity: each AI takes stock of its surroundings...
+        let (snapshots, alliances, relations, entities) = Arc::make_mut(&mut self.data).entity_update_buffers();
+        let state_updater = &self.state_updater;
+        let grid_builder = &self.grid_builder;
+        let commands = &self.commands;
+        self.views.clear();
+        self.views.resize(entity_count, None);
+        let observe = |((i, entity), view): ((usize, &mut AiEntity), &mut Option<EntityView>)| match commands[i] {
+            Some((command, destination)) => {
+                state_updater.follow_command(entity, command, destination);
             }
-        });
+            None => {
+                *view = state_updater.observe(entity, i, snapshots, grid_builder, alliances, relations, behaviors[i]);
+            }
+        };
+        #[cfg(feature = "parallel")]
+        entities.par_iter_mut().enumerate().zip(self.views.par_iter_mut()).for_each(observe);
+        #[cfg(not(feature = "parallel"))]
+        entities.iter_mut().enumerate().zip(self.views.iter_mut()).for_each(observe);
+
+        // ...its brain decides, one entity at a time since brains may keep state...
+        let world = WorldView {
+            tick: current_tick,
+            #[cfg(not(target_arch = "wasm32"))]
+            entities: snapshots,
+            #[cfg(not(target_arch = "wasm32"))]
+            alliances,
+            relations,
+        };
+        self.actions.clear();
+        for (entity, view) in entities.iter().zip(&self.views) {
+            let action = view.map(|view| self.brains[entity.brain as usize].decide(view, &world));
+            self.actions.push(action);
+        }
 
-        // Process conquests - attackers try to conquer adjacent grid spaces
+        // ...and it acts on the decision
+        let act = |(entity, action): (&mut AiEntity, &Option<EntityAction>)| {
+            if let Some(action) = *action {
+                state_updater.act(entity, action);
+            }
+        };
+        #[cfg(feature = "parallel")]
+        entities.par_iter_mut().zip(self.actions.par_iter()).for_each(act);
+        #[cfg(not(feature = "parallel"))]
+        entities.iter_mut().zip(&self.actions).for_each(act);
+        // Tribute changes hands once every entity has set its own aside
+        self.writable_data().collect_tribute();
+        self.tick_profiler.profile.entity_update_ms = self.tick_profiler.lap(&self.benchmark_builder);
+
+        // Attackers close in on the frontier, then strike the space in front of them
+        self.advance_attackers();
         self.process_conquests();
+        self.advance_armies();
+        if current_tick.is_multiple_of(SUPPLY_CHECK_INTERVAL) {
+            self.update_supply();
+        }
+        if current_tick.is_multiple_of(RELATIONS_CHECK_INTERVAL) {
+            self.writable_data().keep_peace();
+        }
+        if current_tick.is_multiple_of(VASSAL_CHECK_INTERVAL) {
+            self.writable_data().submit_vassals();
+        }
+        if current_tick.is_multiple_of(NEUTRAL_GROWTH_INTERVAL) {
+            self.writable_data().grow_garrisons(NEUTRAL_GROWTH_INTERVAL);
+        }
+        if current_tick.is_multiple_of(UNREST_CHECK_INTERVAL) {
+            let revolts = self.writable_data().stir_unrest(UNREST_CHECK_INTERVAL);
+            if revolts > 0 {
+                log!(LogLevel::Debug, "tick {}: {} restless grid spaces revolted", current_tick, revolts);
+            }
+        }
+        if current_tick.is_multiple_of(DISASTER_CHECK_INTERVAL) {
+            self.writable_data().strike_disasters();
+        }
+        self.writable_data().apply_afflictions();
+        self.tick_profiler.profile.conquest_ms = self.tick_profiler.lap(&self.benchmark_builder);
 
-        self.data.reset_tick_buffers();
+        self.writable_data().reset_tick_buffers();
 
         // Check for AIs that lost all territory (death condition)
         let entity_count = self.data.entity_len();
@@ -77,9 +309,10 @@ impl SimulationLogic {
 
             // AI dies when it loses all its territory
             if territory == 0 && state != AiState::Dead {
-                self.data.dead_indices_mut().push(i);
+                self.writable_data().dead_indices_mut().push(i);
 
-                // Transfer remaining resources to nearest attacker
+                // Transfer remaining resources to the nearest attacker, or failing
+                // that the nearest surviving neighbor of any kind
                 if military_strength > 0.0 || money > 0.0 {
                     let (pos_x, pos_y) = {
                         let entity = self.data.entity(i).expect("entity must exist");
@@ -87,7 +320,9 @@ impl SimulationLogic {
                     };
                     
                     let mut nearest_attacker_idx: Option<usize> = None;
-                    let mut nearest_dist_sq = f32::INFINITY;
+                    let mut nearest_attacker_dist_sq = f32::INFINITY;
+                    let mut nearest_survivor_idx: Option<usize> = None;
+                    let mut nearest_survivor_dist_sq = f32::INFINITY;
 
                     self.grid_builder.for_each_neighbor(pos_x, pos_y, |idx| {
                         if idx == i {
@@ -95,22 +330,28 @@ impl SimulationLogic {
                         }
 
                         if let Some(other) = self.data.entity(idx) {
-                            if matches!(other.state, AiState::Attacking) {
-                                let dx = pos_x - other.position_x;
-                                let dy = pos_y - other.position_y;
-                                let dist_sq = dx * dx + dy * dy;
-
-                                if dist_sq < nearest_dist_sq {
-                                    nearest_dist_sq = dist_sq;
-                                    nearest_attacker_idx = Some(idx);
-                                }
+                            // Skip entities that are dying this tick as well
+                            if other.state == AiState::Dead || other.territory == 0 {
+                                return;
+                            }
+                            let dx = pos_x - other.position_x;
+                            let dy = pos_y - other.position_y;
+                            let dist_sq = dx * dx + dy * dy;
+
+                            if other.state == AiState::Attacking && dist_sq < nearest_attacker_dist_sq {
+                                nearest_attacker_dist_sq = dist_sq;
+                                nearest_attacker_idx = Some(idx);
+                            }
+                            if dist_sq < nearest_survivor_dist_sq {
+                                nearest_survivor_dist_sq = dist_sq;
+                                nearest_survivor_idx = Some(idx);
                             }
                         }
                     });
 
-                    if let Some(attacker_idx) = nearest_attacker_idx {
-                        self.data.resource_transfers_mut().push((
-                            attacker_idx,
+                    if let Some(heir_idx) = nearest_attacker_idx.or(nearest_survivor_idx) {
+                        self.writable_data().resource_transfers_mut().push((
+                            heir_idx,
                             military_strength,
                             money,
                         ));
@@ -119,41 +360,117 @@ impl SimulationLogic {
             }
         }
 
-        let mut transfers = mem::take(self.data.resource_transfers_mut());
+        let mut transfers = mem::take(self.writable_data().resource_transfers_mut());
         for &(attacker_idx, military_strength, money) in &transfers {
-            if let Some(attacker) = self.data.entity_mut(attacker_idx) {
+            if let Some(attacker) = self.writable_data().entity_mut(attacker_idx) {
                 attacker.military_strength += military_strength;
                 attacker.money += money;
             }
         }
         transfers.clear();
-        *self.data.resource_transfers_mut() = transfers;
+        *self.writable_data().resource_transfers_mut() = transfers;
 
-        let mut dead_indices = mem::take(self.data.dead_indices_mut());
+        let mut dead_indices = mem::take(self.writable_data().dead_indices_mut());
         for &dead_idx in &dead_indices {
-            if let Some(dead_entity) = self.data.entity_mut(dead_idx) {
+            if let Some(dead_entity) = self.writable_data().entity_mut(dead_idx) {
                 dead_entity.state = AiState::Dead;
                 dead_entity.military_strength = 0.0;
                 dead_entity.money = 0.0;
                 dead_entity.territory = 0;
+                dead_entity.command = None;
+                dead_entity.capital = None;
+                let entity_id = dead_entity.id;
+                self.writable_data().alliances_mut().remove_entity(entity_id);
+                self.writable_data().relations_mut().remove_entity(entity_id);
+                self.writable_data().end_vassalage(entity_id);
+                self.writable_data().record_event(SimulationEvent::Death {
+                    tick: current_tick,
+                    entity_id,
+                });
             }
         }
         dead_indices.clear();
-        *self.data.dead_indices_mut() = dead_indices;
+        *self.writable_data().dead_indices_mut() = dead_indices;
+        self.tick_profiler.profile.death_processing_ms = self.tick_profiler.lap(&self.benchmark_builder);
 
         // Update territory counts based on owned grid spaces
-        self.data.update_territories();
+        self.writable_data().update_territories();
+        self.writable_data().hold_victory_points();
+        self.writable_data().record_entity_history();
+        self.writable_data().record_stats();
+        self.tick_profiler.profile.territory_update_ms = self.tick_profiler.lap(&self.benchmark_builder);
 
-        self.data.mark_snapshots_dirty();
+        self.writable_data().mark_snapshots_dirty();
+        self.spatial_index_stale = true;
 
-        if duration > 0.0 {
-            self.data.metrics_mut().update_tick(duration);
+        let profile = self.tick_profiler.finish(&self.benchmark_builder);
+        if profile.total_ms > 0.0 {
+            let update_duration = profile.snapshot_rebuild_ms + profile.grid_rebuild_ms + profile.entity_update_ms;
+            self.writable_data()
+                .metrics_mut()
+                .update_tick(profile.total_ms, update_duration, profile.conquest_ms);
         }
 
         // Check if simulation should end (only one AI alive)
         if self.is_complete() {
-            self.data.set_running(false);
+            self.writable_data().set_running(false);
+        }
+        if cfg!(debug_assertions) && self.validate_steps {
+            let diagnostics = self.diagnostics();
+            if !diagnostics.is_clean() {
+                log!(LogLevel::Warn, "tick {}: simulation state failed validation: {:?}", current_tick, diagnostics);
+            }
         }
+        let data = &self.data;
+        self.heatmaps.end_tick(current_tick, data.grid_spaces(), data.dirty_grid_cells());
+        self.notable.end_tick(current_tick, data.entities());
+        self.dispatch_events();
+    }
+
+    /// Call `callback` for every entity death from now on, or stop with `None`
+    pub fn set_on_entity_death(&mut self, callback: Option<EventCallback>) {
+        self.on_entity_death = callback;
+    }
+
+    /// Call `callback` whenever a grid space changes hands, or stop with `None`
+    pub fn set_on_conquest(&mut self, callback: Option<EventCallback>) {
+        self.on_conquest = callback;
+    }
+
+    /// Hand the events recorded since the last dispatch to their callbacks
+    fn dispatch_events(&mut self) {
+        for event in Arc::make_mut(&mut self.data).drain_new_events() {
+            self.heatmaps.record_event(&event);
+            self.notable.record_event(&event);
+            let callback = match event {
+                SimulationEvent::Death { .. } => self.on_entity_death.as_mut(),
+                SimulationEvent::Conquest { .. } => self.on_conquest.as_mut(),
+                SimulationEvent::Battle { .. }
+                | SimulationEvent::CapitalCaptured { .. }
+                | SimulationEvent::Revolt { .. }
+                | SimulationEvent::Disaster { .. }
+                | SimulationEvent::Victory { .. }
+                | SimulationEvent::Vassalized { .. } => None,
+            };
+            if let Some(callback) = callback {
+                callback(&event);
+            }
+        }
+    }
+
+    /// Step up to `max_ticks` times, stopping once `budget_ms` has been spent.
+    /// The budget is checked after each tick, so at least one runs; returns the ticks run
+    pub fn step_many(&mut self, max_ticks: u32, budget_ms: f64) -> u32 {
+        let start = self.benchmark_builder.start();
+        let mut ran = 0;
+        while ran < max_ticks {
+            self.step();
+            ran += 1;
+            if self.benchmark_builder.elapsed(start) >= budget_ms {
+                break;
+            }
+        }
+        ran
     }
 
     pub fn update(&mut self) {
@@ -162,9 +479,20 @@ impl SimulationLogic {
         }
     }
 
+    /// Finished once at most one entity, a single team, or one overlord and
+    /// its vassals are left standing, or an entity has won on victory points
     pub fn is_complete(&self) -> bool {
-        let alive_count = self.count_alive();
-        alive_count <= 1
+        if self.data.victor().is_some() {
+            return true;
+        }
+        let mut alive = self.data.entities().iter().filter(|e| e.state != AiState::Dead);
+        let realm = |entity: &AiEntity| entity.overlord.unwrap_or(entity.id);
+        match alive.next() {
+            None => true,
+            Some(first) => alive.all(|other| {
+                (first.team_id.is_some() && other.team_id == first.team_id) || realm(other) == realm(first)
+            }),
+        }
     }
 
     pub fn count_alive(&self) -> usize {
@@ -176,11 +504,11 @@ impl SimulationLogic {
     }
 
     pub fn start(&mut self) {
-        self.data.set_running(true);
+        self.writable_data().set_running(true);
     }
 
     pub fn pause(&mut self) {
-        self.data.set_running(false);
+        self.writable_data().set_running(false);
     }
 
     pub fn resume(&mut self) {
@@ -188,8 +516,22 @@ impl SimulationLogic {
     }
 
     pub fn reset(&mut self) {
-        self.data.set_running(false);
-        self.data.reset_entities();
+        self.writable_data().set_running(false);
+        self.writable_data().reset_entities();
+        self.spatial_index_stale = true;
+    }
+
+    /// With `keep_map`, stop and start the run over from its own starts on
+    /// the same land; otherwise lay freshly generated land under the entities
+    /// and carry on
+    pub fn soft_reset(&mut self, keep_map: bool) {
+        if keep_map {
+            self.writable_data().set_running(false);
+            self.writable_data().restart_from_run_start();
+        } else {
+            self.writable_data().regenerate_world_layers();
+        }
+        self.spatial_index_stale = true;
     }
 
     pub fn running(&self) -> bool {
@@ -205,7 +547,24 @@ impl SimulationLogic {
     }
 
     pub fn set_tick_rate(&mut self, tick_rate: u32) {
-        self.data.set_tick_rate(tick_rate);
+        self.writable_data().set_tick_rate(tick_rate);
+    }
+
+    pub fn time_mode(&self) -> TimeMode {
+        self.time_mode
+    }
+
+    /// Switch clocks; takes effect on the next step
+    pub fn set_time_mode(&mut self, time_mode: TimeMode) {
+        self.time_mode = time_mode;
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.state_updater.time_scale()
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.state_updater.set_time_scale(time_scale);
     }
 
     pub fn entity_count(&self) -> usize {
@@ -213,7 +572,89 @@ impl SimulationLogic {
     }
 
     pub fn set_entity_count(&mut self, entity_count: usize) {
-        self.data.set_entity_count(entity_count);
+        self.writable_data().set_entity_count(entity_count);
+        self.spatial_index_stale = true;
+    }
+
+    pub fn entity_details(&self, entity_id: u32) -> Option<EntityDetails> {
+        self.data.entity_details(entity_id)
+    }
+
+    /// Stats the entity had over the last ticks, oldest first
+    ///
+    /// Recording starts on the first request, so that call only returns the
+    /// current stats.
+    pub fn entity_history(&mut self, entity_id: u32) -> Option<Vec<EntityStats>> {
+        if !self.writable_data().watch_entity(entity_id) {
+            return None;
+        }
+        self.data
+            .entity_history(entity_id)
+            .map(|history| history.iter().copied().collect())
+    }
+
+    /// World aggregates over the last `STATS_HISTORY_LEN` ticks, oldest first
+    pub fn stats_history(&self) -> &VecDeque<TickStats> {
+        self.data.stats().history()
+    }
+
+    /// Territory the entity held over the run, or `None` if it has never been sampled
+    pub fn territory_timeline(&self, entity_id: u32) -> Option<EntityTimeline> {
+        self.data.stats().timeline().entity(entity_id)
+    }
+
+    /// Territory every entity held over the run, by id
+    pub fn all_timelines(&self) -> Vec<EntityTimeline> {
+        self.data.stats().timeline().all()
+    }
+
+    pub fn set_leaderboard_key(&mut self, key: LeaderboardKey) {
+        self.writable_data().set_leaderboard_key(key);
+    }
+
+    /// The `n` alive entities ranking highest by the leaderboard key
+    pub fn leaderboard(&self, n: usize) -> Vec<LeaderboardEntry> {
+        self.data.leaderboard(n)
+    }
+
+    /// Add an entity at `(x, y)` without disturbing the running simulation
+    pub fn spawn_entity(&mut self, x: f32, y: f32, military_strength: f32) -> Result<u32, String> {
+        let id = self.writable_data().spawn_entity(x, y, military_strength)?;
+        self.spatial_index_stale = true;
+        Ok(id)
+    }
+
+    /// Take an entity out without disturbing the rest of the simulation
+    pub fn remove_entity(&mut self, entity_id: u32) -> Result<(), String> {
+        self.writable_data().remove_entity(entity_id)?;
+        self.spatial_index_stale = true;
+        self.dispatch_events();
+        Ok(())
+    }
+
+    /// Fold `absorbed_id`'s land, armies and resources into `entity_id`
+    pub fn merge_entities(&mut self, entity_id: u32, absorbed_id: u32) -> Result<(), String> {
+        self.writable_data().merge_entities(entity_id, absorbed_id)?;
+        self.spatial_index_stale = true;
+        self.dispatch_events();
+        Ok(())
+    }
+
+    /// Break `fraction` of an entity's empire away as a new entity
+    pub fn split_entity(&mut self, entity_id: u32, fraction: f32) -> Result<u32, String> {
+        let id = self.writable_data().split_entity(entity_id, fraction)?;
+        self.spatial_index_stale = true;
+        Ok(id)
+    }
+
+    /// Respawn the entities as teams of `team_sizes`, in id order
+    pub fn set_teams(&mut self, team_sizes: &[usize]) {
+        self.writable_data().set_teams(team_sizes);
+        self.spatial_index_stale = true;
+    }
+
+    pub fn team_sizes(&self) -> &[usize] {
+        self.data.team_sizes()
     }
 
     pub fn grid_size(&self) -> usize {
@@ -221,123 +662,778 @@ impl SimulationLogic {
     }
 
     pub fn set_grid_size(&mut self, grid_size: usize) {
-        self.data.set_grid_size(grid_size);
+        self.writable_data().set_grid_size(grid_size);
+        self.spatial_index_stale = true;
+    }
+
+    /// Rescale the grid mid-run, keeping the territories where they are in the world
+    pub fn resize_grid(&mut self, grid_size: usize, policy: ResizePolicy) {
+        self.writable_data().resize_grid(grid_size, policy);
+        self.grid_builder = GridUpdateBuilder::for_world(&self.data.world_config());
+        self.spatial_index_stale = true;
+    }
+
+    pub fn propose_alliance(&mut self, a: u32, b: u32) -> bool {
+        self.writable_data().propose_alliance(a, b)
+    }
+
+    pub fn are_allies(&self, a: u32, b: u32) -> bool {
+        self.data.alliances().are_allies(a, b)
+    }
+
+    /// Entity `id` pays tribute to, if any
+    pub fn overlord(&self, id: u32) -> Option<u32> {
+        self.data.entity(id as usize).and_then(|entity| entity.overlord)
+    }
+
+    pub fn vassals(&self, id: u32) -> Vec<u32> {
+        self.data.vassals_of(id).collect()
+    }
+
+    /// Relations between every pair of entities, row-major by entity index
+    pub fn relations_matrix(&self) -> Vec<f32> {
+        self.data.relations().matrix(self.data.entity_len())
+    }
+
+    pub fn world_config(&self) -> WorldConfig {
+        self.data.world_config()
+    }
+
+    pub fn set_world_config(&mut self, world: WorldConfig) {
+        self.writable_data().set_world_config(world);
+        self.grid_builder = GridUpdateBuilder::for_world(&world);
+        self.spatial_index_stale = true;
+    }
+
+    /// Stop the simulation and start it over on a map's grid, land and entities
+    pub fn load_map(&mut self, map: &MapDefinition) -> Result<(), String> {
+        self.writable_data().load_map(map)?;
+        self.writable_data().set_running(false);
+        self.grid_builder = GridUpdateBuilder::for_world(&self.data.world_config());
+        self.spatial_index_stale = true;
+        Ok(())
+    }
+
+    pub fn export_map(&self) -> MapDefinition {
+        self.data.export_map()
+    }
+
+    /// One value per grid space for an overlay, row-major; tallies cover the
+    /// current run
+    pub fn heatmap(&self, kind: HeatmapKind) -> Vec<f32> {
+        self.heatmaps.heatmap(kind, self.data.tick(), self.data.grid_spaces())
+    }
+
+    /// First blood, comebacks and record expansions of the current run, oldest first
+    pub fn notable_events(&self) -> Vec<NotableEvent> {
+        self.notable.notable_events(self.data.tick())
+    }
+
+    /// How evenly matched the alive entities' capitals are
+    pub fn validate_start_positions(&self) -> FairnessReport {
+        self.data.validate_start_positions()
+    }
+
+    pub fn fairness_config(&self) -> FairnessConfig {
+        self.data.fairness_config()
+    }
+
+    /// Respawn the entities on starts placed under the new fairness settings
+    pub fn set_fairness_config(&mut self, fairness: FairnessConfig) -> Result<(), String> {
+        self.writable_data().set_fairness_config(fairness)?;
+        self.spatial_index_stale = true;
+        Ok(())
+    }
+
+    pub fn terrain_config(&self) -> TerrainConfig {
+        self.data.terrain_config()
+    }
+
+    /// Regenerate the terrain layer; ownership and entities are kept
+    pub fn set_terrain_config(&mut self, terrain: TerrainConfig) -> Result<(), String> {
+        self.writable_data().set_terrain_config(terrain)
+    }
+
+    pub fn resource_config(&self) -> ResourceConfig {
+        self.data.resource_config()
+    }
+
+    /// Respawn resource nodes; ownership and entities are kept
+    pub fn set_resource_config(&mut self, resources: ResourceConfig) -> Result<(), String> {
+        self.writable_data().set_resource_config(resources)
+    }
+
+    pub fn conquest_config(&self) -> ConquestConfig {
+        self.data.conquest_config()
+    }
+
+    pub fn set_conquest_config(&mut self, conquest: ConquestConfig) -> Result<(), String> {
+        self.writable_data().set_conquest_config(conquest)
+    }
+
+    pub fn neutral_config(&self) -> NeutralConfig {
+        self.data.neutral_config()
+    }
+
+    /// Re-garrison unowned land; owned spaces and entities are kept
+    pub fn set_neutral_config(&mut self, neutrals: NeutralConfig) -> Result<(), String> {
+        self.writable_data().set_neutral_config(neutrals)
+    }
+
+    pub fn unrest_config(&self) -> UnrestConfig {
+        self.data.unrest_config()
+    }
+
+    pub fn set_unrest_config(&mut self, unrest: UnrestConfig) -> Result<(), String> {
+        self.writable_data().set_unrest_config(unrest)
+    }
+
+    pub fn event_config(&self) -> EventConfig {
+        self.data.event_config()
+    }
+
+    pub fn set_event_config(&mut self, event_config: EventConfig) -> Result<(), String> {
+        self.writable_data().set_event_config(event_config)
+    }
+
+    /// Strike `target` with a disaster right away; see `SimulationData::trigger_disaster`
+    pub fn trigger_disaster(&mut self, kind: DisasterKind, target: u32) -> Result<(), String> {
+        self.writable_data().trigger_disaster(kind, target)?;
+        self.dispatch_events();
+        Ok(())
+    }
+
+    pub fn afflictions(&self) -> &[Affliction] {
+        self.data.afflictions()
+    }
+
+    pub fn calendar_config(&self) -> CalendarConfig {
+        self.data.calendar_config()
+    }
+
+    pub fn set_calendar_config(&mut self, calendar: CalendarConfig) -> Result<(), String> {
+        self.writable_data().set_calendar_config(calendar)
+    }
+
+    pub fn calendar_date(&self) -> CalendarDate {
+        self.data.calendar_date()
+    }
+
+    pub fn win_condition(&self) -> WinCondition {
+        self.data.win_condition()
+    }
+
+    pub fn set_win_condition(&mut self, win_condition: WinCondition) -> Result<(), String> {
+        self.writable_data().set_win_condition(win_condition)
+    }
+
+    pub fn victory_cells(&self) -> &[u32] {
+        self.data.victory_cells()
+    }
+
+    pub fn set_victory_cells(&mut self, cells: &[u32]) -> Result<(), String> {
+        self.writable_data().set_victory_cells(cells)
+    }
+
+    pub fn victor(&self) -> Option<u32> {
+        self.data.victor()
+    }
+
+    /// Resource node of every grid space as a `u8` (0 for none), row-major
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn grid_resources(&self) -> Vec<u8> {
+        self.data
+            .grid_spaces()
+            .iter()
+            .map(|space| space.resource.map_or(0, |node| node as u8))
+            .collect()
+    }
+
+    /// Terrain type of every grid space as a `u8`, row-major
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn grid_terrain(&self) -> Vec<u8> {
+        self.data
+            .grid_spaces()
+            .iter()
+            .map(|space| space.terrain as u8)
+            .collect()
+    }
+
+    /// Split part of an entity's military strength off into an army marching on `objective`
+    pub fn raise_army(&mut self, owner_id: u32, strength: f32, objective: u32) -> Result<u32, String> {
+        self.writable_data().raise_army(owner_id, strength, objective)
+    }
+
+    /// Convert part of an entity's money into military strength
+    pub fn buy_military(&mut self, entity_id: u32, amount: f32) -> Result<(), String> {
+        self.writable_data().buy_military(entity_id, amount)
+    }
+
+    pub fn set_behavior(&mut self, entity_id: u32, profile: BehaviorProfile) -> Result<(), String> {
+        self.writable_data().set_behavior(entity_id, profile)
+    }
+
+    /// Add a brain to the registry and return the id to assign it by
+    pub fn register_brain(&mut self, brain: Box<dyn Brain>) -> u32 {
+        self.brains.push(brain);
+        (self.brains.len() - 1) as u32
+    }
+
+    /// Have a registered brain decide for an entity from the next step on
+    pub fn set_brain(&mut self, entity_id: u32, brain_id: u32) -> Result<(), String> {
+        if brain_id as usize >= self.brains.len() {
+            return Err(format!("No brain is registered with id {}", brain_id));
+        }
+        self.writable_data().set_brain(entity_id, brain_id)
+    }
+
+    /// Have a behavior tree decide for an entity, replacing any tree loaded
+    /// for it before along with that tree's stats
+    pub fn load_behavior_tree(&mut self, entity_id: u32, tree: BehaviorTree) -> Result<(), String> {
+        // Each entity keeps one brain slot for its trees, reused on reload
+        let brain_id = match self.behavior_trees.get(&entity_id) {
+            Some(&brain_id) => brain_id,
+            None => self.brains.len() as u32,
+        };
+        self.writable_data().set_brain(entity_id, brain_id)?;
+        match self.brains.get_mut(brain_id as usize) {
+            Some(brain) => *brain = Box::new(tree),
+            None => {
+                self.brains.push(Box::new(tree));
+                self.behavior_trees.insert(entity_id, brain_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Execution counts of each node of the behavior tree deciding for an entity
+    pub fn behavior_tree_stats(&self, entity_id: u32) -> Result<&[NodeStats], String> {
+        self.behavior_tree_brain(entity_id)
+            .and_then(|brain_id| self.brains[brain_id as usize].node_stats())
+            .ok_or_else(|| format!("Entity {} is not running a behavior tree", entity_id))
+    }
+
+    /// Brain id of the tree loaded for an entity, if that tree still decides for it
+    fn behavior_tree_brain(&self, entity_id: u32) -> Option<u32> {
+        let brain_id = *self.behavior_trees.get(&entity_id)?;
+        let entity = self.data.entity(entity_id as usize)?;
+        (entity.brain == brain_id).then_some(brain_id)
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fn entities(&self) -> &[AiEntity] {
+        self.data.entities()
+    }
+
+    /// Logged events, oldest first
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fn events(&self) -> impl Iterator<Item = &SimulationEvent> {
+        self.data.events()
+    }
+
+    /// Reseed every entity's RNG from `seed`
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub fn reseed_entities(&mut self, seed: u32) {
+        self.writable_data().reseed_entities(seed);
+    }
+
+    pub fn armies(&self) -> &[Army] {
+        self.data.armies()
+    }
+
+    /// Queue a player command, taking the entity over from the AI
+    pub fn queue_command(&mut self, entity_id: u32, command: PlayerCommand) -> Result<(), String> {
+        self.writable_data().queue_command(entity_id, command)
+    }
+
+    /// Schedule a player command for the step that reaches `tick`, for lockstep play
+    pub fn schedule_command(&mut self, tick: u64, entity_id: u32, command: PlayerCommand) -> Result<(), String> {
+        self.writable_data().schedule_command(tick, entity_id, command)
+    }
+
+    pub fn scheduled_input_hash(&self, tick: u64) -> u64 {
+        self.data.scheduled_input_hash(tick)
+    }
+
+    /// Commands scheduled for ticks not yet simulated
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn scheduled_command_count(&self) -> usize {
+        self.data.scheduled_command_count()
+    }
+
+    pub fn input_hash(&self) -> u64 {
+        self.data.input_hash()
+    }
+
+    /// Apply one protocol command through the same paths as the direct calls.
+    /// A refused alliance isn't an error, so replaying a log stays idempotent.
+    pub fn apply_command(&mut self, command: Command) -> Result<(), String> {
+        match command {
+            Command::SpawnEntity { x, y, strength } => self.spawn_entity(x, y, strength).map(|_| ()),
+            Command::RemoveEntity { entity_id } => self.remove_entity(entity_id),
+            Command::QueueOrder { entity_id, order } => self.queue_command(entity_id, order),
+            Command::ScheduleOrder { tick, entity_id, order } => self.schedule_command(tick, entity_id, order),
+            Command::RaiseArmy { entity_id, strength, grid_index } => {
+                self.raise_army(entity_id, strength, grid_index).map(|_| ())
+            }
+            Command::BuyMilitary { entity_id, amount } => self.buy_military(entity_id, amount),
+            Command::SetBehavior { entity_id, profile } => self.set_behavior(entity_id, profile),
+            Command::ProposeAlliance { a, b } => {
+                self.propose_alliance(a, b);
+                Ok(())
+            }
+            Command::SetTickRate { tick_rate } => {
+                let tick_rate = SimError::check_tick_rate(tick_rate).map_err(|e| e.to_string())?;
+                self.set_tick_rate(tick_rate);
+                Ok(())
+            }
+            Command::SetTimeScale { time_scale } => {
+                self.set_time_scale(time_scale);
+                Ok(())
+            }
+            Command::Pause => {
+                self.pause();
+                Ok(())
+            }
+            Command::Resume => {
+                self.resume();
+                Ok(())
+            }
+        }
     }
 
     /// Process conquest attempts by attacking AIs
+    ///
+    /// Only the winning claim on each space strikes; the others keep their
+    /// strength for another tick. AI attackers that take their space may push
+    /// on to up to `max_conquests_per_tick` spaces in all, each costing more.
     fn process_conquests(&mut self) {
         let grid_size = self.data.grid_size();
         let entity_count = self.data.entity_len();
         
-        // First, defenders add to defense strength of their grid spaces
-        let mut defense_updates = Vec::new();
+        // First, defenders add to defense strength of the space they fortify (their
+        // home space by default), and allied supporters reinforce the home space
+        // of the ally they back
+        let mut defense_updates = mem::take(&mut self.defense_updates);
+        defense_updates.clear();
         for i in 0..entity_count {
             if let Some(entity) = self.data.entity(i) {
                 if entity.state == AiState::Defending {
-                    if let Some(grid_idx) = self.data.position_to_grid_index(entity.position_x, entity.position_y) {
+                    if let Some(grid_idx) = entity.fortify_target {
+                        defense_updates.push((grid_idx as usize, entity.id));
+                    } else if let Some(grid_idx) = self.data.position_to_grid_index(entity.position_x, entity.position_y) {
                         defense_updates.push((grid_idx, entity.id));
                     }
+                } else if let (AiState::Allied, Some(ally_id)) = (entity.state, entity.support_target) {
+                    if let Some(ally) = self.data.entity(ally_id as usize) {
+                        if let Some(grid_idx) = self.data.position_to_grid_index(ally.position_x, ally.position_y) {
+                            defense_updates.push((grid_idx, ally_id));
+                        }
+                    }
                 }
             }
         }
         
         // Apply defense updates
-        for (grid_idx, entity_id) in defense_updates {
-            if let Some(space) = self.data.grid_space_mut(grid_idx) {
+        for &(grid_idx, entity_id) in &defense_updates {
+            let mut changed = false;
+            if let Some(space) = self.writable_data().grid_space_mut(grid_idx) {
                 if space.owner_id == Some(entity_id) {
+                    let previous = space.defense_strength;
                     space.defense_strength += DEFENSE_ACCUMULATION;
                     // Cap defense strength
                     space.defense_strength = space.defense_strength.min(MAX_DEFENSE_STRENGTH);
+                    changed = space.defense_strength != previous;
                 }
             }
+            if changed {
+                self.writable_data().mark_grid_cell_dirty(grid_idx);
+            }
         }
+        self.defense_updates = defense_updates;
         
-        // Collect all attacking entities
-        let mut attackers = Vec::new();
+        // Collect attackers standing on their own space right next to the one
+        // they are after
+        let mut attackers = mem::take(&mut self.assaults);
+        attackers.clear();
         for i in 0..entity_count {
             if let Some(entity) = self.data.entity(i) {
-                if entity.state == AiState::Attacking && entity.military_strength >= ATTACK_COST {
-                    attackers.push((i, entity.id, entity.military_strength));
+                if entity.state != AiState::Attacking || entity.effective_strength() < ATTACK_COST {
+                    continue;
+                }
+                let (Some(target_grid_idx), Some(standing_idx)) = (
+                    entity.attack_space,
+                    self.data.position_to_grid_index(entity.position_x, entity.position_y),
+                ) else {
+                    continue;
+                };
+                let target_grid_idx = target_grid_idx as usize;
+                let in_reach = Pathfinder::are_adjacent(standing_idx, target_grid_idx, grid_size)
+                    && self.data.grid_spaces()[standing_idx].owner_id == Some(entity.id);
+                if in_reach {
+                    attackers.push((i, entity.id, entity.effective_strength(), target_grid_idx));
                 }
             }
         }
-        
-        // Build a list of (grid_idx, owner_id, defense_strength) to avoid borrowing issues
-        let grid_data: Vec<(Option<u32>, f32)> = self.data.grid_spaces()
-            .iter()
-            .map(|space| (space.owner_id, space.defense_strength))
-            .collect();
-        
-        // For each attacker, try to conquer an adjacent grid space
-        // Check adjacency to ALL owned spaces, not just the spawn position
-        for (attacker_idx, attacker_id, military_strength) in attackers {
-            let mut conquered = false;
-            
-            // Find all grid spaces owned by this attacker
-            for grid_idx in 0..grid_data.len() {
-                if conquered {
+
+        let terrain = self.data.terrain_config();
+        let resources = self.data.resource_config();
+        let conquest = self.data.conquest_config();
+        let attack_factor = self.data.season_effects().attack_factor;
+        let tick = self.data.tick() as u32;
+        for nth in 0..conquest.max_conquests_per_tick {
+            if nth > 0 {
+                // AI attackers that broke through push on from the space they
+                // just took, paying more for every extra space
+                let data = &self.data;
+                let state_updater = &self.state_updater;
+                attackers.retain_mut(|(attacker_idx, attacker_id, military_strength, target_grid_idx)| {
+                    let Some(entity) = data.entity(*attacker_idx) else {
+                        return false;
+                    };
+                    if entity.command.is_some() || data.grid_spaces()[*target_grid_idx].owner_id != Some(*attacker_id) {
+                        return false;
+                    }
+                    let next = state_updater.choose_follow_up_space(
+                        entity,
+                        data.grid_spaces(),
+                        grid_size,
+                        *target_grid_idx,
+                        &terrain,
+                        &resources,
+                    );
+                    match next {
+                        Some(next) => {
+                            *military_strength = entity.effective_strength();
+                            *target_grid_idx = next;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+                if attackers.is_empty() {
                     break;
                 }
-                
-                let (owner_id, _) = grid_data[grid_idx];
-                if owner_id != Some(attacker_id) {
-                    continue; // Not owned by this attacker
+            }
+
+            // Settle contested spaces before anyone strikes: each goes to its strongest
+            // claimant, with ties broken by a per-tick hash so entity order never decides
+            attackers.sort_unstable_by(|a, b| {
+                a.3.cmp(&b.3)
+                    .then(b.2.total_cmp(&a.2))
+                    .then_with(|| grid_hash(tick, a.3 as i32, a.1 as i32).cmp(&grid_hash(tick, b.3 as i32, b.1 as i32)))
+                    .then(a.1.cmp(&b.1))
+            });
+            attackers.dedup_by_key(|assault| assault.3);
+
+            let cost_multiplier = conquest.cost_multiplier(nth);
+            for &(attacker_idx, attacker_id, military_strength, target_grid_idx) in &attackers {
+                // Strength fights at its morale- and season-scaled value, so losses scale back down
+                let scale = self.data.entity(attacker_idx).map_or(1.0, |attacker| attacker.morale) * attack_factor;
+                let strength = military_strength * attack_factor;
+                let spent = self.assault_space(attacker_id, strength, target_grid_idx, cost_multiplier, &terrain);
+                if let Some(attacker) = self.writable_data().entity_mut(attacker_idx) {
+                    attacker.military_strength -= spent / scale;
+                }
+            }
+        }
+        self.assaults = attackers;
+    }
+
+    /// Throw `strength` of `attacker_id`'s military at a grid space and
+    /// return the strength it costs the attacker
+    ///
+    /// Nothing happens unless the strength covers the space's conquest cost,
+    /// scaled by `cost_multiplier`.
+    /// Unowned land is then simply claimed; spaces held by an enemy are
+    /// fought over with odds from the `CombatResolver`, rolled on the
+    /// attacker's seeded RNG, and both sides take casualties.
+    fn assault_space(
+        &mut self,
+        attacker_id: u32,
+        strength: f32,
+        grid_index: usize,
+        cost_multiplier: f32,
+        terrain: &TerrainConfig,
+    ) -> f32 {
+        // Read the space now so earlier conquests this tick are respected
+        let Some(&target_space) = self.data.grid_spaces().get(grid_index) else {
+            return 0.0;
+        };
+        let previous_owner = target_space.owner_id;
+        let friendly = previous_owner.is_some_and(|defender_id| {
+            defender_id == attacker_id
+                || self.data.alliances().are_allies(attacker_id, defender_id)
+                || self.data.is_vassal_of(defender_id, attacker_id)
+        });
+        // Fortifications and rough terrain both raise the price
+        let cost = target_space.conquest_cost(terrain) * cost_multiplier;
+        if friendly || strength < cost {
+            return 0.0;
+        }
+
+        let (success, spent) = match previous_owner {
+            None => (true, cost),
+            Some(defender_id) => {
+                let Some(roll) = self.writable_data().entity_mut(attacker_id as usize).map(|attacker| attacker.next_random()) else {
+                    return 0.0;
+                };
+                let outcome = self.combat_resolver.resolve(strength, cost, roll);
+                let mut defender_losses = 0.0;
+                if let Some(defender) = self.writable_data().entity_mut(defender_id as usize) {
+                    defender_losses = outcome.defender_losses.min(defender.military_strength.max(0.0));
+                    defender.military_strength -= defender_losses;
                 }
-                
-                // Try to conquer adjacent spaces
-                let row = grid_idx / grid_size;
-                let col = grid_idx % grid_size;
-                
-                // Check adjacent cells (4-directional)
-                let adjacent_offsets = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-                
-                for (dr, dc) in adjacent_offsets {
-                    if conquered {
-                        break;
+                if !outcome.success {
+                    if let Some(space) = self.writable_data().grid_space_mut(grid_index) {
+                        space.defense_strength *= 1.0 - COMBAT_DEFENSE_WEAR;
                     }
-                    
-                    let new_row = row as i32 + dr;
-                    let new_col = col as i32 + dc;
-                    
-                    if new_row < 0 || new_row >= grid_size as i32 || new_col < 0 || new_col >= grid_size as i32 {
-                        continue;
+                    if let Some(attacker) = self.writable_data().entity_mut(attacker_id as usize) {
+                        attacker.adjust_morale(-MORALE_LOSS_DRAIN);
                     }
-                    
-                    let target_grid_idx = (new_row as usize) * grid_size + (new_col as usize);
-                    
-                    // Check if this space is owned by a different AI or unowned
-                    let (target_owner_id, target_defense_strength) = grid_data[target_grid_idx];
-                    let (can_attack, total_defense) = if let Some(defender_id) = target_owner_id {
-                        if defender_id != attacker_id {
-                            let defense = ATTACK_COST + target_defense_strength * DEFENSE_BONUS_MULTIPLIER;
-                            (military_strength >= defense, defense)
-                        } else {
-                            (false, 0.0) // Own space
-                        }
-                    } else {
-                        // Unowned space
-                        (military_strength >= ATTACK_COST, ATTACK_COST)
-                    };
-                    
-                    if can_attack {
-                        // Conquest successful! Transfer ownership
-                        if let Some(target_space) = self.data.grid_space_mut(target_grid_idx) {
-                            target_space.owner_id = Some(attacker_id);
-                            target_space.defense_strength = 5.0;
-                        }
-                        
-                        // Deduct cost from attacker
-                        if let Some(attacker) = self.data.entity_mut(attacker_idx) {
-                            attacker.military_strength -= total_defense;
+                    self.writable_data().mark_grid_cell_dirty(grid_index);
+                }
+                let tick = self.data.tick();
+                self.writable_data()
+                    .relations_mut()
+                    .record_clash(attacker_id, defender_id, tick, RELATIONS_ASSAULT_DROP);
+                self.writable_data().record_event(SimulationEvent::Battle {
+                    tick,
+                    grid_index: grid_index as u32,
+                    attacker_id,
+                    defender_id,
+                    success: outcome.success,
+                    attacker_losses: outcome.attacker_losses,
+                    defender_losses,
+                });
+                (outcome.success, outcome.attacker_losses)
+            }
+        };
+
+        if success {
+            let conquest_unrest = self.data.unrest_config().conquest_unrest;
+            let captured_capital = previous_owner.filter(|&defender_id| {
+                self.data
+                    .entity(defender_id as usize)
+                    .is_some_and(|defender| defender.capital == Some(grid_index as u32))
+            });
+            if let Some(space) = self.writable_data().grid_space_mut(grid_index) {
+                space.owner_id = Some(attacker_id);
+                space.defense_strength = 5.0;
+                space.supply = 1.0;
+                space.garrison = 0.0;
+                space.unrest = conquest_unrest;
+                space.former_owner = previous_owner;
+            }
+            if let Some(attacker) = self.writable_data().entity_mut(attacker_id as usize) {
+                attacker.adjust_morale(MORALE_CONQUEST_GAIN);
+            }
+            if let Some(defender) = previous_owner.and_then(|id| self.writable_data().entity_mut(id as usize)) {
+                defender.adjust_morale(-MORALE_LOSS_DRAIN);
+            }
+            self.writable_data().mark_grid_cell_dirty(grid_index);
+            let tick = self.data.tick();
+            self.writable_data().record_event(SimulationEvent::Conquest {
+                tick,
+                grid_index: grid_index as u32,
+                attacker_id,
+                previous_owner,
+            });
+            if let Some(defender_id) = captured_capital {
+                let spaces_transferred = self.writable_data().capture_capital(defender_id, attacker_id, grid_index);
+                self.writable_data().record_event(SimulationEvent::CapitalCaptured {
+                    tick,
+                    grid_index: grid_index as u32,
+                    attacker_id,
+                    defender_id,
+                    spaces_transferred,
+                });
+            }
+        }
+        spent
+    }
+
+    /// Trace supply lines out from every capital; spaces cut off from theirs
+    /// run down and are eventually lost, so an encircled pocket falls without
+    /// a fight
+    fn update_supply(&mut self) {
+        self.writable_data().relocate_lost_capitals();
+        let data = &self.data;
+        let capitals = data.entities().iter().filter_map(|entity| entity.capital.map(|index| index as usize));
+        let connected = self.supply_network.trace(data.grid_spaces(), data.grid_size(), capitals);
+        let lost = Arc::make_mut(&mut self.data).apply_supply(connected);
+        if lost > 0 {
+            log!(LogLevel::Debug, "tick {}: {} cut-off grid spaces fell", self.data.tick(), lost);
+        }
+    }
+
+    /// Pick the frontier space each attacker goes for and walk it one tick
+    /// along the cheapest route to an owned space bordering it
+    ///
+    /// AI attackers go for the cheapest space of their target for the income
+    /// it brings (or unowned land when expanding); player attacks go for the
+    /// commanded space.
+    fn advance_attackers(&mut self) {
+        let grid_size = self.data.grid_size();
+        let terrain = self.data.terrain_config();
+        let resources = self.data.resource_config();
+        for i in 0..self.data.entity_len() {
+            let Some(entity) = self.data.entity(i) else {
+                continue;
+            };
+            let (id, state, command) = (entity.id, entity.state, entity.command);
+            let standing_idx = self.data.position_to_grid_index(entity.position_x, entity.position_y);
+
+            let plan = match (state, standing_idx) {
+                (AiState::Attacking, Some(from)) => {
+                    let grid_spaces = self.data.grid_spaces();
+                    match command {
+                        Some(PlayerCommand::Attack { grid_index }) => {
+                            let target = grid_index as usize;
+                            Pathfinder::staging_space(grid_spaces, grid_size, id, target, from)
+                                .map(|staging| (target, staging))
                         }
-                        
-                        conquered = true;
+                        _ => self.state_updater.choose_attack_space(
+                            entity,
+                            grid_spaces,
+                            grid_size,
+                            from,
+                            &terrain,
+                            &resources,
+                        ),
                     }
+                    .map(|(target, staging)| (from, target, staging))
+                }
+                _ => None,
+            };
+
+            let Some((from, target, staging)) = plan else {
+                if let Some(entity) = self.writable_data().entity_mut(i) {
+                    entity.attack_space = None;
+                }
+                continue;
+            };
+
+            let alliances = self.data.alliances();
+            let next_step = self
+                .pathfinder
+                .find_path(self.data.grid_spaces(), grid_size, from, staging, id, alliances, &terrain)
+                .and_then(|path| path.first().copied());
+            let waypoint = next_step.map(|index| self.data.grid_index_to_position(index));
+
+            if let Some(entity) = Arc::make_mut(&mut self.data).entity_mut(i) {
+                entity.attack_space = Some(target as u32);
+                if let Some(waypoint) = waypoint {
+                    self.state_updater
+                        .step_toward(&mut entity.position_x, &mut entity.position_y, waypoint);
                 }
             }
         }
     }
 
+    /// March every army one tick toward its objective
+    ///
+    /// Hostile armies sharing a grid space first wear each other down. An
+    /// army next to an enemy or unowned objective tries to take it, then
+    /// returns whatever strength is left to its owner, as does one that
+    /// reaches a friendly objective or finds no route. Armies of dead
+    /// entities are lost.
+    fn advance_armies(&mut self) {
+        let mut armies = mem::take(self.writable_data().armies_mut());
+        armies.retain(|army| {
+            self.data
+                .entity(army.owner_id as usize)
+                .is_some_and(|owner| owner.state != AiState::Dead)
+        });
+        if armies.is_empty() {
+            *self.writable_data().armies_mut() = armies;
+            return;
+        }
+
+        // Clashes between hostile armies in the same space
+        let cells: Vec<Option<usize>> = armies
+            .iter()
+            .map(|army| self.data.position_to_grid_index(army.position_x, army.position_y))
+            .collect();
+        for i in 0..armies.len() {
+            for j in (i + 1)..armies.len() {
+                let (a, b) = (armies[i], armies[j]);
+                let hostile = a.owner_id != b.owner_id
+                    && !self.data.alliances().are_allies(a.owner_id, b.owner_id);
+                if hostile && cells[i].is_some() && cells[i] == cells[j] {
+                    let losses = a.strength.min(b.strength);
+                    armies[i].strength -= losses;
+                    armies[j].strength -= losses;
+                }
+            }
+        }
+        armies.retain(|army| army.strength > 0.0);
+
+        let grid_size = self.data.grid_size();
+        let terrain = self.data.terrain_config();
+        let mut disbanded = Vec::new();
+        for (index, army) in armies.iter_mut().enumerate() {
+            let objective = army.objective as usize;
+            let Some(from) = self.data.position_to_grid_index(army.position_x, army.position_y) else {
+                disbanded.push(index);
+                continue;
+            };
+            let next_step = if from == objective {
+                Some(objective)
+            } else {
+                self.pathfinder
+                    .find_path(
+                        self.data.grid_spaces(),
+                        grid_size,
+                        from,
+                        objective,
+                        army.owner_id,
+                        self.data.alliances(),
+                        &terrain,
+                    )
+                    .and_then(|path| path.first().copied())
+            };
+            let Some(next_step) = next_step else {
+                disbanded.push(index);
+                continue;
+            };
+
+            let Some(&target_space) = self.data.grid_spaces().get(objective) else {
+                disbanded.push(index);
+                continue;
+            };
+            let friendly = target_space.owner_id.is_some_and(|owner_id| {
+                owner_id == army.owner_id
+                    || self.data.alliances().are_allies(army.owner_id, owner_id)
+                    || self.data.is_vassal_of(owner_id, army.owner_id)
+            });
+            if next_step != objective || (friendly && from != objective) {
+                let waypoint = self.data.grid_index_to_position(next_step);
+                self.state_updater
+                    .step_toward(&mut army.position_x, &mut army.position_y, waypoint);
+                continue;
+            }
+
+            if !friendly {
+                // Armies strike at the season's strength too
+                let attack_factor = self.data.season_effects().attack_factor;
+                let strength = army.strength * attack_factor;
+                army.strength -= self.assault_space(army.owner_id, strength, objective, 1.0, &terrain) / attack_factor;
+            }
+            disbanded.push(index);
+        }
+
+        // Survivors of a finished march rejoin their owner
+        for &index in disbanded.iter().rev() {
+            let army = armies.swap_remove(index);
+            if let Some(owner) = self.writable_data().entity_mut(army.owner_id as usize) {
+                owner.military_strength += army.strength;
+            }
+        }
+        armies.sort_by_key(|army| army.id);
+        *self.writable_data().armies_mut() = armies;
+    }
+
     pub fn request_snapshot(&mut self) -> Option<SimulationSnapshot> {
         if !self.data.snapshot_dirty() {
             return None;
@@ -345,42 +1441,229 @@ impl SimulationLogic {
 
         let (snapshot, duration) = self
             .benchmark_builder
-            .measure_snapshot(|| self.data.build_public_snapshot());
+            .measure_snapshot(|| Arc::make_mut(&mut self.data).build_public_snapshot());
         if duration > 0.0 {
-            self.data.metrics_mut().update_snapshot(duration);
+            self.writable_data().metrics_mut().update_snapshot(duration);
         }
         Some(snapshot)
     }
 
-    #[cfg(target_arch = "wasm32")]
     pub fn request_flat_snapshot(&mut self) -> Option<&[f32]> {
         if !self.data.flat_snapshot_dirty() {
             return Some(self.data.flat_snapshot_slice());
         }
 
         let (_, duration) = self.benchmark_builder.measure_snapshot(|| {
-            self.data.ensure_flat_snapshot_ready();
+            Arc::make_mut(&mut self.data).ensure_flat_snapshot_ready();
         });
         if duration > 0.0 {
-            self.data.metrics_mut().update_snapshot(duration);
+            self.writable_data().metrics_mut().update_snapshot(duration);
         }
         Some(self.data.flat_snapshot_slice())
     }
 
+    /// Select the flat and binary snapshot fields by `SnapshotField` bit
+    pub fn set_snapshot_fields(&mut self, mask: u32) -> Result<(), String> {
+        self.writable_data().set_snapshot_fields(mask)
+    }
+
+    pub fn snapshot_schema(&self) -> SnapshotSchema {
+        self.data.snapshot_schema()
+    }
+
+    /// Every entity in the compact binary format of `encode_snapshot_v1`, with
+    /// the selected snapshot fields, encoded fresh on each call
+    pub fn request_binary_snapshot(&mut self) -> Vec<u8> {
+        let (bytes, duration) = self.benchmark_builder.measure_snapshot(|| {
+            encode_snapshot_v1(self.data.tick(), self.data.entities(), self.data.snapshot_fields())
+        });
+        if duration > 0.0 {
+            self.writable_data().metrics_mut().update_snapshot(duration);
+        }
+        bytes
+    }
+
+    /// Owner ids and defense strengths for every grid space, row-major
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn request_grid_snapshot(&mut self) -> (&[u32], &[f32]) {
+        let (_, duration) = self.benchmark_builder.measure_snapshot(|| {
+            Arc::make_mut(&mut self.data).rebuild_grid_snapshot();
+        });
+        if duration > 0.0 {
+            self.writable_data().metrics_mut().update_snapshot(duration);
+        }
+        self.data.grid_snapshot_slices()
+    }
+
+    /// Grid spaces changed since the previous diff request
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn request_grid_diff(&mut self) -> &GridDiff {
+        self.writable_data().take_grid_diff()
+    }
+
+    pub fn state_hash(&self) -> u64 {
+        self.data.state_hash()
+    }
+
+    pub fn sync_bundle(&self) -> SyncBundle {
+        self.data.build_sync_bundle()
+    }
+
+    /// Adopt the state of another simulation so a late joiner can continue from it;
+    /// the joiner needs the host's brains registered under the same ids
+    pub fn apply_sync_bundle(&mut self, bundle: &SyncBundle) -> Result<(), String> {
+        if let Some(entity) = bundle.entities.iter().find(|entity| entity.brain as usize >= self.brains.len()) {
+            return Err(format!("Sync bundle entity {} uses brain {}, which is not registered", entity.id, entity.brain));
+        }
+        self.writable_data().apply_sync_bundle(bundle)?;
+        self.grid_builder = GridUpdateBuilder::for_world(&bundle.world);
+        self.writable_data().mark_snapshots_dirty();
+        self.spatial_index_stale = true;
+        Ok(())
+    }
+
+    /// Alive entity ids within `radius` of a world position, nearest first
+    pub fn entities_near(&mut self, x: f32, y: f32, radius: f32) -> Vec<u32> {
+        self.ensure_spatial_index();
+        self.grid_builder
+            .nearest_within(x, y, radius)
+            .into_iter()
+            .map(|index| index as u32)
+            .collect()
+    }
+
+    /// Alive entities and grid spaces inside the rectangle between two corners,
+    /// so a renderer only receives what its camera can see
+    pub fn snapshot_in_rect(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) -> ViewportSnapshot {
+        if [x0, y0, x1, y1].iter().any(|value| value.is_nan()) {
+            return ViewportSnapshot {
+                entities: Vec::new(),
+                grid_cells: Vec::new(),
+            };
+        }
+        let (min, max) = ((x0.min(x1), y0.min(y1)), (x0.max(x1), y0.max(y1)));
+        self.ensure_spatial_index();
+        let indices = self.grid_builder.within_rect(min, max);
+        let (snapshot, duration) = self
+            .benchmark_builder
+            .measure_snapshot(|| self.data.build_viewport_snapshot(&indices, min, max));
+        if duration > 0.0 {
+            self.writable_data().metrics_mut().update_snapshot(duration);
+        }
+        snapshot
+    }
+
+    /// Ids of the `k` alive entities closest to a world position, nearest first
+    pub fn nearest_entities(&mut self, x: f32, y: f32, k: usize) -> Vec<u32> {
+        self.ensure_spatial_index();
+        self.grid_builder
+            .nearest_k(x, y, k)
+            .into_iter()
+            .map(|index| index as u32)
+            .collect()
+    }
+
+    /// Re-index entity positions if they changed since the last tick's rebuild
+    fn ensure_spatial_index(&mut self) {
+        if self.spatial_index_stale {
+            self.neighbor_builder.rebuild_snapshots(Arc::make_mut(&mut self.data));
+            self.grid_builder.rebuild(self.data.snapshots());
+            self.spatial_index_stale = false;
+        }
+    }
+
+    pub fn spatial_overflow_count(&self) -> usize {
+        self.grid_builder.overflow_count()
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        self.data.diagnose(self.grid_builder.overflow_count())
+    }
+
+    /// Check the state after every step and warn about anything wrong;
+    /// release builds skip the check
+    pub fn set_step_validation(&mut self, enabled: bool) {
+        self.validate_steps = enabled;
+    }
+
     pub fn last_tick_duration(&self) -> f64 {
-        self.data.metrics().last_tick_duration_ms
+        self.data.metrics().last_tick_duration_ms()
     }
 
     pub fn last_snapshot_duration(&self) -> f64 {
-        self.data.metrics().last_snapshot_duration_ms
+        self.data.metrics().last_snapshot_duration_ms()
+    }
+
+    /// Phase timings of the most recent tick
+    pub fn tick_profile(&self) -> TickProfile {
+        self.tick_profiler.profile
+    }
+
+    /// Rolling duration statistics for ticks, snapshots and each tick phase
+    pub fn metrics_report(&self) -> MetricsReport {
+        self.data.metrics().report()
+    }
+
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.data.memory_stats()
+    }
+
+    /// Hand unused buffer capacity back to the allocator
+    pub fn shrink_to_fit(&mut self) {
+        self.writable_data().shrink_to_fit();
+        self.commands.shrink_to_fit();
     }
 
     pub fn destroy(&mut self) {
-        self.data.destroy();
+        self.writable_data().destroy();
+        self.spatial_index_stale = true;
+    }
+
+    /// Remember the current state for `rollback_to`. The checkpoint shares the
+    /// state until the next change copies it, so creating one every tick is cheap
+    /// while nothing changes; only the newest `MAX_CHECKPOINTS` are kept.
+    pub fn create_checkpoint(&mut self) -> u32 {
+        let id = self.next_checkpoint_id;
+        self.next_checkpoint_id = self.next_checkpoint_id.wrapping_add(1);
+        if self.checkpoints.len() >= MAX_CHECKPOINTS {
+            self.checkpoints.remove(0);
+        }
+        self.checkpoints.push((id, Arc::clone(&self.data)));
+        id
+    }
+
+    /// Restore a checkpoint so late remote commands can be applied before
+    /// re-simulating forward. Later checkpoints belong to the discarded future
+    /// and are dropped; the restored one stays usable. Re-simulation only
+    /// reproduces the same ticks under a fixed timestep.
+    pub fn rollback_to(&mut self, checkpoint_id: u32) -> Result<(), String> {
+        let index = self
+            .checkpoints
+            .iter()
+            .position(|(id, _)| *id == checkpoint_id)
+            .ok_or_else(|| format!("Unknown checkpoint {}", checkpoint_id))?;
+        self.checkpoints.truncate(index + 1);
+        let world = self.data.world_config();
+        self.data = Arc::clone(&self.checkpoints[index].1);
+        if self.data.world_config() != world {
+            self.grid_builder = GridUpdateBuilder::for_world(&self.data.world_config());
+        }
+        self.spatial_index_stale = true;
+        Ok(())
+    }
+
+    pub fn checkpoint_count(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    /// Live state for writing, copied first if a checkpoint still shares it
+    fn writable_data(&mut self) -> &mut SimulationData {
+        Arc::make_mut(&mut self.data)
     }
 
-    #[cfg(test)]
+    #[cfg(any(test, feature = "bench"))]
     pub fn data_mut(&mut self) -> &mut SimulationData {
-        &mut self.data
+        self.spatial_index_stale = true;
+        self.writable_data()
     }
 }
@@ -0,0 +1,232 @@
+//! Invariants checked over random worlds, seeds and command sequences
+//!
+//! `cargo test properties` runs them natively; proptest shrinks any failure
+//! to the smallest world and command sequence that still breaks an invariant.
+
+use proptest::prelude::*;
+
+use crate::decision_scoring::{DecisionSystem, Scenario};
+use crate::logic::SimulationLogic;
+use crate::protocol::Command;
+use crate::types::grid_space::NEUTRAL_GRID_OWNER;
+use crate::types::{
+    BehaviorProfile, EventConfig, PlayerCommand, TerrainConfig, TimeMode, WorldConfig, UNOWNED_GRID_OWNER,
+};
+
+const MS_PER_TICK: f64 = 50.0;
+
+/// Everything a run starts from
+#[derive(Debug, Clone)]
+struct SimSetup {
+    entity_count: usize,
+    grid_size: usize,
+    entity_seed: u32,
+    terrain_seed: u32,
+    disaster_chance: f32,
+}
+
+impl SimSetup {
+    /// A fresh run on the fixed clock, so the same setup always plays out the same
+    fn build(&self) -> SimulationLogic {
+        let world = WorldConfig::default().with_grid_size(self.grid_size);
+        let mut logic = SimulationLogic::with_world(self.entity_count, world);
+        logic.set_time_mode(TimeMode::fixed(MS_PER_TICK).unwrap());
+        logic.reseed_entities(self.entity_seed);
+        let terrain = TerrainConfig {
+            seed: self.terrain_seed,
+            ..TerrainConfig::default()
+        };
+        logic.set_terrain_config(terrain).unwrap();
+        let events = EventConfig {
+            seed: self.entity_seed,
+            plague_chance: self.disaster_chance,
+            famine_chance: self.disaster_chance,
+            earthquake_chance: self.disaster_chance,
+            ..EventConfig::default()
+        };
+        logic.set_event_config(events).unwrap();
+        logic
+    }
+}
+
+fn sim_setup() -> impl Strategy<Value = SimSetup> {
+    (2usize..=12, 4usize..=10, any::<u32>(), any::<u32>(), 0.0f32..=0.5).prop_map(
+        |(entity_count, grid_size, entity_seed, terrain_seed, disaster_chance)| SimSetup {
+            entity_count,
+            grid_size,
+            entity_seed,
+            terrain_seed,
+            disaster_chance,
+        },
+    )
+}
+
+fn player_command() -> impl Strategy<Value = PlayerCommand> {
+    prop_oneof![
+        (0u32..100).prop_map(|grid_index| PlayerCommand::MoveTo { grid_index }),
+        (0u32..100).prop_map(|grid_index| PlayerCommand::Attack { grid_index }),
+        Just(PlayerCommand::Defend),
+        Just(PlayerCommand::Hold),
+    ]
+}
+
+fn behavior_profile() -> impl Strategy<Value = BehaviorProfile> {
+    prop_oneof![
+        Just(BehaviorProfile::Aggressive),
+        Just(BehaviorProfile::Turtle),
+        Just(BehaviorProfile::Economic),
+        Just(BehaviorProfile::Opportunist),
+    ]
+}
+
+/// Any command a peer could send; ids and grid indices run past the world
+/// now and then, which the simulation must turn away without harm
+fn command() -> impl Strategy<Value = Command> {
+    let entity_id = 0u32..16;
+    prop_oneof![
+        (-1200.0f32..1200.0, -1200.0f32..1200.0, 0.0f32..100.0)
+            .prop_map(|(x, y, strength)| Command::SpawnEntity { x, y, strength }),
+        entity_id.clone().prop_map(|entity_id| Command::RemoveEntity { entity_id }),
+        (entity_id.clone(), player_command()).prop_map(|(entity_id, order)| Command::QueueOrder { entity_id, order }),
+        (entity_id.clone(), 0.0f32..50.0, 0u32..100)
+            .prop_map(|(entity_id, strength, grid_index)| Command::RaiseArmy { entity_id, strength, grid_index }),
+        (entity_id.clone(), entity_id.clone()).prop_map(|(a, b)| Command::ProposeAlliance { a, b }),
+        (entity_id.clone(), 0.0f32..20.0).prop_map(|(entity_id, amount)| Command::BuyMilitary { entity_id, amount }),
+        (entity_id, behavior_profile()).prop_map(|(entity_id, profile)| Command::SetBehavior { entity_id, profile }),
+        (1u32..=120).prop_map(|tick_rate| Command::SetTickRate { tick_rate }),
+    ]
+}
+
+/// Commands to apply before each tick, one batch per tick
+fn command_batches() -> impl Strategy<Value = Vec<Vec<Command>>> {
+    prop::collection::vec(prop::collection::vec(command(), 0..3), 1..40)
+}
+
+/// Apply a batch, ignoring the commands the simulation turns away
+fn apply_batch(logic: &mut SimulationLogic, batch: &[Command]) {
+    for &command in batch {
+        let _ = logic.apply_command(command);
+    }
+}
+
+/// Every grid space counts once, toward its owner's territory or as unowned land,
+/// garrisoned by neutrals or not
+fn check_territory(logic: &mut SimulationLogic) -> Result<(), TestCaseError> {
+    let cells = logic.grid_size() * logic.grid_size();
+    let territory: usize = logic.entities().iter().map(|entity| entity.territory as usize).sum();
+    let (owners, _) = logic.request_grid_snapshot();
+    prop_assert_eq!(owners.len(), cells);
+    let unowned = owners
+        .iter()
+        .filter(|&&owner| owner == UNOWNED_GRID_OWNER || owner == NEUTRAL_GRID_OWNER)
+        .count();
+    prop_assert_eq!(territory + unowned, cells);
+    Ok(())
+}
+
+/// A decision world of `territories.len()` countries, each bordering the next
+fn decision_system(seed: u64, territories: &[u32], policy: &str) -> DecisionSystem {
+    let mut system = DecisionSystem::init(seed);
+    let count = territories.len() as u32;
+    for (id, territory) in (0..count).zip(territories) {
+        system.add_country(id);
+        let stats = format!(r#"{{"territory": {}, "m_eff": {}}}"#, territory, 50 + territory * 10);
+        system.set_country_stats(id, &stats).unwrap();
+    }
+    for id in 0..count {
+        let next = (id + 1) % count;
+        if next != id {
+            system.add_edge(id, next, 1, 0.7);
+            system.add_edge(next, id, 1, 0.7);
+        }
+    }
+    system.set_selection_policy(policy).unwrap();
+    system
+}
+
+fn total_territory(system: &DecisionSystem) -> u32 {
+    let scenario: Scenario = serde_json::from_str(&system.export_scenario().unwrap()).unwrap();
+    scenario.countries.iter().map(|country| country.stats.territory.unwrap_or(0)).sum()
+}
+
+fn selection_policy() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(r#"{"kind": "argmax"}"#.to_string()),
+        (0.0f32..=1.0).prop_map(|epsilon| format!(r#"{{"kind": "epsilon_greedy", "epsilon": {}}}"#, epsilon)),
+        (0.1f32..10.0).prop_map(|temperature| format!(r#"{{"kind": "softmax", "temperature": {}}}"#, temperature)),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn territory_is_conserved_across_ticks(setup in sim_setup(), batches in command_batches()) {
+        let mut logic = setup.build();
+        for batch in &batches {
+            apply_batch(&mut logic, batch);
+            logic.step();
+            check_territory(&mut logic)?;
+        }
+    }
+
+    #[test]
+    fn tick_counter_advances_by_one_per_step(setup in sim_setup(), batches in command_batches()) {
+        let mut logic = setup.build();
+        for batch in &batches {
+            apply_batch(&mut logic, batch);
+            let tick = logic.tick();
+            logic.step();
+            prop_assert_eq!(logic.tick(), tick + 1);
+        }
+    }
+
+    #[test]
+    fn fixed_clock_runs_are_deterministic(setup in sim_setup(), batches in command_batches()) {
+        let mut a = setup.build();
+        let mut b = setup.build();
+        for batch in &batches {
+            apply_batch(&mut a, batch);
+            apply_batch(&mut b, batch);
+            a.step();
+            b.step();
+            prop_assert_eq!(a.state_hash(), b.state_hash(), "diverged on tick {}", a.tick());
+        }
+    }
+
+    #[test]
+    fn decision_ticks_conserve_territory_and_count_up(
+        seed in any::<u64>(),
+        territories in prop::collection::vec(1u32..50, 2..8),
+        policy in selection_policy(),
+        ticks in 1u64..30,
+    ) {
+        let mut system = decision_system(seed, &territories, &policy);
+        let total: u32 = territories.iter().sum();
+        for tick in 0..ticks {
+            prop_assert_eq!(system.get_tick(), tick);
+            system.tick();
+            prop_assert_eq!(total_territory(&system), total);
+        }
+        prop_assert_eq!(system.get_tick(), ticks);
+    }
+
+    #[test]
+    fn decision_runs_with_the_same_seed_are_deterministic(
+        seed in any::<u64>(),
+        territories in prop::collection::vec(1u32..50, 2..8),
+        policy in selection_policy(),
+        ticks in 1u64..30,
+    ) {
+        let mut a = decision_system(seed, &territories, &policy);
+        let mut b = decision_system(seed, &territories, &policy);
+        for _ in 0..ticks {
+            a.tick();
+            b.tick();
+            prop_assert_eq!(a.deltas(), b.deltas());
+        }
+        prop_assert_eq!(a.export_scenario().unwrap(), b.export_scenario().unwrap());
+        let (logs_a, logs_b) = (a.take_logs(), b.take_logs());
+        prop_assert_eq!(format!("{:?}", logs_a), format!("{:?}", logs_b));
+    }
+}
@@ -0,0 +1,202 @@
+//! Binary command protocol for driving a simulation over the network
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::binary_snapshot::ByteReader;
+use crate::types::{BehaviorProfile, PlayerCommand};
+
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// One change to a simulation, as sent between peers or from a server.
+/// JSON uses a `kind` tag, e.g. `{"kind":"SpawnEntity","x":0,"y":0,"strength":50}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Command {
+    SpawnEntity { x: f32, y: f32, strength: f32 },
+    RemoveEntity { entity_id: u32 },
+    /// Player order applied on the next step
+    QueueOrder { entity_id: u32, order: PlayerCommand },
+    /// Player order applied on the step that reaches `tick`, for lockstep play
+    ScheduleOrder { tick: u64, entity_id: u32, order: PlayerCommand },
+    /// Send part of an entity's military to assault a grid space
+    RaiseArmy { entity_id: u32, strength: f32, grid_index: u32 },
+    ProposeAlliance { a: u32, b: u32 },
+    SetTickRate { tick_rate: u32 },
+    SetTimeScale { time_scale: f32 },
+    Pause,
+    Resume,
+    /// Spend an entity's money on military strength
+    BuyMilitary { entity_id: u32, amount: f32 },
+    /// Switch an entity's AI to another behavior profile
+    SetBehavior { entity_id: u32, profile: BehaviorProfile },
+}
+
+impl Command {
+    fn opcode(&self) -> u8 {
+        match self {
+            Command::SpawnEntity { .. } => 1,
+            Command::RemoveEntity { .. } => 2,
+            Command::QueueOrder { .. } => 3,
+            Command::ScheduleOrder { .. } => 4,
+            Command::RaiseArmy { .. } => 5,
+            Command::ProposeAlliance { .. } => 6,
+            Command::SetTickRate { .. } => 7,
+            Command::SetTimeScale { .. } => 8,
+            Command::Pause => 9,
+            Command::Resume => 10,
+            Command::BuyMilitary { .. } => 11,
+            Command::SetBehavior { .. } => 12,
+        }
+    }
+}
+
+/// Encode commands as one little-endian message:
+///
+/// ```text
+/// version  u8   (PROTOCOL_VERSION)
+/// commands back to back, each an opcode u8 and its fields:
+///   1 SpawnEntity      x f32, y f32, strength f32
+///   2 RemoveEntity     entity_id u32
+///   3 QueueOrder       entity_id u32, order
+///   4 ScheduleOrder    tick u64, entity_id u32, order
+///   5 RaiseArmy        entity_id u32, strength f32, grid_index u32
+///   6 ProposeAlliance  a u32, b u32
+///   7 SetTickRate      tick_rate u32
+///   8 SetTimeScale     time_scale f32
+///   9 Pause, 10 Resume (no fields)
+///  11 BuyMilitary      entity_id u32, amount f32
+///  12 SetBehavior      entity_id u32, profile u8 (0 Aggressive, 1 Turtle, 2 Economic, 3 Opportunist)
+/// order = kind u8 (0 MoveTo, 1 Attack, 2 Defend, 3 Hold), grid_index u32 (0 if unused)
+/// ```
+///
+/// There is no checksum; the transport (e.g. a WebSocket) is expected to deliver bytes intact
+pub fn encode_commands(commands: &[Command]) -> Vec<u8> {
+    let mut bytes = vec![PROTOCOL_VERSION];
+    for command in commands {
+        bytes.push(command.opcode());
+        match *command {
+            Command::SpawnEntity { x, y, strength } => {
+                for value in [x, y, strength] {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            Command::RemoveEntity { entity_id } => bytes.extend_from_slice(&entity_id.to_le_bytes()),
+            Command::QueueOrder { entity_id, order } => {
+                bytes.extend_from_slice(&entity_id.to_le_bytes());
+                encode_order(&mut bytes, order);
+            }
+            Command::ScheduleOrder { tick, entity_id, order } => {
+                bytes.extend_from_slice(&tick.to_le_bytes());
+                bytes.extend_from_slice(&entity_id.to_le_bytes());
+                encode_order(&mut bytes, order);
+            }
+            Command::RaiseArmy { entity_id, strength, grid_index } => {
+                bytes.extend_from_slice(&entity_id.to_le_bytes());
+                bytes.extend_from_slice(&strength.to_le_bytes());
+                bytes.extend_from_slice(&grid_index.to_le_bytes());
+            }
+            Command::ProposeAlliance { a, b } => {
+                bytes.extend_from_slice(&a.to_le_bytes());
+                bytes.extend_from_slice(&b.to_le_bytes());
+            }
+            Command::SetTickRate { tick_rate } => bytes.extend_from_slice(&tick_rate.to_le_bytes()),
+            Command::SetTimeScale { time_scale } => bytes.extend_from_slice(&time_scale.to_le_bytes()),
+            Command::Pause | Command::Resume => {}
+            Command::BuyMilitary { entity_id, amount } => {
+                bytes.extend_from_slice(&entity_id.to_le_bytes());
+                bytes.extend_from_slice(&amount.to_le_bytes());
+            }
+            Command::SetBehavior { entity_id, profile } => {
+                bytes.extend_from_slice(&entity_id.to_le_bytes());
+                bytes.push(profile as u8);
+            }
+        }
+    }
+    bytes
+}
+
+fn encode_order(bytes: &mut Vec<u8>, command: PlayerCommand) {
+    let (kind, target) = command.key();
+    bytes.push(kind as u8);
+    bytes.extend_from_slice(&target.to_le_bytes());
+}
+
+/// Decode a message written by `encode_commands`, rejecting unknown versions
+/// and opcodes, truncated data, non-finite numbers and targets on orders that take none
+pub fn decode_commands(bytes: &[u8]) -> Result<Vec<Command>, String> {
+    let mut reader = ByteReader::new(bytes);
+    let version = reader.u8()?;
+    if version != PROTOCOL_VERSION {
+        return Err(format!(
+            "Unsupported command protocol version {} (expected {})",
+            version, PROTOCOL_VERSION
+        ));
+    }
+
+    let mut commands = Vec::new();
+    while reader.remaining() > 0 {
+        let command = match reader.u8()? {
+            1 => Command::SpawnEntity {
+                x: finite(reader.f32()?)?,
+                y: finite(reader.f32()?)?,
+                strength: finite(reader.f32()?)?,
+            },
+            2 => Command::RemoveEntity { entity_id: reader.u32()? },
+            3 => Command::QueueOrder {
+                entity_id: reader.u32()?,
+                order: decode_order(&mut reader)?,
+            },
+            4 => Command::ScheduleOrder {
+                tick: reader.u64()?,
+                entity_id: reader.u32()?,
+                order: decode_order(&mut reader)?,
+            },
+            5 => Command::RaiseArmy {
+                entity_id: reader.u32()?,
+                strength: finite(reader.f32()?)?,
+                grid_index: reader.u32()?,
+            },
+            6 => Command::ProposeAlliance { a: reader.u32()?, b: reader.u32()? },
+            7 => Command::SetTickRate { tick_rate: reader.u32()? },
+            8 => Command::SetTimeScale { time_scale: finite(reader.f32()?)? },
+            9 => Command::Pause,
+            10 => Command::Resume,
+            11 => Command::BuyMilitary {
+                entity_id: reader.u32()?,
+                amount: finite(reader.f32()?)?,
+            },
+            12 => Command::SetBehavior {
+                entity_id: reader.u32()?,
+                profile: decode_profile(&mut reader)?,
+            },
+            opcode => return Err(format!("Unknown command opcode {}", opcode)),
+        };
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+fn decode_order(reader: &mut ByteReader) -> Result<PlayerCommand, String> {
+    let kind = reader.u8()?;
+    let target = reader.u32()?;
+    let order =
+        PlayerCommand::from_key(kind as u32, target).ok_or_else(|| format!("Unknown player command kind {}", kind))?;
+    // Orders without a grid space always encode a zero target
+    if order.key() != (kind as u32, target) {
+        return Err(format!("Player command kind {} takes no target, got {}", kind, target));
+    }
+    Ok(order)
+}
+
+fn decode_profile(reader: &mut ByteReader) -> Result<BehaviorProfile, String> {
+    let index = reader.u8()?;
+    BehaviorProfile::from_index(index).ok_or_else(|| format!("Unknown behavior profile {}", index))
+}
+
+fn finite(value: f32) -> Result<f32, String> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(format!("Command holds a non-finite number: {}", value))
+    }
+}
@@ -0,0 +1,108 @@
+use wasm_bindgen::prelude::*;
+
+use crate::error::SimError;
+use crate::logic::ComparisonRunner;
+use crate::types::RunConfig;
+
+use super::sim_handler::to_js;
+
+/// Two simulations from the same seed stepped in lockstep, for seeing how a
+/// config change plays out against the original
+#[wasm_bindgen]
+pub struct ComparisonHandler {
+    runner: ComparisonRunner,
+}
+
+#[wasm_bindgen]
+impl ComparisonHandler {
+    /// Compare two `RunConfig`s given as JSON such as `{"entity_count":20}`;
+    /// omitted fields keep their defaults and `seed` replaces both configs' seeds
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u32, config_a_json: &str, config_b_json: &str) -> Result<ComparisonHandler, SimError> {
+        let config_a: RunConfig = serde_json::from_str(config_a_json)?;
+        let config_b: RunConfig = serde_json::from_str(config_b_json)?;
+        SimError::check_entity_count(config_a.entity_count)?;
+        SimError::check_entity_count(config_b.entity_count)?;
+        Ok(Self {
+            runner: ComparisonRunner::new(seed, &config_a, &config_b)?,
+        })
+    }
+
+    /// Step both simulations up to `ticks` times, stopping once both runs are
+    /// decided, and return how many ticks ran
+    #[wasm_bindgen]
+    pub fn step(&mut self, ticks: u32) -> u32 {
+        self.runner.step_many(ticks as u64) as u32
+    }
+
+    #[wasm_bindgen]
+    pub fn get_tick(&self) -> u64 {
+        self.runner.a().tick()
+    }
+
+    /// Whether both runs are decided
+    #[wasm_bindgen]
+    pub fn is_complete(&self) -> bool {
+        self.runner.is_complete()
+    }
+
+    /// Territory distance and alive counts for every tick stepped so far, with their extremes
+    #[wasm_bindgen(unchecked_return_type = "DivergenceReport")]
+    pub fn get_divergence_report(&self) -> Result<JsValue, SimError> {
+        to_js(&self.runner.report())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAGUE: &str = r#"{"entity_count": 8, "events": {"plague_chance": 1.0, "plague_military_factor": 0.0}}"#;
+
+    #[test]
+    fn identical_configs_never_diverge() {
+        let mut comparison = ComparisonHandler::new(7, r#"{"entity_count": 8}"#, r#"{"entity_count": 8}"#).unwrap();
+        assert_eq!(comparison.step(200), 200);
+        assert_eq!(comparison.get_tick(), 200);
+
+        let report = comparison.runner.report();
+        assert_eq!(report.ticks, 200);
+        assert_eq!(report.samples.len(), 200);
+        assert_eq!(report.max_territory_distance, 0.0);
+        assert_eq!(report.max_alive_difference, 0);
+        assert_eq!(report.first_divergent_tick, None);
+    }
+
+    #[test]
+    fn a_config_change_shows_up_as_divergence() {
+        let mut comparison = ComparisonHandler::new(7, r#"{"entity_count": 8}"#, PLAGUE).unwrap();
+        comparison.step(300);
+
+        let report = comparison.runner.report();
+        let first = report.first_divergent_tick.expect("a permanent plague changes the run");
+        assert!(report.max_territory_distance > 0.0);
+        assert!(report.mean_territory_distance <= report.max_territory_distance);
+        assert!(report.samples.iter().all(|sample| (0.0..=1.0).contains(&sample.territory_distance)));
+        assert!(report.samples.iter().take_while(|sample| sample.tick < first).all(|sample| !sample.is_divergent()));
+    }
+
+    #[test]
+    fn comparisons_are_deterministic() {
+        let run = || {
+            let mut comparison = ComparisonHandler::new(11, r#"{"entity_count": 8}"#, PLAGUE).unwrap();
+            comparison.step(150);
+            comparison.runner.report()
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn rejects_bad_configs() {
+        assert_eq!(
+            ComparisonHandler::new(1, r#"{"entity_count": 0}"#, "{}").err(),
+            Some(SimError::ZeroEntityCount)
+        );
+        assert!(matches!(ComparisonHandler::new(1, "{}", "not json"), Err(SimError::MalformedInput(_))));
+        assert!(ComparisonHandler::new(1, "{}", r#"{"ms_per_tick": 0}"#).is_err());
+    }
+}
@@ -1,3 +1,11 @@
+mod comparison_handler;
+mod scheduler;
 mod sim_handler;
+#[cfg(all(target_arch = "wasm32", feature = "parallel"))]
+mod thread_pool;
 
+pub use comparison_handler::ComparisonHandler;
+pub use scheduler::Scheduler;
 pub use sim_handler::SimulationHandler;
+#[cfg(all(target_arch = "wasm32", feature = "parallel"))]
+pub use thread_pool::init_thread_pool;
@@ -0,0 +1,179 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+
+#[cfg(target_arch = "wasm32")]
+use crate::error::SimError;
+use crate::logic::SimulationLogic;
+use crate::types::FrameClock;
+
+use super::SimulationHandler;
+
+/// Game loop for a simulation: steps it at its tick rate from
+/// `requestAnimationFrame` or `setInterval`, so JS doesn't run its own timer
+#[wasm_bindgen]
+pub struct Scheduler {
+    logic: Rc<RefCell<SimulationLogic>>,
+    clock: Rc<RefCell<FrameClock>>,
+    #[cfg(target_arch = "wasm32")]
+    attachment: Rc<RefCell<Option<Attachment>>>,  // Timer currently driving the loop
+}
+
+/// A running browser timer and the callback it calls
+#[cfg(target_arch = "wasm32")]
+enum Attachment {
+    AnimationFrame { handle: i32, callback: Closure<dyn FnMut(f64)> },
+    Interval { handle: i32, _callback: Closure<dyn FnMut()> },
+}
+
+#[wasm_bindgen]
+impl Scheduler {
+    /// Create a detached scheduler for `simulation`; the simulation stays usable from JS
+    #[wasm_bindgen(constructor)]
+    pub fn new(simulation: &SimulationHandler) -> Scheduler {
+        Self {
+            logic: simulation.shared_logic(),
+            clock: Rc::new(RefCell::new(FrameClock::default())),
+            #[cfg(target_arch = "wasm32")]
+            attachment: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Run the ticks due at `now_ms` (e.g. `performance.now()`) and return how many ran;
+    /// for callers driving the loop from their own timer
+    #[wasm_bindgen]
+    pub fn tick_at(&mut self, now_ms: f64) -> u32 {
+        run_frame(&mut self.logic.borrow_mut(), &mut self.clock.borrow_mut(), now_ms)
+    }
+
+    /// Step the simulation on every animation frame until `detach`
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn attach_to_raf(&mut self) -> Result<(), SimError> {
+        self.detach();
+        let window = browser_window()?;
+        let (logic, clock, attachment) = (self.logic.clone(), self.clock.clone(), self.attachment.clone());
+        let next_window = window.clone();
+        let callback = Closure::<dyn FnMut(f64)>::new(move |now_ms: f64| {
+            run_frame(&mut logic.borrow_mut(), &mut clock.borrow_mut(), now_ms);
+            // Queue the next frame unless the loop was detached meanwhile
+            if let Some(Attachment::AnimationFrame { handle, callback }) = attachment.borrow_mut().as_mut() {
+                if let Ok(next) = next_window.request_animation_frame(callback.as_ref().unchecked_ref()) {
+                    *handle = next;
+                }
+            }
+        });
+        let handle = window
+            .request_animation_frame(callback.as_ref().unchecked_ref())
+            .map_err(browser_error)?;
+        *self.attachment.borrow_mut() = Some(Attachment::AnimationFrame { handle, callback });
+        Ok(())
+    }
+
+    /// Step the simulation from a `setInterval` timer firing every `interval_ms`,
+    /// which keeps running in background tabs where animation frames stop
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn attach_to_interval(&mut self, interval_ms: i32) -> Result<(), SimError> {
+        self.detach();
+        let window = browser_window()?;
+        let performance = window
+            .performance()
+            .ok_or_else(|| SimError::Browser("performance is unavailable".to_string()))?;
+        let (logic, clock) = (self.logic.clone(), self.clock.clone());
+        let callback = Closure::<dyn FnMut()>::new(move || {
+            run_frame(&mut logic.borrow_mut(), &mut clock.borrow_mut(), performance.now());
+        });
+        let handle = window
+            .set_interval_with_callback_and_timeout_and_arguments_0(
+                callback.as_ref().unchecked_ref(),
+                interval_ms.max(1),
+            )
+            .map_err(browser_error)?;
+        *self.attachment.borrow_mut() = Some(Attachment::Interval { handle, _callback: callback });
+        Ok(())
+    }
+
+    /// Stop the timer; the simulation keeps its state and can be reattached
+    #[wasm_bindgen]
+    pub fn detach(&mut self) {
+        #[cfg(target_arch = "wasm32")]
+        if let Some(attachment) = self.attachment.borrow_mut().take() {
+            if let Some(window) = web_sys::window() {
+                match attachment {
+                    Attachment::AnimationFrame { handle, .. } => {
+                        let _ = window.cancel_animation_frame(handle);
+                    }
+                    Attachment::Interval { handle, .. } => window.clear_interval_with_handle(handle),
+                }
+            }
+        }
+        self.clock.borrow_mut().reset();
+    }
+
+    #[wasm_bindgen]
+    pub fn is_attached(&self) -> bool {
+        #[cfg(target_arch = "wasm32")]
+        return self.attachment.borrow().is_some();
+        #[cfg(not(target_arch = "wasm32"))]
+        false
+    }
+}
+
+impl Drop for Scheduler {
+    /// The running callback keeps the loop alive on its own, so stop it with the scheduler
+    fn drop(&mut self) {
+        self.detach();
+    }
+}
+
+/// Run the ticks due by `now_ms`. A paused simulation runs none and banks no
+/// time, so resuming doesn't burst through the ticks it missed
+fn run_frame(logic: &mut SimulationLogic, clock: &mut FrameClock, now_ms: f64) -> u32 {
+    if !logic.running() {
+        clock.reset();
+        return 0;
+    }
+    let due = clock.advance(now_ms, logic.tick_rate());
+    for _ in 0..due {
+        logic.update();
+    }
+    due
+}
+
+#[cfg(target_arch = "wasm32")]
+fn browser_window() -> Result<web_sys::Window, SimError> {
+    web_sys::window().ok_or_else(|| SimError::Browser("no window".to_string()))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn browser_error(err: JsValue) -> SimError {
+    SimError::Browser(format!("{:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_at_the_tick_rate_while_running() {
+        let mut simulation = SimulationHandler::init(4, 20).unwrap();
+        let mut scheduler = Scheduler::new(&simulation);
+
+        assert_eq!(scheduler.tick_at(0.0), 0);
+        assert_eq!(scheduler.tick_at(500.0), 0);  // Paused
+        simulation.start();
+        assert_eq!(scheduler.tick_at(1000.0), 0);  // Resuming starts the clock afresh
+        assert_eq!(scheduler.tick_at(1120.0), 2);
+        assert_eq!(simulation.get_tick(), 2);
+
+        scheduler.detach();
+        assert!(!scheduler.is_attached());
+        assert_eq!(scheduler.tick_at(5000.0), 0);
+        assert_eq!(scheduler.tick_at(5050.0), 1);
+        assert_eq!(simulation.get_tick(), 3);
+    }
+}
@@ -1,402 +1,4569 @@
-use serde_wasm_bindgen;
+#[cfg(test)]
+use std::cell::Ref;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
+use crate::data::BehaviorTree;
+#[cfg(target_arch = "wasm32")]
+use crate::data::ScriptedBrain;
+use crate::error::SimError;
+#[cfg(target_arch = "wasm32")]
+use crate::logger::{log, LogLevel};
+#[cfg(target_arch = "wasm32")]
+use crate::logic::EventCallback;
 use crate::logic::SimulationLogic;
+use crate::protocol::{decode_commands, encode_commands, Command};
+use crate::types::{
+    decode_snapshot_v1, BehaviorProfile, CalendarConfig, ConquestConfig, DisasterKind, EventConfig, FairnessConfig,
+    HeatmapKind, LeaderboardKey, MapDefinition, NeutralConfig, PlayerCommand, ResizePolicy, ResourceConfig,
+    ResourceClock, SyncBundle, TerrainConfig, TimeMode, UnrestConfig, WinCondition, WorldConfig,
+};
 
 #[wasm_bindgen]
 pub struct SimulationHandler {
-    logic: SimulationLogic,
+    logic: Rc<RefCell<SimulationLogic>>,  // Shared with any Scheduler driving this simulation
 }
 
 #[wasm_bindgen]
 impl SimulationHandler {
     #[wasm_bindgen(constructor)]
-    pub fn new(entity_count: usize) -> Self {
-        Self {
-            logic: SimulationLogic::new(entity_count),
-        }
+    pub fn new(entity_count: usize) -> Result<SimulationHandler, SimError> {
+        Ok(Self {
+            logic: Rc::new(RefCell::new(SimulationLogic::new(SimError::check_entity_count(entity_count)?))),
+        })
     }
 
     #[wasm_bindgen]
-    pub fn init(entity_count: usize, tick_rate: u32) -> Self {
-        let mut handler = Self::new(entity_count);
-        handler.logic.set_tick_rate(tick_rate);
-        handler
+    pub fn init(entity_count: usize, tick_rate: u32) -> Result<SimulationHandler, SimError> {
+        let mut handler = Self::new(entity_count)?;
+        handler.set_tick_rate(tick_rate)?;
+        Ok(handler)
     }
 
     #[wasm_bindgen]
-    pub fn init_with_grid(entity_count: usize, tick_rate: u32, grid_size: usize) -> Self {
-        let mut handler = Self::new(entity_count);
-        handler.logic.set_tick_rate(tick_rate);
-        handler.logic.set_grid_size(grid_size);
-        handler
+    pub fn init_with_grid(entity_count: usize, tick_rate: u32, grid_size: usize) -> Result<SimulationHandler, SimError> {
+        let mut handler = Self::new(entity_count)?;
+        handler.set_tick_rate(tick_rate)?;
+        handler.set_grid_size(grid_size)?;
+        Ok(handler)
+    }
+
+    /// Create a simulation of teams with `team_sizes` entities each; teammates
+    /// ally from the start and the run ends once a single team is left
+    #[wasm_bindgen]
+    pub fn init_with_teams(team_sizes: &[usize], tick_rate: u32) -> Result<SimulationHandler, SimError> {
+        SimError::check_entity_count(team_sizes.iter().sum())?;
+        let mut handler = Self {
+            logic: Rc::new(RefCell::new(SimulationLogic::new(0))),
+        };
+        handler.set_tick_rate(tick_rate)?;
+        handler.logic.borrow_mut().set_teams(team_sizes);
+        Ok(handler)
+    }
+
+    /// Entities per team, in id order; empty for a free-for-all
+    #[wasm_bindgen]
+    pub fn get_team_sizes(&self) -> Vec<usize> {
+        self.logic.borrow().team_sizes().to_vec()
+    }
+
+    /// Create a simulation at an arbitrary world scale
+    #[wasm_bindgen]
+    pub fn init_with_config(
+        entity_count: usize,
+        tick_rate: u32,
+        config: WorldConfig,
+    ) -> Result<SimulationHandler, SimError> {
+        SimError::check_entity_count(entity_count)?;
+        SimError::check_grid_size(config.grid_size)?;
+        let mut handler = Self {
+            logic: Rc::new(RefCell::new(SimulationLogic::with_world(entity_count, config))),
+        };
+        handler.set_tick_rate(tick_rate)?;
+        Ok(handler)
     }
 
     #[wasm_bindgen]
     pub fn start(&mut self) {
-        self.logic.start();
+        self.logic.borrow_mut().start();
     }
 
     #[wasm_bindgen]
     pub fn pause(&mut self) {
-        self.logic.pause();
+        self.logic.borrow_mut().pause();
     }
 
     #[wasm_bindgen]
     pub fn resume(&mut self) {
-        self.logic.resume();
+        self.logic.borrow_mut().resume();
     }
 
     #[wasm_bindgen]
     pub fn reset(&mut self) {
-        self.logic.reset();
+        self.logic.borrow_mut().reset();
+    }
+
+    /// Start over without rebuilding everything. With `keep_map` the terrain and
+    /// resources stay and the entities go back to how this run started them, a loaded
+    /// map's starts included, at tick 0. Without it the entities and tick carry on while
+    /// terrain and resources are generated afresh from their configs, replacing a
+    /// loaded map's.
+    #[wasm_bindgen]
+    pub fn soft_reset(&mut self, keep_map: bool) {
+        self.logic.borrow_mut().soft_reset(keep_map);
     }
 
     #[wasm_bindgen]
     pub fn step(&mut self) {
-        self.logic.step();
+        self.logic.borrow_mut().step();
+    }
+
+    /// Catch up after the tab was throttled: step up to `max_ticks` times, but stop
+    /// once `budget_ms` of `performance.now()` time is spent so the main thread
+    /// stays responsive. At least one tick runs; returns how many did.
+    #[wasm_bindgen]
+    pub fn step_many(&mut self, max_ticks: u32, budget_ms: f64) -> u32 {
+        self.logic.borrow_mut().step_many(max_ticks, budget_ms)
     }
 
     #[wasm_bindgen]
     pub fn update(&mut self) {
-        self.logic.update();
+        self.logic.borrow_mut().update();
     }
 
     #[wasm_bindgen]
     pub fn get_tick(&self) -> u64 {
-        self.logic.tick()
+        self.logic.borrow().tick()
     }
 
     #[wasm_bindgen]
     pub fn is_running(&self) -> bool {
-        self.logic.running()
+        self.logic.borrow().running()
     }
 
     #[wasm_bindgen]
     pub fn get_entity_count(&self) -> usize {
-        self.logic.entity_count()
+        self.logic.borrow().entity_count()
     }
 
     #[wasm_bindgen]
     pub fn get_tick_rate(&self) -> u32 {
-        self.logic.tick_rate()
+        self.logic.borrow().tick_rate()
+    }
+
+    #[wasm_bindgen]
+    pub fn set_tick_rate(&mut self, tick_rate: u32) -> Result<(), SimError> {
+        self.logic.borrow_mut().set_tick_rate(SimError::check_tick_rate(tick_rate)?);
+        Ok(())
     }
 
+    /// Advance the resource clock by exactly `ms_per_tick` each step instead of
+    /// wall-clock time, so runs are reproducible regardless of machine speed.
+    /// Returns `false` and keeps the current clock for a non-positive step.
     #[wasm_bindgen]
-    pub fn set_tick_rate(&mut self, tick_rate: u32) {
-        self.logic.set_tick_rate(tick_rate);
+    pub fn set_fixed_timestep(&mut self, ms_per_tick: f64) -> bool {
+        match TimeMode::fixed(ms_per_tick) {
+            Some(mode) => {
+                self.logic.borrow_mut().set_time_mode(mode);
+                true
+            }
+            None => false,
+        }
     }
 
+    /// Go back to wall-clock resource accumulation
     #[wasm_bindgen]
-    pub fn set_entity_count(&mut self, entity_count: usize) {
-        self.logic.set_entity_count(entity_count);
+    pub fn use_real_time(&mut self) {
+        self.logic.borrow_mut().set_time_mode(TimeMode::RealTime);
     }
 
+    /// Accumulate resources on simulated time, `1 / tick_rate` seconds per tick, so
+    /// the economy depends only on how many ticks ran, not on pauses or machine
+    /// speed; or go back to wall-clock time
     #[wasm_bindgen]
-    pub fn get_grid_size(&self) -> usize {
-        self.logic.grid_size()
+    pub fn set_resource_clock(&mut self, clock: ResourceClock) {
+        self.logic.borrow_mut().set_time_mode(clock.into());
+    }
+
+    #[wasm_bindgen]
+    pub fn is_fixed_timestep(&self) -> bool {
+        matches!(self.logic.borrow().time_mode(), TimeMode::Fixed { .. })
+    }
+
+    #[wasm_bindgen]
+    pub fn get_time_scale(&self) -> f32 {
+        self.logic.borrow().time_scale()
+    }
+
+    /// Speed up (2.0, 4.0) or slow down (0.5) resource generation without
+    /// touching the tick rate. Negative or non-finite values are ignored.
+    #[wasm_bindgen]
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.logic.borrow_mut().set_time_scale(time_scale);
     }
 
     #[wasm_bindgen]
-    pub fn set_grid_size(&mut self, grid_size: usize) {
-        self.logic.set_grid_size(grid_size);
+    pub fn set_entity_count(&mut self, entity_count: usize) -> Result<(), SimError> {
+        self.logic.borrow_mut().set_entity_count(SimError::check_entity_count(entity_count)?);
+        Ok(())
     }
 
+    /// Every field of one AI, including its targets, yields, allies and armies,
+    /// or `null` for an unknown id
     #[wasm_bindgen]
-    pub fn get_snapshot(&mut self) -> JsValue {
-        match self.logic.request_snapshot() {
-            Some(snapshot) => serde_wasm_bindgen::to_value(&snapshot).unwrap_or(JsValue::NULL),
-            None => JsValue::NULL,
+    pub fn get_entity(&self, entity_id: u32) -> Result<JsValue, SimError> {
+        match self.logic.borrow().entity_details(entity_id) {
+            Some(details) => to_js(&details),
+            None => Ok(JsValue::NULL),
         }
     }
 
-    #[cfg(target_arch = "wasm32")]
+    /// Military strength, money, territory and state of one AI over its recent
+    /// ticks, oldest first, or `null` for an unknown id. Recording starts with the
+    /// first request for an entity.
     #[wasm_bindgen]
-    pub fn get_flat_snapshot(&mut self) -> js_sys::Float32Array {
-        match self.logic.request_flat_snapshot() {
-            Some(slice) => js_sys::Float32Array::from(slice),
-            None => js_sys::Float32Array::new_with_length(0),
+    pub fn get_entity_history(&mut self, entity_id: u32) -> Result<JsValue, SimError> {
+        match self.logic.borrow_mut().entity_history(entity_id) {
+            Some(history) => to_js(&history),
+            None => Ok(JsValue::NULL),
         }
     }
 
+    /// Per-tick `{ tick, alive, territory, mean_military_strength,
+    /// max_military_strength, territory_gini }` for recent ticks, oldest first,
+    /// where `territory` holds each entity's spaces by id
     #[wasm_bindgen]
-    pub fn get_last_tick_duration(&self) -> f64 {
-        self.logic.last_tick_duration()
+    pub fn get_stats_history(&self) -> Result<JsValue, SimError> {
+        to_js(self.logic.borrow().stats_history())
     }
 
+    /// `{ entity_id, points }` with one `{ tick, territory }` point per sample over
+    /// the whole run, oldest first, or `null` for an entity never sampled. Samples
+    /// start every 10 ticks and thin out to every other one as the run grows long.
     #[wasm_bindgen]
-    pub fn get_last_snapshot_duration(&self) -> f64 {
-        self.logic.last_snapshot_duration()
+    pub fn get_territory_timeline(&self, entity_id: u32) -> Result<JsValue, SimError> {
+        match self.logic.borrow().territory_timeline(entity_id) {
+            Some(timeline) => to_js(&timeline),
+            None => Ok(JsValue::NULL),
+        }
     }
 
+    /// Territory timelines of every entity, by id, as `get_territory_timeline` gives them
     #[wasm_bindgen]
-    pub fn destroy(&mut self) {
-        self.logic.destroy();
+    pub fn get_all_timelines(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().all_timelines())
     }
 
+    /// Rank the leaderboard by `"territory"` (the default), `"money"` or
+    /// `"military_strength"`
     #[wasm_bindgen]
-    pub fn is_complete(&self) -> bool {
-        self.logic.is_complete()
+    pub fn set_leaderboard_key(&mut self, key: &str) -> Result<(), SimError> {
+        let key: LeaderboardKey = key.parse()?;
+        self.logic.borrow_mut().set_leaderboard_key(key);
+        Ok(())
     }
 
+    /// Top `n` alive entities as `[{ id, territory, money, military_strength }]`,
+    /// best first, kept sorted by the simulation so JS doesn't sort the snapshot
     #[wasm_bindgen]
-    pub fn count_alive(&self) -> usize {
-        self.logic.count_alive()
+    pub fn get_leaderboard(&self, n: usize) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().leaderboard(n))
     }
-}
 
-#[cfg(test)]
-impl SimulationHandler {
-    pub fn logic(&self) -> &SimulationLogic {
-        &self.logic
+    /// Add an AI on the grid space under `(x, y)` mid-simulation, taking that
+    /// space from its owner. Returns the new entity's id.
+    #[wasm_bindgen]
+    pub fn spawn_entity(&mut self, x: f32, y: f32, strength: f32) -> Result<u32, SimError> {
+        Ok(self.logic.borrow_mut().spawn_entity(x, y, strength)?)
     }
 
-    pub fn logic_mut(&mut self) -> &mut SimulationLogic {
-        &mut self.logic
+    /// Remove an AI mid-simulation; its land becomes unowned and its armies disband.
+    /// The id stays taken by a dead entity.
+    #[wasm_bindgen]
+    pub fn remove_entity(&mut self, entity_id: u32) -> Result<(), SimError> {
+        Ok(self.logic.borrow_mut().remove_entity(entity_id)?)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Fold AI `b` into AI `a`: `a` takes over its land, armies, money and military
+    /// strength, and `b` dies. Land cut off from `a`'s capital withers over time.
+    #[wasm_bindgen]
+    pub fn merge_entities(&mut self, a: u32, b: u32) -> Result<(), SimError> {
+        Ok(self.logic.borrow_mut().merge_entities(a, b)?)
+    }
 
-    #[test]
-    fn creates_simulation_with_entities() {
-        let handler = SimulationHandler::new(10);
-        assert_eq!(handler.get_entity_count(), 10);
-        assert_eq!(handler.get_tick(), 0);
-        assert!(!handler.is_running());
+    /// Break part of an AI's empire away as a new AI with the same profile: `fraction`
+    /// (between 0 and 1) of its money, military strength and land, the land being the
+    /// region around its space farthest from the capital. Returns the new entity's id.
+    #[wasm_bindgen]
+    pub fn split_entity(&mut self, entity_id: u32, fraction: f32) -> Result<u32, SimError> {
+        Ok(self.logic.borrow_mut().split_entity(entity_id, fraction)?)
     }
 
-    #[test]
-    fn steps_and_updates_tick() {
-        let mut handler = SimulationHandler::new(5);
-        handler.step();
-        assert_eq!(handler.get_tick(), 1);
+    /// Call `callback(event)` with `{ kind: "Death", tick, entity_id }` whenever an
+    /// AI dies, including through `remove_entity`; `null` stops the calls.
+    /// The callback runs inside the simulation and must not call back into it.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn set_on_entity_death(&mut self, callback: Option<js_sys::Function>) {
+        self.logic.borrow_mut().set_on_entity_death(callback.map(js_event_callback));
     }
 
-    #[test]
-    fn toggles_running_state() {
-        let mut handler = SimulationHandler::new(3);
-        handler.start();
-        assert!(handler.is_running());
-        handler.pause();
-        assert!(!handler.is_running());
-        handler.resume();
-        assert!(handler.is_running());
+    /// Call `callback(event)` with `{ kind: "Conquest", tick, grid_index, attacker_id,
+    /// previous_owner }` whenever a grid space changes hands; `null` stops the calls.
+    /// The callback runs inside the simulation and must not call back into it.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn set_on_conquest(&mut self, callback: Option<js_sys::Function>) {
+        self.logic.borrow_mut().set_on_conquest(callback.map(js_event_callback));
     }
 
-    #[test]
-    fn reset_rebuilds_entities() {
-        let mut handler = SimulationHandler::new(4);
-        handler.step();
-        handler.reset();
-        assert_eq!(handler.get_tick(), 0);
-        assert_eq!(handler.get_entity_count(), 4);
-        assert!(!handler.is_running());
+    /// Register `callback(view, tick)` as a brain and return its id for
+    /// `set_brain`. It gets each assigned entity's view of itself, e.g.
+    /// `{ id, money, nearby_attackers, rival, suggestion: { kind: "Expand" }, ... }`,
+    /// and returns an action such as `{ kind: "Attack", target_id: 3, buy_military: 5 }`
+    /// (kinds: Hold, Expand, Attack, Defend, Fortify, Support). Returning
+    /// `undefined` or throwing leaves that decision to the built-in greedy brain.
+    /// The callback runs inside the simulation and must not call back into it.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn register_brain(&mut self, callback: js_sys::Function) -> u32 {
+        self.logic.borrow_mut().register_brain(Box::new(js_brain(callback)))
     }
 
-    #[test]
-    fn counts_alive_entities() {
-        let handler = SimulationHandler::new(5);
-        assert_eq!(handler.count_alive(), 5);
+    /// Let a registered brain decide for an entity; brain 0 is the built-in greedy one
+    #[wasm_bindgen]
+    pub fn set_brain(&mut self, entity_id: u32, brain_id: u32) -> Result<(), SimError> {
+        Ok(self.logic.borrow_mut().set_brain(entity_id, brain_id)?)
     }
 
-    #[test]
-    fn detects_completion_when_one_alive() {
-        use crate::types::AiState;
-        
-        let mut handler = SimulationHandler::new(3);
-        
-        // Initially not complete
-        assert!(!handler.is_complete());
-        assert_eq!(handler.count_alive(), 3);
-        
-        // Kill two entities manually for testing by removing their territory
-        if let Some(entity) = handler.logic_mut().data_mut().entity_mut(0) {
-            entity.state = AiState::Dead;
-            entity.territory = 0;
-        }
-        if let Some(entity) = handler.logic_mut().data_mut().entity_mut(1) {
-            entity.state = AiState::Dead;
-            entity.territory = 0;
-        }
-        
-        // Should be complete with only one alive
-        assert!(handler.is_complete());
-        assert_eq!(handler.count_alive(), 1);
+    /// Let a behavior tree decide for an entity, replacing any tree loaded for
+    /// it before. Trees nest `sequence` and `selector` nodes over `condition`
+    /// and `action` leaves, e.g.
+    /// `{"type":"selector","children":[{"type":"sequence","children":[
+    /// {"type":"condition","check":"attackers_at_least","value":1},{"type":"action","do":"defend"}]},
+    /// {"type":"action","do":"expand"}]}`
+    #[wasm_bindgen]
+    pub fn load_behavior_tree(&mut self, entity_id: u32, json: &str) -> Result<(), SimError> {
+        let tree = BehaviorTree::from_json(json)?;
+        Ok(self.logic.borrow_mut().load_behavior_tree(entity_id, tree)?)
     }
 
-    #[test]
-    fn simulation_stops_when_complete() {
-        use crate::types::AiState;
-        
-        let mut handler = SimulationHandler::new(2);
-        handler.start();
-        assert!(handler.is_running());
-        
-        // Kill one entity by removing its territory
-        if let Some(entity) = handler.logic_mut().data_mut().entity_mut(0) {
-            entity.state = AiState::Dead;
-            entity.territory = 0;
-        }
-        
-        // Step should detect completion and stop
-        handler.step();
-        assert!(!handler.is_running(), "Simulation should stop when complete");
-        assert!(handler.is_complete());
+    /// How often each node of an entity's behavior tree ran, in pre-order, as
+    /// `[{ node: "condition attackers_at_least", ticks, successes, failures }, ...]`
+    #[wasm_bindgen]
+    pub fn get_behavior_tree_stats(&self, entity_id: u32) -> Result<JsValue, SimError> {
+        to_js(self.logic.borrow().behavior_tree_stats(entity_id)?)
     }
 
-    #[test]
-    fn grid_size_configuration() {
-        let handler = SimulationHandler::new(10);
-        assert_eq!(handler.get_grid_size(), 50); // Default grid size
-        
-        let mut handler = SimulationHandler::init_with_grid(5, 60, 20);
-        assert_eq!(handler.get_grid_size(), 20);
-        assert_eq!(handler.get_entity_count(), 5);
-        
-        handler.set_grid_size(30);
-        assert_eq!(handler.get_grid_size(), 30);
+    #[wasm_bindgen]
+    pub fn get_grid_size(&self) -> usize {
+        self.logic.borrow().grid_size()
     }
 
-    #[test]
-    fn entities_start_with_correct_values() {
-        let mut handler = SimulationHandler::new(5);
-        
-        // Access entities through the logic
-        for i in 0..5 {
-            let entity = handler.logic_mut().data_mut().entity(i).expect("Entity should exist");
-            assert_eq!(entity.territory, 1, "Entity {} should start with 1 territory", i);
-            assert_eq!(entity.money, 0.0, "Entity {} should start with 0 money", i);
-            assert_eq!(entity.military_strength, 10.0, "Entity {} should start with 10 military strength", i);
-        }
+    #[wasm_bindgen]
+    pub fn set_grid_size(&mut self, grid_size: usize) -> Result<(), SimError> {
+        self.logic.borrow_mut().set_grid_size(SimError::check_grid_size(grid_size)?);
+        Ok(())
     }
 
-    #[test]
-    fn time_based_resource_accumulation() {
-        let mut handler = SimulationHandler::new(2);
-        
-        // Get initial values
-        let initial_money = handler.logic_mut().data_mut().entity(0).unwrap().money;
-        let initial_military = handler.logic_mut().data_mut().entity(0).unwrap().military_strength;
-        
-        // Run several steps
-        for _ in 0..10 {
-            handler.step();
-        }
-        
-        // Money should always increase (never spent in current implementation)
-        let final_money = handler.logic_mut().data_mut().entity(0).unwrap().money;
-        assert!(final_money > initial_money, "Money should increase over time");
-        
-        // Military strength accumulates but may be spent on attacks
-        // So we verify the total resources generated + spent is reasonable
-        let final_military = handler.logic_mut().data_mut().entity(0).unwrap().military_strength;
-        
-        // With aggressive AI, military might be consumed by attacks
-        // But the accumulation mechanism should still work - verify the AI can gain resources
-        // If it's not higher, it means resources were spent on expansion (which is good!)
-        // Just verify it didn't go negative or unreasonably low
-        assert!(
-            final_military >= 0.0,
-            "Military strength should not be negative, got: {}",
-            final_military
-        );
+    /// Change the grid size without restarting: each new space takes its owner from the
+    /// old space under its center (`"nearest"`) or from whoever held most of the old spaces
+    /// it covers (`"majority"`). Terrain comes from the old space under each center;
+    /// capitals, resource nodes, orders and armies follow their spaces.
+    #[wasm_bindgen]
+    pub fn resize_grid(&mut self, grid_size: usize, policy: &str) -> Result<(), SimError> {
+        let grid_size = SimError::check_grid_size(grid_size)?;
+        let policy: ResizePolicy = policy.parse()?;
+        self.logic.borrow_mut().resize_grid(grid_size, policy);
+        Ok(())
     }
 
-    #[test]
-    fn entity_dies_when_territory_zero() {
-        use crate::types::AiState;
-        
-        let mut handler = SimulationHandler::new(3);
-        
-        // Manually set territory to 0 and clear grid space ownership
-        let entity_id = {
-            let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
-            entity.territory = 0;
-            entity.id
-        };
-        
-        // Also need to clear grid space ownership for this entity
-        let grid_size = handler.logic_mut().data_mut().grid_size();
-        for i in 0..(grid_size * grid_size) {
-            if let Some(space) = handler.logic_mut().data_mut().grid_space_mut(i) {
-                if space.owner_id == Some(entity_id) {
-                    space.owner_id = None;
-                }
-            }
+    /// Ally two entities; returns `false` if either is dead/unknown or they're already allied
+    #[wasm_bindgen]
+    pub fn propose_alliance(&mut self, a: u32, b: u32) -> bool {
+        self.logic.borrow_mut().propose_alliance(a, b)
+    }
+
+    #[wasm_bindgen]
+    pub fn are_allies(&self, a: u32, b: u32) -> bool {
+        self.logic.borrow().are_allies(a, b)
+    }
+
+    /// Entity `id` pays tribute to as its vassal, or `undefined`. An AI holding under
+    /// 2% of the grid submits to a bordering neighbor with four times its territory,
+    /// which never attacks it while collecting 0.5 money a second; the bond lasts
+    /// until either of them dies.
+    #[wasm_bindgen]
+    pub fn get_overlord(&self, id: u32) -> Option<u32> {
+        self.logic.borrow().overlord(id)
+    }
+
+    /// Vassals paying tribute to entity `id`, as a `Uint32Array` in id order
+    #[wasm_bindgen]
+    pub fn get_vassals(&self, id: u32) -> Vec<u32> {
+        self.logic.borrow().vassals(id)
+    }
+
+    /// Relations between every pair of AIs as a row-major `entity_count * entity_count`
+    /// `Float32Array`: -100 for sworn rivals up to 100 for firm friends, 0 neutral.
+    /// Assaults sour a pair; neighbors that leave each other alone slowly warm.
+    #[wasm_bindgen]
+    pub fn get_relations_matrix(&self) -> Vec<f32> {
+        self.logic.borrow().relations_matrix()
+    }
+
+    /// Highlights of the current run, oldest first: `{ kind: "FirstBlood", tick, grid_index,
+    /// attacker_id, defender_id }`, `{ kind: "Comeback", tick, entity_id, low_tick, low_share }`
+    /// for an AI that fell below 5% of the held territory and climbed back to 15%, and
+    /// `{ kind: "FastestExpansion", tick, entity_id, ticks }` whenever an AI takes 10 spaces
+    /// quicker than anyone before it
+    #[wasm_bindgen]
+    pub fn get_notable_events(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().notable_events())
+    }
+
+    /// Overlay values as a row-major `grid_size * grid_size` `Float32Array`. `kind` is
+    /// `"defense_strength"`, `"contested_frequency"` (battles fought over each space),
+    /// `"recent_combat"` (battles, fading by a tenth every tick), `"ownership_changes"`
+    /// (ticks in which each space changed hands) or `"unrest"` (from 0 to 1 on owned
+    /// spaces). Tallies start over with the run.
+    #[wasm_bindgen]
+    pub fn get_heatmap(&self, kind: &str) -> Result<Vec<f32>, SimError> {
+        let kind: HeatmapKind = kind.parse()?;
+        Ok(self.logic.borrow().heatmap(kind))
+    }
+
+    /// Hand an entity over to the player and queue an order for it, applied on the next step.
+    /// `command_json` is one of `{"kind":"MoveTo","grid_index":n}`,
+    /// `{"kind":"Attack","grid_index":n}`, `{"kind":"Defend"}` or `{"kind":"Hold"}`.
+    #[wasm_bindgen]
+    pub fn queue_command(&mut self, entity_id: u32, command_json: &str) -> Result<(), SimError> {
+        let command: PlayerCommand = serde_json::from_str(command_json)?;
+        Ok(self.logic.borrow_mut().queue_command(entity_id, command)?)
+    }
+
+    /// Queue an order, in the same JSON as `queue_command`, for the step that reaches `tick`.
+    /// Lockstep peers that schedule the same commands apply them in the same order however
+    /// they arrived; `tick` must still be ahead of the simulation.
+    #[wasm_bindgen]
+    pub fn schedule_command(&mut self, tick: u64, entity_id: u32, command_json: &str) -> Result<(), SimError> {
+        let command: PlayerCommand = serde_json::from_str(command_json)?;
+        Ok(self.logic.borrow_mut().schedule_command(tick, entity_id, command)?)
+    }
+
+    /// Hash of the commands scheduled for `tick`; peers compare it before simulating that tick
+    #[wasm_bindgen]
+    pub fn get_scheduled_input_hash(&self, tick: u64) -> u64 {
+        self.logic.borrow().scheduled_input_hash(tick)
+    }
+
+    /// Hash of every command applied so far; peers whose input hashes match but
+    /// whose `get_state_hash` values differ have desynced in the simulation itself
+    #[wasm_bindgen]
+    pub fn get_input_hash(&self) -> u64 {
+        self.logic.borrow().input_hash()
+    }
+
+    /// Remember the current state; returns an id for `rollback_to`. Cheap to call
+    /// every tick, since a checkpoint is only copied once the simulation changes.
+    #[wasm_bindgen]
+    pub fn create_checkpoint(&mut self) -> u32 {
+        self.logic.borrow_mut().create_checkpoint()
+    }
+
+    /// Return to a checkpoint, e.g. to schedule late remote commands at an earlier
+    /// tick and re-simulate. Checkpoints made after it are dropped.
+    #[wasm_bindgen]
+    pub fn rollback_to(&mut self, checkpoint_id: u32) -> Result<(), SimError> {
+        Ok(self.logic.borrow_mut().rollback_to(checkpoint_id)?)
+    }
+
+    /// Checkpoints held for `rollback_to`, which each share state until the simulation moves on
+    #[wasm_bindgen]
+    pub fn get_checkpoint_count(&self) -> usize {
+        self.logic.borrow().checkpoint_count()
+    }
+
+    /// Apply a message from `encode_command_json` (or a peer's `encode_commands`).
+    /// The whole message is decoded before anything is applied, so corrupt bytes
+    /// change nothing; commands then apply in order and stop at the first one
+    /// refused. Returns how many commands were applied.
+    #[wasm_bindgen]
+    pub fn apply_command_bytes(&mut self, bytes: &[u8]) -> Result<u32, SimError> {
+        let commands = decode_commands(bytes).map_err(SimError::MalformedInput)?;
+        let mut logic = self.logic.borrow_mut();
+        for (i, command) in commands.iter().enumerate() {
+            logic
+                .apply_command(*command)
+                .map_err(|e| SimError::InvalidInput(format!("Command {}: {}", i, e)))?;
         }
-        
-        // Step the simulation
-        handler.step();
-        
-        // Entity should be marked as dead
-        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
-        assert_eq!(entity.state, AiState::Dead);
-        assert_eq!(entity.territory, 0);
+        Ok(commands.len() as u32)
     }
 
-    #[test]
+    /// Encode a JSON array of commands, e.g.
+    /// `[{"kind":"SpawnEntity","x":0,"y":0,"strength":50},{"kind":"Pause"}]`,
+    /// into the compact binary form accepted by `apply_command_bytes`
+    #[wasm_bindgen]
+    pub fn encode_command_json(commands_json: &str) -> Result<Vec<u8>, SimError> {
+        let commands: Vec<Command> = serde_json::from_str(commands_json)?;
+        Ok(encode_commands(&commands))
+    }
+
+    /// Decode bytes from `encode_command_json` back into an array of commands
+    #[wasm_bindgen]
+    pub fn decode_command_bytes(bytes: &[u8]) -> Result<JsValue, SimError> {
+        let commands = decode_commands(bytes).map_err(SimError::MalformedInput)?;
+        to_js_with_bigints(&commands)
+    }
+
+    /// Send `strength` of an entity's military toward a grid space as a separate army.
+    /// The army assaults the space if it can afford to, then rejoins its owner.
+    /// Returns the new army's id.
+    #[wasm_bindgen]
+    pub fn raise_army(&mut self, entity_id: u32, strength: f32, grid_index: u32) -> Result<u32, SimError> {
+        Ok(self.logic.borrow_mut().raise_army(entity_id, strength, grid_index)?)
+    }
+
+    /// Spend an entity's money on `amount` military strength, 2 money a point.
+    /// Fails without spending anything if the entity can't pay.
+    #[wasm_bindgen]
+    pub fn buy_military(&mut self, entity_id: u32, amount: f32) -> Result<(), SimError> {
+        Ok(self.logic.borrow_mut().buy_military(entity_id, amount)?)
+    }
+
+    /// Switch an entity's AI to the `aggressive`, `turtle`, `economic` or
+    /// `opportunist` profile
+    #[wasm_bindgen]
+    pub fn set_behavior(&mut self, entity_id: u32, profile: &str) -> Result<(), SimError> {
+        let profile: BehaviorProfile = profile.parse()?;
+        Ok(self.logic.borrow_mut().set_behavior(entity_id, profile)?)
+    }
+
+    /// Every army currently marching, ordered by id
+    #[wasm_bindgen]
+    pub fn get_armies(&self) -> Result<JsValue, SimError> {
+        to_js(self.logic.borrow().armies())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_world_config(&self) -> WorldConfig {
+        self.logic.borrow().world_config()
+    }
+
+    #[wasm_bindgen]
+    pub fn set_world_config(&mut self, config: WorldConfig) -> Result<(), SimError> {
+        SimError::check_grid_size(config.grid_size)?;
+        self.logic.borrow_mut().set_world_config(config);
+        Ok(())
+    }
+
+    /// Stop the simulation and start it over on a hand-authored map, e.g.
+    /// `{"grid_size":8,"terrain":["Plains","Water",...],"resources":[{"grid_index":9,"node":"GoldMine"}],
+    /// "entities":[{"capital":0,"military_strength":40},{"capital":63}],"cells":[{"grid_index":1,"owner_id":0}]}`.
+    /// `terrain` lists every space row-major; left out, it and `resources`
+    /// are generated from the current configs. Entity ids follow the order
+    /// of `entities`, each owning its capital; `cells` hands out more land.
+    #[wasm_bindgen]
+    pub fn load_map(&mut self, map_json: &str) -> Result<(), SimError> {
+        let map: MapDefinition = serde_json::from_str(map_json)?;
+        SimError::check_grid_size(map.grid_size)?;
+        Ok(self.logic.borrow_mut().load_map(&map)?)
+    }
+
+    /// The current grid, land and alive entities as JSON for `load_map`
+    #[wasm_bindgen]
+    pub fn export_map(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().export_map()).map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// How evenly matched the entities' starts are: per entity `{ entity_id, grid_index,
+    /// reachable_area, resource_value, nearest_neighbor }`, plus `area_spread`,
+    /// `resource_spread` and `distance_spread` (gap between best and worst as a
+    /// fraction of the best), `fair` against the tolerance and the placement `attempts`
+    #[wasm_bindgen]
+    pub fn validate_start_positions(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().validate_start_positions())
+    }
+
+    /// Fair-start radius, tolerance and attempts as `FairnessConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_fairness_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().fairness_config())
+            .map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Respawn the entities on starts placed under JSON such as `{"tolerance":0.25}`;
+    /// omitted fields keep their defaults. The generator tries up to
+    /// `max_attempts` placements until every spread is within `tolerance`.
+    #[wasm_bindgen]
+    pub fn set_fairness_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: FairnessConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_fairness_config(config)?)
+    }
+
+    /// Terrain seed and per-type effects as `TerrainConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_terrain_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().terrain_config()).map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Regenerate the terrain layer from JSON such as `{"seed":7}`; omitted
+    /// fields keep their defaults. Territories and entities are left as they are.
+    #[wasm_bindgen]
+    pub fn set_terrain_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: TerrainConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_terrain_config(config)?)
+    }
+
+    /// Terrain of every grid space, row-major: 0 plains, 1 forest, 2 mountain, 3 water
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn get_grid_terrain(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(self.logic.borrow().grid_terrain().as_slice())
+    }
+
+    /// Resource node spawn chances and multipliers as `ResourceConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_resource_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().resource_config()).map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Respawn gold mines and farms from JSON such as `{"seed":3,"gold_mine_chance":0.05}`;
+    /// omitted fields keep their defaults. Territories and entities are left as they are.
+    #[wasm_bindgen]
+    pub fn set_resource_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: ResourceConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_resource_config(config)?)
+    }
+
+    /// Conquests per tick and the cost growth of each extra one as `ConquestConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_conquest_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().conquest_config()).map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Let attackers take several spaces a tick from JSON such as
+    /// `{"max_conquests_per_tick":3,"extra_conquest_cost_growth":2}`; omitted
+    /// fields keep their defaults
+    #[wasm_bindgen]
+    pub fn set_conquest_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: ConquestConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_conquest_config(config)?)
+    }
+
+    /// Neutral garrison spawn chance, starting defense and growth as `NeutralConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_neutral_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().neutral_config()).map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Re-garrison unowned land from JSON such as `{"seed":3,"garrison_chance":0.5}`;
+    /// omitted fields keep their defaults. Garrisoned spaces show up in grid
+    /// snapshots as owner `0xFFFFFFFE` with the garrison as their defense.
+    #[wasm_bindgen]
+    pub fn set_neutral_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: NeutralConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_neutral_config(config)?)
+    }
+
+    /// Unrest build-up and revolt rates as `UnrestConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_unrest_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().unrest_config()).map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Set how restless held land gets from JSON such as `{"revolt_chance":0.1}`;
+    /// omitted fields keep their defaults. Conquered spaces start restless and far-flung
+    /// ones grow more so; at the threshold they may defect back to the entity they were
+    /// taken from or fall to a rebel garrison. A `revolt_chance` of 0 turns revolts off.
+    #[wasm_bindgen]
+    pub fn set_unrest_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: UnrestConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_unrest_config(config)?)
+    }
+
+    /// Disaster chances and strengths as `EventConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_event_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().event_config()).map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Turn on seeded disasters from JSON such as `{"plague_chance":0.02,"earthquake_chance":0.01}`;
+    /// omitted fields keep their defaults, which strike nothing. Every 10 ticks each kind rolls its
+    /// chance: plagues cut an AI's military generation and famines drain its money for
+    /// `duration_ticks`, earthquakes wear down the defense around a grid space. Each one is logged
+    /// as a `Disaster` event.
+    #[wasm_bindgen]
+    pub fn set_event_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: EventConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_event_config(config)?)
+    }
+
+    /// Strike now with `"plague"` or `"famine"` on entity `target`, or `"earthquake"`
+    /// centered on grid space `target`
+    #[wasm_bindgen]
+    pub fn trigger_disaster(&mut self, kind: &str, target: u32) -> Result<(), SimError> {
+        let kind: DisasterKind = kind.parse()?;
+        Ok(self.logic.borrow_mut().trigger_disaster(kind, target)?)
+    }
+
+    /// Plagues and famines under way as `[{ kind, entity_id, ends_at }]`, `ends_at`
+    /// being the first tick the entity is free of it
+    #[wasm_bindgen]
+    pub fn get_afflictions(&self) -> Result<JsValue, SimError> {
+        to_js(self.logic.borrow().afflictions())
+    }
+
+    /// Season length and per-season effects as `CalendarConfig` JSON
+    #[wasm_bindgen]
+    pub fn get_calendar_config(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().calendar_config())
+            .map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Change the seasons from JSON such as `{"ticks_per_season":600,"winter":{"attack_factor":0.5}}`;
+    /// omitted fields keep their defaults. `attack_factor` scales the strength attacks and armies
+    /// strike with, `money_factor` the money owned land generates.
+    #[wasm_bindgen]
+    pub fn set_calendar_config(&mut self, config_json: &str) -> Result<(), SimError> {
+        let config: CalendarConfig = serde_json::from_str(config_json)?;
+        Ok(self.logic.borrow_mut().set_calendar_config(config)?)
+    }
+
+    /// Today in the world calendar as
+    /// `{ tick, year, season, day, ticks_per_season, effects: { attack_factor, money_factor } }`,
+    /// `season` being `"spring"`, `"summer"`, `"autumn"` or `"winter"`
+    #[wasm_bindgen]
+    pub fn get_calendar(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().calendar_date())
+    }
+
+    /// What it takes to win as JSON, `"Annihilation"` or
+    /// `{"VictoryPoints":{"needed":3,"hold_ticks":600}}`
+    #[wasm_bindgen]
+    pub fn get_win_condition(&self) -> Result<String, SimError> {
+        serde_json::to_string(&self.logic.borrow().win_condition())
+            .map_err(|err| SimError::Serialization(err.to_string()))
+    }
+
+    /// Change what it takes to win from JSON as `get_win_condition` gives it. Under
+    /// `VictoryPoints` the first entity to hold `needed` victory point cells for
+    /// `hold_ticks` ticks in a row wins; snapshots report each entity's
+    /// `victory_points` and `victory_hold` to show the race.
+    #[wasm_bindgen]
+    pub fn set_win_condition(&mut self, condition_json: &str) -> Result<(), SimError> {
+        let condition: WinCondition = serde_json::from_str(condition_json)?;
+        Ok(self.logic.borrow_mut().set_win_condition(condition)?)
+    }
+
+    /// Grid indices of the victory point cells, sorted
+    #[wasm_bindgen]
+    pub fn get_victory_cells(&self) -> Vec<u32> {
+        self.logic.borrow().victory_cells().to_vec()
+    }
+
+    /// Designate the victory point cells by grid index, replacing the current ones
+    #[wasm_bindgen]
+    pub fn set_victory_cells(&mut self, cells: &[u32]) -> Result<(), SimError> {
+        Ok(self.logic.borrow_mut().set_victory_cells(cells)?)
+    }
+
+    /// Entity that has won on victory points, if any
+    #[wasm_bindgen]
+    pub fn get_victor(&self) -> Option<u32> {
+        self.logic.borrow().victor()
+    }
+
+    /// Resource node of every grid space, row-major: 0 none, 1 gold mine, 2 farm
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn get_grid_resources(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(self.logic.borrow().grid_resources().as_slice())
+    }
+
+    /// Every entity's public state, or `null` if nothing changed since the last call
+    #[wasm_bindgen(unchecked_return_type = "PublicEntitySnapshot[] | null")]
+    pub fn get_snapshot(&mut self) -> Result<JsValue, SimError> {
+        match self.logic.borrow_mut().request_snapshot() {
+            Some(snapshot) => to_js(&snapshot),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn get_flat_snapshot(&mut self) -> js_sys::Float32Array {
+        match self.logic.borrow_mut().request_flat_snapshot() {
+            Some(slice) => js_sys::Float32Array::from(slice),
+            None => js_sys::Float32Array::new_with_length(0),
+        }
+    }
+
+    /// Copy the flat snapshot into a `Float32Array` the caller keeps between frames
+    /// rather than allocating one per call like `get_flat_snapshot`. Returns how many
+    /// values the snapshot holds; nothing is copied if `target` is shorter, so the
+    /// caller can grow it and call again.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn copy_flat_snapshot_into(&mut self, target: &js_sys::Float32Array) -> u32 {
+        let mut logic = self.logic.borrow_mut();
+        let Some(slice) = logic.request_flat_snapshot() else {
+            return 0;
+        };
+        let len = slice.len() as u32;
+        if target.length() >= len {
+            target.subarray(0, len).copy_from(slice);
+        }
+        len
+    }
+
+    /// Only include the fields whose bits are set in the flat and binary snapshots:
+    /// 1 id, 2 military_strength, 4 money, 8 territory, 16 state, 32 position_x,
+    /// 64 position_y. `get_snapshot_schema` describes the resulting layout.
+    #[wasm_bindgen]
+    pub fn set_snapshot_fields(&mut self, mask: u32) -> Result<(), SimError> {
+        Ok(self.logic.borrow_mut().set_snapshot_fields(mask)?)
+    }
+
+    /// `{ mask, stride, fields: [{ field, bit, offset }] }` for the flat snapshot,
+    /// where each entity takes `stride` floats
+    #[wasm_bindgen]
+    pub fn get_snapshot_schema(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().snapshot_schema())
+    }
+
+    /// Refresh the flat snapshot and return its address in wasm memory, so JS can
+    /// read it without a copy:
+    /// `new Float32Array(memory.buffer, sim.flat_snapshot_ptr(), sim.flat_snapshot_len())`.
+    /// The view is only valid until the next call into the simulation, which may
+    /// move the buffer or grow the memory.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn flat_snapshot_ptr(&mut self) -> *const f32 {
+        self.logic.borrow_mut()
+            .request_flat_snapshot()
+            .map_or(std::ptr::null(), |slice| slice.as_ptr())
+    }
+
+    /// Number of `f32` values in the flat snapshot, refreshing it first
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn flat_snapshot_len(&mut self) -> usize {
+        self.logic.borrow_mut().request_flat_snapshot().map_or(0, |slice| slice.len())
+    }
+
+    /// Every entity as a little-endian binary snapshot with a version byte,
+    /// field schema and CRC-32, for streaming or storage without JSON
+    #[wasm_bindgen]
+    pub fn get_binary_snapshot(&mut self) -> Vec<u8> {
+        self.logic.borrow_mut().request_binary_snapshot()
+    }
+
+    /// Decode bytes from `get_binary_snapshot` into `{ tick, fields, entities }`
+    #[wasm_bindgen]
+    pub fn decode_binary_snapshot(bytes: &[u8]) -> Result<JsValue, SimError> {
+        let snapshot = decode_snapshot_v1(bytes).map_err(SimError::MalformedInput)?;
+        to_js_with_bigints(&snapshot)
+    }
+
+    /// `{ entities, grid_cells }` inside the world rectangle between `(x0, y0)`
+    /// and `(x1, y1)`, so off-screen entities aren't serialized every frame.
+    /// Grid cells are `{ index, owner_id, defense_strength }`.
+    #[wasm_bindgen(unchecked_return_type = "ViewportSnapshot")]
+    pub fn get_snapshot_in_rect(&mut self, x0: f32, y0: f32, x1: f32, y1: f32) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow_mut().snapshot_in_rect(x0, y0, x1, y1))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_state_hash(&self) -> u64 {
+        self.logic.borrow().state_hash()
+    }
+
+    /// Everything a late-joining client needs to continue the current match
+    #[wasm_bindgen]
+    pub fn get_sync_bundle(&self) -> Result<JsValue, SimError> {
+        to_js_with_bigints(&self.logic.borrow().sync_bundle())
+    }
+
+    #[wasm_bindgen]
+    pub fn apply_sync_bundle(&mut self, bundle: JsValue) -> Result<(), SimError> {
+        let bundle: SyncBundle =
+            serde_wasm_bindgen::from_value(bundle).map_err(|err| SimError::MalformedInput(err.to_string()))?;
+        Ok(self.logic.borrow_mut().apply_sync_bundle(&bundle)?)
+    }
+
+    /// Returns `[owners: Uint32Array, defense: Float32Array]` covering every grid space.
+    /// Unowned spaces report `0xFFFFFFFF` as their owner.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn get_grid_snapshot(&mut self) -> js_sys::Array {
+        let mut logic = self.logic.borrow_mut();
+        let (owners, defense) = logic.request_grid_snapshot();
+        let pair = js_sys::Array::new_with_length(2);
+        pair.set(0, js_sys::Uint32Array::from(owners).into());
+        pair.set(1, js_sys::Float32Array::from(defense).into());
+        pair
+    }
+
+    /// Returns `[indices: Uint32Array, owners: Uint32Array, defense: Float32Array]`
+    /// for grid spaces changed since the previous call. The first call after
+    /// construction, reset or a grid resize reports every space.
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub fn get_grid_diff(&mut self) -> js_sys::Array {
+        let mut logic = self.logic.borrow_mut();
+        let diff = logic.request_grid_diff();
+        let triple = js_sys::Array::new_with_length(3);
+        triple.set(0, js_sys::Uint32Array::from(diff.indices.as_slice()).into());
+        triple.set(1, js_sys::Uint32Array::from(diff.owners.as_slice()).into());
+        triple.set(2, js_sys::Float32Array::from(diff.defense.as_slice()).into());
+        triple
+    }
+
+    /// Ids of alive entities within `radius` world units of `(x, y)`, nearest
+    /// first, for hover tooltips and selection boxes
+    #[wasm_bindgen]
+    pub fn query_entities_near(&mut self, x: f32, y: f32, radius: f32) -> Vec<u32> {
+        self.logic.borrow_mut().entities_near(x, y, radius)
+    }
+
+    /// Ids of the `k` alive entities closest to `(x, y)`, nearest first
+    #[wasm_bindgen]
+    pub fn query_nearest_entities(&mut self, x: f32, y: f32, k: usize) -> Vec<u32> {
+        self.logic.borrow_mut().nearest_entities(x, y, k)
+    }
+
+    /// Entities stored in spatial grid spill lists on the last tick; a high
+    /// count means the map is dense enough that neighbor queries slow down
+    #[wasm_bindgen]
+    pub fn get_overflow_count(&self) -> usize {
+        self.logic.borrow().spatial_overflow_count()
+    }
+
+    /// `{ tick, spatial_overflow, out_of_bounds, orphaned_cells, negative_resources }`:
+    /// the overflow count above plus the ids of alive entities outside the world,
+    /// grid spaces still owned by dead entities and entities with negative or
+    /// non-finite money or military. Anything but overflow means a bug.
+    #[wasm_bindgen]
+    pub fn get_diagnostics(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().diagnostics())
+    }
+
+    /// Run the `get_diagnostics` checks after every step and log a warning when
+    /// they find something. Only debug builds check; release builds ignore this.
+    #[wasm_bindgen]
+    pub fn set_step_validation(&mut self, enabled: bool) {
+        self.logic.borrow_mut().set_step_validation(enabled);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_last_tick_duration(&self) -> f64 {
+        self.logic.borrow().last_tick_duration()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_last_snapshot_duration(&self) -> f64 {
+        self.logic.borrow().last_snapshot_duration()
+    }
+
+    /// Min, max, mean and p95 of recent tick and snapshot durations, with ticks
+    /// broken down into `entity_update` and `conquest` time
+    #[wasm_bindgen]
+    pub fn get_metrics(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().metrics_report())
+    }
+
+    /// Milliseconds the last tick spent rebuilding snapshots and the spatial grid,
+    /// updating entities, resolving conquests, processing deaths and recounting territory
+    #[wasm_bindgen]
+    pub fn get_tick_profile(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().tick_profile())
+    }
+
+    /// `{ entity_count, entity_capacity, entity_bytes, grid_cells, grid_bytes,
+    /// snapshot_bytes, event_count, event_log_bytes, total_bytes }`, counting each
+    /// buffer's reserved capacity, for watching the wasm heap in long sessions
+    #[wasm_bindgen]
+    pub fn get_memory_stats(&self) -> Result<JsValue, SimError> {
+        to_js(&self.logic.borrow().memory_stats())
+    }
+
+    /// Release buffer capacity left over from larger worlds or entity counts.
+    /// The wasm heap itself never shrinks, but freed space is reused.
+    #[wasm_bindgen]
+    pub fn shrink_to_fit(&mut self) {
+        self.logic.borrow_mut().shrink_to_fit();
+    }
+
+    #[wasm_bindgen]
+    pub fn destroy(&mut self) {
+        self.logic.borrow_mut().destroy();
+    }
+
+    #[wasm_bindgen]
+    pub fn is_complete(&self) -> bool {
+        self.logic.borrow().is_complete()
+    }
+
+    #[wasm_bindgen]
+    pub fn count_alive(&self) -> usize {
+        self.logic.borrow().count_alive()
+    }
+}
+
+pub(super) fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, SimError> {
+    serde_wasm_bindgen::to_value(value).map_err(|err| SimError::Serialization(err.to_string()))
+}
+
+/// Like `to_js`, but 64-bit integers such as ticks and hashes become BigInts
+/// instead of losing precision
+fn to_js_with_bigints<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, SimError> {
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+    value
+        .serialize(&serializer)
+        .map_err(|err| SimError::Serialization(err.to_string()))
+}
+
+impl SimulationHandler {
+    /// The simulation state, for a `Scheduler` to step between frames
+    pub(crate) fn shared_logic(&self) -> Rc<RefCell<SimulationLogic>> {
+        Rc::clone(&self.logic)
+    }
+}
+
+/// Forward events to a JS function as plain objects, logging exceptions it throws
+#[cfg(target_arch = "wasm32")]
+fn js_event_callback(callback: js_sys::Function) -> EventCallback {
+    Box::new(move |event| {
+        let event = to_js(event).unwrap_or(JsValue::NULL);
+        if let Err(err) = callback.call1(&JsValue::NULL, &event) {
+            log!(LogLevel::Warn, "Event callback threw: {:?}", err);
+        }
+    })
+}
+
+/// Ask a JS function for each decision, leaving it to the greedy brain when
+/// the function returns nothing usable or throws
+#[cfg(target_arch = "wasm32")]
+fn js_brain(callback: js_sys::Function) -> ScriptedBrain {
+    ScriptedBrain::new(move |view, world| {
+        let view = to_js(view).ok()?;
+        match callback.call2(&JsValue::NULL, &view, &JsValue::from_f64(world.tick as f64)) {
+            Ok(action) if action.is_undefined() || action.is_null() => None,
+            Ok(action) => serde_wasm_bindgen::from_value(action)
+                .map_err(|err| {
+                    log!(LogLevel::Warn, "Brain callback returned an invalid action: {}", err);
+                })
+                .ok(),
+            Err(err) => {
+                log!(LogLevel::Warn, "Brain callback threw: {:?}", err);
+                None
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+impl SimulationHandler {
+    pub fn logic(&self) -> Ref<'_, SimulationLogic> {
+        self.logic.borrow()
+    }
+
+    pub fn logic_mut(&mut self) -> &mut SimulationLogic {
+        Rc::get_mut(&mut self.logic).expect("no scheduler shares the logic").get_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts allocations on each thread, so tests can check hot paths reuse their buffers.
+    /// Left out of `parallel` builds, where rayon's job queue allocates a block every
+    /// few dozen parallel updates.
+    #[cfg(not(feature = "parallel"))]
+    mod allocation_counter {
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+
+        struct CountingAllocator;
+
+        thread_local! {
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        fn count_allocation() {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        }
+
+        unsafe impl GlobalAlloc for CountingAllocator {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                count_allocation();
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+
+            unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+                count_allocation();
+                System.realloc(ptr, layout, new_size)
+            }
+        }
+
+        #[global_allocator]
+        static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+        /// Allocations `f` makes on this thread
+        pub fn allocations_during(f: impl FnOnce()) -> usize {
+            let before = ALLOCATIONS.with(Cell::get);
+            f();
+            ALLOCATIONS.with(Cell::get) - before
+        }
+    }
+
+    #[test]
+    fn creates_simulation_with_entities() {
+        let handler = SimulationHandler::new(10).unwrap();
+        assert_eq!(handler.get_entity_count(), 10);
+        assert_eq!(handler.get_tick(), 0);
+        assert!(!handler.is_running());
+    }
+
+    #[test]
+    fn steps_and_updates_tick() {
+        let mut handler = SimulationHandler::new(5).unwrap();
+        handler.step();
+        assert_eq!(handler.get_tick(), 1);
+    }
+
+    #[test]
+    fn step_many_stops_at_max_ticks_or_budget() {
+        let mut handler = SimulationHandler::new(5).unwrap();
+        assert_eq!(handler.step_many(0, 100.0), 0);
+        assert_eq!(handler.step_many(4, 1.0e6), 4);
+        assert_eq!(handler.get_tick(), 4);
+        // A spent budget still lets one tick through
+        assert_eq!(handler.step_many(10, 0.0), 1);
+        assert_eq!(handler.get_tick(), 5);
+    }
+
+    #[test]
+    fn toggles_running_state() {
+        let mut handler = SimulationHandler::new(3).unwrap();
+        handler.start();
+        assert!(handler.is_running());
+        handler.pause();
+        assert!(!handler.is_running());
+        handler.resume();
+        assert!(handler.is_running());
+    }
+
+    #[test]
+    fn reset_rebuilds_entities() {
+        let mut handler = SimulationHandler::new(4).unwrap();
+        handler.step();
+        handler.reset();
+        assert_eq!(handler.get_tick(), 0);
+        assert_eq!(handler.get_entity_count(), 4);
+        assert!(!handler.is_running());
+    }
+
+    #[test]
+    fn counts_alive_entities() {
+        let handler = SimulationHandler::new(5).unwrap();
+        assert_eq!(handler.count_alive(), 5);
+    }
+
+    #[test]
+    fn detects_completion_when_one_alive() {
+        use crate::types::AiState;
+        
+        let mut handler = SimulationHandler::new(3).unwrap();
+        
+        // Initially not complete
+        assert!(!handler.is_complete());
+        assert_eq!(handler.count_alive(), 3);
+        
+        // Kill two entities manually for testing by removing their territory
+        if let Some(entity) = handler.logic_mut().data_mut().entity_mut(0) {
+            entity.state = AiState::Dead;
+            entity.territory = 0;
+        }
+        if let Some(entity) = handler.logic_mut().data_mut().entity_mut(1) {
+            entity.state = AiState::Dead;
+            entity.territory = 0;
+        }
+        
+        // Should be complete with only one alive
+        assert!(handler.is_complete());
+        assert_eq!(handler.count_alive(), 1);
+    }
+
+    #[test]
+    fn simulation_stops_when_complete() {
+        use crate::types::AiState;
+        
+        let mut handler = SimulationHandler::new(2).unwrap();
+        handler.start();
+        assert!(handler.is_running());
+        
+        // Kill one entity by removing its territory
+        if let Some(entity) = handler.logic_mut().data_mut().entity_mut(0) {
+            entity.state = AiState::Dead;
+            entity.territory = 0;
+        }
+        
+        // Step should detect completion and stop
+        handler.step();
+        assert!(!handler.is_running(), "Simulation should stop when complete");
+        assert!(handler.is_complete());
+    }
+
+    #[test]
+    fn grid_size_configuration() {
+        let handler = SimulationHandler::new(10).unwrap();
+        assert_eq!(handler.get_grid_size(), 50); // Default grid size
+        
+        let mut handler = SimulationHandler::init_with_grid(5, 60, 20).unwrap();
+        assert_eq!(handler.get_grid_size(), 20);
+        assert_eq!(handler.get_entity_count(), 5);
+        
+        handler.set_grid_size(30).unwrap();
+        assert_eq!(handler.get_grid_size(), 30);
+    }
+
+    #[test]
+    fn world_config_scales_positions() {
+        let config = WorldConfig::new(240.0, 2.0, 10);
+        let mut handler = SimulationHandler::init_with_config(6, 30, config).unwrap();
+        assert_eq!(handler.get_grid_size(), 10);
+        assert_eq!(handler.get_tick_rate(), 30);
+        assert_eq!(handler.get_world_config(), config);
+
+        for i in 0..6 {
+            let entity = handler.logic_mut().data_mut().entity(i).unwrap().clone();
+            assert!(entity.position_x.abs() < 120.0 && entity.position_y.abs() < 120.0);
+            let idx = handler
+                .logic_mut()
+                .data_mut()
+                .position_to_grid_index(entity.position_x, entity.position_y)
+                .expect("entity should sit inside the grid");
+            let owner = handler.logic_mut().data_mut().grid_spaces()[idx].owner_id;
+            assert_eq!(owner, Some(i as u32));
+        }
+
+        for _ in 0..5 {
+            handler.step();
+        }
+        assert_eq!(handler.get_tick(), 5);
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected_with_descriptive_errors() {
+        assert_eq!(SimulationHandler::new(0).err(), Some(SimError::ZeroEntityCount));
+        assert_eq!(SimulationHandler::init(5, 0).err(), Some(SimError::ZeroTickRate));
+        assert_eq!(SimulationHandler::init_with_grid(5, 60, 0).err(), Some(SimError::ZeroGridSize));
+        assert_eq!(SimulationHandler::init_with_teams(&[], 60).err(), Some(SimError::ZeroEntityCount));
+        let config = WorldConfig {
+            grid_size: 0,
+            ..WorldConfig::default()
+        };
+        assert_eq!(SimulationHandler::init_with_config(5, 60, config).err(), Some(SimError::ZeroGridSize));
+
+        let mut handler = SimulationHandler::init_with_grid(5, 60, 10).unwrap();
+        assert_eq!(handler.set_tick_rate(0), Err(SimError::ZeroTickRate));
+        assert_eq!(handler.set_entity_count(0), Err(SimError::ZeroEntityCount));
+        assert_eq!(handler.set_grid_size(0), Err(SimError::ZeroGridSize));
+        assert_eq!(handler.set_world_config(config), Err(SimError::ZeroGridSize));
+        // Rejected inputs leave the simulation as it was
+        assert_eq!((handler.get_tick_rate(), handler.get_entity_count(), handler.get_grid_size()), (60, 5, 10));
+
+        assert!(matches!(handler.queue_command(0, "{\"kind\":"), Err(SimError::MalformedInput(_))));
+        assert!(matches!(handler.remove_entity(99), Err(SimError::InvalidInput(_))));
+        assert!(matches!(handler.set_leaderboard_key("gold"), Err(SimError::InvalidInput(_))));
+        assert_eq!(SimError::ZeroTickRate.to_string(), "Tick rate must be at least 1");
+    }
+
+    #[test]
+    fn world_config_rejects_invalid_values() {
+        let config = WorldConfig::new(-5.0, f32::NAN, 0);
+        assert_eq!(config, WorldConfig::new(2400.0, 5.0, 1));
+    }
+
+    #[test]
+    fn entities_start_with_correct_values() {
+        let mut handler = SimulationHandler::new(5).unwrap();
+        
+        // Access entities through the logic
+        for i in 0..5 {
+            let entity = handler.logic_mut().data_mut().entity(i).expect("Entity should exist");
+            assert_eq!(entity.territory, 1, "Entity {} should start with 1 territory", i);
+            assert_eq!(entity.money, 0.0, "Entity {} should start with 0 money", i);
+            assert_eq!(entity.military_strength, 10.0, "Entity {} should start with 10 military strength", i);
+        }
+    }
+
+    #[test]
+    fn time_based_resource_accumulation() {
+        let mut handler = SimulationHandler::new(2).unwrap();
+        
+        // Get initial values
+        let initial_money = handler.logic_mut().data_mut().entity(0).unwrap().money;
+        
+        // Run several steps
+        for _ in 0..10 {
+            handler.step();
+        }
+        
+        // Income from a space outpaces its upkeep
+        let final_money = handler.logic_mut().data_mut().entity(0).unwrap().money;
+        assert!(final_money > initial_money, "Money should increase over time");
+        
+        // Military strength accumulates but may be spent on attacks
+        // So we verify the total resources generated + spent is reasonable
+        let final_military = handler.logic_mut().data_mut().entity(0).unwrap().military_strength;
+        
+        // With aggressive AI, military might be consumed by attacks
+        // But the accumulation mechanism should still work - verify the AI can gain resources
+        // If it's not higher, it means resources were spent on expansion (which is good!)
+        // Just verify it didn't go negative or unreasonably low
+        assert!(
+            final_military >= 0.0,
+            "Military strength should not be negative, got: {}",
+            final_military
+        );
+    }
+
+    #[test]
+    fn time_scale_multiplies_resource_accumulation() {
+        use crate::constants::MONEY_PER_SPACE_PER_SEC;
+        use crate::data::AiStateUpdater;
+        use crate::types::{AiEntity, PlayerCommand};
+
+        let mut handler = SimulationHandler::new(2).unwrap();
+        assert_eq!(handler.get_time_scale(), 1.0);
+        handler.set_time_scale(-1.0);
+        handler.set_time_scale(f32::NAN);
+        assert_eq!(handler.get_time_scale(), 1.0);
+        handler.set_time_scale(0.0);
+        for _ in 0..5 {
+            handler.step();
+        }
+        assert_eq!(handler.logic_mut().data_mut().entity(0).unwrap().money, 0.0);
+
+        let money_after_half_second = |time_scale: f32| {
+            let mut updater = AiStateUpdater::new();
+            updater.set_time_scale(time_scale);
+            let mut entity = AiEntity::new(0);
+            updater.update_time(1000.0);
+            updater.follow_command(&mut entity, PlayerCommand::Hold, None);
+            updater.update_time(1500.0);
+            updater.follow_command(&mut entity, PlayerCommand::Hold, None);
+            entity.money
+        };
+        // Income from the one space less upkeep on it and the starting military
+        let net_per_sec = MONEY_PER_SPACE_PER_SEC - AiEntity::new(0).upkeep_per_sec();
+        for time_scale in [1.0, 4.0, 0.5] {
+            let money = money_after_half_second(time_scale);
+            assert!((money - 0.5 * time_scale * net_per_sec).abs() < 1e-6, "money was {}", money);
+        }
+    }
+
+    #[test]
+    fn terrain_is_seeded_and_weighs_on_conquest_and_income() {
+        use crate::types::terrain::{TerrainEffects, TerrainType};
+        use crate::types::{GridSpace, TerrainConfig};
+
+        let seeded = TerrainConfig {
+            seed: 7,
+            ..TerrainConfig::default()
+        };
+        assert_eq!(seeded.generate(20), seeded.generate(20));
+        assert_ne!(seeded.generate(20), TerrainConfig::default().generate(20));
+        assert_eq!(serde_json::from_str::<TerrainConfig>(r#"{"seed":7}"#).unwrap(), seeded);
+
+        let terrain = TerrainConfig::default();
+        let mountain = GridSpace {
+            terrain: TerrainType::Mountain,
+            ..GridSpace::new()
+        };
+        assert!(mountain.conquest_cost(&terrain) > GridSpace::new().conquest_cost(&terrain));
+
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 6).unwrap();
+        let mut invalid = TerrainConfig::default();
+        invalid.water.movement_cost = 0.0;
+        assert!(handler.logic_mut().set_terrain_config(invalid).is_err());
+
+        // Every space pays double, so income follows territory twice over
+        let rich = TerrainConfig {
+            seed: 7,
+            plains: TerrainEffects::new(1.0, 0.0, 2.0),
+            forest: TerrainEffects::new(1.0, 0.0, 2.0),
+            mountain: TerrainEffects::new(1.0, 0.0, 2.0),
+            water: TerrainEffects::new(1.0, 0.0, 2.0),
+        };
+        handler.logic_mut().set_terrain_config(rich).unwrap();
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.money_yield, entity.territory as f32 * 2.0);
+
+        // Late joiners regenerate the same terrain from the bundle
+        let bundle = handler.logic_mut().sync_bundle();
+        let mut joiner = SimulationHandler::new(1).unwrap();
+        joiner.logic_mut().apply_sync_bundle(&bundle).unwrap();
+        assert_eq!(joiner.logic_mut().terrain_config(), rich);
+        assert_eq!(joiner.logic_mut().grid_terrain(), handler.logic_mut().grid_terrain());
+    }
+
+    #[test]
+    fn resource_nodes_boost_income_and_draw_attacks() {
+        use crate::data::AiStateUpdater;
+        use crate::types::resource::ResourceNode;
+        use crate::types::{AiEntity, GridSpace, ResourceConfig, TerrainConfig};
+
+        let invalid = ResourceConfig {
+            gold_mine_chance: 1.5,
+            ..ResourceConfig::default()
+        };
+        assert!(invalid.validate().is_err());
+
+        // Gold everywhere: every land space the entity holds is a mine
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 6).unwrap();
+        let gold_rush = ResourceConfig {
+            gold_mine_chance: 1.0,
+            ..ResourceConfig::default()
+        };
+        handler.logic_mut().set_resource_config(gold_rush).unwrap();
+        let data = handler.logic_mut().data_mut();
+        let home = data.grid_spaces()[0];
+        let entity = data.entity(0).unwrap();
+        let (money_yield, military_yield) = gold_rush.space_yields(&home, &TerrainConfig::default());
+        assert_eq!((entity.money_yield, entity.military_yield), (money_yield, military_yield));
+        assert_eq!(home.resource, Some(ResourceNode::GoldMine), "Home is on plains");
+        assert_eq!(entity.resource_nodes, 1);
+        assert_eq!(money_yield, military_yield * 3.0);
+        assert_eq!(
+            handler.logic_mut().grid_resources().iter().filter(|&&node| node == 1).count(),
+            handler
+                .logic_mut()
+                .data_mut()
+                .grid_spaces()
+                .iter()
+                .filter(|space| space.resource.is_some())
+                .count()
+        );
+
+        // An expanding entity in the middle of open plains heads for the farm
+        let mut grid = vec![GridSpace::new(); 9];
+        grid[4] = GridSpace::with_owner(0, 5.0);
+        grid[7].resource = Some(ResourceNode::Farm);
+        let updater = AiStateUpdater::new();
+        let entity = AiEntity::new(0);
+        let chosen = updater.choose_attack_space(
+            &entity,
+            &grid,
+            3,
+            4,
+            &TerrainConfig::default(),
+            &ResourceConfig::default(),
+        );
+        assert_eq!(chosen, Some((7, 4)));
+    }
+
+    #[test]
+    fn armies_march_fight_and_rejoin_their_owner() {
+        use crate::types::terrain::TerrainEffects;
+        use crate::types::{Army, PlayerCommand, TerrainConfig};
+
+        let flat = TerrainEffects::new(1.0, 0.0, 1.0);
+        let plains = TerrainConfig {
+            seed: 0,
+            plains: flat,
+            forest: flat,
+            mountain: flat,
+            water: flat,
+        };
+        let config = WorldConfig::new(240.0, 2.0, 6);
+        let mut handler = SimulationHandler::init_with_config(2, 60, config).unwrap();
+        let logic = handler.logic_mut();
+        logic.set_terrain_config(plains).unwrap();
+        logic.queue_command(0, PlayerCommand::Hold).unwrap();
+        logic.queue_command(1, PlayerCommand::Hold).unwrap();
+        logic.data_mut().entity_mut(0).unwrap().military_strength = 100.0;
+
+        assert!(logic.raise_army(0, 500.0, 5).is_err());
+        assert!(logic.raise_army(0, -1.0, 5).is_err());
+        assert!(logic.raise_army(0, 30.0, 36).is_err());
+        assert!(logic.raise_army(9, 30.0, 5).is_err());
+        assert_eq!(logic.raise_army(0, 30.0, 5), Ok(0));
+        assert_eq!(logic.data_mut().entity(0).unwrap().military_strength, 70.0);
+        let snapshot = logic.request_snapshot().unwrap();
+        assert_eq!(snapshot[0].armies.len(), 1);
+        assert!(snapshot[1].armies.is_empty());
+        assert_eq!(logic.sync_bundle().armies, logic.armies());
+
+        // The army walks along the top row, takes the corner and comes home
+        for _ in 0..40 {
+            handler.step();
+        }
+        let logic = handler.logic_mut();
+        assert!(logic.armies().is_empty());
+        assert_eq!(logic.data_mut().grid_spaces()[5].owner_id, Some(0));
+        assert!(logic.data_mut().entity(0).unwrap().military_strength >= 90.0);
+
+        // Hostile armies sharing a space trade losses before they move on
+        let (x, y) = logic.data_mut().grid_index_to_position(14);
+        let army = |id, owner_id, strength, objective| Army {
+            id,
+            owner_id,
+            strength,
+            position_x: x,
+            position_y: y,
+            objective,
+        };
+        logic
+            .data_mut()
+            .armies_mut()
+            .extend([army(1, 0, 30.0, 17), army(2, 1, 20.0, 12)]);
+        handler.step();
+        let armies = handler.logic_mut().armies().to_vec();
+        assert_eq!(armies.len(), 1);
+        assert_eq!((armies[0].id, armies[0].strength), (1, 10.0));
+    }
+
+    #[test]
+    fn battles_are_rolled_on_seeded_odds_and_bleed_both_sides() {
+        use crate::data::CombatResolver;
+        use crate::types::{Army, PlayerCommand, SimulationEvent};
+
+        let resolver = CombatResolver::new();
+        let even = resolver.win_probability(20.0, 20.0);
+        assert!((even - 0.5).abs() < 0.01, "even odds were {}", even);
+        assert!(resolver.win_probability(30.0, 20.0) > even);
+        assert!(resolver.win_probability(60.0, 20.0) > resolver.win_probability(30.0, 20.0));
+        assert!(resolver.resolve(20.0, 20.0, 0.0).success);
+        let repelled = resolver.resolve(20.0, 20.0, 0.99);
+        assert!(!repelled.success);
+        assert!(repelled.attacker_losses > 0.0 && repelled.defender_losses > 0.0);
+
+        // An army standing on an enemy space assaults it straight away
+        let battle = || {
+            let config = WorldConfig::new(240.0, 2.0, 6);
+            let mut handler = SimulationHandler::init_with_config(2, 60, config).unwrap();
+            let logic = handler.logic_mut();
+            logic.queue_command(0, PlayerCommand::Hold).unwrap();
+            logic.queue_command(1, PlayerCommand::Hold).unwrap();
+            let data = logic.data_mut();
+            data.entity_mut(1).unwrap().military_strength = 100.0;
+            let target = data.grid_spaces().iter().position(|space| space.owner_id == Some(1)).unwrap();
+            let (x, y) = data.grid_index_to_position(target);
+            data.armies_mut().push(Army {
+                id: 0,
+                owner_id: 0,
+                strength: 60.0,
+                position_x: x,
+                position_y: y,
+                objective: target as u32,
+            });
+            handler.step();
+            handler
+        };
+        let mut handler = battle();
+        let bundle = handler.logic().sync_bundle();
+        let outcome = bundle.recent_events.iter().find_map(|event| match *event {
+            SimulationEvent::Battle {
+                attacker_id: 0,
+                defender_id: 1,
+                success,
+                attacker_losses,
+                defender_losses,
+                ..
+            } => Some((success, attacker_losses, defender_losses)),
+            _ => None,
+        });
+        let (success, attacker_losses, defender_losses) = outcome.expect("no battle was recorded");
+        assert!(attacker_losses > 0.0 && defender_losses > 0.0);
+        assert!(handler.logic_mut().data_mut().entity(1).unwrap().military_strength < 100.0);
+        let conquered = bundle
+            .recent_events
+            .iter()
+            .any(|event| matches!(event, SimulationEvent::Conquest { attacker_id: 0, .. }));
+        assert_eq!(conquered, success);
+        assert!(handler.logic().armies().is_empty());
+
+        // Same seeds, same battle
+        assert_eq!(battle().logic().state_hash(), handler.logic().state_hash());
+    }
+
+    #[test]
+    fn metrics_keep_rolling_statistics_of_recent_ticks() {
+        use crate::constants::BENCHMARK_WINDOW;
+
+        let mut handler = SimulationHandler::init(2, 60).unwrap();
+        let logic = handler.logic_mut();
+        let report = logic.metrics_report();
+        assert_eq!(report.tick.samples, 0);
+        assert_eq!(report.tick.p95_ms, 0.0);
+
+        // The oldest 30 ticks fall out of the window
+        let metrics = logic.data_mut().metrics_mut();
+        for duration in 1..=150 {
+            let duration = duration as f64;
+            metrics.update_tick(duration, duration * 0.75, duration * 0.25);
+        }
+        metrics.update_snapshot(2.0);
+        metrics.update_tick(-1.0, 0.0, 0.0);
+
+        let report = logic.metrics_report();
+        assert_eq!(report.tick.samples, BENCHMARK_WINDOW);
+        assert_eq!(report.tick.last_ms, 150.0);
+        assert_eq!(report.tick.min_ms, 31.0);
+        assert_eq!(report.tick.max_ms, 150.0);
+        assert_eq!(report.tick.mean_ms, 90.5);
+        assert_eq!(report.tick.p95_ms, 144.0);
+        assert_eq!(report.entity_update.max_ms, 112.5);
+        assert_eq!(report.conquest.mean_ms, 22.625);
+        assert_eq!((report.snapshot.samples, report.snapshot.p95_ms), (1, 2.0));
+        assert_eq!(logic.last_tick_duration(), 150.0);
+        assert_eq!(logic.last_snapshot_duration(), 2.0);
+    }
+
+    #[test]
+    fn tick_profile_covers_the_latest_tick() {
+        let mut handler = SimulationHandler::init(4, 60).unwrap();
+        assert_eq!(handler.logic().tick_profile().tick, 0);
+        for _ in 0..3 {
+            handler.step();
+        }
+        let profile = handler.logic().tick_profile();
+        assert_eq!(profile.tick, 3);
+        let phases = [
+            profile.snapshot_rebuild_ms,
+            profile.grid_rebuild_ms,
+            profile.entity_update_ms,
+            profile.conquest_ms,
+            profile.death_processing_ms,
+            profile.territory_update_ms,
+        ];
+        assert!(phases.iter().all(|&phase| phase >= 0.0));
+        assert!(phases.iter().sum::<f64>() <= profile.total_ms + 1e-9);
+    }
+
+    #[test]
+    fn batch_runs_are_reproducible_and_export_reports() {
+        use crate::logic::BatchRunner;
+        use crate::types::RunConfig;
+
+        let base = RunConfig {
+            entity_count: 3,
+            world: WorldConfig::new(240.0, 2.0, 6),
+            max_ticks: 400,
+            ..RunConfig::default()
+        };
+        let reports = BatchRunner::sweep_seeds(base, [1, 2]).unwrap();
+        assert_eq!(reports, BatchRunner::sweep_seeds(base, [1, 2]).unwrap());
+        assert_eq!(reports.len(), 2);
+        for report in &reports {
+            assert!(report.ticks <= 400);
+            assert_eq!(report.completed, report.survivors <= 1);
+            assert_eq!(report.winner_id.is_some(), report.completed && report.survivors == 1);
+            assert!(report.leader_territory > 0 || report.survivors == 0);
+        }
+        assert_eq!((reports[0].seed, reports[1].seed), (1, 2));
+
+        let csv = BatchRunner::to_csv(&reports);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+        let json: serde_json::Value = serde_json::from_str(&BatchRunner::to_json(&reports).unwrap()).unwrap();
+        assert_eq!(json[1]["seed"], 2);
+
+        let broken = RunConfig {
+            ms_per_tick: 0.0,
+            ..base
+        };
+        assert!(BatchRunner::run(&[base, broken]).is_err());
+    }
+
+    #[test]
+    fn run_configs_from_json_are_checked_before_building_the_world() {
+        use crate::logic::SimulationLogic;
+        use crate::types::RunConfig;
+
+        let load = |json: &str| SimulationLogic::from_run_config(&serde_json::from_str::<RunConfig>(json).unwrap());
+        let no_grid = load(r#"{"world":{"world_size":2400,"cell_size":150,"grid_size":0}}"#);
+        assert_eq!(no_grid.err(), Some(SimError::ZeroGridSize));
+        for world in [
+            r#"{"world_size":-5,"cell_size":-1,"grid_size":8}"#,
+            r#"{"world_size":2400,"cell_size":0,"grid_size":8}"#,
+            r#"{"world_size":1e39,"cell_size":150,"grid_size":8}"#,
+            r#"{"world_size":2400,"cell_size":150,"grid_size":18446744073709551615}"#,
+        ] {
+            let result = load(&format!(r#"{{"world":{}}}"#, world));
+            assert!(matches!(result, Err(SimError::InvalidInput(_))), "{}", world);
+        }
+        assert_eq!(load(r#"{"entity_count":0}"#).err(), Some(SimError::ZeroEntityCount));
+        assert!(load(r#"{"world":{"world_size":2400,"cell_size":150,"grid_size":8}}"#).is_ok());
+    }
+
+    #[test]
+    fn fixed_timestep_runs_are_reproducible() {
+        let run = || {
+            let mut handler = SimulationHandler::init_with_grid(6, 60, 12).unwrap();
+            assert!(!handler.set_fixed_timestep(0.0));
+            assert!(!handler.is_fixed_timestep());
+            assert!(handler.set_fixed_timestep(100.0));
+            assert!(handler.is_fixed_timestep());
+            for _ in 0..200 {
+                handler.step();
+            }
+            handler
+        };
+        let mut first = run();
+        let second = run();
+        assert_eq!(first.logic().state_hash(), second.logic().state_hash());
+
+        // The resource clock reads 100ms per tick, whatever the wall clock did
+        let entity = first.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.last_update_time, 200.0 * 100.0);
+
+        first.use_real_time();
+        assert!(!first.is_fixed_timestep());
+    }
+
+    #[test]
+    fn sim_time_clock_follows_ticks_and_tick_rate() {
+        use crate::types::ResourceClock;
+
+        let run = |use_sim_time: bool| {
+            let mut handler = SimulationHandler::init_with_grid(6, 50, 12).unwrap();
+            if use_sim_time {
+                handler.set_resource_clock(ResourceClock::SimTime);
+            } else {
+                handler.set_fixed_timestep(20.0);
+            }
+            for _ in 0..100 {
+                handler.step();
+            }
+            handler
+        };
+        // 1 / 50 seconds a tick is the same as a fixed 20ms step
+        let mut handler = run(true);
+        assert_eq!(handler.get_state_hash(), run(false).get_state_hash());
+        assert!(!handler.is_fixed_timestep());
+
+        // A slower tick rate makes each tick count for more from then on
+        handler.set_tick_rate(25).unwrap();
+        handler.step();
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.last_update_time, 100.0 * 20.0 + 40.0);
+        handler.set_resource_clock(ResourceClock::WallTime);
+        assert_eq!(handler.logic().time_mode(), TimeMode::RealTime);
+    }
+
+    #[test]
+    fn entity_dies_when_territory_zero() {
+        use crate::types::AiState;
+        
+        let mut handler = SimulationHandler::new(3).unwrap();
+        
+        // Manually set territory to 0 and clear grid space ownership
+        let entity_id = {
+            let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+            entity.territory = 0;
+            entity.id
+        };
+        
+        // Also need to clear grid space ownership for this entity
+        let grid_size = handler.logic_mut().data_mut().grid_size();
+        for i in 0..(grid_size * grid_size) {
+            if let Some(space) = handler.logic_mut().data_mut().grid_space_mut(i) {
+                if space.owner_id == Some(entity_id) {
+                    space.owner_id = None;
+                }
+            }
+        }
+        
+        // Step the simulation
+        handler.step();
+        
+        // Entity should be marked as dead
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.state, AiState::Dead);
+        assert_eq!(entity.territory, 0);
+    }
+
+    #[test]
+    fn dying_entities_leave_resources_to_idle_neighbors() {
+        use crate::types::{AiState, PlayerCommand};
+
+        let mut handler = SimulationHandler::new(3).unwrap();
+        let logic = handler.logic_mut();
+        logic.queue_command(1, PlayerCommand::Hold).unwrap();
+        logic.queue_command(2, PlayerCommand::Hold).unwrap();
+
+        // Entity 1 idles right next to entity 0, entity 2 is far away
+        let (x, y) = {
+            let entity = logic.data_mut().entity_mut(0).unwrap();
+            entity.military_strength = 30.0;
+            entity.money = 12.0;
+            entity.territory = 0;
+            (entity.position_x, entity.position_y)
+        };
+        let neighbor = logic.data_mut().entity_mut(1).unwrap();
+        neighbor.position_x = x + 2.0;
+        neighbor.position_y = y;
+        neighbor.military_strength = 0.0;
+        for i in 0..50 * 50 {
+            let space = logic.data_mut().grid_space_mut(i).unwrap();
+            if space.owner_id == Some(0) {
+                space.owner_id = None;
+            }
+        }
+
+        handler.step();
+
+        let dead = handler.logic_mut().data_mut().entity(0).unwrap().clone();
+        assert_eq!(dead.state, AiState::Dead);
+        let heir = handler.logic_mut().data_mut().entity(1).unwrap().clone();
+        assert_eq!(heir.state, AiState::Idle);
+        assert_eq!(heir.military_strength, 30.0);
+        assert!(heir.money >= 12.0);
+    }
+
+    #[test]
     fn conquest_mechanics_work() {
         use crate::types::AiState;
-        
-        let mut handler = SimulationHandler::new(2);
-        
-        // Set up two adjacent AIs, one attacking with enough strength
-        {
-            let grid_size = handler.logic_mut().data_mut().grid_size();
-            
-            // Position AI 0 and AI 1 next to each other
-            let entity0 = handler.logic_mut().data_mut().entity_mut(0).unwrap();
-            entity0.state = AiState::Attacking;
-            entity0.military_strength = 100.0; // Plenty of strength to attack
-            entity0.position_x = 0.0;
-            entity0.position_y = 0.0;
-            let entity0_id = entity0.id;
-            
-            let entity1 = handler.logic_mut().data_mut().entity_mut(1).unwrap();
-            entity1.state = AiState::Idle;
-            entity1.position_x = (2400.0 / grid_size as f32); // Next grid cell
-            entity1.position_y = 0.0;
-            let entity1_id = entity1.id;
-            
-            // Set up initial grid ownership
-            if let Some(idx0) = handler.logic_mut().data_mut().position_to_grid_index(0.0, 0.0) {
-                if let Some(space) = handler.logic_mut().data_mut().grid_space_mut(idx0) {
-                    space.owner_id = Some(entity0_id);
-                    space.defense_strength = 5.0;
-                }
+        
+        let mut handler = SimulationHandler::new(2).unwrap();
+        
+        // Set up two adjacent AIs, one attacking with enough strength
+        {
+            let grid_size = handler.logic_mut().data_mut().grid_size();
+            
+            // Position AI 0 and AI 1 next to each other
+            let entity0 = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+            entity0.state = AiState::Attacking;
+            entity0.military_strength = 100.0; // Plenty of strength to attack
+            entity0.position_x = 0.0;
+            entity0.position_y = 0.0;
+            let entity0_id = entity0.id;
+            
+            let entity1 = handler.logic_mut().data_mut().entity_mut(1).unwrap();
+            entity1.state = AiState::Idle;
+            entity1.position_x = 2400.0 / grid_size as f32; // Next grid cell
+            entity1.position_y = 0.0;
+            let entity1_id = entity1.id;
+            
+            // Set up initial grid ownership
+            if let Some(idx0) = handler.logic_mut().data_mut().position_to_grid_index(0.0, 0.0) {
+                if let Some(space) = handler.logic_mut().data_mut().grid_space_mut(idx0) {
+                    space.owner_id = Some(entity0_id);
+                    space.defense_strength = 5.0;
+                }
+            }
+            
+            if let Some(idx1) = handler.logic_mut().data_mut().position_to_grid_index(2400.0 / grid_size as f32, 0.0) {
+                if let Some(space) = handler.logic_mut().data_mut().grid_space_mut(idx1) {
+                    space.owner_id = Some(entity1_id);
+                    space.defense_strength = 5.0;
+                }
+            }
+        }
+        
+        // Update territories before step
+        handler.logic_mut().data_mut().update_territories();
+        
+        let initial_territory_0 = handler.logic_mut().data_mut().entity(0).unwrap().territory;
+        let initial_territory_1 = handler.logic_mut().data_mut().entity(1).unwrap().territory;
+        
+        // Run several steps to allow conquest
+        for _ in 0..5 {
+            handler.step();
+        }
+        
+        // Check if territory changed (conquest happened)
+        let final_territory_0 = handler.logic_mut().data_mut().entity(0).unwrap().territory;
+        let final_territory_1 = handler.logic_mut().data_mut().entity(1).unwrap().territory;
+        
+        // Attacker should have gained territory or defender should have lost some
+        // (Conquest may or may not happen depending on positioning, so we just verify the mechanism works)
+        assert!(
+            final_territory_0 != initial_territory_0 || final_territory_1 != initial_territory_1 || final_territory_0 > 0,
+            "Conquest mechanics should be working"
+        );
+    }
+
+    #[test]
+    fn strong_attackers_take_several_spaces_a_tick_at_rising_cost() {
+        use crate::constants::MORALE_CONQUEST_GAIN;
+        use crate::types::terrain::TerrainType;
+
+        // Territory gained and strength spent by entity 0 expanding for one tick
+        let expand = |config_json: &str, military_strength: f32| {
+            let mut handler = SimulationHandler::init_with_grid(2, 60, 6).unwrap();
+            handler.set_conquest_config(config_json).unwrap();
+            let data = handler.logic_mut().data_mut();
+            for i in 0..6 * 6 {
+                let space = data.grid_space_mut(i).unwrap();
+                space.terrain = TerrainType::Plains;
+                space.resource = None;
+                space.garrison = 0.0;
+            }
+            let entity = data.entity_mut(0).unwrap();
+            entity.military_strength = military_strength;
+            let territory = entity.territory;
+            handler.step();
+            let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+            (entity.territory - territory, military_strength - entity.military_strength)
+        };
+
+        let assert_expands = |(territory, spent): (u32, f32), expected_territory: u32, expected_spent: f32| {
+            assert_eq!(territory, expected_territory);
+            assert!((spent - expected_spent).abs() < 1e-3, "spent {spent}, expected {expected_spent}");
+        };
+
+        // Ungarrisoned plains cost 10, then 20 and 40 for the extra spaces, each
+        // paid for at the morale the last conquest raised
+        let gain = MORALE_CONQUEST_GAIN;
+        assert_expands(expand("{}", 200.0), 3, 10.0 + 20.0 / (1.0 + gain) + 40.0 / (1.0 + 2.0 * gain));
+        assert_expands(expand(r#"{"max_conquests_per_tick":2}"#, 200.0), 2, 10.0 + 20.0 / (1.0 + gain));
+        assert_expands(expand(r#"{"max_conquests_per_tick":1}"#, 200.0), 1, 10.0);
+        assert_expands(expand("{}", 25.0), 1, 10.0); // An early army pays for one space only
+
+        let mut handler = SimulationHandler::new(2).unwrap();
+        assert!(handler.set_conquest_config(r#"{"max_conquests_per_tick":0}"#).is_err());
+        assert!(handler.set_conquest_config(r#"{"extra_conquest_cost_growth":0.5}"#).is_err());
+    }
+
+    #[test]
+    fn neutral_garrisons_make_early_land_cost_more() {
+        use crate::constants::{ATTACK_COST, DEFENSE_BONUS_MULTIPLIER, NEUTRAL_GROWTH_INTERVAL};
+        use crate::types::grid_space::NEUTRAL_GRID_OWNER;
+        use crate::types::terrain::TerrainType;
+        use crate::types::{AiState, TerrainConfig};
+
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 10).unwrap();
+        handler.set_fixed_timestep(16.0);
+        let data = handler.logic_mut().data_mut();
+        for i in 0..10 * 10 {
+            let space = data.grid_space_mut(i).unwrap();
+            space.terrain = TerrainType::Plains;
+            space.resource = None;
+        }
+        let config = r#"{"garrison_chance":1,"initial_defense":4,"defense_growth_per_tick":0.1,"max_defense":5.5}"#;
+        handler.set_neutral_config(config).unwrap();
+
+        // Every unowned space holds a garrison, shown under the neutral owner id
+        let (owners, defense) = handler.logic_mut().request_grid_snapshot();
+        for (owner, strength) in owners.iter().zip(defense) {
+            if *owner >= 2 {
+                assert_eq!(*owner, NEUTRAL_GRID_OWNER);
+                assert_eq!(*strength, 4.0);
+            }
+        }
+        let viewport = handler.logic_mut().snapshot_in_rect(-1200.0, -1200.0, 1200.0, 1200.0);
+        assert!(viewport.grid_cells.iter().any(|cell| cell.owner_id == Some(NEUTRAL_GRID_OWNER)));
+        let data = handler.logic_mut().data_mut();
+        let neutral = data.grid_spaces().iter().position(|space| space.owner_id.is_none()).unwrap();
+        let space = &data.grid_spaces()[neutral];
+        assert_eq!(space.conquest_cost(&TerrainConfig::default()), ATTACK_COST + 4.0 * DEFENSE_BONUS_MULTIPLIER);
+
+        // Garrisons grow while nobody takes them, up to their cap
+        for entity in 0..2 {
+            let entity = handler.logic_mut().data_mut().entity_mut(entity).unwrap();
+            entity.state = AiState::Defending;
+            entity.military_strength = 0.0;
+        }
+        for _ in 0..NEUTRAL_GROWTH_INTERVAL {
+            handler.step();
+        }
+        let garrison = handler.logic_mut().data_mut().grid_spaces()[neutral].garrison;
+        assert_eq!(garrison, 4.0 + 0.1 * NEUTRAL_GROWTH_INTERVAL as f32);
+        for _ in 0..NEUTRAL_GROWTH_INTERVAL {
+            handler.step();
+        }
+        assert_eq!(handler.logic_mut().data_mut().grid_spaces()[neutral].garrison, 5.5);
+
+        // Taking a garrisoned space clears it, and neutrals are never entities or winners
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.state = AiState::Attacking;
+        entity.military_strength = 500.0;
+        let territory = entity.territory;
+        handler.step();
+        let data = handler.logic_mut().data_mut();
+        assert!(data.entity(0).unwrap().territory > territory);
+        for space in data.grid_spaces() {
+            assert!(space.owner_id.is_none() || space.garrison == 0.0);
+        }
+        assert_eq!(handler.get_entity_count(), 2);
+        handler.logic_mut().data_mut().entity_mut(1).unwrap().state = AiState::Dead;
+        assert!(handler.is_complete(), "Land still held by garrisons does not keep the game going");
+
+        assert!(handler.set_neutral_config(r#"{"garrison_chance":1.5}"#).is_err());
+        assert!(handler.set_neutral_config(r#"{"initial_defense":30}"#).is_err());
+        assert!(handler.set_neutral_config(r#"{"defense_growth_per_tick":-1}"#).is_err());
+    }
+
+    #[test]
+    fn cut_off_territory_loses_supply_and_falls() {
+        use crate::constants::SUPPLY_CHECK_INTERVAL;
+        use crate::types::{GridSpace, PlayerCommand, TerrainConfig};
+
+        // Entity 1's space splits entity 0's strip in two: 0 1 | 2 | 3
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 6).unwrap();
+        handler.set_fixed_timestep(16.0);
+        let logic = handler.logic_mut();
+        let data = logic.data_mut();
+        for index in 0..6 * 6 {
+            *data.grid_space_mut(index).unwrap() = GridSpace::default();
+        }
+        for (index, owner) in [(0, 0), (1, 0), (2, 1), (3, 0)] {
+            *data.grid_space_mut(index).unwrap() = GridSpace::with_owner(owner, 10.0);
+        }
+        data.entity_mut(0).unwrap().capital = Some(0);
+        data.entity_mut(1).unwrap().capital = Some(2);
+        data.update_territories();
+        for id in 0..2 {
+            logic.queue_command(id, PlayerCommand::Hold).unwrap();
+        }
+
+        for _ in 0..SUPPLY_CHECK_INTERVAL {
+            handler.step();
+        }
+        let data = handler.logic_mut().data_mut();
+        let (linked, pocket) = (data.grid_spaces()[1], data.grid_spaces()[3]);
+        assert_eq!(linked.supply, 1.0);
+        assert!(pocket.supply < 1.0 && pocket.owner_id == Some(0));
+        let terrain = TerrainConfig::default();
+        assert!(pocket.conquest_cost(&terrain) < linked.conquest_cost(&terrain), "Cut-off defenses weaken");
+
+        for _ in 0..SUPPLY_CHECK_INTERVAL * 3 {
+            handler.step();
+        }
+        let data = handler.logic_mut().data_mut();
+        assert_eq!(data.grid_spaces()[3].owner_id, None, "The pocket fell");
+        assert_eq!(data.grid_spaces()[1].owner_id, Some(0));
+        assert_eq!(data.entity(0).unwrap().territory, 2);
+    }
+
+    #[test]
+    fn lost_capitals_move_to_the_nearest_owned_space() {
+        use crate::types::GridSpace;
+
+        let mut handler = SimulationHandler::init_with_grid(1, 60, 6).unwrap();
+        let data = handler.logic_mut().data_mut();
+        for index in 0..6 * 6 {
+            *data.grid_space_mut(index).unwrap() = GridSpace::default();
+        }
+        for index in [4, 20, 35] {
+            *data.grid_space_mut(index).unwrap() = GridSpace::with_owner(0, 5.0);
+        }
+        data.entity_mut(0).unwrap().capital = Some(8);
+        data.relocate_lost_capitals();
+        assert_eq!(data.entity(0).unwrap().capital, Some(20), "Two spaces away beats three");
+    }
+
+    #[test]
+    fn capturing_a_capital_hands_over_land_and_resources() {
+        use crate::types::{GridSpace, PlayerCommand, SimulationEvent};
+
+        // Entity 0 holds spaces 0-1 and strikes entity 1's capital at 2;
+        // entity 1 holds the rest of the top two rows' right side
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 6).unwrap();
+        handler.set_fixed_timestep(16.0);
+        let logic = handler.logic_mut();
+        let data = logic.data_mut();
+        for index in 0..6 * 6 {
+            *data.grid_space_mut(index).unwrap() = GridSpace::default();
+        }
+        for index in [0, 1] {
+            *data.grid_space_mut(index).unwrap() = GridSpace::with_owner(0, 5.0);
+        }
+        for index in [2, 3, 4, 5, 8, 9, 10, 11] {
+            *data.grid_space_mut(index).unwrap() = GridSpace::with_owner(1, 5.0);
+        }
+        let (x, y) = data.grid_index_to_position(1);
+        let attacker = data.entity_mut(0).unwrap();
+        (attacker.position_x, attacker.position_y) = (x, y);
+        attacker.capital = Some(0);
+        attacker.military_strength = 500.0;
+        let defender = data.entity_mut(1).unwrap();
+        defender.capital = Some(2);
+        defender.money = 100.0;
+        data.update_territories();
+        logic.queue_command(0, PlayerCommand::Attack { grid_index: 2 }).unwrap();
+        logic.queue_command(1, PlayerCommand::Hold).unwrap();
+        handler.step();
+
+        let logic = handler.logic_mut();
+        let captured = logic.events().find_map(|event| match *event {
+            SimulationEvent::CapitalCaptured { grid_index, attacker_id, defender_id, spaces_transferred, .. } => {
+                Some((grid_index, attacker_id, defender_id, spaces_transferred))
+            }
+            _ => None,
+        });
+        // A quarter of the 7 spaces left, rounded up, nearest the capital first
+        assert_eq!(captured, Some((2, 0, 1, 2)));
+        let data = logic.data_mut();
+        let owners: Vec<_> = [2, 3, 8, 4, 9].iter().map(|&index| data.grid_spaces()[index].owner_id).collect();
+        assert_eq!(owners, [Some(0), Some(0), Some(0), Some(1), Some(1)]);
+        assert_eq!(data.entity(1).unwrap().money, 75.0);
+        assert_eq!(data.entity(0).unwrap().money, 25.0);
+
+        let snapshot = data.build_public_snapshot();
+        assert_eq!(snapshot[0].capital, Some(0));
+        assert_eq!(snapshot[1].capital, Some(4), "Moved to the nearest space left, lowest index first");
+    }
+
+    #[test]
+    fn threatened_ais_fortify_their_capital() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 12).unwrap();
+        let data = handler.logic_mut().data_mut();
+        let (x, y, capital) = {
+            let entity = data.entity(0).unwrap();
+            (entity.position_x, entity.position_y, entity.capital)
+        };
+        assert!(capital.is_some());
+        for (id, offset) in [(1, 4.0), (2, -4.0)] {
+            let attacker = data.entity_mut(id).unwrap();
+            attacker.state = AiState::Attacking;
+            (attacker.position_x, attacker.position_y) = (x + offset, y);
+        }
+        data.entity_mut(0).unwrap().military_strength = 200.0;
+        handler.step();
+
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap().clone();
+        assert_eq!(entity.state, AiState::Defending);
+        assert_eq!(entity.fortify_target, capital);
+    }
+
+    #[test]
+    fn relations_sour_in_battle_warm_in_peace_and_steer_targets() {
+        use crate::constants::{RELATIONS_ASSAULT_DROP, RELATIONS_PEACE_GAIN};
+        use crate::data::{AiStateUpdater, GridBehavior, GridUpdateBuilder};
+        use crate::types::relations::Relation;
+        use crate::types::{AiState, Army, PlayerCommand, Relations};
+
+        // An army's assault sours relations between the two sides
+        let config = WorldConfig::new(240.0, 2.0, 6);
+        let mut handler = SimulationHandler::init_with_config(2, 60, config).unwrap();
+        let logic = handler.logic_mut();
+        logic.queue_command(0, PlayerCommand::Hold).unwrap();
+        logic.queue_command(1, PlayerCommand::Hold).unwrap();
+        let data = logic.data_mut();
+        let target = data.grid_spaces().iter().position(|space| space.owner_id == Some(1)).unwrap();
+        // A second space, made its capital, keeps entity 1 alive whichever way the battle goes
+        let spare = if target % 6 == 5 { target - 1 } else { target + 1 };
+        data.grid_space_mut(spare).unwrap().owner_id = Some(1);
+        let defender = data.entity_mut(1).unwrap();
+        defender.territory += 1;
+        defender.capital = Some(spare as u32);
+        let (x, y) = data.grid_index_to_position(target);
+        data.armies_mut().push(Army {
+            id: 0,
+            owner_id: 0,
+            strength: 60.0,
+            position_x: x,
+            position_y: y,
+            objective: target as u32,
+        });
+        handler.step();
+        let soured = -RELATIONS_ASSAULT_DROP;
+        assert_eq!(handler.get_relations_matrix(), vec![0.0, soured, soured, 0.0]);
+
+        // Peace only counts once a full check interval has passed since the fighting
+        for _ in 0..9 {
+            handler.step();
+        }
+        assert_eq!(handler.get_relations_matrix()[1], soured);
+        for _ in 0..10 {
+            handler.step();
+        }
+        assert_eq!(handler.get_relations_matrix()[1], soured + RELATIONS_PEACE_GAIN);
+
+        let mut relations = Relations::new();
+        relations.record_clash(0, 1, 0, 500.0);
+        relations.keep_peace(20, 10, [(2, 1)], 10.0);
+        assert_eq!(relations.get(1, 0), -90.0);
+        assert_eq!(relations.get(1, 2), 10.0);
+
+        // A nearby rival is attacked instead of unowned land, and a friend is spared
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 12).unwrap();
+        let data = handler.logic_mut().data_mut();
+        let (x, y) = {
+            let entity = data.entity(0).unwrap();
+            (entity.position_x, entity.position_y)
+        };
+        for (id, offset) in [(1, 4.0), (2, -4.0)] {
+            let neighbor = data.entity_mut(id).unwrap();
+            (neighbor.position_x, neighbor.position_y) = (x + offset, y);
+        }
+        data.relations_mut().record_clash(0, 1, 0, 60.0);
+        data.relations_mut().insert(0, 2, Relation { score: 60.0, last_clash: None });
+        data.rebuild_snapshot_buffer();
+        let world = handler.logic().world_config();
+        let data = handler.logic_mut().data_mut();
+        let mut grid = GridUpdateBuilder::for_world(&world);
+        grid.rebuild(data.snapshots());
+        let updater = AiStateUpdater::new();
+        let (snapshots, alliances, relations, entities) = data.entity_update_buffers();
+        let decide = |behavior: GridBehavior| {
+            let mut entity = entities[0].clone();
+            updater.update_entity(&mut entity, 1, 0, snapshots, &grid, alliances, relations, behavior);
+            (entity.state, entity.attack_target)
+        };
+        assert_eq!(decide(GridBehavior::Expand), (AiState::Attacking, Some(1)));
+        assert_eq!(decide(GridBehavior::Attack { target_id: 2 }), (AiState::Attacking, Some(1)));
+    }
+
+    #[test]
+    fn behavior_profiles_set_how_readily_ais_fight() {
+        use crate::data::{AiStateUpdater, GridBehavior, GridUpdateBuilder};
+        use crate::types::{AiEntity, AiState, BehaviorProfile};
+
+        // Entity 0 decides with `profile`, its strength and money beside entity 1,
+        // which has `neighbor_strength` and is attacking if `raiding`
+        fn decide(
+            handler: &mut SimulationHandler,
+            profile: BehaviorProfile,
+            (military_strength, money): (f32, f32),
+            (neighbor_strength, raiding): (f32, bool),
+            behavior: GridBehavior,
+        ) -> AiEntity {
+            let world = handler.logic().world_config();
+            let data = handler.logic_mut().data_mut();
+            let (x, y) = {
+                let entity = data.entity(0).unwrap();
+                (entity.position_x, entity.position_y)
+            };
+            let neighbor = data.entity_mut(1).unwrap();
+            (neighbor.position_x, neighbor.position_y) = (x + 4.0, y);
+            neighbor.military_strength = neighbor_strength;
+            neighbor.state = if raiding { AiState::Attacking } else { AiState::Idle };
+            data.rebuild_snapshot_buffer();
+            let mut grid = GridUpdateBuilder::for_world(&world);
+            grid.rebuild(data.snapshots());
+            let (snapshots, alliances, relations, entities) = data.entity_update_buffers();
+            let mut entity = entities[0].clone();
+            entity.profile = profile;
+            entity.military_strength = military_strength;
+            entity.money = money;
+            let updater = AiStateUpdater::new();
+            updater.update_entity(&mut entity, 1, 0, snapshots, &grid, alliances, relations, behavior);
+            entity
+        }
+
+        // Profiles cycle through the entities at init and can be switched by name
+        let mut handler = SimulationHandler::init_with_grid(5, 60, 12).unwrap();
+        let profiles: Vec<BehaviorProfile> = handler.logic().entities().iter().map(|entity| entity.profile).collect();
+        assert_eq!(profiles[..4], BehaviorProfile::ALL);
+        assert_eq!(profiles[4], BehaviorProfile::Aggressive);
+        handler.set_behavior(4, "turtle").unwrap();
+        assert_eq!(handler.logic().entities()[4].profile, BehaviorProfile::Turtle);
+        assert!(handler.set_behavior(4, "reckless").is_err());
+        assert!(handler.set_behavior(9, "turtle").is_err());
+        let bytes = encode_commands(&[Command::SetBehavior { entity_id: 3, profile: BehaviorProfile::Economic }]);
+        handler.apply_command_bytes(&bytes).unwrap();
+        assert_eq!(handler.logic().entities()[3].profile, BehaviorProfile::Economic);
+
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 12).unwrap();
+        let capital = handler.logic().entities()[0].capital.unwrap();
+        let attack = GridBehavior::Attack { target_id: 1 };
+        let calm = (15.0, false);
+        let (aggressive, turtle, economic, opportunist) = (
+            BehaviorProfile::Aggressive,
+            BehaviorProfile::Turtle,
+            BehaviorProfile::Economic,
+            BehaviorProfile::Opportunist,
+        );
+
+        // An aggressive AI takes up the fight and spends its savings on it
+        let entity = decide(&mut handler, aggressive, (15.0, 100.0), calm, attack);
+        assert_eq!((entity.state, entity.attack_target), (AiState::Attacking, Some(1)));
+        assert!(entity.money < 100.0);
+
+        // A turtle won't start a war, and needs a wide margin even to expand
+        assert_eq!(decide(&mut handler, turtle, (15.0, 0.0), calm, attack).state, AiState::Idle);
+        let entity = decide(&mut handler, turtle, (40.0, 0.0), calm, attack);
+        assert_eq!((entity.state, entity.attack_target), (AiState::Attacking, None));
+        // One raider is enough to send it back to its capital, where an aggressive AI holds its course
+        assert_eq!(decide(&mut handler, turtle, (40.0, 0.0), (15.0, true), attack).fortify_target, Some(capital));
+        assert_eq!(decide(&mut handler, aggressive, (40.0, 0.0), (15.0, true), attack).fortify_target, None);
+
+        // An economic AI claims land instead and keeps its money while safe
+        let entity = decide(&mut handler, economic, (25.0, 100.0), calm, attack);
+        assert_eq!((entity.state, entity.attack_target, entity.money), (AiState::Attacking, None, 100.0));
+        assert_eq!(decide(&mut handler, economic, (15.0, 0.0), calm, GridBehavior::Expand).state, AiState::Idle);
+
+        // An opportunist leaves an even match alone but falls on a weak neighbor
+        assert_eq!(decide(&mut handler, opportunist, (20.0, 0.0), calm, attack).attack_target, None);
+        let entity = decide(&mut handler, opportunist, (20.0, 0.0), (5.0, false), GridBehavior::Accumulate);
+        assert_eq!((entity.state, entity.attack_target), (AiState::Attacking, Some(1)));
+    }
+
+    #[test]
+    fn scripted_brains_take_over_chosen_entities() {
+        use crate::data::{EntityAction, EntityOrder, ScriptedBrain};
+        use crate::types::AiState;
+        use std::cell::RefCell;
+
+        let run = |script: Option<ScriptedBrain>| {
+            let mut handler = SimulationHandler::init_with_grid(3, 60, 10).unwrap();
+            handler.set_fixed_timestep(16.0);
+            if let Some(script) = script {
+                let brain = handler.logic_mut().register_brain(Box::new(script));
+                assert_eq!(brain, 1);
+                handler.set_brain(0, brain).unwrap();
+            }
+            let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+            entity.military_strength = 200.0;
+            entity.money = 100.0;
+            handler
+        };
+
+        // A script that fortifies the capital after buying troops is asked about entity 0 only
+        let asked = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&asked);
+        let mut handler = run(Some(ScriptedBrain::new(move |view, world| {
+            log.borrow_mut().push((view.id, world.tick));
+            Some(EntityAction {
+                order: EntityOrder::Defend,
+                buy_military: 10.0,
+            })
+        })));
+        handler.step();
+        assert_eq!(*asked.borrow(), vec![(0, 1)]);
+        let entity = handler.logic().entities()[0].clone();
+        assert_eq!(entity.state, AiState::Defending);
+        assert_eq!(entity.fortify_target, entity.capital);
+        assert!(entity.military_strength >= 210.0);
+        assert!(entity.money <= 80.0);
+
+        // A script with no answer leaves every decision to the greedy brain
+        let mut scripted = run(Some(ScriptedBrain::new(|_, _| None)));
+        let mut greedy = run(None);
+        for _ in 0..20 {
+            scripted.step();
+            greedy.step();
+        }
+        assert_eq!(scripted.get_state_hash(), greedy.get_state_hash());
+
+        assert!(handler.set_brain(1, 2).is_err(), "Only registered brains can be assigned");
+        assert!(handler.set_brain(9, 0).is_err());
+        handler.set_brain(0, 0).unwrap();
+        handler.step();
+        assert_eq!(asked.borrow().len(), 1);
+
+        // Actions arrive from JS as tagged objects
+        let action: EntityAction = serde_json::from_str(r#"{"kind":"Attack","target_id":2,"buy_military":5}"#).unwrap();
+        assert_eq!(action.order, EntityOrder::Attack { target_id: 2 });
+        assert_eq!(action.buy_military, 5.0);
+        let action: EntityAction = serde_json::from_str(r#"{"kind":"Hold"}"#).unwrap();
+        assert_eq!(action, EntityOrder::Hold.into());
+        assert!(serde_json::from_str::<EntityAction>(r#"{"kind":"Dance"}"#).is_err());
+    }
+
+    #[test]
+    fn behavior_trees_decide_and_count_node_runs() {
+        use crate::constants::BEHAVIOR_TREE_MAX_NODES;
+        use crate::types::AiState;
+
+        let tree = |invert: bool| {
+            format!(
+                r#"{{"type":"selector","children":[
+                    {{"type":"sequence","children":[
+                        {{"type":"condition","check":"money_at_least","value":1e9,"invert":{}}},
+                        {{"type":"action","do":"defend","buy_military":10}}]}},
+                    {{"type":"action","do":"hold"}}]}}"#,
+                invert
+            )
+        };
+        let runs = |handler: &SimulationHandler| {
+            let logic = handler.logic();
+            let stats = logic.behavior_tree_stats(0).unwrap();
+            stats.iter().map(|node| (node.ticks, node.successes, node.failures)).collect::<Vec<_>>()
+        };
+
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 10).unwrap();
+        handler.set_fixed_timestep(16.0);
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.military_strength = 200.0;
+        entity.money = 100.0;
+        assert!(handler.logic().behavior_tree_stats(0).is_err());
+
+        // Nobody is that rich, so the selector falls through to holding
+        handler.load_behavior_tree(0, &tree(false)).unwrap();
+        handler.step();
+        assert_eq!(handler.logic().entities()[0].state, AiState::Idle);
+        assert_eq!(runs(&handler), vec![(1, 1, 0), (1, 0, 1), (1, 0, 1), (0, 0, 0), (1, 1, 0)]);
+        let logic = handler.logic();
+        let names: Vec<&str> = logic.behavior_tree_stats(0).unwrap().iter().map(|n| n.node.as_str()).collect();
+        assert_eq!(names, ["selector", "sequence", "condition money_at_least", "action defend", "action hold"]);
+        drop(logic);
+
+        // Reloading swaps the tree in place and starts its stats over
+        handler.load_behavior_tree(0, &tree(true)).unwrap();
+        handler.step();
+        handler.step();
+        let entity = handler.logic().entities()[0].clone();
+        assert_eq!(entity.state, AiState::Defending);
+        assert_eq!(entity.fortify_target, entity.capital);
+        assert!(entity.military_strength >= 210.0);
+        assert_eq!(runs(&handler), vec![(2, 2, 0), (2, 2, 0), (2, 2, 0), (2, 2, 0), (0, 0, 0)]);
+        assert!(handler.logic().behavior_tree_stats(1).is_err(), "Other entities keep the greedy brain");
+
+        // Handing the entity another brain retires its tree until one is loaded again
+        handler.set_brain(0, 0).unwrap();
+        assert!(handler.logic().behavior_tree_stats(0).is_err());
+        handler.load_behavior_tree(0, &tree(false)).unwrap();
+        assert_eq!(runs(&handler), vec![(0, 0, 0); 5]);
+        assert!(handler.set_brain(1, 2).is_err(), "Reloading reuses the entity's brain slot");
+
+        let too_big = format!(
+            r#"{{"type":"sequence","children":[{}]}}"#,
+            vec![r#"{"type":"action","do":"hold"}"#; BEHAVIOR_TREE_MAX_NODES].join(",")
+        );
+        for json in [
+            "{",
+            r#"{"type":"action","do":"dance"}"#,
+            r#"{"type":"condition","check":"is_raining"}"#,
+            r#"{"type":"selector","children":[]}"#,
+            too_big.as_str(),
+        ] {
+            assert!(matches!(handler.load_behavior_tree(0, json), Err(SimError::InvalidInput(_))), "{}", json);
+        }
+        assert!(handler.load_behavior_tree(9, &tree(false)).is_err());
+    }
+
+    #[test]
+    fn money_pays_upkeep_and_buys_military() {
+        use crate::constants::{MILITARY_PRICE, UPKEEP_RESERVE_SECS};
+        use crate::data::AiStateUpdater;
+        use crate::types::{AiEntity, AiState, PlayerCommand};
+
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 12).unwrap();
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.money = 100.0;
+        let military_strength = entity.military_strength;
+        handler.buy_military(0, 20.0).unwrap();
+        assert!(matches!(handler.buy_military(0, 100.0), Err(SimError::InvalidInput(_))));
+        assert!(matches!(handler.buy_military(0, -1.0), Err(SimError::InvalidInput(_))));
+        assert!(matches!(handler.buy_military(9, 1.0), Err(SimError::InvalidInput(_))));
+        handler.apply_command_bytes(&encode_commands(&[Command::BuyMilitary { entity_id: 0, amount: 5.0 }])).unwrap();
+        let entity = handler.logic().entities()[0].clone();
+        assert_eq!(entity.money, 100.0 - 25.0 * MILITARY_PRICE);
+        assert_eq!(entity.military_strength, military_strength + 25.0);
+
+        // An army its land can't pay for deserts and loses heart
+        let mut updater = AiStateUpdater::new();
+        let mut entity = AiEntity::new(0);
+        entity.military_strength = 1000.0;
+        updater.update_time(1000.0);
+        updater.follow_command(&mut entity, PlayerCommand::Hold, None);
+        updater.update_time(2000.0);
+        updater.follow_command(&mut entity, PlayerCommand::Hold, None);
+        assert_eq!(entity.money, 0.0);
+        assert!(entity.military_strength < 1000.0, "military was {}", entity.military_strength);
+        assert!(entity.morale < 1.0);
+
+        // A threatened AI spends its savings on troops, keeping a reserve for upkeep
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 12).unwrap();
+        let data = handler.logic_mut().data_mut();
+        let (x, y) = {
+            let entity = data.entity(0).unwrap();
+            (entity.position_x, entity.position_y)
+        };
+        for (id, offset) in [(1, 4.0), (2, -4.0)] {
+            let attacker = data.entity_mut(id).unwrap();
+            attacker.state = AiState::Attacking;
+            (attacker.position_x, attacker.position_y) = (x + offset, y);
+        }
+        data.entity_mut(0).unwrap().money = 1000.0;
+        handler.step();
+
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap().clone();
+        assert_eq!(entity.state, AiState::Defending);
+        assert!(entity.military_strength > 400.0, "military was {}", entity.military_strength);
+        assert!((entity.money - entity.upkeep_per_sec() * UPKEEP_RESERVE_SECS).abs() < 1.0, "money was {}", entity.money);
+    }
+
+    #[test]
+    fn morale_rises_with_conquests_and_shakes_resolve_when_low() {
+        use crate::constants::{MORALE_CONQUEST_GAIN, MORALE_MIN};
+        use crate::types::terrain::TerrainType;
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 12).unwrap();
+        handler.set_conquest_config(r#"{"max_conquests_per_tick":1}"#).unwrap();
+        let data = handler.logic_mut().data_mut();
+        for i in 0..12 * 12 {
+            let space = data.grid_space_mut(i).unwrap();
+            space.terrain = TerrainType::Plains;
+            space.resource = None;
+        }
+        let entity = data.entity_mut(0).unwrap();
+        assert_eq!(entity.morale, 1.0);
+        entity.military_strength = 200.0;
+        let territory = entity.territory;
+        handler.step();
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.territory, territory + 1);
+        assert_eq!(entity.morale, 1.0 + MORALE_CONQUEST_GAIN);
+        assert_eq!(entity.effective_strength(), entity.military_strength * entity.morale);
+
+        // A shaken AI fortifies against a single attacker a steady one would shrug off
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 12).unwrap();
+        let data = handler.logic_mut().data_mut();
+        let (x, y, capital) = {
+            let entity = data.entity(0).unwrap();
+            (entity.position_x, entity.position_y, entity.capital)
+        };
+        let attacker = data.entity_mut(1).unwrap();
+        attacker.state = AiState::Attacking;
+        (attacker.position_x, attacker.position_y) = (x + 4.0, y);
+        let entity = data.entity_mut(0).unwrap();
+        entity.military_strength = 200.0;
+        entity.morale = MORALE_MIN;
+        handler.step();
+
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap().clone();
+        assert_eq!(entity.state, AiState::Defending);
+        assert_eq!(entity.fortify_target, capital);
+    }
+
+    #[test]
+    fn alliances_prevent_conquest_between_allies() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 4).unwrap();
+        assert!(handler.propose_alliance(0, 1));
+        assert!(!handler.propose_alliance(1, 0), "Alliance already exists");
+        assert!(!handler.propose_alliance(0, 0));
+        assert!(handler.are_allies(1, 0));
+
+        // Give entity 0 everything but entity 1's space
+        let grid_size = handler.get_grid_size();
+        for i in 0..grid_size * grid_size {
+            let space = handler.logic_mut().data_mut().grid_space_mut(i).unwrap();
+            if space.owner_id != Some(1) {
+                space.owner_id = Some(0);
+            }
+        }
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.state = AiState::Attacking;
+        entity.military_strength = 1000.0;
+
+        for _ in 0..10 {
+            handler.step();
+        }
+        let ally = handler.logic_mut().data_mut().entity(1).unwrap();
+        assert_ne!(ally.state, AiState::Dead);
+        assert_eq!(ally.territory, 1);
+    }
+
+    #[test]
+    fn single_entities_can_be_inspected_with_history() {
+        use crate::constants::{ENTITY_HISTORY_LEN, MAX_WATCHED_ENTITIES};
+
+        let mut handler = SimulationHandler::init_with_grid(20, 60, 12).unwrap();
+        assert!(handler.propose_alliance(0, 2));
+        let logic = handler.logic_mut();
+        assert!(logic.entity_details(20).is_none());
+        assert!(logic.entity_history(20).is_none());
+        logic.data_mut().entity_mut(0).unwrap().military_strength = 40.0;
+        logic.raise_army(0, 15.0, 5).unwrap();
+
+        let details = logic.entity_details(0).unwrap();
+        assert_eq!((details.id, details.military_strength), (0, 25.0));
+        assert_eq!(details.allies, vec![2]);
+        assert_eq!(details.armies.len(), 1);
+        assert_eq!(details.money_yield, logic.entities()[0].money_yield);
+
+        // Recording starts with the first request
+        assert_eq!(logic.entity_history(3).unwrap().len(), 1);
+        for _ in 0..5 {
+            handler.step();
+        }
+        let history = handler.logic_mut().entity_history(3).unwrap();
+        let ticks: Vec<u64> = history.iter().map(|stats| stats.tick).collect();
+        assert_eq!(ticks, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(history[5].territory, handler.logic().entities()[3].territory);
+
+        for _ in 0..ENTITY_HISTORY_LEN {
+            handler.step();
+        }
+        let history = handler.logic_mut().entity_history(3).unwrap();
+        assert_eq!(history.len(), ENTITY_HISTORY_LEN);
+        assert_eq!(history.last().unwrap().tick, handler.get_tick());
+
+        // Watching too many entities drops the oldest watch
+        let logic = handler.logic_mut();
+        for id in 4..4 + MAX_WATCHED_ENTITIES as u32 {
+            logic.entity_history(id).unwrap();
+        }
+        assert_eq!(logic.entity_history(3).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn binary_snapshots_round_trip_and_reject_corruption() {
+        use crate::types::binary_snapshot::BINARY_SNAPSHOT_VERSION;
+        use crate::types::{encode_snapshot_v1, AiState, SnapshotField};
+
+        let mut handler = SimulationHandler::init_with_grid(6, 30, 12).unwrap();
+        for _ in 0..3 {
+            handler.step();
+        }
+        let bytes = handler.get_binary_snapshot();
+        assert_eq!(bytes[0], BINARY_SNAPSHOT_VERSION);
+        // Header, 8 field ids, 6 records of 29 bytes and the CRC
+        assert_eq!(bytes.len(), 14 + 8 + 6 * 29 + 4);
+
+        let snapshot = decode_snapshot_v1(&bytes).unwrap();
+        assert_eq!(snapshot.tick, 3);
+        assert_eq!(snapshot.fields, SnapshotField::ALL.to_vec());
+        for (decoded, entity) in snapshot.entities.iter().zip(handler.logic().entities()) {
+            assert_eq!(decoded.id, entity.id);
+            assert_eq!(decoded.military_strength, entity.military_strength);
+            assert_eq!(decoded.money, entity.money);
+            assert_eq!(decoded.territory, entity.territory);
+            assert_eq!(decoded.state, entity.state);
+            assert_eq!((decoded.position_x, decoded.position_y), (entity.position_x, entity.position_y));
+            assert_eq!(decoded.morale, entity.morale);
+        }
+
+        // A subset of fields leaves the rest at their defaults
+        let logic = handler.logic();
+        let entities = logic.entities();
+        let partial = encode_snapshot_v1(3, entities, &[SnapshotField::Id, SnapshotField::Territory]);
+        let decoded = decode_snapshot_v1(&partial).unwrap();
+        assert_eq!(decoded.entities[2].territory, entities[2].territory);
+        assert_eq!(decoded.entities[2].money, 0.0);
+        assert_eq!(decoded.entities[2].morale, 0.0);
+
+        let mut corrupted = bytes.clone();
+        corrupted[20] ^= 0x01;
+        assert!(decode_snapshot_v1(&corrupted).unwrap_err().contains("checksum"));
+        assert!(decode_snapshot_v1(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode_snapshot_v1(&[]).is_err());
+
+        // Well-sealed headers the encoder never writes are refused too
+        let seal = |header: &[u8]| {
+            let mut bytes = header.to_vec();
+            bytes.extend_from_slice(&crate::utils::crc32(header).to_le_bytes());
+            bytes
+        };
+        let header = |entity_count: u32, fields: &[u8]| {
+            let mut bytes = vec![BINARY_SNAPSHOT_VERSION];
+            bytes.extend_from_slice(&3u64.to_le_bytes());
+            bytes.extend_from_slice(&entity_count.to_le_bytes());
+            bytes.push(fields.len() as u8);
+            bytes.extend_from_slice(fields);
+            bytes
+        };
+        assert!(decode_snapshot_v1(&seal(&header(u32::MAX, &[]))).unwrap_err().contains("no fields"));
+        assert!(decode_snapshot_v1(&seal(&header(0, &[0, 0]))).unwrap_err().contains("twice"));
+        let mut dead = header(1, &[SnapshotField::State as u8]);
+        dead.push(AiState::Dead as u8);
+        assert_eq!(decode_snapshot_v1(&seal(&dead)).unwrap().entities[0].state, AiState::Dead);
+        let mut unknown = header(1, &[SnapshotField::State as u8]);
+        unknown.push(9);
+        assert!(decode_snapshot_v1(&seal(&unknown)).unwrap_err().contains("state 9"));
+    }
+
+    #[test]
+    fn typescript_declarations_cover_the_json_payloads() {
+        use crate::decision_scoring::{DecisionLog, DecisionSystem, PruningConfig, ScoreComponents};
+        use crate::types::PublicEntitySnapshot;
+        use tsify::Tsify;
+
+        // Every key a payload serializes with is declared, so the .d.ts can't fall behind the structs
+        let assert_declared = |decl: &str, value: serde_json::Value| {
+            for key in value.as_object().unwrap().keys() {
+                let declared = decl.contains(&format!("\n    {}:", key)) || decl.contains(&format!("\n    {}?:", key));
+                assert!(declared, "{} is missing from {}", key, decl);
+            }
+        };
+
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 8).unwrap();
+        handler.logic_mut().raise_army(0, 1.0, 5).unwrap();
+        handler.step();
+        let snapshot = handler.logic_mut().request_snapshot().unwrap();
+        assert_declared(PublicEntitySnapshot::DECL, serde_json::to_value(&snapshot[0]).unwrap());
+        assert!(PublicEntitySnapshot::DECL.contains("state: number;"));
+        assert!(PublicEntitySnapshot::DECL.contains("armies: Army[];"));
+
+        let logic = handler.logic();
+        let configs = [
+            (FairnessConfig::DECL, serde_json::to_value(logic.fairness_config())),
+            (TerrainConfig::DECL, serde_json::to_value(logic.terrain_config())),
+            (ResourceConfig::DECL, serde_json::to_value(logic.resource_config())),
+            (ConquestConfig::DECL, serde_json::to_value(logic.conquest_config())),
+            (NeutralConfig::DECL, serde_json::to_value(logic.neutral_config())),
+            (UnrestConfig::DECL, serde_json::to_value(logic.unrest_config())),
+            (EventConfig::DECL, serde_json::to_value(logic.event_config())),
+            (CalendarConfig::DECL, serde_json::to_value(logic.calendar_config())),
+            (PruningConfig::DECL, serde_json::to_value(PruningConfig::default())),
+        ];
+        for (decl, value) in configs {
+            assert_declared(decl, value.unwrap());
+        }
+
+        let mut system = DecisionSystem::new();
+        system.add_country(1);
+        system.add_country(2);
+        system.add_edge(1, 2, 1, 0.5);
+        system.tick();
+        let log = system.take_logs().remove(0);
+        assert_declared(ScoreComponents::DECL, serde_json::to_value(&log.components).unwrap());
+        assert_declared(DecisionLog::DECL, serde_json::to_value(log).unwrap());
+    }
+
+    #[test]
+    fn stats_history_tracks_world_aggregates_per_tick() {
+        use crate::constants::STATS_HISTORY_LEN;
+        use crate::types::{AiEntity, AiState, TickStats};
+
+        let mut handler = SimulationHandler::init_with_grid(8, 60, 12).unwrap();
+        assert!(handler.logic().stats_history().is_empty());
+        for _ in 0..4 {
+            handler.step();
+        }
+        let logic = handler.logic();
+        let history = logic.stats_history();
+        let ticks: Vec<u64> = history.iter().map(|stats| stats.tick).collect();
+        assert_eq!(ticks, vec![1, 2, 3, 4]);
+        let latest = history.back().unwrap();
+        assert_eq!(latest.alive, 8);
+        assert_eq!(latest.territory.len(), 8);
+        assert_eq!(latest.territory[5], logic.entities()[5].territory);
+        let max = logic.entities().iter().map(|entity| entity.military_strength).fold(0.0, f32::max);
+        assert_eq!(latest.max_military_strength, max);
+
+        // One entity holding everything among four is a Gini of 0.75; the dead are left out
+        let mut entities: Vec<AiEntity> = (0..5).map(AiEntity::new).collect();
+        for (entity, territory) in entities.iter_mut().zip([0, 0, 0, 10, 0]) {
+            entity.territory = territory;
+        }
+        entities[4].state = AiState::Dead;
+        let stats = TickStats::compute(0, &entities);
+        assert_eq!(stats.alive, 4);
+        assert!((stats.territory_gini - 0.75).abs() < 1e-6);
+        for entity in &mut entities {
+            entity.territory = 3;
+        }
+        assert_eq!(TickStats::compute(0, &entities).territory_gini, 0.0);
+
+        drop(logic);
+        for _ in 0..STATS_HISTORY_LEN {
+            handler.step();
+        }
+        assert_eq!(handler.logic().stats_history().len(), STATS_HISTORY_LEN);
+        assert_eq!(handler.logic().stats_history().back().unwrap().tick, handler.get_tick());
+    }
+
+    #[test]
+    fn territory_timelines_cover_the_whole_run() {
+        use crate::constants::{TIMELINE_CAPACITY, TIMELINE_SAMPLE_INTERVAL};
+        use crate::types::timeline::{TerritoryTimeline, TimelinePoint};
+
+        let mut handler = SimulationHandler::init_with_grid(4, 60, 12).unwrap();
+        for _ in 0..3 * TIMELINE_SAMPLE_INTERVAL + 5 {
+            handler.step();
+        }
+        let logic = handler.logic();
+        let timeline = logic.territory_timeline(2).unwrap();
+        let ticks: Vec<u64> = timeline.points.iter().map(|point| point.tick).collect();
+        assert_eq!(ticks, [1, 2, 3].map(|n| n * TIMELINE_SAMPLE_INTERVAL));
+        let history = logic.stats_history();
+        let sampled = history.iter().find(|stats| stats.tick == 3 * TIMELINE_SAMPLE_INTERVAL).unwrap();
+        assert_eq!(timeline.points[2].territory, sampled.territory[2]);
+        assert!(logic.territory_timeline(4).is_none());
+        let all = logic.all_timelines();
+        assert_eq!(all.iter().map(|timeline| timeline.entity_id).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!(all[2], timeline);
+        drop(logic);
+
+        handler.reset();
+        assert!(handler.logic().all_timelines().is_empty());
+
+        // A full buffer keeps every other sample and samples half as often
+        let entities = handler.logic().entities().to_vec();
+        let mut timeline = TerritoryTimeline::new();
+        let last = (TIMELINE_CAPACITY as u64 + 1) * TIMELINE_SAMPLE_INTERVAL;
+        for tick in 1..=last {
+            timeline.record(tick, &entities);
+        }
+        let points = timeline.entity(0).unwrap().points;
+        assert_eq!(points.len(), TIMELINE_CAPACITY / 2);
+        assert_eq!(points[0], TimelinePoint { tick: 2 * TIMELINE_SAMPLE_INTERVAL, territory: entities[0].territory });
+        assert_eq!(points.last().unwrap().tick, last - TIMELINE_SAMPLE_INTERVAL);
+        assert!(points.windows(2).all(|pair| pair[1].tick - pair[0].tick == 2 * TIMELINE_SAMPLE_INTERVAL));
+    }
+
+    #[test]
+    fn notable_events_pick_out_first_blood_comebacks_and_quick_expansions() {
+        use crate::data::NotableDetector;
+        use crate::types::{AiEntity, NotableEvent, PlayerCommand, SimulationEvent};
+
+        let map = r#"{"grid_size":3,"entities":[{"capital":0,"military_strength":500},{"capital":8}],
+            "cells":[{"grid_index":1,"owner_id":1,"defense_strength":2}]}"#;
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 3).unwrap();
+        handler.load_map(map).unwrap();
+        handler
+            .logic_mut()
+            .queue_command(0, PlayerCommand::Attack { grid_index: 1 })
+            .unwrap();
+        handler.step();
+        let first_blood = NotableEvent::FirstBlood {
+            tick: 1,
+            grid_index: 1,
+            attacker_id: 0,
+            defender_id: 1,
+        };
+        assert_eq!(handler.logic().notable_events(), [first_blood]);
+        handler.reset();
+        assert!(handler.logic().notable_events().is_empty());
+
+        // Entity 0 takes a space a tick, then entity 1 takes ten at once
+        let conquest = |tick, attacker_id, previous_owner| SimulationEvent::Conquest {
+            tick,
+            grid_index: 0,
+            attacker_id,
+            previous_owner,
+        };
+        let mut detector = NotableDetector::new();
+        for tick in 1..=11 {
+            detector.record_event(&conquest(tick, 0, None));
+        }
+        for _ in 0..10 {
+            detector.record_event(&conquest(12, 1, Some(0)));
+        }
+        let expansion = |tick, entity_id, ticks| NotableEvent::FastestExpansion { tick, entity_id, ticks };
+        let first_blood = NotableEvent::FirstBlood {
+            tick: 12,
+            grid_index: 0,
+            attacker_id: 1,
+            defender_id: 0,
+        };
+        assert_eq!(detector.notable_events(12), [expansion(10, 0, 9), first_blood, expansion(12, 1, 0)]);
+
+        // Entity 0 holds a fifth of the land, falls to nothing, then recovers
+        let mut entities: Vec<AiEntity> = (0..2).map(AiEntity::new).collect();
+        entities[1].territory = 40;
+        let mut detector = NotableDetector::new();
+        for (tick, territory) in [(1, 10), (2, 1), (3, 0), (4, 2)] {
+            entities[0].territory = territory;
+            detector.end_tick(tick, &entities);
+        }
+        assert!(detector.notable_events(4).is_empty());
+        entities[0].territory = 8;
+        detector.end_tick(5, &entities);
+        let comeback = NotableEvent::Comeback {
+            tick: 5,
+            entity_id: 0,
+            low_tick: 3,
+            low_share: 0.0,
+        };
+        assert_eq!(detector.notable_events(5), [comeback]);
+        assert!(detector.notable_events(4).is_empty(), "Rewound past the last tick watched");
+    }
+
+    #[test]
+    fn leaderboard_ranks_alive_entities_by_the_chosen_key() {
+        use crate::types::{AiEntity, AiState, LeaderboardKey};
+
+        let mut handler = SimulationHandler::init_with_grid(10, 60, 12).unwrap();
+        for _ in 0..20 {
+            handler.step();
+        }
+        let expected = |logic: &SimulationLogic, key: LeaderboardKey| {
+            let mut alive: Vec<&AiEntity> = logic.entities().iter().filter(|e| e.state != AiState::Dead).collect();
+            alive.sort_by(|a, b| key.compare(a, b));
+            alive.iter().map(|entity| entity.id).collect::<Vec<u32>>()
+        };
+        let ids = |logic: &SimulationLogic, n| logic.leaderboard(n).iter().map(|entry| entry.id).collect::<Vec<u32>>();
+
+        let logic = handler.logic_mut();
+        assert_eq!(ids(logic, 3), expected(logic, LeaderboardKey::Territory)[..3]);
+        let top = logic.leaderboard(1)[0];
+        assert_eq!(top.territory, logic.entities()[top.id as usize].territory);
+
+        assert!("gold".parse::<LeaderboardKey>().is_err());
+        logic.set_leaderboard_key("money".parse().unwrap());
+        assert_eq!(ids(logic, 100), expected(logic, LeaderboardKey::Money));
+
+        // Removed entities drop off and spawned ones are ranked right away
+        let leader = logic.leaderboard(1)[0].id;
+        logic.remove_entity(leader).unwrap();
+        let spawned = logic.spawn_entity(0.0, 0.0, 30.0).unwrap();
+        logic.data_mut().entity_mut(spawned as usize).unwrap().money = 1.0e6;
+        logic.set_leaderboard_key(LeaderboardKey::Money);
+        assert_eq!(logic.leaderboard(1)[0].id, spawned);
+        assert!(!ids(logic, 100).contains(&leader));
+        assert_eq!(ids(logic, 100), expected(logic, LeaderboardKey::Money));
+    }
+
+    #[test]
+    fn death_and_conquest_callbacks_fire_once_per_event() {
+        use crate::types::{AiState, SimulationEvent};
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut handler = SimulationHandler::init_with_grid(10, 60, 12).unwrap();
+        let deaths = Rc::new(RefCell::new(Vec::new()));
+        let conquests = Rc::new(RefCell::new(0));
+        let logic = handler.logic_mut();
+        let seen = Rc::clone(&deaths);
+        logic.set_on_entity_death(Some(Box::new(move |event| {
+            if let SimulationEvent::Death { entity_id, .. } = *event {
+                seen.borrow_mut().push(entity_id);
+            }
+        })));
+        let seen = Rc::clone(&conquests);
+        logic.set_on_conquest(Some(Box::new(move |event| {
+            assert!(matches!(event, SimulationEvent::Conquest { .. }));
+            *seen.borrow_mut() += 1;
+        })));
+
+        logic.remove_entity(4).unwrap();
+        assert_eq!(*deaths.borrow(), vec![4]);
+        for _ in 0..200 {
+            handler.step();
+        }
+        let logged = |kind: fn(&SimulationEvent) -> bool| {
+            handler.logic().events().filter(|event| kind(event)).count()
+        };
+        // The log is bounded, so it can only hold fewer events than were dispatched
+        assert!(*conquests.borrow() > 0);
+        assert!(*conquests.borrow() >= logged(|event| matches!(event, SimulationEvent::Conquest { .. })));
+        let dead = handler.logic().entities().iter().filter(|e| e.state == AiState::Dead).count();
+        assert_eq!(deaths.borrow().len(), dead);
+
+        // Clearing a callback stops it
+        handler.logic_mut().set_on_conquest(None);
+        let before = *conquests.borrow();
+        for _ in 0..20 {
+            handler.step();
+        }
+        assert_eq!(*conquests.borrow(), before);
+    }
+
+    #[test]
+    fn viewport_snapshots_only_cover_the_visible_rect() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::init_with_grid(40, 60, 12).unwrap();
+        for _ in 0..3 {
+            handler.step();
+        }
+        let logic = handler.logic_mut();
+        // Grid spaces are 200 units wide in the default 2400 unit world
+        let viewport = logic.snapshot_in_rect(-1.0, -1.0, -1200.0, -1200.0);
+        assert_eq!(viewport.grid_cells.len(), 36);
+        assert!(viewport.grid_cells.iter().all(|cell| (cell.index % 12) < 6 && (cell.index / 12) < 6));
+
+        let expected: Vec<u32> = logic
+            .entities()
+            .iter()
+            .filter(|entity| entity.state != AiState::Dead)
+            .filter(|entity| entity.position_x <= -1.0 && entity.position_y <= -1.0)
+            .map(|entity| entity.id)
+            .collect();
+        let ids: Vec<u32> = viewport.entities.iter().map(|entity| entity.id).collect();
+        assert!(!ids.is_empty());
+        assert_eq!(ids, expected);
+
+        assert_eq!(logic.snapshot_in_rect(-1200.0, -1200.0, 1200.0, 1200.0).entities.len(), 40);
+        let outside = logic.snapshot_in_rect(5000.0, 5000.0, 6000.0, 6000.0);
+        assert!(outside.entities.is_empty() && outside.grid_cells.is_empty());
+        assert!(logic.snapshot_in_rect(f32::NAN, 0.0, 1.0, 1.0).grid_cells.is_empty());
+    }
+
+    #[test]
+    fn snapshot_fields_can_be_narrowed_to_what_the_ui_needs() {
+        use crate::types::{SnapshotField, SNAPSHOT_FIELD_COUNT};
+
+        let mut handler = SimulationHandler::init_with_grid(5, 30, 12).unwrap();
+        handler.step();
+        let logic = handler.logic_mut();
+        assert_eq!(logic.snapshot_schema().stride, SNAPSHOT_FIELD_COUNT);
+        assert!(logic.set_snapshot_fields(0).is_err());
+        assert!(logic.set_snapshot_fields(1 << SNAPSHOT_FIELD_COUNT).is_err());
+
+        let mask = SnapshotField::State.bit() | SnapshotField::PositionX.bit() | SnapshotField::PositionY.bit();
+        logic.set_snapshot_fields(mask).unwrap();
+        let schema = logic.snapshot_schema();
+        assert_eq!((schema.mask, schema.stride), (mask, 3));
+        let offsets: Vec<(SnapshotField, usize)> = schema.fields.iter().map(|layout| (layout.field, layout.offset)).collect();
+        assert_eq!(
+            offsets,
+            vec![(SnapshotField::State, 0), (SnapshotField::PositionX, 1), (SnapshotField::PositionY, 2)]
+        );
+
+        let data = logic.data_mut();
+        data.ensure_flat_snapshot_ready();
+        let flat = data.flat_snapshot_slice().to_vec();
+        assert_eq!(flat.len(), 5 * 3);
+        let entity = &data.entities()[4];
+        assert_eq!(&flat[12..], &[u32::from(entity.state) as f32, entity.position_x, entity.position_y]);
+
+        let decoded = decode_snapshot_v1(&logic.request_binary_snapshot()).unwrap();
+        assert_eq!(decoded.fields.len(), 3);
+        assert_eq!(decoded.entities[4].position_x, logic.entities()[4].position_x);
+    }
+
+    #[test]
+    fn entities_spawn_and_leave_mid_simulation() {
+        use crate::types::{AiState, SimulationEvent};
+
+        let config = WorldConfig::new(240.0, 2.0, 6);
+        let mut handler = SimulationHandler::init_with_config(3, 60, config).unwrap();
+        for _ in 0..20 {
+            handler.step();
+        }
+        let territory = |handler: &mut SimulationHandler, id: usize| {
+            handler.logic_mut().data_mut().entity(id).unwrap().territory
+        };
+        let before = territory(&mut handler, 0);
+        let (x, y) = {
+            let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+            (entity.position_x, entity.position_y)
+        };
+
+        // The newcomer takes the space it lands on
+        assert!(handler.logic_mut().spawn_entity(x, y, -1.0).is_err());
+        assert!(handler.logic_mut().spawn_entity(1000.0, 0.0, 5.0).is_err());
+        assert_eq!(handler.logic_mut().spawn_entity(x, y, 25.0).unwrap(), 3);
+        assert_eq!(handler.get_tick(), 20);
+        assert_eq!(handler.get_entity_count(), 4);
+        assert_eq!(territory(&mut handler, 0), before - 1);
+        assert_eq!(territory(&mut handler, 3), 1);
+        let snapshot = handler.logic_mut().request_snapshot().unwrap();
+        assert_eq!(snapshot.len(), 4);
+        assert_eq!(snapshot[3].military_strength, 25.0);
+        assert_eq!(handler.logic_mut().entities_near(x, y, 1.0), vec![0, 3]);
+
+        // A removed entity gives up its land and armies but keeps its id
+        handler.logic_mut().data_mut().entity_mut(1).unwrap().military_strength = 50.0;
+        handler.logic_mut().raise_army(1, 20.0, 0).unwrap();
+        handler.logic_mut().remove_entity(1).unwrap();
+        assert!(handler.logic_mut().remove_entity(1).is_err());
+        assert!(handler.logic_mut().remove_entity(9).is_err());
+        let data = handler.logic_mut().data_mut();
+        assert_eq!(data.entity(1).unwrap().state, AiState::Dead);
+        assert!(data.grid_spaces().iter().all(|space| space.owner_id != Some(1)));
+        assert!(handler.logic().armies().is_empty());
+        let bundle = handler.logic().sync_bundle();
+        assert!(matches!(
+            bundle.recent_events.last(),
+            Some(SimulationEvent::Death { entity_id: 1, .. })
+        ));
+
+        for _ in 0..5 {
+            handler.step();
+        }
+        let snapshot = handler.logic_mut().request_snapshot().unwrap();
+        assert_eq!(snapshot.len(), 4);
+        assert_ne!(snapshot[3].state, AiState::Dead);
+    }
+
+    #[test]
+    fn teams_hold_together_until_one_team_is_left() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::init_with_teams(&[2, 1, 2], 60).unwrap();
+        handler.set_grid_size(6).unwrap();
+        assert_eq!(handler.get_team_sizes(), vec![2, 1, 2]);
+        let teams: Vec<Option<u32>> = handler.logic().entities().iter().map(|e| e.team_id).collect();
+        assert_eq!(teams, vec![Some(0), Some(0), Some(1), Some(2), Some(2)]);
+        assert!(handler.are_allies(0, 1) && handler.are_allies(3, 4));
+        assert!(!handler.are_allies(1, 2) && !handler.are_allies(2, 3));
+
+        // A well-armed teammate leaves its partner's space alone
+        for i in 0..6 * 6 {
+            let space = handler.logic_mut().data_mut().grid_space_mut(i).unwrap();
+            if space.owner_id != Some(1) {
+                space.owner_id = Some(0);
+            }
+        }
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.state = AiState::Attacking;
+        entity.military_strength = 1000.0;
+        for _ in 0..10 {
+            handler.step();
+        }
+        let teammate = handler.logic_mut().data_mut().entity(1).unwrap();
+        assert_ne!(teammate.state, AiState::Dead);
+        assert_eq!(teammate.territory, 1);
+
+        // The other teams lost their land, leaving two teammates standing
+        assert_eq!(handler.logic().count_alive(), 2);
+        assert!(handler.logic().is_complete());
+
+        // Teams survive a reset and a late join, and end with a new entity count
+        handler.reset();
+        assert_eq!(handler.logic().entities()[4].team_id, Some(2));
+        assert!(!handler.logic().is_complete());
+        let bundle = handler.logic().sync_bundle();
+        let mut joiner = SimulationHandler::new(1).unwrap();
+        joiner.logic_mut().apply_sync_bundle(&bundle).unwrap();
+        assert_eq!(joiner.get_team_sizes(), vec![2, 1, 2]);
+        assert!(joiner.are_allies(3, 4));
+        handler.set_entity_count(3).unwrap();
+        assert!(handler.get_team_sizes().is_empty());
+        assert!(handler.logic().entities().iter().all(|e| e.team_id.is_none()));
+    }
+
+    #[test]
+    fn allied_entities_reinforce_defending_allies() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::new(2).unwrap();
+        assert!(handler.propose_alliance(0, 1));
+
+        let (ally_x, ally_y) = {
+            let ally = handler.logic_mut().data_mut().entity_mut(1).unwrap();
+            ally.state = AiState::Defending;
+            (ally.position_x, ally.position_y)
+        };
+        let supporter = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        supporter.position_x = ally_x + 3.0;
+        supporter.position_y = ally_y;
+
+        let ally_cell = handler
+            .logic_mut()
+            .data_mut()
+            .position_to_grid_index(ally_x, ally_y)
+            .unwrap();
+        let before = handler.logic_mut().data_mut().grid_spaces()[ally_cell].defense_strength;
+
+        handler.step();
+
+        let supporter = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(supporter.state, AiState::Allied);
+        assert_eq!(supporter.support_target, Some(1));
+        let after = handler.logic_mut().data_mut().grid_spaces()[ally_cell].defense_strength;
+        assert!(after > before, "Supporter should add defense to the ally's space");
+    }
+
+    #[test]
+    fn alliances_appear_in_snapshots_and_end_on_death() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::new(3).unwrap();
+        assert!(handler.propose_alliance(0, 2));
+
+        let snapshot = handler.logic_mut().request_snapshot().unwrap();
+        assert_eq!(snapshot[0].allies, vec![2]);
+        assert_eq!(snapshot[2].allies, vec![0]);
+        assert!(snapshot[1].allies.is_empty());
+
+        let entity = handler.logic_mut().data_mut().entity_mut(2).unwrap();
+        entity.state = AiState::Dead;
+        assert!(!handler.propose_alliance(1, 2), "Dead entities can't ally");
+
+        handler.logic_mut().data_mut().entity_mut(2).unwrap().state = AiState::Idle;
+        for i in 0..50 * 50 {
+            let space = handler.logic_mut().data_mut().grid_space_mut(i).unwrap();
+            if space.owner_id == Some(2) {
+                space.owner_id = None;
+            }
+        }
+        handler.logic_mut().data_mut().entity_mut(2).unwrap().territory = 0;
+        handler.step();
+        assert!(!handler.are_allies(0, 2));
+    }
+
+    #[test]
+    fn decision_scoring_drives_expansion_and_attacks() {
+        use crate::types::AiState;
+
+        // Entity 1 keeps expanding too, so entity 0 later faces an active attacker
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 6).unwrap();
+        handler.set_behavior(1, "aggressive").unwrap();
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.military_strength = 200.0;
+        handler.step();
+
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.state, AiState::Attacking);
+        assert_eq!(entity.attack_target, None, "Only unowned land borders entity 0");
+        assert!(entity.territory > 1, "Scored expansion should claim land");
+
+        // Once the two territories touch, a well-stocked neighbor becomes the target
+        for i in 0..6 * 6 {
+            let space = handler.logic_mut().data_mut().grid_space_mut(i).unwrap();
+            if space.owner_id != Some(1) {
+                space.owner_id = Some(0);
+                space.defense_strength = 0.0;
+            }
+        }
+        handler.logic_mut().data_mut().update_territories();
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.military_strength = 60.0;
+        let neighbor = handler.logic_mut().data_mut().entity_mut(1).unwrap();
+        neighbor.money = 100.0;
+        neighbor.military_strength = 20.0;
+        handler.step();
+
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.state, AiState::Attacking);
+        assert_eq!(entity.attack_target, Some(1));
+    }
+
+    #[test]
+    fn pathfinder_skirts_fortified_spaces_to_the_weakest_frontier() {
+        use crate::data::Pathfinder;
+        use crate::types::{AllianceGraph, GridSpace, TerrainConfig};
+
+        // Entity 0 holds the left column, entity 1 the middle one, and the
+        // right column is unowned
+        let mut grid = vec![GridSpace::new(); 9];
+        for index in [0, 3, 6] {
+            grid[index] = GridSpace::with_owner(0, 0.0);
+        }
+        grid[1] = GridSpace::with_owner(1, 50.0);
+        grid[4] = GridSpace::with_owner(1, 50.0);
+        grid[7] = GridSpace::with_owner(1, 0.0);
+        let alliances = AllianceGraph::default();
+        let terrain = TerrainConfig::default();
+
+        let conquest_cost = |space: &GridSpace| space.conquest_cost(&terrain);
+        assert_eq!(
+            Pathfinder::cheapest_frontier(&grid, 3, 0, Some(1), 0, conquest_cost),
+            Some((7, 6))
+        );
+        assert_eq!(Pathfinder::cheapest_frontier(&grid, 3, 0, None, 0, conquest_cost), None);
+        assert_eq!(Pathfinder::staging_space(&grid, 3, 0, 4, 0), Some(3));
+
+        let mut pathfinder = Pathfinder::new();
+        let path = pathfinder.find_path(&grid, 3, 0, 2, 0, &alliances, &terrain).unwrap();
+        assert_eq!(path, &[3, 6, 7, 8, 5, 2], "Route through the undefended space");
+        assert_eq!(pathfinder.find_path(&grid, 3, 4, 4, 0, &alliances, &terrain), Some(&[][..]));
+        assert_eq!(pathfinder.find_path(&grid, 3, 0, 9, 0, &alliances, &terrain), None);
+    }
+
+    #[test]
+    fn attackers_walk_to_the_front_before_conquering() {
+        use crate::types::{GridSpace, PlayerCommand};
+
+        let config = WorldConfig::new(240.0, 2.0, 6);
+        let mut handler = SimulationHandler::init_with_config(2, 60, config).unwrap();
+        // Keep the first placement, which starts entity 0 in the corner
+        handler.set_fairness_config(r#"{"max_attempts":1}"#).unwrap();
+        let data = handler.logic_mut().data_mut();
+        assert_eq!(data.grid_spaces()[0].owner_id, Some(0));
+        for index in 1..5 {
+            *data.grid_space_mut(index).unwrap() = GridSpace::with_owner(0, 5.0);
+        }
+        data.update_territories();
+        data.entity_mut(0).unwrap().military_strength = 200.0;
+
+        // The far end of the strip is four spaces from where entity 0 stands
+        handler
+            .logic_mut()
+            .queue_command(0, PlayerCommand::Attack { grid_index: 5 })
+            .unwrap();
+        handler.step();
+        let data = handler.logic_mut().data_mut();
+        assert_eq!(data.grid_spaces()[5].owner_id, None, "Too far away to strike yet");
+        let (start_x, _) = data.grid_index_to_position(0);
+        assert!(data.entity(0).unwrap().position_x > start_x);
+
+        for _ in 0..30 {
+            handler.step();
+        }
+        let data = handler.logic_mut().data_mut();
+        assert_eq!(data.grid_spaces()[5].owner_id, Some(0));
+        let entity = data.entity(0).unwrap();
+        assert_eq!(
+            data.position_to_grid_index(entity.position_x, entity.position_y),
+            Some(4),
+            "Struck from the owned space next to the target"
+        );
+    }
+
+    #[test]
+    fn contested_spaces_go_to_the_strongest_claimant_whatever_the_order() {
+        use crate::types::{GridSpace, PlayerCommand};
+
+        // Entities 0 and 1 both strike the empty middle of a 3x3 grid, from its
+        // left (space 3) and right (space 5), or the other way round when mirrored
+        let contest = |strengths: [f32; 2], mirrored: bool, tick: u64| {
+            let config = WorldConfig::new(240.0, 2.0, 3);
+            let mut handler = SimulationHandler::init_with_config(2, 60, config).unwrap();
+            handler.set_fixed_timestep(16.0);
+            let logic = handler.logic_mut();
+            let data = logic.data_mut();
+            for _ in 0..tick {
+                data.increment_tick();
+            }
+            for index in 0..9 {
+                *data.grid_space_mut(index).unwrap() = GridSpace::default();
+            }
+            for (id, strength) in strengths.into_iter().enumerate() {
+                let home = if (id == 0) != mirrored { 3 } else { 5 };
+                *data.grid_space_mut(home).unwrap() = GridSpace::with_owner(id as u32, 5.0);
+                let (x, y) = data.grid_index_to_position(home);
+                let entity = data.entity_mut(id).unwrap();
+                entity.position_x = x;
+                entity.position_y = y;
+                entity.military_strength = strength;
+            }
+            data.update_territories();
+            for id in 0..2 {
+                logic.queue_command(id, PlayerCommand::Attack { grid_index: 4 }).unwrap();
+            }
+            handler.step();
+            handler.logic_mut().data_mut().grid_spaces()[4].owner_id
+        };
+
+        for mirrored in [false, true] {
+            assert_eq!(contest([90.0, 60.0], mirrored, 0), Some(0));
+            assert_eq!(contest([60.0, 90.0], mirrored, 0), Some(1));
+        }
+        // Even claims fall either way from tick to tick, but never on position
+        let winners: Vec<_> = (0..16).map(|tick| contest([80.0, 80.0], false, tick)).collect();
+        let mirrored: Vec<_> = (0..16).map(|tick| contest([80.0, 80.0], true, tick)).collect();
+        assert_eq!(winners, mirrored);
+        assert!(winners.contains(&Some(0)) && winners.contains(&Some(1)), "{:?}", winners);
+    }
+
+    #[test]
+    fn scheduled_commands_keep_lockstep_peers_in_sync() {
+        use crate::types::PlayerCommand;
+
+        let hold = r#"{"kind":"Hold"}"#;
+        let defend = r#"{"kind":"Defend"}"#;
+        let mut a = SimulationHandler::init_with_grid(3, 60, 8).unwrap();
+        let mut b = SimulationHandler::init_with_grid(3, 60, 8).unwrap();
+        a.set_fixed_timestep(16.0);
+        b.set_fixed_timestep(16.0);
+        // Same commands, arriving in a different order on each peer
+        a.schedule_command(2, 0, hold).unwrap();
+        a.schedule_command(2, 1, defend).unwrap();
+        b.schedule_command(2, 1, defend).unwrap();
+        b.schedule_command(2, 0, hold).unwrap();
+        assert_eq!(a.get_scheduled_input_hash(2), b.get_scheduled_input_hash(2));
+        assert_ne!(a.get_scheduled_input_hash(2), a.get_scheduled_input_hash(3));
+
+        a.step();
+        assert_eq!(a.logic().entities()[0].command, None);  // Not due yet
+        a.step();
+        b.step_many(2, 1.0e6);
+        assert_eq!(a.logic().entities()[0].command, Some(PlayerCommand::Hold));
+        assert_eq!(a.get_input_hash(), b.get_input_hash());
+        assert_eq!(a.get_state_hash(), b.get_state_hash());
+
+        // A peer that missed a command shows up in the input hash before the state drifts
+        a.schedule_command(4, 2, hold).unwrap();
+        a.step_many(2, 1.0e6);
+        b.step_many(2, 1.0e6);
+        assert_ne!(a.get_input_hash(), b.get_input_hash());
+
+        assert!(matches!(a.schedule_command(4, 0, hold), Err(SimError::InvalidInput(_))));
+        assert!(matches!(a.schedule_command(9, 7, hold), Err(SimError::InvalidInput(_))));
+        assert!(matches!(a.schedule_command(9, 0, "{}"), Err(SimError::MalformedInput(_))));
+        a.reset();
+        assert_eq!(a.get_input_hash(), SimulationHandler::new(1).unwrap().get_input_hash());
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    #[test]
+    fn steady_state_ticks_do_not_allocate() {
+        use crate::constants::STATS_HISTORY_LEN;
+
+        let mut handler = SimulationHandler::init_with_grid(40, 60, 20).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.start();
+        // Every entity is left to the AI, so decision scoring runs each tick.
+        // Fill the stats history and let every buffer reach its working size
+        for _ in 0..=STATS_HISTORY_LEN {
+            handler.step();
+            handler.logic_mut().request_flat_snapshot();
+        }
+        assert!(handler.logic_mut().count_alive() > 1, "no one left to score");
+
+        let allocations = allocation_counter::allocations_during(|| {
+            for _ in 0..20 {
+                handler.step();
+                handler.logic_mut().request_flat_snapshot();
+            }
+        });
+        assert_eq!(allocations, 0);
+    }
+
+    #[test]
+    fn entity_updates_do_not_depend_on_index_order() {
+        use crate::data::{AiStateUpdater, DecisionAdapter, GridUpdateBuilder};
+
+        let mut handler = SimulationHandler::init_with_grid(30, 60, 12).unwrap();
+        handler.set_fixed_timestep(16.0);
+        for _ in 0..20 {
+            handler.step();
+        }
+        let logic = handler.logic_mut();
+        let world = logic.world_config();
+        let data = logic.data_mut();
+        data.rebuild_snapshot_buffer();
+        let mut grid = GridUpdateBuilder::for_world(&world);
+        grid.rebuild(data.snapshots());
+        let mut updater = AiStateUpdater::new();
+        updater.update_time(2_000.0);
+        let mut adapter = DecisionAdapter::new();
+        let behaviors = adapter
+            .decide(data.entities(), data.grid_spaces(), data.grid_size(), data.alliances(), &data.terrain_config())
+            .to_vec();
+
+        let (snapshots, alliances, relations, entities) = data.entity_update_buffers();
+        let update_in = |order: &mut dyn Iterator<Item = usize>| {
+            let mut next = entities.to_vec();
+            for i in order {
+                updater.update_entity(&mut next[i], 21, i, snapshots, &grid, alliances, relations, behaviors[i]);
+            }
+            format!("{:?}", next)
+        };
+        assert_eq!(update_in(&mut (0..30)), update_in(&mut (0..30).rev()));
+    }
+
+    #[test]
+    fn shrink_to_fit_releases_capacity_after_fewer_entities() {
+        use crate::types::AiEntity;
+
+        let mut handler = SimulationHandler::init_with_grid(200, 60, 20).unwrap();
+        handler.step();
+        let before = handler.logic().memory_stats();
+        assert_eq!(before.entity_count, 200);
+        assert!(before.entity_capacity >= 200);
+        assert!(before.entity_bytes >= 200 * std::mem::size_of::<AiEntity>());
+        assert_eq!(before.grid_cells, 400);
+        assert!(before.snapshot_bytes > 0);
+        assert!(before.total_bytes >= before.entity_bytes + before.grid_bytes + before.snapshot_bytes);
+
+        handler.set_entity_count(10).unwrap();
+        handler.step();
+        assert_eq!(handler.logic().memory_stats().entity_capacity, before.entity_capacity);
+        handler.shrink_to_fit();
+        let after = handler.logic().memory_stats();
+        assert_eq!(after.entity_count, 10);
+        assert_eq!(after.entity_capacity, 10);
+        assert!(after.total_bytes < before.total_bytes);
+
+        // Buffers grow back as needed
+        let tick = handler.get_tick();
+        handler.step();
+        assert_eq!(handler.get_tick(), tick + 1);
+        assert_eq!(handler.logic().memory_stats().entity_count, 10);
+    }
+
+    #[test]
+    fn rollback_resimulates_with_late_commands() {
+        let mut handler = SimulationHandler::init_with_grid(4, 60, 8).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.step_many(3, 1.0e6);
+        let checkpoint = handler.create_checkpoint();
+        let hash_at_checkpoint = handler.get_state_hash();
+        handler.step_many(5, 1.0e6);
+        let later = handler.create_checkpoint();
+        let predicted = handler.get_state_hash();
+        assert_eq!(handler.get_checkpoint_count(), 2);
+
+        // Re-simulating without new input reproduces the prediction
+        handler.rollback_to(checkpoint).unwrap();
+        assert_eq!(handler.get_tick(), 3);
+        assert_eq!(handler.get_state_hash(), hash_at_checkpoint);
+        assert_eq!(handler.logic().checkpoint_count(), 1);
+        assert!(matches!(handler.rollback_to(later), Err(SimError::InvalidInput(_))));
+        handler.step_many(5, 1.0e6);
+        assert_eq!(handler.get_state_hash(), predicted);
+
+        // A late remote command changes the outcome, and the checkpoint is untouched
+        handler.rollback_to(checkpoint).unwrap();
+        handler.schedule_command(4, 0, r#"{"kind":"Hold"}"#).unwrap();
+        handler.step_many(5, 1.0e6);
+        assert_ne!(handler.get_input_hash(), SimulationHandler::new(1).unwrap().get_input_hash());
+        assert_eq!(handler.logic().entities()[0].command, Some(crate::types::PlayerCommand::Hold));
+        handler.rollback_to(checkpoint).unwrap();
+        assert_eq!(handler.get_state_hash(), hash_at_checkpoint);
+        let empty = SimulationHandler::new(1).unwrap();
+        assert_eq!(handler.get_scheduled_input_hash(4), empty.get_scheduled_input_hash(4));
+    }
+
+    #[test]
+    fn sim_time_rollback_resimulates_the_same_state() {
+        use crate::types::ResourceClock;
+
+        let start = || {
+            let mut handler = SimulationHandler::init_with_grid(4, 60, 8).unwrap();
+            handler.set_resource_clock(ResourceClock::SimTime);
+            handler
+        };
+        let mut uninterrupted = start();
+        for _ in 0..8 {
+            uninterrupted.step();
+        }
+
+        let mut handler = start();
+        for _ in 0..3 {
+            handler.step();
+        }
+        let checkpoint = handler.create_checkpoint();
+        for _ in 0..5 {
+            handler.step();
+        }
+        handler.rollback_to(checkpoint).unwrap();
+        for _ in 0..5 {
+            handler.step();
+        }
+        assert_eq!(handler.get_state_hash(), uninterrupted.get_state_hash());
+
+        // A late joiner takes over the host's clock instead of starting its own at 0
+        let mut joiner = SimulationHandler::new(2).unwrap();
+        joiner.set_resource_clock(ResourceClock::SimTime);
+        joiner.logic_mut().apply_sync_bundle(&handler.logic().sync_bundle()).unwrap();
+        for _ in 0..5 {
+            handler.step();
+            joiner.step();
+            assert_eq!(joiner.get_state_hash(), handler.get_state_hash());
+        }
+    }
+
+    #[test]
+    fn command_bytes_round_trip_and_apply_in_order() {
+        use crate::protocol::PROTOCOL_VERSION;
+        use crate::types::PlayerCommand;
+
+        let json = r#"[
+            {"kind":"SpawnEntity","x":1.5,"y":2.0,"strength":40},
+            {"kind":"QueueOrder","entity_id":0,"order":{"kind":"MoveTo","grid_index":5}},
+            {"kind":"ScheduleOrder","tick":3,"entity_id":1,"order":{"kind":"Hold"}},
+            {"kind":"ProposeAlliance","a":0,"b":1},
+            {"kind":"SetTickRate","tick_rate":30},
+            {"kind":"SetTimeScale","time_scale":2},
+            {"kind":"Resume"}
+        ]"#;
+        let bytes = SimulationHandler::encode_command_json(json).unwrap();
+        assert_eq!(bytes[0], PROTOCOL_VERSION);
+        let decoded = decode_commands(&bytes).unwrap();
+        assert_eq!(decoded.len(), 7);
+        assert_eq!(decoded, serde_json::from_str::<Vec<Command>>(json).unwrap());
+        assert_eq!(encode_commands(&decoded), bytes);
+
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 8).unwrap();
+        assert_eq!(handler.apply_command_bytes(&bytes).unwrap(), 7);
+        assert_eq!(handler.get_entity_count(), 4);
+        assert_eq!(handler.logic().entities()[3].military_strength, 40.0);
+        assert_eq!(handler.logic().entities()[0].command, None);
+        assert!(handler.are_allies(0, 1));
+        assert_eq!(handler.get_tick_rate(), 30);
+        assert_eq!(handler.get_time_scale(), 2.0);
+        assert!(handler.is_running());
+        handler.step();
+        assert_eq!(handler.logic().entities()[0].command, Some(PlayerCommand::MoveTo { grid_index: 5 }));
+
+        // Corrupt messages apply nothing
+        let truncated = &bytes[..bytes.len() - 3];  // Cuts into SetTimeScale
+        assert!(matches!(handler.apply_command_bytes(truncated), Err(SimError::MalformedInput(_))));
+        let mut wrong_version = bytes.clone();
+        wrong_version[0] = PROTOCOL_VERSION + 1;
+        assert!(matches!(handler.apply_command_bytes(&wrong_version), Err(SimError::MalformedInput(_))));
+        assert!(matches!(handler.apply_command_bytes(&[PROTOCOL_VERSION, 99]), Err(SimError::MalformedInput(_))));
+        let nan = encode_commands(&[Command::SetTimeScale { time_scale: f32::NAN }]);
+        assert!(matches!(handler.apply_command_bytes(&nan), Err(SimError::MalformedInput(_))));
+        let mut hold = encode_commands(&[Command::QueueOrder { entity_id: 0, order: PlayerCommand::Hold }]);
+        hold[7] = 1;  // A target on an order that takes none
+        assert!(matches!(handler.apply_command_bytes(&hold), Err(SimError::MalformedInput(_))));
+        assert_eq!(handler.get_entity_count(), 4);
+
+        // A refused command stops the rest, leaving earlier ones applied
+        let bytes = encode_commands(&[Command::Pause, Command::SetTickRate { tick_rate: 0 }, Command::Resume]);
+        assert!(matches!(handler.apply_command_bytes(&bytes), Err(SimError::InvalidInput(m)) if m.starts_with("Command 1")));
+        assert!(!handler.is_running());
+        assert_eq!(handler.get_tick_rate(), 30);
+    }
+
+    #[test]
+    fn player_commands_override_the_ai() {
+        use crate::types::{AiState, PlayerCommand};
+
+        let parse = |json: &str| serde_json::from_str::<PlayerCommand>(json);
+        assert_eq!(
+            parse(r#"{"kind":"MoveTo","grid_index":3}"#).unwrap(),
+            PlayerCommand::MoveTo { grid_index: 3 }
+        );
+        assert!(parse(r#"{"kind":"Fly"}"#).is_err());
+
+        let config = WorldConfig::new(240.0, 2.0, 6);
+        let mut handler = SimulationHandler::init_with_config(2, 60, config).unwrap();
+        let logic = handler.logic_mut();
+        assert!(logic.queue_command(7, PlayerCommand::Defend).is_err());
+        assert!(logic
+            .queue_command(0, PlayerCommand::MoveTo { grid_index: 36 })
+            .is_err());
+        assert!(logic.queue_command(0, PlayerCommand::Hold).is_ok());
+
+        // Holding entities stay put even when the AI would expand
+        handler.logic_mut().data_mut().entity_mut(0).unwrap().military_strength = 200.0;
+        handler.step();
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap().clone();
+        assert_eq!(entity.command, Some(PlayerCommand::Hold));
+        assert_eq!(entity.state, AiState::Idle);
+        assert_eq!(entity.territory, 1);
+
+        // Attack a specific neighboring space, then fall back to holding
+        let home = handler
+            .logic_mut()
+            .data_mut()
+            .position_to_grid_index(entity.position_x, entity.position_y)
+            .unwrap();
+        let target = if home % 6 == 5 { home - 1 } else { home + 1 };
+        let command = PlayerCommand::Attack { grid_index: target as u32 };
+        handler.logic_mut().queue_command(0, command).unwrap();
+        handler.step();
+        let owner = handler.logic_mut().data_mut().grid_spaces()[target].owner_id;
+        assert_eq!(owner, Some(0));
+        assert_eq!(handler.logic_mut().data_mut().entity(0).unwrap().territory, 2);
+        handler.step();
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap();
+        assert_eq!(entity.command, Some(PlayerCommand::Hold));
+        assert_eq!(entity.territory, 2);
+
+        // Walk to the far corner and stop there
+        handler
+            .logic_mut()
+            .queue_command(0, PlayerCommand::MoveTo { grid_index: 35 })
+            .unwrap();
+        for _ in 0..50 {
+            handler.step();
+        }
+        let entity = handler.logic_mut().data_mut().entity(0).unwrap().clone();
+        assert_eq!(entity.command, Some(PlayerCommand::Hold));
+        assert_eq!(
+            (entity.position_x, entity.position_y),
+            handler.logic_mut().data_mut().grid_index_to_position(35)
+        );
+    }
+
+    #[test]
+    fn dense_spatial_cells_spill_instead_of_dropping() {
+        use crate::data::GridUpdateBuilder;
+        use crate::types::EntitySnapshot;
+
+        let world = WorldConfig::default();
+        let mut grid = GridUpdateBuilder::for_world(&world);
+        let mut snapshots: Vec<EntitySnapshot> = (0..10)
+            .map(|i| EntitySnapshot::from(&crate::types::AiEntity::new(i)))
+            .collect();
+        for snapshot in &mut snapshots {
+            snapshot.position_x = 1.0;
+            snapshot.position_y = 1.0;
+        }
+        // Far outside the world, still indexed at the edge
+        snapshots[9].position_x = 5000.0;
+        grid.rebuild(&snapshots);
+
+        assert_eq!(grid.overflow_count(), 5);
+        let mut seen = Vec::new();
+        grid.for_each_neighbor(1.0, 1.0, |index| seen.push(index));
+        seen.sort_unstable();
+        assert_eq!(seen, (0..9).collect::<Vec<_>>());
+        let mut edge = Vec::new();
+        grid.for_each_neighbor(world.half_extent(), 1.0, |index| edge.push(index));
+        assert_eq!(edge, vec![9]);
+
+        grid.rebuild(&snapshots[..3]);
+        assert_eq!(grid.overflow_count(), 0);
+
+        let mut handler = SimulationHandler::new(4).unwrap();
+        handler.step();
+        assert_eq!(handler.get_overflow_count(), 0);
+    }
+
+    #[test]
+    fn diagnostics_flag_corrupted_state() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::init_with_grid(4, 60, 6).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.set_step_validation(true);
+        for _ in 0..30 {
+            handler.step();
+        }
+        let diagnostics = handler.logic().diagnostics();
+        assert!(diagnostics.is_clean(), "{:?}", diagnostics);
+        assert_eq!(diagnostics.tick, 30);
+
+        let data = handler.logic_mut().data_mut();
+        let capital = data.entity(2).unwrap().capital.unwrap();
+        data.entity_mut(0).unwrap().position_x = 1e6;
+        data.entity_mut(1).unwrap().money = -1.0;
+        data.entity_mut(3).unwrap().military_strength = f32::NAN;
+        data.entity_mut(2).unwrap().state = AiState::Dead;
+        let diagnostics = handler.logic().diagnostics();
+        assert!(!diagnostics.is_clean());
+        assert_eq!(diagnostics.out_of_bounds, vec![0]);
+        assert_eq!(diagnostics.negative_resources, vec![1, 3]);
+        assert!(diagnostics.orphaned_cells.contains(&capital));
+    }
+
+    #[test]
+    fn spatial_queries_return_nearest_first() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::new(6).unwrap();
+        let offsets = [(0.0, 0.0), (30.0, 0.0), (-4.0, 3.0), (0.0, -12.0), (500.0, 500.0)];
+        for (i, &(x, y)) in offsets.iter().enumerate() {
+            let entity = handler.logic_mut().data_mut().entity_mut(i).unwrap();
+            entity.position_x = x;
+            entity.position_y = y;
+        }
+        let dead = handler.logic_mut().data_mut().entity_mut(5).unwrap();
+        dead.state = AiState::Dead;
+        dead.position_x = 1.0;
+        dead.position_y = 1.0;
+
+        assert_eq!(handler.query_entities_near(0.0, 0.0, 20.0), vec![0, 2, 3]);
+        assert_eq!(handler.query_entities_near(0.0, 0.0, 5.0), vec![0, 2]);
+        assert!(handler.query_entities_near(-900.0, -900.0, 10.0).is_empty());
+        assert!(handler.query_entities_near(0.0, 0.0, f32::NAN).is_empty());
+
+        assert_eq!(handler.query_nearest_entities(0.0, 0.0, 4), vec![0, 2, 3, 1]);
+        assert_eq!(handler.query_nearest_entities(450.0, 450.0, 1), vec![4]);
+        assert_eq!(handler.query_nearest_entities(0.0, 0.0, 10).len(), 5);
+        assert!(handler.query_nearest_entities(0.0, 0.0, 0).is_empty());
+    }
+
+    #[test]
+    fn grid_snapshot_covers_every_space() {
+        use crate::types::grid_space::NEUTRAL_GRID_OWNER;
+        use crate::types::UNOWNED_GRID_OWNER;
+
+        let mut handler = SimulationHandler::init_with_grid(4, 60, 10).unwrap();
+        let (owners, defense) = handler.logic_mut().request_grid_snapshot();
+        assert_eq!(owners.len(), 100);
+        assert_eq!(defense.len(), 100);
+
+        let owned = owners.iter().filter(|&&o| o < 4).count();
+        assert_eq!(owned, 4, "Each entity starts with exactly one grid space");
+        for (owner, strength) in owners.iter().zip(defense) {
+            if *owner == UNOWNED_GRID_OWNER {
+                assert_eq!(*strength, 0.0);
+            } else if *owner == NEUTRAL_GRID_OWNER {
+                assert_eq!(*strength, 2.0, "Garrisons start at their initial defense");
+            } else {
+                assert_eq!(*strength, 5.0);
             }
-            
-            if let Some(idx1) = handler.logic_mut().data_mut().position_to_grid_index((2400.0 / grid_size as f32), 0.0) {
-                if let Some(space) = handler.logic_mut().data_mut().grid_space_mut(idx1) {
-                    space.owner_id = Some(entity1_id);
-                    space.defense_strength = 5.0;
-                }
+        }
+    }
+
+    #[test]
+    fn grid_diff_reports_only_changed_spaces() {
+        use crate::types::AiState;
+
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 10).unwrap();
+        assert_eq!(handler.logic_mut().request_grid_diff().indices.len(), 100);
+        assert!(handler.logic_mut().request_grid_diff().indices.is_empty());
+
+        let entity = handler.logic_mut().data_mut().entity_mut(0).unwrap();
+        entity.state = AiState::Attacking;
+        entity.military_strength = 100.0;
+        handler.step();
+
+        let diff = handler.logic_mut().request_grid_diff().clone();
+        assert!(!diff.indices.is_empty());
+        assert!(diff.indices.len() < 100);
+        assert_eq!(diff.indices.len(), diff.owners.len());
+        assert_eq!(diff.indices.len(), diff.defense.len());
+        let grid = handler.logic_mut().data_mut().grid_spaces().to_vec();
+        for (i, &index) in diff.indices.iter().enumerate() {
+            assert_eq!(grid[index as usize].owner_id, Some(diff.owners[i]));
+        }
+    }
+
+    #[test]
+    fn sync_bundle_restores_state_on_late_joiner() {
+        let mut host = SimulationHandler::init_with_grid(8, 60, 12).unwrap();
+        for _ in 0..20 {
+            host.step();
+        }
+
+        let bundle = host.logic().sync_bundle();
+        assert_eq!(bundle.tick, 20);
+        assert!(bundle.grid_runs.len() < 12 * 12, "Grid should be run-length encoded");
+
+        let mut joiner = SimulationHandler::new(3).unwrap();
+        joiner
+            .logic_mut()
+            .apply_sync_bundle(&bundle)
+            .expect("bundle should apply");
+
+        assert_eq!(joiner.get_tick(), host.get_tick());
+        assert_eq!(joiner.get_grid_size(), 12);
+        assert_eq!(joiner.get_entity_count(), 8);
+        assert_eq!(joiner.logic().state_hash(), host.logic().state_hash());
+    }
+
+    #[test]
+    fn late_joiner_keeps_pace_with_the_host() {
+        let mut host = SimulationHandler::init_with_grid(8, 60, 12).unwrap();
+        host.set_fixed_timestep(100.0);
+        host.start();
+        // Join while some entity is partway through an attack or a fortification
+        let busy = |host: &SimulationHandler| {
+            host.logic().entities().iter().any(|entity| {
+                entity.attack_target.is_some() || entity.attack_space.is_some() || entity.fortify_target.is_some()
+            })
+        };
+        while !busy(&host) {
+            assert!(host.get_tick() < 2_000, "No entity ever attacked or fortified");
+            host.step();
+        }
+        let bundle = host.logic().sync_bundle();
+
+        let mut joiner = SimulationHandler::new(3).unwrap();
+        joiner.set_fixed_timestep(100.0);
+        joiner.logic_mut().apply_sync_bundle(&bundle).expect("bundle should apply");
+        for tick in 0..60 {
+            host.step();
+            joiner.step();
+            assert_eq!(joiner.logic().state_hash(), host.logic().state_hash(), "Diverged {} ticks after joining", tick);
+        }
+
+        let mut bundle = host.logic().sync_bundle();
+        bundle.entities[0].brain = 5;
+        assert!(joiner.logic_mut().apply_sync_bundle(&bundle).is_err(), "Unregistered brains are refused");
+    }
+
+    #[test]
+    fn maps_load_from_json_and_export_for_reloading() {
+        use crate::constants::CAPITAL_START_DEFENSE;
+        use crate::types::resource::ResourceNode;
+        use crate::types::terrain::TerrainType;
+        use crate::types::{AiState, TerrainConfig};
+
+        let map = r#"{
+            "grid_size": 4,
+            "terrain": ["Plains", "Plains", "Forest", "Water", "Plains", "Plains", "Forest", "Water",
+                        "Mountain", "Plains", "Plains", "Water", "Mountain", "Plains", "Plains", "Plains"],
+            "resources": [{"grid_index": 5, "node": "GoldMine"}],
+            "entities": [{"capital": 0, "military_strength": 40, "money": 12}, {"capital": 15, "profile": "turtle"}],
+            "cells": [{"grid_index": 1, "owner_id": 0, "defense_strength": 8}, {"grid_index": 5, "owner_id": 0}]
+        }"#;
+        let mut handler = SimulationHandler::init_with_grid(5, 60, 10).unwrap();
+        for _ in 0..5 {
+            handler.step();
+        }
+        handler.load_map(map).unwrap();
+        assert_eq!((handler.get_tick(), handler.get_grid_size(), handler.get_entity_count()), (0, 4, 2));
+        let logic = handler.logic();
+        let entities = logic.entities();
+        assert_eq!((entities[0].capital, entities[0].territory, entities[0].resource_nodes), (Some(0), 3, 1));
+        assert_eq!((entities[0].military_strength, entities[0].money), (40.0, 12.0));
+        assert_eq!(entities[1].profile, BehaviorProfile::Turtle);
+        assert_eq!((entities[1].capital, entities[1].territory), (Some(15), 1));
+        assert!(entities.iter().all(|e| e.state != AiState::Dead));
+        let terrain = logic.grid_terrain();
+        drop(logic);
+        let spaces = handler.logic_mut().data_mut().grid_spaces().to_vec();
+        assert_eq!((spaces[0].owner_id, spaces[0].defense_strength), (Some(0), CAPITAL_START_DEFENSE));
+        assert_eq!((spaces[1].owner_id, spaces[1].defense_strength), (Some(0), 8.0));
+        assert_eq!(spaces[5].resource, Some(ResourceNode::GoldMine));
+        assert_eq!(spaces.iter().filter(|space| space.resource.is_some()).count(), 1);
+        assert_eq!(spaces[3].terrain, TerrainType::Water);
+
+        // The painted layers outlast a new terrain seed and reach late joiners
+        handler.set_terrain_config(r#"{"seed":7}"#).unwrap();
+        assert_eq!(handler.logic().grid_terrain(), terrain);
+        let bundle = handler.logic().sync_bundle();
+        let mut joiner = SimulationHandler::new(3).unwrap();
+        joiner.logic_mut().apply_sync_bundle(&bundle).unwrap();
+        assert_eq!(joiner.logic().grid_terrain(), terrain);
+        assert_eq!(joiner.get_state_hash(), handler.get_state_hash());
+
+        // An exported map starts the same run over elsewhere
+        for _ in 0..10 {
+            handler.step();
+        }
+        let exported = handler.export_map().unwrap();
+        let mut reloaded = SimulationHandler::new(3).unwrap();
+        reloaded.set_terrain_config(r#"{"seed":7}"#).unwrap();
+        reloaded.load_map(&exported).unwrap();
+        handler.load_map(&exported).unwrap();
+        assert_eq!(reloaded.get_state_hash(), handler.get_state_hash());
+        assert_eq!(reloaded.logic().grid_terrain(), terrain);
+        assert_eq!(reloaded.export_map().unwrap(), exported);
+
+        // Resizing the grid goes back to generated land
+        handler.set_grid_size(4).unwrap();
+        let generated = TerrainConfig { seed: 7, ..TerrainConfig::default() }.generate(4);
+        assert_eq!(handler.logic().grid_terrain(), generated.iter().map(|&t| t as u8).collect::<Vec<_>>());
+
+        let hash = reloaded.get_state_hash();
+        assert!(matches!(reloaded.load_map(r#"{"grid_size":0}"#), Err(SimError::ZeroGridSize)));
+        assert!(matches!(reloaded.load_map(r#"{"entities":[]}"#), Err(SimError::MalformedInput(_))));
+        for map in [
+            r#"{"grid_size":2,"terrain":["Plains"]}"#,
+            r#"{"grid_size":2,"resources":[{"grid_index":4,"node":"Farm"}]}"#,
+            r#"{"grid_size":2,"entities":[{"capital":1},{"capital":1}]}"#,
+            r#"{"grid_size":2,"entities":[{"capital":1,"money":-1}]}"#,
+            r#"{"grid_size":2,"entities":[{"capital":1}],"cells":[{"grid_index":0,"owner_id":1}]}"#,
+            r#"{"grid_size":2,"entities":[{"capital":0},{"capital":1}],"cells":[{"grid_index":1,"owner_id":0}]}"#,
+            r#"{"grid_size":2,"entities":[{"capital":0}],
+                "cells":[{"grid_index":1,"owner_id":0},{"grid_index":1,"owner_id":0}]}"#,
+        ] {
+            assert!(matches!(reloaded.load_map(map), Err(SimError::InvalidInput(_))), "{}", map);
+        }
+        assert_eq!(reloaded.get_state_hash(), hash, "Rejected maps leave the simulation alone");
+    }
+
+    #[test]
+    fn fair_starts_are_measured_and_retried_until_even() {
+        let mut handler = SimulationHandler::init_with_grid(4, 60, 20).unwrap();
+        handler.set_fairness_config(r#"{"max_attempts":1}"#).unwrap();
+        let first = handler.logic().validate_start_positions();
+        assert_eq!((first.attempts, first.starts.len()), (1, 4));
+
+        // Retrying never settles for less even starts than the first placement
+        handler.set_fairness_config("{}").unwrap();
+        let report = handler.logic().validate_start_positions();
+        assert!(report.fair || report.attempts == 8);
+        assert!(report.worst_spread() <= first.worst_spread());
+        for (start, entity) in report.starts.iter().zip(handler.logic().entities()) {
+            assert_eq!((start.entity_id, Some(start.grid_index)), (entity.id, entity.capital));
+            assert!(start.nearest_neighbor.is_some());
+        }
+        handler.set_fairness_config(r#"{"tolerance":0,"max_attempts":3}"#).unwrap();
+        let report = handler.logic().validate_start_positions();
+        assert_eq!((report.fair, report.attempts), (false, 3));
+
+        // Mountains next to the corner start leave it boxed in
+        let mut terrain = vec!["\"Plains\""; 25];
+        terrain[1] = "\"Mountain\"";
+        terrain[5] = "\"Mountain\"";
+        let map = format!(
+            r#"{{"grid_size":5,"terrain":[{}],"resources":[],"entities":[{{"capital":0}},{{"capital":12}}]}}"#,
+            terrain.join(",")
+        );
+        handler.set_fairness_config("{}").unwrap();
+        handler.load_map(&map).unwrap();
+        let report = handler.logic().validate_start_positions();
+        let measures: Vec<_> = report
+            .starts
+            .iter()
+            .map(|start| (start.reachable_area, start.resource_value, start.nearest_neighbor))
+            .collect();
+        assert_eq!(measures, [(6, 10.0, Some(4)), (22, 44.0, Some(4))]);
+        assert_eq!(report.area_spread, 1.0 - 6.0 / 22.0);
+        assert_eq!((report.distance_spread, report.fair, report.attempts), (0.0, false, 0));
+
+        handler.remove_entity(1).unwrap();
+        let report = handler.logic().validate_start_positions();
+        assert_eq!((report.starts[0].nearest_neighbor, report.worst_spread(), report.fair), (None, 0.0, true));
+
+        for config in [r#"{"tolerance":1.5}"#, r#"{"max_attempts":0}"#, r#"{"radius":-1}"#] {
+            assert!(matches!(handler.set_fairness_config(config), Err(SimError::InvalidInput(_))), "{}", config);
+        }
+    }
+
+    #[test]
+    fn heatmaps_track_combat_and_ownership_changes() {
+        use crate::types::PlayerCommand;
+
+        // Entity 0 stands next to the space entity 1 holds in the middle of the strip
+        let map = r#"{"grid_size":3,"entities":[{"capital":0,"military_strength":500},{"capital":8}],
+            "cells":[{"grid_index":1,"owner_id":1,"defense_strength":2}]}"#;
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 3).unwrap();
+        handler.load_map(map).unwrap();
+        handler.set_fixed_timestep(16.0);
+        let defense = handler.get_heatmap("defense_strength").unwrap();
+        let spaces = handler.logic_mut().data_mut().grid_spaces().to_vec();
+        assert_eq!(defense, spaces.iter().map(|space| space.displayed_defense()).collect::<Vec<_>>());
+        assert_eq!(handler.get_heatmap("contested_frequency").unwrap(), vec![0.0; 9]);
+
+        handler
+            .logic_mut()
+            .queue_command(0, PlayerCommand::Attack { grid_index: 1 })
+            .unwrap();
+        handler.step();
+        assert_eq!(handler.logic_mut().data_mut().grid_spaces()[1].owner_id, Some(0));
+        let contested = handler.get_heatmap("contested_frequency").unwrap();
+        let changes = handler.get_heatmap("ownership_changes").unwrap();
+        assert_eq!((contested[1], changes[1]), (1.0, 1.0));
+        assert_eq!(contested.iter().sum::<f32>(), 1.0);
+        let heat = handler.get_heatmap("recent_combat").unwrap()[1];
+        assert_eq!(heat, 1.0);
+
+        // Heat fades while the tallies stay
+        handler.step();
+        let faded = handler.get_heatmap("recent_combat").unwrap()[1];
+        assert!(faded < heat && faded > 0.0, "{}", faded);
+        assert_eq!(handler.get_heatmap("contested_frequency").unwrap()[1], 1.0);
+
+        // Only the current run counts
+        handler.reset();
+        assert_eq!(handler.get_heatmap("ownership_changes").unwrap(), vec![0.0; 9]);
+        assert!(matches!(handler.get_heatmap("gold"), Err(SimError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn restless_spaces_revolt_or_defect_back() {
+        use crate::types::{GridSpace, PlayerCommand, SimulationEvent, UnrestConfig};
+
+        let map = r#"{"grid_size":3,"entities":[{"capital":0,"military_strength":500},{"capital":8}],
+            "cells":[{"grid_index":1,"owner_id":1,"defense_strength":2}]}"#;
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 3).unwrap();
+        handler.load_map(map).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler
+            .logic_mut()
+            .queue_command(0, PlayerCommand::Attack { grid_index: 1 })
+            .unwrap();
+        handler.step();
+        let conquered = handler.logic_mut().data_mut().grid_spaces()[1];
+        assert_eq!((conquered.owner_id, conquered.former_owner), (Some(0), Some(1)));
+        assert_eq!(conquered.unrest, UnrestConfig::default().conquest_unrest);
+        assert_eq!(handler.get_heatmap("unrest").unwrap()[1], conquered.unrest);
+
+        // Every restless space revolts: the conquered one defects, land that was
+        // never anyone else's falls to rebels
+        let config = r#"{"decay_per_tick":0,"revolt_threshold":0.5,"revolt_chance":1,"defect_chance":1}"#;
+        handler.set_unrest_config(config).unwrap();
+        let data = handler.logic_mut().data_mut();
+        *data.grid_space_mut(2).unwrap() = GridSpace {
+            unrest: 0.6,
+            ..GridSpace::with_owner(0, 5.0)
+        };
+        data.update_territories();
+        assert_eq!(data.stir_unrest(10), 2);
+        let spaces = data.grid_spaces();
+        assert_eq!((spaces[1].owner_id, spaces[1].former_owner, spaces[1].unrest), (Some(1), Some(0), 0.0));
+        assert_eq!((spaces[2].owner_id, spaces[2].garrison), (None, 4.0));
+        assert_eq!(spaces[0].owner_id, Some(0), "Capitals never revolt");
+        let defected = SimulationEvent::Revolt { tick: 1, grid_index: 1, owner_id: 0, defected_to: Some(1) };
+        assert!(handler.logic().events().any(|event| *event == defected));
+        assert_eq!(handler.logic().entities()[1].territory, 2);
+
+        // Far from the capital unrest builds up, three steps' worth here
+        handler
+            .set_unrest_config(r#"{"distance_unrest_per_tick":0.01,"decay_per_tick":0,"revolt_chance":0}"#)
+            .unwrap();
+        handler.logic_mut().data_mut().stir_unrest(10);
+        let unrest = handler.get_heatmap("unrest").unwrap();
+        assert!((unrest[1] - 0.3).abs() < 1e-6, "{}", unrest[1]);
+        assert_eq!((unrest[0], unrest[8]), (0.0, 0.0));
+        assert!(handler.set_unrest_config(r#"{"revolt_chance":2}"#).is_err());
+    }
+
+    #[test]
+    fn disasters_strike_entities_and_regions() {
+        use crate::types::{DisasterKind, SimulationEvent};
+
+        let map = r#"{"grid_size":5,"entities":[{"capital":0,"military_strength":0},{"capital":24,"money":10}]}"#;
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 5).unwrap();
+        handler.load_map(map).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.set_event_config(r#"{"duration_ticks":5,"earthquake_radius":1}"#).unwrap();
+
+        // A plague halves military generation until it runs its course; without an
+        // army entity 0 holds on to just its capital meanwhile
+        let military_yield = handler.logic().entities()[0].military_yield;
+        handler.trigger_disaster("plague", 0).unwrap();
+        assert_eq!(handler.logic().entities()[0].military_yield, military_yield * 0.5);
+        assert_eq!(handler.logic().afflictions()[0].ends_at, 5);
+        let plague = SimulationEvent::Disaster {
+            tick: 0,
+            disaster: DisasterKind::Plague,
+            entity_id: Some(0),
+            grid_index: None,
+        };
+        assert!(handler.logic().events().any(|event| *event == plague));
+        for _ in 0..5 {
+            handler.step();
+        }
+        assert!(handler.logic().afflictions().is_empty());
+        assert_eq!(handler.logic().entities()[0].military_yield, military_yield);
+
+        // A famine drains money every tick
+        handler.trigger_disaster("famine", 1).unwrap();
+        let data = handler.logic_mut().data_mut();
+        let money = data.entities()[1].money;
+        data.apply_afflictions();
+        assert!((money - data.entities()[1].money - 0.05).abs() < 1e-6);
+
+        // An earthquake wears down defenses within its radius only
+        let defenses = |handler: &mut SimulationHandler| {
+            let spaces = handler.logic_mut().data_mut().grid_spaces();
+            (spaces[0].defense_strength, spaces[24].defense_strength)
+        };
+        let (near, far) = defenses(&mut handler);
+        handler.trigger_disaster("earthquake", 1).unwrap();
+        assert_eq!(defenses(&mut handler), (near * 0.25, far));
+        assert!(handler.trigger_disaster("earthquake", 25).is_err());
+        assert!(handler.trigger_disaster("flood", 0).is_err());
+
+        // Seeded rolls strike the same way on every peer
+        let config = r#"{"seed":7,"plague_chance":1,"famine_chance":1,"earthquake_chance":1}"#;
+        let mut peers = [(); 2].map(|_| SimulationHandler::init_with_grid(3, 60, 5).unwrap());
+        for peer in &mut peers {
+            peer.set_event_config(config).unwrap();
+            peer.logic_mut().data_mut().strike_disasters();
+        }
+        let struck = |peer: &SimulationHandler| {
+            peer.logic().events().filter(|event| matches!(event, SimulationEvent::Disaster { .. })).count()
+        };
+        assert_eq!(peers[0].logic().afflictions().len(), 2);
+        assert_eq!(peers[0].logic().afflictions(), peers[1].logic().afflictions());
+        assert_eq!(peers[0].get_state_hash(), peers[1].get_state_hash());
+        assert_eq!(struck(&peers[0]), 3);
+        assert!(handler.set_event_config(r#"{"plague_chance":2}"#).is_err());
+    }
+
+    #[test]
+    fn seasons_turn_with_the_ticks_and_sway_attacks_and_money() {
+        use crate::types::{PlayerCommand, Season};
+
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 3).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.set_calendar_config(r#"{"ticks_per_season":10}"#).unwrap();
+        for _ in 0..35 {
+            handler.step();
+        }
+        let date = handler.logic().calendar_date();
+        assert_eq!((date.year, date.season, date.day), (0, Season::Winter, 5));
+        assert_eq!(date.effects.attack_factor, 0.5);
+        for _ in 0..5 {
+            handler.step();
+        }
+        let date = handler.logic().calendar_date();
+        assert_eq!((date.year, date.season, date.day), (1, Season::Spring, 0));
+
+        // A harsh enough season stops an attack that would otherwise carry the space
+        let map = r#"{"grid_size":3,"entities":[{"capital":0,"military_strength":500},{"capital":8}],
+            "cells":[{"grid_index":1,"owner_id":1,"defense_strength":2}]}"#;
+        handler.load_map(map).unwrap();
+        handler.set_calendar_config(r#"{"spring":{"attack_factor":0.001}}"#).unwrap();
+        let attack = |handler: &mut SimulationHandler| {
+            handler
+                .logic_mut()
+                .queue_command(0, PlayerCommand::Attack { grid_index: 1 })
+                .unwrap();
+            handler.step();
+            handler.logic_mut().data_mut().grid_spaces()[1].owner_id
+        };
+        assert_eq!(attack(&mut handler), Some(1));
+        handler.set_calendar_config("{}").unwrap();
+        assert_eq!(attack(&mut handler), Some(0));
+
+        // Money generated scales with the season's factor; upkeep doesn't
+        let money = [0, 1, 2].map(|factor| {
+            let mut handler = SimulationHandler::init_with_grid(2, 60, 3).unwrap();
+            handler.set_fixed_timestep(16.0);
+            handler.load_map(r#"{"grid_size":3,"entities":[{"capital":0,"money":10},{"capital":8}]}"#).unwrap();
+            handler.set_calendar_config(&format!(r#"{{"spring":{{"money_factor":{}}}}}"#, factor)).unwrap();
+            handler.logic_mut().queue_command(0, PlayerCommand::Hold).unwrap();
+            for _ in 0..10 {
+                handler.step();
             }
+            let money = handler.logic().entities()[0].money;
+            money
+        });
+        assert!(money[1] > money[0]);
+        assert!((money[2] - money[1] - (money[1] - money[0])).abs() < 1e-4, "{:?}", money);
+        assert!(handler.set_calendar_config(r#"{"ticks_per_season":0}"#).is_err());
+        assert!(handler.set_calendar_config(r#"{"winter":{"attack_factor":0}}"#).is_err());
+    }
+
+    #[test]
+    fn holding_victory_points_long_enough_wins_the_run() {
+        use crate::types::{PlayerCommand, SimulationEvent};
+
+        let map = r#"{"grid_size":3,"entities":[{"capital":0,"military_strength":500},{"capital":8}],
+            "cells":[{"grid_index":1,"owner_id":1,"defense_strength":2}],"victory_points":[1]}"#;
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 3).unwrap();
+        handler.load_map(map).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.set_win_condition(r#"{"VictoryPoints":{"needed":1,"hold_ticks":3}}"#).unwrap();
+        assert_eq!(handler.logic().entities()[1].victory_points, 1);
+        handler
+            .logic_mut()
+            .queue_command(0, PlayerCommand::Attack { grid_index: 1 })
+            .unwrap();
+        handler.step();
+        let progress = |handler: &SimulationHandler, id: usize| {
+            let logic = handler.logic();
+            (logic.entities()[id].victory_points, logic.entities()[id].victory_hold)
+        };
+        assert_eq!((progress(&handler, 0), progress(&handler, 1)), ((1, 1), (0, 0)));
+
+        // Losing the points starts the count over
+        handler.set_victory_cells(&[2]).unwrap();
+        handler.step();
+        assert_eq!(progress(&handler, 0), (0, 0));
+
+        handler.set_victory_cells(&[2, 1, 2]).unwrap();
+        assert_eq!(handler.get_victory_cells(), vec![1, 2]);
+        for _ in 0..2 {
+            handler.step();
         }
-        
-        // Update territories before step
-        handler.logic_mut().data_mut().update_territories();
-        
-        let initial_territory_0 = handler.logic_mut().data_mut().entity(0).unwrap().territory;
-        let initial_territory_1 = handler.logic_mut().data_mut().entity(1).unwrap().territory;
-        
-        // Run several steps to allow conquest
+        assert_eq!(handler.get_victor(), None);
+        assert!(!handler.is_complete());
+        handler.step();
+        assert_eq!(handler.get_victor(), Some(0));
+        assert!(handler.is_complete() && handler.count_alive() == 2);
+        let tick = handler.get_tick();
+        let won = SimulationEvent::Victory { tick, entity_id: 0 };
+        assert!(handler.logic().events().any(|event| *event == won));
+        let snapshot = handler.logic_mut().data_mut().build_public_snapshot();
+        assert_eq!((snapshot[0].victory_points, snapshot[0].victory_hold), (1, 3));
+        assert_eq!(handler.logic().export_map().victory_points, vec![1, 2]);
+
+        // Other win conditions have no victor
+        handler.set_win_condition(r#""Annihilation""#).unwrap();
+        assert_eq!(handler.get_victor(), None);
+        assert!(handler.set_victory_cells(&[9]).is_err());
+        assert!(handler.set_win_condition(r#"{"VictoryPoints":{"needed":0,"hold_ticks":3}}"#).is_err());
+        let twice = r#"{"grid_size":3,"entities":[{"capital":0}],"victory_points":[4,4]}"#;
+        assert!(handler.load_map(twice).is_err());
+    }
+
+    #[test]
+    fn soft_reset_keeps_either_the_map_or_the_entities() {
+        let map = r#"{
+            "grid_size": 4,
+            "terrain": ["Plains", "Plains", "Forest", "Water", "Plains", "Plains", "Forest", "Water",
+                        "Mountain", "Plains", "Plains", "Water", "Mountain", "Plains", "Plains", "Plains"],
+            "resources": [{"grid_index": 5, "node": "GoldMine"}],
+            "entities": [{"capital": 0, "military_strength": 40}, {"capital": 15}],
+            "cells": [{"grid_index": 1, "owner_id": 0, "defense_strength": 8}]
+        }"#;
+        let mut handler = SimulationHandler::init_with_grid(3, 60, 10).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.load_map(map).unwrap();
+        let start_hash = handler.get_state_hash();
+        let terrain = handler.logic().grid_terrain();
+        for _ in 0..30 {
+            handler.step();
+        }
+        assert_ne!(handler.get_state_hash(), start_hash);
+
+        // Back to the map's own starts, not the evenly spaced ones `reset` gives
+        handler.soft_reset(true);
+        assert_eq!(handler.get_tick(), 0);
+        assert_eq!(handler.get_state_hash(), start_hash);
+        assert_eq!(handler.logic().grid_terrain(), terrain);
+        handler.reset();
+        assert_ne!(handler.get_state_hash(), start_hash);
+
+        // The entities stay put on land generated from the terrain config
         for _ in 0..5 {
             handler.step();
         }
-        
-        // Check if territory changed (conquest happened)
-        let final_territory_0 = handler.logic_mut().data_mut().entity(0).unwrap().territory;
-        let final_territory_1 = handler.logic_mut().data_mut().entity(1).unwrap().territory;
-        
-        // Attacker should have gained territory or defender should have lost some
-        // (Conquest may or may not happen depending on positioning, so we just verify the mechanism works)
-        assert!(
-            final_territory_0 != initial_territory_0 || final_territory_1 != initial_territory_1 || final_territory_0 > 0,
-            "Conquest mechanics should be working"
+        let holdings = |handler: &SimulationHandler| {
+            let logic = handler.logic();
+            logic.entities().iter().map(|entity| (entity.capital, entity.territory)).collect::<Vec<_>>()
+        };
+        let before = holdings(&handler);
+        handler.soft_reset(false);
+        assert_eq!(handler.get_tick(), 5);
+        let generated = SimulationHandler::init_with_grid(2, 60, 4).unwrap().logic().grid_terrain();
+        assert_eq!(handler.logic().grid_terrain(), generated);
+        assert_ne!(generated, terrain);
+        assert_eq!(holdings(&handler), before);
+    }
+
+    #[test]
+    fn resizing_the_grid_keeps_territories_in_place() {
+        use crate::types::PlayerCommand;
+
+        // Entity 0 holds three of the four top-left spaces, but not the one under
+        // the center of the top-left space of a 2x2 grid
+        let map = r#"{"grid_size":4,"entities":[{"capital":2},{"capital":15}],
+            "cells":[{"grid_index":0,"owner_id":0},{"grid_index":1,"owner_id":0},{"grid_index":4,"owner_id":0},
+                {"grid_index":3,"owner_id":0},{"grid_index":6,"owner_id":0},{"grid_index":7,"owner_id":0}]}"#;
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 4).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.load_map(map).unwrap();
+        for _ in 0..3 {
+            handler.step();
+        }
+        let owners = |handler: &mut SimulationHandler| -> Vec<Option<u32>> {
+            handler.logic_mut().data_mut().grid_spaces().iter().map(|space| space.owner_id).collect()
+        };
+        let before = owners(&mut handler);
+        let terrain = handler.logic().grid_terrain();
+        handler
+            .logic_mut()
+            .queue_command(1, PlayerCommand::MoveTo { grid_index: 10 })
+            .unwrap();
+
+        // Doubling the grid turns every space into four
+        handler.resize_grid(8, "nearest").unwrap();
+        assert_eq!((handler.get_grid_size(), handler.get_tick()), (8, 3));
+        let after = owners(&mut handler);
+        let source = |index: usize| index / 16 * 4 + index % 8 / 2;
+        let upscaled_terrain = handler.logic().grid_terrain();
+        assert!((0..64).all(|index| after[index] == before[source(index)]));
+        assert!((0..64).all(|index| upscaled_terrain[index] == terrain[source(index)]));
+        let logic = handler.logic();
+        let held = before.iter().filter(|&&owner| owner == Some(0)).count() as u32;
+        assert_eq!((logic.entities()[0].capital, logic.entities()[0].territory), (Some(13), 4 * held));
+        assert_eq!(logic.entities()[1].capital, Some(63));
+        drop(logic);
+        handler.step();
+        let data = handler.logic_mut().data_mut();
+        assert_eq!(data.entity(1).unwrap().command, Some(PlayerCommand::MoveTo { grid_index: 45 }));
+
+        // Halving it again keeps whoever holds most of each block, or the center space
+        let mut nearest = SimulationHandler::init_with_grid(2, 60, 4).unwrap();
+        nearest.load_map(map).unwrap();
+        nearest.resize_grid(2, "nearest").unwrap();
+        let mut majority = SimulationHandler::init_with_grid(2, 60, 4).unwrap();
+        majority.load_map(map).unwrap();
+        majority.resize_grid(2, "majority").unwrap();
+        assert_eq!(owners(&mut nearest), [None, Some(0), None, Some(1)]);
+        assert_eq!(owners(&mut majority), [Some(0), Some(0), None, Some(1)]);
+        assert_eq!(majority.logic().entities()[0].capital, Some(1));
+        assert!(matches!(handler.resize_grid(0, "nearest"), Err(SimError::ZeroGridSize)));
+        assert!(matches!(handler.resize_grid(4, "bilinear"), Err(SimError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn entities_merge_and_split_their_empires() {
+        use crate::types::{AiState, SimulationEvent};
+
+        let map = r#"{"grid_size":4,
+            "entities":[{"capital":0,"military_strength":20,"money":10},{"capital":15,"military_strength":6,"money":4}],
+            "cells":[{"grid_index":1,"owner_id":0},{"grid_index":2,"owner_id":0},{"grid_index":3,"owner_id":0},
+                {"grid_index":4,"owner_id":0},{"grid_index":14,"owner_id":1}]}"#;
+        let mut handler = SimulationHandler::init_with_grid(2, 60, 4).unwrap();
+        handler.load_map(map).unwrap();
+        let summary = |handler: &SimulationHandler, id: usize| {
+            let logic = handler.logic();
+            let entity = &logic.entities()[id];
+            (entity.capital, entity.territory, entity.money, entity.military_strength)
+        };
+
+        // The far end of entity 0's strip breaks away
+        assert_eq!(handler.split_entity(0, 0.4).unwrap(), 2);
+        assert_eq!(summary(&handler, 0), (Some(0), 3, 6.0, 12.0));
+        assert_eq!(summary(&handler, 2), (Some(3), 2, 4.0, 8.0));
+        let owners: Vec<_> = handler.logic_mut().data_mut().grid_spaces()[..4].iter().map(|s| s.owner_id).collect();
+        assert_eq!(owners, [Some(0), Some(0), Some(2), Some(2)]);
+
+        handler.merge_entities(2, 1).unwrap();
+        assert_eq!(summary(&handler, 2), (Some(3), 4, 8.0, 14.0));
+        assert_eq!(handler.logic().entities()[1].state, AiState::Dead);
+        assert_eq!(handler.logic_mut().data_mut().grid_spaces()[15].owner_id, Some(2));
+        let died = handler.logic().events().any(|event| *event == SimulationEvent::Death { tick: 0, entity_id: 1 });
+        assert!(died);
+
+        assert!(matches!(handler.merge_entities(2, 2), Err(SimError::InvalidInput(_))));
+        assert!(matches!(handler.merge_entities(0, 1), Err(SimError::InvalidInput(_))));
+        assert!(matches!(handler.split_entity(0, 1.0), Err(SimError::InvalidInput(_))));
+        handler.split_entity(0, 0.5).unwrap();
+        assert_eq!(summary(&handler, 0).1, 1);
+        assert!(matches!(handler.split_entity(0, 0.5), Err(SimError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn small_entities_submit_as_vassals_and_pay_tribute() {
+        use crate::types::SimulationEvent;
+
+        // Entity 1 holds one space of a hundred next to entity 0's six; entity 2 borders no one
+        let map = r#"{"grid_size":10,
+            "entities":[{"capital":2,"military_strength":500},{"capital":3,"money":10},{"capital":99}],
+            "cells":[{"grid_index":0,"owner_id":0},{"grid_index":1,"owner_id":0},{"grid_index":10,"owner_id":0},
+                {"grid_index":11,"owner_id":0},{"grid_index":12,"owner_id":0}]}"#;
+        let setup = || {
+            let mut handler = SimulationHandler::init_with_grid(3, 60, 10).unwrap();
+            handler.load_map(map).unwrap();
+            handler.set_fixed_timestep(16.0);
+            for id in 0..3 {
+                handler.logic_mut().queue_command(id, PlayerCommand::Hold).unwrap();
+            }
+            handler
+        };
+        let mut handler = setup();
+        let mut control = setup();
+        handler.logic_mut().data_mut().submit_vassals();
+        assert_eq!((handler.get_overlord(1), handler.get_overlord(2)), (Some(0), None));
+        assert_eq!((handler.get_vassals(0), handler.get_vassals(1)), (vec![1], vec![]));
+        let event = SimulationEvent::Vassalized { tick: 0, vassal_id: 1, overlord_id: 0 };
+        assert!(handler.logic().events().any(|logged| *logged == event));
+        assert_eq!(handler.logic().entity_details(1).unwrap().overlord, Some(0));
+        assert_eq!(handler.logic().entity_details(0).unwrap().vassals, vec![1]);
+
+        // Tribute moves money from the vassal to its overlord and nowhere else. The
+        // control run stops short of the first periodic vassal check at tick 30.
+        for _ in 0..20 {
+            handler.step();
+            control.step();
+        }
+        let money = |handler: &SimulationHandler, id: usize| handler.logic().entities()[id].money;
+        let paid = money(&control, 1) - money(&handler, 1);
+        assert!(paid > 0.0);
+        assert!((money(&handler, 0) - money(&control, 0) - paid).abs() < 1e-4);
+        assert_eq!(money(&handler, 2), money(&control, 2));
+
+        // The overlord's attack on its vassal goes nowhere
+        for handler in [&mut handler, &mut control] {
+            handler
+                .logic_mut()
+                .queue_command(0, PlayerCommand::Attack { grid_index: 3 })
+                .unwrap();
+            for _ in 0..5 {
+                handler.step();
+            }
+        }
+        assert_eq!(handler.logic_mut().data_mut().grid_spaces()[3].owner_id, Some(1));
+        assert_eq!(control.logic_mut().data_mut().grid_spaces()[3].owner_id, Some(0));
+
+        let bundle = handler.logic().sync_bundle();
+        let mut joiner = SimulationHandler::init_with_grid(3, 60, 10).unwrap();
+        joiner.logic_mut().apply_sync_bundle(&bundle).unwrap();
+        assert_eq!(joiner.get_overlord(1), Some(0));
+
+        // An overlord alone with its vassals has won, and its death frees them
+        assert!(!handler.is_complete());
+        handler.merge_entities(0, 2).unwrap();
+        assert!(handler.is_complete());
+        handler.merge_entities(1, 0).unwrap();
+        assert_eq!(handler.get_overlord(1), None);
+    }
+
+    #[test]
+    fn sync_bundle_rejects_tampered_state() {
+        let mut host = SimulationHandler::new(4).unwrap();
+        host.step();
+
+        let mut bundle = host.logic().sync_bundle();
+        bundle.entities[0].money += 100.0;
+
+        let mut joiner = SimulationHandler::new(2).unwrap();
+        assert!(joiner.logic_mut().apply_sync_bundle(&bundle).is_err());
+        assert_eq!(joiner.get_entity_count(), 2, "Failed apply must leave state untouched");
+    }
+
+    #[test]
+    fn sync_bundle_rejects_broken_worlds() {
+        let mut host = SimulationHandler::new(4).unwrap();
+        host.step();
+        let bundle = host.logic().sync_bundle();
+
+        let mut joiner = SimulationHandler::new(2).unwrap();
+        for (grid_size, world_size, cell_size) in [
+            (0, bundle.world.world_size, bundle.world.cell_size),
+            (usize::MAX, bundle.world.world_size, bundle.world.cell_size),
+            (bundle.world.grid_size, -5.0, bundle.world.cell_size),
+            (bundle.world.grid_size, bundle.world.world_size, f32::NAN),
+        ] {
+            let mut broken = bundle.clone();
+            broken.world.grid_size = grid_size;
+            broken.world.world_size = world_size;
+            broken.world.cell_size = cell_size;
+            assert!(joiner.logic_mut().apply_sync_bundle(&broken).is_err(), "{:?}", broken.world);
+        }
+        assert_eq!(joiner.get_entity_count(), 2);
+        assert!(joiner.logic_mut().apply_sync_bundle(&bundle).is_ok());
+    }
+
+    /// State hashes of the golden replay every `GOLDEN_INTERVAL` ticks, from tick 1000 to 10000
+    const GOLDEN_HASHES: [u64; 10] = [
+        0x30ab_547e_9ac4_95d6,
+        0x89d1_c6c5_0448_f26b,
+        0x4572_2806_bafd_df89,
+        0xf7a9_9d20_87dd_927f,
+        0xebf4_3cf8_c909_9a4c,
+        0x5e0a_2b0b_15eb_f04f,
+        0x4c1d_d0c4_97bf_a046,
+        0xec03_a0d0_d3ca_8f87,
+        0x88a9_ff8e_7869_b9de,
+        0xe059_361a_b947_66a8,
+    ];
+    const GOLDEN_INTERVAL: u64 = 1000;
+
+    /// A seeded run on a fixed timestep, hashed every `GOLDEN_INTERVAL` ticks
+    fn golden_replay() -> Vec<u64> {
+        let mut handler = SimulationHandler::init_with_grid(12, 60, 24).unwrap();
+        handler.set_fixed_timestep(16.0);
+        handler.logic_mut().reseed_entities(0x0601_de11);
+        handler.start();
+        (0..GOLDEN_HASHES.len())
+            .map(|_| {
+                for _ in 0..GOLDEN_INTERVAL {
+                    handler.step();
+                }
+                handler.get_state_hash()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn golden_replay_matches_checked_in_hashes() {
+        // Anything that changes how a run plays out changes these hashes. If the
+        // change is meant, paste the hashes this prints over GOLDEN_HASHES.
+        let hashes = golden_replay();
+        for (i, (&hash, &golden)) in hashes.iter().zip(&GOLDEN_HASHES).enumerate() {
+            assert_eq!(
+                hash,
+                golden,
+                "tick {} diverged from the golden replay; hashes this run: {:#018x?}",
+                (i as u64 + 1) * GOLDEN_INTERVAL,
+                hashes
+            );
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_updates_match_serial_updates() {
+        // A one-thread pool runs the same updates one entity after another
+        let run = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| {
+                let mut handler = SimulationHandler::init_with_grid(200, 60, 40).unwrap();
+                handler.set_fixed_timestep(16.0);
+                handler.start();
+                (0..300)
+                    .map(|_| {
+                        handler.step();
+                        handler.logic().state_hash()
+                    })
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(run(1), run(4));
+    }
+
+    #[test]
+    #[ignore] // Benchmark, run with --ignored --nocapture
+    fn dense_neighbor_scan_benchmark() {
+        use std::time::Instant;
+
+        // A small world packs entities into shared spatial cells so every
+        // update scans plenty of neighbors
+        let config = WorldConfig::new(200.0, 5.0, 40);
+        let mut handler = SimulationHandler::init_with_config(2000, 60, config).unwrap();
+        handler.set_fixed_timestep(16.0);
+
+        let ticks = 200;
+        let start = Instant::now();
+        for _ in 0..ticks {
+            handler.step();
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "✓ {} ticks with 2000 entities in {:?} ({:.3} ms/tick)",
+            ticks,
+            elapsed,
+            elapsed.as_secs_f64() * 1000.0 / ticks as f64
         );
     }
 
@@ -412,7 +4579,8 @@ mod tests {
         use std::time::{Duration, Instant};
         
         let entity_count = 5; // Small number of entities for faster completion
-        let mut handler = SimulationHandler::new(entity_count);
+        let mut handler = SimulationHandler::new(entity_count).unwrap();
+        handler.set_resource_clock(ResourceClock::SimTime);
         handler.start();
         
         let start = Instant::now();
@@ -478,7 +4646,8 @@ mod tests {
         
         let entity_count = 100;
         let grid_size = 10; // 10x10 grid = 100 spaces
-        let mut handler = SimulationHandler::init_with_grid(entity_count, 60, grid_size);
+        let mut handler = SimulationHandler::init_with_grid(entity_count, 60, grid_size).unwrap();
+        handler.set_resource_clock(ResourceClock::SimTime);
         handler.start();
         
         let start = Instant::now();
@@ -0,0 +1,73 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+
+use rayon::{ThreadBuilder, ThreadPoolBuilder};
+use wasm_bindgen::prelude::*;
+
+use crate::error::SimError;
+
+/// Rayon threads waiting for a Web Worker to run them
+static PENDING_THREADS: OnceLock<Mutex<Receiver<ThreadBuilder>>> = OnceLock::new();
+
+#[wasm_bindgen(module = "/src/service/worker_helpers.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = startWorkers)]
+    fn start_workers(module: JsValue, memory: JsValue, builder: ThreadPoolStarter) -> js_sys::Promise;
+}
+
+/// Builds rayon's global pool once every worker has loaded the module
+#[wasm_bindgen]
+pub struct ThreadPoolStarter {
+    threads: usize,
+    sender: Sender<ThreadBuilder>,
+}
+
+#[wasm_bindgen]
+impl ThreadPoolStarter {
+    #[wasm_bindgen(js_name = threadCount)]
+    pub fn thread_count(&self) -> usize {
+        self.threads
+    }
+
+    /// Hand each rayon thread to a waiting worker; called by `startWorkers`
+    pub fn build(self) -> Result<(), SimError> {
+        let sender = self.sender;
+        ThreadPoolBuilder::new()
+            .num_threads(self.threads)
+            .spawn_handler(move |thread| {
+                sender.send(thread).map_err(|_| std::io::Error::other("thread pool workers are gone"))
+            })
+            .build_global()
+            .map_err(|err| SimError::Browser(err.to_string()))
+    }
+}
+
+/// Start `threads` Web Workers sharing this module's memory and make them
+/// rayon's global pool, so `parallel` updates run across them. Needs a
+/// cross-origin isolated page for `SharedArrayBuffer`; await it once before
+/// stepping, and step from a worker since the main thread can't block on the pool
+#[wasm_bindgen(js_name = initThreadPool)]
+pub fn init_thread_pool(threads: usize) -> Result<js_sys::Promise, SimError> {
+    if threads == 0 {
+        return Err(SimError::InvalidInput("thread pool needs at least one thread".to_string()));
+    }
+    let (sender, receiver) = mpsc::channel();
+    PENDING_THREADS
+        .set(Mutex::new(receiver))
+        .map_err(|_| SimError::Browser("thread pool is already started".to_string()))?;
+    Ok(start_workers(wasm_bindgen::module(), wasm_bindgen::memory(), ThreadPoolStarter { threads, sender }))
+}
+
+/// Worker entry point: run the next rayon thread until the pool shuts down
+#[doc(hidden)]
+#[wasm_bindgen(js_name = runThreadPoolWorker)]
+pub fn run_thread_pool_worker() -> Result<(), SimError> {
+    let pending = PENDING_THREADS.get().ok_or_else(|| SimError::Browser("thread pool isn't started".to_string()))?;
+    let thread = pending
+        .lock()
+        .map_err(|_| SimError::Browser("thread pool queue is poisoned".to_string()))?
+        .recv()
+        .map_err(|_| SimError::Browser("thread pool never built".to_string()))?;
+    thread.run();
+    Ok(())
+}
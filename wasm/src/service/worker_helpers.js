@@ -0,0 +1,38 @@
+// Web Workers behind `initThreadPool`. wasm-bindgen copies this file to
+// snippets/<crate>-<hash>/src/service/, four directories below the package.
+
+function waitForMessage(target, type) {
+  return new Promise((resolve) => {
+    target.addEventListener('message', function onMessage({ data }) {
+      if (data?.type !== type) return;
+      target.removeEventListener('message', onMessage);
+      resolve(data);
+    });
+  });
+}
+
+// Inside a worker: load the module on the shared memory, then run a rayon thread
+if (typeof WorkerGlobalScope !== 'undefined' && self instanceof WorkerGlobalScope) {
+  waitForMessage(self, 'invasia_worker_init').then(async ({ init }) => {
+    const pkg = await import('../../../../wasm.js');
+    await pkg.default(init);
+    postMessage({ type: 'invasia_worker_ready' });
+    pkg.runThreadPoolWorker();
+  });
+}
+
+// Workers stay referenced here so they aren't collected while the pool runs
+let workers = [];
+
+export async function startWorkers(module, memory, starter) {
+  const init = { type: 'invasia_worker_init', init: { module_or_path: module, memory } };
+  workers = await Promise.all(
+    Array.from({ length: starter.threadCount() }, async () => {
+      const worker = new Worker(new URL('./worker_helpers.js', import.meta.url), { type: 'module' });
+      worker.postMessage(init);
+      await waitForMessage(worker, 'invasia_worker_ready');
+      return worker;
+    })
+  );
+  starter.build();
+}
@@ -1,5 +1,12 @@
 use serde::{Deserialize, Serialize};
 
+use crate::constants::{
+    DEFAULT_WORLD_SIZE, MILITARY_PRICE, MORALE_MAX, MORALE_MIN, UPKEEP_PER_MILITARY_PER_SEC, UPKEEP_PER_SPACE_PER_SEC,
+};
+
+use super::behavior_profile::BehaviorProfile;
+use super::command::PlayerCommand;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(into = "u32", from = "u32")]
 pub enum AiState {
@@ -7,6 +14,7 @@ pub enum AiState {
     Attacking = 1,
     Defending = 2,
     Dead = 3,
+    Allied = 4,
 }
 
 impl From<AiState> for u32 {
@@ -21,6 +29,7 @@ impl From<u32> for AiState {
             1 => AiState::Attacking,
             2 => AiState::Defending,
             3 => AiState::Dead,
+            4 => AiState::Allied,
             _ => AiState::Idle,
         }
     }
@@ -34,7 +43,36 @@ pub struct AiEntity {
     pub position_y: f32,
     pub state: AiState,
     pub territory: u32, // Number of grid spaces owned
+    pub team_id: Option<u32>, // Teammates never attack each other; None plays for itself
+    pub overlord: Option<u32>, // Entity this one pays tribute to as its vassal; an overlord never attacks its vassals
+    #[serde(skip)]
+    pub victory_points: u32, // Victory point cells among the owned grid spaces
+    pub victory_hold: u64, // Ticks in a row this entity has held the victory points the win condition needs
+    #[serde(skip)]
+    pub money_yield: f32, // Owned grid spaces weighted by their terrain and gold mines
+    #[serde(skip)]
+    pub military_yield: f32, // Owned grid spaces weighted by their terrain and farms
+    #[serde(skip)]
+    pub resource_nodes: u32, // Gold mines and farms among the owned grid spaces
     pub money: f32,
+    pub morale: f32, // Multiplier on military strength in battle, between MORALE_MIN and MORALE_MAX
+    pub profile: BehaviorProfile, // Temperament the AI decides with
+    #[serde(skip)]
+    pub support_target: Option<u32>, // Ally whose home space this entity reinforces while Allied
+    #[serde(skip)]
+    pub attack_target: Option<u32>, // Neighbor whose cells this entity conquers while Attacking (None = unowned land)
+    #[serde(skip)]
+    pub capital: Option<u32>, // Grid space this entity's supply lines run from
+    #[serde(skip)]
+    pub fortify_target: Option<u32>, // Grid space this entity reinforces while Defending
+    #[serde(skip)]
+    pub attack_space: Option<u32>, // Frontier grid space this entity is closing in on while Attacking
+    #[serde(skip)]
+    pub command: Option<PlayerCommand>, // Current player order; entities with one bypass the AI
+    #[serde(skip)]
+    pub tribute_due: f32, // Tribute set aside this tick, handed to the overlord once every entity has updated
+    #[serde(skip)]
+    pub brain: u32, // Registry index of the brain deciding for this entity
     #[serde(skip)]
     rng_state: u32,
     #[serde(skip)]
@@ -43,14 +81,19 @@ pub struct AiEntity {
 
 impl AiEntity {
     pub fn new(id: u32) -> Self {
+        Self::with_half_extent(id, DEFAULT_WORLD_SIZE * 0.5)
+    }
+
+    /// Create an entity spawned inside a world spanning `-half_extent..half_extent`
+    pub fn with_half_extent(id: u32, half_extent: f32) -> Self {
         let id_seed = id as f32;
         
         // Deterministic position generation based on ID
         let x_seed = ((id_seed * 0.3371).sin() + (id_seed * 0.0157).sin()) * 0.5;
         let y_seed = ((id_seed * 0.4219).cos() + (id_seed * 0.0213).cos()) * 0.5;
 
-        let spawn_x = x_seed * 1200.0;
-        let spawn_y = y_seed * 1200.0;
+        let spawn_x = x_seed * half_extent;
+        let spawn_y = y_seed * half_extent;
 
         Self {
             id,
@@ -59,12 +102,58 @@ impl AiEntity {
             position_y: spawn_y,
             state: AiState::Idle,
             territory: 1, // All AIs start with 1 grid space
+            team_id: None,
+            overlord: None,
+            victory_points: 0,
+            victory_hold: 0,
+            money_yield: 1.0,
+            military_yield: 1.0,
+            resource_nodes: 0,
             money: 0.0,   // All AIs start with 0 money
+            morale: 1.0,
+            profile: BehaviorProfile::for_id(id), // A mix of temperaments across the world
+            support_target: None,
+            attack_target: None,
+            capital: None,
+            fortify_target: None,
+            attack_space: None,
+            command: None,
+            tribute_due: 0.0,
+            brain: 0, // The greedy brain
             rng_state: Self::seed_rng(id),
             last_update_time: 0.0,
         }
     }
 
+    /// Military strength as it counts in battle, scaled by morale
+    pub fn effective_strength(&self) -> f32 {
+        self.military_strength * self.morale
+    }
+
+    /// Raise or lower morale, keeping it between `MORALE_MIN` and `MORALE_MAX`
+    pub fn adjust_morale(&mut self, delta: f32) {
+        self.morale = (self.morale + delta).clamp(MORALE_MIN, MORALE_MAX);
+    }
+
+    /// Money owed per second for the territory and military currently held
+    pub fn upkeep_per_sec(&self) -> f32 {
+        UPKEEP_PER_SPACE_PER_SEC * self.territory as f32 + UPKEEP_PER_MILITARY_PER_SEC * self.military_strength
+    }
+
+    /// Turn `MILITARY_PRICE` money per point into `amount` military strength
+    pub fn buy_military(&mut self, amount: f32) -> Result<(), String> {
+        if !(amount.is_finite() && amount > 0.0) {
+            return Err("Military to buy must be positive".to_string());
+        }
+        let price = amount * MILITARY_PRICE;
+        if price > self.money {
+            return Err(format!("Entity {} has only {} money, {} needed", self.id, self.money, price));
+        }
+        self.money -= price;
+        self.military_strength += amount;
+        Ok(())
+    }
+
     #[inline]
     pub fn next_random(&mut self) -> f32 {
         let mut x = self.rng_state;
@@ -91,6 +180,14 @@ impl AiEntity {
         self.next_random() * 2.0 - 1.0
     }
 
+    pub fn rng_state(&self) -> u32 {
+        self.rng_state
+    }
+
+    pub fn set_rng_state(&mut self, rng_state: u32) {
+        self.rng_state = if rng_state == 0 { 1 } else { rng_state };
+    }
+
     fn seed_rng(id: u32) -> u32 {
         let mut seed = id.wrapping_mul(747_796_405).wrapping_add(2_891_336_453) ^ 0xA511_E9B3;
         if seed == 0 {
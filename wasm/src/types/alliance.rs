@@ -0,0 +1,62 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Symmetric alliance relationships between grid entities
+///
+/// Backed by ordered collections so iteration (and therefore state hashing
+/// and sync bundles) is deterministic.
+#[derive(Debug, Clone, Default)]
+pub struct AllianceGraph {
+    allies: BTreeMap<u32, BTreeSet<u32>>,
+}
+
+impl AllianceGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Link two entities, returning `false` if they were already allied
+    pub fn add(&mut self, a: u32, b: u32) -> bool {
+        if a == b || self.are_allies(a, b) {
+            return false;
+        }
+        self.allies.entry(a).or_default().insert(b);
+        self.allies.entry(b).or_default().insert(a);
+        true
+    }
+
+    pub fn are_allies(&self, a: u32, b: u32) -> bool {
+        self.allies.get(&a).is_some_and(|set| set.contains(&b))
+    }
+
+    pub fn allies_of(&self, id: u32) -> impl Iterator<Item = u32> + '_ {
+        self.allies.get(&id).into_iter().flatten().copied()
+    }
+
+    /// Drop every alliance involving `id`
+    pub fn remove_entity(&mut self, id: u32) {
+        if let Some(partners) = self.allies.remove(&id) {
+            for partner in partners {
+                if let Some(set) = self.allies.get_mut(&partner) {
+                    set.remove(&id);
+                    if set.is_empty() {
+                        self.allies.remove(&partner);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Each alliance once, as `(lower_id, higher_id)` in ascending order
+    pub fn pairs(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.allies.iter().flat_map(|(&a, partners)| {
+            partners
+                .iter()
+                .filter(move |&&b| a < b)
+                .map(move |&b| (a, b))
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.allies.clear();
+    }
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+/// Detachment of an entity's military strength marching on its own
+///
+/// An army walks toward its objective grid space, tries to take it on
+/// arrival and then returns whatever strength is left to its owner, so an
+/// entity can fight on several fronts at once.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+pub struct Army {
+    pub id: u32,
+    pub owner_id: u32,
+    pub strength: f32,
+    pub position_x: f32,
+    pub position_y: f32,
+    pub objective: u32, // Grid space the army marches on
+}
@@ -0,0 +1,51 @@
+use serde::Serialize;
+
+/// Outcome of one headless run
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RunReport {
+    pub seed: u32,
+    pub entity_count: usize,
+    pub grid_size: usize,
+    /// Whether a single entity or overlord with its vassals (or none) was
+    /// left, or an entity won on victory points, before `max_ticks`
+    pub completed: bool,
+    /// Victory point winner, last entity standing or the overlord of those left,
+    /// only set for a completed run
+    pub winner_id: Option<u32>,
+    pub ticks: u64,
+    pub survivors: usize,
+    /// Survivors paying tribute to an overlord when the run stopped
+    pub vassals: usize,
+    /// Entity holding the most territory when the run stopped
+    pub leader_id: Option<u32>,
+    pub leader_territory: u32,
+    pub conquests: u32,
+    pub battles: u32,
+    pub deaths: u32,
+}
+
+impl RunReport {
+    pub const CSV_HEADER: &'static str = "seed,entity_count,grid_size,completed,winner_id,ticks,survivors,vassals,\
+                                          leader_id,leader_territory,conquests,battles,deaths";
+
+    /// One CSV line in `CSV_HEADER` order; a missing id is left empty
+    pub fn to_csv_row(&self) -> String {
+        let id = |id: Option<u32>| id.map(|id| id.to_string()).unwrap_or_default();
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.seed,
+            self.entity_count,
+            self.grid_size,
+            self.completed,
+            id(self.winner_id),
+            self.ticks,
+            self.survivors,
+            self.vassals,
+            id(self.leader_id),
+            self.leader_territory,
+            self.conquests,
+            self.battles,
+            self.deaths,
+        )
+    }
+}
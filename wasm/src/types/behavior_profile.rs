@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use crate::constants::{CAPITAL_DEFENSE_THREAT, MORALE_WAVER, UPKEEP_RESERVE_SECS};
+
+/// Temperament an AI plays with, deciding how readily it fights, fortifies and spends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum BehaviorProfile {
+    /// Marches on anyone the moment it can pay for the assault
+    #[default]
+    Aggressive = 0,
+    /// Holds its capital at the first sign of trouble and only fights with a wide margin
+    Turtle = 1,
+    /// Grows by claiming land, keeping its money unless it is threatened
+    Economic = 2,
+    /// Leaves the strong alone and falls on neighbors much weaker than itself
+    Opportunist = 3,
+}
+
+/// Decision thresholds of one `BehaviorProfile`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfileThresholds {
+    /// Nearby attackers, at full morale, that make the AI drop its plans to fortify its capital
+    pub capital_threat: f32,
+    /// Morale below which the AI won't march on a neighbor
+    pub waver_morale: f32,
+    /// Effective strength needed to attack or expand, in multiples of `ATTACK_COST`
+    pub attack_margin: f32,
+    /// Seconds of upkeep kept in hand when buying troops
+    pub upkeep_reserve_secs: f32,
+    /// Whether savings go on troops only under threat, rather than whenever there is fighting to do
+    pub hoards_money: bool,
+    /// Whether the AI starts wars on neighbors it holds no grudge against
+    pub picks_fights: bool,
+    /// Attack a neighbor whose strength is at most this fraction of the AI's own effective strength, 0 for never
+    pub prey_ratio: f32,
+}
+
+impl BehaviorProfile {
+    pub const ALL: [BehaviorProfile; 4] = [
+        BehaviorProfile::Aggressive,
+        BehaviorProfile::Turtle,
+        BehaviorProfile::Economic,
+        BehaviorProfile::Opportunist,
+    ];
+
+    /// Profile an entity starts with, cycling through every profile by id
+    pub fn for_id(id: u32) -> Self {
+        Self::ALL[id as usize % Self::ALL.len()]
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+
+    pub fn thresholds(self) -> ProfileThresholds {
+        let aggressive = ProfileThresholds {
+            capital_threat: CAPITAL_DEFENSE_THREAT as f32,
+            waver_morale: MORALE_WAVER,
+            attack_margin: 1.0,
+            upkeep_reserve_secs: UPKEEP_RESERVE_SECS,
+            hoards_money: false,
+            picks_fights: true,
+            prey_ratio: 0.0,
+        };
+        match self {
+            BehaviorProfile::Aggressive => aggressive,
+            BehaviorProfile::Turtle => ProfileThresholds {
+                capital_threat: 1.0,
+                waver_morale: 0.9,
+                attack_margin: 3.0,
+                picks_fights: false,
+                ..aggressive
+            },
+            BehaviorProfile::Economic => ProfileThresholds {
+                attack_margin: 2.0,
+                upkeep_reserve_secs: 6.0 * UPKEEP_RESERVE_SECS,
+                hoards_money: true,
+                picks_fights: false,
+                ..aggressive
+            },
+            BehaviorProfile::Opportunist => ProfileThresholds {
+                picks_fights: false,
+                prey_ratio: 0.5,
+                ..aggressive
+            },
+        }
+    }
+}
+
+impl FromStr for BehaviorProfile {
+    type Err = String;
+
+    fn from_str(profile: &str) -> Result<Self, Self::Err> {
+        match profile {
+            "aggressive" => Ok(BehaviorProfile::Aggressive),
+            "turtle" => Ok(BehaviorProfile::Turtle),
+            "economic" => Ok(BehaviorProfile::Economic),
+            "opportunist" => Ok(BehaviorProfile::Opportunist),
+            _ => Err(format!(
+                "Unknown behavior profile {:?}, expected aggressive, turtle, economic or opportunist",
+                profile
+            )),
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+
+use super::ai_entity::{AiEntity, AiState};
+use super::snapshot::SnapshotField;
+use crate::utils::crc32;
+
+pub const BINARY_SNAPSHOT_VERSION: u8 = 1;
+
+/// Bytes before the field ids: version, tick, entity count and field count
+const HEADER_LEN: usize = 1 + 8 + 4 + 1;
+const CRC_LEN: usize = 4;
+
+/// Entity read back from a binary snapshot; fields missing from its schema stay zeroed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BinaryEntity {
+    pub id: u32,
+    pub military_strength: f32,
+    pub money: f32,
+    pub territory: u32,
+    pub state: AiState,
+    pub position_x: f32,
+    pub position_y: f32,
+    pub morale: f32,
+}
+
+impl Default for BinaryEntity {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            military_strength: 0.0,
+            money: 0.0,
+            territory: 0,
+            state: AiState::Idle,
+            position_x: 0.0,
+            position_y: 0.0,
+            morale: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BinarySnapshot {
+    pub tick: u64,
+    pub fields: Vec<SnapshotField>,
+    pub entities: Vec<BinaryEntity>,
+}
+
+/// Encode `entities` as a compact little-endian snapshot:
+///
+/// ```text
+/// version      u8   (BINARY_SNAPSHOT_VERSION)
+/// tick         u64
+/// entity_count u32
+/// field_count  u8
+/// field ids    u8 × field_count
+/// records      entity_count × fields, u32/f32 values in 4 bytes and the state in 1
+/// crc32        u32  over every byte before it
+/// ```
+pub fn encode_snapshot_v1(tick: u64, entities: &[AiEntity], fields: &[SnapshotField]) -> Vec<u8> {
+    let record_len: usize = fields.iter().map(|field| field.width()).sum();
+    let mut bytes = Vec::with_capacity(HEADER_LEN + fields.len() + entities.len() * record_len + CRC_LEN);
+    bytes.push(BINARY_SNAPSHOT_VERSION);
+    bytes.extend_from_slice(&tick.to_le_bytes());
+    bytes.extend_from_slice(&(entities.len() as u32).to_le_bytes());
+    bytes.push(fields.len() as u8);
+    bytes.extend(fields.iter().map(|&field| field as u8));
+
+    for entity in entities {
+        for field in fields {
+            match field {
+                SnapshotField::Id => bytes.extend_from_slice(&entity.id.to_le_bytes()),
+                SnapshotField::MilitaryStrength => bytes.extend_from_slice(&entity.military_strength.to_le_bytes()),
+                SnapshotField::Money => bytes.extend_from_slice(&entity.money.to_le_bytes()),
+                SnapshotField::Territory => bytes.extend_from_slice(&entity.territory.to_le_bytes()),
+                SnapshotField::State => bytes.push(entity.state as u8),
+                SnapshotField::PositionX => bytes.extend_from_slice(&entity.position_x.to_le_bytes()),
+                SnapshotField::PositionY => bytes.extend_from_slice(&entity.position_y.to_le_bytes()),
+                SnapshotField::Morale => bytes.extend_from_slice(&entity.morale.to_le_bytes()),
+            }
+        }
+    }
+
+    let crc = crc32(&bytes);
+    bytes.extend_from_slice(&crc.to_le_bytes());
+    bytes
+}
+
+/// Decode a snapshot written by `encode_snapshot_v1`, rejecting unknown
+/// versions, truncated data, checksum mismatches, empty or repeated field
+/// lists and unknown entity states
+pub fn decode_snapshot_v1(bytes: &[u8]) -> Result<BinarySnapshot, String> {
+    if bytes.len() < HEADER_LEN + CRC_LEN {
+        return Err(format!("Binary snapshot is too short: {} bytes", bytes.len()));
+    }
+    let (body, crc) = bytes.split_at(bytes.len() - CRC_LEN);
+    let expected = u32::from_le_bytes([crc[0], crc[1], crc[2], crc[3]]);
+    if crc32(body) != expected {
+        return Err("Binary snapshot checksum mismatch".to_string());
+    }
+
+    let mut reader = ByteReader::new(body);
+    let version = reader.u8()?;
+    if version != BINARY_SNAPSHOT_VERSION {
+        return Err(format!(
+            "Unsupported binary snapshot version {} (expected {})",
+            version, BINARY_SNAPSHOT_VERSION
+        ));
+    }
+    let tick = reader.u64()?;
+    let entity_count = reader.u32()? as usize;
+    let field_count = reader.u8()? as usize;
+    if field_count == 0 {
+        return Err("Binary snapshot lists no fields".to_string());
+    }
+    let mut fields = Vec::with_capacity(field_count);
+    for _ in 0..field_count {
+        let id = reader.u8()?;
+        let field = SnapshotField::from_u8(id).ok_or_else(|| format!("Unknown snapshot field {}", id))?;
+        if fields.contains(&field) {
+            return Err(format!("Snapshot field {} is listed twice", id));
+        }
+        fields.push(field);
+    }
+
+    // Checked, since a forged entity count overflows a 32-bit usize
+    let record_len: usize = fields.iter().map(|field| field.width()).sum();
+    if entity_count.checked_mul(record_len) != Some(reader.remaining()) {
+        return Err(format!(
+            "Binary snapshot holds {} record bytes, expected {} per entity for {} entities",
+            reader.remaining(),
+            record_len,
+            entity_count
+        ));
+    }
+
+    let mut entities = Vec::with_capacity(entity_count);
+    for _ in 0..entity_count {
+        let mut entity = BinaryEntity::default();
+        for field in &fields {
+            match field {
+                SnapshotField::Id => entity.id = reader.u32()?,
+                SnapshotField::MilitaryStrength => entity.military_strength = reader.f32()?,
+                SnapshotField::Money => entity.money = reader.f32()?,
+                SnapshotField::Territory => entity.territory = reader.u32()?,
+                SnapshotField::State => entity.state = decode_state(reader.u8()?)?,
+                SnapshotField::PositionX => entity.position_x = reader.f32()?,
+                SnapshotField::PositionY => entity.position_y = reader.f32()?,
+                SnapshotField::Morale => entity.morale = reader.f32()?,
+            }
+        }
+        entities.push(entity);
+    }
+
+    Ok(BinarySnapshot { tick, fields, entities })
+}
+
+fn decode_state(value: u8) -> Result<AiState, String> {
+    if value > AiState::Allied as u8 {
+        return Err(format!("Unknown entity state {}", value));
+    }
+    Ok(AiState::from(value as u32))
+}
+
+/// Little-endian reader over a byte slice, shared by the binary formats
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let end = self.offset + N;
+        let slice = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or("Binary data ended unexpectedly")?;
+        self.offset = end;
+        let mut out = [0u8; N];
+        out.copy_from_slice(slice);
+        Ok(out)
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, String> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, String> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn f32(&mut self) -> Result<f32, String> {
+        self.take().map(f32::from_le_bytes)
+    }
+}
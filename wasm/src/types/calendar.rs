@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+/// Quarter of the world calendar's year
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [Season::Spring, Season::Summer, Season::Autumn, Season::Winter];
+}
+
+/// How a season changes the fighting and the economy
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct SeasonEffects {
+    /// Multiplier on the strength attackers and armies strike with
+    pub attack_factor: f32,
+    /// Multiplier on the money owned land generates
+    pub money_factor: f32,
+}
+
+impl SeasonEffects {
+    pub const NEUTRAL: SeasonEffects = SeasonEffects {
+        attack_factor: 1.0,
+        money_factor: 1.0,
+    };
+
+    fn validate(&self, season: &str) -> Result<(), String> {
+        if !(self.attack_factor.is_finite() && self.attack_factor > 0.0) {
+            return Err(format!("{} attack_factor must be positive", season));
+        }
+        if !(self.money_factor.is_finite() && self.money_factor >= 0.0) {
+            return Err(format!("{} money_factor must not be negative", season));
+        }
+        Ok(())
+    }
+}
+
+impl Default for SeasonEffects {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+/// Length of the seasons and what each of them does
+///
+/// The calendar starts in spring on tick 0. By default winter halves the
+/// strength of every attack and the autumn harvest brings in half as much
+/// money again.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct CalendarConfig {
+    pub ticks_per_season: u64,
+    pub spring: SeasonEffects,
+    pub summer: SeasonEffects,
+    pub autumn: SeasonEffects,
+    pub winter: SeasonEffects,
+}
+
+impl CalendarConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ticks_per_season == 0 {
+            return Err("ticks_per_season must be at least 1".to_string());
+        }
+        self.spring.validate("spring")?;
+        self.summer.validate("summer")?;
+        self.autumn.validate("autumn")?;
+        self.winter.validate("winter")
+    }
+
+    pub fn season(&self, tick: u64) -> Season {
+        Season::ALL[(tick / self.ticks_per_season.max(1) % 4) as usize]
+    }
+
+    pub fn effects(&self, season: Season) -> SeasonEffects {
+        match season {
+            Season::Spring => self.spring,
+            Season::Summer => self.summer,
+            Season::Autumn => self.autumn,
+            Season::Winter => self.winter,
+        }
+    }
+
+    /// Where `tick` falls in the calendar
+    pub fn date(&self, tick: u64) -> CalendarDate {
+        let ticks_per_season = self.ticks_per_season.max(1);
+        let season = self.season(tick);
+        CalendarDate {
+            tick,
+            year: tick / (ticks_per_season * 4),
+            season,
+            day: tick % ticks_per_season,
+            ticks_per_season,
+            effects: self.effects(season),
+        }
+    }
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            ticks_per_season: 1800,
+            spring: SeasonEffects::NEUTRAL,
+            summer: SeasonEffects::NEUTRAL,
+            autumn: SeasonEffects {
+                money_factor: 1.5,
+                ..SeasonEffects::NEUTRAL
+            },
+            winter: SeasonEffects {
+                attack_factor: 0.5,
+                ..SeasonEffects::NEUTRAL
+            },
+        }
+    }
+}
+
+/// The world calendar on a given tick, for display
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CalendarDate {
+    pub tick: u64,
+    /// Years gone by since tick 0
+    pub year: u64,
+    pub season: Season,
+    /// Ticks into the season, up to `ticks_per_season`
+    pub day: u64,
+    pub ticks_per_season: u64,
+    /// What the season does to attacks and money
+    pub effects: SeasonEffects,
+}
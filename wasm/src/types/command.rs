@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// Order from the frontend for a player-controlled entity
+///
+/// Serialized with a `kind` tag, e.g. `{"kind":"MoveTo","grid_index":12}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum PlayerCommand {
+    /// Walk toward the center of a grid space, then hold there
+    MoveTo { grid_index: u32 },
+    /// Conquer a grid space bordering our territory, then hold
+    Attack { grid_index: u32 },
+    /// Reinforce the grid space the entity stands on
+    Defend,
+    /// Stand still and accumulate resources
+    Hold,
+}
+
+impl PlayerCommand {
+    /// Kind and target as integers, for ordering and hashing commands the same way on every peer
+    pub fn key(&self) -> (u32, u32) {
+        match *self {
+            PlayerCommand::MoveTo { grid_index } => (0, grid_index),
+            PlayerCommand::Attack { grid_index } => (1, grid_index),
+            PlayerCommand::Defend => (2, 0),
+            PlayerCommand::Hold => (3, 0),
+        }
+    }
+
+    /// The same order, aimed at the grid space `remap` moves its target to
+    pub fn remap_grid_index(self, remap: impl Fn(u32) -> u32) -> Self {
+        match self {
+            PlayerCommand::MoveTo { grid_index } => PlayerCommand::MoveTo {
+                grid_index: remap(grid_index),
+            },
+            PlayerCommand::Attack { grid_index } => PlayerCommand::Attack {
+                grid_index: remap(grid_index),
+            },
+            PlayerCommand::Defend | PlayerCommand::Hold => self,
+        }
+    }
+
+    /// Inverse of `key`; None for an unknown kind
+    pub fn from_key(kind: u32, target: u32) -> Option<Self> {
+        match kind {
+            0 => Some(PlayerCommand::MoveTo { grid_index: target }),
+            1 => Some(PlayerCommand::Attack { grid_index: target }),
+            2 => Some(PlayerCommand::Defend),
+            3 => Some(PlayerCommand::Hold),
+            _ => None,
+        }
+    }
+}
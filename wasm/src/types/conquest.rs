@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+/// How many spaces an attacker may take in one tick and what each extra one costs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct ConquestConfig {
+    /// Spaces one attacker may conquer per tick, pushing on from each it takes
+    pub max_conquests_per_tick: u32,
+    /// Multiplier on the conquest cost for every conquest after the first in a tick
+    pub extra_conquest_cost_growth: f32,
+}
+
+impl ConquestConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_conquests_per_tick == 0 {
+            return Err("max_conquests_per_tick must be at least 1".to_string());
+        }
+        if !(self.extra_conquest_cost_growth.is_finite() && self.extra_conquest_cost_growth >= 1.0) {
+            return Err("extra_conquest_cost_growth must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Multiplier on the conquest cost of an attacker's `nth` conquest in a tick, from 0
+    pub fn cost_multiplier(&self, nth: u32) -> f32 {
+        self.extra_conquest_cost_growth.powi(nth as i32)
+    }
+}
+
+impl Default for ConquestConfig {
+    fn default() -> Self {
+        Self {
+            max_conquests_per_tick: 3,
+            extra_conquest_cost_growth: 2.0,
+        }
+    }
+}
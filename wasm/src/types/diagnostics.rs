@@ -0,0 +1,25 @@
+use serde::Serialize;
+
+/// Signs the simulation state has gone wrong, gathered after a tick
+///
+/// A healthy run reports nothing but, at worst, some spatial grid overflow,
+/// which only costs speed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Diagnostics {
+    pub tick: u64,
+    /// Entities that spilled past their spatial grid cell's inline slots on the last rebuild
+    pub spatial_overflow: usize,
+    /// Alive entities standing outside the world
+    pub out_of_bounds: Vec<u32>,
+    /// Grid spaces owned by dead or unknown entities
+    pub orphaned_cells: Vec<u32>,
+    /// Entities with negative or non-finite money or military strength
+    pub negative_resources: Vec<u32>,
+}
+
+impl Diagnostics {
+    /// Whether nothing is actually wrong; overflow alone doesn't count
+    pub fn is_clean(&self) -> bool {
+        self.out_of_bounds.is_empty() && self.orphaned_cells.is_empty() && self.negative_resources.is_empty()
+    }
+}
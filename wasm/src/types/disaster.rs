@@ -0,0 +1,143 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use crate::utils::grid_hash;
+
+/// Random event that can strike during a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisasterKind {
+    /// Cuts an entity's military generation for a while
+    Plague,
+    /// Drains an entity's money for a while
+    Famine,
+    /// Flattens the defenses around a grid space at once
+    Earthquake,
+}
+
+impl DisasterKind {
+    pub const ALL: [DisasterKind; 3] = [DisasterKind::Plague, DisasterKind::Famine, DisasterKind::Earthquake];
+
+    /// Keeps each kind's rolls independent of the others'
+    fn salt(self) -> u32 {
+        match self {
+            DisasterKind::Plague => 0x27d4_eb2f,
+            DisasterKind::Famine => 0x1656_67b1,
+            DisasterKind::Earthquake => 0xd3a2_646c,
+        }
+    }
+}
+
+impl FromStr for DisasterKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "plague" => Ok(DisasterKind::Plague),
+            "famine" => Ok(DisasterKind::Famine),
+            "earthquake" => Ok(DisasterKind::Earthquake),
+            _ => Err(format!("Unknown disaster kind {:?}, expected plague, famine or earthquake", kind)),
+        }
+    }
+}
+
+/// Plague or famine weighing on an entity until `ends_at`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Affliction {
+    pub kind: DisasterKind,
+    pub entity_id: u32,
+    /// First tick the entity is free of it
+    pub ends_at: u64,
+}
+
+/// How often disasters strike and how hard they hit
+///
+/// Every chance defaults to 0, so a run has no disasters until they are
+/// configured. Rolls are seeded, so peers with the same config see the same
+/// disasters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct EventConfig {
+    pub seed: u32,
+    /// Chance per disaster check that a plague strikes an alive entity
+    pub plague_chance: f32,
+    /// Chance per disaster check that a famine strikes an alive entity
+    pub famine_chance: f32,
+    /// Chance per disaster check that an earthquake strikes somewhere on the grid
+    pub earthquake_chance: f32,
+    /// Ticks a plague or famine lasts; striking again extends it
+    pub duration_ticks: u64,
+    /// Multiplier on a plagued entity's military generation
+    pub plague_military_factor: f32,
+    /// Money a famine drains from its entity every tick
+    pub famine_money_per_tick: f32,
+    /// Grid steps from its epicenter an earthquake reaches
+    pub earthquake_radius: u32,
+    /// Share of their defense and garrisons the spaces an earthquake reaches keep
+    pub earthquake_defense_factor: f32,
+}
+
+impl EventConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("plague_chance", self.plague_chance),
+            ("famine_chance", self.famine_chance),
+            ("earthquake_chance", self.earthquake_chance),
+            ("plague_military_factor", self.plague_military_factor),
+            ("earthquake_defense_factor", self.earthquake_defense_factor),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1", name));
+            }
+        }
+        if !(self.famine_money_per_tick.is_finite() && self.famine_money_per_tick >= 0.0) {
+            return Err("famine_money_per_tick must not be negative".to_string());
+        }
+        if self.duration_ticks == 0 {
+            return Err("duration_ticks must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn chance(&self, kind: DisasterKind) -> f32 {
+        match kind {
+            DisasterKind::Plague => self.plague_chance,
+            DisasterKind::Famine => self.famine_chance,
+            DisasterKind::Earthquake => self.earthquake_chance,
+        }
+    }
+
+    /// Whether a disaster of `kind` strikes on `tick`
+    pub fn strikes(&self, kind: DisasterKind, tick: u64) -> bool {
+        (self.roll(kind, tick, 0) as f32 / u32::MAX as f32) < self.chance(kind)
+    }
+
+    /// Which of `count` candidates a disaster of `kind` striking on `tick` hits
+    pub fn pick(&self, kind: DisasterKind, tick: u64, count: usize) -> usize {
+        self.roll(kind, tick, 1) as usize % count.max(1)
+    }
+
+    fn roll(&self, kind: DisasterKind, tick: u64, draw: i32) -> u32 {
+        grid_hash(self.seed ^ kind.salt() ^ (tick >> 32) as u32, tick as i32, draw)
+    }
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            plague_chance: 0.0,
+            famine_chance: 0.0,
+            earthquake_chance: 0.0,
+            duration_ticks: 300,
+            plague_military_factor: 0.5,
+            famine_money_per_tick: 0.05,
+            earthquake_radius: 2,
+            earthquake_defense_factor: 0.25,
+        }
+    }
+}
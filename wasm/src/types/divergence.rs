@@ -0,0 +1,56 @@
+use serde::Serialize;
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+/// How far two lockstep simulations had drifted apart at one tick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+pub struct DivergenceSample {
+    pub tick: u64,
+    /// Total variation distance between the two territory distributions, 0
+    /// for identical maps up to 1 for maps sharing no owner
+    pub territory_distance: f32,
+    pub alive_a: usize,
+    pub alive_b: usize,
+    /// `alive_a - alive_b`
+    pub alive_difference: i64,
+}
+
+/// Divergence of two simulations over every tick they were stepped together
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+pub struct DivergenceReport {
+    pub ticks: u64,
+    pub max_territory_distance: f32,
+    pub mean_territory_distance: f32,
+    /// Largest alive-count gap either way
+    pub max_alive_difference: u64,
+    /// First tick the territory or alive counts differed, if any did
+    pub first_divergent_tick: Option<u64>,
+    /// One sample per tick, oldest first
+    pub samples: Vec<DivergenceSample>,
+}
+
+impl DivergenceSample {
+    pub fn is_divergent(&self) -> bool {
+        self.territory_distance > 0.0 || self.alive_difference != 0
+    }
+}
+
+impl DivergenceReport {
+    pub fn from_samples(samples: &[DivergenceSample]) -> Self {
+        let total: f32 = samples.iter().map(|sample| sample.territory_distance).sum();
+        Self {
+            ticks: samples.last().map_or(0, |sample| sample.tick),
+            max_territory_distance: samples.iter().map(|sample| sample.territory_distance).fold(0.0, f32::max),
+            mean_territory_distance: if samples.is_empty() { 0.0 } else { total / samples.len() as f32 },
+            max_alive_difference: samples
+                .iter()
+                .map(|sample| sample.alive_difference.unsigned_abs())
+                .max()
+                .unwrap_or(0),
+            first_divergent_tick: samples.iter().find(|sample| sample.is_divergent()).map(|sample| sample.tick),
+            samples: samples.to_vec(),
+        }
+    }
+}
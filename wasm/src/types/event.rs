@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use super::disaster::DisasterKind;
+
+/// Notable things that happened during a tick, kept in a bounded log
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum SimulationEvent {
+    /// A grid space changed hands
+    Conquest {
+        tick: u64,
+        grid_index: u32,
+        attacker_id: u32,
+        previous_owner: Option<u32>,
+    },
+    /// An attack on an owned space was fought out, won or lost
+    Battle {
+        tick: u64,
+        grid_index: u32,
+        attacker_id: u32,
+        defender_id: u32,
+        success: bool,
+        attacker_losses: f32,
+        defender_losses: f32,
+    },
+    /// An AI lost its capital, and with it part of its land and resources
+    CapitalCaptured {
+        tick: u64,
+        grid_index: u32,
+        attacker_id: u32,
+        defender_id: u32,
+        spaces_transferred: u32,
+    },
+    /// An AI lost its last grid space
+    Death { tick: u64, entity_id: u32 },
+    /// A restless space rose up against its owner, defecting to the entity it
+    /// was taken from or, with `defected_to` unset, falling to rebels
+    Revolt {
+        tick: u64,
+        grid_index: u32,
+        owner_id: u32,
+        defected_to: Option<u32>,
+    },
+    /// A plague or famine struck `entity_id`, or an earthquake struck around `grid_index`
+    Disaster {
+        tick: u64,
+        disaster: DisasterKind,
+        entity_id: Option<u32>,
+        grid_index: Option<u32>,
+    },
+    /// A small AI submitted to a much larger neighbor, paying it tribute from now on
+    Vassalized { tick: u64, vassal_id: u32, overlord_id: u32 },
+    /// An AI held enough victory points for long enough to win the run
+    Victory { tick: u64, entity_id: u32 },
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+/// How even generated starting positions must be, and how hard the
+/// generator tries before settling for the fairest placement it found
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct FairnessConfig {
+    /// Walking distance around a start, in plains spaces, that counts as its neighborhood
+    pub radius: f32,
+    /// Largest allowed gap between the best and worst start on any measure,
+    /// as a fraction of the best
+    pub tolerance: f32,
+    /// Placements tried before settling; 1 keeps the first one
+    pub max_attempts: u32,
+}
+
+impl FairnessConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.radius.is_finite() && self.radius >= 0.0) {
+            return Err("radius must not be negative".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.tolerance) {
+            return Err("tolerance must be between 0 and 1".to_string());
+        }
+        if self.max_attempts == 0 {
+            return Err("max_attempts must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for FairnessConfig {
+    fn default() -> Self {
+        Self {
+            radius: 4.0,
+            tolerance: 0.5,
+            max_attempts: 8,
+        }
+    }
+}
+
+/// What one entity has to work with around its start
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StartFairness {
+    pub entity_id: u32,
+    pub grid_index: u32,
+    /// Spaces within walking distance, counting the start
+    pub reachable_area: u32,
+    /// Money and military multipliers of those spaces added up
+    pub resource_value: f32,
+    /// Grid steps to the closest other start, `None` when alone
+    pub nearest_neighbor: Option<u32>,
+}
+
+/// How evenly the starting positions are matched
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FairnessReport {
+    pub starts: Vec<StartFairness>,
+    /// Gap between the best and worst start on each measure, as a fraction of the best
+    pub area_spread: f32,
+    pub resource_spread: f32,
+    pub distance_spread: f32,
+    pub tolerance: f32,
+    /// Whether every spread is within the tolerance
+    pub fair: bool,
+    /// Placements the generator tried for the current starts, 0 for starts it didn't place
+    pub attempts: u32,
+}
+
+impl FairnessReport {
+    /// Largest of the three spreads
+    pub fn worst_spread(&self) -> f32 {
+        self.area_spread.max(self.resource_spread).max(self.distance_spread)
+    }
+}
@@ -0,0 +1,64 @@
+use crate::constants::MAX_TICKS_PER_FRAME;
+
+/// Fixed-timestep accumulator that turns frame timestamps into whole ticks,
+/// so the tick rate holds however often frames arrive
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameClock {
+    last_frame_ms: Option<f64>,
+    accumulator_ms: f64,
+}
+
+impl FrameClock {
+    /// Ticks due at `now_ms` at `tick_rate` ticks per second. The first frame
+    /// only starts the clock; after a stall at most `MAX_TICKS_PER_FRAME` ticks
+    /// are due and the rest of the backlog is dropped.
+    pub fn advance(&mut self, now_ms: f64, tick_rate: u32) -> u32 {
+        if !now_ms.is_finite() || tick_rate == 0 {
+            return 0;
+        }
+        let Some(last_ms) = self.last_frame_ms.replace(now_ms) else {
+            return 0;
+        };
+        let tick_ms = 1000.0 / tick_rate as f64;
+        self.accumulator_ms += (now_ms - last_ms).max(0.0);
+        let due = (self.accumulator_ms / tick_ms).floor();
+        if due > MAX_TICKS_PER_FRAME as f64 {
+            self.accumulator_ms = 0.0;
+            return MAX_TICKS_PER_FRAME;
+        }
+        self.accumulator_ms -= due * tick_ms;
+        due as u32
+    }
+
+    /// Forget the last frame, so time spent detached or paused isn't caught up
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_partial_ticks_across_frames() {
+        let mut clock = FrameClock::default();
+        assert_eq!(clock.advance(1000.0, 10), 0);
+        assert_eq!(clock.advance(1060.0, 10), 0);
+        assert_eq!(clock.advance(1120.0, 10), 1);
+        assert_eq!(clock.advance(1320.0, 10), 2);
+        assert_eq!(clock.advance(1300.0, 10), 0);  // Timestamps going backwards add nothing
+    }
+
+    #[test]
+    fn drops_backlog_after_a_stall() {
+        let mut clock = FrameClock::default();
+        clock.advance(0.0, 60);
+        assert_eq!(clock.advance(10_000.0, 60), MAX_TICKS_PER_FRAME);
+        assert_eq!(clock.advance(10_001.0, 60), 0);
+
+        clock.reset();
+        assert_eq!(clock.advance(20_000.0, 60), 0);
+        assert_eq!(clock.advance(f64::NAN, 60), 0);
+    }
+}
@@ -1,3 +1,14 @@
+use crate::constants::{ATTACK_COST, DEFENSE_BONUS_MULTIPLIER};
+
+use super::resource::ResourceNode;
+use super::terrain::{TerrainConfig, TerrainType};
+
+/// Owner id written to flat grid snapshots for unowned spaces
+pub const UNOWNED_GRID_OWNER: u32 = u32::MAX;
+
+/// Owner id grid snapshots report for unowned spaces held by a neutral garrison
+pub const NEUTRAL_GRID_OWNER: u32 = u32::MAX - 1;
+
 /// Represents a grid space in the world
 #[derive(Debug, Clone, Copy)]
 pub struct GridSpace {
@@ -5,6 +16,21 @@ pub struct GridSpace {
     pub owner_id: Option<u32>,
     /// Defense strength accumulated on this space
     pub defense_strength: f32,
+    /// Land covering this space, generated from the terrain seed
+    pub terrain: TerrainType,
+    /// Gold mine or farm on this space, generated from the resource seed
+    pub resource: Option<ResourceNode>,
+    /// How well the space is linked to its owner's capital, from 0 (cut off
+    /// long enough to be lost) to 1; scales the defense strength
+    pub supply: f32,
+    /// Defense strength of the neutral garrison holding this space while
+    /// unowned, 0 for none
+    pub garrison: f32,
+    /// How restless the space is under its owner, from 0 to 1; at the
+    /// unrest config's threshold it may revolt
+    pub unrest: f32,
+    /// Entity the owner took this space from, which it may defect back to
+    pub former_owner: Option<u32>,
 }
 
 impl GridSpace {
@@ -12,6 +38,12 @@ impl GridSpace {
         Self {
             owner_id: None,
             defense_strength: 0.0,
+            terrain: TerrainType::Plains,
+            resource: None,
+            supply: 1.0,
+            garrison: 0.0,
+            unrest: 0.0,
+            former_owner: None,
         }
     }
 
@@ -19,6 +51,37 @@ impl GridSpace {
         Self {
             owner_id: Some(owner_id),
             defense_strength,
+            terrain: TerrainType::Plains,
+            resource: None,
+            supply: 1.0,
+            garrison: 0.0,
+            unrest: 0.0,
+            former_owner: None,
+        }
+    }
+
+    /// Military strength an attacker spends to take this space
+    pub fn conquest_cost(&self, terrain: &TerrainConfig) -> f32 {
+        let base = match self.owner_id {
+            Some(_) => ATTACK_COST + self.defense_strength * self.supply * DEFENSE_BONUS_MULTIPLIER,
+            None => ATTACK_COST + self.garrison * DEFENSE_BONUS_MULTIPLIER,
+        };
+        base * (1.0 + terrain.effects(self.terrain).defense_bonus)
+    }
+
+    /// Owner as snapshots report it, `NEUTRAL_GRID_OWNER` for a garrisoned unowned space
+    pub fn displayed_owner(&self) -> Option<u32> {
+        match self.owner_id {
+            None if self.garrison > 0.0 => Some(NEUTRAL_GRID_OWNER),
+            owner => owner,
+        }
+    }
+
+    /// Defense strength as snapshots report it, the garrison's for an unowned space
+    pub fn displayed_defense(&self) -> f32 {
+        match self.owner_id {
+            Some(_) => self.defense_strength,
+            None => self.garrison,
         }
     }
 }
@@ -28,3 +91,19 @@ impl Default for GridSpace {
         Self::new()
     }
 }
+
+/// Grid spaces that changed since the last diff was taken, as parallel arrays
+#[derive(Debug, Clone, Default)]
+pub struct GridDiff {
+    pub indices: Vec<u32>,
+    pub owners: Vec<u32>,
+    pub defense: Vec<f32>,
+}
+
+impl GridDiff {
+    pub fn clear(&mut self) {
+        self.indices.clear();
+        self.owners.clear();
+        self.defense.clear();
+    }
+}
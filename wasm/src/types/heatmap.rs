@@ -0,0 +1,38 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Per-space measure a heatmap overlay shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapKind {
+    /// Defense as snapshots report it, the garrison's for unowned land
+    DefenseStrength,
+    /// Battles fought over the space
+    ContestedFrequency,
+    /// Battles fought over the space, fading a little every tick
+    RecentCombat,
+    /// Ticks in which the space changed hands
+    OwnershipChanges,
+    /// How restless the space is under its owner, 0 for unowned land
+    Unrest,
+}
+
+impl FromStr for HeatmapKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "defense_strength" => Ok(HeatmapKind::DefenseStrength),
+            "contested_frequency" => Ok(HeatmapKind::ContestedFrequency),
+            "recent_combat" => Ok(HeatmapKind::RecentCombat),
+            "ownership_changes" => Ok(HeatmapKind::OwnershipChanges),
+            "unrest" => Ok(HeatmapKind::Unrest),
+            _ => Err(format!(
+                "Unknown heatmap kind {:?}, expected defense_strength, contested_frequency, recent_combat, \
+                 ownership_changes or unrest",
+                kind
+            )),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::ai_entity::AiEntity;
+
+/// Stat the leaderboard ranks entities by, highest first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardKey {
+    #[default]
+    Territory,
+    Money,
+    MilitaryStrength,
+}
+
+impl LeaderboardKey {
+    pub fn value(self, entity: &AiEntity) -> f32 {
+        match self {
+            LeaderboardKey::Territory => entity.territory as f32,
+            LeaderboardKey::Money => entity.money,
+            LeaderboardKey::MilitaryStrength => entity.military_strength,
+        }
+    }
+
+    /// Leaderboard order: higher values first, ties to the lower id
+    pub fn compare(self, a: &AiEntity, b: &AiEntity) -> Ordering {
+        self.value(b).total_cmp(&self.value(a)).then(a.id.cmp(&b.id))
+    }
+}
+
+impl FromStr for LeaderboardKey {
+    type Err = String;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        match key {
+            "territory" => Ok(LeaderboardKey::Territory),
+            "money" => Ok(LeaderboardKey::Money),
+            "military_strength" => Ok(LeaderboardKey::MilitaryStrength),
+            _ => Err(format!(
+                "Unknown leaderboard key {:?}, expected territory, money or military_strength",
+                key
+            )),
+        }
+    }
+}
+
+/// One row of the leaderboard
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LeaderboardEntry {
+    pub id: u32,
+    pub territory: u32,
+    pub money: f32,
+    pub military_strength: f32,
+}
+
+impl From<&AiEntity> for LeaderboardEntry {
+    fn from(entity: &AiEntity) -> Self {
+        Self {
+            id: entity.id,
+            territory: entity.territory,
+            money: entity.money,
+            military_strength: entity.military_strength,
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use crate::constants::CAPITAL_START_DEFENSE;
+
+use super::behavior_profile::BehaviorProfile;
+use super::resource::ResourceNode;
+use super::terrain::TerrainType;
+
+/// Hand-authored starting position: the grid, the land on it and the
+/// entities that start on it
+///
+/// Grid indices are row-major and entity ids follow the order of `entities`.
+/// Layers left out are generated from the current terrain and resource
+/// configs, and unowned land is garrisoned from the neutral config as usual.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapDefinition {
+    pub grid_size: usize,
+    /// Terrain of every space
+    #[serde(default)]
+    pub terrain: Option<Vec<TerrainType>>,
+    /// Every resource node on the map
+    #[serde(default)]
+    pub resources: Option<Vec<MapResource>>,
+    #[serde(default)]
+    pub entities: Vec<MapEntity>,
+    /// Spaces owned from the start, on top of each entity's capital
+    #[serde(default)]
+    pub cells: Vec<MapCell>,
+    /// Grid indices of the victory point cells
+    #[serde(default)]
+    pub victory_points: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapResource {
+    pub grid_index: u32,
+    pub node: ResourceNode,
+}
+
+/// Entity spawned on its capital; fields left out keep an AI's usual start
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapEntity {
+    pub capital: u32,
+    #[serde(default)]
+    pub military_strength: Option<f32>,
+    #[serde(default)]
+    pub money: Option<f32>,
+    #[serde(default)]
+    pub profile: Option<BehaviorProfile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapCell {
+    pub grid_index: u32,
+    pub owner_id: u32,
+    #[serde(default = "default_cell_defense")]
+    pub defense_strength: f32,
+}
+
+fn default_cell_defense() -> f32 {
+    CAPITAL_START_DEFENSE
+}
+
+impl MapDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.grid_size == 0 {
+            return Err("grid_size must be at least 1".to_string());
+        }
+        let grid_len = self.grid_size * self.grid_size;
+        let check_index = |what: &str, grid_index: u32| {
+            if grid_index as usize >= grid_len {
+                return Err(format!(
+                    "{} grid index {} is outside the {}x{} grid",
+                    what, grid_index, self.grid_size, self.grid_size
+                ));
+            }
+            Ok(grid_index as usize)
+        };
+        if let Some(terrain) = &self.terrain {
+            if terrain.len() != grid_len {
+                return Err(format!("terrain covers {} spaces, expected {}", terrain.len(), grid_len));
+            }
+        }
+        let mut has_node = vec![false; grid_len];
+        for resource in self.resources.iter().flatten() {
+            let index = check_index("Resource", resource.grid_index)?;
+            if std::mem::replace(&mut has_node[index], true) {
+                return Err(format!("Grid space {} holds more than one resource node", index));
+            }
+        }
+        let mut claimed = vec![false; grid_len];
+        for (id, entity) in self.entities.iter().enumerate() {
+            let index = check_index("Capital", entity.capital)?;
+            if std::mem::replace(&mut claimed[index], true) {
+                return Err(format!("Entity {} starts on grid space {}, which is already taken", id, index));
+            }
+            for (name, value) in [("military_strength", entity.military_strength), ("money", entity.money)] {
+                if value.is_some_and(|value| !(value.is_finite() && value >= 0.0)) {
+                    return Err(format!("Entity {} {} must not be negative", id, name));
+                }
+            }
+        }
+        let mut owned = vec![false; grid_len];
+        for cell in &self.cells {
+            let index = check_index("Cell", cell.grid_index)?;
+            match self.entities.get(cell.owner_id as usize) {
+                None => return Err(format!("Cell {} is owned by unknown entity {}", index, cell.owner_id)),
+                Some(_) if std::mem::replace(&mut owned[index], true) => {
+                    return Err(format!("Grid space {} is listed more than once", index));
+                }
+                Some(owner) if claimed[index] && owner.capital as usize != index => {
+                    return Err(format!("Grid space {} is another entity's capital", index));
+                }
+                Some(_) => {}
+            }
+            if !(cell.defense_strength.is_finite() && cell.defense_strength >= 0.0) {
+                return Err(format!("Cell {} defense_strength must not be negative", index));
+            }
+        }
+        let mut scoring = vec![false; grid_len];
+        for &grid_index in &self.victory_points {
+            let index = check_index("Victory point", grid_index)?;
+            if std::mem::replace(&mut scoring[index], true) {
+                return Err(format!("Victory point {} is listed more than once", index));
+            }
+        }
+        Ok(())
+    }
+
+    /// The resource nodes as a layer covering every space
+    pub fn resource_layer(&self) -> Option<Vec<Option<ResourceNode>>> {
+        let resources = self.resources.as_ref()?;
+        let mut layer = vec![None; self.grid_size * self.grid_size];
+        for resource in resources {
+            layer[resource.grid_index as usize] = Some(resource.node);
+        }
+        Some(layer)
+    }
+}
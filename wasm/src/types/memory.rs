@@ -0,0 +1,25 @@
+use std::mem::size_of;
+
+use serde::Serialize;
+
+/// Heap held by a simulation's largest buffers, in bytes unless noted
+///
+/// Sizes follow each buffer's capacity, so they include space reserved but
+/// not in use; `shrink_to_fit` hands that back to the allocator.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct MemoryStats {
+    pub entity_count: usize,
+    pub entity_capacity: usize, // Entities the vector holds before it reallocates
+    pub entity_bytes: usize, // Entities, and their copies from the start of the run
+    pub grid_cells: usize,
+    pub grid_bytes: usize, // Grid spaces plus their owner/defense snapshots, dirty flags, diff and starting owners
+    pub snapshot_bytes: usize, // Entity snapshot buffer and the flat `Float32Array` records
+    pub event_count: usize,
+    pub event_log_bytes: usize,
+    pub total_bytes: usize, // Everything above plus smaller per-tick buffers
+}
+
+/// Bytes a vector has allocated, used or not
+pub fn vec_bytes<T>(items: &Vec<T>) -> usize {
+    items.capacity() * size_of::<T>()
+}
@@ -1,19 +1,124 @@
-#[derive(Clone, Copy, Debug, Default)]
+use serde::Serialize;
+
+use crate::constants::BENCHMARK_WINDOW;
+
+/// Summary of the durations currently held in a `DurationWindow`, in milliseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct DurationStats {
+    pub samples: usize,
+    pub last_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub p95_ms: f64,
+}
+
+/// Ring buffer of the most recent `BENCHMARK_WINDOW` durations
+#[derive(Clone, Debug, Default)]
+pub struct DurationWindow {
+    samples: Vec<f64>,
+    next: usize, // Slot the next sample overwrites once the buffer is full
+    last: f64,
+}
+
+impl DurationWindow {
+    pub fn record(&mut self, duration: f64) {
+        if self.samples.len() < BENCHMARK_WINDOW {
+            self.samples.push(duration);
+        } else {
+            self.samples[self.next] = duration;
+        }
+        self.next = (self.next + 1) % BENCHMARK_WINDOW;
+        self.last = duration;
+    }
+
+    pub fn last(&self) -> f64 {
+        self.last
+    }
+
+    pub fn stats(&self) -> DurationStats {
+        if self.samples.is_empty() {
+            return DurationStats::default();
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_by(f64::total_cmp);
+        let count = sorted.len();
+        // Nearest-rank percentile
+        let p95_rank = (count as f64 * 0.95).ceil() as usize;
+        DurationStats {
+            samples: count,
+            last_ms: self.last,
+            min_ms: sorted[0],
+            max_ms: sorted[count - 1],
+            mean_ms: sorted.iter().sum::<f64>() / count as f64,
+            p95_ms: sorted[p95_rank.max(1) - 1],
+        }
+    }
+}
+
+/// Rolling timings of the simulation
+///
+/// Each tick is also split into the entity update (commands, decisions and
+/// movement) and the conquest phase (attackers, battles and armies).
+#[derive(Clone, Debug, Default)]
 pub struct BenchmarkMetrics {
-    pub last_tick_duration_ms: f64,
-    pub last_snapshot_duration_ms: f64,
+    tick: DurationWindow,
+    snapshot: DurationWindow,
+    entity_update: DurationWindow,
+    conquest: DurationWindow,
+}
+
+/// Serializable statistics for every timed section
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct MetricsReport {
+    pub tick: DurationStats,
+    pub snapshot: DurationStats,
+    pub entity_update: DurationStats,
+    pub conquest: DurationStats,
+}
+
+/// How long each phase of a single tick took, in milliseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct TickProfile {
+    pub tick: u64,
+    pub snapshot_rebuild_ms: f64,
+    pub grid_rebuild_ms: f64,
+    pub entity_update_ms: f64, // Commands, decisions and movement
+    pub conquest_ms: f64,      // Attackers, battles and armies
+    pub death_processing_ms: f64,
+    pub territory_update_ms: f64,
+    pub total_ms: f64,
 }
 
 impl BenchmarkMetrics {
-    pub fn update_tick(&mut self, duration: f64) {
+    pub fn update_tick(&mut self, duration: f64, entity_update: f64, conquest: f64) {
         if duration >= 0.0 {
-            self.last_tick_duration_ms = duration;
+            self.tick.record(duration);
+            self.entity_update.record(entity_update.max(0.0));
+            self.conquest.record(conquest.max(0.0));
         }
     }
 
     pub fn update_snapshot(&mut self, duration: f64) {
         if duration >= 0.0 {
-            self.last_snapshot_duration_ms = duration;
+            self.snapshot.record(duration);
+        }
+    }
+
+    pub fn last_tick_duration_ms(&self) -> f64 {
+        self.tick.last()
+    }
+
+    pub fn last_snapshot_duration_ms(&self) -> f64 {
+        self.snapshot.last()
+    }
+
+    pub fn report(&self) -> MetricsReport {
+        MetricsReport {
+            tick: self.tick.stats(),
+            snapshot: self.snapshot.stats(),
+            entity_update: self.entity_update.stats(),
+            conquest: self.conquest.stats(),
         }
     }
 }
@@ -1,11 +1,91 @@
+//! Plain data shared by the simulation, its services and the wire formats
+//!
+//! Configs deserialize with `#[serde(default)]`: any field missing from the
+//! JSON takes its value from the config's `Default`.
+
 pub mod ai_entity;
+pub mod alliance;
+pub mod army;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
+pub mod behavior_profile;
+#[cfg_attr(not(feature = "bindings"), allow(dead_code))] // Decoded only for JS callers
+pub mod binary_snapshot;
+pub mod calendar;
+pub mod command;
+pub mod conquest;
+pub mod diagnostics;
+pub mod disaster;
+pub mod divergence;
+pub mod event;
+pub mod fairness;
+#[cfg(feature = "bindings")]
+pub mod frame_clock;
 pub mod grid_space;
+pub mod heatmap;
+pub mod leaderboard;
+pub mod map;
+pub mod memory;
 pub mod metrics;
+pub mod neutral;
+pub mod notable;
+pub mod relations;
+pub mod resize_policy;
+pub mod resource;
+pub mod run_config;
 pub mod snapshot;
+pub mod stats;
+pub mod sync_bundle;
+pub mod terrain;
+pub mod time_mode;
+pub mod timeline;
+pub mod unrest;
+pub mod victory;
+pub mod world_config;
 
 pub use ai_entity::{AiEntity, AiState};
-pub use grid_space::GridSpace;
-pub use metrics::BenchmarkMetrics;
+pub use alliance::AllianceGraph;
+pub use army::Army;
+#[cfg(not(target_arch = "wasm32"))]
+pub use batch::RunReport;
+pub use behavior_profile::BehaviorProfile;
+#[cfg_attr(not(feature = "bindings"), allow(unused_imports))]
+pub use binary_snapshot::{decode_snapshot_v1, encode_snapshot_v1};
+pub use calendar::{CalendarConfig, CalendarDate, Season, SeasonEffects};
+pub use command::PlayerCommand;
+pub use conquest::ConquestConfig;
+pub use diagnostics::Diagnostics;
+pub use disaster::{Affliction, DisasterKind, EventConfig};
+pub use divergence::{DivergenceReport, DivergenceSample};
+pub use event::SimulationEvent;
+pub use fairness::{FairnessConfig, FairnessReport, StartFairness};
+#[cfg(feature = "bindings")]
+pub use frame_clock::FrameClock;
+pub use grid_space::{GridDiff, GridSpace, UNOWNED_GRID_OWNER};
+pub use heatmap::HeatmapKind;
+pub use leaderboard::{LeaderboardEntry, LeaderboardKey};
+pub use map::{MapCell, MapDefinition, MapEntity, MapResource};
+pub use memory::{vec_bytes, MemoryStats};
+pub use metrics::{BenchmarkMetrics, MetricsReport, TickProfile};
+pub use neutral::NeutralConfig;
+pub use notable::NotableEvent;
+pub use relations::Relations;
+pub use resize_policy::ResizePolicy;
+pub use resource::ResourceConfig;
+pub use run_config::RunConfig;
 pub use snapshot::{
-    EntitySnapshot, PublicEntitySnapshot, SimulationSnapshot, SNAPSHOT_FIELD_COUNT,
+    EntityDetails, EntitySnapshot, EntityStats, PublicEntitySnapshot, SimulationSnapshot, SnapshotField,
+    SnapshotSchema, ViewportCell, ViewportSnapshot, SNAPSHOT_FIELD_COUNT,
+};
+pub use stats::{SimStats, TickStats};
+pub use sync_bundle::{
+    decode_grid_runs, encode_grid_runs, SyncBundle, SyncedEntity, SYNC_BUNDLE_VERSION,
 };
+pub use terrain::TerrainConfig;
+#[cfg(feature = "bindings")]
+pub use time_mode::ResourceClock;
+pub use time_mode::TimeMode;
+pub use timeline::EntityTimeline;
+pub use unrest::UnrestConfig;
+pub use victory::WinCondition;
+pub use world_config::WorldConfig;
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use crate::utils::grid_hash;
+
+use super::terrain::TerrainType;
+
+/// Keeps garrison placement independent of terrain and resource seeds with the same value
+const NEUTRAL_SEED_SALT: u32 = 0x9e37_79b9;
+
+/// Where neutral garrisons hold unowned land and how tough they get
+///
+/// Garrisons belong to no entity: they never expand, never attack and never
+/// count towards winning, they only make land cost more to claim.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct NeutralConfig {
+    pub seed: u32,
+    /// Chance that an unclaimed land space starts with a garrison
+    pub garrison_chance: f32,
+    /// Defense strength a garrison starts with
+    pub initial_defense: f32,
+    /// Defense strength every garrison gains per tick, so later expansion costs more
+    pub defense_growth_per_tick: f32,
+    /// Cap on a garrison's defense strength
+    pub max_defense: f32,
+}
+
+impl NeutralConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.garrison_chance) {
+            return Err("garrison_chance must be between 0 and 1".to_string());
+        }
+        for (name, value) in [
+            ("initial_defense", self.initial_defense),
+            ("defense_growth_per_tick", self.defense_growth_per_tick),
+            ("max_defense", self.max_defense),
+        ] {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(format!("{} must not be negative", name));
+            }
+        }
+        if self.initial_defense > self.max_defense {
+            return Err("initial_defense must not exceed max_defense".to_string());
+        }
+        Ok(())
+    }
+
+    /// Starting garrison on a space of a square grid, 0 for none; water never holds one
+    pub fn garrison(&self, index: usize, grid_size: usize, terrain: TerrainType) -> f32 {
+        if terrain == TerrainType::Water {
+            return 0.0;
+        }
+        let (row, col) = (index / grid_size, index % grid_size);
+        let roll = grid_hash(self.seed ^ NEUTRAL_SEED_SALT, col as i32, row as i32) as f32 / u32::MAX as f32;
+        if roll < self.garrison_chance {
+            self.initial_defense
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for NeutralConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            garrison_chance: 0.3,
+            initial_defense: 2.0,
+            defense_growth_per_tick: 0.01,
+            max_defense: 20.0,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+/// Highlight worth showing players, picked out of a run's events
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "kind")]
+pub enum NotableEvent {
+    /// The first space one entity took from another
+    FirstBlood {
+        tick: u64,
+        grid_index: u32,
+        attacker_id: u32,
+        defender_id: u32,
+    },
+    /// An entity fell below `NOTABLE_COMEBACK_LOW` of all held territory and
+    /// climbed back to `NOTABLE_COMEBACK_HIGH`
+    Comeback {
+        tick: u64,
+        entity_id: u32,
+        low_tick: u64,
+        low_share: f32,
+    },
+    /// The quickest `NOTABLE_EXPANSION_SPACES` conquests by one entity so far
+    FastestExpansion {
+        tick: u64,
+        entity_id: u32,
+        ticks: u64, // Ticks between the first and last of the conquests
+    },
+}
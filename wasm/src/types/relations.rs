@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::RELATIONS_LIMIT;
+
+/// How one pair of entities regard each other
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Relation {
+    pub score: f32, // -RELATIONS_LIMIT (sworn rivals) to RELATIONS_LIMIT (firm friends), 0 neutral
+    pub last_clash: Option<u64>, // Tick of the last assault either made on the other
+}
+
+impl Relation {
+    const NEUTRAL: Relation = Relation {
+        score: 0.0,
+        last_clash: None,
+    };
+}
+
+/// Symmetric relations between grid entities
+///
+/// Only pairs that have bordered or fought are stored; every other pair is
+/// neutral. Keyed by `(lower_id, higher_id)` in an ordered map so iteration
+/// (and therefore state hashing and sync bundles) is deterministic.
+#[derive(Debug, Clone, Default)]
+pub struct Relations {
+    pairs: BTreeMap<(u32, u32), Relation>,
+}
+
+impl Relations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(a: u32, b: u32) -> (u32, u32) {
+        (a.min(b), a.max(b))
+    }
+
+    pub fn get(&self, a: u32, b: u32) -> f32 {
+        self.pairs.get(&Self::key(a, b)).map_or(0.0, |relation| relation.score)
+    }
+
+    /// Sour relations after `a` and `b` fought on `tick`
+    pub fn record_clash(&mut self, a: u32, b: u32, tick: u64, drop: f32) {
+        if a == b {
+            return;
+        }
+        let relation = self.pairs.entry(Self::key(a, b)).or_insert(Relation::NEUTRAL);
+        relation.score = (relation.score - drop).max(-RELATIONS_LIMIT);
+        relation.last_clash = Some(tick);
+    }
+
+    /// Warm every pair in `neighbors` or with a history by `gain`, unless
+    /// they fought within the last `interval` ticks
+    pub fn keep_peace(&mut self, tick: u64, interval: u64, neighbors: impl IntoIterator<Item = (u32, u32)>, gain: f32) {
+        for (a, b) in neighbors {
+            if a != b {
+                self.pairs.entry(Self::key(a, b)).or_insert(Relation::NEUTRAL);
+            }
+        }
+        for relation in self.pairs.values_mut() {
+            if relation.last_clash.is_none_or(|clash| tick.saturating_sub(clash) >= interval) {
+                relation.score = (relation.score + gain).min(RELATIONS_LIMIT);
+            }
+        }
+    }
+
+    /// Set a pair's relation outright, as when restoring a sync bundle
+    pub fn insert(&mut self, a: u32, b: u32, relation: Relation) {
+        if a != b {
+            self.pairs.insert(Self::key(a, b), relation);
+        }
+    }
+
+    /// Forget every relation involving `id`
+    pub fn remove_entity(&mut self, id: u32) {
+        self.pairs.retain(|&(a, b), _| a != id && b != id);
+    }
+
+    /// Each stored pair once, as `(lower_id, higher_id, relation)` in ascending order
+    pub fn pairs(&self) -> impl Iterator<Item = (u32, u32, Relation)> + '_ {
+        self.pairs.iter().map(|(&(a, b), &relation)| (a, b, relation))
+    }
+
+    /// Scores of `entity_count` entities as a row-major square matrix, with
+    /// each entity neutral toward itself
+    pub fn matrix(&self, entity_count: usize) -> Vec<f32> {
+        let mut matrix = vec![0.0; entity_count * entity_count];
+        for (a, b, relation) in self.pairs() {
+            let (a, b) = (a as usize, b as usize);
+            if b < entity_count {
+                matrix[a * entity_count + b] = relation.score;
+                matrix[b * entity_count + a] = relation.score;
+            }
+        }
+        matrix
+    }
+
+    pub fn clear(&mut self) {
+        self.pairs.clear();
+    }
+}
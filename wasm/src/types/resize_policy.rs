@@ -0,0 +1,28 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// How a resized grid picks the owner of each new space from the old
+/// spaces covering the same ground
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizePolicy {
+    /// The old space under the new space's center
+    #[default]
+    Nearest,
+    /// Whoever held most of the old spaces it overlaps, ties going to the
+    /// one under its center
+    Majority,
+}
+
+impl FromStr for ResizePolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "nearest" => Ok(ResizePolicy::Nearest),
+            "majority" => Ok(ResizePolicy::Majority),
+            _ => Err(format!("Unknown resize policy {:?}, expected nearest or majority", policy)),
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use crate::utils::grid_hash;
+
+use super::grid_space::GridSpace;
+use super::terrain::{TerrainConfig, TerrainType};
+
+/// Keeps resource placement independent of a terrain seed with the same value
+const RESOURCE_SEED_SALT: u32 = 0x5bd1_e995;
+
+/// Special resource found on a grid space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum ResourceNode {
+    /// Multiplies the money its owner earns from the space
+    GoldMine = 1,
+    /// Multiplies the military strength its owner raises from the space
+    Farm = 2,
+}
+
+/// Where resource nodes spawn and how much they are worth
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct ResourceConfig {
+    pub seed: u32,
+    /// Chance that a land space holds a gold mine
+    pub gold_mine_chance: f32,
+    /// Chance that a land space without a gold mine holds a farm
+    pub farm_chance: f32,
+    pub gold_mine_money_multiplier: f32,
+    pub farm_military_multiplier: f32,
+}
+
+impl ResourceConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, chance) in [
+            ("gold_mine_chance", self.gold_mine_chance),
+            ("farm_chance", self.farm_chance),
+        ] {
+            if !(0.0..=1.0).contains(&chance) {
+                return Err(format!("{} must be between 0 and 1", name));
+            }
+        }
+        for (name, multiplier) in [
+            ("gold_mine_money_multiplier", self.gold_mine_money_multiplier),
+            ("farm_military_multiplier", self.farm_military_multiplier),
+        ] {
+            if !(multiplier.is_finite() && multiplier >= 0.0) {
+                return Err(format!("{} must not be negative", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resource node for every space of a square grid, row-major; water never
+    /// holds one
+    pub fn generate(&self, terrain: &[TerrainType], grid_size: usize) -> Vec<Option<ResourceNode>> {
+        let seed = self.seed ^ RESOURCE_SEED_SALT;
+        terrain
+            .iter()
+            .enumerate()
+            .map(|(index, &terrain)| {
+                if terrain == TerrainType::Water {
+                    return None;
+                }
+                let (row, col) = (index / grid_size, index % grid_size);
+                let roll = grid_hash(seed, col as i32, row as i32) as f32 / u32::MAX as f32;
+                if roll < self.gold_mine_chance {
+                    Some(ResourceNode::GoldMine)
+                } else if roll < self.gold_mine_chance + self.farm_chance {
+                    Some(ResourceNode::Farm)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Money and military strength multipliers a space gives its owner,
+    /// combining its terrain and resource node
+    pub fn space_yields(&self, space: &GridSpace, terrain: &TerrainConfig) -> (f32, f32) {
+        let base = terrain.effects(space.terrain).resource_multiplier;
+        match space.resource {
+            Some(ResourceNode::GoldMine) => (base * self.gold_mine_money_multiplier, base),
+            Some(ResourceNode::Farm) => (base, base * self.farm_military_multiplier),
+            None => (base, base),
+        }
+    }
+}
+
+impl Default for ResourceConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            gold_mine_chance: 0.02,
+            farm_chance: 0.05,
+            gold_mine_money_multiplier: 3.0,
+            farm_military_multiplier: 2.0,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use super::calendar::CalendarConfig;
+use super::disaster::EventConfig;
+use super::neutral::NeutralConfig;
+use super::resource::ResourceConfig;
+use super::terrain::TerrainConfig;
+use super::unrest::UnrestConfig;
+use super::world_config::WorldConfig;
+
+/// Setup of one headless run, in a batch or a comparison
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunConfig {
+    /// Seeds every entity's RNG
+    pub seed: u32,
+    pub entity_count: usize,
+    pub world: WorldConfig,
+    pub terrain: TerrainConfig,
+    pub resources: ResourceConfig,
+    pub neutrals: NeutralConfig,
+    pub unrest: UnrestConfig,
+    pub events: EventConfig,
+    pub calendar: CalendarConfig,
+    /// Synthetic clock step, so income does not depend on machine speed
+    pub ms_per_tick: f64,
+    /// Ticks after which a run that has not finished is stopped
+    pub max_ticks: u64,
+}
+
+impl RunConfig {
+    /// Same setup with `seed` driving the entities, terrain, resources, neutral garrisons, revolts and disasters
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self.terrain.seed = seed;
+        self.resources.seed = seed;
+        self.neutrals.seed = seed;
+        self.unrest.seed = seed;
+        self.events.seed = seed;
+        self
+    }
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            entity_count: 10,
+            world: WorldConfig::default(),
+            terrain: TerrainConfig::default(),
+            resources: ResourceConfig::default(),
+            neutrals: NeutralConfig::default(),
+            unrest: UnrestConfig::default(),
+            events: EventConfig::default(),
+            calendar: CalendarConfig::default(),
+            ms_per_tick: 100.0,
+            max_ticks: 10_000,
+        }
+    }
+}
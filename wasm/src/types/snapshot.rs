@@ -1,41 +1,273 @@
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
 
 use super::ai_entity::{AiEntity, AiState};
+use super::army::Army;
+use super::behavior_profile::BehaviorProfile;
+use super::command::PlayerCommand;
 
-pub const SNAPSHOT_FIELD_COUNT: usize = 7;
+/// Number of selectable snapshot fields; the flat snapshot stride when all are selected
+pub const SNAPSHOT_FIELD_COUNT: usize = 8;
+
+/// Per-entity value in the flat and binary snapshots
+///
+/// The discriminant is the field's bit in `set_snapshot_fields` masks and its
+/// id in binary snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum SnapshotField {
+    Id = 0,
+    MilitaryStrength = 1,
+    Money = 2,
+    Territory = 3,
+    State = 4,
+    PositionX = 5,
+    PositionY = 6,
+    Morale = 7,
+}
+
+impl SnapshotField {
+    /// Every field, in snapshot order
+    pub const ALL: [SnapshotField; SNAPSHOT_FIELD_COUNT] = [
+        SnapshotField::Id,
+        SnapshotField::MilitaryStrength,
+        SnapshotField::Money,
+        SnapshotField::Territory,
+        SnapshotField::State,
+        SnapshotField::PositionX,
+        SnapshotField::PositionY,
+        SnapshotField::Morale,
+    ];
+
+    /// Mask selecting every field
+    pub const ALL_MASK: u32 = (1 << SNAPSHOT_FIELD_COUNT) - 1;
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    pub fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    /// Fields whose bits are set, in snapshot order, or `None` if the mask is
+    /// empty or has unknown bits
+    pub fn from_mask(mask: u32) -> Option<Vec<SnapshotField>> {
+        if mask == 0 || mask & !Self::ALL_MASK != 0 {
+            return None;
+        }
+        Some(Self::ALL.into_iter().filter(|field| mask & field.bit() != 0).collect())
+    }
+
+    /// Encoded size of one value in a binary snapshot: the state fits a byte,
+    /// everything else is 32 bits
+    pub fn width(self) -> usize {
+        match self {
+            SnapshotField::State => 1,
+            _ => 4,
+        }
+    }
+
+    /// The field as stored in the flat snapshot
+    pub fn flat_value(self, entity: &AiEntity) -> f32 {
+        match self {
+            SnapshotField::Id => entity.id as f32,
+            SnapshotField::MilitaryStrength => entity.military_strength,
+            SnapshotField::Money => entity.money,
+            SnapshotField::Territory => entity.territory as f32,
+            SnapshotField::State => u32::from(entity.state) as f32,
+            SnapshotField::PositionX => entity.position_x,
+            SnapshotField::PositionY => entity.position_y,
+            SnapshotField::Morale => entity.morale,
+        }
+    }
+}
+
+/// Where a field sits in each flat snapshot record
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SnapshotFieldLayout {
+    pub field: SnapshotField,
+    pub bit: u32,
+    pub offset: usize,
+}
+
+/// Layout of the flat snapshot: `stride` floats per entity, fields at their offsets
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotSchema {
+    pub mask: u32,
+    pub stride: usize,
+    pub fields: Vec<SnapshotFieldLayout>,
+}
+
+impl SnapshotSchema {
+    pub fn new(fields: &[SnapshotField]) -> Self {
+        Self {
+            mask: fields.iter().fold(0, |mask, field| mask | field.bit()),
+            stride: fields.len(),
+            fields: fields
+                .iter()
+                .enumerate()
+                .map(|(offset, &field)| SnapshotFieldLayout {
+                    field,
+                    bit: field.bit(),
+                    offset,
+                })
+                .collect(),
+        }
+    }
+}
 
 #[derive(Clone, Copy)]
 pub struct EntitySnapshot {
+    pub id: u32,
     pub position_x: f32,
     pub position_y: f32,
     pub state: AiState,
     pub military_strength: f32,
+    pub overlord: Option<u32>,
 }
 
 impl From<&AiEntity> for EntitySnapshot {
     fn from(entity: &AiEntity) -> Self {
         Self {
+            id: entity.id,
             position_x: entity.position_x,
             position_y: entity.position_y,
             state: entity.state,
             military_strength: entity.military_strength,
+            overlord: entity.overlord,
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
 pub struct PublicEntitySnapshot {
     pub id: u32,
     pub military_strength: f32,
     pub position_x: f32,
     pub position_y: f32,
+    // AiState serializes as its discriminant, 0 Idle to 4 Allied
+    #[cfg_attr(feature = "bindings", tsify(type = "number"))]
     pub state: AiState,
     pub territory: u32,
+    pub team_id: Option<u32>,
     pub money: f32,
+    pub resource_nodes: u32, // Gold mines and farms held
+    pub victory_points: u32, // Victory point cells held
+    pub victory_hold: u64, // Ticks in a row enough victory points have been held to win
+    pub morale: f32,
+    pub profile: BehaviorProfile,
+    pub capital: Option<u32>, // Grid space the entity's supply lines run from
+    pub allies: Vec<u32>,
+    pub overlord: Option<u32>,
+    pub vassals: Vec<u32>,
+    pub armies: Vec<Army>,
 }
 
 pub type SimulationSnapshot = Vec<PublicEntitySnapshot>;
 
+/// Grid space inside a viewport
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+pub struct ViewportCell {
+    pub index: u32,
+    pub owner_id: Option<u32>,
+    pub defense_strength: f32,
+}
+
+/// Alive entities and grid spaces inside a camera rectangle
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+pub struct ViewportSnapshot {
+    pub entities: Vec<PublicEntitySnapshot>,
+    pub grid_cells: Vec<ViewportCell>,
+}
+
+/// Every field of a single entity, for debugging one AI
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityDetails {
+    pub id: u32,
+    pub military_strength: f32,
+    pub position_x: f32,
+    pub position_y: f32,
+    pub state: AiState,
+    pub territory: u32,
+    pub team_id: Option<u32>,
+    pub money: f32,
+    pub morale: f32,
+    pub profile: BehaviorProfile,
+    pub money_yield: f32,
+    pub military_yield: f32,
+    pub resource_nodes: u32,
+    pub victory_points: u32,
+    pub victory_hold: u64,
+    pub support_target: Option<u32>,
+    pub attack_target: Option<u32>,
+    pub fortify_target: Option<u32>,
+    pub attack_space: Option<u32>,
+    pub command: Option<PlayerCommand>,
+    pub allies: Vec<u32>,
+    pub overlord: Option<u32>,
+    pub vassals: Vec<u32>,
+    pub armies: Vec<Army>,
+}
+
+impl From<&AiEntity> for EntityDetails {
+    fn from(entity: &AiEntity) -> Self {
+        Self {
+            id: entity.id,
+            military_strength: entity.military_strength,
+            position_x: entity.position_x,
+            position_y: entity.position_y,
+            state: entity.state,
+            territory: entity.territory,
+            team_id: entity.team_id,
+            money: entity.money,
+            morale: entity.morale,
+            profile: entity.profile,
+            money_yield: entity.money_yield,
+            military_yield: entity.military_yield,
+            resource_nodes: entity.resource_nodes,
+            victory_points: entity.victory_points,
+            victory_hold: entity.victory_hold,
+            support_target: entity.support_target,
+            attack_target: entity.attack_target,
+            fortify_target: entity.fortify_target,
+            attack_space: entity.attack_space,
+            command: entity.command,
+            allies: Vec::new(),
+            overlord: entity.overlord,
+            vassals: Vec::new(),
+            armies: Vec::new(),
+        }
+    }
+}
+
+/// An entity's headline stats at the end of a tick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EntityStats {
+    pub tick: u64,
+    pub military_strength: f32,
+    pub money: f32,
+    pub territory: u32,
+    pub state: AiState,
+}
+
+impl EntityStats {
+    pub fn sample(entity: &AiEntity, tick: u64) -> Self {
+        Self {
+            tick,
+            military_strength: entity.military_strength,
+            money: entity.money,
+            territory: entity.territory,
+            state: entity.state,
+        }
+    }
+}
+
 impl From<&AiEntity> for PublicEntitySnapshot {
     fn from(entity: &AiEntity) -> Self {
         Self {
@@ -45,7 +277,18 @@ impl From<&AiEntity> for PublicEntitySnapshot {
             position_y: entity.position_y,
             state: entity.state,
             territory: entity.territory,
+            team_id: entity.team_id,
             money: entity.money,
+            resource_nodes: entity.resource_nodes,
+            victory_points: entity.victory_points,
+            victory_hold: entity.victory_hold,
+            morale: entity.morale,
+            profile: entity.profile,
+            capital: entity.capital,
+            allies: Vec::new(),
+            overlord: entity.overlord,
+            vassals: Vec::new(),
+            armies: Vec::new(),
         }
     }
 }
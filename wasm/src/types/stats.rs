@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use super::ai_entity::{AiEntity, AiState};
+use super::timeline::TerritoryTimeline;
+use crate::constants::STATS_HISTORY_LEN;
+
+/// World-wide aggregates at the end of a tick
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TickStats {
+    pub tick: u64,
+    pub alive: usize,
+    pub territory: Vec<u32>, // Spaces held by each entity, indexed by id
+    pub mean_military_strength: f32,
+    pub max_military_strength: f32,
+    pub territory_gini: f32, // 0 when alive entities hold equal territory, towards 1 as one dominates
+}
+
+impl TickStats {
+    pub fn compute(tick: u64, entities: &[AiEntity]) -> Self {
+        let mut stats = Self {
+            tick,
+            alive: 0,
+            territory: Vec::with_capacity(entities.len()),
+            mean_military_strength: 0.0,
+            max_military_strength: 0.0,
+            territory_gini: 0.0,
+        };
+        stats.recompute(tick, entities, &mut Vec::with_capacity(entities.len()));
+        stats
+    }
+
+    /// Overwrite these stats with `tick`'s, reusing the territory vector and
+    /// `alive_territory` as scratch so a full history records without allocating
+    pub fn recompute(&mut self, tick: u64, entities: &[AiEntity], alive_territory: &mut Vec<u32>) {
+        let mut alive = 0;
+        let mut total_military = 0.0;
+        let mut max_military_strength: f32 = 0.0;
+        alive_territory.clear();
+        for entity in entities.iter().filter(|entity| entity.state != AiState::Dead) {
+            alive += 1;
+            total_military += entity.military_strength;
+            max_military_strength = max_military_strength.max(entity.military_strength);
+            alive_territory.push(entity.territory);
+        }
+        self.tick = tick;
+        self.alive = alive;
+        self.territory.clear();
+        self.territory.extend(entities.iter().map(|entity| entity.territory));
+        self.mean_military_strength = if alive > 0 { total_military / alive as f32 } else { 0.0 };
+        self.max_military_strength = max_military_strength;
+        self.territory_gini = gini(alive_territory);
+    }
+}
+
+/// Gini coefficient of `values`, sorting them in place
+fn gini(values: &mut [u32]) -> f32 {
+    let total: u64 = values.iter().map(|&value| value as u64).sum();
+    if values.is_empty() || total == 0 {
+        return 0.0;
+    }
+    values.sort_unstable();
+    let n = values.len() as f64;
+    // G = 2 * sum(rank * value) / (n * total) - (n + 1) / n, with ranks from 1
+    let weighted: f64 = values
+        .iter()
+        .enumerate()
+        .map(|(rank, &value)| (rank + 1) as f64 * value as f64)
+        .sum();
+    (2.0 * weighted / (n * total as f64) - (n + 1.0) / n) as f32
+}
+
+/// Rolling history of the most recent `STATS_HISTORY_LEN` ticks' aggregates,
+/// alongside the whole run's territory timeline
+#[derive(Debug, Clone, Default)]
+pub struct SimStats {
+    history: VecDeque<TickStats>,
+    timeline: TerritoryTimeline,
+    alive_territory: Vec<u32>, // Scratch for the Gini coefficient
+}
+
+impl SimStats {
+    /// Once the history is full the oldest entry is recomputed in place
+    pub fn record(&mut self, tick: u64, entities: &[AiEntity]) {
+        self.timeline.record(tick, entities);
+        if self.history.len() == STATS_HISTORY_LEN {
+            if let Some(mut oldest) = self.history.pop_front() {
+                oldest.recompute(tick, entities, &mut self.alive_territory);
+                self.history.push_back(oldest);
+                return;
+            }
+        }
+        self.history.push_back(TickStats::compute(tick, entities));
+    }
+
+    /// Recorded ticks, oldest first
+    pub fn history(&self) -> &VecDeque<TickStats> {
+        &self.history
+    }
+
+    pub fn timeline(&self) -> &TerritoryTimeline {
+        &self.timeline
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.timeline.clear();
+    }
+}
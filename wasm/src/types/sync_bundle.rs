@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use super::ai_entity::{AiEntity, AiState};
+use super::army::Army;
+use super::behavior_profile::BehaviorProfile;
+use super::calendar::CalendarConfig;
+use super::command::PlayerCommand;
+use super::conquest::ConquestConfig;
+use super::disaster::{Affliction, EventConfig};
+use super::event::SimulationEvent;
+use super::grid_space::GridSpace;
+use super::neutral::NeutralConfig;
+use super::relations::Relation;
+use super::resource::{ResourceConfig, ResourceNode};
+use super::terrain::{TerrainConfig, TerrainType};
+use super::unrest::UnrestConfig;
+use super::victory::WinCondition;
+use super::world_config::WorldConfig;
+
+pub const SYNC_BUNDLE_VERSION: u32 = 21;
+
+/// Full entity state including the fields hidden from public snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncedEntity {
+    pub id: u32,
+    pub military_strength: f32,
+    pub position_x: f32,
+    pub position_y: f32,
+    pub state: AiState,
+    pub territory: u32,
+    pub team_id: Option<u32>,
+    pub overlord: Option<u32>,
+    pub victory_hold: u64,
+    pub money: f32,
+    pub morale: f32,
+    pub profile: BehaviorProfile,
+    pub support_target: Option<u32>,
+    pub attack_target: Option<u32>,
+    pub fortify_target: Option<u32>,
+    pub attack_space: Option<u32>,
+    pub command: Option<PlayerCommand>,
+    pub capital: Option<u32>,
+    pub tribute_due: f32,
+    pub brain: u32,
+    pub rng_state: u32,
+    pub last_update_time: f64, // Resources accrue from here on the joiner's first tick
+}
+
+impl From<&AiEntity> for SyncedEntity {
+    fn from(entity: &AiEntity) -> Self {
+        Self {
+            id: entity.id,
+            military_strength: entity.military_strength,
+            position_x: entity.position_x,
+            position_y: entity.position_y,
+            state: entity.state,
+            territory: entity.territory,
+            team_id: entity.team_id,
+            overlord: entity.overlord,
+            victory_hold: entity.victory_hold,
+            money: entity.money,
+            morale: entity.morale,
+            profile: entity.profile,
+            support_target: entity.support_target,
+            attack_target: entity.attack_target,
+            fortify_target: entity.fortify_target,
+            attack_space: entity.attack_space,
+            command: entity.command,
+            capital: entity.capital,
+            tribute_due: entity.tribute_due,
+            brain: entity.brain,
+            rng_state: entity.rng_state(),
+            last_update_time: entity.last_update_time,
+        }
+    }
+}
+
+impl From<&SyncedEntity> for AiEntity {
+    fn from(synced: &SyncedEntity) -> Self {
+        let mut entity = AiEntity::new(synced.id);
+        entity.military_strength = synced.military_strength;
+        entity.position_x = synced.position_x;
+        entity.position_y = synced.position_y;
+        entity.state = synced.state;
+        entity.territory = synced.territory;
+        entity.team_id = synced.team_id;
+        entity.overlord = synced.overlord;
+        entity.victory_hold = synced.victory_hold;
+        entity.money = synced.money;
+        entity.morale = synced.morale;
+        entity.profile = synced.profile;
+        entity.support_target = synced.support_target;
+        entity.attack_target = synced.attack_target;
+        entity.fortify_target = synced.fortify_target;
+        entity.attack_space = synced.attack_space;
+        entity.command = synced.command;
+        entity.capital = synced.capital;
+        entity.tribute_due = synced.tribute_due;
+        entity.brain = synced.brain;
+        entity.set_rng_state(synced.rng_state);
+        entity.last_update_time = synced.last_update_time;
+        entity
+    }
+}
+
+/// Run-length encoded stretch of identical grid spaces
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GridRun {
+    pub owner_id: Option<u32>,
+    pub defense_strength: f32,
+    pub supply: f32,
+    pub garrison: f32,
+    pub unrest: f32,
+    pub former_owner: Option<u32>,
+    pub length: u32,
+}
+
+/// Collapse consecutive identical grid spaces into runs
+pub fn encode_grid_runs(spaces: &[GridSpace]) -> Vec<GridRun> {
+    let mut runs: Vec<GridRun> = Vec::new();
+    for space in spaces {
+        match runs.last_mut() {
+            Some(run)
+                if run.owner_id == space.owner_id
+                    && run.defense_strength.to_bits() == space.defense_strength.to_bits()
+                    && run.supply.to_bits() == space.supply.to_bits()
+                    && run.garrison.to_bits() == space.garrison.to_bits()
+                    && run.unrest.to_bits() == space.unrest.to_bits()
+                    && run.former_owner == space.former_owner =>
+            {
+                run.length += 1;
+            }
+            _ => runs.push(GridRun {
+                owner_id: space.owner_id,
+                defense_strength: space.defense_strength,
+                supply: space.supply,
+                garrison: space.garrison,
+                unrest: space.unrest,
+                former_owner: space.former_owner,
+                length: 1,
+            }),
+        }
+    }
+    runs
+}
+
+/// Expand runs back into a flat grid, returning `None` if the length doesn't match
+///
+/// Terrain and resource nodes are not part of the runs; they are regenerated
+/// from the bundle's configs or taken from its map layers. `expected_len`
+/// comes from the untrusted bundle too, so the grid grows only as runs fill it.
+pub fn decode_grid_runs(runs: &[GridRun], expected_len: usize) -> Option<Vec<GridSpace>> {
+    let mut spaces = Vec::new();
+    for run in runs {
+        let space = GridSpace {
+            owner_id: run.owner_id,
+            defense_strength: run.defense_strength,
+            supply: run.supply,
+            garrison: run.garrison,
+            unrest: run.unrest,
+            former_owner: run.former_owner,
+            ..GridSpace::new()
+        };
+        for _ in 0..run.length {
+            if spaces.len() == expected_len {
+                return None;
+            }
+            spaces.push(space);
+        }
+    }
+    if spaces.len() == expected_len {
+        Some(spaces)
+    } else {
+        None
+    }
+}
+
+/// Minimal data a late-joining client needs to pick up a running match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncBundle {
+    pub version: u32,
+    pub tick: u64,
+    /// The host's `TimeMode::SimTime` clock, which tick rate changes keep from following the tick
+    pub sim_time_ms: f64,
+    pub state_hash: u64,
+    pub running: bool,
+    pub tick_rate: u32,
+    pub world: WorldConfig,
+    pub terrain: TerrainConfig,
+    pub resources: ResourceConfig,
+    pub conquest: ConquestConfig,
+    pub neutrals: NeutralConfig,
+    pub unrest: UnrestConfig,
+    pub event_config: EventConfig,
+    pub calendar: CalendarConfig,
+    pub win_condition: WinCondition,
+    pub victory_cells: Vec<u32>,
+    pub map_terrain: Option<Vec<TerrainType>>, // Terrain of a loaded map, replacing the generated layer
+    pub map_resources: Option<Vec<Option<ResourceNode>>>, // Resource nodes of a loaded map, replacing generated ones
+    pub entities: Vec<SyncedEntity>,
+    pub teams: Vec<usize>, // Team sizes in entity order, empty for a free-for-all
+    pub grid_runs: Vec<GridRun>,
+    pub alliances: Vec<(u32, u32)>,
+    pub relations: Vec<(u32, u32, Relation)>,
+    pub armies: Vec<Army>,
+    pub next_army_id: u32,
+    pub afflictions: Vec<Affliction>,
+    pub recent_events: Vec<SimulationEvent>,
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use crate::utils::grid_hash;
+
+/// Grid spaces per noise lattice cell, roughly the width of a terrain feature
+const TERRAIN_FEATURE_SPACES: f32 = 8.0;
+const TERRAIN_OCTAVES: u32 = 3;
+
+// Noise thresholds, checked in order
+const WATER_BELOW: f32 = 0.3;
+const PLAINS_BELOW: f32 = 0.58;
+const FOREST_BELOW: f32 = 0.72;
+
+/// Kind of land covering a grid space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum TerrainType {
+    #[default]
+    Plains = 0,
+    Forest = 1,
+    Mountain = 2,
+    Water = 3,
+}
+
+/// How a terrain type affects the entities fighting over it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+pub struct TerrainEffects {
+    /// Multiplier on the cost of walking through the space
+    pub movement_cost: f32,
+    /// Extra fraction of the conquest cost paid to take the space
+    pub defense_bonus: f32,
+    /// Multiplier on the resources the space generates for its owner
+    pub resource_multiplier: f32,
+}
+
+impl TerrainEffects {
+    pub const fn new(movement_cost: f32, defense_bonus: f32, resource_multiplier: f32) -> Self {
+        Self {
+            movement_cost,
+            defense_bonus,
+            resource_multiplier,
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<(), String> {
+        if !(self.movement_cost.is_finite() && self.movement_cost > 0.0) {
+            return Err(format!("{} movement_cost must be positive", name));
+        }
+        if !(self.defense_bonus.is_finite() && self.defense_bonus >= 0.0) {
+            return Err(format!("{} defense_bonus must not be negative", name));
+        }
+        if !(self.resource_multiplier.is_finite() && self.resource_multiplier >= 0.0) {
+            return Err(format!("{} resource_multiplier must not be negative", name));
+        }
+        Ok(())
+    }
+}
+
+/// Seed and per-type effects of the terrain layer
+///
+/// JSON such as `{"seed":7}` keeps the default effects.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct TerrainConfig {
+    pub seed: u32,
+    pub plains: TerrainEffects,
+    pub forest: TerrainEffects,
+    pub mountain: TerrainEffects,
+    pub water: TerrainEffects,
+}
+
+impl TerrainConfig {
+    pub fn effects(&self, terrain: TerrainType) -> TerrainEffects {
+        match terrain {
+            TerrainType::Plains => self.plains,
+            TerrainType::Forest => self.forest,
+            TerrainType::Mountain => self.mountain,
+            TerrainType::Water => self.water,
+        }
+    }
+
+    /// Cheapest movement cost of any terrain type
+    pub fn min_movement_cost(&self) -> f32 {
+        [self.plains, self.forest, self.mountain, self.water]
+            .iter()
+            .map(|effects| effects.movement_cost)
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.plains.validate("plains")?;
+        self.forest.validate("forest")?;
+        self.mountain.validate("mountain")?;
+        self.water.validate("water")
+    }
+
+    /// Terrain for every space of a `grid_size` x `grid_size` grid, row-major
+    pub fn generate(&self, grid_size: usize) -> Vec<TerrainType> {
+        let mut terrain = Vec::with_capacity(grid_size * grid_size);
+        for row in 0..grid_size {
+            for col in 0..grid_size {
+                let height = fractal_noise(
+                    self.seed,
+                    col as f32 / TERRAIN_FEATURE_SPACES,
+                    row as f32 / TERRAIN_FEATURE_SPACES,
+                );
+                terrain.push(if height < WATER_BELOW {
+                    TerrainType::Water
+                } else if height < PLAINS_BELOW {
+                    TerrainType::Plains
+                } else if height < FOREST_BELOW {
+                    TerrainType::Forest
+                } else {
+                    TerrainType::Mountain
+                });
+            }
+        }
+        terrain
+    }
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            plains: TerrainEffects::new(1.0, 0.0, 1.0),
+            forest: TerrainEffects::new(1.5, 0.25, 0.8),
+            mountain: TerrainEffects::new(3.0, 0.75, 0.5),
+            water: TerrainEffects::new(4.0, 0.5, 0.25),
+        }
+    }
+}
+
+/// Pseudo-random value in `0..=1` for a noise lattice point
+fn lattice_value(seed: u32, x: i32, y: i32) -> f32 {
+    grid_hash(seed, x, y) as f32 / u32::MAX as f32
+}
+
+/// Smoothly interpolated value noise
+fn value_noise(seed: u32, x: f32, y: f32) -> f32 {
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let tx = smooth(x - x0);
+    let ty = smooth(y - y0);
+
+    let top = lattice_value(seed, ix, iy) * (1.0 - tx) + lattice_value(seed, ix + 1, iy) * tx;
+    let bottom = lattice_value(seed, ix, iy + 1) * (1.0 - tx) + lattice_value(seed, ix + 1, iy + 1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+/// Value noise summed over octaves, normalized back to `0..=1`
+fn fractal_noise(seed: u32, x: f32, y: f32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut amplitude_sum = 0.0;
+    for octave in 0..TERRAIN_OCTAVES {
+        let octave_seed = seed.wrapping_add(octave.wrapping_mul(0x9e37_79b9));
+        total += value_noise(octave_seed, x * frequency, y * frequency) * amplitude;
+        amplitude_sum += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / amplitude_sum
+}
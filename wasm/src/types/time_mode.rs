@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use wasm_bindgen::prelude::*;
+
+/// Clock that drives time-based resource accumulation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum TimeMode {
+    /// Wall-clock time since the simulation was created
+    #[default]
+    RealTime,
+    /// Synthetic clock advancing a fixed amount per tick, for reproducible runs
+    Fixed { ms_per_tick: f64 },
+    /// Simulated time, advancing `1 / tick_rate` seconds per tick at whatever
+    /// the tick rate was on that tick, so the economy depends only on ticks run
+    SimTime,
+}
+
+impl TimeMode {
+    /// Fixed timestep, or `None` for a non-finite or non-positive step
+    pub fn fixed(ms_per_tick: f64) -> Option<Self> {
+        (ms_per_tick.is_finite() && ms_per_tick > 0.0).then_some(Self::Fixed { ms_per_tick })
+    }
+}
+
+/// Clock resources accumulate on, as JS picks it
+#[cfg(feature = "bindings")]
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceClock {
+    /// `TimeMode::SimTime`
+    SimTime,
+    /// `TimeMode::RealTime`
+    WallTime,
+}
+
+#[cfg(feature = "bindings")]
+impl From<ResourceClock> for TimeMode {
+    fn from(clock: ResourceClock) -> Self {
+        match clock {
+            ResourceClock::SimTime => TimeMode::SimTime,
+            ResourceClock::WallTime => TimeMode::RealTime,
+        }
+    }
+}
+
@@ -0,0 +1,130 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+use super::ai_entity::AiEntity;
+use crate::constants::{TIMELINE_CAPACITY, TIMELINE_SAMPLE_INTERVAL};
+
+/// One entity's territory at a sampled tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TimelinePoint {
+    pub tick: u64,
+    pub territory: u32,
+}
+
+/// Territory an entity held over the whole run, oldest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntityTimeline {
+    pub entity_id: u32,
+    pub points: Vec<TimelinePoint>,
+}
+
+/// Every entity's territory sampled over the whole run, for graphing how
+/// empires rose and fell
+///
+/// Samples are taken every `interval` ticks and stored back to back. Once
+/// `TIMELINE_CAPACITY` are kept, every other one is dropped and the interval
+/// doubles, so a run of any length fits in the same buffer at a coarser
+/// resolution.
+#[derive(Debug, Clone)]
+pub struct TerritoryTimeline {
+    interval: u64,
+    ticks: Vec<u64>,
+    starts: Vec<usize>, // Where each sample begins in `territory`
+    territory: Vec<u32>, // Each sample's territory by entity id, oldest sample first
+}
+
+impl TerritoryTimeline {
+    pub fn new() -> Self {
+        Self {
+            interval: TIMELINE_SAMPLE_INTERVAL,
+            ticks: Vec::with_capacity(TIMELINE_CAPACITY),
+            starts: Vec::with_capacity(TIMELINE_CAPACITY),
+            territory: Vec::new(),
+        }
+    }
+
+    /// Sample `entities` if `tick` falls on the interval
+    pub fn record(&mut self, tick: u64, entities: &[AiEntity]) {
+        if !tick.is_multiple_of(self.interval) {
+            return;
+        }
+        if self.ticks.len() == TIMELINE_CAPACITY {
+            self.thin_out();
+            if !tick.is_multiple_of(self.interval) {
+                return;
+            }
+        }
+        // Room for a full buffer up front, so sampling doesn't reallocate
+        // until the entity count grows
+        let full = entities.len() * TIMELINE_CAPACITY;
+        if self.territory.capacity() < full {
+            self.territory.reserve(full - self.territory.len());
+        }
+        self.ticks.push(tick);
+        self.starts.push(self.territory.len());
+        self.territory.extend(entities.iter().map(|entity| entity.territory));
+    }
+
+    /// Double the interval, keeping the samples that fall on it
+    fn thin_out(&mut self) {
+        self.interval *= 2;
+        let (mut kept, mut end) = (0, 0);
+        for index in 0..self.ticks.len() {
+            if !self.ticks[index].is_multiple_of(self.interval) {
+                continue;
+            }
+            let sample = self.sample_range(index);
+            let len = sample.len();
+            self.territory.copy_within(sample, end);
+            self.ticks[kept] = self.ticks[index];
+            self.starts[kept] = end;
+            kept += 1;
+            end += len;
+        }
+        self.ticks.truncate(kept);
+        self.starts.truncate(kept);
+        self.territory.truncate(end);
+    }
+
+    fn sample_range(&self, index: usize) -> Range<usize> {
+        let end = self.starts.get(index + 1).copied().unwrap_or(self.territory.len());
+        self.starts[index]..end
+    }
+
+    /// Samples of one entity, or `None` if it has never been sampled
+    pub fn entity(&self, entity_id: u32) -> Option<EntityTimeline> {
+        let points: Vec<TimelinePoint> = (0..self.ticks.len())
+            .filter_map(|index| {
+                let territory = self.territory[self.sample_range(index)].get(entity_id as usize)?;
+                Some(TimelinePoint {
+                    tick: self.ticks[index],
+                    territory: *territory,
+                })
+            })
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        Some(EntityTimeline { entity_id, points })
+    }
+
+    /// Samples of every entity sampled so far, by id
+    pub fn all(&self) -> Vec<EntityTimeline> {
+        let entity_count = (0..self.ticks.len()).map(|index| self.sample_range(index).len()).max().unwrap_or(0);
+        (0..entity_count as u32).filter_map(|entity_id| self.entity(entity_id)).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.interval = TIMELINE_SAMPLE_INTERVAL;
+        self.ticks.clear();
+        self.starts.clear();
+        self.territory.clear();
+    }
+}
+
+impl Default for TerritoryTimeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
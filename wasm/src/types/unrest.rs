@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use tsify::Tsify;
+
+use crate::utils::grid_hash;
+
+/// Keeps revolt rolls independent of the other seeds with the same value
+const UNREST_SEED_SALT: u32 = 0x85eb_ca6b;
+/// Separates the roll for defecting from the roll for revolting
+const DEFECT_SEED_SALT: u32 = 0xc2b2_ae35;
+
+/// How restless held land gets and what happens when it boils over
+///
+/// Unrest runs from 0 to 1 on every owned space. Conquest sets it, distance
+/// from the owner's capital keeps raising it and it otherwise settles.
+/// Spaces at the revolt threshold may rise as rebels, becoming unowned land
+/// under a neutral garrison, or defect back to the entity they were taken
+/// from. Capitals never revolt.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bindings", derive(Tsify))]
+#[serde(default)]
+pub struct UnrestConfig {
+    pub seed: u32,
+    /// Unrest a space starts with right after it is conquered
+    pub conquest_unrest: f32,
+    /// Unrest gained per tick for every grid step between a space and its owner's capital
+    pub distance_unrest_per_tick: f32,
+    /// Unrest every space loses per tick
+    pub decay_per_tick: f32,
+    /// Unrest at which a space may revolt
+    pub revolt_threshold: f32,
+    /// Chance per unrest check that a space at the threshold revolts
+    pub revolt_chance: f32,
+    /// Chance a revolting space goes back to its former owner, if still alive,
+    /// instead of rising as rebels
+    pub defect_chance: f32,
+    /// Defense strength of the garrison rebels hold a space with
+    pub rebel_garrison: f32,
+}
+
+impl UnrestConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("conquest_unrest", self.conquest_unrest),
+            ("revolt_threshold", self.revolt_threshold),
+            ("revolt_chance", self.revolt_chance),
+            ("defect_chance", self.defect_chance),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1", name));
+            }
+        }
+        for (name, value) in [
+            ("distance_unrest_per_tick", self.distance_unrest_per_tick),
+            ("decay_per_tick", self.decay_per_tick),
+            ("rebel_garrison", self.rebel_garrison),
+        ] {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err(format!("{} must not be negative", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the space at `index` of a square grid revolts on `tick`
+    pub fn revolts(&self, tick: u64, index: usize, grid_size: usize) -> bool {
+        self.roll(UNREST_SEED_SALT, tick, index, grid_size) < self.revolt_chance
+    }
+
+    /// Whether a revolt on the space at `index` on `tick` defects rather than rebels
+    pub fn defects(&self, tick: u64, index: usize, grid_size: usize) -> bool {
+        self.roll(DEFECT_SEED_SALT, tick, index, grid_size) < self.defect_chance
+    }
+
+    fn roll(&self, salt: u32, tick: u64, index: usize, grid_size: usize) -> f32 {
+        let (row, col) = (index / grid_size, index % grid_size);
+        let seed = self.seed ^ salt ^ (tick as u32).wrapping_mul(0x9e37_79b9);
+        grid_hash(seed, col as i32, row as i32) as f32 / u32::MAX as f32
+    }
+}
+
+impl Default for UnrestConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            conquest_unrest: 0.5,
+            distance_unrest_per_tick: 0.0001,
+            decay_per_tick: 0.002,
+            revolt_threshold: 0.8,
+            revolt_chance: 0.05,
+            defect_chance: 0.5,
+            rebel_garrison: 4.0,
+        }
+    }
+}
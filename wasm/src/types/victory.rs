@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// What it takes to win a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WinCondition {
+    /// Last entity, team or overlord with its vassals standing
+    #[default]
+    Annihilation,
+    /// First entity to hold `needed` victory point cells for `hold_ticks`
+    /// ticks in a row, or the last one standing if that comes sooner
+    VictoryPoints { needed: u32, hold_ticks: u64 },
+}
+
+impl WinCondition {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            WinCondition::VictoryPoints { needed: 0, .. } => Err("needed must be at least 1".to_string()),
+            WinCondition::VictoryPoints { hold_ticks: 0, .. } => Err("hold_ticks must be at least 1".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
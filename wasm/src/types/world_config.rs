@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "bindings")]
+use wasm_bindgen::prelude::*;
+
+use crate::constants::{DEFAULT_CELL_SIZE, DEFAULT_GRID_SIZE, DEFAULT_WORLD_SIZE};
+use crate::error::SimError;
+
+/// Scale of the simulated world
+///
+/// The world is a square of `world_size` units centered on the origin,
+/// divided into `grid_size` x `grid_size` territory spaces. `cell_size` is
+/// the bucket size of the spatial hash used for neighbor queries.
+#[cfg_attr(feature = "bindings", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WorldConfig {
+    pub world_size: f32,
+    pub cell_size: f32,
+    pub grid_size: usize,
+}
+
+#[cfg_attr(feature = "bindings", wasm_bindgen)]
+impl WorldConfig {
+    /// Non-finite or non-positive values fall back to the defaults
+    #[cfg_attr(feature = "bindings", wasm_bindgen(constructor))]
+    pub fn new(world_size: f32, cell_size: f32, grid_size: usize) -> Self {
+        let world_size = if world_size.is_finite() && world_size > 0.0 {
+            world_size
+        } else {
+            DEFAULT_WORLD_SIZE
+        };
+        let cell_size = if cell_size.is_finite() && cell_size > 0.0 {
+            cell_size
+        } else {
+            DEFAULT_CELL_SIZE
+        };
+        Self {
+            world_size,
+            cell_size,
+            grid_size: grid_size.max(1),
+        }
+    }
+}
+
+impl WorldConfig {
+    /// Check a config that skipped `new`, e.g. one deserialized from JSON,
+    /// which keeps whatever sizes it was given
+    pub fn validate(&self) -> Result<(), SimError> {
+        SimError::check_grid_size(self.grid_size)?;
+        if self.grid_size.checked_mul(self.grid_size).is_none() {
+            return Err(SimError::InvalidInput(format!("grid_size {} is too large", self.grid_size)));
+        }
+        for (name, size) in [("world_size", self.world_size), ("cell_size", self.cell_size)] {
+            if !(size.is_finite() && size > 0.0) {
+                return Err(SimError::InvalidInput(format!("{} must be positive", name)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Distance from the origin to the world edge
+    pub fn half_extent(&self) -> f32 {
+        self.world_size * 0.5
+    }
+
+    /// World units covered by one territory grid space
+    pub fn grid_space_extent(&self) -> f32 {
+        self.world_size / self.grid_size as f32
+    }
+
+    /// Neighbor search radius, kept at two spatial hash cells
+    pub fn search_radius(&self) -> f32 {
+        self.cell_size * 2.0
+    }
+
+    pub fn with_grid_size(mut self, grid_size: usize) -> Self {
+        self.grid_size = grid_size.max(1);
+        self
+    }
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_WORLD_SIZE, DEFAULT_CELL_SIZE, DEFAULT_GRID_SIZE)
+    }
+}
@@ -4,7 +4,10 @@
 /// and WebAssembly contexts.
 
 #[cfg(target_arch = "wasm32")]
-use web_sys::window;
+use wasm_bindgen::{JsCast, JsValue};
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use core::arch::wasm32;
 
 /// A simple instant implementation that works in both WASM and native contexts
 #[derive(Debug, Clone, Copy)]
@@ -17,13 +20,7 @@ impl Instant {
     pub fn now() -> Self {
         #[cfg(target_arch = "wasm32")]
         {
-            // Use Performance API in WASM context
-            let timestamp_ms = window()
-                .expect("should have a Window")
-                .performance()
-                .expect("should have a Performance")
-                .now();
-            Instant { timestamp_ms }
+            Instant { timestamp_ms: now_ms() }
         }
         
         #[cfg(not(target_arch = "wasm32"))]
@@ -47,6 +44,38 @@ impl Instant {
     }
 }
 
+/// Milliseconds from the global `performance` clock, which browsers, Web
+/// Workers and Node all have, falling back to `Date.now()` where it's missing
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    thread_local! {
+        static PERFORMANCE: Option<web_sys::Performance> = global_performance();
+    }
+
+    PERFORMANCE.with(|performance| match performance {
+        Some(performance) => performance.now(),
+        None => js_sys::Date::now(),
+    })
+}
+
+/// The `performance` of whichever global scope the module runs in: a
+/// browser window, a dedicated worker, or any other host that puts one on
+/// `globalThis`, like Node
+#[cfg(target_arch = "wasm32")]
+fn global_performance() -> Option<web_sys::Performance> {
+    let global = js_sys::global();
+    if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+        return window.performance();
+    }
+    if let Some(worker) = global.dyn_ref::<web_sys::DedicatedWorkerGlobalScope>() {
+        return worker.performance();
+    }
+    js_sys::Reflect::get(&global, &JsValue::from_str("performance"))
+        .ok()
+        .filter(|performance| performance.is_object())
+        .map(|performance| performance.unchecked_into())
+}
+
 /// Duration type that works across WASM and native
 #[derive(Debug, Clone, Copy)]
 pub struct Duration {
@@ -60,11 +89,137 @@ impl Duration {
     }
     
     /// Returns the duration in seconds
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn as_secs_f64(&self) -> f64 {
         self.millis / 1000.0
     }
 }
 
+/// Squared distances from `(x, y)` to four points at once, using SIMD128 lanes when available
+#[inline]
+pub fn distances_sq_x4(x: f32, y: f32, xs: [f32; 4], ys: [f32; 4]) -> [f32; 4] {
+    #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+    {
+        unsafe { distances_sq_x4_simd(x, y, xs, ys) }
+    }
+    #[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+    {
+        distances_sq_x4_scalar(x, y, xs, ys)
+    }
+}
+
+#[cfg_attr(all(target_arch = "wasm32", target_feature = "simd128"), allow(dead_code))]
+fn distances_sq_x4_scalar(x: f32, y: f32, xs: [f32; 4], ys: [f32; 4]) -> [f32; 4] {
+    let mut out = [0.0; 4];
+    for lane in 0..4 {
+        let dx = x - xs[lane];
+        let dy = y - ys[lane];
+        out[lane] = dx * dx + dy * dy;
+    }
+    out
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+unsafe fn distances_sq_x4_simd(x: f32, y: f32, xs: [f32; 4], ys: [f32; 4]) -> [f32; 4] {
+    use core::mem::transmute;
+
+    let dx = wasm32::f32x4_sub(wasm32::f32x4_splat(x), transmute::<[f32; 4], wasm32::v128>(xs));
+    let dy = wasm32::f32x4_sub(wasm32::f32x4_splat(y), transmute::<[f32; 4], wasm32::v128>(ys));
+    let dist_sq = wasm32::f32x4_add(wasm32::f32x4_mul(dx, dx), wasm32::f32x4_mul(dy, dy));
+    transmute::<wasm32::v128, [f32; 4]>(dist_sq)
+}
+
+/// Well-mixed pseudo-random bits for a grid coordinate, shared by the seeded
+/// world generators
+pub fn grid_hash(seed: u32, x: i32, y: i32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x27d4_eb2d)
+        ^ (y as u32).wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+/// FNV-1a hasher used for deterministic state hashes
+///
+/// Unlike `std::hash::DefaultHasher` the output is stable across platforms,
+/// builds and runs, so hashes can be compared between peers.
+#[derive(Debug, Clone, Copy)]
+pub struct StateHasher {
+    state: u64,
+}
+
+impl StateHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Self {
+            state: Self::OFFSET_BASIS,
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state ^= byte as u64;
+            self.state = self.state.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    /// Hash floats by bit pattern so identical states hash identically
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// CRC-32 (IEEE 802.3, as used by zip and PNG) of `bytes`
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc = CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+const CRC32_TABLE: [u32; 256] = build_crc32_table();
+
+const fn build_crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,7 +241,7 @@ mod tests {
         let _ = sum; // Use sum to prevent optimization
         
         let elapsed = instant.elapsed();
-        assert!(elapsed.as_millis() >= 0);
+        assert!(elapsed.as_secs_f64() >= 0.0);
     }
     
     #[test]
@@ -95,4 +250,39 @@ mod tests {
         assert_eq!(duration.as_millis(), 1500);
         assert!((duration.as_secs_f64() - 1.5).abs() < 0.001);
     }
+
+    #[test]
+    fn distances_sq_x4_matches_scalar_math() {
+        let xs = [0.0, 3.0, -4.0, 1.5];
+        let ys = [0.0, 4.0, 3.0, -2.5];
+        let distances = distances_sq_x4(1.0, 1.0, xs, ys);
+        for lane in 0..4 {
+            let dx = 1.0 - xs[lane];
+            let dy = 1.0 - ys[lane];
+            assert_eq!(distances[lane], dx * dx + dy * dy);
+        }
+        assert_eq!(distances_sq_x4(0.0, 0.0, xs, ys)[1], 25.0);
+    }
+
+    #[test]
+    fn state_hasher_is_order_sensitive() {
+        let mut a = StateHasher::new();
+        a.write_u32(1);
+        a.write_u32(2);
+        let mut b = StateHasher::new();
+        b.write_u32(2);
+        b.write_u32(1);
+        assert_ne!(a.finish(), b.finish());
+
+        let mut c = StateHasher::new();
+        c.write_u32(1);
+        c.write_u32(2);
+        assert_eq!(a.finish(), c.finish());
+    }
+
+    #[test]
+    fn crc32_matches_the_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
 }